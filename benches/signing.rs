@@ -0,0 +1,42 @@
+//! Signing throughput benchmarks, run with `cargo bench --bench signing`
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use puffersecuresigner::crypto::bls_keys;
+use puffersecuresigner::io::key_management::delete_bls_key;
+
+const BATCH_SIZE: u64 = 64;
+
+fn signing_benches(c: &mut Criterion) {
+    let sk_set = bls_keys::new_bls_key(0);
+    let pk_hex = sk_set.public_keys().public_key().to_hex();
+    bls_keys::save_bls_key(&sk_set).unwrap();
+    // Warm the key cache so the benchmark measures steady state signing
+    bls_keys::preload_keys(&[pk_hex.clone()]).unwrap();
+
+    c.bench_function("bls_sign_single", |b| {
+        b.iter(|| bls_keys::bls_agg_sign(&sk_set, black_box(&[7_u8; 32])))
+    });
+
+    c.bench_function("bls_sign_saved_key", |b| {
+        b.iter(|| bls_keys::bls_agg_sign_from_saved_sk(&pk_hex, black_box(&[7_u8; 32])).unwrap())
+    });
+
+    let mut group = c.benchmark_group("bls_sign_batch");
+    group.throughput(Throughput::Elements(BATCH_SIZE));
+    group.bench_function("saved_key", |b| {
+        b.iter_batched(
+            || (0..BATCH_SIZE).map(|i| i.to_le_bytes()).collect::<Vec<_>>(),
+            |msgs| {
+                for msg in msgs.iter() {
+                    bls_keys::bls_agg_sign_from_saved_sk(&pk_hex, msg).unwrap();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+
+    delete_bls_key(&pk_hex).unwrap();
+}
+
+criterion_group!(benches, signing_benches);
+criterion_main!(benches);
@@ -50,12 +50,16 @@ typedef struct {
  * key            Service provider private key file path.
  * accesskey      Service provider access key, see also here:
  *                https://api.portal.trustedservices.intel.com/EPID-attestation
+ * timeout_secs   Bound on each request to the service, 60 seconds if not positive.
+ * connect_timeout_secs  Bound on connecting to the service, 10 seconds if not positive.
  */
 typedef struct {
   std::string endpoint;
   std::string cert;
   std::string key;
   std::string accesskey;
+  long timeout_secs;
+  long connect_timeout_secs;
 } RaIasServerCfg;
 
 #endif  // REMOTE_ATTESTATION_LIB_INCLUDE_TEE_COMMON_TYPE_H_
@@ -73,7 +73,8 @@ class RaIasClient {
   TeeErrorCode FetchReport(const std::string& quote, RaIasReport* ias_report);
 
  private:
-  void InitIasConnection(const std::string& url);
+  void InitIasConnection(const std::string& url, long timeout_secs,
+                         long connect_timeout_secs);
 
   CURL* curl_ = NULL;
   curl_slist* headers_ = NULL;
@@ -115,7 +115,9 @@ static size_t ParseReportResponseHeader(const void* contents, size_t size,
 
 std::mutex RaIasClient::init_mutex_;
 
-void RaIasClient::InitIasConnection(const std::string& endpoint) {
+void RaIasClient::InitIasConnection(const std::string& endpoint,
+                                    long timeout_secs,
+                                    long connect_timeout_secs) {
   if (endpoint.empty()) {
     curl_ = NULL;
     return;
@@ -148,8 +150,10 @@ void RaIasClient::InitIasConnection(const std::string& endpoint) {
   /* set common option */
   curl_easy_setopt(curl_, CURLOPT_FORBID_REUSE, 1L);
   curl_easy_setopt(curl_, CURLOPT_NOSIGNAL, 1L);
-  curl_easy_setopt(curl_, CURLOPT_TIMEOUT, 60L);
-  curl_easy_setopt(curl_, CURLOPT_CONNECTTIMEOUT, 10L);
+  curl_easy_setopt(curl_, CURLOPT_TIMEOUT,
+                   timeout_secs > 0 ? timeout_secs : 60L);
+  curl_easy_setopt(curl_, CURLOPT_CONNECTTIMEOUT,
+                   connect_timeout_secs > 0 ? connect_timeout_secs : 10L);
   curl_easy_setopt(curl_, CURLOPT_SSL_VERIFYPEER, 0L);
   curl_easy_setopt(curl_, CURLOPT_SSL_VERIFYHOST, 0L);
 
@@ -158,7 +162,8 @@ void RaIasClient::InitIasConnection(const std::string& endpoint) {
 
 RaIasClient::RaIasClient(const RaIasServerCfg& ias_server) {
   // Configure the other normal settings firstly.
-  InitIasConnection(ias_server.endpoint);
+  InitIasConnection(ias_server.endpoint, ias_server.timeout_secs,
+                    ias_server.connect_timeout_secs);
 
   // Check the HTTPS server addr and set the cert/key settings
   // Or use the Access key authentication
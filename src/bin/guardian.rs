@@ -23,6 +23,11 @@ async fn main() {
         port, genesis_fork_version
     );
 
+    // Remote attestation round trips to IAS are bounded by these timeouts and retries
+    if let Err(e) = puffersecuresigner::io::remote_attestation::IasClientConfig::from_env() {
+        panic!("Bad IAS client configuration: {:?}", e)
+    }
+
     let app = axum::Router::new()
         // Endpoint to check health
         .route(
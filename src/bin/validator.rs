@@ -24,38 +24,63 @@ async fn main() {
         genesis_fork_version
     );
 
+    // Roll back any slashing DB write torn by a crash before serving signing requests
+    match puffersecuresigner::eth2::slash_protection::recover_slashing_db() {
+        Ok(0) => {}
+        Ok(n) => log::info!("Rolled back {n} incomplete slashing DB writes"),
+        Err(e) => panic!("Slashing protection DB failed its integrity check: {:?}", e),
+    }
+
+    // Optionally prune attestation history below a weak subjectivity checkpoint
+    match puffersecuresigner::eth2::slash_protection::init_weak_subjectivity_epoch_from_env() {
+        Ok(0) => {}
+        Ok(epoch) => println!("Pruning attestation history below epoch {epoch}"),
+        Err(e) => panic!("Bad weak subjectivity epoch: {:?}", e),
+    }
+
+    // Optionally push keygen and signing events to an external audit webhook
+    match puffersecuresigner::io::webhook::init_webhook_from_env() {
+        Ok(true) => log::info!("Sending audit events to the configured webhook"),
+        Ok(false) => {}
+        Err(e) => panic!("Bad audit webhook configuration: {:?}", e),
+    }
+
+    // Optionally register networks beyond the known ones, e.g. devnets signing under their own DST
+    match puffersecuresigner::eth2::eth_types::register_networks_from_env() {
+        Ok(0) => {}
+        Ok(n) => log::info!("Registered {n} networks from the network registry file"),
+        Err(e) => panic!("Bad network registry file: {:?}", e),
+    }
+
+    // Remote attestation round trips to IAS are bounded by these timeouts and retries
+    if let Err(e) = puffersecuresigner::io::remote_attestation::IasClientConfig::from_env() {
+        panic!("Bad IAS client configuration: {:?}", e)
+    }
+
+    // Messages longer than this are rejected before they are hashed and signed
+    if let Err(e) = puffersecuresigner::crypto::bls_keys::max_message_bytes_from_env() {
+        panic!("Bad max message size: {:?}", e)
+    }
+
+    // Optionally restrict the fee recipients validators may register with builders
+    let fee_recipient_policy =
+        match puffersecuresigner::eth2::fee_recipient_policy::FeeRecipientPolicy::from_env() {
+            Ok(policy) => policy,
+            Err(e) => panic!("Bad fee recipient policy: {:?}", e),
+        };
+
     let app_state = puffersecuresigner::enclave::shared::handlers::AppState {
         genesis_fork_version,
+        debug_responses: std::env::var("SECURE_SIGNER_DEBUG_RESPONSES").is_ok(),
+        allow_genesis_fork: std::env::var("SECURE_SIGNER_ALLOW_GENESIS_FORK").is_ok(),
+        affine_signatures: std::env::var("SECURE_SIGNER_AFFINE_SIGNATURES").is_ok(),
+        verify_only: std::env::var("SECURE_SIGNER_VERIFY_ONLY").is_ok(),
+        trust_selection_proofs: std::env::var("SECURE_SIGNER_TRUST_SELECTION_PROOFS").is_ok(),
+        fee_recipient_policy: std::sync::Arc::new(fee_recipient_policy),
+        beacon_node_url: std::env::var("SECURE_SIGNER_BEACON_NODE_URL").ok(),
     };
 
-    let app = axum::Router::new()
-        // Endpoint to check health
-        .route(
-            "/upcheck",
-            axum::routing::get(puffersecuresigner::enclave::shared::handlers::health::handler),
-        )
-        // Endpoint to securely generate and save a BLS sk
-        .route(
-            "/bls/v1/keygen",
-            axum::routing::post(
-                puffersecuresigner::enclave::validator::handlers::attest_fresh_bls_key::handler,
-            ),
-        )
-        // Endpoint to list all pks of saved bls keys in the enclave
-        .route(
-            "/eth/v1/keystores",
-            axum::routing::get(
-                puffersecuresigner::enclave::shared::handlers::list_bls_keys::handler,
-            ),
-        )
-        // Endpoint to request a signature using BLS sk
-        .route(
-            "/api/v1/eth2/sign/:bls_pk_hex",
-            axum::routing::post(
-                puffersecuresigner::enclave::shared::handlers::secure_sign_bls::handler,
-            ),
-        )
-        .with_state(app_state);
+    let app = puffersecuresigner::enclave::validator::router(app_state);
 
     let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
 
@@ -74,6 +74,9 @@ fn generate_bls_keystore(
             .collect(),
         withdrawal_credentials: hex::encode(withdrawal_credentials),
         fork_version: crate::eth2::eth_types::GENESIS_FORK_VERSION,
+        attestation_type: Default::default(),
+        dcap_quote: String::new(),
+        dcap_collateral: None,
     })
 }
 
@@ -1,16 +1,89 @@
 pub const KEYS_DIR: &str = "./etc/keys/";
 pub const BLS_KEYS_DIR: &str = "./etc/keys/bls_keys/";
 pub const ETH_KEYS_DIR: &str = "./etc/keys/eth_keys/";
+pub const BLS_GROUPS_DIR: &str = "./etc/keys/bls_groups/";
+pub const BLS_KEY_METADATA_DIR: &str = "./etc/keys/bls_metadata/";
+pub const ETH_KEY_METADATA_DIR: &str = "./etc/keys/eth_metadata/";
+pub const BLS_KEY_MACS_DIR: &str = "./etc/keys/bls_macs/";
+pub const ETH_KEY_MACS_DIR: &str = "./etc/keys/eth_macs/";
+// Key authenticating saved key files, saved sealed to the enclave, see `io::sealing`
+pub const KEY_MAC_KEY_PATH: &str = "./etc/keys/key_mac_key";
+pub const VALIDATOR_INDICES_DIR: &str = "./etc/keys/validator_indices/";
 pub const SLASHING_PROTECTION_DIR: &str = "./etc/slashing/";
+// Genesis validators root of the network the slashing DB protects, set by the first import
+pub const SLASHING_GENESIS_VALIDATORS_ROOT_PATH: &str = "./etc/slashing_genesis_validators_root";
+
+// Consensus-layer BLS ciphersuite (proof-of-possession scheme, G2 signatures)
+pub const CIPHER_SUITE: &str = "BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+pub const BLS_LIBRARY: &str = "blsttc 8.0.2 (blst)";
+// Domain separation tag for proofs of possession under the same ciphersuite
+pub const BLS_POP_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+// Ciphersuites of the basic and message augmentation schemes, see `bls_keys::BlsScheme`
+pub const BLS_BASIC_CIPHER_SUITE: &str = "BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+pub const BLS_AUG_CIPHER_SUITE: &str = "BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_AUG_";
 
 pub const BLS_SIG_BYTES: usize = 96;
 pub const BLS_PUB_KEY_BYTES: usize = 48;
 pub const BLS_PRIV_KEY_BYTES: usize = 32;
+// Minimum input keying material accepted when deterministically deriving a BLS key
+pub const BLS_MIN_IKM_BYTES: usize = 32;
+// Order r of the BLS12-381 scalar field
+pub const BLS_CURVE_ORDER: [u8; 32] = [
+    0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1, 0xd8, 0x05,
+    0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+];
 pub const ETH_COMPRESSED_PK_BYTES: usize = 33;
 pub const ETH_UNCOMPRESSED_PK_BYTES: usize = 65;
 pub const ETH_SIGNATURE_BYTES: usize = 64;
 
+// Default bound on arbitrary messages signed as is rather than as a 32B signing root
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 1 << 20;
+
+// Most keys a single batch keygen request may generate
+pub const MAX_BATCH_KEYGEN: usize = 10_000;
+
+// Most public keys a single aggregate verify request may name, each costing a pairing
+pub const MAX_AGGREGATE_VERIFY_KEYS: usize = 1024;
+
+// Highest share index searched for a saved key share, imported shares are indexed by a u8
+pub const MAX_KEY_SHARES: usize = 256;
+
 pub const ALLOW_GROWABLE_SLASH_PROTECTION_DB: bool = false;
+// EIP-3076 interchange format version supported for import/export
+pub const SLASHING_INTERCHANGE_FORMAT_VERSION: &str = "5";
+// Newest signing request schema version accepted, assumed when a request omits `version`
+pub const SIGN_REQUEST_VERSION: u32 = 1;
+
+// BLS secret keys kept in memory after first use or preloading at boot
+pub const BLS_KEY_CACHE_CAPACITY: usize = 1024;
+pub const SIGNATURE_CACHE_CAPACITY: usize = 1024;
+pub const VERIFICATION_CACHE_CAPACITY: usize = 1024;
+
+// Readiness fails if less free disk space than this is available for keys and slashing data
+pub const READYZ_MIN_FREE_DISK_BYTES: u64 = 64 * 1024 * 1024;
 
 pub const FULL_DEPOSIT_AMOUNT: u64 = 32000000000;
+// Least the deposit contract accepts, 1 ETH in Gwei
+pub const MIN_DEPOSIT_AMOUNT: u64 = 1000000000;
+// staking-deposit-cli release whose deposit_data JSON shape generated deposits follow
+pub const DEPOSIT_CLI_VERSION: &str = "2.7.0";
 pub const WITHDRAWAL_CREDENTIALS_BYTES: usize = 32;
+
+// Bounds on each round trip to the Intel attestation service
+pub const IAS_TIMEOUT_SECS: u64 = 30;
+pub const IAS_CONNECT_TIMEOUT_SECS: u64 = 10;
+pub const IAS_MAX_RETRIES: u32 = 3;
+pub const IAS_BASE_BACKOFF_MS: u64 = 500;
+
+// Single-use nonces issued for replay-resistant non-consensus signing requests
+pub const NONCE_TTL_SECS: u64 = 60;
+
+// Bound on the unexpired keys recorded by the replay store, e.g. issued nonces
+pub const REPLAY_STORE_CAPACITY: usize = 4096;
+
+// Responses smaller than this are never compressed, e.g. signatures
+pub const DEFAULT_COMPRESSION_MIN_BYTES: u16 = 1024;
+
+// Bound on the audit events queued for the webhook, later events are dropped while it is full
+pub const WEBHOOK_QUEUE_CAPACITY: usize = 1024;
+pub const WEBHOOK_TIMEOUT_MILLIS: u64 = 5_000;
@@ -1,15 +1,30 @@
-use crate::constants::BLS_PUB_KEY_BYTES;
+use crate::constants::{
+    BLS_AUG_CIPHER_SUITE, BLS_BASIC_CIPHER_SUITE, BLS_CURVE_ORDER, BLS_MIN_IKM_BYTES, BLS_POP_DST,
+    BLS_PRIV_KEY_BYTES, BLS_PUB_KEY_BYTES, BLS_SIG_BYTES, CIPHER_SUITE, DEFAULT_MAX_MESSAGE_BYTES,
+    MAX_KEY_SHARES,
+};
+use crate::crypto::key_cache;
+use crate::crypto::signature_cache;
 use crate::io::key_management::{
-    read_bls_key, read_bls_keystore, write_bls_key, write_bls_keystore,
+    bls_key_exists, list_bls_keys, read_bls_group_pubkey, read_bls_key, read_bls_key_metadata,
+    read_bls_keystore, write_bls_group_pubkey, write_bls_key, write_bls_key_metadata,
+    write_bls_keystore, KeyMetadata, KeyRole,
 };
 use crate::strip_0x_prefix;
 
 use blsttc::{
-    PublicKeySet, PublicKeyShare, SecretKeySet, SecretKeyShare, Signature, SignatureShare,
+    Fr, Poly, PublicKey, PublicKeySet, PublicKeyShare, SecretKey, SecretKeySet, SecretKeyShare,
+    Signature, SignatureShare,
 };
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use hkdf::Hkdf;
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::{Zeroize, Zeroizing};
 
 /// Sanitizes a BLS public key hex string, and errors out if malformed.
 pub fn sanitize_bls_pk_hex(bls_pk_hex: &String) -> Result<String> {
@@ -21,6 +36,105 @@ pub fn sanitize_bls_pk_hex(bls_pk_hex: &String) -> Result<String> {
     Ok(bls_pk)
 }
 
+/// A blst decoding or validation failure, displayed with an actionable description
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlstError(pub blst::BLST_ERROR);
+
+impl BlstError {
+    pub fn describe(&self) -> &'static str {
+        use blst::BLST_ERROR::*;
+        match self.0 {
+            BLST_SUCCESS => "no error",
+            BLST_BAD_ENCODING => {
+                "bad encoding, expected a compressed point (48B pubkey, 96B signature) or 32B scalar"
+            }
+            BLST_POINT_NOT_ON_CURVE => {
+                "point is not on the BLS12-381 curve, the bytes are likely corrupted or truncated"
+            }
+            BLST_POINT_NOT_IN_GROUP => "point is on the curve but not in the prime-order subgroup",
+            BLST_AGGR_TYPE_MISMATCH => "mixed pubkey and signature types in one aggregate",
+            BLST_VERIFY_FAIL => "signature does not verify against the public key and message",
+            BLST_PK_IS_INFINITY => "point is the identity (infinity), which is never a valid key",
+            BLST_BAD_SCALAR => "secret key scalar is zero or not below the curve order",
+        }
+    }
+}
+
+impl std::fmt::Display for BlstError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {}", self.0, self.describe())
+    }
+}
+
+impl std::error::Error for BlstError {}
+
+/// Decodes a compressed BLS public key, rejecting infinity and points outside the G1 subgroup
+pub fn bls_pk_from_bytes(pk_bytes: &[u8]) -> Result<PublicKey> {
+    blst::min_pk::PublicKey::key_validate(pk_bytes)
+        .map_err(|e| anyhow!(BlstError(e)).context("Invalid BLS public key"))?;
+    let mut buf = [0_u8; BLS_PUB_KEY_BYTES];
+    buf.copy_from_slice(pk_bytes);
+    PublicKey::from_bytes(buf).map_err(|e| anyhow!("Invalid BLS public key: {:?}", e))
+}
+
+/// Hex variant of `bls_pk_from_bytes`, accepting an optional `0x` prefix
+pub fn bls_pk_from_hex(pk_hex: &str) -> Result<PublicKey> {
+    let pk_hex: &str = strip_0x_prefix!(pk_hex);
+    let pk_bytes = hex::decode(pk_hex).with_context(|| "Unable to hex-decode bls pk")?;
+    bls_pk_from_bytes(&pk_bytes)
+}
+
+/// Decodes a compressed BLS signature, rejecting infinity and points outside the G2 subgroup
+pub fn bls_sig_from_bytes(sig_bytes: &[u8]) -> Result<Signature> {
+    blst::min_pk::Signature::sig_validate(sig_bytes, true)
+        .map_err(|e| anyhow!(BlstError(e)).context("Invalid BLS signature"))?;
+    let mut buf = [0_u8; BLS_SIG_BYTES];
+    buf.copy_from_slice(sig_bytes);
+    Signature::from_bytes(buf).map_err(|e| anyhow!("Invalid BLS signature: {:?}", e))
+}
+
+/// Encodes `sig` as its uncompressed affine G2 point, x || y, so aggregators can skip
+/// decompression. The point is checked to be on the curve and in the G2 subgroup first.
+pub fn bls_sig_to_affine_bytes(sig: &Signature) -> Result<[u8; 2 * BLS_SIG_BYTES]> {
+    let sig = blst::min_pk::Signature::sig_validate(&sig.to_bytes(), true)
+        .map_err(|e| anyhow!(BlstError(e)).context("Invalid BLS signature"))?;
+    Ok(sig.serialize())
+}
+
+/// Decodes an uncompressed affine G2 point as produced by `bls_sig_to_affine_bytes`
+pub fn bls_sig_from_affine_bytes(affine: &[u8]) -> Result<Signature> {
+    let sig = blst::min_pk::Signature::sig_validate(affine, true)
+        .map_err(|e| anyhow!(BlstError(e)).context("Invalid affine BLS signature"))?;
+    bls_sig_from_bytes(&sig.compress())
+}
+
+/// Hex variant of `bls_sig_from_bytes`, accepting an optional `0x` prefix
+pub fn bls_sig_from_hex(sig_hex: &str) -> Result<Signature> {
+    let sig_hex: &str = strip_0x_prefix!(sig_hex);
+    let sig_bytes = hex::decode(sig_hex).with_context(|| "Unable to hex-decode bls signature")?;
+    bls_sig_from_bytes(&sig_bytes)
+}
+
+/// Decodes saved secret key set bytes, checking each 32B coefficient is a canonical scalar and
+/// that the secret itself is non-zero
+pub fn bls_sk_set_from_bytes(sk_bytes: Vec<u8>) -> Result<SecretKeySet> {
+    if sk_bytes.is_empty() || sk_bytes.len() % BLS_PRIV_KEY_BYTES != 0 {
+        return Err(anyhow!(BlstError(blst::BLST_ERROR::BLST_BAD_ENCODING))
+            .context("Invalid BLS secret key"));
+    }
+    let r = BigUint::from_bytes_be(&BLS_CURVE_ORDER);
+    let non_canonical = sk_bytes
+        .chunks(BLS_PRIV_KEY_BYTES)
+        .any(|c| BigUint::from_bytes_be(c) >= r);
+    if non_canonical || sk_bytes[..BLS_PRIV_KEY_BYTES].iter().all(|b| *b == 0) {
+        return Err(
+            anyhow!(BlstError(blst::BLST_ERROR::BLST_BAD_SCALAR)).context("Invalid BLS secret key")
+        );
+    }
+    SecretKeySet::from_bytes(sk_bytes)
+        .map_err(|e| anyhow!("Error deserializing bls sk bytes: {:?}", e))
+}
+
 /// Generate a new BLS secret key
 pub fn new_bls_key(threshold: usize) -> SecretKeySet {
     let mut rng = rand::thread_rng();
@@ -29,6 +143,210 @@ pub fn new_bls_key(threshold: usize) -> SecretKeySet {
     sk_set
 }
 
+/// Deterministically derive a BLS secret key from `ikm` and `key_info`, following the KeyGen
+/// procedure of https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-bls-signature-05#section-2.3
+pub fn new_bls_key_with_info(ikm: &[u8], key_info: &[u8]) -> Result<SecretKeySet> {
+    if ikm.len() < BLS_MIN_IKM_BYTES {
+        bail!("IKM must be at least {} bytes", BLS_MIN_IKM_BYTES)
+    }
+    let mut sk_bytes = hkdf_mod_r(ikm, key_info)?;
+    let sk_set = SecretKeySet::from_bytes(sk_bytes.to_vec());
+    sk_bytes.zeroize();
+    match sk_set {
+        Ok(sk_set) => Ok(sk_set),
+        Err(e) => bail!("Error deserializing derived bls sk bytes: {:?}", e),
+    }
+}
+
+/// HKDF_mod_r from https://eips.ethereum.org/EIPS/eip-2333#hkdf_mod_r, returning the big-endian
+/// bytes of a non-zero scalar mod r.
+pub fn hkdf_mod_r(ikm: &[u8], key_info: &[u8]) -> Result<[u8; BLS_PRIV_KEY_BYTES]> {
+    // L = ceil((3 * ceil(log2(r))) / 16) = 48
+    const L: usize = 48;
+    let r = BigUint::from_bytes_be(&BLS_CURVE_ORDER);
+
+    // IKM || I2OSP(0, 1)
+    let mut ikm_padded = ikm.to_vec();
+    ikm_padded.push(0);
+
+    // key_info || I2OSP(L, 2)
+    let mut info = key_info.to_vec();
+    info.extend_from_slice(&(L as u16).to_be_bytes());
+
+    let mut salt: Vec<u8> = b"BLS-SIG-KEYGEN-SALT-".to_vec();
+    loop {
+        salt = Sha256::digest(&salt).to_vec();
+        let mut okm = [0_u8; L];
+        let expanded = Hkdf::<Sha256>::new(Some(&salt), &ikm_padded).expand(&info, &mut okm);
+        if let Err(e) = expanded {
+            ikm_padded.zeroize();
+            return Err(anyhow!("HKDF expand failed: {:?}", e));
+        }
+        let sk = BigUint::from_bytes_be(&okm) % &r;
+        okm.zeroize();
+
+        if sk != BigUint::from(0_u8) {
+            ikm_padded.zeroize();
+            let sk_be = sk.to_bytes_be();
+            let mut sk_bytes = [0_u8; BLS_PRIV_KEY_BYTES];
+            sk_bytes[BLS_PRIV_KEY_BYTES - sk_be.len()..].copy_from_slice(&sk_be);
+            return Ok(sk_bytes);
+        }
+    }
+}
+
+/// derive_master_SK from https://eips.ethereum.org/EIPS/eip-2333#derive_master_sk
+pub fn derive_master_sk(seed: &[u8]) -> Result<[u8; BLS_PRIV_KEY_BYTES]> {
+    if seed.len() < BLS_MIN_IKM_BYTES {
+        bail!("Seed must be at least {} bytes", BLS_MIN_IKM_BYTES)
+    }
+    hkdf_mod_r(seed, b"")
+}
+
+/// derive_child_SK from https://eips.ethereum.org/EIPS/eip-2333#derive_child_sk
+pub fn derive_child_sk(
+    parent_sk: &[u8; BLS_PRIV_KEY_BYTES],
+    index: u32,
+) -> Result<[u8; BLS_PRIV_KEY_BYTES]> {
+    let compressed_lamport_pk = parent_sk_to_lamport_pk(parent_sk, index)?;
+    hkdf_mod_r(&compressed_lamport_pk, b"")
+}
+
+fn ikm_to_lamport_sk(ikm: &[u8], salt: &[u8]) -> Result<Vec<u8>> {
+    // 255 chunks of 32 bytes
+    let mut okm = vec![0_u8; 32 * 255];
+    match Hkdf::<Sha256>::new(Some(salt), ikm).expand(b"", &mut okm) {
+        Ok(()) => Ok(okm),
+        Err(e) => {
+            okm.zeroize();
+            bail!("HKDF expand failed: {:?}", e)
+        }
+    }
+}
+
+fn parent_sk_to_lamport_pk(parent_sk: &[u8; BLS_PRIV_KEY_BYTES], index: u32) -> Result<[u8; 32]> {
+    let salt = index.to_be_bytes();
+    let mut not_ikm: Vec<u8> = parent_sk.iter().map(|b| !b).collect();
+
+    let lamport_0 = ikm_to_lamport_sk(parent_sk, &salt);
+    let lamport_1 = ikm_to_lamport_sk(&not_ikm, &salt);
+    not_ikm.zeroize();
+    let (mut lamport_0, mut lamport_1) = match (lamport_0, lamport_1) {
+        (Ok(l0), Ok(l1)) => (l0, l1),
+        (Ok(mut l), Err(e)) | (Err(e), Ok(mut l)) => {
+            l.zeroize();
+            return Err(e);
+        }
+        (Err(e), Err(_)) => return Err(e),
+    };
+
+    let mut hasher = Sha256::new();
+    for chunk in lamport_0.chunks(32).chain(lamport_1.chunks(32)) {
+        hasher.update(Sha256::digest(chunk));
+    }
+    lamport_0.zeroize();
+    lamport_1.zeroize();
+    Ok(hasher.finalize().into())
+}
+
+/// Parses an EIP-2334 path such as "m/12381/3600/0/0/0" into its indices
+pub fn parse_derivation_path(path: &str) -> Result<Vec<u32>> {
+    let mut nodes = path.split('/');
+    if nodes.next() != Some("m") {
+        bail!("Derivation path must start with 'm': {path}")
+    }
+    nodes
+        .map(|n| {
+            n.parse::<u32>()
+                .with_context(|| format!("Invalid derivation path index '{n}' in {path}"))
+        })
+        .collect()
+}
+
+/// Derives the BLS secret key at the EIP-2334 `path` from `seed`
+pub fn derive_bls_key_at_path(seed: &[u8], path: &str) -> Result<SecretKeySet> {
+    let indices = parse_derivation_path(path)?;
+    let mut sk = derive_master_sk(seed)?;
+    for index in indices {
+        let child = derive_child_sk(&sk, index);
+        sk.zeroize();
+        sk = child?;
+    }
+    let sk_set = SecretKeySet::from_bytes(sk.to_vec());
+    sk.zeroize();
+    match sk_set {
+        Ok(sk_set) => Ok(sk_set),
+        Err(e) => bail!("Error deserializing derived bls sk bytes: {:?}", e),
+    }
+}
+
+/// Derives the BLS key at the EIP-2334 `path` from `seed` and saves it, recording the path
+/// in the key's sidecar metadata. Refuses to overwrite an existing key.
+pub fn save_bls_key_from_seed(seed: &[u8], path: &str) -> Result<PublicKey> {
+    let sk_set = derive_bls_key_at_path(seed, path)?;
+    let mut sk_bytes = sk_set.to_bytes();
+    let res = import_bls_secret_bytes(&sk_bytes);
+    sk_bytes.zeroize();
+    let pk = res?;
+
+    let metadata = KeyMetadata {
+        derivation_path: Some(path.to_string()),
+        ..Default::default()
+    };
+    write_bls_key_metadata(&pk.to_hex(), &metadata)?;
+    Ok(pk)
+}
+
+/// BIP-39 seed of `mnemonic` and the optional `passphrase`, errors if the mnemonic is not a
+/// valid English mnemonic or its checksum does not match
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: Option<&str>) -> Result<[u8; 64]> {
+    let normalized: Zeroizing<String> = Zeroizing::new(mnemonic.nfkd().collect());
+    let mut mnemonic = bip39::Mnemonic::parse_in_normalized(bip39::Language::English, &normalized)
+        .map_err(|e| anyhow!("Invalid BIP-39 mnemonic: {e}"))?;
+    let passphrase: Zeroizing<String> =
+        Zeroizing::new(passphrase.unwrap_or_default().nfkd().collect());
+    let seed = mnemonic.to_seed_normalized(&passphrase);
+    mnemonic.zeroize();
+    Ok(seed)
+}
+
+/// EIP-2334 path of the signing key of validator `index`
+pub fn validator_signing_key_path(index: u32) -> String {
+    format!("m/12381/3600/{index}/0/0")
+}
+
+/// Derives the signing key of validator `index` from a BIP-39 `mnemonic`, at the EIP-2334 path
+/// `m/12381/3600/{index}/0/0`
+pub fn derive_bls_key_from_mnemonic(
+    mnemonic: &str,
+    passphrase: Option<&str>,
+    index: u32,
+) -> Result<SecretKeySet> {
+    let mut seed = mnemonic_to_seed(mnemonic, passphrase)?;
+    let sk_set = derive_bls_key_at_path(&seed, &validator_signing_key_path(index));
+    seed.zeroize();
+    sk_set
+}
+
+/// Derives the signing key of validator `index` from a BIP-39 `mnemonic` and saves it like
+/// `save_bls_key_from_seed`. Refuses to overwrite an existing key.
+pub fn save_bls_key_from_mnemonic(
+    mnemonic: &str,
+    passphrase: Option<&str>,
+    index: u32,
+) -> Result<PublicKey> {
+    let mut seed = mnemonic_to_seed(mnemonic, passphrase)?;
+    let pk = save_bls_key_from_seed(&seed, &validator_signing_key_path(index));
+    seed.zeroize();
+    pk
+}
+
+/// Returns the EIP-2334 path the saved BLS key `pk_hex` was derived at, if known
+pub fn get_derivation_path(pk_hex: &String) -> Result<Option<String>> {
+    let pk_hex: &str = strip_0x_prefix!(pk_hex);
+    Ok(read_bls_key_metadata(pk_hex)?.and_then(|m| m.derivation_path))
+}
+
 /// Write the BLS secret key to a secure file using the hex encoded pk as filename
 pub fn save_bls_key(sk_set: &SecretKeySet) -> Result<()> {
     // Hex-encode pk and sk
@@ -39,8 +357,177 @@ pub fn save_bls_key(sk_set: &SecretKeySet) -> Result<()> {
     write_bls_key(&pk_hex, &sk_hex).with_context(|| "aggregate bls sk failed to save")
 }
 
+/// Saves the BLS secret key like `save_bls_key`, recording `role` in the key's sidecar metadata
+pub fn save_bls_key_with_role(sk_set: &SecretKeySet, role: KeyRole) -> Result<()> {
+    save_bls_key(sk_set)?;
+    let metadata = KeyMetadata {
+        role: Some(role),
+        ..Default::default()
+    };
+    write_bls_key_metadata(&sk_set.public_keys().public_key().to_hex(), &metadata)
+}
+
+/// Write the BLS secret key share to a secure file using the hex encoded pk share as filename, and
+/// store the group `PublicKeySet` alongside it. Returns the group_id (the hex encoded pk share).
+pub fn save_bls_key_share(sk_share: &SecretKeyShare, pk_set: &PublicKeySet) -> Result<String> {
+    // Hex-encode pk share and sk share
+    let group_id = hex::encode(sk_share.public_key_share().to_bytes());
+    let sk_hex = hex::encode(sk_share.to_bytes());
+
+    // Save to file
+    write_bls_key(&group_id, &sk_hex).with_context(|| "bls sk share failed to save")?;
+    write_bls_group_pubkey(&group_id, &hex::encode(pk_set.to_bytes()))
+        .with_context(|| "bls group pubkey failed to save")?;
+    Ok(group_id)
+}
+
+// Wipes an `Fr` that held secret material. Fr is plain limbs, so all zero bytes is a valid value.
+fn clear_fr(fr: &mut Fr) {
+    let bytes = unsafe {
+        std::slice::from_raw_parts_mut(fr as *mut Fr as *mut u8, std::mem::size_of::<Fr>())
+    };
+    bytes.zeroize();
+}
+
+/// Checks that externally generated threshold `shares` all lie on one polynomial of degree
+/// `threshold` whose secret matches `claimed_group_pk`. `(i, sk)` is the share blsttc derives
+/// with `secret_key_share(i)`. The secret is interpolated from the first `threshold + 1` shares
+/// and every remaining share must match the interpolated polynomial, so a bad dealer cannot
+/// slip in an inconsistent share.
+pub fn verify_shares_consistency(
+    shares: &[(u8, SecretKey)],
+    claimed_group_pk: &PublicKey,
+    threshold: usize,
+) -> Result<()> {
+    if shares.len() <= threshold {
+        bail!(
+            "Need at least {} shares to reconstruct a threshold {threshold} key, got {}",
+            threshold + 1,
+            shares.len()
+        )
+    }
+    let mut ids: Vec<u8> = shares.iter().map(|(i, _)| *i).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    if ids.len() != shares.len() {
+        bail!("Duplicate share indices")
+    }
+
+    // Share i is the polynomial evaluated at i + 1. The secret is only ever held in blsttc types,
+    // which zeroize on drop, or in `samples`, which is wiped before returning.
+    let mut samples: Vec<(u64, Fr)> = Vec::with_capacity(threshold + 1);
+    let mut parsed = Ok(());
+    for (i, sk) in &shares[..=threshold] {
+        let mut sk_bytes = sk.to_bytes();
+        let y: Option<Fr> = Fr::from_bytes_be(&sk_bytes).into();
+        sk_bytes.zeroize();
+        match y {
+            Some(y) => samples.push((*i as u64 + 1, y)),
+            None => {
+                parsed = Err(anyhow!("Share {i} is not a valid scalar"));
+                break;
+            }
+        }
+    }
+    let poly = parsed.and_then(|_| {
+        Poly::interpolate(samples.iter().copied())
+            .map_err(|e| anyhow!("Failed to interpolate the shares: {:?}", e))
+    });
+    samples.iter_mut().for_each(|(_, y)| clear_fr(y));
+    let pk_set = SecretKeySet::from(poly?).public_keys();
+    if pk_set.public_key() != *claimed_group_pk {
+        bail!("Shares do not reconstruct the claimed group public key")
+    }
+
+    // The remaining shares must be points of the same polynomial
+    for (i, sk) in shares.iter().skip(threshold + 1) {
+        if pk_set.public_key_share(*i as usize).to_bytes() != sk.public_key().to_bytes() {
+            bail!("Share {i} is inconsistent with the other shares")
+        }
+    }
+    Ok(())
+}
+
+/// Imports externally generated threshold shares of the group `pk_set`, saving each alongside the
+/// group public key. Rejects the import unless `verify_shares_consistency` passes.
+/// Returns the group_id of each saved share.
+pub fn import_bls_key_shares(
+    shares: &[(u8, SecretKey)],
+    pk_set: &PublicKeySet,
+) -> Result<Vec<String>> {
+    verify_shares_consistency(shares, &pk_set.public_key(), pk_set.threshold())
+        .with_context(|| "Rejected threshold share import")?;
+    shares
+        .iter()
+        .map(|(_, sk)| {
+            let sk_share = SecretKeyShare::from_bytes(sk.to_bytes())
+                .map_err(|e| anyhow!("Invalid BLS secret key share: {:?}", e))?;
+            save_bls_key_share(&sk_share, pk_set)
+        })
+        .collect()
+}
+
+/// Read the BLS group `PublicKeySet` stored alongside the key share identified by `group_id`
+pub fn get_group_pubkey_set(group_id: &String) -> Result<PublicKeySet> {
+    let pk_set_bytes = read_bls_group_pubkey(group_id)?;
+    match PublicKeySet::from_bytes(pk_set_bytes) {
+        Ok(pk_set) => Ok(pk_set),
+        Err(e) => bail!("Error deserializing bls group pubkey bytes: {:?}", e),
+    }
+}
+
+/// Read the BLS group public key stored alongside the key share identified by `group_id`
+pub fn get_group_pubkey(group_id: &String) -> Result<PublicKey> {
+    Ok(get_group_pubkey_set(group_id)?.public_key())
+}
+
+/// Returns the index `i` blsttc derives the saved key share `group_id` with in
+/// `secret_key_share(i)`, which its partial signatures must be combined under
+pub fn get_key_share_index(group_id: &String) -> Result<usize> {
+    let group_id: String = strip_0x_prefix!(group_id);
+    let group_id = group_id.to_lowercase();
+    let pk_set = get_group_pubkey_set(&group_id)?;
+    (0..MAX_KEY_SHARES)
+        .find(|i| hex::encode(pk_set.public_key_share(*i).to_bytes()) == group_id)
+        .with_context(|| {
+            format!("Key share {group_id} is not among the first {MAX_KEY_SHARES} of its group")
+        })
+}
+
+/// Signs `msg` with each of the saved key shares `group_ids` of a single group, returning the
+/// group `PublicKeySet` and each share's index and partial signature
+pub fn bls_sign_with_key_shares(
+    group_ids: &[String],
+    msg: &[u8],
+) -> Result<(PublicKeySet, Vec<(usize, SignatureShare)>)> {
+    check_message_size(msg)?;
+    let Some(first) = group_ids.first() else {
+        bail!("No key shares to sign with")
+    };
+    let pk_set = get_group_pubkey_set(first)?;
+    let mut partials = Vec::with_capacity(group_ids.len());
+    for group_id in group_ids {
+        if get_group_pubkey_set(group_id)? != pk_set {
+            bail!("Key share {group_id} belongs to another group")
+        }
+        let index = get_key_share_index(group_id)?;
+        if partials.iter().any(|(i, _)| *i == index) {
+            bail!("Duplicate key share {group_id}")
+        }
+        let mut sk_bytes = fetch_bls_sk(group_id)?.secret_key().to_bytes();
+        let sk_share = SecretKeyShare::from_bytes(sk_bytes);
+        sk_bytes.zeroize();
+        let sk_share = sk_share.map_err(|e| anyhow!("Invalid BLS secret key share: {:?}", e))?;
+        partials.push((index, sk_share.sign(msg)));
+    }
+    Ok((pk_set, partials))
+}
+
 /// Write the BLS secret key to an encrypted using the hex encoded pk as filename
 pub fn save_bls_keystore(sk_set: &SecretKeySet, password: &String) -> Result<String> {
+    // Enforce the configured password policy on keystore creation
+    crate::crypto::keystore::password_policy().check(password)?;
+
     // Hex-encode pk
     let pk_hex = sk_set.public_keys().public_key().to_hex();
 
@@ -54,24 +541,85 @@ pub fn save_bls_keystore(sk_set: &SecretKeySet, password: &String) -> Result<Str
     Ok(uuid)
 }
 
-/// Read the BLS secret key from a secure file using the hex encoded pk as filename
+/// Imports a raw hex-encoded BLS secret key and saves it using the derived hex encoded pk as filename.
+/// Refuses to overwrite an existing key. The decoded secret bytes are zeroized on every path.
+pub fn import_bls_secret_hex(sk_hex: &str) -> Result<PublicKey> {
+    let sk_hex: &str = strip_0x_prefix!(sk_hex);
+    if sk_hex.len() != 2 * BLS_PRIV_KEY_BYTES {
+        bail!("Invalid bls sk length")
+    }
+    // Decoded in place, so a bad character leaves no partially decoded copy behind
+    let mut sk_bytes = Zeroizing::new([0_u8; BLS_PRIV_KEY_BYTES]);
+    hex::decode_to_slice(sk_hex, &mut sk_bytes[..])
+        .with_context(|| "Unable to hex-decode bls sk")?;
+    import_bls_secret_bytes(&sk_bytes[..])
+}
+
+/// Bytes variant of `import_bls_secret_hex`, leaving zeroizing `sk_bytes` to the caller
+pub fn import_bls_secret_bytes(sk_bytes: &[u8]) -> Result<PublicKey> {
+    if sk_bytes.len() != BLS_PRIV_KEY_BYTES {
+        bail!("Invalid bls sk length")
+    }
+    if sk_bytes.iter().all(|b| *b == 0) {
+        bail!("Invalid bls sk, scalar is zero")
+    }
+
+    // Validate the scalar is canonical
+    let mut sk_buf = Zeroizing::new([0_u8; BLS_PRIV_KEY_BYTES]);
+    sk_buf.copy_from_slice(sk_bytes);
+    let pk = match SecretKey::from_bytes(*sk_buf) {
+        Ok(sk) => sk.public_key(),
+        Err(e) => bail!("Invalid bls sk scalar: {:?}", e),
+    };
+
+    if bls_key_exists(&pk.to_hex()) {
+        bail!("BLS key already exists for pk: {}", pk.to_hex())
+    }
+
+    // Built from the scalar rather than `SecretKeySet::from_bytes`, which would take an owned
+    // copy of the secret bytes that is freed without being zeroized
+    let fr: Option<Fr> = Fr::from_bytes_be(&sk_buf).into();
+    let Some(mut fr) = fr else {
+        bail!("Error deserializing bls sk bytes")
+    };
+    let sk_set = SecretKeySet::from(Poly::constant(fr));
+    clear_fr(&mut fr);
+    save_bls_key(&sk_set)?;
+    Ok(pk)
+}
+
+/// Read the BLS secret key from a secure file using the hex encoded pk as filename.
+/// Keys are served from the in-memory key cache after their first read, so the key file's MAC is
+/// only checked when the key is read into the cache. Changes to the file after that are caught by
+/// the first read after the key is evicted, e.g. by a restart.
 pub fn fetch_bls_sk(pk_hex: &String) -> Result<SecretKeySet> {
     let pk_hex: &str = strip_0x_prefix!(pk_hex);
-    let sk_bytes = read_bls_key(pk_hex)?;
-    match SecretKeySet::from_bytes(sk_bytes) {
-        Ok(sk) => Ok(sk),
-        Err(e) => bail!("Error deserializing bls sk bytes: {:?}", e),
+    key_cache::get_or_fill(pk_hex, || {
+        let sk_bytes = read_bls_key(pk_hex)?;
+        bls_sk_set_from_bytes(sk_bytes)
+            .with_context(|| format!("Saved BLS key {pk_hex} is unusable"))
+    })
+}
+
+/// Reads each saved BLS key in `pks` into the key cache upfront so that its first signature
+/// doesn't touch the filesystem. Returns how many keys were loaded.
+pub fn preload_keys(pks: &[String]) -> Result<usize> {
+    for pk_hex in pks {
+        fetch_bls_sk(pk_hex).with_context(|| format!("Failed to preload BLS key {pk_hex}"))?;
     }
+    Ok(pks.len())
+}
+
+/// Preloads every saved BLS key, see `preload_keys`
+pub fn preload_all_keys() -> Result<usize> {
+    preload_keys(&list_bls_keys()?)
 }
 
 /// Read the BLS secret key from an encrypted keystore file using the hex encoded pk as filename
 pub fn fetch_bls_sk_keystore(pk_hex: &String, password: &String) -> Result<SecretKeySet> {
     let pk_hex: &str = strip_0x_prefix!(pk_hex);
     let sk_bytes = read_bls_keystore(&pk_hex.to_string(), password)?;
-    match SecretKeySet::from_bytes(sk_bytes) {
-        Ok(sk) => Ok(sk),
-        Err(e) => bail!("Error deserializing bls sk bytes: {:?}", e),
-    }
+    bls_sk_set_from_bytes(sk_bytes)
 }
 
 /// Returns BLS signature over `msg` using the supplied BLS secret key
@@ -79,9 +627,50 @@ pub fn bls_agg_sign(secret_key_set: &SecretKeySet, msg: &[u8]) -> Signature {
     secret_key_set.secret_key().sign(msg)
 }
 
+/// Longest message the arbitrary-bytes signing paths accept, read from
+/// `SECURE_SIGNER_MAX_MESSAGE_BYTES`. Defaults to `DEFAULT_MAX_MESSAGE_BYTES`, and fails on a
+/// malformed value rather than silently using the default.
+pub fn max_message_bytes_from_env() -> Result<usize> {
+    parse_max_message_bytes(
+        std::env::var("SECURE_SIGNER_MAX_MESSAGE_BYTES")
+            .ok()
+            .as_deref(),
+    )
+}
+
+fn parse_max_message_bytes(max: Option<&str>) -> Result<usize> {
+    match max {
+        Some(max) if !max.is_empty() => max
+            .parse()
+            .with_context(|| format!("Bad SECURE_SIGNER_MAX_MESSAGE_BYTES {max}")),
+        _ => Ok(DEFAULT_MAX_MESSAGE_BYTES),
+    }
+}
+
+/// The `max_message_bytes_from_env` limit, read once. The binaries refuse to start with a
+/// malformed value, so the default only stands in for it outside of them.
+pub fn max_message_bytes() -> usize {
+    static MAX: OnceLock<usize> = OnceLock::new();
+    *MAX.get_or_init(|| max_message_bytes_from_env().unwrap_or(DEFAULT_MAX_MESSAGE_BYTES))
+}
+
+/// Rejects messages longer than `max_message_bytes` before they are hashed
+pub fn check_message_size(msg: &[u8]) -> Result<()> {
+    let max = max_message_bytes();
+    if msg.len() > max {
+        bail!(
+            "Message of {} bytes exceeds the maximum signable message size of {max} bytes",
+            msg.len()
+        )
+    }
+    Ok(())
+}
+
 /// Performs BLS signature on `msg` using the BLS secret key looked up from memory
 /// with pk_hex as the file name.
 pub fn bls_agg_sign_from_saved_sk(pk_hex: &String, msg: &[u8]) -> Result<Signature> {
+    check_message_size(msg)?;
+
     // Fetch the secret key set from memory using the provided pk_hex
     let secret_key_set = fetch_bls_sk(pk_hex)?;
 
@@ -94,8 +683,120 @@ pub fn bls_agg_sign_from_saved_sk(pk_hex: &String, msg: &[u8]) -> Result<Signatu
     Ok(bls_agg_sign(&secret_key_set, msg))
 }
 
+/// Wrapper over `bls_agg_sign_from_saved_sk` that serves exact repeats of a 32B signing root
+/// from the bounded signature cache rather than re-reading and re-using the secret key.
+pub fn bls_agg_sign_root_from_saved_sk(
+    pk_hex: &String,
+    signing_root: &[u8; 32],
+) -> Result<Signature> {
+    signature_cache::get_or_sign(pk_hex, signing_root, || {
+        bls_agg_sign_from_saved_sk(pk_hex, signing_root)
+    })
+}
+
+/// Returns BLS signature over `msg` hashed to G2 under `dst`. The default `CIPHER_SUITE` signs
+/// through blsttc exactly as `bls_agg_sign`, any other DST is signed with blst.
+pub fn bls_sign_with_dst(
+    secret_key_set: &SecretKeySet,
+    msg: &[u8],
+    dst: &[u8],
+) -> Result<Signature> {
+    check_message_size(msg)?;
+    if dst == CIPHER_SUITE.as_bytes() {
+        return Ok(bls_agg_sign(secret_key_set, msg));
+    }
+    let mut sk_bytes = secret_key_set.secret_key().to_bytes();
+    let sk = blst::min_pk::SecretKey::from_bytes(&sk_bytes);
+    sk_bytes.zeroize();
+    let sk = sk.map_err(|e| anyhow!(BlstError(e)).context("Invalid BLS secret key"))?;
+    bls_sig_from_bytes(&sk.sign(msg, dst, &[]).compress())
+}
+
+/// Returns true if `sig` is a valid signature by `pk` over `msg` hashed to G2 under `dst`
+pub fn bls_verify_with_dst(pk: &PublicKey, sig: &Signature, msg: &[u8], dst: &[u8]) -> bool {
+    let (Ok(pk), Ok(sig)) = (
+        blst::min_pk::PublicKey::from_bytes(&pk.to_bytes()),
+        blst::min_pk::Signature::from_bytes(&sig.to_bytes()),
+    ) else {
+        return false;
+    };
+    sig.verify(true, msg, dst, &[], &pk, true) == blst::BLST_ERROR::BLST_SUCCESS
+}
+
+/// The BLS signature schemes of the IETF BLS draft. Eth2 signs under `ProofOfPossession`, whose
+/// ciphersuite is `CIPHER_SUITE`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlsScheme {
+    /// Aggregates are only secure over distinct messages
+    Basic,
+    /// The signer's public key is prepended to every message it signs
+    MessageAugmentation,
+    /// Public keys carry a proof of possession, see `aggregate_pubkeys_hex_with_pop`
+    #[default]
+    ProofOfPossession,
+}
+
+impl BlsScheme {
+    /// The domain separation tag messages are hashed to G2 under
+    pub fn dst(&self) -> &'static [u8] {
+        match self {
+            BlsScheme::Basic => BLS_BASIC_CIPHER_SUITE.as_bytes(),
+            BlsScheme::MessageAugmentation => BLS_AUG_CIPHER_SUITE.as_bytes(),
+            BlsScheme::ProofOfPossession => CIPHER_SUITE.as_bytes(),
+        }
+    }
+
+    /// Returns the bytes actually signed for `msg` by `pk`, `pk || msg` under augmentation
+    pub fn augment(&self, pk: &PublicKey, msg: &[u8]) -> Vec<u8> {
+        match self {
+            BlsScheme::MessageAugmentation => [&pk.to_bytes()[..], msg].concat(),
+            _ => msg.to_vec(),
+        }
+    }
+}
+
+/// Returns the BLS signature over `msg` under `scheme`
+pub fn bls_sign_with_scheme(
+    secret_key_set: &SecretKeySet,
+    msg: &[u8],
+    scheme: BlsScheme,
+) -> Result<Signature> {
+    check_message_size(msg)?;
+    let pk = secret_key_set.public_keys().public_key();
+    bls_sign_with_dst(secret_key_set, &scheme.augment(&pk, msg), scheme.dst())
+}
+
+/// Returns true if `sig` is a valid signature by `pk` over `msg` under `scheme`
+pub fn bls_verify_with_scheme(
+    pk: &PublicKey,
+    sig: &Signature,
+    msg: &[u8],
+    scheme: BlsScheme,
+) -> bool {
+    bls_verify_with_dst(pk, sig, &scheme.augment(pk, msg), scheme.dst())
+}
+
+/// Variant of `bls_agg_sign_root_from_saved_sk` signing under a network specific `dst`. The
+/// signature cache is keyed by (pk, root) only, so it is used for the default DST alone.
+pub fn bls_sign_root_from_saved_sk_with_dst(
+    pk_hex: &String,
+    signing_root: &[u8; 32],
+    dst: &[u8],
+) -> Result<Signature> {
+    if dst == CIPHER_SUITE.as_bytes() {
+        return bls_agg_sign_root_from_saved_sk(pk_hex, signing_root);
+    }
+    let secret_key_set = fetch_bls_sk(pk_hex)?;
+    if pk_hex != &secret_key_set.public_keys().public_key().to_hex() {
+        bail!("Mismatch with input and derived pk");
+    }
+    bls_sign_with_dst(&secret_key_set, signing_root, dst)
+}
+
 /// Distributes `n` key shares from a given BLS `SecretKeySet`.
 /// Returns a vector of tuples containing the `SecretKeyShare` and corresponding `PublicKeyShare` for each node.
+/// Element `i` is always the share at index `i`, the index its signature shares must be combined
+/// under and the one `get_key_share_index` recovers for a saved share.
 ///
 /// # Arguments
 ///
@@ -109,7 +810,6 @@ pub fn distribute_key_shares(
 
     (0..n)
         .map(|id| {
-            // TODO randomize each id
             let sk_share = sk_set.secret_key_share(id);
             let pk_share = pk_set.public_key_share(id);
             (sk_share, pk_share)
@@ -150,10 +850,430 @@ pub fn aggregate_signature_shares(
     Ok(aggregated_signature)
 }
 
+// Decodes each hex public key, checking it is a non-identity point in the G1 subgroup
+fn parse_validated_pubkeys(pk_hexes: &[String]) -> Result<Vec<blst::min_pk::PublicKey>> {
+    if pk_hexes.is_empty() {
+        bail!("No BLS public keys to aggregate")
+    }
+    pk_hexes
+        .iter()
+        .map(|pk_hex| {
+            let pk_hex = sanitize_bls_pk_hex(pk_hex)?;
+            let pk_bytes = hex::decode(&pk_hex).with_context(|| "Unable to hex-decode bls pk")?;
+            blst::min_pk::PublicKey::key_validate(&pk_bytes).map_err(|e| {
+                anyhow!(BlstError(e)).context(format!("Invalid BLS public key {pk_hex}"))
+            })
+        })
+        .collect()
+}
+
+fn aggregate_validated_pubkeys(pks: &[blst::min_pk::PublicKey]) -> Result<String> {
+    let pk_refs: Vec<&blst::min_pk::PublicKey> = pks.iter().collect();
+    // Re-checked here so the aggregate is never degenerate, whatever the caller validated
+    let agg = blst::min_pk::AggregatePublicKey::aggregate(&pk_refs, true)
+        .map_err(|e| anyhow!("Failed to aggregate BLS public keys: {:?}", e))?;
+    Ok(hex::encode(agg.to_public_key().compress()))
+}
+
+/// Aggregates hex-encoded BLS public keys received from peers, validating each is a non-identity
+/// point in the G1 subgroup. Prefer `aggregate_pubkeys_hex_with_pop` for untrusted keys, as
+/// aggregating keys without proofs of possession is open to rogue key attacks.
+pub fn aggregate_pubkeys_hex(pk_hexes: &[String]) -> Result<String> {
+    aggregate_validated_pubkeys(&parse_validated_pubkeys(pk_hexes)?)
+}
+
+/// Aggregates hex-encoded BLS public keys, requiring `pop_hexes[i]` to be a valid proof of
+/// possession (under `BLS_POP_DST`) for `pk_hexes[i]`.
+pub fn aggregate_pubkeys_hex_with_pop(pk_hexes: &[String], pop_hexes: &[String]) -> Result<String> {
+    if pk_hexes.len() != pop_hexes.len() {
+        bail!(
+            "Expected one proof of possession per public key, got {} for {}",
+            pop_hexes.len(),
+            pk_hexes.len()
+        )
+    }
+    let pks = parse_validated_pubkeys(pk_hexes)?;
+    for (i, (pk, pop_hex)) in pks.iter().zip(pop_hexes.iter()).enumerate() {
+        let pop_hex: String = strip_0x_prefix!(pop_hex);
+        let pop_bytes = hex::decode(&pop_hex).with_context(|| "Unable to hex-decode PoP")?;
+        let pop = blst::min_pk::Signature::from_bytes(&pop_bytes).map_err(|e| {
+            anyhow!(BlstError(e)).context(format!("Invalid proof of possession at index {i}"))
+        })?;
+        let res = pop.verify(true, &pk.compress(), BLS_POP_DST, &[], pk, false);
+        if res != blst::BLST_ERROR::BLST_SUCCESS {
+            bail!(
+                "Proof of possession failed to verify at index {i}: {}",
+                BlstError(res)
+            )
+        }
+    }
+    aggregate_validated_pubkeys(&pks)
+}
+
+/// Number of pairings (Miller loops) and wall time spent by one aggregate or batch verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyProfile {
+    pub pairings: usize,
+    pub elapsed: std::time::Duration,
+}
+
+/// Result of an aggregate or batch verification. `profile` is only populated if requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyOutcome {
+    pub valid: bool,
+    pub profile: Option<VerifyProfile>,
+}
+
+impl VerifyOutcome {
+    // Runs the blst call `verify` performing `pairings` pairings, timing it only if profiling
+    fn run<F>(profile: bool, pairings: usize, verify: F) -> Self
+    where
+        F: FnOnce() -> blst::BLST_ERROR,
+    {
+        let start = profile.then(std::time::Instant::now);
+        let valid = verify() == blst::BLST_ERROR::BLST_SUCCESS;
+        VerifyOutcome {
+            valid,
+            profile: start.map(|start| VerifyProfile {
+                pairings,
+                elapsed: start.elapsed(),
+            }),
+        }
+    }
+}
+
+// Rejects the identity and points outside the G1 subgroup, which `PublicKey` does not rule out
+fn to_blst_pks(pks: &[PublicKey]) -> Result<Vec<blst::min_pk::PublicKey>> {
+    if pks.is_empty() {
+        bail!("No BLS public keys to verify against")
+    }
+    pks.iter()
+        .map(|pk| {
+            blst::min_pk::PublicKey::key_validate(&pk.to_bytes())
+                .map_err(|e| anyhow!(BlstError(e)).context("Invalid BLS public key"))
+        })
+        .collect()
+}
+
+fn to_blst_sig(sig: &Signature) -> Result<blst::min_pk::Signature> {
+    blst::min_pk::Signature::from_bytes(&sig.to_bytes())
+        .map_err(|e| anyhow!(BlstError(e)).context("Invalid BLS signature"))
+}
+
+/// Aggregates BLS signatures into one, checking each is in the G2 subgroup
+pub fn aggregate_bls_sigs(sigs: &[Signature]) -> Result<Signature> {
+    if sigs.is_empty() {
+        bail!("No BLS signatures to aggregate")
+    }
+    let sigs = sigs.iter().map(to_blst_sig).collect::<Result<Vec<_>>>()?;
+    let sig_refs: Vec<&blst::min_pk::Signature> = sigs.iter().collect();
+    let agg = blst::min_pk::AggregateSignature::aggregate(&sig_refs, true)
+        .map_err(|e| anyhow!(BlstError(e)).context("Failed to aggregate BLS signatures"))?;
+    bls_sig_from_bytes(&agg.to_signature().compress())
+}
+
+/// Aggregates the public keys of the committee members whose `participation_bits` are set, the
+/// key an aggregate attestation's signature is checked against
+pub fn aggregate_committee_pubkeys(
+    pks: &[PublicKey],
+    participation_bits: &[bool],
+) -> Result<PublicKey> {
+    if pks.len() != participation_bits.len() {
+        bail!(
+            "Expected one participation bit per committee member, got {} for {}",
+            participation_bits.len(),
+            pks.len()
+        )
+    }
+    let participants: Vec<PublicKey> = pks
+        .iter()
+        .zip(participation_bits.iter())
+        .filter(|(_, bit)| **bit)
+        .map(|(pk, _)| *pk)
+        .collect();
+    if participants.is_empty() {
+        bail!("No committee members participated")
+    }
+    aggregate_bls_pubkeys(&participants)
+        .with_context(|| "Failed to aggregate committee public keys")
+}
+
+/// Aggregates BLS public keys into one, checking each is a non-identity point in the G1 subgroup
+pub fn aggregate_bls_pubkeys(pks: &[PublicKey]) -> Result<PublicKey> {
+    let pks = to_blst_pks(pks)?;
+    let pk_refs: Vec<&blst::min_pk::PublicKey> = pks.iter().collect();
+    let agg = blst::min_pk::AggregatePublicKey::aggregate(&pk_refs, true)
+        .map_err(|e| anyhow!(BlstError(e)).context("Failed to aggregate BLS public keys"))?;
+    bls_pk_from_bytes(&agg.to_public_key().compress())
+}
+
+fn check_one_sig_per_pk(pks: &[PublicKey], sigs: &[Signature]) -> Result<()> {
+    if pks.len() != sigs.len() {
+        bail!(
+            "Expected one signature per public key, got {} for {}",
+            sigs.len(),
+            pks.len()
+        )
+    }
+    Ok(())
+}
+
+/// Aggregates `sigs[i]` by `pks[i]`, all over the same `msg`, returning the aggregate signature
+/// and the aggregate public key it verifies under. Errors unless the aggregate verifies, so
+/// callers can persist or forward both without aggregating again.
+pub fn aggregate_uniform_bls_sigs(
+    pks: &[PublicKey],
+    msg: &[u8],
+    sigs: &[Signature],
+) -> Result<(Signature, PublicKey)> {
+    check_one_sig_per_pk(pks, sigs)?;
+    let agg_sig = aggregate_bls_sigs(sigs)?;
+    let agg_pk = aggregate_bls_pubkeys(pks)?;
+    if !bls_verify_with_dst(&agg_pk, &agg_sig, msg, CIPHER_SUITE.as_bytes()) {
+        bail!("Aggregate signature does not verify against the aggregate public key")
+    }
+    Ok((agg_sig, agg_pk))
+}
+
+/// Aggregates `sigs[i]` by `pks[i]` over its own `msgs[i]`, returning the aggregate signature.
+/// Errors unless the aggregate verifies, see `aggregate_verify_bls`.
+pub fn aggregate_distinct_bls_sigs(
+    pks: &[PublicKey],
+    msgs: &[&[u8]],
+    sigs: &[Signature],
+) -> Result<Signature> {
+    check_one_sig_per_pk(pks, sigs)?;
+    let agg_sig = aggregate_bls_sigs(sigs)?;
+    if !aggregate_verify_bls(pks, msgs, &agg_sig, false)?.valid {
+        bail!("Aggregate signature does not verify against its messages")
+    }
+    Ok(agg_sig)
+}
+
+/// Verifies `agg_sig` as the aggregate of every `pks[i]` signing the same `msg`. The public keys
+/// are aggregated first, so this costs 2 pairings regardless of `pks.len()`.
+pub fn fast_aggregate_verify_bls(
+    pks: &[PublicKey],
+    msg: &[u8],
+    agg_sig: &Signature,
+    profile: bool,
+) -> Result<VerifyOutcome> {
+    fast_aggregate_verify_bls_with_dst(pks, msg, agg_sig, CIPHER_SUITE.as_bytes(), profile)
+}
+
+/// Variant of `fast_aggregate_verify_bls` with `msg` hashed to G2 under `dst`, e.g. the
+/// `eth_types::network_dst` of the network it was signed for
+pub fn fast_aggregate_verify_bls_with_dst(
+    pks: &[PublicKey],
+    msg: &[u8],
+    agg_sig: &Signature,
+    dst: &[u8],
+    profile: bool,
+) -> Result<VerifyOutcome> {
+    let pks = to_blst_pks(pks)?;
+    let pk_refs: Vec<&blst::min_pk::PublicKey> = pks.iter().collect();
+    let sig = to_blst_sig(agg_sig)?;
+    Ok(VerifyOutcome::run(profile, 2, || {
+        sig.fast_aggregate_verify(true, msg, dst, &pk_refs)
+    }))
+}
+
+/// Verifies `agg_sig` as the aggregate of every `pks[i]` signing its own `msgs[i]`, costing
+/// `pks.len() + 1` pairings
+pub fn aggregate_verify_bls(
+    pks: &[PublicKey],
+    msgs: &[&[u8]],
+    agg_sig: &Signature,
+    profile: bool,
+) -> Result<VerifyOutcome> {
+    aggregate_verify_bls_with_scheme(pks, msgs, agg_sig, BlsScheme::ProofOfPossession, profile)
+}
+
+/// Variant of `aggregate_verify_bls` with the messages hashed to G2 under `dst`, e.g. the
+/// `eth_types::network_dst` of the network they were signed for
+pub fn aggregate_verify_bls_with_dst(
+    pks: &[PublicKey],
+    msgs: &[&[u8]],
+    agg_sig: &Signature,
+    dst: &[u8],
+    profile: bool,
+) -> Result<VerifyOutcome> {
+    if pks.len() != msgs.len() {
+        bail!(
+            "Expected one message per public key, got {} for {}",
+            msgs.len(),
+            pks.len()
+        )
+    }
+    blst_aggregate_verify(pks, msgs, agg_sig, dst, profile)
+}
+
+/// Variant of `aggregate_verify_bls` under `scheme`. The basic scheme rejects repeated messages,
+/// as its aggregates are only secure over distinct ones.
+pub fn aggregate_verify_bls_with_scheme(
+    pks: &[PublicKey],
+    msgs: &[&[u8]],
+    agg_sig: &Signature,
+    scheme: BlsScheme,
+    profile: bool,
+) -> Result<VerifyOutcome> {
+    if pks.len() != msgs.len() {
+        bail!(
+            "Expected one message per public key, got {} for {}",
+            msgs.len(),
+            pks.len()
+        )
+    }
+    if scheme == BlsScheme::Basic {
+        let distinct: std::collections::HashSet<&[u8]> = msgs.iter().copied().collect();
+        if distinct.len() != msgs.len() {
+            bail!("Basic scheme aggregates must be over distinct messages")
+        }
+    }
+    let augmented: Vec<Vec<u8>> = pks
+        .iter()
+        .zip(msgs.iter())
+        .map(|(pk, msg)| scheme.augment(pk, msg))
+        .collect();
+    let msgs: Vec<&[u8]> = augmented.iter().map(Vec::as_slice).collect();
+    blst_aggregate_verify(pks, &msgs, agg_sig, scheme.dst(), profile)
+}
+
+// Aggregate verification of `msgs` as given, costing `pks.len() + 1` pairings
+fn blst_aggregate_verify(
+    pks: &[PublicKey],
+    msgs: &[&[u8]],
+    agg_sig: &Signature,
+    dst: &[u8],
+    profile: bool,
+) -> Result<VerifyOutcome> {
+    let pks = to_blst_pks(pks)?;
+    let pk_refs: Vec<&blst::min_pk::PublicKey> = pks.iter().collect();
+    let sig = to_blst_sig(agg_sig)?;
+    Ok(VerifyOutcome::run(profile, pk_refs.len() + 1, || {
+        sig.aggregate_verify(true, msgs, dst, &pk_refs, true)
+    }))
+}
+
+/// Verifies each `sigs[i]` by `pks[i]` over `msgs[i]` in a single batch, combining them under
+/// random 64-bit scalars. Costs `pks.len() + 1` pairings rather than `2 * pks.len()`.
+pub fn batch_verify_bls(
+    pks: &[PublicKey],
+    msgs: &[&[u8]],
+    sigs: &[Signature],
+    profile: bool,
+) -> Result<VerifyOutcome> {
+    if pks.len() != msgs.len() || pks.len() != sigs.len() {
+        bail!(
+            "Mismatched batch lengths: {} pks, {} msgs, {} sigs",
+            pks.len(),
+            msgs.len(),
+            sigs.len()
+        )
+    }
+    let pks = to_blst_pks(pks)?;
+    let pk_refs: Vec<&blst::min_pk::PublicKey> = pks.iter().collect();
+    let sigs = sigs.iter().map(to_blst_sig).collect::<Result<Vec<_>>>()?;
+    let sig_refs: Vec<&blst::min_pk::Signature> = sigs.iter().collect();
+    let rands: Vec<blst::blst_scalar> = (0..sigs.len())
+        .map(|_| {
+            let mut b = [0_u8; 32];
+            b[..8].copy_from_slice(&rand::random::<u64>().max(1).to_le_bytes());
+            blst::blst_scalar { b }
+        })
+        .collect();
+    Ok(VerifyOutcome::run(profile, pk_refs.len() + 1, || {
+        blst::min_pk::Signature::verify_multiple_aggregate_signatures(
+            msgs,
+            CIPHER_SUITE.as_bytes(),
+            &pk_refs,
+            true,
+            &sig_refs,
+            true,
+            &rands,
+            64,
+        )
+    }))
+}
+
+/// Verifies the signatures `sigs[i]` by `pks[i]` over the same `msg`, returning the aggregate of
+/// the valid ones and the indices of the excluded invalid ones in ascending order. Ranges of
+/// signatures are checked as one fast aggregate verification and only split in half when they
+/// fail, so `k` invalid signatures among `n` cost O(k log n) verifications rather than `n`.
+/// Signatures that do not decode or are outside the G2 subgroup are excluded without verifying.
+pub fn verify_and_filter(
+    sigs: &[Signature],
+    pks: &[PublicKey],
+    msg: &[u8],
+) -> Result<(Signature, Vec<usize>)> {
+    if sigs.len() != pks.len() {
+        bail!(
+            "Expected one signature per public key, got {} for {}",
+            sigs.len(),
+            pks.len()
+        )
+    }
+    let blst_pks = to_blst_pks(pks)?;
+    let mut excluded = Vec::new();
+    let mut candidates = Vec::new();
+    for (i, sig) in sigs.iter().enumerate() {
+        match to_blst_sig(sig) {
+            Ok(sig) if sig.validate(true).is_ok() => candidates.push((i, sig)),
+            _ => excluded.push(i),
+        }
+    }
+    filter_invalid(&blst_pks, &candidates, msg, &mut excluded);
+    excluded.sort_unstable();
+
+    let valid: Vec<&blst::min_pk::Signature> = candidates
+        .iter()
+        .filter(|(i, _)| excluded.binary_search(i).is_err())
+        .map(|(_, sig)| sig)
+        .collect();
+    if valid.is_empty() {
+        bail!("None of the {} BLS signatures are valid", sigs.len())
+    }
+    let agg = blst::min_pk::AggregateSignature::aggregate(&valid, false)
+        .map_err(|e| anyhow!(BlstError(e)).context("Failed to aggregate BLS signatures"))?;
+    Ok((
+        bls_sig_from_bytes(&agg.to_signature().compress())?,
+        excluded,
+    ))
+}
+
+// Bisects `candidates` (index, subgroup-checked signature) pushing the index of every invalid
+// signature to `excluded`. Returns the number of verifications performed.
+fn filter_invalid(
+    pks: &[blst::min_pk::PublicKey],
+    candidates: &[(usize, blst::min_pk::Signature)],
+    msg: &[u8],
+    excluded: &mut Vec<usize>,
+) -> usize {
+    if candidates.is_empty() {
+        return 0;
+    }
+    let sig_refs: Vec<&blst::min_pk::Signature> = candidates.iter().map(|(_, sig)| sig).collect();
+    let pk_refs: Vec<&blst::min_pk::PublicKey> = candidates.iter().map(|(i, _)| &pks[*i]).collect();
+    let valid = blst::min_pk::AggregateSignature::aggregate(&sig_refs, false).is_ok_and(|agg| {
+        agg.to_signature()
+            .fast_aggregate_verify(false, msg, CIPHER_SUITE.as_bytes(), &pk_refs)
+            == blst::BLST_ERROR::BLST_SUCCESS
+    });
+    if valid {
+        return 1;
+    }
+    if candidates.len() == 1 {
+        excluded.push(candidates[0].0);
+        return 1;
+    }
+    let (left, right) = candidates.split_at(candidates.len() / 2);
+    1 + filter_invalid(pks, left, msg, excluded) + filter_invalid(pks, right, msg, excluded)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::io::key_management::{bls_key_exists, delete_bls_key};
+    use crate::io::key_management::delete_bls_key;
     #[test]
     fn test_new_bls_key() {
         // Test for different threshold values
@@ -165,6 +1285,170 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_new_bls_key_with_info() {
+        let ikm = [7_u8; 32];
+
+        // Deterministic
+        let sk_set_1 = new_bls_key_with_info(&ikm, b"").unwrap();
+        let sk_set_2 = new_bls_key_with_info(&ikm, b"").unwrap();
+        assert!(sk_set_1 == sk_set_2);
+        assert_eq!(sk_set_1.threshold(), 0);
+
+        // Different key_info gives a different key
+        let sk_set_3 = new_bls_key_with_info(&ikm, b"validator-1").unwrap();
+        assert!(sk_set_1 != sk_set_3);
+
+        // IKM must be at least 32 bytes
+        assert!(new_bls_key_with_info(&[7_u8; 31], b"").is_err());
+    }
+
+    #[test]
+    /// Test vec 0 from: https://eips.ethereum.org/EIPS/eip-2333#test-cases
+    fn test_hkdf_mod_r_eip2333_master_sk() {
+        let seed = hex::decode("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04").unwrap();
+        let sk = hkdf_mod_r(&seed, b"").unwrap();
+        assert_eq!(
+            BigUint::from_bytes_be(&sk).to_string(),
+            "6083874454709270928345386274498605044986640685124978867557563392430687146096"
+        );
+    }
+
+    #[test]
+    /// Test vec 0 from: https://eips.ethereum.org/EIPS/eip-2333#test-cases
+    fn test_derive_child_sk_eip2333() {
+        let seed = hex::decode("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04").unwrap();
+        let master_sk = derive_master_sk(&seed).unwrap();
+        let child_sk = derive_child_sk(&master_sk, 0).unwrap();
+        assert_eq!(
+            BigUint::from_bytes_be(&child_sk).to_string(),
+            "20397789859736650942317412262472558107875392172444076792671091975210932703118"
+        );
+    }
+
+    #[test]
+    fn test_parse_derivation_path() {
+        assert_eq!(
+            parse_derivation_path("m/12381/3600/0/0/0").unwrap(),
+            vec![12381, 3600, 0, 0, 0]
+        );
+        assert!(parse_derivation_path("m").unwrap().is_empty());
+        assert!(parse_derivation_path("12381/3600").is_err());
+        assert!(parse_derivation_path("m/12381/x").is_err());
+    }
+
+    #[test]
+    fn test_get_derivation_path() {
+        let seed = new_bls_key(0).secret_key().to_bytes();
+        let path = "m/12381/3600/0/0/0";
+        let pk = save_bls_key_from_seed(&seed, path).unwrap();
+        let pk_hex = pk.to_hex();
+
+        let exp_pk = derive_bls_key_at_path(&seed, path)
+            .unwrap()
+            .public_keys()
+            .public_key();
+        assert_eq!(pk, exp_pk);
+        assert_eq!(
+            get_derivation_path(&pk_hex).unwrap(),
+            Some(path.to_string())
+        );
+
+        // Keys without sidecar metadata have no known path
+        let sk_set = new_bls_key(0);
+        save_bls_key(&sk_set).unwrap();
+        let other_pk_hex = sk_set.public_keys().public_key().to_hex();
+        assert_eq!(get_derivation_path(&other_pk_hex).unwrap(), None);
+
+        // Deleting the key removes its metadata
+        delete_bls_key(&pk_hex).unwrap();
+        assert_eq!(get_derivation_path(&pk_hex).unwrap(), None);
+        delete_bls_key(&other_pk_hex).unwrap();
+    }
+
+    #[test]
+    /// Test vec 0 from: https://eips.ethereum.org/EIPS/eip-2333#test-cases
+    fn test_derive_bls_key_from_mnemonic() {
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let seed = mnemonic_to_seed(mnemonic, Some("TREZOR")).unwrap();
+        assert_eq!(hex::encode(seed), "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04");
+        let child_sk = derive_child_sk(&derive_master_sk(&seed).unwrap(), 0).unwrap();
+        assert_eq!(
+            BigUint::from_bytes_be(&child_sk).to_string(),
+            "20397789859736650942317412262472558107875392172444076792671091975210932703118"
+        );
+
+        let sk_set = derive_bls_key_from_mnemonic(mnemonic, Some("TREZOR"), 3).unwrap();
+        let exp_sk_set = derive_bls_key_at_path(&seed, "m/12381/3600/3/0/0").unwrap();
+        assert_eq!(sk_set.to_bytes(), exp_sk_set.to_bytes());
+        let without_passphrase = derive_bls_key_from_mnemonic(mnemonic, None, 3).unwrap();
+        assert_ne!(sk_set.to_bytes(), without_passphrase.to_bytes());
+
+        // The last word carries the checksum, and unknown words are rejected
+        let bad_checksum = mnemonic.replace("about", "abandon");
+        assert!(derive_bls_key_from_mnemonic(&bad_checksum, None, 0).is_err());
+        let unknown_word = mnemonic.replace("about", "aboot");
+        assert!(derive_bls_key_from_mnemonic(&unknown_word, None, 0).is_err());
+
+        let pk_hex = sk_set.public_keys().public_key().to_hex();
+        let _ = delete_bls_key(&pk_hex);
+        let pk = save_bls_key_from_mnemonic(mnemonic, Some("TREZOR"), 3).unwrap();
+        assert_eq!(pk.to_hex(), pk_hex);
+        assert_eq!(
+            get_derivation_path(&pk_hex).unwrap(),
+            Some("m/12381/3600/3/0/0".to_string())
+        );
+        assert!(save_bls_key_from_mnemonic(mnemonic, Some("TREZOR"), 3).is_err());
+        delete_bls_key(&pk_hex).unwrap();
+    }
+
+    #[test]
+    fn test_aggregate_pubkeys_hex() {
+        let sk_sets: Vec<SecretKeySet> = (0..4).map(|_| new_bls_key(0)).collect();
+        let pk_hexes: Vec<String> = sk_sets
+            .iter()
+            .map(|sk| format!("0x{}", sk.public_keys().public_key().to_hex()))
+            .collect();
+
+        // The aggregate pubkey is the pubkey of the summed secret keys
+        let r = BigUint::from_bytes_be(&BLS_CURVE_ORDER);
+        let sk_sum = sk_sets.iter().fold(BigUint::from(0_u8), |acc, sk| {
+            (acc + BigUint::from_bytes_be(&sk.secret_key().to_bytes())) % &r
+        });
+        let sk_sum_be = sk_sum.to_bytes_be();
+        let mut sk_sum_bytes = [0_u8; BLS_PRIV_KEY_BYTES];
+        sk_sum_bytes[BLS_PRIV_KEY_BYTES - sk_sum_be.len()..].copy_from_slice(&sk_sum_be);
+        let exp_pk = SecretKey::from_bytes(sk_sum_bytes).unwrap().public_key();
+        assert_eq!(aggregate_pubkeys_hex(&pk_hexes).unwrap(), exp_pk.to_hex());
+
+        // Proofs of possession over each compressed pubkey
+        let pops: Vec<String> = sk_sets
+            .iter()
+            .map(|sk| {
+                let blst_sk =
+                    blst::min_pk::SecretKey::from_bytes(&sk.secret_key().to_bytes()).unwrap();
+                let pk_bytes = sk.public_keys().public_key().to_bytes();
+                hex::encode(blst_sk.sign(&pk_bytes, BLS_POP_DST, &[]).compress())
+            })
+            .collect();
+        assert_eq!(
+            aggregate_pubkeys_hex_with_pop(&pk_hexes, &pops).unwrap(),
+            exp_pk.to_hex()
+        );
+
+        // A mismatched or missing proof of possession is rejected
+        let mut swapped = pops.clone();
+        swapped.swap(0, 1);
+        assert!(aggregate_pubkeys_hex_with_pop(&pk_hexes, &swapped).is_err());
+        assert!(aggregate_pubkeys_hex_with_pop(&pk_hexes, &pops[1..]).is_err());
+
+        // The identity point and empty input are rejected
+        let mut infinity = vec![0_u8; BLS_PUB_KEY_BYTES];
+        infinity[0] = 0xc0;
+        assert!(aggregate_pubkeys_hex(&[hex::encode(infinity)]).is_err());
+        assert!(aggregate_pubkeys_hex(&[]).is_err());
+    }
+
     #[test]
     fn test_save_and_fetch_bls_key() {
         let threshold = 3;
@@ -190,6 +1474,301 @@ mod tests {
         assert!(!bls_key_exists(&pk_hex));
     }
 
+    #[test]
+    fn test_verify_shares_consistency() {
+        let threshold = 2;
+        let sk_set = new_bls_key(threshold);
+        let group_pk = sk_set.public_keys().public_key();
+        let share = |i: u8| {
+            let sk_share = sk_set.secret_key_share(i as usize);
+            (i, SecretKey::from_bytes(sk_share.to_bytes()).unwrap())
+        };
+        let mut shares: Vec<(u8, SecretKey)> = [4, 0, 2, 1, 3].into_iter().map(share).collect();
+        assert!(verify_shares_consistency(&shares, &group_pk, threshold).is_ok());
+
+        // Not enough shares, or the wrong group key
+        assert!(verify_shares_consistency(&shares[..2], &group_pk, threshold).is_err());
+        let other_pk = SecretKey::random().public_key();
+        assert!(verify_shares_consistency(&shares, &other_pk, threshold).is_err());
+
+        // A tampered share outside the reconstruction subset is still caught
+        shares[4].1 = SecretKey::random();
+        assert!(verify_shares_consistency(&shares, &group_pk, threshold).is_err());
+        assert!(verify_shares_consistency(&shares[..4], &group_pk, threshold).is_ok());
+
+        // A tampered share inside the subset breaks reconstruction
+        shares[0].1 = SecretKey::random();
+        assert!(verify_shares_consistency(&shares[..3], &group_pk, threshold).is_err());
+        let err = import_bls_key_shares(&shares, &sk_set.public_keys()).unwrap_err();
+        assert!(format!("{:?}", err).contains("Rejected threshold share import"));
+    }
+
+    #[test]
+    fn test_affine_signature_round_trip() {
+        let sk = SecretKey::random();
+        let sig = sk.sign(b"affine");
+        let affine = bls_sig_to_affine_bytes(&sig).unwrap();
+        assert_eq!(affine.len(), 192);
+        assert_eq!(bls_sig_from_affine_bytes(&affine).unwrap(), sig);
+
+        // The compressed form is x with flag bits set, y is dropped
+        let compressed = sig.to_bytes();
+        assert_eq!(affine[1..96], compressed[1..]);
+        assert_eq!(affine[0], compressed[0] & 0x1f);
+
+        // Points off the curve are rejected
+        let mut bad = affine;
+        bad[191] ^= 1;
+        assert!(bls_sig_from_affine_bytes(&bad).is_err());
+    }
+
+    #[test]
+    fn test_aggregate_committee_pubkeys() {
+        let msg = b"attestation data root";
+        let sks: Vec<SecretKey> = (0..8).map(|_| SecretKey::random()).collect();
+        let pks: Vec<PublicKey> = sks.iter().map(|sk| sk.public_key()).collect();
+        let bits = [true, false, true, true, false, false, true, false];
+
+        let sigs: Vec<Signature> = sks
+            .iter()
+            .zip(bits.iter())
+            .filter(|(_, bit)| **bit)
+            .map(|(sk, _)| sk.sign(msg))
+            .collect();
+        let agg_sig = aggregate_bls_sigs(&sigs).unwrap();
+
+        let committee_pk = aggregate_committee_pubkeys(&pks, &bits).unwrap();
+        assert!(committee_pk.verify(&agg_sig, msg));
+
+        // Every member's key does not verify the participants' aggregate
+        let all_pk = aggregate_committee_pubkeys(&pks, &[true; 8]).unwrap();
+        assert!(!all_pk.verify(&agg_sig, msg));
+
+        assert!(aggregate_committee_pubkeys(&pks, &bits[..7]).is_err());
+        assert!(aggregate_committee_pubkeys(&pks, &[false; 8]).is_err());
+    }
+
+    #[test]
+    fn test_aggregate_uniform_and_distinct_sigs() {
+        let sks: Vec<SecretKey> = (0..4).map(|_| SecretKey::random()).collect();
+        let pks: Vec<PublicKey> = sks.iter().map(|sk| sk.public_key()).collect();
+
+        let msg = b"same msg";
+        let sigs: Vec<Signature> = sks.iter().map(|sk| sk.sign(msg)).collect();
+        let (agg_sig, agg_pk) = aggregate_uniform_bls_sigs(&pks, msg, &sigs).unwrap();
+        // The returned aggregates verify without the inputs that produced them
+        assert!(agg_pk.verify(&agg_sig, msg));
+        assert!(
+            fast_aggregate_verify_bls(&pks, msg, &agg_sig, false)
+                .unwrap()
+                .valid
+        );
+        assert_eq!(agg_sig, aggregate_bls_sigs(&sigs).unwrap());
+
+        let msgs: Vec<Vec<u8>> = (0..4u8).map(|i| vec![i; 32]).collect();
+        let msg_refs: Vec<&[u8]> = msgs.iter().map(Vec::as_slice).collect();
+        let sigs: Vec<Signature> = sks
+            .iter()
+            .zip(msgs.iter())
+            .map(|(sk, m)| sk.sign(m))
+            .collect();
+        let agg_sig = aggregate_distinct_bls_sigs(&pks, &msg_refs, &sigs).unwrap();
+        assert!(
+            aggregate_verify_bls(&pks, &msg_refs, &agg_sig, false)
+                .unwrap()
+                .valid
+        );
+
+        // A bad or missing signature fails rather than returning an unusable aggregate
+        let mut bad = sigs.clone();
+        bad[1] = sks[1].sign(b"other msg");
+        assert!(aggregate_distinct_bls_sigs(&pks, &msg_refs, &bad).is_err());
+        assert!(aggregate_distinct_bls_sigs(&pks, &msg_refs, &sigs[..3]).is_err());
+        assert!(aggregate_uniform_bls_sigs(&pks, &msgs[0], &sigs).is_err());
+    }
+
+    #[test]
+    fn test_infinity_pubkey_is_never_aggregated() {
+        let mut infinity_bytes = [0_u8; BLS_PUB_KEY_BYTES];
+        infinity_bytes[0] = 0xc0;
+        // Decoding without validation lets the identity through, as an untrusted import could
+        let infinity = PublicKey::from_bytes(infinity_bytes).unwrap();
+        let sk = SecretKey::random();
+        let msg = b"attestation data root";
+        let pks = vec![sk.public_key(), infinity];
+
+        let err = aggregate_committee_pubkeys(&pks, &[true, true]).unwrap_err();
+        assert!(format!("{:?}", err).contains("BLST_PK_IS_INFINITY"));
+        // Excluding the identity member aggregates the remaining key
+        assert_eq!(
+            aggregate_committee_pubkeys(&pks, &[true, false]).unwrap(),
+            sk.public_key()
+        );
+        assert!(fast_aggregate_verify_bls(&pks, msg, &sk.sign(msg), false).is_err());
+
+        let pk_hexes = vec![sk.public_key().to_hex(), hex::encode(infinity_bytes)];
+        assert!(aggregate_pubkeys_hex(&pk_hexes).is_err());
+    }
+
+    #[test]
+    fn test_verify_and_filter() {
+        let msg = b"gossiped aggregate";
+        let n = 128;
+        let bad_indices = [3, 17, 40, 41, 100];
+        let sks: Vec<SecretKey> = (0..n).map(|_| SecretKey::random()).collect();
+        let pks: Vec<PublicKey> = sks.iter().map(|sk| sk.public_key()).collect();
+        let sigs: Vec<Signature> = sks
+            .iter()
+            .enumerate()
+            .map(|(i, sk)| match bad_indices.contains(&i) {
+                true => sk.sign(b"some other message"),
+                false => sk.sign(msg),
+            })
+            .collect();
+
+        let (agg_sig, excluded) = verify_and_filter(&sigs, &pks, msg).unwrap();
+        assert_eq!(excluded, bad_indices.to_vec());
+        let valid_pks: Vec<PublicKey> = (0..n)
+            .filter(|i| !bad_indices.contains(i))
+            .map(|i| pks[i])
+            .collect();
+        assert!(
+            fast_aggregate_verify_bls(&valid_pks, msg, &agg_sig, false)
+                .unwrap()
+                .valid
+        );
+
+        // Far fewer verifications than checking each signature in turn
+        let blst_pks = to_blst_pks(&pks).unwrap();
+        let candidates: Vec<(usize, blst::min_pk::Signature)> = sigs
+            .iter()
+            .enumerate()
+            .map(|(i, sig)| (i, to_blst_sig(sig).unwrap()))
+            .collect();
+        let mut found = Vec::new();
+        let verifications = filter_invalid(&blst_pks, &candidates, msg, &mut found);
+        assert!(verifications < n / 2, "{verifications} verifications");
+
+        // All valid needs one verification, all invalid is an error
+        let good: Vec<Signature> = sks.iter().map(|sk| sk.sign(msg)).collect();
+        let (agg_sig, excluded) = verify_and_filter(&good, &pks, msg).unwrap();
+        assert!(excluded.is_empty());
+        assert_eq!(agg_sig, aggregate_bls_sigs(&good).unwrap());
+        assert!(verify_and_filter(&good, &pks, b"wrong message").is_err());
+        assert!(verify_and_filter(&good[1..], &pks, msg).is_err());
+    }
+
+    #[test]
+    fn test_verify_profile() {
+        let msg = b"profiled message";
+        let sks: Vec<SecretKey> = (0..8).map(|_| SecretKey::random()).collect();
+        let pks: Vec<PublicKey> = sks.iter().map(|sk| sk.public_key()).collect();
+        let sigs: Vec<Signature> = sks.iter().map(|sk| sk.sign(msg)).collect();
+        let agg_sig = aggregate_bls_sigs(&sigs).unwrap();
+
+        // No profile unless requested
+        let outcome = fast_aggregate_verify_bls(&pks, msg, &agg_sig, false).unwrap();
+        assert!(outcome.valid);
+        assert!(outcome.profile.is_none());
+
+        let profile = fast_aggregate_verify_bls(&pks, msg, &agg_sig, true)
+            .unwrap()
+            .profile
+            .unwrap();
+        assert_eq!(profile.pairings, 2);
+        assert!(profile.elapsed > std::time::Duration::ZERO);
+        assert!(profile.elapsed < std::time::Duration::from_secs(10));
+
+        let msgs: Vec<Vec<u8>> = (0..8_u8).map(|i| vec![i; 32]).collect();
+        let msg_refs: Vec<&[u8]> = msgs.iter().map(|m| m.as_slice()).collect();
+        let sigs: Vec<Signature> = sks.iter().zip(&msgs).map(|(sk, m)| sk.sign(m)).collect();
+        let agg_sig = aggregate_bls_sigs(&sigs).unwrap();
+        let outcome = aggregate_verify_bls(&pks, &msg_refs, &agg_sig, true).unwrap();
+        assert!(outcome.valid);
+        let profile = outcome.profile.unwrap();
+        assert_eq!(profile.pairings, 9);
+        assert!(profile.elapsed > std::time::Duration::ZERO);
+
+        let outcome = batch_verify_bls(&pks, &msg_refs, &sigs, true).unwrap();
+        assert!(outcome.valid);
+        let profile = outcome.profile.unwrap();
+        assert_eq!(profile.pairings, 9);
+        assert!(profile.elapsed > std::time::Duration::ZERO);
+
+        // A swapped signature fails, and is still profiled
+        let mut bad = sigs.clone();
+        bad.swap(0, 1);
+        let outcome = batch_verify_bls(&pks, &msg_refs, &bad, true).unwrap();
+        assert!(!outcome.valid);
+        assert!(outcome.profile.is_some());
+    }
+
+    #[test]
+    fn test_signing_throughput() {
+        // Tripwire for regressions in the saved-key signing path. The baseline is the same
+        // signatures made with the key in hand, measured here so the bound scales with the host.
+        // Saved keys are served from the key cache, so the overhead should stay well within 4x.
+        const N: u64 = 500;
+        let sk_set = new_bls_key(0);
+        let pk_hex = sk_set.public_keys().public_key().to_hex();
+        save_bls_key(&sk_set).unwrap();
+
+        let start = std::time::Instant::now();
+        for i in 0..N {
+            bls_agg_sign(&sk_set, &i.to_le_bytes());
+        }
+        let baseline = start.elapsed();
+
+        let start = std::time::Instant::now();
+        for i in 0..N {
+            bls_agg_sign_from_saved_sk(&pk_hex, &i.to_le_bytes()).unwrap();
+        }
+        let elapsed = start.elapsed();
+        delete_bls_key(&pk_hex).unwrap();
+        println!(
+            "{N} signatures: {:?} from a saved key, {:?} baseline",
+            elapsed, baseline
+        );
+        // The fixed allowance absorbs scheduling noise, which dominates on fast hosts
+        assert!(
+            elapsed < baseline * 4 + std::time::Duration::from_millis(500),
+            "{N} signatures took {:?} from a saved key against a {:?} baseline",
+            elapsed,
+            baseline
+        );
+    }
+
+    #[test]
+    fn test_preload_keys_skips_filesystem() {
+        let sk_sets: Vec<SecretKeySet> = (0..3).map(|_| new_bls_key(0)).collect();
+        let pks: Vec<String> = sk_sets
+            .iter()
+            .map(|sk_set| {
+                save_bls_key(sk_set).unwrap();
+                sk_set.public_keys().public_key().to_hex()
+            })
+            .collect();
+        assert_eq!(preload_keys(&pks).unwrap(), 3);
+
+        // With the key files gone, signing is served from the cache
+        let msg = b"preloaded";
+        for (pk_hex, sk_set) in pks.iter().zip(sk_sets.iter()) {
+            let path: std::path::PathBuf =
+                [crate::constants::BLS_KEYS_DIR, pk_hex].iter().collect();
+            std::fs::remove_file(path).unwrap();
+            let sig = bls_agg_sign_from_saved_sk(pk_hex, msg).unwrap();
+            assert_eq!(sig, bls_agg_sign(sk_set, msg));
+        }
+
+        // Evicted keys must be read from disk again
+        key_cache::evict_pk(&pks[0]);
+        assert!(bls_agg_sign_from_saved_sk(&pks[0], msg).is_err());
+        assert!(preload_keys(&pks).is_err());
+        for pk_hex in pks.iter() {
+            key_cache::evict_pk(pk_hex);
+        }
+    }
+
     #[test]
     fn test_save_and_fetch_bls_keystore() {
         let threshold = 3;
@@ -217,6 +1796,157 @@ mod tests {
         assert!(!bls_key_exists(&pk_hex));
     }
 
+    #[test]
+    fn test_import_bls_secret_hex() {
+        let sk = SecretKey::random();
+        let sk_hex = format!("0x{}", sk.to_hex());
+        let msg = b"Hello, world!";
+
+        let pk = import_bls_secret_hex(&sk_hex).unwrap();
+        assert_eq!(pk, sk.public_key());
+        assert!(bls_key_exists(&pk.to_hex()));
+
+        // Signing with the imported key matches signing with the original
+        let sig = bls_agg_sign_from_saved_sk(&pk.to_hex(), msg).unwrap();
+        assert_eq!(sig, sk.sign(msg));
+
+        // Refuse to overwrite the existing key
+        assert!(import_bls_secret_hex(&sk_hex).is_err());
+
+        delete_bls_key(&pk.to_hex()).unwrap();
+    }
+
+    #[test]
+    fn test_bls_schemes() {
+        let schemes = [
+            BlsScheme::Basic,
+            BlsScheme::MessageAugmentation,
+            BlsScheme::ProofOfPossession,
+        ];
+        let sk_set = new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        let msg = b"scheme msg";
+
+        // The default scheme is the consensus one
+        assert_eq!(
+            bls_sign_with_scheme(&sk_set, msg, BlsScheme::default()).unwrap(),
+            bls_agg_sign(&sk_set, msg)
+        );
+
+        for scheme in schemes {
+            let sig = bls_sign_with_scheme(&sk_set, msg, scheme).unwrap();
+            assert!(bls_verify_with_scheme(&pk, &sig, msg, scheme));
+            assert!(!bls_verify_with_scheme(&pk, &sig, b"other msg", scheme));
+            // A signature under one scheme never verifies under another
+            for other in schemes.iter().filter(|s| **s != scheme) {
+                assert!(!bls_verify_with_scheme(&pk, &sig, msg, *other));
+            }
+        }
+
+        // Augmentation binds the signer's key, so it differs from signing `msg` under its DST
+        let aug = BlsScheme::MessageAugmentation;
+        let sig = bls_sign_with_scheme(&sk_set, msg, aug).unwrap();
+        assert!(!bls_verify_with_dst(&pk, &sig, msg, aug.dst()));
+        assert!(bls_verify_with_dst(
+            &pk,
+            &sig,
+            &aug.augment(&pk, msg),
+            aug.dst()
+        ));
+    }
+
+    #[test]
+    fn test_aggregate_verify_with_scheme() {
+        let sk_sets: Vec<_> = (0..3).map(|_| new_bls_key(0)).collect();
+        let pks: Vec<PublicKey> = sk_sets
+            .iter()
+            .map(|s| s.public_keys().public_key())
+            .collect();
+        let distinct: Vec<Vec<u8>> = (0..3u8).map(|i| vec![i; 32]).collect();
+        let distinct: Vec<&[u8]> = distinct.iter().map(Vec::as_slice).collect();
+        let repeated: Vec<&[u8]> = vec![&b"same msg"[..]; 3];
+
+        let aggregate = |msgs: &[&[u8]], scheme: BlsScheme| {
+            let sigs: Vec<Signature> = sk_sets
+                .iter()
+                .zip(msgs.iter())
+                .map(|(s, m)| bls_sign_with_scheme(s, m, scheme).unwrap())
+                .collect();
+            aggregate_bls_sigs(&sigs).unwrap()
+        };
+        let verify = |msgs: &[&[u8]], sig: &Signature, scheme: BlsScheme| {
+            aggregate_verify_bls_with_scheme(&pks, msgs, sig, scheme, false).map(|o| o.valid)
+        };
+
+        for scheme in [
+            BlsScheme::Basic,
+            BlsScheme::MessageAugmentation,
+            BlsScheme::ProofOfPossession,
+        ] {
+            let sig = aggregate(&distinct, scheme);
+            assert!(verify(&distinct, &sig, scheme).unwrap());
+            for other in [BlsScheme::Basic, BlsScheme::MessageAugmentation] {
+                if other != scheme {
+                    assert!(!verify(&distinct, &sig, other).unwrap());
+                }
+            }
+        }
+        // The scheme-less variant is the consensus one
+        let sig = aggregate(&distinct, BlsScheme::default());
+        assert!(
+            aggregate_verify_bls(&pks, &distinct, &sig, false)
+                .unwrap()
+                .valid
+        );
+
+        // Repeated messages are refused under the basic scheme only
+        let sig = aggregate(&repeated, BlsScheme::Basic);
+        assert!(verify(&repeated, &sig, BlsScheme::Basic).is_err());
+        let sig = aggregate(&repeated, BlsScheme::MessageAugmentation);
+        assert!(verify(&repeated, &sig, BlsScheme::MessageAugmentation).unwrap());
+    }
+
+    #[test]
+    fn test_parse_max_message_bytes() {
+        assert_eq!(
+            parse_max_message_bytes(None).unwrap(),
+            DEFAULT_MAX_MESSAGE_BYTES
+        );
+        assert_eq!(
+            parse_max_message_bytes(Some("")).unwrap(),
+            DEFAULT_MAX_MESSAGE_BYTES
+        );
+        assert_eq!(parse_max_message_bytes(Some("4096")).unwrap(), 4096);
+        assert!(parse_max_message_bytes(Some("1MiB")).is_err());
+        assert!(parse_max_message_bytes(Some("-1")).is_err());
+    }
+
+    #[test]
+    fn test_oversized_message_is_rejected() {
+        let sk_set = new_bls_key(0);
+        save_bls_key(&sk_set).unwrap();
+        let pk_hex = sk_set.public_keys().public_key().to_hex();
+
+        let msg = vec![7_u8; max_message_bytes()];
+        let sig = bls_agg_sign_from_saved_sk(&pk_hex, &msg).unwrap();
+        assert_eq!(sig, bls_agg_sign(&sk_set, &msg));
+
+        let oversized = vec![7_u8; max_message_bytes() + 1];
+        let err = bls_agg_sign_from_saved_sk(&pk_hex, &oversized).unwrap_err();
+        assert!(err.to_string().contains("exceeds the maximum signable"));
+        assert!(bls_sign_with_dst(&sk_set, &oversized, b"OTHER_DST").is_err());
+
+        delete_bls_key(&pk_hex).unwrap();
+    }
+
+    #[test]
+    fn test_import_bls_secret_hex_rejects_bad_scalar() {
+        assert!(import_bls_secret_hex(&"00".repeat(BLS_PRIV_KEY_BYTES)).is_err());
+        assert!(import_bls_secret_hex(&"ff".repeat(BLS_PRIV_KEY_BYTES)).is_err());
+        assert!(import_bls_secret_hex("abcd").is_err());
+        assert!(import_bls_secret_hex(&"zz".repeat(BLS_PRIV_KEY_BYTES)).is_err());
+    }
+
     #[test]
     fn test_bls_agg_sign_from_saved_sk_success() {
         let threshold = 1;
@@ -284,6 +2014,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_save_bls_key_share_and_get_group_pubkey() {
+        let threshold = 2;
+        let sk_set = new_bls_key(threshold);
+        let pk_set = sk_set.public_keys();
+        let key_shares = distribute_key_shares(&sk_set, 3);
+
+        let (sk_share, pk_share) = &key_shares[1];
+        let group_id = save_bls_key_share(sk_share, &pk_set).unwrap();
+        assert_eq!(group_id, hex::encode(pk_share.to_bytes()));
+        assert!(bls_key_exists(&group_id));
+
+        // The stored group pubkey is the original aggregate pk
+        let group_pk = get_group_pubkey(&group_id).unwrap();
+        assert_eq!(group_pk, pk_set.public_key());
+        assert_eq!(get_group_pubkey_set(&group_id).unwrap(), pk_set);
+
+        delete_bls_key(&group_id).unwrap();
+    }
+
     #[test]
     fn test_distribute_key_shares_and_aggregate_signature_shares() {
         let threshold = 2;
@@ -308,6 +2058,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_distributed_key_shares_are_index_ordered() {
+        use rand::SeedableRng;
+        let threshold = 2;
+        let n = 5;
+        let seeded =
+            |seed| SecretKeySet::random(threshold, &mut rand::rngs::StdRng::seed_from_u64(seed));
+        let sk_set = seeded(7);
+        let pk_set = sk_set.public_keys();
+        let key_shares = distribute_key_shares(&sk_set, n);
+
+        // The same seed yields the same shares in the same order
+        let again = distribute_key_shares(&seeded(7), n);
+        for ((sk_share, pk_share), (sk_again, pk_again)) in key_shares.iter().zip(again.iter()) {
+            assert_eq!(sk_share.to_bytes(), sk_again.to_bytes());
+            assert_eq!(pk_share, pk_again);
+        }
+
+        // Element i is the share at index i of the group
+        for (i, (sk_share, pk_share)) in key_shares.iter().enumerate() {
+            assert_eq!(*pk_share, pk_set.public_key_share(i));
+            assert_eq!(sk_share.public_key_share(), *pk_share);
+        }
+
+        // Interpolating the shares at their position reproduces the group key, any other
+        // labelling does not
+        let indexed = |index: &dyn Fn(usize) -> usize| -> Vec<(u8, SecretKey)> {
+            key_shares
+                .iter()
+                .enumerate()
+                .map(|(i, (sk_share, _))| {
+                    (
+                        index(i) as u8,
+                        SecretKey::from_bytes(sk_share.to_bytes()).unwrap(),
+                    )
+                })
+                .collect()
+        };
+        let group_pk = pk_set.public_key();
+        assert!(verify_shares_consistency(&indexed(&|i| i), &group_pk, threshold).is_ok());
+        assert!(verify_shares_consistency(&indexed(&|i| n - 1 - i), &group_pk, threshold).is_err());
+
+        // Any threshold + 1 signature shares combine under their positions
+        let msg = b"ordered shares";
+        let sig_shares: BTreeMap<usize, SignatureShare> = [0, 2, 4]
+            .iter()
+            .map(|i| (*i, key_shares[*i].0.sign(msg)))
+            .collect();
+        let sig = pk_set.combine_signatures(sig_shares).unwrap();
+        assert_eq!(sig, sk_set.secret_key().sign(msg));
+    }
+
     #[test]
     #[should_panic]
     fn test_aggregate_signature_shares_not_enough_shares() {
@@ -346,4 +2148,97 @@ mod tests {
 
         aggregate_signature_shares(&pk_set, &sig_shares).unwrap();
     }
+
+    fn blst_err(e: anyhow::Error) -> blst::BLST_ERROR {
+        e.downcast_ref::<BlstError>()
+            .unwrap_or_else(|| panic!("expected a BlstError, got {:?}", e))
+            .0
+    }
+
+    #[test]
+    fn test_pubkey_decoding_errors() {
+        use blst::BLST_ERROR::*;
+
+        // Missing the compression flag / wrong length
+        assert_eq!(
+            blst_err(bls_pk_from_bytes(&[0_u8; 48]).unwrap_err()),
+            BLST_BAD_ENCODING
+        );
+        assert_eq!(
+            blst_err(bls_pk_from_bytes(&[0x80; 47]).unwrap_err()),
+            BLST_BAD_ENCODING
+        );
+
+        // x = 1 has no y on y^2 = x^3 + 4
+        let mut not_on_curve = [0_u8; 48];
+        not_on_curve[0] = 0x80;
+        not_on_curve[47] = 1;
+        assert_eq!(
+            blst_err(bls_pk_from_bytes(&not_on_curve).unwrap_err()),
+            BLST_POINT_NOT_ON_CURVE
+        );
+
+        // x = 4 is on the curve but outside the prime-order subgroup
+        let mut not_in_group = [0_u8; 48];
+        not_in_group[0] = 0x80;
+        not_in_group[47] = 4;
+        assert_eq!(
+            blst_err(bls_pk_from_bytes(&not_in_group).unwrap_err()),
+            BLST_POINT_NOT_IN_GROUP
+        );
+
+        let mut infinity = [0_u8; 48];
+        infinity[0] = 0xc0;
+        let err = bls_pk_from_hex(&format!("0x{}", hex::encode(infinity))).unwrap_err();
+        assert!(format!("{:#}", err).contains("identity"));
+        assert_eq!(blst_err(err), BLST_PK_IS_INFINITY);
+
+        let pk = new_bls_key(0).public_keys().public_key();
+        assert_eq!(bls_pk_from_hex(&pk.to_hex()).unwrap(), pk);
+    }
+
+    #[test]
+    fn test_signature_decoding_errors() {
+        use blst::BLST_ERROR::*;
+        assert_eq!(
+            blst_err(bls_sig_from_bytes(&[0_u8; 96]).unwrap_err()),
+            BLST_BAD_ENCODING
+        );
+
+        let mut infinity = [0_u8; 96];
+        infinity[0] = 0xc0;
+        assert_eq!(
+            blst_err(bls_sig_from_bytes(&infinity).unwrap_err()),
+            BLST_PK_IS_INFINITY
+        );
+
+        let sig = new_bls_key(0).secret_key().sign(b"msg");
+        assert_eq!(bls_sig_from_hex(&hex::encode(sig.to_bytes())).unwrap(), sig);
+    }
+
+    #[test]
+    fn test_saved_secret_key_decoding_errors() {
+        use blst::BLST_ERROR::*;
+        assert_eq!(
+            blst_err(bls_sk_set_from_bytes(vec![1_u8; 31]).unwrap_err()),
+            BLST_BAD_ENCODING
+        );
+        assert_eq!(
+            blst_err(bls_sk_set_from_bytes(vec![0_u8; 32]).unwrap_err()),
+            BLST_BAD_SCALAR
+        );
+        assert_eq!(
+            blst_err(bls_sk_set_from_bytes(BLS_CURVE_ORDER.to_vec()).unwrap_err()),
+            BLST_BAD_SCALAR
+        );
+
+        // A corrupted key file surfaces the scalar error rather than a generic failure
+        let pk_hex = new_bls_key(0).public_keys().public_key().to_hex();
+        write_bls_key(&pk_hex, &hex::encode(BLS_CURVE_ORDER)).unwrap();
+        assert_eq!(
+            blst_err(fetch_bls_sk(&pk_hex).unwrap_err()),
+            BLST_BAD_SCALAR
+        );
+        delete_bls_key(&pk_hex).unwrap();
+    }
 }
@@ -0,0 +1,210 @@
+use crate::crypto::bls_keys::{
+    aggregate_verify_bls_with_dst, bls_pk_from_hex, bls_sig_from_hex, bls_verify_with_dst,
+    fast_aggregate_verify_bls_with_dst,
+};
+use crate::crypto::verification_cache::verify_cached;
+use crate::strip_0x_prefix;
+
+use anyhow::{Context, Result};
+use blsttc::{PublicKey, Signature};
+
+/// Returns true if `sig` is a valid signature by `pk` over `msg` hashed to G2 under `dst`, the
+/// `eth_types::network_dst` of the network it was signed for
+pub fn verify(pk: &PublicKey, msg: &[u8], sig: &Signature, dst: &[u8]) -> bool {
+    bls_verify_with_dst(pk, sig, msg, dst)
+}
+
+/// Hex variant of `verify`, accepting optional `0x` prefixes. Errors if an input does not decode,
+/// and public keys and signatures must also be valid subgroup points.
+pub fn verify_hex(pk_hex: &str, msg_hex: &str, sig_hex: &str, dst: &[u8]) -> Result<bool> {
+    let pk = bls_pk_from_hex(pk_hex)?;
+    let sig = bls_sig_from_hex(sig_hex)?;
+    Ok(verify(&pk, &decode_msg(msg_hex)?, &sig, dst))
+}
+
+/// Returns true if `agg_sig` is the aggregate of every `pks[i]` signing the same `msg` under `dst`
+pub fn fast_aggregate_verify(
+    pks: &[PublicKey],
+    msg: &[u8],
+    agg_sig: &Signature,
+    dst: &[u8],
+) -> Result<bool> {
+    cached(true, pks, &[msg], agg_sig, dst, || {
+        Ok(fast_aggregate_verify_bls_with_dst(pks, msg, agg_sig, dst, false)?.valid)
+    })
+}
+
+/// Hex variant of `fast_aggregate_verify`
+pub fn fast_aggregate_verify_hex(
+    pk_hexes: &[String],
+    msg_hex: &str,
+    agg_sig_hex: &str,
+    dst: &[u8],
+) -> Result<bool> {
+    let pks = decode_pks(pk_hexes)?;
+    let agg_sig = bls_sig_from_hex(agg_sig_hex)?;
+    fast_aggregate_verify(&pks, &decode_msg(msg_hex)?, &agg_sig, dst)
+}
+
+/// Returns true if `agg_sig` is the aggregate of every `pks[i]` signing its own `msgs[i]` under
+/// `dst`
+pub fn aggregate_verify(
+    pks: &[PublicKey],
+    msgs: &[&[u8]],
+    agg_sig: &Signature,
+    dst: &[u8],
+) -> Result<bool> {
+    cached(false, pks, msgs, agg_sig, dst, || {
+        Ok(aggregate_verify_bls_with_dst(pks, msgs, agg_sig, dst, false)?.valid)
+    })
+}
+
+// Serves a repeated aggregate from the verification cache, keyed by the mode, every public key,
+// message and the DST, so re-broadcast aggregates only pay for the pairings once
+fn cached<F>(
+    uniform: bool,
+    pks: &[PublicKey],
+    msgs: &[&[u8]],
+    agg_sig: &Signature,
+    dst: &[u8],
+    verify: F,
+) -> Result<bool>
+where
+    F: FnOnce() -> Result<bool>,
+{
+    let Some(pk) = pks.first() else {
+        return verify();
+    };
+    let msg = aggregate_cache_msg(uniform, pks, msgs, dst);
+    verify_cached(pk, agg_sig, &msg, verify)
+}
+
+// Length prefixes keep distinct inputs from concatenating to the same bytes
+fn aggregate_cache_msg(uniform: bool, pks: &[PublicKey], msgs: &[&[u8]], dst: &[u8]) -> Vec<u8> {
+    let mut buf = vec![uniform as u8];
+    buf.extend_from_slice(&(dst.len() as u64).to_be_bytes());
+    buf.extend_from_slice(dst);
+    buf.extend_from_slice(&(pks.len() as u64).to_be_bytes());
+    for pk in pks {
+        buf.extend_from_slice(&pk.to_bytes());
+    }
+    for msg in msgs {
+        buf.extend_from_slice(&(msg.len() as u64).to_be_bytes());
+        buf.extend_from_slice(msg);
+    }
+    buf
+}
+
+/// Hex variant of `aggregate_verify`
+pub fn aggregate_verify_hex(
+    pk_hexes: &[String],
+    msg_hexes: &[String],
+    agg_sig_hex: &str,
+    dst: &[u8],
+) -> Result<bool> {
+    let pks = decode_pks(pk_hexes)?;
+    let msgs = msg_hexes
+        .iter()
+        .map(|m| decode_msg(m))
+        .collect::<Result<Vec<_>>>()?;
+    let msg_refs: Vec<&[u8]> = msgs.iter().map(Vec::as_slice).collect();
+    let agg_sig = bls_sig_from_hex(agg_sig_hex)?;
+    aggregate_verify(&pks, &msg_refs, &agg_sig, dst)
+}
+
+fn decode_pks(pk_hexes: &[String]) -> Result<Vec<PublicKey>> {
+    pk_hexes.iter().map(|pk| bls_pk_from_hex(pk)).collect()
+}
+
+fn decode_msg(msg_hex: &str) -> Result<Vec<u8>> {
+    let msg_hex: &str = strip_0x_prefix!(msg_hex);
+    hex::decode(msg_hex).with_context(|| "Unable to hex-decode message")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::CIPHER_SUITE;
+    use crate::crypto::bls_keys::{aggregate_bls_sigs, bls_agg_sign, new_bls_key};
+
+    const DST: &[u8] = CIPHER_SUITE.as_bytes();
+
+    #[test]
+    fn test_verify() {
+        let sk_set = new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        let sig = bls_agg_sign(&sk_set, b"msg");
+        assert!(verify(&pk, b"msg", &sig, DST));
+        assert!(!verify(&pk, b"other msg", &sig, DST));
+
+        let msg_hex = format!("0x{}", hex::encode(b"msg"));
+        let sig_hex = hex::encode(sig.to_bytes());
+        assert!(verify_hex(&pk.to_hex(), &msg_hex, &sig_hex, DST).unwrap());
+        let other_pk = new_bls_key(0).public_keys().public_key();
+        assert!(!verify_hex(&other_pk.to_hex(), &msg_hex, &sig_hex, DST).unwrap());
+        assert!(verify_hex(&pk.to_hex(), "0xzz", &sig_hex, DST).is_err());
+
+        // Signatures verify only under the DST they were signed under
+        let devnet_dst = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_DEVNET_";
+        let devnet_sig = crate::crypto::bls_keys::bls_sign_with_dst(&sk_set, b"msg", devnet_dst);
+        let devnet_sig = devnet_sig.unwrap();
+        assert!(verify(&pk, b"msg", &devnet_sig, devnet_dst));
+        assert!(!verify(&pk, b"msg", &devnet_sig, DST));
+        assert!(!verify(&pk, b"msg", &sig, devnet_dst));
+    }
+
+    #[test]
+    fn test_fast_aggregate_verify() {
+        let sk_sets: Vec<_> = (0..3).map(|_| new_bls_key(0)).collect();
+        let pks: Vec<PublicKey> = sk_sets
+            .iter()
+            .map(|s| s.public_keys().public_key())
+            .collect();
+        let sigs: Vec<Signature> = sk_sets.iter().map(|s| bls_agg_sign(s, b"msg")).collect();
+        let agg_sig = aggregate_bls_sigs(&sigs).unwrap();
+        assert!(fast_aggregate_verify(&pks, b"msg", &agg_sig, DST).unwrap());
+        assert!(!fast_aggregate_verify(&pks[..2], b"msg", &agg_sig, DST).unwrap());
+        assert!(fast_aggregate_verify(&[], b"msg", &agg_sig, DST).is_err());
+
+        let pk_hexes: Vec<String> = pks.iter().map(|pk| pk.to_hex()).collect();
+        let agg_sig_hex = hex::encode(agg_sig.to_bytes());
+        let msg_hex = hex::encode(b"msg");
+        assert!(fast_aggregate_verify_hex(&pk_hexes, &msg_hex, &agg_sig_hex, DST).unwrap());
+        assert!(!fast_aggregate_verify_hex(&pk_hexes, "00", &agg_sig_hex, DST).unwrap());
+
+        // The result was cached, so a repeat skips the pairings
+        let msg = aggregate_cache_msg(true, &pks, &[b"msg"], DST);
+        assert!(verify_cached(&pks[0], &agg_sig, &msg, || unreachable!()).unwrap());
+        // Under another DST or as a non-uniform aggregate it is verified afresh
+        let msg = aggregate_cache_msg(true, &pks, &[b"msg"], b"OTHER_DST_");
+        assert!(!verify_cached(&pks[0], &agg_sig, &msg, || Ok(false)).unwrap());
+        let msgs: [&[u8]; 1] = [b"msg"];
+        assert!(aggregate_verify(&pks, &msgs, &agg_sig, DST).is_err());
+    }
+
+    #[test]
+    fn test_aggregate_verify() {
+        let sk_sets: Vec<_> = (0..3).map(|_| new_bls_key(0)).collect();
+        let pks: Vec<PublicKey> = sk_sets
+            .iter()
+            .map(|s| s.public_keys().public_key())
+            .collect();
+        let msgs: Vec<Vec<u8>> = (0..3u8).map(|i| vec![i; 32]).collect();
+        let msg_refs: Vec<&[u8]> = msgs.iter().map(Vec::as_slice).collect();
+        let sigs: Vec<Signature> = sk_sets
+            .iter()
+            .zip(msgs.iter())
+            .map(|(s, m)| bls_agg_sign(s, m))
+            .collect();
+        let agg_sig = aggregate_bls_sigs(&sigs).unwrap();
+        assert!(aggregate_verify(&pks, &msg_refs, &agg_sig, DST).unwrap());
+        let swapped = [msg_refs[1], msg_refs[0], msg_refs[2]];
+        assert!(!aggregate_verify(&pks, &swapped, &agg_sig, DST).unwrap());
+        assert!(aggregate_verify(&pks, &msg_refs[..2], &agg_sig, DST).is_err());
+
+        let pk_hexes: Vec<String> = pks.iter().map(|pk| pk.to_hex()).collect();
+        let msg_hexes: Vec<String> = msgs.iter().map(hex::encode).collect();
+        let agg_sig_hex = hex::encode(agg_sig.to_bytes());
+        assert!(aggregate_verify_hex(&pk_hexes, &msg_hexes, &agg_sig_hex, DST).unwrap());
+    }
+}
@@ -2,11 +2,64 @@ use crate::constants::{ETH_COMPRESSED_PK_BYTES, ETH_SIGNATURE_BYTES, ETH_UNCOMPR
 use crate::io::key_management::{read_eth_key, write_eth_key};
 use crate::strip_0x_prefix;
 
-use anyhow::{bail, Context, Result};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, bail, Context, Result};
 use ecies::{utils::generate_keypair, PublicKey as EthPublicKey, SecretKey as EthSecretKey};
+use hkdf::Hkdf;
 use libsecp256k1::{Message, Signature};
+use rand::RngCore;
+use sha2::Sha256;
 use sha3::{Digest, Keccak256};
 
+const ECIES_COMPRESSED_NONCE_BYTES: usize = 12;
+
+/// The ECIES scheme used to envelope encrypt messages to a SECP256K1 public key
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EciesScheme {
+    /// The `ecies` crate format: uncompressed ephemeral pk || 16B nonce || tag || ciphertext
+    #[default]
+    Secp256k1Aes256Gcm,
+    /// Compact format: compressed ephemeral pk || 12B nonce || ciphertext || tag, where the
+    /// AES-256-GCM key is HKDF-SHA256(compressed ephemeral pk || compressed shared point)
+    Secp256k1CompressedAes256Gcm,
+}
+
+impl std::str::FromStr for EciesScheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "secp256k1-aes256gcm" => Ok(EciesScheme::Secp256k1Aes256Gcm),
+            "secp256k1-compressed-aes256gcm" => Ok(EciesScheme::Secp256k1CompressedAes256Gcm),
+            _ => bail!("Unknown ECIES scheme {s}"),
+        }
+    }
+}
+
+/// The scheme keys envelope encrypted to the enclave are decrypted with, both when provisioning
+/// and importing, read from `SECURE_SIGNER_ECIES_SCHEME`. Accepts `secp256k1-aes256gcm`, the
+/// default, or `secp256k1-compressed-aes256gcm`, and fails on any other value.
+pub fn ecies_scheme_from_env() -> Result<EciesScheme> {
+    parse_ecies_scheme(std::env::var("SECURE_SIGNER_ECIES_SCHEME").ok().as_deref())
+}
+
+fn parse_ecies_scheme(scheme: Option<&str>) -> Result<EciesScheme> {
+    match scheme {
+        Some(scheme) if !scheme.is_empty() => scheme
+            .parse()
+            .with_context(|| format!("Bad SECURE_SIGNER_ECIES_SCHEME {scheme}")),
+        _ => Ok(EciesScheme::default()),
+    }
+}
+
+/// The `ecies_scheme_from_env` scheme, read once. The binaries refuse to start with a malformed
+/// value, so the default only stands in for it outside of them.
+pub fn ecies_scheme() -> EciesScheme {
+    static SCHEME: std::sync::OnceLock<EciesScheme> = std::sync::OnceLock::new();
+    *SCHEME.get_or_init(|| ecies_scheme_from_env().unwrap_or_default())
+}
+
 /// Wrapper around ecies utility function to generate SECP256K1 keypair
 pub fn new_eth_key() -> Result<(EthSecretKey, EthPublicKey)> {
     Ok(generate_keypair())
@@ -78,6 +131,45 @@ pub fn eth_pk_from_hex_uncompressed(pk_hex: &String) -> Result<EthPublicKey> {
     }
 }
 
+/// Derives an ETH public key from a hex-string in either compressed 33B or uncompressed 65B form
+pub fn eth_pk_from_any_hex(pk_hex: &String) -> Result<EthPublicKey> {
+    let stripped: String = strip_0x_prefix!(pk_hex);
+    match stripped.len() / 2 {
+        ETH_COMPRESSED_PK_BYTES => eth_pk_from_hex(pk_hex),
+        ETH_UNCOMPRESSED_PK_BYTES => eth_pk_from_hex_uncompressed(pk_hex),
+        _ => bail!("ETH pk should be in compressed 33B or uncompressed 65B form"),
+    }
+}
+
+/// Returns the EIP-55 mixed-case checksum encoding of an ETH address. The address may be
+/// 0x-prefixed but must otherwise be exactly 40 hex characters.
+pub fn checksum(address: &str) -> Result<String> {
+    let trimmed = address.trim();
+    let address: &str = strip_0x_prefix!(trimmed);
+    if address.len() != 40 || !address.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("ETH address must be 40 hex characters, got '{trimmed}'")
+    }
+    let address = address.to_lowercase();
+    let hash = hex::encode(Keccak256::digest(address.as_bytes()));
+
+    let checksummed: String = address
+        .chars()
+        .zip(hash.chars())
+        .map(|(c, h)| match h.to_digit(16) {
+            Some(n) if n >= 8 => c.to_ascii_uppercase(),
+            _ => c,
+        })
+        .collect();
+    Ok(format!("0x{checksummed}"))
+}
+
+/// Derives the EIP-55 checksummed ETH address of a SECP256K1 public key: the last 20 bytes of
+/// keccak256 over the uncompressed key without its 0x04 prefix.
+pub fn pk_to_eth_addr(pk: &EthPublicKey) -> Result<String> {
+    let digest = Keccak256::digest(&pk.serialize()[1..]);
+    checksum(&hex::encode(&digest[12..]))
+}
+
 /// Derives an ETH public key from a hex-string, expects the hex string to be in compressed 33B form
 pub fn eth_sk_from_bytes(sk: Vec<u8>) -> Result<EthSecretKey> {
     EthSecretKey::parse_slice(&sk).with_context(|| "couldn't parse sk bytes to eth sk type")
@@ -141,21 +233,105 @@ pub fn verify_message(
 /// Use ECIES to encrypt the message using the provided public key. The encrypted message
 /// can only be decrypted by the owner of the corresponding private key.
 pub fn envelope_encrypt(public_key: &EthPublicKey, message: &[u8]) -> Result<Vec<u8>> {
-    // Encrypt the message using the public key
-    let encrypted_message = ecies::encrypt(&public_key.serialize(), message)
-        .with_context(|| "Failed to encrypt the message using the provided public key")?;
-
-    Ok(encrypted_message)
+    envelope_encrypt_with_scheme(EciesScheme::default(), public_key, message)
 }
 
 /// Use ECIES to decrypt the encrypted message using the provided secret key. This function
 /// will fail if the encrypted message was not encrypted using the corresponding public key.
 pub fn envelope_decrypt(secret_key: &EthSecretKey, encrypted_message: &[u8]) -> Result<Vec<u8>> {
-    // Decrypt the encrypted message using the secret key
-    let decrypted_message = ecies::decrypt(&secret_key.serialize(), encrypted_message)
-        .with_context(|| "Failed to decrypt the message using the provided secret key")?;
+    envelope_decrypt_with_scheme(EciesScheme::default(), secret_key, encrypted_message)
+}
 
-    Ok(decrypted_message)
+/// Same as `envelope_encrypt` but using the specified `EciesScheme`
+pub fn envelope_encrypt_with_scheme(
+    scheme: EciesScheme,
+    public_key: &EthPublicKey,
+    message: &[u8],
+) -> Result<Vec<u8>> {
+    match scheme {
+        EciesScheme::Secp256k1Aes256Gcm => {
+            // Encrypt the message using the public key
+            let encrypted_message = ecies::encrypt(&public_key.serialize(), message)
+                .with_context(|| "Failed to encrypt the message using the provided public key")?;
+            Ok(encrypted_message)
+        }
+        EciesScheme::Secp256k1CompressedAes256Gcm => {
+            let (ephemeral_sk, ephemeral_pk) = generate_keypair();
+            let mut shared_point = public_key.clone();
+            shared_point
+                .tweak_mul_assign(&ephemeral_sk)
+                .map_err(|e| anyhow!("Failed to derive ECDH shared point: {:?}", e))?;
+            let cipher = compressed_ecies_cipher(&ephemeral_pk, &shared_point)?;
+
+            let mut nonce = [0_u8; ECIES_COMPRESSED_NONCE_BYTES];
+            rand::thread_rng().fill_bytes(&mut nonce);
+            let ciphertext = cipher
+                .encrypt(Nonce::from_slice(&nonce), message)
+                .map_err(|e| anyhow!("Failed to encrypt the message: {:?}", e))?;
+
+            let mut encrypted_message = Vec::with_capacity(
+                ETH_COMPRESSED_PK_BYTES + ECIES_COMPRESSED_NONCE_BYTES + ciphertext.len(),
+            );
+            encrypted_message.extend_from_slice(&ephemeral_pk.serialize_compressed());
+            encrypted_message.extend_from_slice(&nonce);
+            encrypted_message.extend_from_slice(&ciphertext);
+            Ok(encrypted_message)
+        }
+    }
+}
+
+/// Same as `envelope_decrypt` but using the specified `EciesScheme`
+pub fn envelope_decrypt_with_scheme(
+    scheme: EciesScheme,
+    secret_key: &EthSecretKey,
+    encrypted_message: &[u8],
+) -> Result<Vec<u8>> {
+    match scheme {
+        EciesScheme::Secp256k1Aes256Gcm => {
+            // Decrypt the encrypted message using the secret key
+            let decrypted_message = ecies::decrypt(&secret_key.serialize(), encrypted_message)
+                .with_context(|| "Failed to decrypt the message using the provided secret key")?;
+            Ok(decrypted_message)
+        }
+        EciesScheme::Secp256k1CompressedAes256Gcm => {
+            if encrypted_message.len() < ETH_COMPRESSED_PK_BYTES + ECIES_COMPRESSED_NONCE_BYTES {
+                bail!("Encrypted message is too short")
+            }
+            let (pk_bytes, rest) = encrypted_message.split_at(ETH_COMPRESSED_PK_BYTES);
+            let (nonce, ciphertext) = rest.split_at(ECIES_COMPRESSED_NONCE_BYTES);
+
+            let mut pk_compressed_bytes = [0_u8; ETH_COMPRESSED_PK_BYTES];
+            pk_compressed_bytes.copy_from_slice(pk_bytes);
+            let ephemeral_pk = EthPublicKey::parse_compressed(&pk_compressed_bytes)
+                .map_err(|e| anyhow!("Bad ephemeral public key: {:?}", e))?;
+
+            let mut shared_point = ephemeral_pk.clone();
+            shared_point
+                .tweak_mul_assign(secret_key)
+                .map_err(|e| anyhow!("Failed to derive ECDH shared point: {:?}", e))?;
+            let cipher = compressed_ecies_cipher(&ephemeral_pk, &shared_point)?;
+
+            cipher
+                .decrypt(Nonce::from_slice(nonce), ciphertext)
+                .map_err(|_| anyhow!("Failed to decrypt the message using the provided secret key"))
+        }
+    }
+}
+
+// Derives the AES-256-GCM cipher for `EciesScheme::Secp256k1CompressedAes256Gcm`
+fn compressed_ecies_cipher(
+    ephemeral_pk: &EthPublicKey,
+    shared_point: &EthPublicKey,
+) -> Result<Aes256Gcm> {
+    let mut ikm = Vec::with_capacity(2 * ETH_COMPRESSED_PK_BYTES);
+    ikm.extend_from_slice(&ephemeral_pk.serialize_compressed());
+    ikm.extend_from_slice(&shared_point.serialize_compressed());
+
+    let mut key = [0_u8; 32];
+    Hkdf::<Sha256>::new(None, &ikm)
+        .expand(&[], &mut key)
+        .map_err(|e| anyhow!("Failed to derive ECIES key: {:?}", e))?;
+    Aes256Gcm::new_from_slice(&key).map_err(|e| anyhow!("Bad ECIES key length: {:?}", e))
 }
 
 /// Wrapper over `envelope_decrypt` that fetches the secret key corresponding to the
@@ -164,12 +340,25 @@ pub fn envelope_decrypt(secret_key: &EthSecretKey, encrypted_message: &[u8]) ->
 pub fn envelope_decrypt_from_saved_sk(
     eth_pk_hex: &String,
     encrypted_message: &[u8],
+) -> Result<Vec<u8>> {
+    envelope_decrypt_from_saved_sk_with_scheme(
+        EciesScheme::default(),
+        eth_pk_hex,
+        encrypted_message,
+    )
+}
+
+/// Same as `envelope_decrypt_from_saved_sk` but using the specified `EciesScheme`
+pub fn envelope_decrypt_from_saved_sk_with_scheme(
+    scheme: EciesScheme,
+    eth_pk_hex: &String,
+    encrypted_message: &[u8],
 ) -> Result<Vec<u8>> {
     // Fetch the secret key from file
     let secret_key = fetch_eth_key(&eth_pk_hex)?;
 
-    // Decrypt the encrypted message using the fetched secret key by calling the generic envelope_decrypt function
-    let decrypted_message = envelope_decrypt(&secret_key, &encrypted_message)?;
+    // Decrypt the encrypted message using the fetched secret key
+    let decrypted_message = envelope_decrypt_with_scheme(scheme, &secret_key, &encrypted_message)?;
 
     Ok(decrypted_message)
 }
@@ -178,6 +367,33 @@ pub fn envelope_decrypt_from_saved_sk(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_checksum() {
+        // https://eips.ethereum.org/EIPS/eip-55#test-cases
+        let addr = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        assert_eq!(checksum(addr).unwrap(), addr);
+        assert_eq!(checksum(&addr[2..].to_lowercase()).unwrap(), addr);
+        assert_eq!(checksum(&format!(" {addr} ")).unwrap(), addr);
+
+        // Too short, too long and non-hex
+        assert!(checksum(&addr[..41]).is_err());
+        assert!(checksum(&format!("{addr}00")).is_err());
+        assert!(checksum("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeg").is_err());
+        assert!(checksum("").is_err());
+    }
+
+    #[test]
+    fn test_pk_to_eth_addr() {
+        let mut sk_bytes = vec![0_u8; 32];
+        sk_bytes[31] = 1;
+        let sk = eth_sk_from_bytes(sk_bytes).unwrap();
+        let pk = EthPublicKey::from_secret_key(&sk);
+        assert_eq!(
+            pk_to_eth_addr(&pk).unwrap(),
+            "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+        );
+    }
+
     #[test]
     fn test_sign_message() {
         // Generate a new SECP256K1 keypair (ETH keypair)
@@ -297,6 +513,59 @@ mod tests {
         assert_eq!(message.to_vec(), decrypted_message);
     }
 
+    #[test]
+    fn test_envelope_encrypt_and_decrypt_each_scheme() {
+        let (secret_key, public_key) = new_eth_key().unwrap();
+        let (other_secret_key, _) = new_eth_key().unwrap();
+        let message = b"Test message for encryption.";
+
+        for scheme in [
+            EciesScheme::Secp256k1Aes256Gcm,
+            EciesScheme::Secp256k1CompressedAes256Gcm,
+        ] {
+            let encrypted_message =
+                envelope_encrypt_with_scheme(scheme, &public_key, &message[..]).unwrap();
+            let decrypted_message =
+                envelope_decrypt_with_scheme(scheme, &secret_key, &encrypted_message).unwrap();
+            assert_eq!(message.to_vec(), decrypted_message);
+
+            // The wrong secret key can not decrypt
+            assert!(
+                envelope_decrypt_with_scheme(scheme, &other_secret_key, &encrypted_message)
+                    .is_err()
+            );
+        }
+
+        // The default scheme is the original ecies crate format
+        assert_eq!(EciesScheme::default(), EciesScheme::Secp256k1Aes256Gcm);
+        let encrypted_message = envelope_encrypt(&public_key, &message[..]).unwrap();
+        let decrypted_message = envelope_decrypt_with_scheme(
+            EciesScheme::Secp256k1Aes256Gcm,
+            &secret_key,
+            &encrypted_message,
+        )
+        .unwrap();
+        assert_eq!(message.to_vec(), decrypted_message);
+    }
+
+    #[test]
+    fn test_parse_ecies_scheme() {
+        assert_eq!(parse_ecies_scheme(None).unwrap(), EciesScheme::default());
+        assert_eq!(
+            parse_ecies_scheme(Some("")).unwrap(),
+            EciesScheme::default()
+        );
+        assert_eq!(
+            parse_ecies_scheme(Some("secp256k1-aes256gcm")).unwrap(),
+            EciesScheme::Secp256k1Aes256Gcm
+        );
+        assert_eq!(
+            parse_ecies_scheme(Some(" SECP256K1-COMPRESSED-AES256GCM ")).unwrap(),
+            EciesScheme::Secp256k1CompressedAes256Gcm
+        );
+        assert!(parse_ecies_scheme(Some("chacha20poly1305")).is_err());
+    }
+
     #[test]
     fn test_envelope_decrypt_from_saved_sk() {
         // Generate a new SECP256K1 keypair (ETH keypair) and save the secret key to a file
@@ -318,3 +587,84 @@ mod tests {
         assert_eq!(message.to_vec(), decrypted_message);
     }
 }
+
+#[cfg(test)]
+mod test_address_vectors {
+    use super::*;
+
+    // https://eips.ethereum.org/EIPS/eip-55#test-cases
+    const EIP55_ADDRESSES: [&str; 8] = [
+        // All caps
+        "0x52908400098527886E0F7030069857D2E4169EE7",
+        "0x8617E340B3D01FA5F11F306F4090FD50E238070D",
+        // All lower
+        "0xde709f2102306220921060314715629080e2fb77",
+        "0x27b1fdb04752bbc536007a920d24acb045561c26",
+        // Normal
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    // (secret key, compressed public key, address). The first is go-ethereum's crypto test key,
+    // the next two the default dev chain accounts 0 and 1, then the secret keys 1, 2 and 3.
+    const KEY_VECTORS: [(&str, &str, &str); 6] = [
+        (
+            "289c2857d4598e37fb9647507e47a309d6133539bf21a8b9cb6df88fd5232032",
+            "037db227d7094ce215c3a0f57e1bcc732551fe351f94249471934567e0f5dc1bf7",
+            "0x970E8128AB834E8EAC17Ab8E3812F010678CF791",
+        ),
+        (
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            "038318535b54105d4a7aae60c08fc45f9687181b4fdfc625bd1a753fa7397fed75",
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+        ),
+        (
+            "59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d",
+            "02ba5734d8f7091719471e7f7ed6b9df170dc70cc661ca05e688601ad984f068b0",
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+            "0x2B5AD5c4795c026514f8317c7a215E218DcCD6cF",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+            "0x6813Eb9362372EEF6200f3b1dbC3f819671cBA69",
+        ),
+    ];
+
+    #[test]
+    fn test_eip55_checksum_vectors() {
+        for addr in EIP55_ADDRESSES {
+            assert_eq!(checksum(addr).unwrap(), addr);
+            assert_eq!(checksum(&addr.to_lowercase()).unwrap(), addr);
+            assert_eq!(checksum(&addr[2..].to_uppercase()).unwrap(), addr);
+        }
+    }
+
+    #[test]
+    fn test_pk_to_eth_addr_vectors() {
+        for (sk_hex, pk_hex, addr) in KEY_VECTORS {
+            let sk = eth_sk_from_bytes(hex::decode(sk_hex).unwrap()).unwrap();
+            let pk = EthPublicKey::from_secret_key(&sk);
+            assert_eq!(eth_pk_to_hex(&pk), pk_hex);
+            assert_eq!(pk_to_eth_addr(&pk).unwrap(), addr);
+
+            // Same address from the compressed and uncompressed encodings
+            let pk = eth_pk_from_hex(&pk_hex.to_string()).unwrap();
+            assert_eq!(pk_to_eth_addr(&pk).unwrap(), addr);
+            let uncompressed = eth_pk_to_hex_uncompressed(&pk);
+            let pk = eth_pk_from_any_hex(&uncompressed).unwrap();
+            assert_eq!(pk_to_eth_addr(&pk).unwrap(), addr);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use crate::constants::BLS_KEY_CACHE_CAPACITY;
+use crate::strip_0x_prefix;
+
+use blsttc::SecretKeySet;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock, RwLock};
+
+/// Bounded in-memory cache of saved BLS secret keys keyed by pk_hex, so signing with a known key
+/// skips reading its file. The oldest entry is evicted once `capacity` is reached. Keys enter the
+/// cache only once their key file passed its MAC check, which is not repeated on hits.
+pub struct SecretKeyCache {
+    capacity: usize,
+    entries: HashMap<String, SecretKeySet>,
+    order: VecDeque<String>,
+}
+
+impl SecretKeyCache {
+    pub fn new(capacity: usize) -> Self {
+        SecretKeyCache {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, pk_hex: &str) -> Option<SecretKeySet> {
+        let pk_hex: &str = strip_0x_prefix!(pk_hex);
+        self.entries.get(pk_hex).cloned()
+    }
+
+    pub fn insert(&mut self, pk_hex: &str, sk_set: SecretKeySet) {
+        if self.capacity == 0 {
+            return;
+        }
+        let pk_hex: String = strip_0x_prefix!(pk_hex);
+        if self.entries.insert(pk_hex.clone(), sk_set).is_some() {
+            return;
+        }
+        self.order.push_back(pk_hex);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn evict_pk(&mut self, pk_hex: &str) {
+        let pk_hex: &str = strip_0x_prefix!(pk_hex);
+        self.entries.remove(pk_hex);
+        self.order.retain(|pk| pk != pk_hex);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+/// Returns the process-wide secret key cache
+pub fn global_key_cache() -> &'static Mutex<SecretKeyCache> {
+    static CACHE: OnceLock<Mutex<SecretKeyCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(SecretKeyCache::new(BLS_KEY_CACHE_CAPACITY)))
+}
+
+// Held shared while a key file is read into the cache and exclusively while a key file is deleted,
+// so a fill that read a key before its file was deleted cannot insert it after the eviction
+fn fill_lock() -> &'static RwLock<()> {
+    static LOCK: RwLock<()> = RwLock::new(());
+    &LOCK
+}
+
+/// Returns the cached secret key for `pk_hex`, or reads it with `fill` and caches it
+pub fn get_or_fill<F>(pk_hex: &str, fill: F) -> anyhow::Result<SecretKeySet>
+where
+    F: FnOnce() -> anyhow::Result<SecretKeySet>,
+{
+    if let Some(sk_set) = get(pk_hex) {
+        return Ok(sk_set);
+    }
+    let _fill = fill_lock().read().unwrap_or_else(|e| e.into_inner());
+    let sk_set = fill()?;
+    insert(pk_hex, &sk_set);
+    Ok(sk_set)
+}
+
+/// Runs `delete` to remove the key file of `pk_hex` and then evicts the key, with no cache fill
+/// running in between. The key is evicted even if `delete` fails part way.
+pub fn delete_and_evict<T, F>(pk_hex: &str, delete: F) -> anyhow::Result<T>
+where
+    F: FnOnce() -> anyhow::Result<T>,
+{
+    let _fills = fill_lock().write().unwrap_or_else(|e| e.into_inner());
+    let deleted = delete();
+    evict_pk(pk_hex);
+    deleted
+}
+
+/// Returns the cached secret key for `pk_hex`, treating a poisoned lock as a miss
+pub fn get(pk_hex: &str) -> Option<SecretKeySet> {
+    global_key_cache().lock().ok()?.get(pk_hex)
+}
+
+/// Caches the secret key for `pk_hex` in the process-wide cache
+pub fn insert(pk_hex: &str, sk_set: &SecretKeySet) {
+    if let Ok(mut cache) = global_key_cache().lock() {
+        cache.insert(pk_hex, sk_set.clone());
+    }
+}
+
+/// Evicts the secret key for `pk_hex` from the process-wide cache
+pub fn evict_pk(pk_hex: &str) {
+    if let Ok(mut cache) = global_key_cache().lock() {
+        cache.evict_pk(pk_hex);
+    }
+}
+
+/// Drops every cached secret key from the process-wide cache
+pub fn clear() {
+    if let Ok(mut cache) = global_key_cache().lock() {
+        cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_cache_is_bounded() {
+        let mut cache = SecretKeyCache::new(2);
+        let sk_sets: Vec<SecretKeySet> = (0..3)
+            .map(|_| crate::crypto::bls_keys::new_bls_key(0))
+            .collect();
+        for (i, sk_set) in sk_sets.iter().enumerate() {
+            cache.insert(&format!("0x{i}"), sk_set.clone());
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("0").is_none());
+        assert!(cache.get("2") == Some(sk_sets[2].clone()));
+
+        cache.evict_pk("0x2");
+        assert!(cache.get("2").is_none());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_fill_cannot_outlive_delete() {
+        let pk_hex = "0xcace0430";
+        let sk_set = crate::crypto::bls_keys::new_bls_key(0);
+        let (read_tx, read_rx) = std::sync::mpsc::channel();
+        let (delete_tx, delete_rx) = std::sync::mpsc::channel::<()>();
+
+        // A fill that read the key file before the delete started inserts before it is evicted
+        let filler = {
+            let sk_set = sk_set.clone();
+            std::thread::spawn(move || {
+                get_or_fill(pk_hex, || {
+                    read_tx.send(()).unwrap();
+                    // Give the delete time to queue behind this fill
+                    let _ = delete_rx.recv_timeout(std::time::Duration::from_millis(100));
+                    Ok(sk_set)
+                })
+                .unwrap();
+            })
+        };
+        read_rx.recv().unwrap();
+        delete_and_evict(pk_hex, || {
+            delete_tx.send(()).ok();
+            Ok(())
+        })
+        .unwrap();
+        filler.join().unwrap();
+        assert!(get(pk_hex).is_none());
+
+        // The key is evicted even if deleting failed
+        insert(pk_hex, &sk_set);
+        assert!(
+            delete_and_evict(pk_hex, || -> anyhow::Result<()> { anyhow::bail!("EIO") }).is_err()
+        );
+        assert!(get(pk_hex).is_none());
+    }
+}
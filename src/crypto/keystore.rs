@@ -1,9 +1,72 @@
 use crate::strip_0x_prefix;
 
 use super::eth_keys;
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use ecies::SecretKey as EthSecretKey;
 use eth_keystore::decrypt_keystore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::sync::RwLock;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroize;
+
+/// Password-strength policy enforced when creating or exporting keystores. Importing an
+/// existing keystore is never subject to the policy. Disabled by default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    /// Minimum number of characters, `None` disables the check
+    pub min_length: Option<usize>,
+}
+
+impl PasswordPolicy {
+    /// Reads the policy from `SECURE_SIGNER_MIN_KEYSTORE_PASSWORD_LENGTH`, disabled if unset.
+    /// Fails on a malformed value rather than silently disabling the policy.
+    pub fn from_env() -> Result<Self> {
+        parse_password_policy(
+            std::env::var("SECURE_SIGNER_MIN_KEYSTORE_PASSWORD_LENGTH")
+                .ok()
+                .as_deref(),
+        )
+    }
+
+    pub fn check(&self, password: &str) -> Result<()> {
+        if let Some(min_length) = self.min_length {
+            if password.chars().count() < min_length {
+                bail!("Keystore password must be at least {min_length} characters")
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_password_policy(min_length: Option<&str>) -> Result<PasswordPolicy> {
+    match min_length {
+        Some(min_length) if !min_length.is_empty() => Ok(PasswordPolicy {
+            min_length: Some(min_length.parse().with_context(|| {
+                format!("Bad SECURE_SIGNER_MIN_KEYSTORE_PASSWORD_LENGTH {min_length}")
+            })?),
+        }),
+        _ => Ok(PasswordPolicy::default()),
+    }
+}
+
+static PASSWORD_POLICY: RwLock<PasswordPolicy> = RwLock::new(PasswordPolicy { min_length: None });
+
+/// Sets the process-wide password policy used for keystore creation and export
+pub fn set_password_policy(policy: PasswordPolicy) {
+    match PASSWORD_POLICY.write() {
+        Ok(mut p) => *p = policy,
+        Err(e) => *e.into_inner() = policy,
+    }
+}
+
+/// Returns the process-wide password policy
+pub fn password_policy() -> PasswordPolicy {
+    match PASSWORD_POLICY.read() {
+        Ok(p) => p.clone(),
+        Err(e) => e.into_inner().clone(),
+    }
+}
 
 pub fn import_keystore(
     keystore: &String,
@@ -13,22 +76,390 @@ pub fn import_keystore(
     // Decrypt the password
     let ct_password_hex: String = strip_0x_prefix!(ct_password_hex);
     let ct_password_bytes = hex::decode(ct_password_hex)?;
-    let password_bytes = eth_keys::envelope_decrypt(envelope_sk, &ct_password_bytes)?;
+    let password_bytes = eth_keys::envelope_decrypt_with_scheme(
+        eth_keys::ecies_scheme(),
+        envelope_sk,
+        &ct_password_bytes,
+    )?;
     let password = String::from_utf8(password_bytes).with_context(|| "non-utf8 password")?;
     decrypt_keystore(keystore, password).with_context(|| "Failed to decrypt keystore")
 }
 
+/// A module of an EIP-2335 keystore's `crypto` section
+#[derive(Debug, Deserialize)]
+struct KeystoreModule {
+    function: String,
+    #[serde(default)]
+    params: serde_json::Value,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeystoreCrypto {
+    kdf: KeystoreModule,
+    checksum: KeystoreModule,
+    cipher: KeystoreModule,
+}
+
+#[derive(Debug, Deserialize)]
+struct Eip2335Keystore {
+    crypto: KeystoreCrypto,
+    #[serde(default)]
+    pubkey: String,
+}
+
+/// The password does not open the keystore, as opposed to the keystore being malformed
+#[derive(Debug)]
+pub struct WrongKeystorePassword;
+
+impl std::fmt::Display for WrongKeystorePassword {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Wrong keystore password, the checksum does not match")
+    }
+}
+
+impl std::error::Error for WrongKeystorePassword {}
+
+impl WrongKeystorePassword {
+    /// Returns true if `e` was caused by a wrong keystore password
+    pub fn is_cause_of(e: &anyhow::Error) -> bool {
+        e.chain().any(|c| c.is::<WrongKeystorePassword>())
+    }
+}
+
+// EIP-2335 passwords are NFKD normalized with control codes stripped before use
+fn process_password(password: &str) -> Vec<u8> {
+    password
+        .nfkd()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .into_bytes()
+}
+
+fn kdf_param<'a>(kdf: &'a KeystoreModule, name: &str) -> Result<&'a serde_json::Value> {
+    kdf.params
+        .get(name)
+        .ok_or_else(|| anyhow!("Keystore {} params missing {name}", kdf.function))
+}
+
+fn kdf_u32(kdf: &KeystoreModule, name: &str) -> Result<u32> {
+    kdf_param(kdf, name)?
+        .as_u64()
+        .and_then(|v| u32::try_from(v).ok())
+        .ok_or_else(|| anyhow!("Bad keystore {} param {name}", kdf.function))
+}
+
+/// Highest KDF costs accepted from a keystore, the EIP-2335 recommended ones. The costs are
+/// client-supplied, so unbounded they would let one request pin a core or exhaust memory.
+const MAX_SCRYPT_N: u32 = 1 << 18;
+const MAX_SCRYPT_R: u32 = 8;
+const MAX_SCRYPT_P: u32 = 1;
+const MAX_PBKDF2_C: u32 = 1 << 18;
+const MAX_DKLEN: usize = 64;
+
+// Derives the decryption key of `kdf` from the processed `password`
+fn derive_keystore_key(kdf: &KeystoreModule, password: &[u8]) -> Result<Vec<u8>> {
+    let salt = kdf_param(kdf, "salt")?
+        .as_str()
+        .ok_or_else(|| anyhow!("Bad keystore salt"))?;
+    let salt = hex::decode(salt).with_context(|| "Unable to hex-decode keystore salt")?;
+    let dklen = kdf_u32(kdf, "dklen")? as usize;
+    if !(32..=MAX_DKLEN).contains(&dklen) {
+        bail!("Keystore dklen must be between 32 and {MAX_DKLEN} bytes")
+    }
+    let mut key = vec![0_u8; dklen];
+    match kdf.function.as_str() {
+        "scrypt" => {
+            let n = kdf_u32(kdf, "n")?;
+            if !n.is_power_of_two() || n < 2 {
+                bail!("Keystore scrypt n must be a power of two")
+            }
+            let (r, p) = (kdf_u32(kdf, "r")?, kdf_u32(kdf, "p")?);
+            if n > MAX_SCRYPT_N || r > MAX_SCRYPT_R || p > MAX_SCRYPT_P {
+                bail!(
+                    "Keystore scrypt params exceed n = {MAX_SCRYPT_N}, r = {MAX_SCRYPT_R}, p = {MAX_SCRYPT_P}"
+                )
+            }
+            let params = scrypt::Params::new(n.trailing_zeros() as u8, r, p)
+                .map_err(|e| anyhow!("Bad keystore scrypt params: {:?}", e))?;
+            scrypt::scrypt(password, &salt, &params, &mut key)
+                .map_err(|e| anyhow!("Keystore scrypt failed: {:?}", e))?;
+        }
+        "pbkdf2" => {
+            let prf = kdf_param(kdf, "prf")?.as_str().unwrap_or_default();
+            if prf != "hmac-sha256" {
+                bail!("Unsupported keystore pbkdf2 prf {prf}")
+            }
+            let c = kdf_u32(kdf, "c")?;
+            if c > MAX_PBKDF2_C {
+                bail!("Keystore pbkdf2 c exceeds {MAX_PBKDF2_C}")
+            }
+            pbkdf2::pbkdf2::<hmac::Hmac<Sha256>>(password, &salt, c, &mut key);
+        }
+        f => bail!("Unsupported keystore kdf {f}"),
+    }
+    Ok(key)
+}
+
+// Runs the KDF of `crypto` over `password`, returning the derived key only if it matches the
+// keystore checksum
+fn checked_keystore_key(crypto: &KeystoreCrypto, password: &str) -> Result<Option<Vec<u8>>> {
+    if crypto.checksum.function != "sha256" {
+        bail!(
+            "Unsupported keystore checksum function {}",
+            crypto.checksum.function
+        )
+    }
+    let cipher_message = hex::decode(&crypto.cipher.message)
+        .with_context(|| "Unable to hex-decode keystore cipher message")?;
+    let exp_checksum = hex::decode(&crypto.checksum.message)
+        .with_context(|| "Unable to hex-decode keystore checksum")?;
+
+    let mut key = derive_keystore_key(&crypto.kdf, &process_password(password))?;
+    let checksum = Sha256::new()
+        .chain_update(&key[16..32])
+        .chain_update(&cipher_message)
+        .finalize();
+    if checksum.as_slice() != exp_checksum.as_slice() {
+        key.zeroize();
+        return Ok(None);
+    }
+    Ok(Some(key))
+}
+
+// Decrypts the secret of an aes-128-ctr `cipher` module under the first 16 bytes of `key`
+fn decrypt_keystore_secret(cipher: &KeystoreModule, key: &[u8]) -> Result<Vec<u8>> {
+    if cipher.function != "aes-128-ctr" {
+        bail!("Unsupported keystore cipher {}", cipher.function)
+    }
+    let iv = cipher
+        .params
+        .get("iv")
+        .and_then(|iv| iv.as_str())
+        .ok_or_else(|| anyhow!("Keystore cipher params missing iv"))?;
+    let iv = hex::decode(iv).with_context(|| "Unable to hex-decode keystore iv")?;
+    let mut secret = hex::decode(&cipher.message)
+        .with_context(|| "Unable to hex-decode keystore cipher message")?;
+    apply_aes_128_ctr(key, &iv, &mut secret)?;
+    Ok(secret)
+}
+
+// Encrypts or decrypts `data` in place with the aes-128-ctr keystream of the first 16 bytes of
+// `key` and `iv`
+fn apply_aes_128_ctr(key: &[u8], iv: &[u8], data: &mut [u8]) -> Result<()> {
+    use ctr::cipher::{KeyIvInit, StreamCipher};
+    let mut aes_ctr = ctr::Ctr128BE::<aes::Aes128>::new_from_slices(&key[..16], iv)
+        .map_err(|e| anyhow!("Bad keystore cipher params: {:?}", e))?;
+    aes_ctr.apply_keystream(data);
+    Ok(())
+}
+
+/// Checks `password` opens the EIP-2335 keystore `keystore_json` by running its KDF and comparing
+/// the checksum only. The secret is never decrypted. Errors if the keystore is malformed.
+pub fn verify_keystore_password(keystore_json: &str, password: &str) -> Result<bool> {
+    let keystore: Eip2335Keystore =
+        serde_json::from_str(keystore_json).with_context(|| "Malformed EIP-2335 keystore")?;
+    match checked_keystore_key(&keystore.crypto, password)? {
+        Some(mut key) => {
+            key.zeroize();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Imports the BLS secret of the EIP-2335 keystore `keystore_json`, encrypted under a scrypt or
+/// pbkdf2 derived key, and saves it under its hex encoded pk. The secret must match the
+/// keystore's `pubkey`, and is only ever held in enclave memory. A wrong password fails with
+/// `WrongKeystorePassword`. Refuses to overwrite an existing key.
+pub fn import_eip2335_keystore(keystore_json: &str, password: &str) -> Result<blsttc::PublicKey> {
+    let keystore: Eip2335Keystore =
+        serde_json::from_str(keystore_json).with_context(|| "Malformed EIP-2335 keystore")?;
+    let Some(mut key) = checked_keystore_key(&keystore.crypto, password)? else {
+        return Err(anyhow!(WrongKeystorePassword));
+    };
+    let secret = decrypt_keystore_secret(&keystore.crypto.cipher, &key);
+    key.zeroize();
+    let mut secret = secret?;
+    let res = check_keystore_pubkey(&secret, &keystore.pubkey)
+        .and_then(|_| super::bls_keys::import_bls_secret_bytes(&secret));
+    secret.zeroize();
+    res
+}
+
+/// Returns the hex encoded `pubkey` of the EIP-2335 keystore `keystore_json` without decrypting
+/// it, or None if the keystore omits it
+pub fn eip2335_keystore_pubkey(keystore_json: &str) -> Result<Option<String>> {
+    let keystore: Eip2335Keystore =
+        serde_json::from_str(keystore_json).with_context(|| "Malformed EIP-2335 keystore")?;
+    if keystore.pubkey.is_empty() {
+        return Ok(None);
+    }
+    let pk_hex: String = strip_0x_prefix!(keystore.pubkey);
+    Ok(Some(pk_hex.to_lowercase()))
+}
+
+/// scrypt cost parameters of exported keystores
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeystoreScryptParams {
+    /// CPU/memory cost, a power of two
+    pub n: u32,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for KeystoreScryptParams {
+    /// The EIP-2335 recommended parameters
+    fn default() -> Self {
+        KeystoreScryptParams {
+            n: 262144,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+// A random version 4 UUID
+fn random_uuid() -> String {
+    let mut b: [u8; 16] = rand::random();
+    b[6] = (b[6] & 0x0f) | 0x40;
+    b[8] = (b[8] & 0x3f) | 0x80;
+    let h = hex::encode(b);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &h[..8],
+        &h[8..12],
+        &h[12..16],
+        &h[16..20],
+        &h[20..]
+    )
+}
+
+/// Exports the saved BLS key `pk_hex` as EIP-2335 keystore JSON encrypted under `password`,
+/// using the default `KeystoreScryptParams`
+pub fn export_eip2335_keystore(pk_hex: &String, password: &str) -> Result<String> {
+    export_eip2335_keystore_with_params(pk_hex, password, KeystoreScryptParams::default())
+}
+
+/// Variant of `export_eip2335_keystore` under the scrypt cost `params`. The secret is encrypted
+/// with aes-128-ctr under a fresh salt and IV, and the `pubkey` field is the key's name in
+/// `list_bls_keys`. Subject to the password policy.
+pub fn export_eip2335_keystore_with_params(
+    pk_hex: &String,
+    password: &str,
+    params: KeystoreScryptParams,
+) -> Result<String> {
+    export_eip2335_keystore_under_policy(pk_hex, password, params, &password_policy())
+}
+
+// The export of `export_eip2335_keystore_with_params`, refusing passwords `policy` rejects
+fn export_eip2335_keystore_under_policy(
+    pk_hex: &String,
+    password: &str,
+    params: KeystoreScryptParams,
+    policy: &PasswordPolicy,
+) -> Result<String> {
+    policy.check(password)?;
+    let pk_hex = super::bls_keys::sanitize_bls_pk_hex(pk_hex)?;
+    let mut secret = crate::io::key_management::read_bls_key(&pk_hex)?;
+    let res = check_keystore_pubkey(&secret, &pk_hex)
+        .with_context(|| format!("Saved BLS key {pk_hex} cannot be exported as a keystore"));
+    if let Err(e) = res {
+        secret.zeroize();
+        return Err(e);
+    }
+
+    let salt: [u8; 32] = rand::random();
+    let iv: [u8; 16] = rand::random();
+    let kdf = KeystoreModule {
+        function: "scrypt".to_string(),
+        params: serde_json::json!({
+            "dklen": 32,
+            "n": params.n,
+            "r": params.r,
+            "p": params.p,
+            "salt": hex::encode(salt),
+        }),
+        message: String::new(),
+    };
+    let res = derive_keystore_key(&kdf, &process_password(password)).and_then(|mut key| {
+        let mut ciphertext = secret.clone();
+        let res = apply_aes_128_ctr(&key, &iv, &mut ciphertext);
+        let checksum = Sha256::new()
+            .chain_update(&key[16..32])
+            .chain_update(&ciphertext)
+            .finalize();
+        key.zeroize();
+        if let Err(e) = res {
+            ciphertext.zeroize();
+            return Err(e);
+        }
+        Ok((ciphertext, checksum))
+    });
+    secret.zeroize();
+    let (ciphertext, checksum) = res?;
+
+    let keystore = serde_json::json!({
+        "crypto": {
+            "kdf": {
+                "function": kdf.function,
+                "params": kdf.params,
+                "message": "",
+            },
+            "checksum": {
+                "function": "sha256",
+                "params": {},
+                "message": hex::encode(checksum),
+            },
+            "cipher": {
+                "function": "aes-128-ctr",
+                "params": { "iv": hex::encode(iv) },
+                "message": hex::encode(ciphertext),
+            },
+        },
+        "description": "",
+        "pubkey": pk_hex,
+        "path": super::bls_keys::get_derivation_path(&pk_hex)?.unwrap_or_default(),
+        "uuid": random_uuid(),
+        "version": 4,
+    });
+    Ok(serde_json::to_string(&keystore)?)
+}
+
+// Errors unless the BLS secret `sk_bytes` derives `pubkey_hex`
+fn check_keystore_pubkey(sk_bytes: &[u8], pubkey_hex: &str) -> Result<()> {
+    let mut sk_buf: [u8; 32] = sk_bytes
+        .try_into()
+        .map_err(|_| anyhow!("Keystore secret must be 32 bytes"))?;
+    let sk = blsttc::SecretKey::from_bytes(sk_buf);
+    sk_buf.zeroize();
+    let pk = sk
+        .map_err(|e| anyhow!("Invalid keystore secret: {:?}", e))?
+        .public_key();
+    let exp_pk_hex: &str = strip_0x_prefix!(pubkey_hex);
+    if pk.to_hex() != exp_pk_hex.to_lowercase() {
+        bail!(
+            "Keystore secret derives pk {} rather than its pubkey {exp_pk_hex}",
+            pk.to_hex()
+        )
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 pub mod keystore_tests {
     use crate::crypto::eth_keys;
 
-    use super::import_keystore;
+    use super::{
+        eip2335_keystore_pubkey, export_eip2335_keystore_under_policy,
+        export_eip2335_keystore_with_params, import_eip2335_keystore, import_keystore,
+        parse_password_policy, verify_keystore_password, KeystoreScryptParams, PasswordPolicy,
+        WrongKeystorePassword,
+    };
     use hex::FromHex;
 
-    #[test]
-    /// Test vec from: https://eips.ethereum.org/EIPS/eip-2335
-    fn test_import_keystore() {
-        let keystore = r#"
+    /// EIP-2335 test vectors, both encrypting the same secret under the password "testpassword🔑"
+    const SCRYPT_KEYSTORE: &str = r#"
         {
             "crypto": {
                 "kdf": {
@@ -60,7 +491,108 @@ pub mod keystore_tests {
             "path": "m/12381/60/3141592653/589793238",
             "uuid": "1d85ae20-35c5-4611-98e8-aa14a633906f",
             "version": 4
-        }"#.to_string();
+        }"#;
+
+    const PBKDF2_KEYSTORE: &str = r#"
+        {
+            "crypto": {
+                "kdf": {
+                    "function": "pbkdf2",
+                    "params": {
+                        "dklen": 32,
+                        "c": 262144,
+                        "prf": "hmac-sha256",
+                        "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                    },
+                    "message": ""
+                },
+                "checksum": {
+                    "function": "sha256",
+                    "params": {},
+                    "message": "8a9f5d9912ed7e75ea794bc5a89bca5f193721d30868ade6f73043c6ea6febf1"
+                },
+                "cipher": {
+                    "function": "aes-128-ctr",
+                    "params": {
+                        "iv": "264daa3f303d7259501c93d997d84fe6"
+                    },
+                    "message": "cee03fde2af33149775b7223e7845e4fb2c8ae1792e5f99fe9ecf474cc8c16ad"
+                }
+            },
+            "description": "This is a test keystore that uses PBKDF2 to secure the secret.",
+            "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+            "path": "m/12381/60/0/0",
+            "uuid": "64625def-3331-4eea-ab6f-782f3ed16a83",
+            "version": 4
+        }"#;
+
+    #[test]
+    fn test_password_policy_disabled_by_default() {
+        let policy = PasswordPolicy::default();
+        assert!(policy.min_length.is_none());
+        assert!(policy.check("").is_ok());
+        assert!(policy.check("a").is_ok());
+    }
+
+    #[test]
+    fn test_password_policy_min_length() {
+        let policy = PasswordPolicy {
+            min_length: Some(12),
+        };
+        assert!(policy.check("short").is_err());
+        assert!(policy.check("elevenchars").is_err());
+        assert!(policy.check("twelve chars").is_ok());
+        // Length is counted in characters rather than bytes
+        assert!(policy.check("🔑🔑🔑🔑🔑🔑").is_err());
+        assert!(policy.check("🔑🔑🔑🔑🔑🔑🔑🔑🔑🔑🔑🔑").is_ok());
+    }
+
+    #[test]
+    fn test_parse_password_policy() {
+        assert_eq!(
+            parse_password_policy(None).unwrap(),
+            PasswordPolicy::default()
+        );
+        assert_eq!(
+            parse_password_policy(Some("")).unwrap(),
+            PasswordPolicy::default()
+        );
+        assert_eq!(
+            parse_password_policy(Some("12")).unwrap().min_length,
+            Some(12)
+        );
+        assert!(parse_password_policy(Some("twelve")).is_err());
+        assert!(parse_password_policy(Some("-1")).is_err());
+    }
+
+    #[test]
+    fn test_export_enforces_password_policy() {
+        let policy = parse_password_policy(Some("16")).unwrap();
+        let params = KeystoreScryptParams { n: 16, r: 8, p: 1 };
+        let sk_set = crate::crypto::bls_keys::new_bls_key(0);
+        crate::crypto::bls_keys::save_bls_key(&sk_set).unwrap();
+        let pk = sk_set.public_keys().public_key();
+        let pk_hex = pk.to_hex();
+
+        // A weak password is refused with a descriptive error
+        let err = export_eip2335_keystore_under_policy(&pk_hex, "too short", params, &policy)
+            .unwrap_err();
+        assert!(err.to_string().contains("at least 16 characters"));
+
+        let password = "long enough password";
+        let keystore =
+            export_eip2335_keystore_under_policy(&pk_hex, password, params, &policy).unwrap();
+
+        // The compliant export imports back to the same key
+        crate::io::key_management::delete_bls_key(&pk_hex).unwrap();
+        assert_eq!(import_eip2335_keystore(&keystore, password).unwrap(), pk);
+        crate::io::key_management::delete_bls_key(&pk_hex).unwrap();
+    }
+
+    #[test]
+    /// Test vec from: https://eips.ethereum.org/EIPS/eip-2335
+    fn test_import_keystore() {
+        let keystore = SCRYPT_KEYSTORE.to_string();
 
         let (eth_sk, eth_pk) = eth_keys::new_eth_key().unwrap();
         let encoded_pw = hex::decode("7465737470617373776f7264f09f9491").unwrap();
@@ -75,6 +607,118 @@ pub mod keystore_tests {
         );
     }
 
+    #[test]
+    fn test_verify_keystore_password() {
+        let password = "testpassword🔑";
+        assert!(verify_keystore_password(PBKDF2_KEYSTORE, password).unwrap());
+        assert!(verify_keystore_password(SCRYPT_KEYSTORE, password).unwrap());
+        assert!(!verify_keystore_password(PBKDF2_KEYSTORE, "testpassword").unwrap());
+        assert!(!verify_keystore_password(PBKDF2_KEYSTORE, "").unwrap());
+
+        // Control codes are stripped from the password as EIP-2335 requires
+        assert!(verify_keystore_password(PBKDF2_KEYSTORE, "test\u{7f}password🔑").unwrap());
+
+        // A malformed keystore is an error rather than a wrong password
+        assert!(verify_keystore_password("{}", password).is_err());
+        let bad_kdf = PBKDF2_KEYSTORE.replace("hmac-sha256", "hmac-sha512");
+        assert!(verify_keystore_password(&bad_kdf, password).is_err());
+
+        // Costs above the recommended ones are refused before running the KDF
+        for costly in [
+            SCRYPT_KEYSTORE.replace("\"n\": 262144", "\"n\": 1073741824"),
+            SCRYPT_KEYSTORE.replace("\"r\": 8", "\"r\": 4096"),
+            SCRYPT_KEYSTORE.replace("\"p\": 1", "\"p\": 64"),
+            PBKDF2_KEYSTORE.replace("\"c\": 262144", "\"c\": 4294967295"),
+            PBKDF2_KEYSTORE.replace("\"dklen\": 32", "\"dklen\": 4294967295"),
+        ] {
+            let err = verify_keystore_password(&costly, password).unwrap_err();
+            assert!(!WrongKeystorePassword::is_cause_of(&err));
+        }
+    }
+
+    #[test]
+    fn test_import_eip2335_keystore() {
+        let password = "testpassword🔑";
+        let exp_pk_hex = "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07";
+
+        for keystore in [SCRYPT_KEYSTORE, PBKDF2_KEYSTORE] {
+            let pubkey = eip2335_keystore_pubkey(keystore).unwrap();
+            assert_eq!(pubkey.as_deref(), Some(exp_pk_hex));
+
+            // A wrong password is told apart from other failures
+            let err = import_eip2335_keystore(keystore, "testpassword").unwrap_err();
+            assert!(WrongKeystorePassword::is_cause_of(&err));
+
+            let pk = import_eip2335_keystore(keystore, password).unwrap();
+            assert_eq!(pk.to_hex(), exp_pk_hex);
+            let sk_set = crate::crypto::bls_keys::fetch_bls_sk(&pk.to_hex()).unwrap();
+            assert_eq!(
+                hex::encode(sk_set.secret_key().to_bytes()),
+                "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+            );
+
+            // Existing keys are never overwritten
+            assert!(import_eip2335_keystore(keystore, password).is_err());
+            crate::io::key_management::delete_bls_key(&pk.to_hex()).unwrap();
+        }
+
+        // The secret must match the keystore's pubkey
+        let other_pubkey = PBKDF2_KEYSTORE.replace(exp_pk_hex, &"a".repeat(96));
+        let err = import_eip2335_keystore(&other_pubkey, password).unwrap_err();
+        assert!(!WrongKeystorePassword::is_cause_of(&err));
+        assert!(!crate::io::key_management::bls_key_exists(exp_pk_hex));
+
+        let bad_cipher = PBKDF2_KEYSTORE.replace("aes-128-ctr", "aes-256-gcm");
+        assert!(import_eip2335_keystore(&bad_cipher, password).is_err());
+    }
+
+    #[test]
+    fn test_export_eip2335_keystore() {
+        assert_eq!(KeystoreScryptParams::default().n, 262144);
+        // Cheap parameters keep the test fast
+        let params = KeystoreScryptParams { n: 16, r: 8, p: 1 };
+        let password = "export password🔑";
+        let sk_set = crate::crypto::bls_keys::new_bls_key(0);
+        crate::crypto::bls_keys::save_bls_key(&sk_set).unwrap();
+        let pk = sk_set.public_keys().public_key();
+
+        let keystore =
+            export_eip2335_keystore_with_params(&format!("0x{}", pk.to_hex()), password, params)
+                .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&keystore).unwrap();
+        assert_eq!(json["version"], 4);
+        assert_eq!(json["crypto"]["kdf"]["function"], "scrypt");
+        assert_eq!(json["crypto"]["kdf"]["params"]["n"], 16);
+        assert!(crate::io::key_management::list_bls_keys()
+            .unwrap()
+            .contains(&json["pubkey"].as_str().unwrap().to_string()));
+        assert!(verify_keystore_password(&keystore, password).unwrap());
+        assert!(!verify_keystore_password(&keystore, "wrong password").unwrap());
+
+        // Every export is encrypted under a fresh salt and IV
+        let again = export_eip2335_keystore_with_params(&pk.to_hex(), password, params).unwrap();
+        let again: serde_json::Value = serde_json::from_str(&again).unwrap();
+        assert_ne!(
+            again["crypto"]["kdf"]["params"]["salt"],
+            json["crypto"]["kdf"]["params"]["salt"]
+        );
+        assert_ne!(
+            again["crypto"]["cipher"]["params"]["iv"],
+            json["crypto"]["cipher"]["params"]["iv"]
+        );
+
+        // The export imports back to the same key
+        crate::io::key_management::delete_bls_key(&pk.to_hex()).unwrap();
+        assert_eq!(import_eip2335_keystore(&keystore, password).unwrap(), pk);
+        let imported = crate::crypto::bls_keys::fetch_bls_sk(&pk.to_hex()).unwrap();
+        assert_eq!(
+            imported.secret_key().to_bytes(),
+            sk_set.secret_key().to_bytes()
+        );
+        crate::io::key_management::delete_bls_key(&pk.to_hex()).unwrap();
+        assert!(export_eip2335_keystore_with_params(&pk.to_hex(), password, params).is_err());
+    }
+
     #[test]
     fn test_encrypt_decrypt_keystore() {
         std::fs::create_dir_all("./test_keys").unwrap();
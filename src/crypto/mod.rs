@@ -1,3 +1,10 @@
 pub mod bls_keys;
+/// Pure BLS verification that never touches the key store or slashing DB
+pub mod bls_verify;
 pub mod eth_keys;
+pub mod key_cache;
 pub mod keystore;
+pub mod nonce;
+pub mod replay_store;
+pub mod signature_cache;
+pub mod verification_cache;
@@ -0,0 +1,198 @@
+use crate::constants::NONCE_TTL_SECS;
+use crate::crypto::replay_store::{global_replay_store, ReplayStore};
+use crate::io::unix_now;
+use crate::strip_0x_prefix;
+
+use anyhow::{Context, Result};
+use rand::RngCore;
+
+pub type Nonce = [u8; 32];
+
+/// Why a nonce was rejected by `NonceIssuer::consume`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonceError {
+    /// Never issued, or no longer recorded long after expiring
+    Unknown,
+    /// Issued but past its TTL
+    Expired,
+    /// Already consumed by an earlier request
+    Reused,
+}
+
+impl std::fmt::Display for NonceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NonceError::Unknown => write!(f, "Nonce was not issued by this signer"),
+            NonceError::Expired => write!(f, "Nonce has expired"),
+            NonceError::Reused => write!(f, "Nonce was already used"),
+        }
+    }
+}
+
+impl std::error::Error for NonceError {}
+
+// Issued nonces are recorded for twice their TTL, so a replay within that window is reported
+// as reuse or expiry rather than as unknown
+const RECORD_TTL_FACTOR: u64 = 2;
+
+/// Issues random single-use nonces that expire `ttl_secs` after issue, recorded in a
+/// `ReplayStore`. With the persistent store, a nonce consumed before a restart is still rejected
+/// after it. Issuing fails with `ReplayStoreFull` rather than forgetting a recorded nonce.
+pub struct NonceIssuer {
+    ttl_secs: u64,
+}
+
+impl NonceIssuer {
+    pub fn new(ttl_secs: u64) -> Self {
+        NonceIssuer { ttl_secs }
+    }
+
+    pub fn ttl_secs(&self) -> u64 {
+        self.ttl_secs
+    }
+
+    /// Issues a fresh nonce valid until `now + ttl_secs`
+    pub fn issue_at(&self, store: &mut dyn ReplayStore, now: u64) -> Result<Nonce> {
+        let mut nonce = [0_u8; 32];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        store.put(
+            &nonce_key(&nonce),
+            nonce_record(now.saturating_add(self.ttl_secs), false),
+            self.ttl_secs.saturating_mul(RECORD_TTL_FACTOR),
+            now,
+        )?;
+        Ok(nonce)
+    }
+
+    /// Accepts `nonce` once if it was issued and has not expired by `now`. A rejected nonce is
+    /// returned as a `NonceError`.
+    pub fn consume_at(&self, store: &mut dyn ReplayStore, nonce: &Nonce, now: u64) -> Result<()> {
+        let key = nonce_key(nonce);
+        let Some(record) = store.get(&key, now)? else {
+            return Err(NonceError::Unknown.into());
+        };
+        let (expires_at, consumed) = parse_nonce_record(&record)?;
+        if consumed {
+            return Err(NonceError::Reused.into());
+        }
+        if expires_at <= now {
+            return Err(NonceError::Expired.into());
+        }
+        // Kept until the record would have expired, so replays are reported as reuse
+        let issued_at = expires_at.saturating_sub(self.ttl_secs);
+        let record_expires_at =
+            issued_at.saturating_add(self.ttl_secs.saturating_mul(RECORD_TTL_FACTOR));
+        store.put(
+            &key,
+            nonce_record(expires_at, true),
+            record_expires_at.saturating_sub(now),
+            now,
+        )
+    }
+}
+
+fn nonce_key(nonce: &Nonce) -> String {
+    format!("nonce:{}", hex::encode(nonce))
+}
+
+// A nonce's expiry as big-endian unix seconds, followed by whether it was consumed
+fn nonce_record(expires_at: u64, consumed: bool) -> Vec<u8> {
+    let mut record = expires_at.to_be_bytes().to_vec();
+    record.push(consumed as u8);
+    record
+}
+
+fn parse_nonce_record(record: &[u8]) -> Result<(u64, bool)> {
+    if record.len() != 9 {
+        anyhow::bail!("Malformed nonce record")
+    }
+    let mut expires_at = [0_u8; 8];
+    expires_at.copy_from_slice(&record[..8]);
+    Ok((u64::from_be_bytes(expires_at), record[8] != 0))
+}
+
+// Runs `f` on the process-wide replay store
+fn with_global_store<T>(f: impl FnOnce(&mut dyn ReplayStore) -> Result<T>) -> Result<T> {
+    let mut store = match global_replay_store()?.lock() {
+        Ok(store) => store,
+        Err(e) => anyhow::bail!("Replay store lock poisoned: {:?}", e),
+    };
+    f(&mut **store)
+}
+
+/// Issues a nonce recorded in the process-wide replay store
+pub fn issue() -> Result<Nonce> {
+    let now = unix_now()?;
+    with_global_store(|store| NonceIssuer::new(NONCE_TTL_SECS).issue_at(store, now))
+}
+
+/// Consumes the hex-encoded `nonce_hex` from the process-wide replay store. A rejected nonce is
+/// returned as a `NonceError`.
+pub fn consume(nonce_hex: &str) -> Result<()> {
+    let nonce_hex: &str = strip_0x_prefix!(nonce_hex);
+    let bytes = hex::decode(nonce_hex).with_context(|| "Unable to hex-decode nonce")?;
+    let nonce: Nonce = match bytes.try_into() {
+        Ok(nonce) => nonce,
+        Err(_) => return Err(NonceError::Unknown.into()),
+    };
+    let now = unix_now()?;
+    with_global_store(|store| NonceIssuer::new(NONCE_TTL_SECS).consume_at(store, &nonce, now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::replay_store::{MemoryReplayStore, ReplayStoreFull};
+
+    fn rejection(res: Result<()>) -> Option<NonceError> {
+        res.unwrap_err().downcast_ref::<NonceError>().copied()
+    }
+
+    #[test]
+    fn test_nonce_is_single_use() {
+        let issuer = NonceIssuer::new(60);
+        let mut store = MemoryReplayStore::new(16);
+        let nonce = issuer.issue_at(&mut store, 100).unwrap();
+        issuer.consume_at(&mut store, &nonce, 100).unwrap();
+        let res = issuer.consume_at(&mut store, &nonce, 100);
+        assert_eq!(rejection(res), Some(NonceError::Reused));
+        let res = issuer.consume_at(&mut store, &[0_u8; 32], 100);
+        assert_eq!(rejection(res), Some(NonceError::Unknown));
+
+        // Through the process-wide store with hex encoding
+        let nonce_hex = format!("0x{}", hex::encode(issue().unwrap()));
+        consume(&nonce_hex).unwrap();
+        assert_eq!(rejection(consume(&nonce_hex)), Some(NonceError::Reused));
+    }
+
+    #[test]
+    fn test_nonce_expires() {
+        let issuer = NonceIssuer::new(60);
+        let mut store = MemoryReplayStore::new(16);
+        let stale = issuer.issue_at(&mut store, 100).unwrap();
+        let fresh = issuer.issue_at(&mut store, 130).unwrap();
+
+        let res = issuer.consume_at(&mut store, &stale, 161);
+        assert_eq!(rejection(res), Some(NonceError::Expired));
+        issuer.consume_at(&mut store, &fresh, 161).unwrap();
+        // A consumed nonce is reported as reused until its record expires
+        let res = issuer.consume_at(&mut store, &fresh, 249);
+        assert_eq!(rejection(res), Some(NonceError::Reused));
+        let res = issuer.consume_at(&mut store, &stale, 221);
+        assert_eq!(rejection(res), Some(NonceError::Unknown));
+    }
+
+    #[test]
+    fn test_nonce_issuer_is_bounded() {
+        let issuer = NonceIssuer::new(60);
+        let mut store = MemoryReplayStore::new(2);
+        let first = issuer.issue_at(&mut store, 100).unwrap();
+        issuer.issue_at(&mut store, 100).unwrap();
+        // A full store refuses to issue rather than forgetting an outstanding nonce
+        let err = issuer.issue_at(&mut store, 100).unwrap_err();
+        assert!(ReplayStoreFull::is_cause_of(&err));
+        issuer.consume_at(&mut store, &first, 100).unwrap();
+        // Records make room once they expire
+        issuer.issue_at(&mut store, 220).unwrap();
+    }
+}
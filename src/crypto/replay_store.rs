@@ -0,0 +1,394 @@
+use crate::constants::REPLAY_STORE_CAPACITY;
+use crate::io::unix_now;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Error returned when recording a new key in a `ReplayStore` holding `capacity` unexpired
+/// entries. Live entries are never evicted, as a dropped entry could be replayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayStoreFull {
+    pub capacity: usize,
+}
+
+impl std::fmt::Display for ReplayStoreFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Replay store is full with {} unexpired entries",
+            self.capacity
+        )
+    }
+}
+
+impl std::error::Error for ReplayStoreFull {}
+
+impl ReplayStoreFull {
+    pub fn is_cause_of(e: &anyhow::Error) -> bool {
+        e.chain().any(|c| c.is::<ReplayStoreFull>())
+    }
+}
+
+/// Keyed store of expiring values behind the nonce checks. Times are unix seconds, passed in so
+/// callers and tests control the clock. Expired entries are never returned and are dropped
+/// lazily, when next accessed or when room is needed.
+pub trait ReplayStore: Send {
+    /// Returns the value recorded under `key` unless it expired by `now`
+    fn get(&mut self, key: &str, now: u64) -> Result<Option<Vec<u8>>>;
+
+    /// Records `value` under `key` until `now + ttl_secs`, replacing any earlier value. Fails
+    /// with `ReplayStoreFull` for a new `key` while the store is full of unexpired entries.
+    fn put(&mut self, key: &str, value: Vec<u8>, ttl_secs: u64, now: u64) -> Result<()>;
+
+    fn remove(&mut self, key: &str) -> Result<()>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Records `value` under `key` only if no unexpired entry exists, returning true if it was
+    /// recorded. A replayed key is reported as false.
+    fn insert_if_absent(
+        &mut self,
+        key: &str,
+        value: Vec<u8>,
+        ttl_secs: u64,
+        now: u64,
+    ) -> Result<bool> {
+        if self.get(key, now)?.is_some() {
+            return Ok(false);
+        }
+        self.put(key, value, ttl_secs, now)?;
+        Ok(true)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+struct StoreEntry {
+    key: String,
+    value: Vec<u8>,
+    expires_at: u64,
+}
+
+/// In-memory `ReplayStore` holding at most `capacity` entries. Once full, expired entries are
+/// dropped to make room, and new keys are refused while none have expired.
+pub struct MemoryReplayStore {
+    capacity: usize,
+    entries: HashMap<String, StoreEntry>,
+}
+
+impl MemoryReplayStore {
+    pub fn new(capacity: usize) -> Self {
+        MemoryReplayStore {
+            capacity,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // Drops every entry expired by `now`
+    fn purge(&mut self, now: u64) {
+        self.entries.retain(|_, e| e.expires_at > now);
+    }
+
+    // Errors unless one more entry fits, dropping expired ones first
+    fn make_room(&mut self, now: u64) -> Result<()> {
+        if self.entries.len() >= self.capacity {
+            self.purge(now);
+        }
+        if self.entries.len() >= self.capacity {
+            return Err(ReplayStoreFull {
+                capacity: self.capacity,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    // Records `entry` as given, its key must already fit
+    fn insert(&mut self, entry: StoreEntry) {
+        self.entries.insert(entry.key.clone(), entry);
+    }
+}
+
+impl ReplayStore for MemoryReplayStore {
+    fn get(&mut self, key: &str, now: u64) -> Result<Option<Vec<u8>>> {
+        match self.entries.get(key) {
+            None => Ok(None),
+            Some(e) if e.expires_at <= now => {
+                self.entries.remove(key);
+                Ok(None)
+            }
+            Some(e) => Ok(Some(e.value.clone())),
+        }
+    }
+
+    fn put(&mut self, key: &str, value: Vec<u8>, ttl_secs: u64, now: u64) -> Result<()> {
+        if !self.entries.contains_key(key) {
+            self.make_room(now)?;
+        }
+        self.insert(StoreEntry {
+            key: key.to_string(),
+            value,
+            expires_at: now.saturating_add(ttl_secs),
+        });
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> Result<()> {
+        self.entries.remove(key);
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// `MemoryReplayStore` persisted to an append-only log of JSON lines, so recorded keys survive a
+/// restart and a replay after the restart is still rejected. Each change appends one line, and
+/// the log is rewritten with only the live entries when opened and once it outgrows them.
+pub struct PersistentReplayStore {
+    memory: MemoryReplayStore,
+    path: PathBuf,
+    log: fs::File,
+    // Lines in the log, compacted once past twice the capacity
+    log_lines: usize,
+}
+
+impl PersistentReplayStore {
+    /// Opens the store saved at `path`, or an empty one if none was saved. Entries expired by
+    /// `now` are not loaded.
+    pub fn open(path: impl Into<PathBuf>, capacity: usize, now: u64) -> Result<Self> {
+        let path = path.into();
+        let mut memory = MemoryReplayStore::new(capacity);
+        if path.exists() {
+            let log = fs::read_to_string(&path).with_context(|| "Failed to read replay store")?;
+            // Later lines supersede earlier ones, and removals are logged as expired entries
+            let mut entries: HashMap<String, StoreEntry> = HashMap::new();
+            for line in complete_lines(&log) {
+                let e: StoreEntry =
+                    serde_json::from_str(line).with_context(|| "Malformed replay store")?;
+                entries.insert(e.key.clone(), e);
+            }
+            for e in entries.into_values().filter(|e| e.expires_at > now) {
+                memory
+                    .make_room(now)
+                    .with_context(|| "Saved replay store exceeds its capacity")?;
+                memory.insert(e);
+            }
+        }
+        let log = compact(&path, &memory)?;
+        let log_lines = memory.len();
+        Ok(PersistentReplayStore {
+            memory,
+            path,
+            log,
+            log_lines,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Appends `entry` to the log, first compacting it if it outgrew the live entries
+    fn append(&mut self, entry: &StoreEntry) -> Result<()> {
+        if self.log_lines >= 2 * self.memory.capacity().max(1) {
+            self.log = compact(&self.path, &self.memory)?;
+            self.log_lines = self.memory.len();
+        }
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.log
+            .write_all(&line)
+            .and_then(|_| self.log.sync_data())
+            .with_context(|| "Failed to write replay store")?;
+        self.log_lines += 1;
+        Ok(())
+    }
+}
+
+// Lines ending in a newline. A trailing partial line is a write torn by a crash, whose change
+// was never acknowledged.
+fn complete_lines(log: &str) -> impl Iterator<Item = &str> {
+    let complete = match log.rfind('\n') {
+        Some(end) => &log[..end],
+        None => "",
+    };
+    complete.lines().filter(|line| !line.is_empty())
+}
+
+// Writes the entries of `memory` to a pending file then renames it over the log at `path`, so it
+// is never torn, and returns the new log opened for appending
+fn compact(path: &Path, memory: &MemoryReplayStore) -> Result<fs::File> {
+    if let Some(p) = path.parent() {
+        fs::create_dir_all(p).with_context(|| "Failed to create replay store dir")?;
+    }
+    let mut lines = Vec::new();
+    for e in memory.entries.values() {
+        lines.extend(serde_json::to_vec(e)?);
+        lines.push(b'\n');
+    }
+    let pending_path = path.with_extension("pending");
+    let mut f = fs::File::create(&pending_path).with_context(|| "Failed to write replay store")?;
+    f.write_all(&lines)
+        .and_then(|_| f.sync_all())
+        .with_context(|| "Failed to write replay store")?;
+    fs::rename(&pending_path, path).with_context(|| "Failed to commit replay store")?;
+    fs::OpenOptions::new()
+        .append(true)
+        .open(path)
+        .with_context(|| "Failed to open replay store")
+}
+
+impl ReplayStore for PersistentReplayStore {
+    fn get(&mut self, key: &str, now: u64) -> Result<Option<Vec<u8>>> {
+        // Expired entries dropped here need no log line, they are not loaded again either
+        self.memory.get(key, now)
+    }
+
+    fn put(&mut self, key: &str, value: Vec<u8>, ttl_secs: u64, now: u64) -> Result<()> {
+        let entry = StoreEntry {
+            key: key.to_string(),
+            value: value.clone(),
+            expires_at: now.saturating_add(ttl_secs),
+        };
+        self.memory.put(key, value, ttl_secs, now)?;
+        self.append(&entry)
+    }
+
+    fn remove(&mut self, key: &str) -> Result<()> {
+        self.memory.remove(key)?;
+        let tombstone = StoreEntry {
+            key: key.to_string(),
+            value: vec![],
+            expires_at: 0,
+        };
+        self.append(&tombstone)
+    }
+
+    fn len(&self) -> usize {
+        self.memory.len()
+    }
+}
+
+/// Returns the process-wide replay store. It is persisted to `SECURE_SIGNER_REPLAY_STORE_PATH`
+/// if set, and held in memory only otherwise.
+pub fn global_replay_store() -> Result<&'static Mutex<Box<dyn ReplayStore>>> {
+    static STORE: OnceLock<Mutex<Box<dyn ReplayStore>>> = OnceLock::new();
+    if let Some(store) = STORE.get() {
+        return Ok(store);
+    }
+    let store: Box<dyn ReplayStore> = match std::env::var("SECURE_SIGNER_REPLAY_STORE_PATH") {
+        Ok(path) if !path.is_empty() => Box::new(PersistentReplayStore::open(
+            path,
+            REPLAY_STORE_CAPACITY,
+            unix_now()?,
+        )?),
+        _ => Box::new(MemoryReplayStore::new(REPLAY_STORE_CAPACITY)),
+    };
+    Ok(STORE.get_or_init(|| Mutex::new(store)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_store_refuses_new_keys() {
+        let mut store = MemoryReplayStore::new(2);
+        store.put("a", vec![1], 60, 0).unwrap();
+        store.put("b", vec![2], 60, 0).unwrap();
+        // Nothing live is evicted, so neither key can be replayed
+        let err = store.put("c", vec![3], 60, 1).unwrap_err();
+        assert!(ReplayStoreFull::is_cause_of(&err));
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get("a", 1).unwrap(), Some(vec![1]));
+        assert_eq!(store.get("b", 1).unwrap(), Some(vec![2]));
+        assert_eq!(store.get("c", 1).unwrap(), None);
+        // Recorded keys can still be updated
+        store.put("a", vec![4], 60, 1).unwrap();
+        assert_eq!(store.get("a", 1).unwrap(), Some(vec![4]));
+
+        // Expired entries make room
+        let mut store = MemoryReplayStore::new(2);
+        store.put("live", vec![], 60, 0).unwrap();
+        store.put("stale", vec![], 5, 0).unwrap();
+        store.put("new", vec![], 60, 10).unwrap();
+        assert_eq!(store.get("live", 10).unwrap(), Some(vec![]));
+        assert_eq!(store.get("new", 10).unwrap(), Some(vec![]));
+        assert!(MemoryReplayStore::new(0).put("a", vec![], 60, 0).is_err());
+    }
+
+    #[test]
+    fn test_ttl_expiry() {
+        let mut store = MemoryReplayStore::new(16);
+        assert!(store.insert_if_absent("nonce", vec![], 60, 100).unwrap());
+        assert!(!store.insert_if_absent("nonce", vec![], 60, 159).unwrap());
+        assert_eq!(store.len(), 1);
+
+        // Expired entries are not returned and are dropped once accessed
+        assert_eq!(store.get("nonce", 160).unwrap(), None);
+        assert!(store.is_empty());
+        assert!(store.insert_if_absent("nonce", vec![], 60, 160).unwrap());
+    }
+
+    #[test]
+    fn test_persistence_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "replay_store_{}.json",
+            hex::encode(rand::random::<[u8; 8]>())
+        ));
+        let mut store = PersistentReplayStore::open(&path, 16, 100).unwrap();
+        assert!(store
+            .insert_if_absent("replayed", vec![7], 60, 100)
+            .unwrap());
+        store.put("short", vec![8], 10, 100).unwrap();
+        store.put("removed", vec![9], 60, 100).unwrap();
+        store.remove("removed").unwrap();
+        drop(store);
+        // Each change appended a line, and a write torn by a crash is ignored
+        let mut log = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        log.write_all(br#"{"key":"torn""#).unwrap();
+        drop(log);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 5);
+
+        // After a restart the replay is still rejected, and expired entries are not loaded
+        let mut store = PersistentReplayStore::open(&path, 16, 120).unwrap();
+        assert_eq!(store.len(), 1);
+        assert!(!store.insert_if_absent("replayed", vec![], 60, 120).unwrap());
+        assert_eq!(store.get("replayed", 120).unwrap(), Some(vec![7]));
+        assert_eq!(store.get("removed", 120).unwrap(), None);
+        assert_eq!(store.get("torn", 120).unwrap(), None);
+        // Opening compacted the log down to the live entry
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+        // The log is compacted again once it outgrows twice the capacity
+        for i in 0..40 {
+            store.put("rewritten", vec![i], 60, 120).unwrap();
+        }
+        assert!(fs::read_to_string(&path).unwrap().lines().count() <= 32);
+        drop(store);
+        let mut store = PersistentReplayStore::open(&path, 16, 120).unwrap();
+        assert_eq!(store.get("rewritten", 120).unwrap(), Some(vec![39]));
+
+        assert_eq!(store.get("replayed", 160).unwrap(), None);
+        drop(store);
+        assert_eq!(
+            PersistentReplayStore::open(&path, 16, 200).unwrap().len(),
+            0
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,246 @@
+use crate::constants::SIGNATURE_CACHE_CAPACITY;
+use crate::strip_0x_prefix;
+
+use anyhow::Result;
+use blsttc::Signature;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+type CacheKey = (String, [u8; 32]);
+
+/// Bounded cache of BLS signatures keyed by (pk_hex, signing_root). BLS signatures are
+/// deterministic, so an exact repeat of a request can be served without touching the secret key.
+/// The oldest entry is evicted once `capacity` is reached.
+pub struct SignatureCache {
+    capacity: usize,
+    entries: HashMap<CacheKey, Signature>,
+    order: VecDeque<CacheKey>,
+    // Bumped by every eviction, so a signature made while its key was evicted is not cached
+    evictions: u64,
+}
+
+impl SignatureCache {
+    pub fn new(capacity: usize) -> Self {
+        SignatureCache {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            evictions: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, pk_hex: &str, signing_root: &[u8; 32]) -> Option<Signature> {
+        let pk_hex: String = strip_0x_prefix!(pk_hex);
+        self.entries.get(&(pk_hex, *signing_root)).cloned()
+    }
+
+    pub fn insert(&mut self, pk_hex: &str, signing_root: &[u8; 32], sig: Signature) {
+        if self.capacity == 0 {
+            return;
+        }
+        let pk_hex: String = strip_0x_prefix!(pk_hex);
+        let key = (pk_hex, *signing_root);
+        if self.entries.insert(key.clone(), sig).is_some() {
+            return;
+        }
+        self.order.push_back(key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// Returns the cached signature if present, otherwise calls `sign` and caches the result.
+    pub fn get_or_sign<F>(
+        &mut self,
+        pk_hex: &str,
+        signing_root: &[u8; 32],
+        sign: F,
+    ) -> Result<Signature>
+    where
+        F: FnOnce() -> Result<Signature>,
+    {
+        if let Some(sig) = self.get(pk_hex, signing_root) {
+            return Ok(sig);
+        }
+        let sig = sign()?;
+        self.insert(pk_hex, signing_root, sig.clone());
+        Ok(sig)
+    }
+
+    /// Removes every cached signature belonging to `pk_hex`
+    pub fn evict_pk(&mut self, pk_hex: &str) {
+        let pk_hex: String = strip_0x_prefix!(pk_hex);
+        self.entries.retain(|(pk, _), _| pk != &pk_hex);
+        self.order.retain(|(pk, _)| pk != &pk_hex);
+        self.evictions += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.evictions += 1;
+    }
+}
+
+/// Returns the process-wide signature cache
+pub fn global_signature_cache() -> &'static Mutex<SignatureCache> {
+    static CACHE: OnceLock<Mutex<SignatureCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(SignatureCache::new(SIGNATURE_CACHE_CAPACITY)))
+}
+
+/// Returns the cached signature if present in the process-wide cache, otherwise calls `sign` and
+/// caches the result. The lock is only held for the lookup and the insert, not while signing, and
+/// nothing is cached if a key was evicted meanwhile.
+pub fn get_or_sign<F>(pk_hex: &str, signing_root: &[u8; 32], sign: F) -> Result<Signature>
+where
+    F: FnOnce() -> Result<Signature>,
+{
+    let evictions = {
+        let cache = lock_global_cache()?;
+        if let Some(sig) = cache.get(pk_hex, signing_root) {
+            return Ok(sig);
+        }
+        cache.evictions
+    };
+    let sig = sign()?;
+    let mut cache = lock_global_cache()?;
+    if cache.evictions == evictions {
+        cache.insert(pk_hex, signing_root, sig.clone());
+    }
+    Ok(sig)
+}
+
+fn lock_global_cache() -> Result<std::sync::MutexGuard<'static, SignatureCache>> {
+    global_signature_cache()
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Signature cache lock poisoned: {:?}", e))
+}
+
+/// Evicts all cached signatures for `pk_hex` from the process-wide cache
+pub fn evict_pk(pk_hex: &str) {
+    if let Ok(mut cache) = global_signature_cache().lock() {
+        cache.evict_pk(pk_hex);
+    }
+}
+
+/// Drops every cached signature from the process-wide cache
+pub fn clear() {
+    if let Ok(mut cache) = global_signature_cache().lock() {
+        cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blsttc::SecretKey;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_cache_hit_skips_signing() {
+        let sk = SecretKey::random();
+        let pk_hex = sk.public_key().to_hex();
+        let root = [1_u8; 32];
+        let calls = Cell::new(0);
+        let mut cache = SignatureCache::new(8);
+
+        let sign = || {
+            calls.set(calls.get() + 1);
+            Ok(sk.sign(root))
+        };
+
+        let sig1 = cache.get_or_sign(&pk_hex, &root, sign).unwrap();
+        let sig2 = cache
+            .get_or_sign(&pk_hex, &root, || {
+                calls.set(calls.get() + 1);
+                Ok(sk.sign(root))
+            })
+            .unwrap();
+        assert_eq!(sig1, sig2);
+        assert_eq!(calls.get(), 1);
+
+        // A different signing root is a miss
+        let other_root = [2_u8; 32];
+        cache
+            .get_or_sign(&pk_hex, &other_root, || {
+                calls.set(calls.get() + 1);
+                Ok(sk.sign(other_root))
+            })
+            .unwrap();
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn test_cache_is_bounded() {
+        let sk = SecretKey::random();
+        let pk_hex = sk.public_key().to_hex();
+        let mut cache = SignatureCache::new(2);
+        for i in 0..3_u8 {
+            let root = [i; 32];
+            cache.insert(&pk_hex, &root, sk.sign(root));
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&pk_hex, &[0_u8; 32]).is_none());
+        assert!(cache.get(&pk_hex, &[2_u8; 32]).is_some());
+    }
+
+    #[test]
+    fn test_signing_does_not_hold_the_cache() {
+        let sk = SecretKey::random();
+        let pk_hex = sk.public_key().to_hex();
+        let root = [4_u8; 32];
+
+        // Another signer can use the cache while this one signs, rather than deadlocking
+        let sig = get_or_sign(&pk_hex, &root, || {
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| {
+                        let other_root = [5_u8; 32];
+                        get_or_sign(&pk_hex, &other_root, || Ok(sk.sign(other_root))).unwrap()
+                    })
+                    .join()
+                    .unwrap()
+            });
+            Ok(sk.sign(root))
+        })
+        .unwrap();
+        assert_eq!(sig, sk.sign(root));
+
+        // A signature made while its key is evicted is not cached
+        let evicted_root = [6_u8; 32];
+        get_or_sign(&pk_hex, &evicted_root, || {
+            evict_pk(&pk_hex);
+            Ok(sk.sign(evicted_root))
+        })
+        .unwrap();
+        assert!(global_signature_cache()
+            .lock()
+            .unwrap()
+            .get(&pk_hex, &evicted_root)
+            .is_none());
+    }
+
+    #[test]
+    fn test_cache_evicted_on_delete() {
+        let sk = SecretKey::random();
+        let pk_hex = sk.public_key().to_hex();
+        let root = [3_u8; 32];
+        let mut cache = SignatureCache::new(8);
+        cache.insert(&format!("0x{pk_hex}"), &root, sk.sign(root));
+        assert!(cache.get(&pk_hex, &root).is_some());
+
+        cache.evict_pk(&pk_hex);
+        assert!(cache.get(&pk_hex, &root).is_none());
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,171 @@
+use crate::constants::{BLS_PUB_KEY_BYTES, BLS_SIG_BYTES, VERIFICATION_CACHE_CAPACITY};
+
+use anyhow::Result;
+use blsttc::{PublicKey, Signature};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+type CacheKey = ([u8; BLS_SIG_BYTES], [u8; BLS_PUB_KEY_BYTES], [u8; 32]);
+
+/// Bounded LRU cache of BLS verification results keyed by (sig_bytes, pk_bytes, sha256(msg)).
+/// Keying by content means a different signature, key or message can never hit a stale result,
+/// so re-broadcast aggregates only pay for the pairing once.
+pub struct VerificationCache {
+    capacity: usize,
+    entries: HashMap<CacheKey, bool>,
+    order: VecDeque<CacheKey>,
+}
+
+impl VerificationCache {
+    pub fn new(capacity: usize) -> Self {
+        VerificationCache {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn key(pk: &PublicKey, sig: &Signature, msg: &[u8]) -> CacheKey {
+        (sig.to_bytes(), pk.to_bytes(), Sha256::digest(msg).into())
+    }
+
+    // Moves `key` to the most recently used position
+    fn touch(&mut self, key: &CacheKey) {
+        if let Some(i) = self.order.iter().position(|k| k == key) {
+            self.order.remove(i);
+        }
+        self.order.push_back(*key);
+    }
+
+    fn insert(&mut self, key: CacheKey, valid: bool) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.insert(key, valid);
+        self.touch(&key);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    // Returns the cached result for `key`, marking it most recently used
+    fn lookup(&mut self, key: &CacheKey) -> Option<bool> {
+        let valid = self.entries.get(key).copied()?;
+        self.touch(key);
+        Some(valid)
+    }
+
+    /// Returns the cached result for (pk, sig, msg) if present, otherwise calls `verify` and caches it.
+    pub fn get_or_verify<F>(
+        &mut self,
+        pk: &PublicKey,
+        sig: &Signature,
+        msg: &[u8],
+        verify: F,
+    ) -> bool
+    where
+        F: FnOnce() -> bool,
+    {
+        let key = Self::key(pk, sig, msg);
+        if let Some(valid) = self.lookup(&key) {
+            return valid;
+        }
+        let valid = verify();
+        self.insert(key, valid);
+        valid
+    }
+
+    /// Verifies `sig` over `msg` under `pk`, serving repeats from the cache
+    pub fn verify(&mut self, pk: &PublicKey, sig: &Signature, msg: &[u8]) -> bool {
+        self.get_or_verify(pk, sig, msg, || pk.verify(sig, msg))
+    }
+}
+
+/// Returns the process-wide verification cache
+pub fn global_verification_cache() -> &'static Mutex<VerificationCache> {
+    static CACHE: OnceLock<Mutex<VerificationCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(VerificationCache::new(VERIFICATION_CACHE_CAPACITY)))
+}
+
+/// Returns the result cached in the process-wide cache for (pk, sig, msg), otherwise calls `verify`
+/// and caches its result. Errors are never cached. `msg` must bind everything else the result
+/// depends on, such as the DST or the other keys of an aggregate. The cache is not locked while
+/// `verify` runs, so concurrent verifications never wait on each other's pairings.
+pub fn verify_cached<F>(pk: &PublicKey, sig: &Signature, msg: &[u8], verify: F) -> Result<bool>
+where
+    F: FnOnce() -> Result<bool>,
+{
+    let key = VerificationCache::key(pk, sig, msg);
+    if let Some(valid) = global_verification_cache()
+        .lock()
+        .ok()
+        .and_then(|mut cache| cache.lookup(&key))
+    {
+        return Ok(valid);
+    }
+    let valid = verify()?;
+    if let Ok(mut cache) = global_verification_cache().lock() {
+        cache.insert(key, valid);
+    }
+    Ok(valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blsttc::SecretKey;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_repeat_verify_is_cached() {
+        let sk = SecretKey::random();
+        let msg = b"aggregate me";
+        let pk = sk.public_key();
+        let sig = sk.sign(msg);
+
+        let calls = Cell::new(0);
+        let verify = |m: &[u8]| {
+            calls.set(calls.get() + 1);
+            pk.verify(&sig, m)
+        };
+        let mut cache = VerificationCache::new(8);
+        assert!(cache.get_or_verify(&pk, &sig, msg, || verify(msg)));
+        assert!(cache.get_or_verify(&pk, &sig, msg, || verify(msg)));
+        assert_eq!(calls.get(), 1);
+
+        // A changed message is a miss and fails verification
+        let other = b"something else";
+        assert!(!cache.get_or_verify(&pk, &sig, other, || verify(other)));
+        assert_eq!(calls.get(), 2);
+        assert!(!cache.verify(&pk, &sig, other));
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let sk = SecretKey::random();
+        let pk = sk.public_key();
+        let mut cache = VerificationCache::new(2);
+        let msgs = [b"a", b"b", b"c"];
+        assert!(cache.verify(&pk, &sk.sign(msgs[0]), msgs[0]));
+        assert!(cache.verify(&pk, &sk.sign(msgs[1]), msgs[1]));
+
+        // Using "a" again makes "b" the least recently used
+        assert!(cache.get_or_verify(&pk, &sk.sign(msgs[0]), msgs[0], || unreachable!()));
+        assert!(cache.verify(&pk, &sk.sign(msgs[2]), msgs[2]));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get_or_verify(&pk, &sk.sign(msgs[0]), msgs[0], || unreachable!()));
+        assert!(!cache.get_or_verify(&pk, &sk.sign(msgs[1]), msgs[1], || false));
+    }
+}
@@ -14,7 +14,7 @@ pub async fn handler(
         Err(e) => {
             error!("eth_key_gen_with_blockhash_service() failed with: {}", e);
             (
-                axum::http::status::StatusCode::INTERNAL_SERVER_ERROR,
+                crate::enclave::shared::handlers::attestation_error_status(&e),
                 format!("eth_key_gen_with_blockhash_service failed: {:?}", e),
             )
                 .into_response()
@@ -42,6 +42,10 @@ pub fn attest_new_eth_key_with_blockhash(
 
     // Commit to the payload
     let proof = crate::io::remote_attestation::AttestationEvidence::new(&payload)?;
+    crate::io::key_management::mark_key_attested(
+        crate::io::key_management::KeyType::Eth,
+        &crate::crypto::eth_keys::eth_pk_to_hex(&pk),
+    )?;
     Ok((proof, pk))
 }
 
@@ -70,10 +74,10 @@ pub async fn verify_and_sign_custody_received(
     // verify enclave received a valid BLS secret key share
     let sk_share = verify_custody(&request.keygen_payload, &guardian_enclave_sk)?;
 
-    // save the keyshare
-    crate::io::key_management::write_bls_key(
-        &hex::encode(sk_share.public_key_share().to_bytes()),
-        &hex::encode(sk_share.to_bytes()),
+    // save the keyshare alongside the group public key
+    crate::crypto::bls_keys::save_bls_key_share(
+        &sk_share,
+        &request.keygen_payload.public_key_set()?,
     )?;
 
     // return guardian enclave signature
@@ -93,14 +97,10 @@ pub fn verify_remote_attestation_evidence(
     mrenclave: &String,
     mrsigner: &String,
 ) -> Result<()> {
-    let e = crate::io::remote_attestation::AttestationEvidence {
-        raw_report: keygen_payload.intel_report.clone(),
-        signed_report: keygen_payload.intel_sig.clone(),
-        signing_cert: keygen_payload.intel_x509.clone(),
-    };
+    let e = keygen_payload.evidence();
 
-    // Verify the evidence was signed from intel x509s
-    e.verify_intel_signing_certificate()?;
+    // Verify the evidence was signed by Intel, under the attestation type that produced it
+    e.verify()?;
 
     if &e.get_mrenclave()? != mrenclave {
         bail!("Invalid MRENCLAVE value");
@@ -291,6 +291,9 @@ mod tests {
             ],
             withdrawal_credentials: "0101010101010101010101010101010101010101010101010101010101010101".to_owned(),
             fork_version: crate::eth2::eth_types::Version::default(),
+            attestation_type: Default::default(),
+            dcap_quote: String::new(),
+            dcap_collateral: None,
         };
 
         let guardian_eth_sks = vec![
@@ -356,6 +359,7 @@ mod tests {
             raw_report: resp.intel_report,
             signed_report: resp.intel_sig,
             signing_cert: resp.intel_x509,
+            ..Default::default()
         };
 
         e.verify_intel_signing_certificate().unwrap();
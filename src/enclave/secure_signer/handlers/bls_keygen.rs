@@ -1,19 +1,35 @@
+use axum::extract::Query;
 use axum::response::IntoResponse;
 use axum::Json;
 use log::{error, info};
+use serde::Deserialize;
+
+use crate::io::key_management::KeyRole;
+
+/// Optional parameters of `POST /eth/v1/keygen/bls`, e.g. `?role=general`
+#[derive(Debug, Default, Deserialize)]
+pub struct BlsKeygenQuery {
+    /// Defaults to a validator key
+    pub role: Option<KeyRole>,
+}
 
 /// Generates, saves, and performs remote attestation on a new ETH key. Returns a `KeyGenResponse` on success.
-pub async fn handler() -> axum::response::Response {
+pub async fn handler(Query(query): Query<BlsKeygenQuery>) -> axum::response::Response {
     info!("eth_bls_gen_service()");
-    match crate::enclave::secure_signer::attest_new_bls_key() {
+    let role = query.role.unwrap_or(KeyRole::Validator);
+    match crate::enclave::secure_signer::attest_new_bls_key(role) {
         Ok((evidence, eth_pk)) => {
+            crate::io::webhook::emit(crate::io::webhook::AuditEvent::keygen(
+                crate::io::key_management::KeyType::Bls,
+                &eth_pk.to_hex(),
+            ));
             let resp = crate::enclave::types::KeyGenResponse::from_bls_key(eth_pk, evidence);
             (axum::http::status::StatusCode::CREATED, Json(resp)).into_response()
         }
         Err(e) => {
             error!("bls_key_gen_service() failed with: {}", e);
             (
-                axum::http::status::StatusCode::INTERNAL_SERVER_ERROR,
+                crate::enclave::shared::handlers::attestation_error_status(&e),
                 format!("bls_key_gen_service failed: {:?}", e),
             )
                 .into_response()
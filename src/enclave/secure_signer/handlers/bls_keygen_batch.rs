@@ -0,0 +1,225 @@
+use anyhow::Result;
+use axum::extract::Query;
+use axum::response::IntoResponse;
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::enclave::types::{BatchKeygenProgress, KeyGenResponse};
+use crate::io::key_management::KeyRole;
+
+/// Parameters of `POST /eth/v1/keygen/bls/batch`, e.g. `?count=1000&role=validator`
+#[derive(Debug, Deserialize)]
+pub struct BatchKeygenQuery {
+    pub count: usize,
+    /// Defaults to validator keys
+    pub role: Option<KeyRole>,
+}
+
+/// Generates `count` attested BLS keys, calling `emit` once each is saved and attested. `emit`
+/// returns whether the key was reported, and a key that was not is deleted before the batch
+/// stops, as is the batch once `cancelled` returns true before the next key. So every key kept
+/// was reported. Returns the keys kept.
+pub fn generate_batch(
+    count: usize,
+    role: KeyRole,
+    mut emit: impl FnMut(BatchKeygenProgress) -> bool,
+    cancelled: impl Fn() -> bool,
+) -> Result<Vec<blsttc::PublicKey>> {
+    let mut keys = Vec::with_capacity(count);
+    for generated in 1..=count {
+        if cancelled() {
+            info!(
+                "bls_keygen_batch() cancelled after {} of {count} keys",
+                keys.len()
+            );
+            break;
+        }
+        let (evidence, pk) = crate::enclave::secure_signer::attest_new_bls_key(role)?;
+        let progress = BatchKeygenProgress {
+            generated,
+            total: count,
+            key: KeyGenResponse::from_bls_key(pk, evidence),
+        };
+        if !emit(progress) {
+            // Nobody learned of this key, so nobody could ever use or delete it
+            crate::io::key_management::delete_bls_key_with_force(&pk.to_hex(), true)?;
+            info!(
+                "bls_keygen_batch() cancelled after {} of {count} keys",
+                keys.len()
+            );
+            break;
+        }
+        keys.push(pk);
+        crate::io::webhook::emit(crate::io::webhook::AuditEvent::keygen(
+            crate::io::key_management::KeyType::Bls,
+            &pk.to_hex(),
+        ));
+    }
+    Ok(keys)
+}
+
+/// Generates a batch of attested BLS keys, streaming one `BatchKeygenProgress` line per key. If
+/// the client disconnects the batch stops cleanly, keeping the keys streamed so far.
+pub async fn handler(Query(query): Query<BatchKeygenQuery>) -> axum::response::Response {
+    info!("bls_keygen_batch()");
+    if query.count == 0 || query.count > crate::constants::MAX_BATCH_KEYGEN {
+        return (
+            axum::http::status::StatusCode::BAD_REQUEST,
+            format!(
+                "count must be between 1 and {}",
+                crate::constants::MAX_BATCH_KEYGEN
+            ),
+        )
+            .into_response();
+    }
+    let role = query.role.unwrap_or(KeyRole::Validator);
+
+    // Keys are generated on a blocking task that waits for each line to be streamed before the
+    // next key, and deletes a key whose line could not be streamed
+    let (progress_tx, mut progress_rx) = tokio::sync::mpsc::channel(1);
+    tokio::task::spawn_blocking(move || {
+        let res = generate_batch(
+            query.count,
+            role,
+            |progress| {
+                let (streamed_tx, streamed_rx) = tokio::sync::oneshot::channel();
+                progress_tx
+                    .blocking_send(Ok((progress, streamed_tx)))
+                    .is_ok()
+                    && streamed_rx.blocking_recv().unwrap_or(false)
+            },
+            || progress_tx.is_closed(),
+        );
+        if let Err(e) = res {
+            error!("bls_keygen_batch() failed with: {:?}", e);
+            let _ = progress_tx.blocking_send(Err(e));
+        }
+    });
+
+    let (mut tx, body) = axum::body::Body::channel();
+    tokio::spawn(async move {
+        while let Some(progress) = progress_rx.recv().await {
+            let (progress, streamed_tx) = match progress {
+                Ok(progress) => progress,
+                Err(_) => {
+                    // Truncate the stream so the client sees the batch did not complete
+                    tx.abort();
+                    return;
+                }
+            };
+            let mut line = match serde_json::to_vec(&progress) {
+                Ok(line) => line,
+                Err(_) => {
+                    let _ = streamed_tx.send(false);
+                    tx.abort();
+                    return;
+                }
+            };
+            line.push(b'\n');
+            let streamed = tx.send_data(line.into()).await.is_ok();
+            let _ = streamed_tx.send(streamed);
+            if !streamed {
+                // The client went away, dropping the receiver stops the batch
+                return;
+            }
+        }
+    });
+
+    (
+        axum::http::status::StatusCode::CREATED,
+        [(axum::http::header::CONTENT_TYPE, "application/x-ndjson")],
+        axum::body::boxed(body),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delete_keys(keys: &[blsttc::PublicKey]) {
+        for pk in keys {
+            crate::io::key_management::delete_bls_key(&pk.to_hex()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_cancelled_batch_keeps_reported_keys() {
+        let mut reported = Vec::new();
+        let keys = generate_batch(
+            10,
+            KeyRole::Validator,
+            |progress| {
+                reported.push(progress);
+                reported.len() < 3
+            },
+            || false,
+        )
+        .unwrap();
+
+        // The third key could not be reported, so it was deleted
+        assert_eq!(keys.len(), 2);
+        assert_eq!(reported.len(), 3);
+        let unreported: String = crate::strip_0x_prefix!(reported[2].key.pk_hex);
+        assert!(!crate::io::key_management::bls_key_exists(&unreported));
+        for (i, (pk, progress)) in keys.iter().zip(reported.iter()).enumerate() {
+            assert_eq!(progress.generated, i + 1);
+            assert_eq!(progress.total, 10);
+            assert_eq!(progress.key.pk_hex, format!("0x{}", pk.to_hex()));
+
+            // Every generated key is saved, attested and slashing protected
+            let metadata = crate::io::key_management::read_bls_key_metadata(&pk.to_hex())
+                .unwrap()
+                .unwrap();
+            assert!(metadata.attested);
+            crate::eth2::slash_protection::SlashingProtectionData::read(&pk.to_hex()).unwrap();
+        }
+        delete_keys(&keys);
+
+        // Nothing is generated once cancelled
+        let keys = generate_batch(10, KeyRole::Validator, |_| true, || true).unwrap();
+        assert!(keys.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_keygen_streams_progress() {
+        let app = axum::Router::new()
+            .route("/eth/v1/keygen/bls/batch", axum::routing::post(handler))
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+
+        let resp = server.post("/eth/v1/keygen/bls/batch?count=0").await;
+        assert_eq!(resp.status_code(), 400);
+
+        let resp = server
+            .post("/eth/v1/keygen/bls/batch?count=3&role=general")
+            .await;
+        assert_eq!(resp.status_code(), 201);
+        assert_eq!(
+            resp.header("content-type").to_str().unwrap(),
+            "application/x-ndjson"
+        );
+        let progress: Vec<BatchKeygenProgress> = resp
+            .text()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            progress.iter().map(|p| p.generated).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert!(progress.iter().all(|p| p.total == 3));
+
+        let keys: Vec<blsttc::PublicKey> = progress
+            .iter()
+            .map(|p| crate::crypto::bls_keys::bls_pk_from_hex(&p.key.pk_hex).unwrap())
+            .collect();
+        for pk in keys.iter() {
+            assert_eq!(
+                crate::io::key_management::read_bls_key_role(&pk.to_hex()).unwrap(),
+                Some(KeyRole::General)
+            );
+        }
+        delete_keys(&keys);
+    }
+}
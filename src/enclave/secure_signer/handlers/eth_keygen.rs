@@ -7,13 +7,17 @@ pub async fn handler() -> axum::response::Response {
     info!("eth_key_gen_service()");
     match crate::enclave::secure_signer::attest_new_eth_key() {
         Ok((evidence, eth_pk)) => {
+            crate::io::webhook::emit(crate::io::webhook::AuditEvent::keygen(
+                crate::io::key_management::KeyType::Eth,
+                &crate::crypto::eth_keys::eth_pk_to_hex(&eth_pk),
+            ));
             let resp = crate::enclave::types::KeyGenResponse::from_eth_key(eth_pk, evidence);
             (axum::http::status::StatusCode::CREATED, Json(resp)).into_response()
         }
         Err(e) => {
             error!("eth_key_gen_service() failed with: {}", e);
             (
-                axum::http::status::StatusCode::INTERNAL_SERVER_ERROR,
+                crate::enclave::shared::handlers::attestation_error_status(&e),
                 format!("eth_key_gen_service failed: {:?}", e),
             )
                 .into_response()
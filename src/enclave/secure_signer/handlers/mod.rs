@@ -1,3 +1,5 @@
 pub mod bls_keygen;
+pub mod bls_keygen_batch;
 pub mod eth_keygen;
+pub mod provisioning;
 pub mod validator_deposit;
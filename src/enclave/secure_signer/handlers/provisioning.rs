@@ -0,0 +1,91 @@
+use axum::response::IntoResponse;
+use axum::Json;
+use log::{error, info};
+
+use crate::enclave::types::{BlsKeyProvisionRequest, BlsKeyProvisionResponse};
+
+/// Generates and attests a new provisioning key, see `add_provisioning_key`. Returns a
+/// `KeyGenResponse` on success.
+pub async fn keygen_handler() -> axum::response::Response {
+    info!("provisioning_key_gen_service()");
+    match crate::enclave::secure_signer::add_provisioning_key() {
+        Ok((evidence, eth_pk)) => {
+            crate::io::webhook::emit(crate::io::webhook::AuditEvent::keygen(
+                crate::io::key_management::KeyType::Eth,
+                &crate::crypto::eth_keys::eth_pk_to_hex(&eth_pk),
+            ));
+            let resp = crate::enclave::types::KeyGenResponse::from_eth_key(eth_pk, evidence);
+            (axum::http::status::StatusCode::CREATED, Json(resp)).into_response()
+        }
+        Err(e) => {
+            error!("provisioning_key_gen_service() failed with: {}", e);
+            (
+                crate::enclave::shared::handlers::attestation_error_status(&e),
+                format!("provisioning_key_gen_service failed: {:?}", e),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Lists the pks of the provisioning key ring
+pub async fn list_handler() -> axum::response::Response {
+    info!("list_provisioning_keys()");
+    match crate::enclave::secure_signer::list_provisioning_keys() {
+        Ok(keys) => {
+            let resp = crate::enclave::types::ListKeysResponse::new(keys);
+            (axum::http::status::StatusCode::OK, Json(resp)).into_response()
+        }
+        Err(e) => {
+            error!("list_provisioning_keys() failed with: {:?}", e);
+            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Decrypts and saves a BLS secret key encrypted to a provisioning key, see `bls_key_provision`.
+/// Returns a `BlsKeyProvisionResponse` on success.
+pub async fn provision_handler(
+    Json(req): Json<BlsKeyProvisionRequest>,
+) -> axum::response::Response {
+    info!("bls_key_provision()");
+    let encrypted_sk: String = crate::strip_0x_prefix!(req.encrypted_sk);
+    let encrypted_sk = match hex::decode(encrypted_sk) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Bad encrypted_sk, {:?}", e),
+            )
+                .into_response()
+        }
+    };
+    let provisioned = tokio::task::spawn_blocking(move || {
+        crate::enclave::secure_signer::bls_key_provision(
+            req.provisioning_key_id.as_deref(),
+            &encrypted_sk,
+        )
+    })
+    .await;
+    match provisioned {
+        Ok(Ok(pk)) => {
+            crate::io::webhook::emit(crate::io::webhook::AuditEvent::import(&pk.to_hex()));
+            let resp = BlsKeyProvisionResponse {
+                pubkey: format!("0x{}", pk.to_hex()),
+            };
+            (axum::http::status::StatusCode::CREATED, Json(resp)).into_response()
+        }
+        Ok(Err(e)) => {
+            error!("bls_key_provision() failed with: {:?}", e);
+            (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("bls_key_provision failed: {:?}", e),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            error!("bls_key_provision() panicked: {:?}", e);
+            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
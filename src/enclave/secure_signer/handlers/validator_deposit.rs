@@ -27,6 +27,16 @@ pub async fn handler(
             .into_response();
     }
 
+    if let Err(e) = crate::eth2::eth_signing::validate_withdrawal_credentials(
+        &req.deposit.withdrawal_credentials,
+    ) {
+        return (
+            axum::http::status::StatusCode::BAD_REQUEST,
+            format!("Bad withdrawal_credentials, {:?}", e),
+        )
+            .into_response();
+    }
+
     info!("Deposit request for validator pubkey: {bls_pk_hex}");
     info!("Request:\n{:#?}", req);
 
@@ -1,5 +1,7 @@
 pub mod handlers;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use tree_hash::TreeHash;
+use zeroize::Zeroize;
 
 fn attest_new_eth_key() -> Result<(
     crate::io::remote_attestation::AttestationEvidence,
@@ -11,22 +13,710 @@ fn attest_new_eth_key() -> Result<(
     // Commit to the payload
     let proof =
         crate::io::remote_attestation::AttestationEvidence::new(&pk.serialize_compressed())?;
+    crate::io::key_management::mark_key_attested(
+        crate::io::key_management::KeyType::Eth,
+        &crate::crypto::eth_keys::eth_pk_to_hex(&pk),
+    )?;
     Ok((proof, pk))
 }
 
-fn attest_new_bls_key() -> Result<(
+/// Produces fresh attestation evidence for the stored ETH key `addr`, given as its hex encoded
+/// compressed public key or its ETH address, using `mode` only. The key itself is unchanged.
+pub fn reattest_eth_key(
+    addr: &str,
+    mode: crate::io::remote_attestation::AttestationType,
+) -> Result<crate::enclave::types::KeyGenResponse> {
+    reattest_eth_key_with(addr, |report_data| {
+        crate::io::remote_attestation::AttestationEvidence::new_with_preference(
+            report_data,
+            &crate::io::remote_attestation::IasClientConfig::from_env()?,
+            &[mode],
+        )
+    })
+}
+
+fn reattest_eth_key_with<F>(addr: &str, attest: F) -> Result<crate::enclave::types::KeyGenResponse>
+where
+    F: FnOnce(&[u8]) -> Result<crate::io::remote_attestation::AttestationEvidence>,
+{
+    let pk_hex = stored_eth_pk_hex(addr)?;
+    let sk = crate::crypto::eth_keys::fetch_eth_key(&pk_hex)
+        .with_context(|| format!("Failed to read ETH key {pk_hex}"))?;
+    // Derive the pubkey from the stored secret so the evidence binds the key actually held
+    let pk = ecies::PublicKey::from_secret_key(&sk);
+    if crate::crypto::eth_keys::eth_pk_to_hex(&pk) != pk_hex {
+        bail!("Stored ETH key {pk_hex} does not match its public key")
+    }
+
+    let report_data = crate::io::remote_attestation::ReportDataLayout::default()
+        .build(&pk.serialize_compressed(), None)?;
+    let evidence = attest(&report_data)?;
+    crate::io::key_management::mark_key_attested(crate::io::key_management::KeyType::Eth, &pk_hex)?;
+    Ok(crate::enclave::types::KeyGenResponse::from_eth_key(
+        pk, evidence,
+    ))
+}
+
+// Resolves a compressed public key or an ETH address to the id the key is stored under
+fn stored_eth_pk_hex(addr: &str) -> Result<String> {
+    let addr: String = crate::strip_0x_prefix!(addr);
+    let addr = addr.to_lowercase();
+    if addr.len() != 40 {
+        return Ok(addr);
+    }
+    for entry in
+        crate::io::key_management::list_keys(Some(crate::io::key_management::KeyType::Eth), None)?
+    {
+        let pk = crate::crypto::eth_keys::eth_pk_from_hex(&entry.pk_hex)?;
+        let entry_addr = crate::crypto::eth_keys::pk_to_eth_addr(&pk)?.to_lowercase();
+        if entry_addr == format!("0x{addr}") {
+            return Ok(entry.pk_hex);
+        }
+    }
+    bail!("No stored ETH key with address 0x{addr}")
+}
+
+/// Generates and attests a fresh ECIES provisioning key, adding it to the ring of keys BLS secret
+/// keys may be encrypted to for `bls_key_provision`. Each key has its own attestation evidence, so
+/// every client can check the key it provisions to lives in the enclave.
+pub fn add_provisioning_key() -> Result<(
+    crate::io::remote_attestation::AttestationEvidence,
+    ecies::PublicKey,
+)> {
+    let (proof, pk) = attest_new_eth_key()?;
+    let pk_hex = crate::crypto::eth_keys::eth_pk_to_hex(&pk);
+    let metadata = crate::io::key_management::read_eth_key_metadata(&pk_hex)?.unwrap_or_default();
+    crate::io::key_management::write_eth_key_metadata(
+        &pk_hex,
+        &crate::io::key_management::KeyMetadata {
+            provisioning: true,
+            ..metadata
+        },
+    )?;
+    Ok((proof, pk))
+}
+
+/// Returns the hex encoded public keys, the ids, of the provisioning key ring
+pub fn list_provisioning_keys() -> Result<Vec<String>> {
+    let mut ids = Vec::new();
+    for entry in
+        crate::io::key_management::list_keys(Some(crate::io::key_management::KeyType::Eth), None)?
+    {
+        if crate::io::key_management::read_eth_key_metadata(&entry.pk_hex)?
+            .is_some_and(|m| m.provisioning)
+        {
+            ids.push(entry.pk_hex);
+        }
+    }
+    Ok(ids)
+}
+
+/// Decrypts `encrypted_sk`, a BLS secret key envelope encrypted to the provisioning key
+/// `provisioning_key_id` under the configured `ecies_scheme`, and saves it as a validator key. The
+/// id may be omitted while the ring holds a single key. Returns the BLS public key.
+pub fn bls_key_provision(
+    provisioning_key_id: Option<&str>,
+    encrypted_sk: &[u8],
+) -> Result<blsttc::PublicKey> {
+    let ring = list_provisioning_keys()?;
+    let id = match provisioning_key_id {
+        Some(id) => {
+            let id: String = crate::strip_0x_prefix!(id);
+            if !ring.contains(&id) {
+                bail!("Unknown provisioning key {id}")
+            }
+            id
+        }
+        None => match ring.as_slice() {
+            [id] => id.clone(),
+            [] => bail!("No provisioning keys exist"),
+            _ => bail!(
+                "{} provisioning keys exist, specify the one the key was encrypted to",
+                ring.len()
+            ),
+        },
+    };
+
+    let mut sk_bytes = crate::crypto::eth_keys::envelope_decrypt_from_saved_sk_with_scheme(
+        crate::crypto::eth_keys::ecies_scheme(),
+        &id,
+        encrypted_sk,
+    )
+    .with_context(|| format!("Failed to decrypt with provisioning key {id}"))?;
+    let pk = crate::crypto::bls_keys::import_bls_secret_bytes(&sk_bytes);
+    sk_bytes.zeroize();
+    let pk = pk?;
+
+    // Provisioned keys are validator keys, so they need a slashing protection database. Keep the
+    // records of a key provisioned again after being deleted.
+    crate::eth2::slash_protection::init_slashing_db_if_missing(&pk.to_hex())?;
+    Ok(pk)
+}
+
+fn attest_new_bls_key(
+    role: crate::io::key_management::KeyRole,
+) -> Result<(
     crate::io::remote_attestation::AttestationEvidence,
     blsttc::PublicKey,
 )> {
     // Generate a fresh BLS keypair (saving BLS private key)
     let sk = crate::crypto::bls_keys::new_bls_key(0);
     let pk = sk.public_keys().public_key();
-    crate::crypto::bls_keys::save_bls_key(&sk).with_context(|| "Failed to save BLS key")?;
+    crate::crypto::bls_keys::save_bls_key_with_role(&sk, role)
+        .with_context(|| "Failed to save BLS key")?;
 
-    // Create a new slashing protection database
-    crate::eth2::slash_protection::SlashingProtectionData::from_pk_hex(&pk.to_hex())?.write()?;
+    // Create a new slashing protection database, general keys never sign slashable messages
+    if role == crate::io::key_management::KeyRole::Validator {
+        crate::eth2::slash_protection::init_slashing_db_if_missing(&pk.to_hex())?;
+    }
 
     // Commit to the payload
     let proof = crate::io::remote_attestation::AttestationEvidence::new(&pk.to_bytes())?;
+    crate::io::key_management::mark_key_attested(
+        crate::io::key_management::KeyType::Bls,
+        &pk.to_hex(),
+    )?;
     Ok((proof, pk))
 }
+
+/// Assembles the public data an operator needs to take over the validator `pk_hex`: its deposit
+/// data for `withdrawal_credentials` under `fork_version` and fresh attestation evidence for its
+/// public key. The bundle is signed by the attested ETH key `signer`, given as its hex encoded
+/// compressed public key or its ETH address, and carries fresh attestation evidence for it.
+pub fn export_validator_bundle(
+    pk_hex: &str,
+    withdrawal_credentials: [u8; 32],
+    fork_version: crate::eth2::eth_types::Version,
+    signer: &str,
+) -> Result<crate::enclave::types::ValidatorBundle> {
+    export_validator_bundle_with(
+        pk_hex,
+        withdrawal_credentials,
+        fork_version,
+        signer,
+        crate::io::remote_attestation::AttestationEvidence::new,
+    )
+}
+
+fn export_validator_bundle_with<F>(
+    pk_hex: &str,
+    withdrawal_credentials: [u8; 32],
+    fork_version: crate::eth2::eth_types::Version,
+    signer: &str,
+    attest: F,
+) -> Result<crate::enclave::types::ValidatorBundle>
+where
+    F: Fn(&[u8]) -> Result<crate::io::remote_attestation::AttestationEvidence>,
+{
+    let pk_hex = crate::crypto::bls_keys::sanitize_bls_pk_hex(&pk_hex.to_string())?;
+    if !crate::io::key_management::bls_key_exists(&pk_hex) {
+        bail!("Unknown BLS public key {pk_hex}")
+    }
+    let signer_pk_hex = stored_eth_pk_hex(signer)?;
+    let attested = crate::io::key_management::read_eth_key_metadata(&signer_pk_hex)?
+        .is_some_and(|m| m.attested);
+    if !attested {
+        bail!("ETH key {signer_pk_hex} was never attested and cannot sign bundles")
+    }
+    let signer_sk = crate::crypto::eth_keys::fetch_eth_key(&signer_pk_hex)
+        .with_context(|| format!("Failed to read ETH key {signer_pk_hex}"))?;
+    // Derive the pubkey from the stored secret so the evidence binds the key actually signing
+    let signer_pk = ecies::PublicKey::from_secret_key(&signer_sk);
+    if crate::crypto::eth_keys::eth_pk_to_hex(&signer_pk) != signer_pk_hex {
+        bail!("Stored ETH key {signer_pk_hex} does not match its public key")
+    }
+
+    let pk = crate::crypto::bls_keys::bls_pk_from_hex(&pk_hex)?;
+    let deposit_message = crate::eth2::eth_types::DepositMessage {
+        pubkey: pk.to_bytes().to_vec().into(),
+        withdrawal_credentials,
+        amount: crate::constants::FULL_DEPOSIT_AMOUNT,
+    };
+    let deposit_data = crate::eth2::eth_signing::get_deposit_signature(
+        pk_hex.clone(),
+        deposit_message,
+        fork_version,
+    )?;
+    let evidence = attest(&pk.to_bytes())?;
+    let signer_evidence = attest(&signer_pk.serialize_compressed())?;
+
+    let bundle = crate::enclave::types::ValidatorBundleBody {
+        pubkey: pk_hex,
+        withdrawal_credentials: hex::encode(withdrawal_credentials),
+        fork_version,
+        deposit_data,
+        evidence,
+    };
+    let (sig, _) = crate::crypto::eth_keys::sign_message(&bundle.signing_bytes()?, &signer_sk)?;
+    Ok(crate::enclave::types::ValidatorBundle {
+        bundle,
+        signer_pubkey: signer_pk_hex,
+        signer_evidence,
+        signature: hex::encode(sig.serialize()),
+    })
+}
+
+/// The output of `ephemeral_sign`. The secret key behind `pubkey` no longer exists.
+pub struct EphemeralSignature {
+    pub signature: blsttc::Signature,
+    pub pubkey: blsttc::PublicKey,
+    pub signing_root: crate::eth2::eth_types::Root,
+    /// Commits to sha256(pubkey) || signing_root under `ReportDataLayout::Sha256PubkeyWithNonce`
+    pub evidence: crate::io::remote_attestation::AttestationEvidence,
+}
+
+/// Generates a fresh BLS key that signs the signing root of (`msg_root`, `domain`) once and is then
+/// zeroized. The key is never written to storage, and the attestation binds its pubkey to the root.
+pub fn ephemeral_sign(
+    msg_root: crate::eth2::eth_types::Root,
+    domain: crate::eth2::eth_types::Domain,
+) -> Result<EphemeralSignature> {
+    let signing_root: crate::eth2::eth_types::Root = crate::eth2::eth_types::SigningData {
+        object_root: msg_root,
+        domain,
+    }
+    .tree_hash_root()
+    .to_fixed_bytes();
+
+    // Derive the key into buffers we control so every copy can be zeroized
+    let mut ikm = [0_u8; crate::constants::BLS_MIN_IKM_BYTES];
+    rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut ikm);
+    let sk_bytes = crate::crypto::bls_keys::hkdf_mod_r(&ikm, b"");
+    ikm.zeroize();
+    let mut sk_bytes = sk_bytes?;
+    let sk = blsttc::SecretKey::from_bytes(sk_bytes);
+    sk_bytes.zeroize();
+    let sk = match sk {
+        Ok(sk) => sk,
+        Err(e) => bail!("Failed to create ephemeral bls sk: {:?}", e),
+    };
+
+    let pubkey = sk.public_key();
+    let signature = sk.sign(signing_root);
+    drop(sk);
+
+    let evidence = crate::io::remote_attestation::AttestationEvidence::new_with_layout(
+        &pubkey.to_bytes(),
+        crate::io::remote_attestation::ReportDataLayout::Sha256PubkeyWithNonce,
+        Some(&signing_root),
+    )?;
+
+    Ok(EphemeralSignature {
+        signature,
+        pubkey,
+        signing_root,
+        evidence,
+    })
+}
+
+/// The routes served by the Secure-Signer binary. Every route that signs, generates or imports
+/// keys, or changes signer state is refused while `app_state.verify_only` is set.
+pub fn router(app_state: crate::enclave::shared::handlers::AppState) -> axum::Router {
+    use crate::enclave::shared::handlers::refuse_if_verify_only;
+    axum::Router::new()
+        // Endpoint to check health
+        .route(
+            "/upcheck",
+            axum::routing::get(crate::enclave::shared::handlers::health::handler),
+        )
+        // Endpoint to check readiness (enough free disk space to persist keys and slashing data)
+        .route(
+            "/readyz",
+            axum::routing::get(crate::enclave::shared::handlers::readyz::handler),
+        )
+        // Endpoint to report the build information of this Secure-Signer
+        .route(
+            "/eth/v1/spec/build-info",
+            axum::routing::get(crate::enclave::shared::handlers::build_info::handler),
+        )
+        // Endpoint to report the signing types supported by this Secure-Signer
+        .route(
+            "/eth/v1/capabilities",
+            axum::routing::get(crate::enclave::shared::handlers::capabilities::handler),
+        )
+        // Endpoint to securely generate and save an ETH sk
+        .route(
+            "/eth/v1/keygen/secp256k1",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::secure_signer::handlers::eth_keygen::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to securely generate and save a BLS sk
+        .route(
+            "/eth/v1/keygen/bls",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::secure_signer::handlers::bls_keygen::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to generate a batch of BLS sks, streaming NDJSON progress as each is saved
+        .route(
+            "/eth/v1/keygen/bls/batch",
+            refuse_if_verify_only(
+                axum::routing::post(
+                    crate::enclave::secure_signer::handlers::bls_keygen_batch::handler,
+                ),
+                &app_state,
+            ),
+        )
+        // Endpoint to generate an attested ECIES key BLS sks may be provisioned to, and to list them
+        .route(
+            "/eth/v1/keygen/provisioning",
+            refuse_if_verify_only(
+                axum::routing::post(
+                    crate::enclave::secure_signer::handlers::provisioning::keygen_handler,
+                ),
+                &app_state,
+            )
+            .get(crate::enclave::secure_signer::handlers::provisioning::list_handler),
+        )
+        // Endpoint to import a BLS sk encrypted to a provisioning key
+        .route(
+            "/eth/v1/keystores/provision",
+            refuse_if_verify_only(
+                axum::routing::post(
+                    crate::enclave::secure_signer::handlers::provisioning::provision_handler,
+                ),
+                &app_state,
+            ),
+        )
+        // Endpoint to list the pks of all the generated ETH keys
+        .route(
+            "/eth/v1/keygen/secp256k1",
+            axum::routing::get(crate::enclave::shared::handlers::list_eth_keys::handler),
+        )
+        // Keymanager API endpoints to list, import, and delete saved bls keys
+        .route(
+            "/eth/v1/keystores",
+            axum::routing::get(crate::enclave::shared::handlers::keystores::list_handler).merge(
+                refuse_if_verify_only(
+                    axum::routing::post(
+                        crate::enclave::shared::handlers::keystores::import_handler,
+                    )
+                    .delete(crate::enclave::shared::handlers::keystores::delete_handler),
+                    &app_state,
+                ),
+            ),
+        )
+        // Endpoint to verify a BLS signature over a signing root
+        .route(
+            "/eth/v1/verify",
+            axum::routing::post(crate::enclave::shared::handlers::verify::handler),
+        )
+        // Endpoint to verify a BLS aggregate signature against its public keys and messages
+        .route(
+            "/eth/v1/verify/aggregate",
+            axum::routing::post(crate::enclave::shared::handlers::verify::aggregate_handler),
+        )
+        // Endpoint to list saved keys, optionally filtered by ?type=bls|eth and ?attested=true|false
+        .route(
+            "/eth/v1/keys",
+            axum::routing::get(crate::enclave::shared::handlers::list_keys::handler),
+        )
+        // Endpoint to compute the checksummed ETH address of a SECP256K1 public key
+        .route(
+            "/eth/v1/util/eth-address",
+            axum::routing::post(crate::enclave::shared::handlers::eth_address::handler),
+        )
+        // Endpoint to issue a short-lived single-use nonce for replay-resistant signing
+        .route(
+            "/eth/v1/nonce",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::nonce::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to sign a non-consensus commitment, consuming a nonce from /eth/v1/nonce
+        .route(
+            "/eth/v1/commitment/sign/:bls_pk_hex",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::commitment_sign::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to sign DepositData message for registering validator on beacon chain
+        .route(
+            "/api/v1/eth2/deposit",
+            refuse_if_verify_only(
+                axum::routing::post(
+                    crate::enclave::secure_signer::handlers::validator_deposit::handler,
+                ),
+                &app_state,
+            ),
+        )
+        // Endpoint to compact the slashing protection DB down to its high-water marks
+        .route(
+            "/eth/v1/slashing/compact",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::slashing_compact::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to read the slashing protection high-water marks of a BLS key
+        .route(
+            "/eth/v1/slashing/:bls_pk_hex",
+            axum::routing::get(crate::enclave::shared::handlers::slashing_watermarks::handler),
+        )
+        // Endpoint to map a validator index to a saved BLS key
+        .route(
+            "/eth/v1/validator_index",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::validator_index::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to request a signature using the BLS sk mapped to a validator index
+        .route(
+            "/api/v1/eth2/sign/index/:validator_index",
+            refuse_if_verify_only(
+                axum::routing::post(
+                    crate::enclave::shared::handlers::secure_sign_bls::by_index_handler,
+                ),
+                &app_state,
+            ),
+        )
+        // Web3Signer compatible endpoint to request a signature using BLS sk
+        .route(
+            "/api/v1/eth2/sign/:bls_pk_hex",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::web3signer_sign::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to sign a light client update's attested header as a sync committee member
+        .route(
+            "/eth/v1/light_client/sign/:bls_pk_hex",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::light_client_sign::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to request the partial signatures of several saved BLS key shares at once
+        .route(
+            "/eth/v1/threshold/sign",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::threshold_sign::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to request a signature over a raw SSZ container using BLS sk
+        .route(
+            "/api/v1/eth2/ssz/sign/:bls_pk_hex",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::secure_sign_ssz::handler),
+                &app_state,
+            ),
+        )
+        .with_state(app_state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ephemeral_sign_never_persists_key() {
+        let msg_root = [5_u8; 32];
+        let domain = crate::eth2::eth_signing::compute_domain([0xaa, 0, 0, 0], None, None);
+
+        let e = ephemeral_sign(msg_root, domain).unwrap();
+
+        // The signature verifies over the signing root of (msg_root, domain)
+        let exp_root = crate::eth2::eth_types::SigningData {
+            object_root: msg_root,
+            domain,
+        }
+        .tree_hash_root()
+        .to_fixed_bytes();
+        assert_eq!(e.signing_root, exp_root);
+        assert!(e.pubkey.verify(&e.signature, exp_root));
+
+        // The key was never saved
+        assert!(!crate::io::key_management::bls_key_exists(
+            &e.pubkey.to_hex()
+        ));
+        let keys_after = crate::io::key_management::list_bls_keys().unwrap_or_default();
+        assert!(!keys_after.contains(&e.pubkey.to_hex()));
+
+        // A fresh key is used for every call
+        let e2 = ephemeral_sign(msg_root, domain).unwrap();
+        assert_ne!(e.pubkey, e2.pubkey);
+    }
+
+    // Stands in for remote attestation outside SGX, quoting `report_data` as DCAP evidence would
+    fn mock_dcap_evidence(
+        report_data: &[u8],
+    ) -> Result<crate::io::remote_attestation::AttestationEvidence> {
+        let mut quote = vec![0_u8; crate::io::dcap::QUOTE_SIGNED_BYTES + 4];
+        quote[48 + 320..48 + 320 + report_data.len()].copy_from_slice(report_data);
+        Ok(crate::io::remote_attestation::AttestationEvidence {
+            attestation_type: crate::io::remote_attestation::AttestationType::Dcap,
+            dcap_quote: openssl::base64::encode_block(&quote),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_reattest_eth_key_binds_stored_pubkey() {
+        let pk = crate::crypto::eth_keys::eth_key_gen().unwrap();
+        let pk_hex = crate::crypto::eth_keys::eth_pk_to_hex(&pk);
+        let exp_report_data = crate::io::remote_attestation::ReportDataLayout::default()
+            .build(&pk.serialize_compressed(), None)
+            .unwrap();
+
+        // The key may be named by its public key or its address
+        let addr = crate::crypto::eth_keys::pk_to_eth_addr(&pk).unwrap();
+        for id in [pk_hex.clone(), format!("0x{pk_hex}"), addr.to_lowercase()] {
+            let resp = reattest_eth_key_with(&id, mock_dcap_evidence).unwrap();
+            assert_eq!(resp.evidence.get_report_data().unwrap(), exp_report_data);
+            assert_eq!(resp.evidence.get_eth_pk().unwrap(), pk);
+            assert_eq!(
+                crate::crypto::eth_keys::eth_pk_from_hex_uncompressed(&resp.pk_hex).unwrap(),
+                pk
+            );
+        }
+        let metadata = crate::io::key_management::read_eth_key_metadata(&pk_hex)
+            .unwrap()
+            .unwrap();
+        assert!(metadata.attested);
+
+        // Unknown keys are not attested
+        let (_, unknown) = crate::crypto::eth_keys::new_eth_key().unwrap();
+        let unknown_addr = crate::crypto::eth_keys::pk_to_eth_addr(&unknown).unwrap();
+        assert!(reattest_eth_key_with(&unknown_addr, mock_dcap_evidence).is_err());
+        assert!(reattest_eth_key_with(
+            &crate::crypto::eth_keys::eth_pk_to_hex(&unknown),
+            mock_dcap_evidence
+        )
+        .is_err());
+
+        crate::io::key_management::delete_eth_key(&pk_hex).unwrap();
+    }
+
+    #[test]
+    fn test_export_validator_bundle() {
+        let (_, bls_pk) =
+            attest_new_bls_key(crate::io::key_management::KeyRole::Validator).unwrap();
+        let signer = crate::crypto::eth_keys::eth_key_gen().unwrap();
+        let signer_hex = crate::crypto::eth_keys::eth_pk_to_hex(&signer);
+        let mut withdrawal_credentials = [0_u8; 32];
+        withdrawal_credentials[0] = 0x01;
+        withdrawal_credentials[12..].copy_from_slice(&[0x42; 20]);
+        let fork_version = [0, 0, 0, 1];
+
+        // Only attested ETH keys sign bundles
+        assert!(export_validator_bundle_with(
+            &bls_pk.to_hex(),
+            withdrawal_credentials,
+            fork_version,
+            &signer_hex,
+            mock_dcap_evidence
+        )
+        .is_err());
+        crate::io::key_management::mark_key_attested(
+            crate::io::key_management::KeyType::Eth,
+            &signer_hex,
+        )
+        .unwrap();
+
+        let bundle = export_validator_bundle_with(
+            &format!("0x{}", bls_pk.to_hex()),
+            withdrawal_credentials,
+            fork_version,
+            &signer_hex,
+            mock_dcap_evidence,
+        )
+        .unwrap();
+        assert_eq!(bundle.bundle.pubkey, bls_pk.to_hex());
+        assert_eq!(bundle.signer_pubkey, signer_hex);
+        assert_eq!(bundle.signer_evidence.get_eth_pk().unwrap(), signer);
+        assert!(bundle.verify_signature_by(&signer).unwrap());
+        assert!(bundle.verify_deposit_data().unwrap());
+
+        // The embedded signer is only trusted through its evidence, which the mock cannot pass
+        assert!(bundle.verify_signature(&"00".repeat(32)).is_err());
+        let (_, other) = crate::crypto::eth_keys::new_eth_key().unwrap();
+        assert!(!bundle.verify_signature_by(&other).unwrap());
+
+        // The bundle survives a JSON round trip, and tampering breaks its signature
+        let json = serde_json::to_string(&bundle).unwrap();
+        let mut decoded: crate::enclave::types::ValidatorBundle =
+            serde_json::from_str(&json).unwrap();
+        assert!(decoded.verify_signature_by(&signer).unwrap());
+        assert_eq!(
+            decoded.bundle.signing_bytes().unwrap(),
+            bundle.bundle.signing_bytes().unwrap()
+        );
+        decoded.bundle.withdrawal_credentials = hex::encode([0_u8; 32]);
+        assert!(!decoded.verify_signature_by(&signer).unwrap());
+        assert!(!decoded.verify_deposit_data().unwrap());
+        let mut decoded: crate::enclave::types::ValidatorBundle =
+            serde_json::from_str(&json).unwrap();
+        decoded.bundle.evidence.dcap_quote.push('A');
+        assert!(!decoded.verify_signature_by(&signer).unwrap());
+
+        crate::io::key_management::delete_bls_key(&bls_pk.to_hex()).unwrap();
+        crate::io::key_management::delete_eth_key(&signer_hex).unwrap();
+    }
+
+    #[test]
+    fn test_provisioning_keys_are_isolated() {
+        let (_, pk_a) = add_provisioning_key().unwrap();
+        let (_, pk_b) = add_provisioning_key().unwrap();
+        let id_a = crate::crypto::eth_keys::eth_pk_to_hex(&pk_a);
+        let id_b = crate::crypto::eth_keys::eth_pk_to_hex(&pk_b);
+        let ring = list_provisioning_keys().unwrap();
+        assert!(ring.contains(&id_a) && ring.contains(&id_b));
+        for id in [&id_a, &id_b] {
+            let metadata = crate::io::key_management::read_eth_key_metadata(id)
+                .unwrap()
+                .unwrap();
+            assert!(metadata.attested);
+        }
+
+        // Ordinary ETH keys are not part of the ring
+        let other = crate::crypto::eth_keys::eth_key_gen().unwrap();
+        let other_id = crate::crypto::eth_keys::eth_pk_to_hex(&other);
+        assert!(!list_provisioning_keys().unwrap().contains(&other_id));
+
+        let sk_set = crate::crypto::bls_keys::new_bls_key(0);
+        let bls_pk = sk_set.public_keys().public_key();
+        let encrypted =
+            crate::crypto::eth_keys::envelope_encrypt(&pk_a, &sk_set.secret_key().to_bytes())
+                .unwrap();
+
+        // A provision encrypted to key A can't be decrypted by key B, nor by a key outside the ring
+        assert!(bls_key_provision(Some(&id_b), &encrypted).is_err());
+        assert!(bls_key_provision(Some(&other_id), &encrypted).is_err());
+        assert!(!crate::io::key_management::bls_key_exists(&bls_pk.to_hex()));
+        // With several keys in the ring the target must be given
+        assert!(bls_key_provision(None, &encrypted).is_err());
+
+        let pk = bls_key_provision(Some(&format!("0x{id_a}")), &encrypted).unwrap();
+        assert_eq!(pk, bls_pk);
+        assert!(crate::io::key_management::bls_key_exists(&pk.to_hex()));
+        let mut db =
+            crate::eth2::slash_protection::SlashingProtectionData::read(&pk.to_hex()).unwrap();
+        db.new_block(
+            crate::eth2::slash_protection::SignedBlockSlot {
+                slot: 100,
+                signing_root: None,
+            },
+            false,
+        )
+        .unwrap();
+        db.write().unwrap();
+
+        // Provisioning the key again after deleting it resumes from its kept records
+        crate::io::key_management::delete_bls_key(&pk.to_hex()).unwrap();
+        bls_key_provision(Some(&id_a), &encrypted).unwrap();
+        let db = crate::eth2::slash_protection::SlashingProtectionData::read(&pk.to_hex()).unwrap();
+        assert_eq!(db.get_latest_signed_block_slot(), 100);
+
+        crate::io::key_management::delete_bls_key(&pk.to_hex()).unwrap();
+        for id in [&id_a, &id_b, &other_id] {
+            crate::io::key_management::delete_eth_key(id).unwrap();
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use axum::{response::IntoResponse, Json};
+use log::info;
+
+/// Returns the crate version, BLS library, ciphersuite and enabled features of this build
+pub async fn handler() -> axum::response::Response {
+    info!("build_info()");
+    let resp = crate::enclave::types::BuildInfoResponse::current();
+    (axum::http::status::StatusCode::OK, Json(resp)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enclave::types::BuildInfoResponse;
+
+    #[tokio::test]
+    async fn test_build_info_reports_cipher_suite() {
+        let app = axum::Router::new()
+            .route(
+                "/eth/v1/spec/build-info",
+                axum::routing::get(super::handler),
+            )
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+
+        let resp = server.get("/eth/v1/spec/build-info").await;
+        assert_eq!(resp.status_code(), 200);
+
+        let info: BuildInfoResponse = serde_json::from_slice(resp.as_bytes()).unwrap();
+        assert_eq!(
+            info.cipher_suite,
+            "BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_"
+        );
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    }
+}
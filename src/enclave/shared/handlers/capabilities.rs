@@ -0,0 +1,53 @@
+use axum::{response::IntoResponse, Json};
+use log::info;
+
+/// Returns the signing types supported by this build and whether slashing protection is enabled
+pub async fn handler() -> axum::response::Response {
+    info!("capabilities()");
+    let resp = crate::enclave::types::CapabilitiesResponse::current();
+    (axum::http::status::StatusCode::OK, Json(resp)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enclave::types::CapabilitiesResponse;
+    use crate::eth2::eth_signing::BLSSignMsg;
+
+    // True if serde knows `signing_type` as a BLSSignMsg variant
+    fn is_compiled_in(signing_type: &str) -> bool {
+        let err = serde_json::from_value::<BLSSignMsg>(serde_json::json!({ "type": signing_type }))
+            .unwrap_err();
+        !err.to_string().contains("unknown variant")
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_reflect_signing_handlers() {
+        let app = axum::Router::new()
+            .route("/eth/v1/capabilities", axum::routing::get(super::handler))
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+
+        let resp = server.get("/eth/v1/capabilities").await;
+        assert_eq!(resp.status_code(), 200);
+        let caps: CapabilitiesResponse = serde_json::from_slice(resp.as_bytes()).unwrap();
+        assert_eq!(caps, CapabilitiesResponse::current());
+        assert!(caps.slashing_protection);
+
+        // Every advertised type is accepted by the signing route, in both cases
+        assert_eq!(caps.signing_types.len(), BLSSignMsg::SIGNING_TYPES.len());
+        for cap in caps.signing_types.iter() {
+            assert!(is_compiled_in(&cap.signing_type), "{}", cap.signing_type);
+            assert!(is_compiled_in(&cap.signing_type.to_lowercase()));
+        }
+        assert!(!is_compiled_in("NOT_A_SIGNING_TYPE"));
+
+        // Only blocks and attestations are slashing protected
+        let protected: Vec<&str> = caps
+            .signing_types
+            .iter()
+            .filter(|c| c.slashing_protection)
+            .map(|c| c.signing_type.as_str())
+            .collect();
+        assert_eq!(protected, vec!["BLOCK", "BLOCK_V2", "ATTESTATION"]);
+    }
+}
@@ -0,0 +1,125 @@
+use axum::{extract::Path, response::IntoResponse, Json};
+use log::{error, info};
+
+use crate::enclave::types::{CommitmentSignRequest, SignatureResponse};
+
+/// Signs a non-consensus commitment, see `eth_signing::sign_commitment`. The request must carry
+/// a nonce issued by `POST /eth/v1/nonce`, which is consumed before signing so a replayed request
+/// is refused with 403.
+pub async fn handler(
+    Path(bls_pk_hex): Path<String>,
+    Json(req): Json<CommitmentSignRequest>,
+) -> axum::response::Response {
+    info!("commitment_sign()");
+    let bls_pk_hex = match crate::crypto::bls_keys::sanitize_bls_pk_hex(&bls_pk_hex) {
+        Ok(pk) => pk,
+        Err(e) => {
+            error!("Bad BLS public key format: {bls_pk_hex}");
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Bad bls_pk_hex, {:?}", e),
+            )
+                .into_response();
+        }
+    };
+    if !crate::io::key_management::bls_key_exists(&bls_pk_hex) {
+        error!("Unknown BLS public key: {bls_pk_hex}");
+        let resp = crate::enclave::types::ErrorResponse::unknown_key(&bls_pk_hex);
+        return (axum::http::status::StatusCode::NOT_FOUND, Json(resp)).into_response();
+    }
+    let parsed = (|| -> anyhow::Result<(crate::eth2::eth_types::DomainType, Vec<u8>)> {
+        let domain_type: String = crate::strip_0x_prefix!(req.domain_type);
+        let domain_type = hex::decode(domain_type)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("domain_type must be 4 bytes"))?;
+        let data: String = crate::strip_0x_prefix!(req.data);
+        Ok((domain_type, hex::decode(data)?))
+    })();
+    let (domain_type, data) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            error!("Bad commitment sign request: {:?}", e);
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Bad commitment sign request, {:?}", e),
+            )
+                .into_response();
+        }
+    };
+
+    let signed = tokio::task::spawn_blocking(move || {
+        crate::crypto::nonce::consume(&req.nonce)?;
+        crate::eth2::eth_signing::sign_commitment(bls_pk_hex, domain_type, &data)
+    })
+    .await
+    .unwrap_or_else(|e| Err(anyhow::anyhow!("Signing task failed: {:?}", e)));
+    match signed {
+        Ok(sig) => (
+            axum::http::status::StatusCode::OK,
+            Json(SignatureResponse::new(&sig.to_vec())),
+        )
+            .into_response(),
+        Err(e) => {
+            error!("Signing operation failed: {:?}", e);
+            let status = if e
+                .chain()
+                .any(|c| c.is::<crate::crypto::nonce::NonceError>())
+            {
+                axum::http::status::StatusCode::FORBIDDEN
+            } else {
+                axum::http::status::StatusCode::BAD_REQUEST
+            };
+            (status, format!("Signing operation failed: {:?}", e)).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enclave::types::{CommitmentSignRequest, NonceResponse, SignatureResponse};
+
+    #[tokio::test]
+    async fn test_commitment_requires_fresh_nonce() {
+        let app = axum::Router::new()
+            .route(
+                "/eth/v1/nonce",
+                axum::routing::post(crate::enclave::shared::handlers::nonce::handler),
+            )
+            .route(
+                "/eth/v1/commitment/sign/:bls_pk_hex",
+                axum::routing::post(super::handler),
+            )
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+
+        // General keys sign commitments without the slashing-bypass flag
+        let sk_set = crate::crypto::bls_keys::new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        crate::crypto::bls_keys::save_bls_key_with_role(
+            &sk_set,
+            crate::io::key_management::KeyRole::General,
+        )
+        .unwrap();
+        let path = format!("/eth/v1/commitment/sign/0x{}", pk.to_hex());
+        let req = |nonce: &str| CommitmentSignRequest {
+            domain_type: "0xaa000000".to_string(),
+            data: format!("0x{}", hex::encode(b"some commitment")),
+            nonce: nonce.to_string(),
+        };
+
+        let resp = server.post("/eth/v1/nonce").await;
+        let nonce: NonceResponse = serde_json::from_slice(resp.as_bytes()).unwrap();
+        let resp = server.post(&path).json(&req(&nonce.nonce)).await;
+        assert_eq!(resp.status_code(), 200);
+        let sig: SignatureResponse = serde_json::from_slice(resp.as_bytes()).unwrap();
+        assert!(sig.signature.starts_with("0x"));
+
+        // A replayed or never issued nonce is refused
+        let resp = server.post(&path).json(&req(&nonce.nonce)).await;
+        assert_eq!(resp.status_code(), 403);
+        let resp = server.post(&path).json(&req(&"00".repeat(32))).await;
+        assert_eq!(resp.status_code(), 403);
+
+        crate::io::key_management::delete_bls_key(&pk.to_hex()).unwrap();
+    }
+}
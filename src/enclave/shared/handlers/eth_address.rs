@@ -0,0 +1,65 @@
+use axum::{response::IntoResponse, Json};
+use log::{error, info};
+
+use crate::enclave::types::{EthAddressRequest, EthAddressResponse};
+
+/// Computes the EIP-55 checksummed ETH address of an arbitrary SECP256K1 public key. Nothing is
+/// read from or written to storage.
+pub async fn handler(Json(req): Json<EthAddressRequest>) -> axum::response::Response {
+    info!("eth_address()");
+    let eth_address = match crate::crypto::eth_keys::eth_pk_from_any_hex(&req.pk_hex)
+        .and_then(|pk| crate::crypto::eth_keys::pk_to_eth_addr(&pk))
+    {
+        Ok(eth_address) => eth_address,
+        Err(e) => {
+            error!("Bad ETH public key: {}", req.pk_hex);
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Bad pk_hex, {:?}", e),
+            )
+                .into_response();
+        }
+    };
+
+    let resp = EthAddressResponse { eth_address };
+    (axum::http::status::StatusCode::OK, Json(resp)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enclave::types::{EthAddressRequest, EthAddressResponse};
+
+    #[tokio::test]
+    async fn test_eth_address_of_known_pubkey() {
+        let app = axum::Router::new()
+            .route(
+                "/eth/v1/util/eth-address",
+                axum::routing::post(super::handler),
+            )
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+
+        // Public key of the secret key 1, i.e. the secp256k1 generator point
+        let compressed = "0x0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let uncompressed = "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+            483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+        for pk_hex in [compressed, uncompressed] {
+            let req = EthAddressRequest {
+                pk_hex: pk_hex.to_string(),
+            };
+            let resp = server.post("/eth/v1/util/eth-address").json(&req).await;
+            assert_eq!(resp.status_code(), 200);
+            let resp: EthAddressResponse = serde_json::from_slice(resp.as_bytes()).unwrap();
+            assert_eq!(
+                resp.eth_address,
+                "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf"
+            );
+        }
+
+        let req = EthAddressRequest {
+            pk_hex: "0xdeadbeef".to_string(),
+        };
+        let resp = server.post("/eth/v1/util/eth-address").json(&req).await;
+        assert_eq!(resp.status_code(), 400);
+    }
+}
@@ -0,0 +1,237 @@
+use axum::{extract::Query, response::IntoResponse, Json};
+use log::{error, info};
+
+use crate::crypto::{bls_keys, keystore};
+use crate::enclave::types::{
+    DeleteKeystoreStatus, DeleteKeystoresRequest, DeleteKeystoresResponse, ImportKeystoreStatus,
+    ImportKeystoresRequest, ImportKeystoresResponse, KeystoreInfo, KeystoreStatus,
+    ListKeystoresResponse,
+};
+use crate::eth2::slash_protection;
+use crate::io::key_management;
+
+/// Keymanager API `GET /eth/v1/keystores`, listing every saved BLS key
+pub async fn list_handler() -> axum::response::Response {
+    info!("list_keystores()");
+    let listed = key_management::list_bls_keys().and_then(|keys| {
+        keys.iter()
+            .map(|pk_hex| keystore_info(pk_hex))
+            .collect::<anyhow::Result<Vec<KeystoreInfo>>>()
+    });
+    match listed {
+        Ok(data) => (
+            axum::http::status::StatusCode::OK,
+            Json(ListKeystoresResponse { data }),
+        )
+            .into_response(),
+        Err(e) => {
+            error!("list_keystores() failed with: {:?}", e);
+            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn keystore_info(pk_hex: &str) -> anyhow::Result<KeystoreInfo> {
+    let pk_hex: String = crate::strip_0x_prefix!(pk_hex);
+    let pubkey = format!("0x{pk_hex}");
+    Ok(KeystoreInfo {
+        validating_pubkey: pubkey.clone(),
+        derivation_path: bls_keys::get_derivation_path(&pk_hex)?,
+        readonly: false,
+        pubkey,
+    })
+}
+
+/// Keymanager API `POST /eth/v1/keystores`, importing EIP-2335 keystores with their slashing
+/// protection. The interchange file is imported first, and if it fails no keystore is imported.
+pub async fn import_handler(Json(req): Json<ImportKeystoresRequest>) -> axum::response::Response {
+    info!("import_keystores()");
+    if req.keystores.len() != req.passwords.len() {
+        return (
+            axum::http::status::StatusCode::BAD_REQUEST,
+            format!(
+                "Got {} keystores but {} passwords",
+                req.keystores.len(),
+                req.passwords.len()
+            ),
+        )
+            .into_response();
+    }
+    match tokio::task::spawn_blocking(move || import_keystores(&req)).await {
+        Ok(data) => (
+            axum::http::status::StatusCode::OK,
+            Json(ImportKeystoresResponse { data }),
+        )
+            .into_response(),
+        Err(e) => {
+            error!("import_keystores() panicked: {:?}", e);
+            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn import_keystores(req: &ImportKeystoresRequest) -> Vec<KeystoreStatus<ImportKeystoreStatus>> {
+    if let Some(interchange) = req.slashing_protection.as_ref() {
+        if let Err(e) = slash_protection::import_interchange(interchange) {
+            error!("Failed to import slashing protection: {:?}", e);
+            let message = format!("Failed to import slashing protection, {:?}", e);
+            return req
+                .keystores
+                .iter()
+                .map(|_| KeystoreStatus {
+                    status: ImportKeystoreStatus::Error,
+                    message: message.clone(),
+                })
+                .collect();
+        }
+    }
+    req.keystores
+        .iter()
+        .zip(req.passwords.iter())
+        .map(|(keystore_json, password)| import_keystore(keystore_json, password))
+        .collect()
+}
+
+fn import_keystore(keystore_json: &str, password: &str) -> KeystoreStatus<ImportKeystoreStatus> {
+    let status = |status, message| KeystoreStatus { status, message };
+    match keystore::eip2335_keystore_pubkey(keystore_json) {
+        Ok(Some(pk_hex)) if key_management::bls_key_exists(&pk_hex) => {
+            return status(ImportKeystoreStatus::Duplicate, String::new())
+        }
+        Ok(_) => {}
+        Err(e) => return status(ImportKeystoreStatus::Error, format!("{:?}", e)),
+    }
+    // Imported keys are validator keys, so they need a slashing protection DB to sign
+    let imported = keystore::import_eip2335_keystore(keystore_json, password).and_then(|pk| {
+        slash_protection::init_slashing_db_if_missing(&pk.to_hex())?;
+        Ok(pk.to_hex())
+    });
+    match imported {
+        Ok(pk_hex) => {
+            crate::io::webhook::emit(crate::io::webhook::AuditEvent::import(&pk_hex));
+            status(ImportKeystoreStatus::Imported, String::new())
+        }
+        Err(e) => {
+            error!("Failed to import keystore: {:?}", e);
+            status(ImportKeystoreStatus::Error, format!("{:?}", e))
+        }
+    }
+}
+
+/// Optional parameters of `DELETE /eth/v1/keystores`
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct DeleteKeystoresQuery {
+    /// Hex encoded root to export the slashing protection under, required until the slashing DB
+    /// is initialized, see `slash_protection::resolve_genesis_validators_root`
+    pub genesis_validators_root: Option<String>,
+    /// Deletes keys saved within the protection window, see `key_management::key_protection_window`
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Keymanager API `DELETE /eth/v1/keystores`, deleting the saved BLS keys `pubkeys` and returning
+/// their slashing protection as an EIP-3076 interchange file. Their slashing protection DBs are
+/// kept, so a key imported again resumes from its records. Fails with 400 if the slashing DB's
+/// genesis_validators_root is unknown or differs from the given one, and with 500 if the
+/// slashing protection cannot be exported, without deleting anything. Keys saved within the
+/// protection window are only deleted with `force`.
+pub async fn delete_handler(
+    Query(query): Query<DeleteKeystoresQuery>,
+    Json(req): Json<DeleteKeystoresRequest>,
+) -> axum::response::Response {
+    info!("delete_keystores()");
+    let gvr = query
+        .genesis_validators_root
+        .map(|gvr| -> anyhow::Result<crate::eth2::eth_types::Root> {
+            let gvr: String = crate::strip_0x_prefix!(gvr);
+            hex::decode(gvr)?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("genesis_validators_root must be 32 bytes"))
+        })
+        .transpose()
+        .and_then(slash_protection::resolve_genesis_validators_root);
+    let gvr = match gvr {
+        Ok(gvr) => gvr,
+        Err(e) => {
+            error!("delete_keystores() failed with: {:?}", e);
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Bad genesis_validators_root, {:?}", e),
+            )
+                .into_response();
+        }
+    };
+    let force = query.force;
+    match tokio::task::spawn_blocking(move || delete_keystores(&req.pubkeys, gvr, force)).await {
+        Ok(Ok(resp)) => (axum::http::status::StatusCode::OK, Json(resp)).into_response(),
+        Ok(Err(e)) => {
+            error!("delete_keystores() failed with: {:?}", e);
+            (
+                axum::http::status::StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to export slashing protection, {:?}", e),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            error!("delete_keystores() panicked: {:?}", e);
+            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+// Holds the signing locks of the saved keys among `pubkeys` from the export until they are
+// deleted, so nothing they sign in between is missing from the exported interchange file
+fn delete_keystores(
+    pubkeys: &[String],
+    genesis_validators_root: crate::eth2::eth_types::Root,
+    force: bool,
+) -> anyhow::Result<DeleteKeystoresResponse> {
+    let pk_hexes: Vec<anyhow::Result<String>> = pubkeys
+        .iter()
+        .map(|pk_hex| bls_keys::sanitize_bls_pk_hex(pk_hex).map(|pk_hex| pk_hex.to_lowercase()))
+        .collect();
+    let mut saved: Vec<String> = pk_hexes
+        .iter()
+        .filter_map(|pk_hex| pk_hex.as_ref().ok())
+        .filter(|pk_hex| key_management::bls_key_exists(pk_hex))
+        .cloned()
+        .collect();
+    // Locked in a fixed order, signers only ever hold one of these locks
+    saved.sort();
+    saved.dedup();
+    let locks: Vec<_> = saved
+        .iter()
+        .map(|pk_hex| slash_protection::validator_signing_lock(pk_hex))
+        .collect();
+    let _guards: Vec<_> = locks
+        .iter()
+        .map(|lock| lock.lock().unwrap_or_else(|e| e.into_inner()))
+        .collect();
+    let slashing_protection =
+        slash_protection::export_interchange_for(&saved, genesis_validators_root)?;
+
+    let status = |status, message| KeystoreStatus { status, message };
+    let data = pk_hexes
+        .into_iter()
+        .map(|pk_hex| match pk_hex {
+            Err(e) => status(DeleteKeystoreStatus::Error, format!("{:?}", e)),
+            Ok(pk_hex) if !key_management::bls_key_exists(&pk_hex) => {
+                status(DeleteKeystoreStatus::NotFound, String::new())
+            }
+            Ok(pk_hex) => match key_management::delete_bls_key_with_force(&pk_hex, force) {
+                Ok(()) => {
+                    crate::io::webhook::emit(crate::io::webhook::AuditEvent::delete(&pk_hex));
+                    status(DeleteKeystoreStatus::Deleted, String::new())
+                }
+                Err(e) => {
+                    error!("Failed to delete BLS key {pk_hex}: {:?}", e);
+                    status(DeleteKeystoreStatus::Error, format!("{:?}", e))
+                }
+            },
+        })
+        .collect();
+    Ok(DeleteKeystoresResponse {
+        data,
+        slashing_protection,
+    })
+}
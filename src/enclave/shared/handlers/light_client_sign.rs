@@ -0,0 +1,67 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use log::{error, info};
+
+use crate::enclave::types::{LightClientSignRequest, LightClientSignResponse};
+
+/// Signs a light client update's attested header as a sync committee member, see
+/// `eth_signing::sign_light_client_header`
+pub async fn handler(
+    Path(bls_pk_hex): Path<String>,
+    State(state): State<crate::enclave::shared::handlers::AppState>,
+    Json(req): Json<LightClientSignRequest>,
+) -> axum::response::Response {
+    info!("light_client_sign()");
+    let bls_pk_hex = match crate::crypto::bls_keys::sanitize_bls_pk_hex(&bls_pk_hex) {
+        Ok(pk) => pk,
+        Err(e) => {
+            error!("Bad BLS public key format: {bls_pk_hex}");
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Bad bls_pk_hex, {:?}", e),
+            )
+                .into_response();
+        }
+    };
+    if !crate::io::key_management::bls_key_exists(&bls_pk_hex) {
+        error!("Unknown BLS public key: {bls_pk_hex}");
+        let resp = crate::enclave::types::ErrorResponse::unknown_key(&bls_pk_hex);
+        return (axum::http::status::StatusCode::NOT_FOUND, Json(resp)).into_response();
+    }
+
+    let signed = tokio::task::spawn_blocking(move || {
+        crate::eth2::eth_signing::sign_light_client_header(
+            bls_pk_hex,
+            req.header,
+            req.fork_info,
+            req.signature_slot,
+            state.allow_genesis_fork,
+        )
+    })
+    .await
+    .unwrap_or_else(|e| Err(anyhow::anyhow!("Signing task failed: {:?}", e)));
+    match signed {
+        Ok((header_root, signature)) => {
+            let resp = LightClientSignResponse {
+                header_root: format!("0x{}", hex::encode(header_root)),
+                signature: format!("0x{}", hex::encode(signature.to_vec())),
+            };
+            (axum::http::status::StatusCode::OK, Json(resp)).into_response()
+        }
+        Err(e) => {
+            error!("Signing operation failed: {:?}", e);
+            match e.downcast::<crate::eth2::eth_signing::SignError>() {
+                Ok(e) => crate::enclave::shared::sign_error_response(e),
+                // A header not preceding the signature slot, or a bad fork
+                Err(e) => (
+                    axum::http::status::StatusCode::BAD_REQUEST,
+                    format!("Signing operation failed: {:?}", e),
+                )
+                    .into_response(),
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use axum::{extract::Query, response::IntoResponse, Json};
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::io::key_management::{self, KeyType};
+
+/// Response encodings of `GET /eth/v1/keys`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListKeysFormat {
+    /// A single `ListKeyInfoResponse` object
+    #[default]
+    Json,
+    /// One `KeyInfo` object per line, streamed as it is written
+    Ndjson,
+}
+
+/// Optional filters of `GET /eth/v1/keys`, e.g. `?type=bls&attested=true&format=ndjson`
+#[derive(Debug, Default, Deserialize)]
+pub struct ListKeysQuery {
+    #[serde(rename = "type")]
+    pub key_type: Option<KeyType>,
+    pub attested: Option<bool>,
+    #[serde(default)]
+    pub format: ListKeysFormat,
+}
+
+/// Lists the saved BLS and ETH keys matching the query filters
+pub async fn handler(Query(query): Query<ListKeysQuery>) -> axum::response::Response {
+    info!("list_keys()");
+    if query.format == ListKeysFormat::Ndjson {
+        return ndjson_response(query.key_type, query.attested);
+    }
+    match key_management::list_keys(query.key_type, query.attested) {
+        Ok(entries) => {
+            let resp = crate::enclave::types::ListKeyInfoResponse::new(entries);
+            (axum::http::status::StatusCode::OK, Json(resp)).into_response()
+        }
+        Err(e) => {
+            error!("list_keys() failed with: {:?}", e);
+            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+// Streams one line per key as the key dirs are read. Each line is only read and serialized once
+// the client has taken the previous one, so a slow reader holds back the dir walk rather than the
+// whole listing being buffered. A failure part way aborts the body, so it cannot pass as complete.
+fn ndjson_response(key_type: Option<KeyType>, attested: Option<bool>) -> axum::response::Response {
+    let (mut tx, body) = axum::body::Body::channel();
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        for entry in key_management::iter_keys(key_type, attested) {
+            let line = entry.and_then(|entry| {
+                let mut line = serde_json::to_vec(&crate::enclave::types::KeyInfo::from(entry))?;
+                line.push(b'\n');
+                Ok(line)
+            });
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    error!("list_keys() failed with: {:?}", e);
+                    tx.abort();
+                    return;
+                }
+            };
+            if runtime.block_on(tx.send_data(line.into())).is_err() {
+                // The client went away
+                return;
+            }
+        }
+    });
+
+    (
+        [(axum::http::header::CONTENT_TYPE, "application/x-ndjson")],
+        axum::body::boxed(body),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enclave::types::KeyInfo;
+    use crate::io::key_management::{self, KeyType};
+
+    #[tokio::test]
+    async fn test_list_keys_ndjson() {
+        let mut pk_hexes = Vec::new();
+        for _ in 0..3 {
+            let pk = crate::crypto::eth_keys::eth_key_gen().unwrap();
+            pk_hexes.push(crate::crypto::eth_keys::eth_pk_to_hex(&pk));
+        }
+
+        let app = axum::Router::new()
+            .route("/eth/v1/keys", axum::routing::get(super::handler))
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+
+        let resp = server.get("/eth/v1/keys?type=eth&format=ndjson").await;
+        assert_eq!(resp.status_code(), 200);
+        assert_eq!(
+            resp.header("content-type").to_str().unwrap(),
+            "application/x-ndjson"
+        );
+        let keys: Vec<KeyInfo> = resp
+            .text()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        // Other tests save keys concurrently, so only this test's keys are counted
+        let own_keys = keys
+            .iter()
+            .filter(|k| {
+                pk_hexes
+                    .iter()
+                    .any(|pk_hex| k.pubkey.ends_with(pk_hex.as_str()))
+            })
+            .count();
+        assert_eq!(own_keys, pk_hexes.len());
+        assert!(keys.iter().all(|k| k.key_type == KeyType::Eth));
+
+        for pk_hex in pk_hexes.iter() {
+            key_management::delete_eth_key(pk_hex).unwrap();
+        }
+    }
+}
@@ -1,9 +1,192 @@
+use axum::response::IntoResponse;
+
+pub mod build_info;
+pub mod capabilities;
+pub mod commitment_sign;
+pub mod eth_address;
 pub mod health;
+pub mod keystores;
+pub mod light_client_sign;
 pub mod list_bls_keys;
 pub mod list_eth_keys;
+pub mod list_keys;
+pub mod nonce;
+pub mod readyz;
 pub mod secure_sign_bls;
+pub mod secure_sign_ssz;
+pub mod slashing_compact;
+pub mod slashing_watermarks;
+pub mod threshold_sign;
+pub mod validator_index;
+pub mod verify;
+pub mod web3signer_sign;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct AppState {
     pub genesis_fork_version: crate::eth2::eth_types::Version,
+    /// Echo the normalized signing object and its root in signing responses
+    pub debug_responses: bool,
+    /// Allow signing consensus messages under the all-zero genesis fork version
+    pub allow_genesis_fork: bool,
+    /// Include the uncompressed affine G2 point alongside each signature in signing responses,
+    /// unless a request sets `SignQuery::affine`
+    pub affine_signatures: bool,
+    /// Refuse every signing and keygen request, for standby instances that must never sign
+    pub verify_only: bool,
+    /// Sign sync committee contributions without checking their embedded selection proof
+    pub trust_selection_proofs: bool,
+    /// Fee recipients validators may register with builders
+    pub fee_recipient_policy: std::sync::Arc<crate::eth2::fee_recipient_policy::FeeRecipientPolicy>,
+    /// Beacon node validator indices are looked up from. Only the operator configures it, as
+    /// requests naming their own node would have this signer fetch arbitrary URLs.
+    pub beacon_node_url: Option<String>,
+}
+
+/// Query parameters accepted by the signing endpoints
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+pub struct SignQuery {
+    /// Include the affine G2 point in this response, overriding `AppState::affine_signatures`
+    pub affine: Option<bool>,
+}
+
+impl SignQuery {
+    pub fn affine(&self, state: &AppState) -> bool {
+        self.affine.unwrap_or(state.affine_signatures)
+    }
+}
+
+/// Wraps a signing or keygen `route` so it is refused with 403 while `state.verify_only` is set
+pub fn refuse_if_verify_only(
+    route: axum::routing::MethodRouter<AppState>,
+    state: &AppState,
+) -> axum::routing::MethodRouter<AppState> {
+    route.route_layer(axum::middleware::from_fn_with_state(
+        state.clone(),
+        verify_only_guard,
+    ))
+}
+
+async fn verify_only_guard<B>(
+    axum::extract::State(state): axum::extract::State<AppState>,
+    req: axum::http::Request<B>,
+    next: axum::middleware::Next<B>,
+) -> axum::response::Response {
+    if state.verify_only {
+        return (
+            axum::http::StatusCode::FORBIDDEN,
+            "Secure-Signer is in verify-only mode and will not sign or generate keys",
+        )
+            .into_response();
+    }
+    next.run(req).await
+}
+
+/// 503 if `e` stems from an unreachable attestation service, otherwise 500
+pub fn attestation_error_status(e: &anyhow::Error) -> axum::http::StatusCode {
+    if crate::io::remote_attestation::AttestationUnavailable::is_cause_of(e) {
+        axum::http::StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        axum::http::StatusCode::INTERNAL_SERVER_ERROR
+    }
+}
+
+/// Compresses responses of at least `min_bytes` with gzip or brotli, as negotiated by the
+/// client's `Accept-Encoding`. Streamed NDJSON is left as is so each line is flushed as sent.
+pub fn compression_layer(
+    min_bytes: u16,
+) -> tower_http::compression::CompressionLayer<
+    tower_http::compression::predicate::And<
+        tower_http::compression::predicate::SizeAbove,
+        tower_http::compression::predicate::NotForContentType,
+    >,
+> {
+    use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+    tower_http::compression::CompressionLayer::new().compress_when(
+        SizeAbove::new(min_bytes).and(NotForContentType::new("application/x-ndjson")),
+    )
+}
+
+/// Reads the `compression_layer` threshold from `SECURE_SIGNER_COMPRESSION_MIN_BYTES`
+pub fn compression_min_bytes_from_env() -> anyhow::Result<u16> {
+    match std::env::var("SECURE_SIGNER_COMPRESSION_MIN_BYTES") {
+        Ok(min_bytes) if !min_bytes.is_empty() => min_bytes.parse().map_err(|e| {
+            anyhow::anyhow!(
+                "Bad SECURE_SIGNER_COMPRESSION_MIN_BYTES {min_bytes}: {:?}",
+                e
+            )
+        }),
+        _ => Ok(crate::constants::DEFAULT_COMPRESSION_MIN_BYTES),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header, HeaderValue};
+    use std::io::Read;
+
+    #[tokio::test]
+    async fn test_large_listings_are_compressed_when_accepted() {
+        let sk_sets: Vec<blsttc::SecretKeySet> = (0..20)
+            .map(|_| crate::crypto::bls_keys::new_bls_key(0))
+            .collect();
+        for sk_set in sk_sets.iter() {
+            crate::crypto::bls_keys::save_bls_key(sk_set).unwrap();
+        }
+        let app = axum::Router::new()
+            .route(
+                "/eth/v1/keystores",
+                axum::routing::get(super::list_bls_keys::handler),
+            )
+            .route("/upcheck", axum::routing::get(super::health::handler))
+            .layer(super::compression_layer(
+                crate::constants::DEFAULT_COMPRESSION_MIN_BYTES,
+            ))
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+        let listed = |body: &[u8]| -> Vec<String> {
+            let resp: crate::enclave::types::ListKeysResponse =
+                serde_json::from_slice(body).unwrap();
+            resp.data.into_iter().map(|k| k.pubkey).collect()
+        };
+        let expected: Vec<String> = sk_sets
+            .iter()
+            .map(|sk_set| format!("0x{}", sk_set.public_keys().public_key().to_hex()))
+            .collect();
+
+        let resp = server
+            .get("/eth/v1/keystores")
+            .add_header(header::ACCEPT_ENCODING, HeaderValue::from_static("gzip"))
+            .await;
+        assert_eq!(resp.status_code(), 200);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_ENCODING).unwrap(),
+            "gzip"
+        );
+        let mut body = Vec::new();
+        flate2::read::GzDecoder::new(resp.as_bytes().as_ref())
+            .read_to_end(&mut body)
+            .unwrap();
+        assert!(resp.as_bytes().len() < body.len());
+        let keys = listed(&body);
+        assert!(expected.iter().all(|pk| keys.contains(pk)));
+
+        // Without Accept-Encoding the listing is sent as is
+        let resp = server.get("/eth/v1/keystores").await;
+        assert!(resp.headers().get(header::CONTENT_ENCODING).is_none());
+        let keys = listed(resp.as_bytes());
+        assert!(expected.iter().all(|pk| keys.contains(pk)));
+
+        // Small responses are never compressed
+        let resp = server
+            .get("/upcheck")
+            .add_header(header::ACCEPT_ENCODING, HeaderValue::from_static("gzip"))
+            .await;
+        assert_eq!(resp.status_code(), 200);
+        assert!(resp.headers().get(header::CONTENT_ENCODING).is_none());
+
+        for sk_set in sk_sets.iter() {
+            let pk_hex = sk_set.public_keys().public_key().to_hex();
+            crate::io::key_management::delete_bls_key(&pk_hex).unwrap();
+        }
+    }
 }
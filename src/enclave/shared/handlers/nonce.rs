@@ -0,0 +1,51 @@
+use axum::{response::IntoResponse, Json};
+use log::{error, info};
+
+/// Issues a short-lived, single-use nonce that a client includes in a later non-consensus
+/// signing request so the signature cannot be replayed
+pub async fn handler() -> axum::response::Response {
+    info!("issue_nonce()");
+    match crate::crypto::nonce::issue() {
+        Ok(nonce) => {
+            let resp = crate::enclave::types::NonceResponse {
+                nonce: format!("0x{}", hex::encode(nonce)),
+                ttl_secs: crate::constants::NONCE_TTL_SECS,
+            };
+            (axum::http::status::StatusCode::OK, Json(resp)).into_response()
+        }
+        // Transient, outstanding nonces make room as they expire
+        Err(e) if crate::crypto::replay_store::ReplayStoreFull::is_cause_of(&e) => {
+            error!("issue_nonce() failed with: {:?}", e);
+            (
+                axum::http::status::StatusCode::SERVICE_UNAVAILABLE,
+                e.to_string(),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            error!("issue_nonce() failed with: {:?}", e);
+            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enclave::types::NonceResponse;
+
+    #[tokio::test]
+    async fn test_issued_nonce_is_single_use() {
+        let app = axum::Router::new()
+            .route("/eth/v1/nonce", axum::routing::post(super::handler))
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+
+        let resp = server.post("/eth/v1/nonce").await;
+        assert_eq!(resp.status_code(), 200);
+        let resp: NonceResponse = serde_json::from_slice(resp.as_bytes()).unwrap();
+        assert_eq!(resp.ttl_secs, crate::constants::NONCE_TTL_SECS);
+
+        crate::crypto::nonce::consume(&resp.nonce).unwrap();
+        assert!(crate::crypto::nonce::consume(&resp.nonce).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use anyhow::{bail, Result};
+use axum::response::IntoResponse;
+use log::{error, info};
+
+/// Returns the number of bytes available to unprivileged users on the filesystem holding `path`
+pub fn available_disk_bytes(path: &str) -> Result<u64> {
+    let c_path = std::ffi::CString::new(path)?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    let ret = unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) };
+    if ret != 0 {
+        bail!("statvfs failed: {}", std::io::Error::last_os_error())
+    }
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// Returns 200 if at least `min_free_bytes` of disk space is available, otherwise 503 since
+/// keys or slashing protection data could fail to be persisted.
+pub fn check_readiness<F>(stat: F, min_free_bytes: u64) -> axum::response::Response
+where
+    F: FnOnce() -> Result<u64>,
+{
+    match stat() {
+        Ok(free) if free >= min_free_bytes => axum::http::status::StatusCode::OK.into_response(),
+        Ok(free) => {
+            error!("readyz() only {free} bytes of disk space available");
+            (
+                axum::http::status::StatusCode::SERVICE_UNAVAILABLE,
+                format!("Insufficient disk space: {free} bytes available"),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            error!("readyz() failed with: {:?}", e);
+            (
+                axum::http::status::StatusCode::SERVICE_UNAVAILABLE,
+                format!("Unable to check disk space: {:?}", e),
+            )
+                .into_response()
+        }
+    }
+}
+
+pub async fn handler() -> axum::response::Response {
+    info!("readyz()");
+    check_readiness(
+        || available_disk_bytes("."),
+        crate::constants::READYZ_MIN_FREE_DISK_BYTES,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_readyz_disk_space_threshold() {
+        let resp = check_readiness(|| Ok(2048), 1024);
+        assert_eq!(resp.status(), 200);
+
+        let resp = check_readiness(|| Ok(1024), 1024);
+        assert_eq!(resp.status(), 200);
+
+        let resp = check_readiness(|| Ok(1023), 1024);
+        assert_eq!(resp.status(), 503);
+
+        let resp = check_readiness(|| Err(anyhow!("stat failed")), 1024);
+        assert_eq!(resp.status(), 503);
+    }
+
+    #[test]
+    fn test_available_disk_bytes() {
+        assert!(available_disk_bytes(".").is_ok());
+        assert!(available_disk_bytes("./does/not/exist").is_err());
+    }
+}
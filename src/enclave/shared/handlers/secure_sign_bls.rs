@@ -1,16 +1,122 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    response::IntoResponse,
     Json,
 };
-use log::info;
+use log::{error, info};
 
 /// Signs the specific type of request
 /// Maintains compatibility with https://consensys.github.io/web3signer/web3signer-eth2.html#tag/Signing
 pub async fn handler(
     Path(bls_pk_hex): Path<String>,
     State(state): State<crate::enclave::shared::handlers::AppState>,
-    Json(req): Json<crate::eth2::eth_signing::BLSSignMsg>,
+    Query(query): Query<crate::enclave::shared::handlers::SignQuery>,
+    Json(req): Json<crate::eth2::eth_signing::SignRequest>,
 ) -> axum::response::Response {
-    info!("secure_sign_bls()");
-    crate::enclave::shared::sign_validator_message(Path(bls_pk_hex), State(state), Json(req))
+    info!("secure_sign_bls(version = {})", req.version);
+    let affine = query.affine(&state);
+    // Signing and the slash protection commit run to completion on a blocking task,
+    // so a client disconnect (dropping this future) cannot interrupt them half way
+    match tokio::task::spawn_blocking(move || {
+        crate::enclave::shared::sign_validator_message(
+            Path(bls_pk_hex),
+            State(state),
+            affine,
+            Json(req.msg),
+        )
+    })
+    .await
+    {
+        Ok(resp) => resp,
+        Err(e) => {
+            error!("Signing task failed");
+            (
+                axum::http::status::StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Signing operation failed: {:?}", e),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Signs on behalf of the BLS key mapped to `validator_index`, see `validator_index::handler`
+pub async fn by_index_handler(
+    Path(validator_index): Path<u64>,
+    state: State<crate::enclave::shared::handlers::AppState>,
+    query: Query<crate::enclave::shared::handlers::SignQuery>,
+    req: Json<crate::eth2::eth_signing::SignRequest>,
+) -> axum::response::Response {
+    info!("secure_sign_bls_by_index()");
+    match crate::io::key_management::read_validator_index(validator_index) {
+        Ok(Some(bls_pk_hex)) => handler(Path(bls_pk_hex), state, query, req).await,
+        Ok(None) => {
+            error!("Unknown validator index: {validator_index}");
+            let resp = crate::enclave::types::ErrorResponse::unknown_validator_index();
+            (axum::http::status::StatusCode::NOT_FOUND, Json(resp)).into_response()
+        }
+        Err(e) => {
+            error!("Failed to read validator index: {:?}", e);
+            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eth2::slash_protection::{fail_next_write, SlashingProtectionData};
+
+    #[tokio::test]
+    async fn test_signing_fails_closed_when_slashing_write_fails() {
+        let sk_set = crate::crypto::bls_keys::new_bls_key(0);
+        let pk_hex = sk_set.public_keys().public_key().to_hex();
+        crate::crypto::bls_keys::save_bls_key(&sk_set).unwrap();
+        SlashingProtectionData::from_pk_hex(&pk_hex)
+            .unwrap()
+            .write()
+            .unwrap();
+
+        let app = axum::Router::new()
+            .route(
+                "/api/v1/eth2/sign/:bls_pk_hex",
+                axum::routing::post(super::handler),
+            )
+            .with_state(crate::enclave::shared::handlers::AppState::default())
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+        let attestation = serde_json::json!({
+            "type": "ATTESTATION",
+            "fork_info": {
+                "fork": {
+                    "previous_version": "0x03000000",
+                    "current_version": "0x04000000",
+                    "epoch": "2"
+                },
+                "genesis_validators_root": format!("0x{}", "2a".repeat(32))
+            },
+            "attestation": {
+                "slot": "100",
+                "index": "0",
+                "beacon_block_root": format!("0x{}", "11".repeat(32)),
+                "source": { "epoch": "2", "root": format!("0x{}", "22".repeat(32)) },
+                "target": { "epoch": "3", "root": format!("0x{}", "33".repeat(32)) }
+            }
+        });
+        let url = format!("/api/v1/eth2/sign/0x{pk_hex}");
+
+        // No signature is released when the attestation cannot be recorded
+        fail_next_write(&pk_hex);
+        let resp = server.post(&url).json(&attestation).await;
+        assert_eq!(resp.status_code(), 503);
+        assert!(!resp.text().contains("signature"));
+        let db = SlashingProtectionData::read(&pk_hex).unwrap();
+        assert!(db.signed_attestations.is_empty());
+
+        // Once writes succeed again the same attestation is signed and recorded
+        let resp = server.post(&url).json(&attestation).await;
+        assert_eq!(resp.status_code(), 200);
+        let db = SlashingProtectionData::read(&pk_hex).unwrap();
+        assert_eq!(db.signed_attestations.len(), 1);
+
+        crate::io::key_management::delete_bls_key(&pk_hex).unwrap();
+    }
 }
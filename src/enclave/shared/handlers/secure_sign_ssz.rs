@@ -0,0 +1,124 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use log::{error, info};
+
+/// Header naming the signing type of the SSZ body, using the Web3Signer type names
+pub const SIGNING_TYPE_HEADER: &str = "X-Signing-Type";
+/// Header carrying the 0x-prefixed 32-byte signature domain
+pub const SIGNING_DOMAIN_HEADER: &str = "X-Signing-Domain";
+/// Header carrying the 0x-prefixed 4-byte fork version the domain was computed from
+pub const FORK_VERSION_HEADER: &str = "X-Fork-Version";
+/// Header carrying the 0x-prefixed 32-byte genesis_validators_root the domain was computed from
+pub const GENESIS_VALIDATORS_ROOT_HEADER: &str = "X-Genesis-Validators-Root";
+
+/// The signing type, domain and fork of a raw SSZ signing request, read from its headers
+#[derive(Debug, Clone)]
+pub struct SszSignRequest {
+    pub signing_type: String,
+    pub domain: crate::eth2::eth_types::Domain,
+    /// Required unless the signing type is always signed under the genesis fork version
+    pub fork_version: Option<crate::eth2::eth_types::Version>,
+    pub genesis_validators_root: Option<crate::eth2::eth_types::Root>,
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+// Decodes the optional 0x-prefixed hex header `name` as N bytes, failing if it is malformed
+fn header_bytes<const N: usize>(
+    headers: &HeaderMap,
+    name: &str,
+) -> Result<Option<[u8; N]>, axum::response::Response> {
+    let Some(value) = header_str(headers, name) else {
+        return Ok(None);
+    };
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
+        .ok()
+        .and_then(|v| v.try_into().ok())
+        .map(Some)
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("{name} header must be {N}-byte hex"),
+            )
+                .into_response()
+        })
+}
+
+/// Signs a raw SSZ container sent as `application/octet-stream`, avoiding the JSON round trip for
+/// clients that already hold the SSZ bytes. The signing type, domain and the fork the domain was
+/// computed from are passed in headers.
+pub async fn handler(
+    Path(bls_pk_hex): Path<String>,
+    State(state): State<super::AppState>,
+    Query(query): Query<super::SignQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> axum::response::Response {
+    info!("secure_sign_ssz()");
+    let content_type = header_str(&headers, header::CONTENT_TYPE.as_str()).unwrap_or_default();
+    if !content_type.starts_with("application/octet-stream") {
+        return (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Expected Content-Type: application/octet-stream",
+        )
+            .into_response();
+    }
+
+    let Some(signing_type) = header_str(&headers, SIGNING_TYPE_HEADER).map(str::to_string) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Missing {SIGNING_TYPE_HEADER} header"),
+        )
+            .into_response();
+    };
+    let domain = match header_bytes::<32>(&headers, SIGNING_DOMAIN_HEADER) {
+        Ok(Some(domain)) => domain,
+        Ok(None) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("{SIGNING_DOMAIN_HEADER} header must be a 32-byte hex domain"),
+            )
+                .into_response()
+        }
+        Err(resp) => return resp,
+    };
+    let fork_version = match header_bytes::<4>(&headers, FORK_VERSION_HEADER) {
+        Ok(fork_version) => fork_version,
+        Err(resp) => return resp,
+    };
+    let genesis_validators_root = match header_bytes::<32>(&headers, GENESIS_VALIDATORS_ROOT_HEADER)
+    {
+        Ok(gvr) => gvr,
+        Err(resp) => return resp,
+    };
+    let req = SszSignRequest {
+        signing_type,
+        domain,
+        fork_version,
+        genesis_validators_root,
+    };
+
+    // Run to completion on a blocking task like JSON signing requests
+    match tokio::task::spawn_blocking(move || {
+        let affine = query.affine(&state);
+        crate::enclave::shared::sign_ssz_message(&bls_pk_hex, &req, &body, &state, affine)
+    })
+    .await
+    {
+        Ok(resp) => resp,
+        Err(e) => {
+            error!("Signing task failed");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Signing operation failed: {:?}", e),
+            )
+                .into_response()
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use axum::{response::IntoResponse, Json};
+use log::{error, info};
+
+/// Compacts the slashing protection DB, keeping only each key's high-water marks. Signing
+/// requests are rejected with 503 until the compaction completes.
+pub async fn handler() -> axum::response::Response {
+    info!("slashing_compact()");
+    match tokio::task::spawn_blocking(crate::eth2::slash_protection::compact_slashing_db).await {
+        Ok(Ok(summary)) => (axum::http::status::StatusCode::OK, Json(summary)).into_response(),
+        Ok(Err(e)) => {
+            error!("slashing_compact() failed with: {:?}", e);
+            (
+                axum::http::status::StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Slashing DB compaction failed: {:?}", e),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            error!("slashing_compact() panicked: {:?}", e);
+            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
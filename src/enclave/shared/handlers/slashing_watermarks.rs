@@ -0,0 +1,35 @@
+use axum::{extract::Path, response::IntoResponse, Json};
+use log::{error, info};
+
+use crate::eth2::slash_protection::SlashingProtectionData;
+
+/// Returns the highest signed block slot and attestation epochs recorded for `bls_pk_hex`,
+/// without exporting the full interchange
+pub async fn handler(Path(bls_pk_hex): Path<String>) -> axum::response::Response {
+    info!("slashing_watermarks()");
+    let bls_pk_hex = match crate::crypto::bls_keys::sanitize_bls_pk_hex(&bls_pk_hex) {
+        Ok(pk) => pk,
+        Err(e) => {
+            error!("Bad BLS public key format: {bls_pk_hex}");
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Bad bls_pk_hex, {:?}", e),
+            )
+                .into_response();
+        }
+    };
+
+    if !crate::io::key_management::bls_key_exists(&bls_pk_hex) {
+        error!("Unknown BLS public key: {bls_pk_hex}");
+        let resp = crate::enclave::types::ErrorResponse::unknown_key(&bls_pk_hex);
+        return (axum::http::status::StatusCode::NOT_FOUND, Json(resp)).into_response();
+    }
+
+    match SlashingProtectionData::read(&bls_pk_hex) {
+        Ok(db) => (axum::http::status::StatusCode::OK, Json(db.watermarks())).into_response(),
+        Err(e) => {
+            error!("slashing_watermarks() failed with: {:?}", e);
+            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
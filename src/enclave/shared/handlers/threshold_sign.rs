@@ -0,0 +1,209 @@
+use axum::{response::IntoResponse, Json};
+use log::{error, info};
+
+use crate::enclave::types::{PartialSignature, ThresholdSignRequest, ThresholdSignResponse};
+
+/// Returns the partial signatures of every requested key share held by this enclave over one
+/// message, so a coordinator needs a single round trip per enclave to collect its partials
+pub async fn handler(Json(req): Json<ThresholdSignRequest>) -> axum::response::Response {
+    info!("threshold_sign()");
+    let parse_root = |hex_root: &String| -> anyhow::Result<[u8; 32]> {
+        let hex_root: String = crate::strip_0x_prefix!(hex_root);
+        hex::decode(hex_root)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Expected 32 bytes"))
+    };
+    let parsed = parse_root(&req.object_root).and_then(|object_root| {
+        let domain = parse_root(&req.domain)?;
+        let shares = req
+            .shares
+            .iter()
+            .map(crate::crypto::bls_keys::sanitize_bls_pk_hex)
+            .collect::<anyhow::Result<Vec<String>>>()?;
+        Ok((object_root, domain, shares))
+    });
+    let (object_root, domain, shares) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            error!("Bad threshold sign request: {:?}", e);
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Bad threshold sign request, {:?}", e),
+            )
+                .into_response();
+        }
+    };
+
+    if let Some(unknown) = shares
+        .iter()
+        .find(|s| !crate::io::key_management::bls_key_exists(s))
+    {
+        error!("Unknown BLS key share: {unknown}");
+        let resp = crate::enclave::types::ErrorResponse::unknown_key(unknown);
+        return (axum::http::status::StatusCode::NOT_FOUND, Json(resp)).into_response();
+    }
+
+    if let Err(e) = crate::eth2::eth_signing::check_key_share_domain(&domain) {
+        error!("Bad threshold sign domain: {:?}", e);
+        return (
+            axum::http::status::StatusCode::BAD_REQUEST,
+            format!("Signing operation failed: {:?}", e),
+        )
+            .into_response();
+    }
+    if let Some(e) = shares
+        .iter()
+        .find_map(|s| crate::eth2::eth_signing::check_key_role(s, false).err())
+    {
+        error!("Key role check failed: {:?}", e);
+        return (
+            axum::http::status::StatusCode::FORBIDDEN,
+            format!("Signing operation failed: {:?}", e),
+        )
+            .into_response();
+    }
+
+    let signed = tokio::task::spawn_blocking(move || {
+        crate::eth2::eth_signing::sign_root_with_key_shares(&shares, object_root, domain)
+            .map(|signed| (shares, signed))
+    })
+    .await
+    .unwrap_or_else(|e| Err(anyhow::anyhow!("Signing task failed: {:?}", e)));
+    match signed {
+        Ok((shares, (signing_root, pk_set, partials))) => {
+            let partial_signatures = shares
+                .into_iter()
+                .zip(partials)
+                .map(|(share, (index, sig))| PartialSignature {
+                    share: format!("0x{share}"),
+                    index,
+                    signature: format!("0x{}", hex::encode(sig.to_bytes())),
+                })
+                .collect();
+            let resp = ThresholdSignResponse {
+                signing_root: format!("0x{}", hex::encode(signing_root)),
+                group_pubkey: format!("0x{}", pk_set.public_key().to_hex()),
+                partial_signatures,
+            };
+            (axum::http::status::StatusCode::OK, Json(resp)).into_response()
+        }
+        Err(e) => {
+            error!("Threshold signing failed: {:?}", e);
+            (
+                axum::http::status::StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Signing operation failed: {:?}", e),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::bls_keys;
+    use crate::enclave::types::{ThresholdSignRequest, ThresholdSignResponse};
+    use crate::eth2::eth_signing::compute_domain;
+    use crate::eth2::eth_types::{DomainType, DOMAIN_APPLICATION_BUILDER, DOMAIN_BEACON_ATTESTER};
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn test_threshold_partials_combine_to_the_group_signature() {
+        let sk_set = bls_keys::new_bls_key(2);
+        let pk_set = sk_set.public_keys();
+        let key_shares = bls_keys::distribute_key_shares(&sk_set, 4);
+        // This enclave holds shares 1 to 3, enough to meet the threshold on its own
+        let group_ids: Vec<String> = key_shares[1..]
+            .iter()
+            .map(|(sk_share, _)| bls_keys::save_bls_key_share(sk_share, &pk_set).unwrap())
+            .collect();
+
+        let app = axum::Router::new()
+            .route(
+                "/eth/v1/threshold/sign",
+                axum::routing::post(super::handler),
+            )
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+
+        // An application domain type outside the consensus and builder ones
+        const DOMAIN_APPLICATION_TEST: DomainType = [0x10, 0x00, 0x00, 0x01];
+        let domain = compute_domain(DOMAIN_APPLICATION_TEST, None, None);
+        let req = |domain: [u8; 32], shares: Vec<String>| ThresholdSignRequest {
+            object_root: format!("0x{}", "5a".repeat(32)),
+            domain: format!("0x{}", hex::encode(domain)),
+            shares,
+        };
+        let resp = server
+            .post("/eth/v1/threshold/sign")
+            .json(&req(domain, group_ids.clone()))
+            .await;
+        assert_eq!(resp.status_code(), 200);
+        let resp: ThresholdSignResponse = serde_json::from_slice(resp.as_bytes()).unwrap();
+        assert_eq!(
+            resp.group_pubkey,
+            format!("0x{}", pk_set.public_key().to_hex())
+        );
+        assert_eq!(resp.partial_signatures.len(), 3);
+
+        let signing_root = hex::decode(&resp.signing_root[2..]).unwrap();
+        let partials: BTreeMap<usize, blsttc::SignatureShare> = resp
+            .partial_signatures
+            .iter()
+            .zip(group_ids.iter())
+            .enumerate()
+            .map(|(i, (partial, group_id))| {
+                assert_eq!(partial.share, format!("0x{group_id}"));
+                assert_eq!(partial.index, i + 1);
+                let sig = bls_keys::bls_sig_from_hex(&partial.signature).unwrap();
+                (
+                    partial.index,
+                    blsttc::SignatureShare::from_bytes(sig.to_bytes()).unwrap(),
+                )
+            })
+            .collect();
+        let sig = pk_set.combine_signatures(partials).unwrap();
+        assert!(pk_set.public_key().verify(&sig, &signing_root));
+        assert_eq!(sig, sk_set.secret_key().sign(&signing_root));
+
+        // Share partials are not slashing protected, so consensus domains are refused
+        let attester_domain = compute_domain(DOMAIN_BEACON_ATTESTER, None, None);
+        let resp = server
+            .post("/eth/v1/threshold/sign")
+            .json(&req(attester_domain, group_ids.clone()))
+            .await;
+        assert_eq!(resp.status_code(), 400);
+        // Registrations are never signed blind of their fee recipient
+        let builder_domain = compute_domain(DOMAIN_APPLICATION_BUILDER, None, None);
+        let resp = server
+            .post("/eth/v1/threshold/sign")
+            .json(&req(builder_domain, group_ids.clone()))
+            .await;
+        assert_eq!(resp.status_code(), 400);
+
+        // Validator keys only sign consensus objects
+        let validator_sk_set = bls_keys::new_bls_key(0);
+        bls_keys::save_bls_key_with_role(
+            &validator_sk_set,
+            crate::io::key_management::KeyRole::Validator,
+        )
+        .unwrap();
+        let validator_pk_hex = validator_sk_set.public_keys().public_key().to_hex();
+        let resp = server
+            .post("/eth/v1/threshold/sign")
+            .json(&req(domain, vec![validator_pk_hex.clone()]))
+            .await;
+        assert_eq!(resp.status_code(), 403);
+        crate::io::key_management::delete_bls_key(&validator_pk_hex).unwrap();
+
+        let other_share = hex::encode(key_shares[0].1.to_bytes());
+        let resp = server
+            .post("/eth/v1/threshold/sign")
+            .json(&req(domain, vec![group_ids[0].clone(), other_share]))
+            .await;
+        assert_eq!(resp.status_code(), 404);
+
+        for group_id in group_ids.iter() {
+            crate::io::key_management::delete_bls_key(group_id).unwrap();
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use axum::{extract::State, response::IntoResponse, Json};
+use log::{error, info};
+
+use crate::enclave::types::{ValidatorIndexRequest, ValidatorIndexResponse};
+
+/// Maps a validator index to a saved BLS key, either as supplied by the client or as looked up
+/// from the beacon node configured in `AppState::beacon_node_url`, so sign requests can target the
+/// index. An index mapped to another key is only remapped if the request sets `overwrite`.
+pub async fn handler(
+    State(state): State<crate::enclave::shared::handlers::AppState>,
+    Json(req): Json<ValidatorIndexRequest>,
+) -> axum::response::Response {
+    info!("register_validator_index()");
+    let bls_pk_hex = match crate::crypto::bls_keys::sanitize_bls_pk_hex(&req.pubkey) {
+        Ok(pk) => pk,
+        Err(e) => {
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Bad pubkey, {:?}", e),
+            )
+                .into_response()
+        }
+    };
+
+    if !crate::io::key_management::bls_key_exists(&bls_pk_hex) {
+        error!("Unknown BLS public key: {bls_pk_hex}");
+        let resp = crate::enclave::types::ErrorResponse::unknown_key(&bls_pk_hex);
+        return (axum::http::status::StatusCode::NOT_FOUND, Json(resp)).into_response();
+    }
+
+    let validator_index = match (&req.validator_index, &state.beacon_node_url) {
+        (Some(index), _) => match index.parse::<u64>() {
+            Ok(index) => index,
+            Err(e) => {
+                return (
+                    axum::http::status::StatusCode::BAD_REQUEST,
+                    format!("Bad validator_index, {:?}", e),
+                )
+                    .into_response()
+            }
+        },
+        (None, Some(url)) => {
+            match crate::eth2::beacon_node::fetch_validator_index(url, &bls_pk_hex).await {
+                Ok(index) => index,
+                Err(e) => {
+                    error!("Beacon node lookup failed: {:?}", e);
+                    return (
+                        axum::http::status::StatusCode::BAD_GATEWAY,
+                        format!("Beacon node lookup failed, {:?}", e),
+                    )
+                        .into_response();
+                }
+            }
+        }
+        (None, None) => {
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                "Expected a validator_index, no beacon node is configured to look it up",
+            )
+                .into_response()
+        }
+    };
+
+    match crate::io::key_management::write_validator_index(
+        validator_index,
+        &bls_pk_hex,
+        req.overwrite,
+    ) {
+        Ok(()) => {
+            let resp = ValidatorIndexResponse {
+                pubkey: format!("0x{}", bls_pk_hex),
+                validator_index: validator_index.to_string(),
+            };
+            (axum::http::status::StatusCode::OK, Json(resp)).into_response()
+        }
+        Err(e) if crate::io::key_management::ValidatorIndexTaken::is_cause_of(&e) => {
+            error!("register_validator_index() failed with: {:?}", e);
+            (axum::http::status::StatusCode::CONFLICT, format!("{:?}", e)).into_response()
+        }
+        Err(e) => {
+            error!("register_validator_index() failed with: {:?}", e);
+            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use axum::{response::IntoResponse, Json};
+use log::{error, info};
+
+use crate::enclave::types::{AggregateVerifyRequest, VerifyRequest, VerifyResponse};
+
+/// Verifies a BLS signature over a signing root. Nothing is read from or written to storage, so
+/// this is served in verify-only mode.
+pub async fn handler(Json(req): Json<VerifyRequest>) -> axum::response::Response {
+    info!("verify()");
+    let parsed = crate::crypto::bls_keys::bls_pk_from_hex(&req.pubkey).and_then(|pk| {
+        let sig = crate::crypto::bls_keys::bls_sig_from_hex(&req.signature)?;
+        let signing_root: String = crate::strip_0x_prefix!(req.signing_root);
+        let dst = request_dst(&req.genesis_validators_root)?;
+        Ok((pk, sig, hex::decode(signing_root)?, dst))
+    });
+    let (pk, sig, signing_root, dst) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            error!("Bad verify request: {:?}", e);
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Bad verify request, {:?}", e),
+            )
+                .into_response();
+        }
+    };
+
+    let resp = VerifyResponse {
+        valid: crate::crypto::bls_verify::verify(&pk, &signing_root, &sig, dst),
+    };
+    (axum::http::status::StatusCode::OK, Json(resp)).into_response()
+}
+
+/// Verifies a BLS aggregate signature by `pubkeys`, over one message in uniform mode or one
+/// distinct message per public key otherwise. Every public key and the signature are subgroup
+/// checked. At most `MAX_AGGREGATE_VERIFY_KEYS` public keys are verified, off the async runtime.
+/// Like `handler` this is served in verify-only mode.
+pub async fn aggregate_handler(
+    Json(req): Json<AggregateVerifyRequest>,
+) -> axum::response::Response {
+    info!("verify_aggregate()");
+    match tokio::task::spawn_blocking(move || verify_aggregate(&req)).await {
+        Ok(Ok(valid)) => {
+            let resp = VerifyResponse { valid };
+            (axum::http::status::StatusCode::OK, Json(resp)).into_response()
+        }
+        Ok(Err(e)) => {
+            error!("Bad aggregate verify request: {:?}", e);
+            (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Bad aggregate verify request, {:?}", e),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            error!("verify_aggregate() panicked: {:?}", e);
+            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+// Aggregate verification over identical messages is only safe against rogue keys with proofs of
+// possession, which fast-aggregate verification assumes, so the non-uniform mode needs distinct
+// messages
+fn verify_aggregate(req: &AggregateVerifyRequest) -> anyhow::Result<bool> {
+    let max_keys = crate::constants::MAX_AGGREGATE_VERIFY_KEYS;
+    if req.pubkeys.len() > max_keys {
+        anyhow::bail!(
+            "Got {} pubkeys, at most {max_keys} are verified",
+            req.pubkeys.len()
+        )
+    }
+    let decode = |msg_hex: &String| -> anyhow::Result<Vec<u8>> {
+        let msg_hex: String = crate::strip_0x_prefix!(msg_hex);
+        Ok(hex::decode(msg_hex)?)
+    };
+    let agg_sig = crate::crypto::bls_keys::bls_sig_from_hex(&req.agg_sig)?;
+    let pks = req
+        .pubkeys
+        .iter()
+        .map(|pk| crate::crypto::bls_keys::bls_pk_from_hex(pk))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let dst = request_dst(&req.genesis_validators_root)?;
+    if req.uniform.unwrap_or(true) {
+        let msg = decode(&req.msg)?;
+        return crate::crypto::bls_verify::fast_aggregate_verify(&pks, &msg, &agg_sig, dst);
+    }
+
+    let Some(msgs) = &req.msgs else {
+        anyhow::bail!("Non-uniform aggregates need one distinct message per pubkey in msgs")
+    };
+    if msgs.len() != pks.len() {
+        anyhow::bail!("Got {} pubkeys but {} msgs", pks.len(), msgs.len())
+    }
+    let msgs = msgs
+        .iter()
+        .map(decode)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let mut seen = std::collections::HashSet::new();
+    if !msgs.iter().all(|msg| seen.insert(msg.as_slice())) {
+        anyhow::bail!("Non-uniform aggregates need distinct msgs, use uniform mode for one message")
+    }
+    let msgs: Vec<&[u8]> = msgs.iter().map(Vec::as_slice).collect();
+    crate::crypto::bls_verify::aggregate_verify(&pks, &msgs, &agg_sig, dst)
+}
+
+// The DST of the network named by `genesis_validators_root`, or the default DST if omitted
+fn request_dst(genesis_validators_root: &Option<String>) -> anyhow::Result<&'static [u8]> {
+    let Some(gvr) = genesis_validators_root else {
+        return Ok(crate::constants::CIPHER_SUITE.as_bytes());
+    };
+    let gvr: String = crate::strip_0x_prefix!(gvr);
+    let gvr: crate::eth2::eth_types::Root = hex::decode(gvr)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("genesis_validators_root must be 32 bytes"))?;
+    Ok(crate::eth2::eth_types::network_dst(&gvr))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enclave::shared::handlers::AppState;
+    use crate::enclave::types::{VerifyRequest, VerifyResponse};
+
+    #[tokio::test]
+    async fn test_verify_only_mode_refuses_signing() {
+        let state = AppState {
+            verify_only: true,
+            ..Default::default()
+        };
+        let secure_signer = crate::enclave::secure_signer::router(state.clone());
+        let secure_signer = axum_test::TestServer::new(secure_signer.into_make_service()).unwrap();
+        let validator = crate::enclave::validator::router(state);
+        let validator = axum_test::TestServer::new(validator.into_make_service()).unwrap();
+
+        let sk_set = crate::crypto::bls_keys::new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        let signing_root = [7_u8; 32];
+        let sign_path = format!("/api/v1/eth2/sign/0x{}", pk.to_hex());
+        let randao = serde_json::json!({"type": "RANDAO_REVEAL"});
+        let validator_index =
+            serde_json::json!({"validator_index": "1", "pubkey": format!("0x{}", pk.to_hex())});
+
+        // Nothing that signs, generates keys or changes signer state is served by either binary
+        for server in [&secure_signer, &validator] {
+            let resp = server.post(&sign_path).json(&randao).await;
+            assert_eq!(resp.status_code(), 403);
+            let resp = server.post("/eth/v1/slashing/compact").await;
+            assert_eq!(resp.status_code(), 403);
+            let resp = server
+                .post("/eth/v1/validator_index")
+                .json(&validator_index)
+                .await;
+            assert_eq!(resp.status_code(), 403);
+        }
+        assert_eq!(
+            secure_signer.post("/eth/v1/keygen/bls").await.status_code(),
+            403
+        );
+        assert_eq!(secure_signer.post("/eth/v1/nonce").await.status_code(), 403);
+        let path = format!("/eth/v1/commitment/sign/0x{}", pk.to_hex());
+        assert_eq!(secure_signer.post(&path).await.status_code(), 403);
+        assert_eq!(validator.post("/bls/v1/keygen").await.status_code(), 403);
+
+        // Verification is still served
+        let sig = sk_set.secret_key().sign(signing_root);
+        let req = |signing_root: [u8; 32]| VerifyRequest {
+            pubkey: format!("0x{}", pk.to_hex()),
+            signing_root: format!("0x{}", hex::encode(signing_root)),
+            signature: format!("0x{}", hex::encode(sig.to_bytes())),
+            genesis_validators_root: None,
+        };
+        for server in [&secure_signer, &validator] {
+            let resp = server.post("/eth/v1/verify").json(&req(signing_root)).await;
+            assert_eq!(resp.status_code(), 200);
+            let resp: VerifyResponse = serde_json::from_slice(resp.as_bytes()).unwrap();
+            assert!(resp.valid);
+        }
+
+        let resp = secure_signer
+            .post("/eth/v1/verify")
+            .json(&req([8_u8; 32]))
+            .await;
+        let resp: VerifyResponse = serde_json::from_slice(resp.as_bytes()).unwrap();
+        assert!(!resp.valid);
+    }
+
+    #[tokio::test]
+    async fn test_verify_under_network_dst() {
+        let gvr_hex = "6d".repeat(32);
+        let dst = "BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_VERIFY_DEVNET_";
+        let networks = serde_json::json!([{
+            "name": "verify-devnet",
+            "genesis_validators_root": format!("0x{gvr_hex}"),
+            "fork_versions": ["0x20000000"],
+            "dst": dst,
+        }]);
+        assert_eq!(
+            crate::eth2::eth_types::register_networks(&networks.to_string()).unwrap(),
+            1
+        );
+        let app = axum::Router::new()
+            .route("/eth/v1/verify", axum::routing::post(super::handler))
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+
+        let sk_set = crate::crypto::bls_keys::new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        let signing_root = [9_u8; 32];
+        let sig =
+            crate::crypto::bls_keys::bls_sign_with_dst(&sk_set, &signing_root, dst.as_bytes())
+                .unwrap();
+        let req = |genesis_validators_root: Option<String>| VerifyRequest {
+            pubkey: format!("0x{}", pk.to_hex()),
+            signing_root: format!("0x{}", hex::encode(signing_root)),
+            signature: format!("0x{}", hex::encode(sig.to_bytes())),
+            genesis_validators_root,
+        };
+        let valid = |resp: axum_test::TestResponse| -> bool {
+            assert_eq!(resp.status_code(), 200);
+            serde_json::from_slice::<VerifyResponse>(resp.as_bytes())
+                .unwrap()
+                .valid
+        };
+
+        // Verifies only under the DST of the network it was signed for
+        let resp = server
+            .post("/eth/v1/verify")
+            .json(&req(Some(gvr_hex.clone())))
+            .await;
+        assert!(valid(resp));
+        assert!(!valid(server.post("/eth/v1/verify").json(&req(None)).await));
+        let resp = server
+            .post("/eth/v1/verify")
+            .json(&req(Some("00".repeat(32))))
+            .await;
+        assert!(!valid(resp));
+        let resp = server
+            .post("/eth/v1/verify")
+            .json(&req(Some("00".repeat(31))))
+            .await;
+        assert_eq!(resp.status_code(), 400);
+
+        let gvr: crate::eth2::eth_types::Root = hex::decode(&gvr_hex).unwrap().try_into().unwrap();
+        assert!(crate::eth2::eth_types::unregister_network(&gvr));
+    }
+
+    #[tokio::test]
+    async fn test_verify_aggregates() {
+        use crate::enclave::types::AggregateVerifyRequest;
+        let app = axum::Router::new()
+            .route(
+                "/eth/v1/verify/aggregate",
+                axum::routing::post(super::aggregate_handler),
+            )
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+
+        let sks: Vec<blsttc::SecretKey> = (0..3).map(|_| blsttc::SecretKey::random()).collect();
+        let pubkeys: Vec<String> = sks
+            .iter()
+            .map(|sk| format!("0x{}", sk.public_key().to_hex()))
+            .collect();
+        let msgs: Vec<Vec<u8>> = (0..3_u8).map(|i| vec![i; 32]).collect();
+        let aggregate = |sigs: Vec<blsttc::Signature>| {
+            let agg = crate::crypto::bls_keys::aggregate_bls_sigs(&sigs).unwrap();
+            format!("0x{}", hex::encode(agg.to_bytes()))
+        };
+        let uniform_sig = aggregate(sks.iter().map(|sk| sk.sign(&msgs[0])).collect());
+        let distinct_sig = aggregate(
+            sks.iter()
+                .zip(msgs.iter())
+                .map(|(sk, msg)| sk.sign(msg))
+                .collect(),
+        );
+        let verify = |agg_sig: &String, per_key: Option<&[Vec<u8>]>, uniform: Option<bool>| {
+            let req = AggregateVerifyRequest {
+                agg_sig: agg_sig.clone(),
+                pubkeys: pubkeys.clone(),
+                msg: hex::encode(&msgs[0]),
+                msgs: per_key.map(|msgs| msgs.iter().map(hex::encode).collect()),
+                uniform,
+                genesis_validators_root: None,
+            };
+            server.post("/eth/v1/verify/aggregate").json(&req)
+        };
+        let valid = |resp: axum_test::TestResponse| -> bool {
+            assert_eq!(resp.status_code(), 200);
+            serde_json::from_slice::<VerifyResponse>(resp.as_bytes())
+                .unwrap()
+                .valid
+        };
+
+        // Uniform, the default mode
+        assert!(valid(verify(&uniform_sig, None, None).await));
+        assert!(valid(verify(&uniform_sig, None, Some(true)).await));
+        assert!(!valid(verify(&distinct_sig, None, None).await));
+
+        // Non-uniform, over one distinct message per public key
+        assert!(valid(
+            verify(&distinct_sig, Some(&msgs[..]), Some(false)).await
+        ));
+        assert!(!valid(
+            verify(&uniform_sig, Some(&msgs[..]), Some(false)).await
+        ));
+        let mut swapped = msgs.clone();
+        swapped.swap(0, 1);
+        assert!(!valid(
+            verify(&distinct_sig, Some(&swapped[..]), Some(false)).await
+        ));
+
+        // Malformed requests and keys outside the subgroup are refused
+        let resp = verify(&uniform_sig, Some(&msgs[..2]), Some(false)).await;
+        assert_eq!(resp.status_code(), 400);
+
+        // Non-uniform mode refuses repeated messages, which are only safe in uniform mode
+        let resp = verify(&uniform_sig, None, Some(false)).await;
+        assert_eq!(resp.status_code(), 400);
+        let repeated = vec![msgs[0].clone(); 3];
+        let resp = verify(&uniform_sig, Some(&repeated[..]), Some(false)).await;
+        assert_eq!(resp.status_code(), 400);
+
+        // Requests naming more keys than are verified at once are refused before any pairing
+        let req = AggregateVerifyRequest {
+            agg_sig: uniform_sig.clone(),
+            pubkeys: vec![pubkeys[0].clone(); crate::constants::MAX_AGGREGATE_VERIFY_KEYS + 1],
+            msg: hex::encode(&msgs[0]),
+            msgs: None,
+            uniform: None,
+            genesis_validators_root: None,
+        };
+        let resp = server.post("/eth/v1/verify/aggregate").json(&req).await;
+        assert_eq!(resp.status_code(), 400);
+        assert!(resp.text().contains("at most"));
+        let mut infinity = vec![0_u8; 48];
+        infinity[0] = 0xc0;
+        let req = AggregateVerifyRequest {
+            agg_sig: uniform_sig.clone(),
+            pubkeys: vec![pubkeys[0].clone(), hex::encode(infinity)],
+            msg: hex::encode(&msgs[0]),
+            msgs: None,
+            uniform: None,
+            genesis_validators_root: None,
+        };
+        let resp = server.post("/eth/v1/verify/aggregate").json(&req).await;
+        assert_eq!(resp.status_code(), 400);
+    }
+}
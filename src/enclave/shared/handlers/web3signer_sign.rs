@@ -0,0 +1,122 @@
+use axum::{
+    extract::{rejection::JsonRejection, Path, Query, State},
+    response::IntoResponse,
+    Json,
+};
+use log::error;
+
+/// The Web3Signer `/api/v1/eth2/sign/{identifier}` endpoint. Signs through
+/// `secure_sign_bls::handler`, but answers 400 rather than 422 for a request whose `type` is
+/// unknown, as Web3Signer does.
+pub async fn handler(
+    path: Path<String>,
+    state: State<crate::enclave::shared::handlers::AppState>,
+    query: Query<crate::enclave::shared::handlers::SignQuery>,
+    body: Result<Json<serde_json::Value>, JsonRejection>,
+) -> axum::response::Response {
+    let Json(body) = match body {
+        Ok(body) => body,
+        Err(e) => return e.into_response(),
+    };
+    match crate::eth2::eth_signing::SignRequest::from_value(body) {
+        Ok(req) => {
+            crate::enclave::shared::handlers::secure_sign_bls::handler(
+                path,
+                state,
+                query,
+                Json(req),
+            )
+            .await
+        }
+        Err(e) if crate::eth2::eth_signing::UnknownSigningType::is_cause_of(&e) => {
+            error!("Unknown signing type: {}", e);
+            (axum::http::status::StatusCode::BAD_REQUEST, e.to_string()).into_response()
+        }
+        Err(e) => (
+            axum::http::status::StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "Failed to deserialize the JSON body into the target type: {}",
+                e
+            ),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enclave::types::SignatureResponse;
+    use crate::eth2::slash_protection::SlashingProtectionData;
+
+    #[tokio::test]
+    async fn test_web3signer_sign_types() {
+        let sk_set = crate::crypto::bls_keys::new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        let pk_hex = pk.to_hex();
+        crate::crypto::bls_keys::save_bls_key(&sk_set).unwrap();
+        SlashingProtectionData::from_pk_hex(&pk_hex)
+            .unwrap()
+            .write()
+            .unwrap();
+
+        let app = axum::Router::new()
+            .route(
+                "/api/v1/eth2/sign/:bls_pk_hex",
+                axum::routing::post(super::handler),
+            )
+            .with_state(crate::enclave::shared::handlers::AppState::default())
+            .into_make_service();
+        let server = axum_test::TestServer::new(app).unwrap();
+        let url = format!("/api/v1/eth2/sign/0x{pk_hex}");
+        let fork_info = serde_json::json!({
+            "fork": {
+                "previous_version": "0x03000000",
+                "current_version": "0x04000000",
+                "epoch": "2"
+            },
+            "genesis_validators_root": format!("0x{}", "2a".repeat(32))
+        });
+
+        let exit = serde_json::json!({
+            "type": "VOLUNTARY_EXIT",
+            "fork_info": fork_info,
+            "voluntary_exit": { "epoch": "3", "validator_index": "7" }
+        });
+        let resp = server.post(&url).json(&exit).await;
+        assert_eq!(resp.status_code(), 200);
+        let resp: SignatureResponse = serde_json::from_slice(resp.as_bytes()).unwrap();
+        let sig = crate::crypto::bls_keys::bls_sig_from_hex(&resp.signature).unwrap();
+        let msg: crate::eth2::eth_signing::BLSSignMsg = serde_json::from_value(exit).unwrap();
+        assert!(pk.verify(&sig, msg.to_signing_root(None)));
+
+        // Attestations go through the slash protection DB
+        let attestation = serde_json::json!({
+            "type": "ATTESTATION",
+            "fork_info": fork_info,
+            "attestation": {
+                "slot": "100",
+                "index": "0",
+                "beacon_block_root": format!("0x{}", "11".repeat(32)),
+                "source": { "epoch": "2", "root": format!("0x{}", "22".repeat(32)) },
+                "target": { "epoch": "3", "root": format!("0x{}", "33".repeat(32)) }
+            }
+        });
+        let resp = server.post(&url).json(&attestation).await;
+        assert_eq!(resp.status_code(), 200);
+        let resp = server.post(&url).json(&attestation).await;
+        assert_eq!(resp.status_code(), 412);
+
+        let unknown = serde_json::json!({ "type": "DEPOSIT_V9", "fork_info": fork_info });
+        let resp = server.post(&url).json(&unknown).await;
+        assert_eq!(resp.status_code(), 400);
+        assert!(resp.text().contains("Unknown signing type DEPOSIT_V9"));
+        assert!(resp.text().contains("BLOCK_V2"));
+
+        // Malformed bodies of a known type are still unprocessable
+        let malformed = serde_json::json!({ "type": "VOLUNTARY_EXIT", "fork_info": fork_info });
+        let resp = server.post(&url).json(&malformed).await;
+        assert_eq!(resp.status_code(), 422);
+
+        crate::io::key_management::delete_bls_key(&pk_hex).unwrap();
+    }
+}
@@ -8,11 +8,12 @@ use axum::{
 use log::{error, info};
 use sha3::Digest;
 
-/// Signs the specific type of request
+/// Signs the specific type of request, including the affine G2 point in the response if `affine`
 /// Maintains compatibility with https://consensys.github.io/web3signer/web3signer-eth2.html#tag/Signing
 pub fn sign_validator_message(
     Path(bls_pk_hex): Path<String>,
     State(state): State<crate::enclave::shared::handlers::AppState>,
+    affine: bool,
     Json(req): Json<crate::eth2::eth_signing::BLSSignMsg>,
 ) -> axum::response::Response {
     info!("secure_sign_bls()");
@@ -33,137 +34,248 @@ pub fn sign_validator_message(
     info!("Request for validator pubkey: {bls_pk_hex}");
     info!("Request:\n{:#?}", serde_json::to_string_pretty(&req));
 
-    // Verify not a slashable msg
-    match crate::enclave::shared::is_slashable(&bls_pk_hex, &req) {
-        Ok(b) => match b {
-            true => {
-                return (
-                    axum::http::status::StatusCode::PRECONDITION_FAILED,
-                    format!("Signing operation failed due to slashing protection rules"),
-                )
-                    .into_response()
-            }
-            false => {}
-        },
-        Err(e) => {
+    // The key must be known to this signer
+    if !crate::io::key_management::bls_key_exists(&bls_pk_hex) {
+        error!("Unknown BLS public key: {bls_pk_hex}");
+        let resp = crate::enclave::types::ErrorResponse::unknown_key(&bls_pk_hex);
+        return (axum::http::status::StatusCode::NOT_FOUND, Json(resp)).into_response();
+    }
+
+    // Guard against signing under the wrong domain
+    if let Err(e) = req.check_fork_version(state.allow_genesis_fork) {
+        error!("Bad fork version: {:?}", e);
+        return (
+            axum::http::status::StatusCode::BAD_REQUEST,
+            format!("Signing operation failed: {:?}", e),
+        )
+            .into_response();
+    }
+
+    // Never sign a contribution for a duty the validator was not selected for
+    if !state.trust_selection_proofs {
+        if let Err(e) = req.check_selection_proof(&bls_pk_hex) {
+            error!("Bad selection proof: {:?}", e);
             return (
-                axum::http::status::StatusCode::INTERNAL_SERVER_ERROR,
+                axum::http::status::StatusCode::BAD_REQUEST,
                 format!("Signing operation failed: {:?}", e),
             )
-                .into_response()
+                .into_response();
         }
-    };
+    }
+
+    // Never register a validator with a fee recipient the operator has not approved
+    if let Err(e) = req.check_fee_recipient(&bls_pk_hex, &state.fee_recipient_policy) {
+        error!("Fee recipient check failed: {:?}", e);
+        return (
+            axum::http::status::StatusCode::FORBIDDEN,
+            format!("Signing operation failed: {:?}", e),
+        )
+            .into_response();
+    }
 
     // Compute the msg to be signed
     let signing_root: crate::eth2::eth_types::Root =
         req.to_signing_root(Some(state.genesis_fork_version));
     info!("signing_root: {}", hex::encode(signing_root));
 
-    // Update the slash protection DB if msg was a block or attestation
-    if req.can_be_slashed() {
-        if let Err(e) = crate::enclave::shared::update_slash_protection_db(&bls_pk_hex, &req) {
-            error!("Failed trying to update slash protection database");
-            return (
-                axum::http::status::StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Signing operation failed: {:?}", e),
-            )
-                .into_response();
+    let sig = match sign_and_record(
+        &bls_pk_hex,
+        req.signing_type(),
+        req.slashable_fields(),
+        signing_root,
+        req.dst(),
+    ) {
+        Ok(sig) => sig,
+        Err(resp) => return resp,
+    };
+
+    let mut response = crate::enclave::types::SignatureResponse::new(&sig.to_bytes());
+    if state.debug_responses {
+        response = response.with_signing_object_ssz(&req.signing_object_ssz());
+        if let crate::eth2::eth_signing::BLSSignMsg::ATTESTATION(m)
+        | crate::eth2::eth_signing::BLSSignMsg::attestation(m) = &req
+        {
+            response = response.with_attestation(&m.attestation);
         }
     }
+    if let crate::eth2::eth_signing::BLSSignMsg::SYNC_COMMITTEE_SELECTION_PROOF(_)
+    | crate::eth2::eth_signing::BLSSignMsg::sync_committee_selection_proof(_) = &req
+    {
+        response = response.with_is_aggregator(&sig);
+    }
+    signature_response(response, &sig, affine)
+}
 
-    // Sign the message
-    match crate::crypto::bls_keys::bls_agg_sign_from_saved_sk(&bls_pk_hex, &signing_root) {
-        Ok(sig) => {
-            info!("signature: {:?}", hex::encode(sig.to_bytes()));
-            let response = crate::enclave::types::SignatureResponse::new(&sig.to_bytes());
-            (axum::http::status::StatusCode::OK, Json(response)).into_response()
-        }
+// Answers 200 with `response`, first adding the affine G2 point of `sig` if `affine`
+fn signature_response(
+    response: crate::enclave::types::SignatureResponse,
+    sig: &blsttc::Signature,
+    affine: bool,
+) -> axum::response::Response {
+    if !affine {
+        return (axum::http::status::StatusCode::OK, Json(response)).into_response();
+    }
+    match response.with_affine(sig) {
+        Ok(response) => (axum::http::status::StatusCode::OK, Json(response)).into_response(),
         Err(e) => {
-            error!("Failed trying to sign");
-            return (
+            error!("Failed to encode affine signature: {:?}", e);
+            (
                 axum::http::status::StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Signing operation failed: {:?}", e),
             )
-                .into_response();
+                .into_response()
         }
     }
 }
 
-/// Returns true if signing_data is a block proposal or attestation and is slashable
-fn is_slashable(
+/// Signs a client-supplied SSZ container of `req.signing_type` under `req.domain`, see
+/// `eth_signing::ssz_signing_root`. The fork is checked and slashable types are checked against
+/// and recorded in the slash protection DB exactly as for JSON requests. The response includes
+/// the affine G2 point if `affine`.
+pub fn sign_ssz_message(
     bls_pk_hex: &String,
-    signing_data: &crate::eth2::eth_signing::BLSSignMsg,
-) -> Result<bool> {
-    // The slashing DB must exist
-    let db: crate::eth2::slash_protection::SlashingProtectionData =
-        crate::eth2::slash_protection::SlashingProtectionData::read(bls_pk_hex.as_str())?;
-
-    match signing_data {
-        crate::eth2::eth_signing::BLSSignMsg::BLOCK(m)
-        | crate::eth2::eth_signing::BLSSignMsg::block(m) => {
-            Ok(db.is_slashable_block_slot(m.block.slot))
+    req: &crate::enclave::shared::handlers::secure_sign_ssz::SszSignRequest,
+    ssz_bytes: &[u8],
+    state: &crate::enclave::shared::handlers::AppState,
+    affine: bool,
+) -> axum::response::Response {
+    info!("secure_sign_ssz()");
+
+    // Sanitize the input bls_pk_hex
+    let bls_pk_hex = match crate::crypto::bls_keys::sanitize_bls_pk_hex(bls_pk_hex) {
+        Ok(pk) => pk,
+        Err(e) => {
+            error!("Bad BLS public key format: {bls_pk_hex}");
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Bad bls_pk_hex, {:?}", e),
+            )
+                .into_response();
         }
-        crate::eth2::eth_signing::BLSSignMsg::BLOCK_V2(m)
-        | crate::eth2::eth_signing::BLSSignMsg::block_v2(m) => {
-            Ok(db.is_slashable_block_slot(m.beacon_block.block_header.slot))
+    };
+
+    // The key must be known to this signer
+    if !crate::io::key_management::bls_key_exists(&bls_pk_hex) {
+        error!("Unknown BLS public key: {bls_pk_hex}");
+        let resp = crate::enclave::types::ErrorResponse::unknown_key(&bls_pk_hex);
+        return (axum::http::status::StatusCode::NOT_FOUND, Json(resp)).into_response();
+    }
+
+    let signing_type = req.signing_type.as_str();
+    let domain = req.domain;
+    let (signing_root, fields) =
+        match crate::eth2::eth_signing::ssz_signing_root(signing_type, ssz_bytes, domain) {
+            Ok(r) => r,
+            Err(e) => {
+                error!("Bad SSZ signing request: {:?}", e);
+                return (
+                    axum::http::status::StatusCode::BAD_REQUEST,
+                    format!("Signing operation failed: {:?}", e),
+                )
+                    .into_response();
+            }
+        };
+    info!("signing_root: {}", hex::encode(signing_root));
+
+    // Guard against signing under the wrong domain
+    let dst = match crate::eth2::eth_signing::check_ssz_fork(
+        signing_type,
+        domain,
+        req.fork_version,
+        req.genesis_validators_root,
+        state.allow_genesis_fork,
+    ) {
+        Ok(dst) => dst,
+        Err(e) => {
+            error!("Bad fork version: {:?}", e);
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Signing operation failed: {:?}", e),
+            )
+                .into_response();
         }
+    };
 
-        crate::eth2::eth_signing::BLSSignMsg::ATTESTATION(m)
-        | crate::eth2::eth_signing::BLSSignMsg::attestation(m) => Ok(db
-            .is_slashable_attestation_epochs(
-                m.attestation.source.epoch,
-                m.attestation.target.epoch,
-            )),
-        _ => {
-            // Only block proposals and attestations are slashable
-            Ok(false)
+    // Never sign a contribution for a duty the validator was not selected for
+    if !state.trust_selection_proofs {
+        if let Err(e) = crate::eth2::eth_signing::check_ssz_selection_proof(
+            signing_type,
+            ssz_bytes,
+            domain,
+            &bls_pk_hex,
+            dst,
+        ) {
+            error!("Bad selection proof: {:?}", e);
+            return (
+                axum::http::status::StatusCode::BAD_REQUEST,
+                format!("Signing operation failed: {:?}", e),
+            )
+                .into_response();
         }
     }
+
+    if let Err(e) = crate::eth2::eth_signing::check_ssz_fee_recipient(
+        signing_type,
+        ssz_bytes,
+        &bls_pk_hex,
+        &state.fee_recipient_policy,
+    ) {
+        error!("Fee recipient check failed: {:?}", e);
+        return (
+            axum::http::status::StatusCode::FORBIDDEN,
+            format!("Signing operation failed: {:?}", e),
+        )
+            .into_response();
+    }
+
+    let signing_type = signing_type.to_uppercase();
+    match sign_and_record(&bls_pk_hex, &signing_type, fields, signing_root, dst) {
+        Ok(sig) => signature_response(
+            crate::enclave::types::SignatureResponse::new(&sig.to_bytes()),
+            &sig,
+            affine,
+        ),
+        Err(resp) => resp,
+    }
 }
 
-fn update_slash_protection_db(
+/// Signs through `eth_signing::sign_and_record`, returning the error response to send if the
+/// signature was withheld
+fn sign_and_record(
     bls_pk_hex: &String,
-    signing_data: &crate::eth2::eth_signing::BLSSignMsg,
-) -> Result<()> {
-    info!("update_slash_protection_db()");
-    let mut db: crate::eth2::slash_protection::SlashingProtectionData =
-        crate::eth2::slash_protection::SlashingProtectionData::read(bls_pk_hex.as_str())?;
-    let signing_root = signing_data.to_signing_root(None);
-    match signing_data {
-        crate::eth2::eth_signing::BLSSignMsg::BLOCK(m)
-        | crate::eth2::eth_signing::BLSSignMsg::block(m) => {
-            let b = crate::eth2::slash_protection::SignedBlockSlot {
-                slot: m.block.slot,
-                signing_root: Some(signing_root),
-            };
-            db.new_block(b, crate::constants::ALLOW_GROWABLE_SLASH_PROTECTION_DB)?;
-            db.write()
-        }
-        crate::eth2::eth_signing::BLSSignMsg::BLOCK_V2(m)
-        | crate::eth2::eth_signing::BLSSignMsg::block_v2(m) => {
-            let b = crate::eth2::slash_protection::SignedBlockSlot {
-                slot: m.beacon_block.block_header.slot,
-                signing_root: Some(signing_root),
-            };
-            db.new_block(b, crate::constants::ALLOW_GROWABLE_SLASH_PROTECTION_DB)?;
-            db.write()
-        }
-        crate::eth2::eth_signing::BLSSignMsg::ATTESTATION(m)
-        | crate::eth2::eth_signing::BLSSignMsg::attestation(m) => {
-            let a = crate::eth2::slash_protection::SignedAttestationEpochs {
-                source_epoch: m.attestation.source.epoch,
-                target_epoch: m.attestation.target.epoch,
-                signing_root: Some(signing_root),
-            };
-            db.new_attestation(a, crate::constants::ALLOW_GROWABLE_SLASH_PROTECTION_DB)?;
-            db.write()
-        }
-        _ => {
-            // Only block proposals and attestations are slashable
-            error!("Attempted to update slash protection db with non-slashable msg type");
-            bail!("Should not update slash protection db for non blocks/attestations")
-        }
+    signing_type: &str,
+    fields: Option<crate::eth2::eth_signing::SlashableFields>,
+    signing_root: crate::eth2::eth_types::Root,
+    dst: &[u8],
+) -> std::result::Result<blsttc::Signature, axum::response::Response> {
+    crate::eth2::eth_signing::sign_and_record(bls_pk_hex, signing_type, fields, signing_root, dst)
+        .map_err(|e| {
+            error!("Signing operation failed: {:?}", e);
+            sign_error_response(e)
+        })
+}
+
+/// The response to a signature withheld by `eth_signing::sign_and_record`
+pub(crate) fn sign_error_response(
+    e: crate::eth2::eth_signing::SignError,
+) -> axum::response::Response {
+    use crate::eth2::eth_signing::SignError;
+    use axum::http::status::StatusCode;
+    match e {
+        SignError::KeyRole(_) => (
+            StatusCode::FORBIDDEN,
+            format!("Signing operation failed: {}", e),
+        ),
+        SignError::Compacting => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
+        SignError::Slashable(_) => (StatusCode::PRECONDITION_FAILED, e.to_string()),
+        // Failing closed on an unwritable DB is transient, e.g. a full disk, so the client retries
+        SignError::Record(_) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
+        SignError::Internal(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Signing operation failed: {}", e),
+        ),
     }
+    .into_response()
 }
 
 pub fn build_validator_remote_attestation_payload(
@@ -1,4 +1,4 @@
-use crate::io::remote_attestation::AttestationEvidence;
+use crate::io::remote_attestation::{AttestationEvidence, ReportDataLayout};
 use crate::{crypto::eth_keys, strip_0x_prefix};
 use anyhow::{bail, Result};
 use blsttc::{PublicKey as BlsPublicKey, PublicKeySet};
@@ -31,7 +31,7 @@ impl KeyGenResponse {
 
     pub fn validate_eth_ra(&self, mrenclave: &String) -> Result<EthPublicKey> {
         // Verify the report is valid
-        self.evidence.verify_intel_signing_certificate()?;
+        self.evidence.verify()?;
 
         // Verify the MRENCLAVE measurement is valid
         let mrenclave: String = strip_0x_prefix!(mrenclave);
@@ -46,17 +46,15 @@ impl KeyGenResponse {
         // Read the 64B payload from RA report
         let got_payload: [u8; 64] = self.evidence.get_report_data()?;
 
-        // Verify the first ETH_COMPRESSED_PK_BYTES of report contains the expected ETH comporessed public key
+        // Verify the report commits to the expected ETH compressed public key
         // TODO: Ideally this should be uncompressed
-        if &got_payload[0..crate::constants::ETH_COMPRESSED_PK_BYTES] != pk.serialize_compressed() {
-            bail!("Remote attestation payload does not match the expected")
-        }
+        ReportDataLayout::default().verify(&got_payload, &pk.serialize_compressed(), None)?;
         Ok(pk)
     }
 
     pub fn validate_bls_ra(&self, mrenclave: &String) -> Result<BlsPublicKey> {
         // Verify the report is valid
-        self.evidence.verify_intel_signing_certificate()?;
+        self.evidence.verify()?;
 
         // Verify the MRENCLAVE measurement is valid
         let mrenclave: String = strip_0x_prefix!(mrenclave);
@@ -66,20 +64,27 @@ impl KeyGenResponse {
         }
 
         // Verify the payload
-        let pk_hex: String = strip_0x_prefix!(&self.pk_hex);
-        let pk = BlsPublicKey::from_hex(&pk_hex)?;
+        let pk = crate::crypto::bls_keys::bls_pk_from_hex(&self.pk_hex)?;
 
         // Read the 64B payload from RA report
         let got_payload: [u8; 64] = self.evidence.get_report_data()?;
 
-        // Verify the first BLS_PUB_KEY_BYTES of report contains the expected BLS comporessed public key
-        if &got_payload[0..crate::constants::BLS_PUB_KEY_BYTES] != pk.to_bytes() {
-            bail!("Remote attestation payload does not match the expected")
-        }
+        // Verify the report commits to the expected BLS compressed public key
+        ReportDataLayout::default().verify(&got_payload, &pk.to_bytes(), None)?;
         Ok(pk)
     }
 }
 
+/// One NDJSON line of a batch keygen, sent once the `generated`th of `total` keys is saved and
+/// attested
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BatchKeygenProgress {
+    pub generated: usize,
+    pub total: usize,
+    #[serde(flatten)]
+    pub key: KeyGenResponse,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub struct ListKeysResponseInner {
     pub pubkey: String,
@@ -110,18 +115,396 @@ impl ListKeysResponse {
     }
 }
 
+/// A saved BLS key as listed by the Keymanager API's `GET /eth/v1/keystores`
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct KeystoreInfo {
+    pub validating_pubkey: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivation_path: Option<String>,
+    pub readonly: bool,
+    /// `validating_pubkey` again, kept for clients of the `ListKeysResponse` listing
+    pub pubkey: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ListKeystoresResponse {
+    pub data: Vec<KeystoreInfo>,
+}
+
+/// Keymanager API `POST /eth/v1/keystores` body. `passwords[i]` decrypts `keystores[i]`, each an
+/// EIP-2335 keystore JSON string, and `slashing_protection` is an EIP-3076 interchange JSON string.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ImportKeystoresRequest {
+    pub keystores: Vec<String>,
+    pub passwords: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slashing_protection: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportKeystoreStatus {
+    Imported,
+    Duplicate,
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteKeystoreStatus {
+    Deleted,
+    NotFound,
+    Error,
+}
+
+/// The outcome for one keystore or pubkey of a Keymanager API request, in request order
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct KeystoreStatus<S> {
+    pub status: S,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ImportKeystoresResponse {
+    pub data: Vec<KeystoreStatus<ImportKeystoreStatus>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DeleteKeystoresRequest {
+    pub pubkeys: Vec<String>,
+}
+
+/// Keymanager API `DELETE /eth/v1/keystores` response, with the EIP-3076 interchange JSON string
+/// of the deleted keys so they can be safely moved to another signer
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DeleteKeystoresResponse {
+    pub data: Vec<KeystoreStatus<DeleteKeystoreStatus>>,
+    pub slashing_protection: String,
+}
+
+/// A BLS secret key envelope encrypted to a provisioning key, see `bls_key_provision`
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BlsKeyProvisionRequest {
+    /// Hex encoded public key of the provisioning key, may be omitted while the ring holds one key
+    #[serde(default)]
+    pub provisioning_key_id: Option<String>,
+    /// Hex encoded envelope, see `eth_keys::envelope_encrypt`
+    pub encrypted_sk: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BlsKeyProvisionResponse {
+    pub pubkey: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct KeyInfo {
+    pub pubkey: String,
+    #[serde(rename = "type")]
+    pub key_type: crate::io::key_management::KeyType,
+    pub attested: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ListKeyInfoResponse {
+    pub data: Vec<KeyInfo>,
+}
+
+impl ListKeyInfoResponse {
+    pub fn new(entries: Vec<crate::io::key_management::KeyEntry>) -> ListKeyInfoResponse {
+        let data = entries.into_iter().map(KeyInfo::from).collect();
+        ListKeyInfoResponse { data }
+    }
+}
+
+impl From<crate::io::key_management::KeyEntry> for KeyInfo {
+    fn from(e: crate::io::key_management::KeyEntry) -> KeyInfo {
+        let pk_hex: String = strip_0x_prefix!(e.pk_hex);
+        KeyInfo {
+            pubkey: format!("0x{}", pk_hex),
+            key_type: e.key_type,
+            attested: e.attested,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct NonceResponse {
+    pub nonce: String,
+    pub ttl_secs: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CommitmentSignRequest {
+    /// Hex encoded 4B non-consensus domain type the commitment is signed under
+    pub domain_type: String,
+    /// Hex encoded bytes, hash-tree-rooted as a `List[byte]`
+    pub data: String,
+    /// Single-use nonce from `NonceResponse`, consumed by this request
+    pub nonce: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct EthAddressRequest {
+    /// SECP256K1 public key in compressed or uncompressed form
+    pub pk_hex: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct EthAddressResponse {
+    pub eth_address: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct VerifyRequest {
+    pub pubkey: String,
+    /// The 32B signing root the signature is over
+    pub signing_root: String,
+    pub signature: String,
+    /// Hex encoded genesis_validators_root of the network the signature is for, selecting its
+    /// DST, see `eth_types::network_dst`. The default DST is used if omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genesis_validators_root: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct VerifyResponse {
+    pub valid: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AggregateVerifyRequest {
+    pub agg_sig: String,
+    pub pubkeys: Vec<String>,
+    /// The message every public key signed in uniform mode
+    pub msg: String,
+    /// One distinct message per public key, required for non-uniform aggregates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub msgs: Option<Vec<String>>,
+    /// Fast-aggregate verification over `msg` if true (the default), aggregate verification
+    /// otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uniform: Option<bool>,
+    /// Hex encoded genesis_validators_root of the network the signature is for, selecting its
+    /// DST, see `eth_types::network_dst`. The default DST is used if omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genesis_validators_root: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ThresholdSignRequest {
+    /// The 32B hash tree root of the message, signed under `domain`
+    pub object_root: String,
+    pub domain: String,
+    /// Group ids (hex encoded public key shares) of saved shares of a single group
+    pub shares: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PartialSignature {
+    pub share: String,
+    /// The index the partial signature must be combined under
+    pub index: usize,
+    pub signature: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ThresholdSignResponse {
+    pub signing_root: String,
+    pub group_pubkey: String,
+    /// One per requested share, in request order
+    pub partial_signatures: Vec<PartialSignature>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LightClientSignRequest {
+    pub header: crate::eth2::eth_types::BeaconBlockHeader,
+    pub fork_info: crate::eth2::eth_types::ForkInfo,
+    /// The slot of the light client update the header is signed for
+    #[serde(with = "serde_utils::quoted_u64")]
+    pub signature_slot: crate::eth2::eth_types::Slot,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LightClientSignResponse {
+    pub header_root: String,
+    pub signature: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ValidatorIndexRequest {
+    pub pubkey: String,
+    /// Quoted index to map, queried from the configured beacon node if omitted
+    #[serde(default)]
+    pub validator_index: Option<String>,
+    /// Remap an index already mapped to another key
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ValidatorIndexResponse {
+    pub pubkey: String,
+    pub validator_index: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ErrorResponseInner {
+    pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ErrorResponse {
+    pub error: ErrorResponseInner,
+}
+
+impl ErrorResponse {
+    pub fn unknown_validator_index() -> Self {
+        ErrorResponse {
+            error: ErrorResponseInner {
+                code: "UNKNOWN_VALIDATOR_INDEX".to_string(),
+                pubkey: None,
+            },
+        }
+    }
+
+    pub fn unknown_key(pk_hex: &str) -> Self {
+        let pk_hex: String = strip_0x_prefix!(pk_hex);
+        ErrorResponse {
+            error: ErrorResponseInner {
+                code: "UNKNOWN_KEY".to_string(),
+                pubkey: Some(format!("0x{}", pk_hex)),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct BuildInfoResponse {
+    pub version: String,
+    pub bls_library: String,
+    pub cipher_suite: String,
+    pub features: Vec<String>,
+}
+
+impl BuildInfoResponse {
+    pub fn current() -> Self {
+        let mut features = vec![];
+        if cfg!(feature = "sgx") {
+            features.push("sgx".to_string());
+        }
+        BuildInfoResponse {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            bls_library: crate::constants::BLS_LIBRARY.to_string(),
+            cipher_suite: crate::constants::CIPHER_SUITE.to_string(),
+            features,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SigningCapability {
+    #[serde(rename = "type")]
+    pub signing_type: String,
+    pub slashing_protection: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CapabilitiesResponse {
+    pub signing_types: Vec<SigningCapability>,
+    pub slashing_protection: bool,
+    pub threshold_signing: bool,
+    pub features: Vec<String>,
+}
+
+impl CapabilitiesResponse {
+    pub fn current() -> Self {
+        let signing_types = crate::eth2::eth_signing::BLSSignMsg::SIGNING_TYPES
+            .iter()
+            .map(|t| SigningCapability {
+                signing_type: t.to_string(),
+                slashing_protection: crate::eth2::eth_signing::BLSSignMsg::is_slashable_type(t),
+            })
+            .collect();
+        CapabilitiesResponse {
+            signing_types,
+            slashing_protection: true,
+            threshold_signing: true,
+            features: BuildInfoResponse::current().features,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SignatureResponse {
     pub signature: String,
+    /// Only set in debug mode for ATTESTATION requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<crate::eth2::eth_types::AttestationData>,
+    /// Only set in debug mode for ATTESTATION requests, hash_tree_root(attestation)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation_root: Option<String>,
+    /// Only set if affine signatures are enabled, the 192B uncompressed G2 point x || y
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_affine: Option<String>,
+    /// Only set for SYNC_COMMITTEE_SELECTION_PROOF requests, whether the proof selects the signer
+    /// as a sync subcommittee aggregator
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_aggregator: Option<bool>,
+    /// Only set in debug mode, the SSZ serialization of the object whose hash tree root was signed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_object_ssz: Option<String>,
 }
 
 impl SignatureResponse {
     pub fn new(sig: &[u8]) -> Self {
         SignatureResponse {
             signature: format!("0x{}", hex::encode(sig)),
+            attestation: None,
+            attestation_root: None,
+            signature_affine: None,
+            is_aggregator: None,
+            signing_object_ssz: None,
         }
     }
 
+    /// Include `sig` as its uncompressed affine point, see `bls_keys::bls_sig_to_affine_bytes`
+    pub fn with_affine(mut self, sig: &blsttc::Signature) -> Result<Self> {
+        let affine = crate::crypto::bls_keys::bls_sig_to_affine_bytes(sig)?;
+        self.signature_affine = Some(format!("0x{}", hex::encode(affine)));
+        Ok(self)
+    }
+
+    /// Include whether the selection proof `sig` selects a sync subcommittee aggregator, see
+    /// `eth_signing::is_sync_committee_aggregator`
+    pub fn with_is_aggregator(mut self, sig: &blsttc::Signature) -> Self {
+        self.is_aggregator = Some(crate::eth2::eth_signing::is_sync_committee_aggregator(
+            &sig.to_bytes(),
+            crate::eth2::eth_types::SYNC_SUBCOMMITTEE_SIZE,
+        ));
+        self
+    }
+
+    /// Include the SSZ serialization of the signed object, see `BLSSignMsg::signing_object_ssz`
+    pub fn with_signing_object_ssz(mut self, ssz_bytes: &[u8]) -> Self {
+        self.signing_object_ssz = Some(format!("0x{}", hex::encode(ssz_bytes)));
+        self
+    }
+
+    /// Echo the normalized `AttestationData` that was signed and its hash tree root
+    pub fn with_attestation(
+        mut self,
+        attestation: &crate::eth2::eth_types::AttestationData,
+    ) -> Self {
+        let root = attestation.tree_hash_root().to_fixed_bytes();
+        self.attestation = Some(attestation.clone());
+        self.attestation_root = Some(format!("0x{}", hex::encode(root)));
+        self
+    }
+
     pub fn to_ssz_bytes(&self) -> Result<crate::eth2::eth_types::BLSSignature> {
         let sig_stripped: String = strip_0x_prefix!(self.signature.clone());
         let sig_bytes = hex::decode(sig_stripped)?;
@@ -189,9 +572,30 @@ pub struct BlsKeygenPayload {
     pub guardian_eth_pub_keys: Vec<String>,
     pub withdrawal_credentials: String,
     pub fork_version: crate::eth2::eth_types::Version,
+    /// The mode that produced the evidence, payloads from before DCAP support are EPID
+    #[serde(default)]
+    pub attestation_type: crate::io::remote_attestation::AttestationType,
+    /// Base64 ECDSA quote, set for DCAP evidence
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub dcap_quote: String,
+    /// The collateral the DCAP quote was produced against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dcap_collateral: Option<crate::io::dcap::DcapCollateral>,
 }
 
 impl BlsKeygenPayload {
+    /// The remote attestation evidence carried by this payload, of either attestation type
+    pub fn evidence(&self) -> crate::io::remote_attestation::AttestationEvidence {
+        crate::io::remote_attestation::AttestationEvidence {
+            raw_report: self.intel_report.clone(),
+            signed_report: self.intel_sig.clone(),
+            signing_cert: self.intel_x509.clone(),
+            attestation_type: self.attestation_type,
+            dcap_quote: self.dcap_quote.clone(),
+            dcap_collateral: self.dcap_collateral.clone(),
+        }
+    }
+
     pub fn public_key_set(&self) -> Result<PublicKeySet> {
         let sanitized: String = crate::strip_0x_prefix!(&self.bls_pub_key_set);
         Ok(PublicKeySet::from_bytes(hex::decode(sanitized)?)?)
@@ -210,11 +614,7 @@ impl BlsKeygenPayload {
     }
 
     pub fn signature(&self) -> Result<blsttc::Signature> {
-        let sanitized: String = crate::strip_0x_prefix!(&self.signature);
-        let mut sig_bytes: [u8; crate::constants::BLS_SIG_BYTES] =
-            [0; crate::constants::BLS_SIG_BYTES];
-        sig_bytes.copy_from_slice(&hex::decode(&sanitized)?);
-        Ok(blsttc::Signature::from_bytes(sig_bytes)?)
+        crate::crypto::bls_keys::bls_sig_from_hex(&self.signature)
     }
 
     pub fn deposit_message_root(&self) -> Result<crate::eth2::eth_types::Root> {
@@ -293,6 +693,153 @@ pub struct SignExitResponse {
     pub signature: String,
 }
 
+/// The public data of a validator handed over to an operator, see
+/// `secure_signer::export_validator_bundle`. Never carries the secret key.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ValidatorBundleBody {
+    /// Hex encoded BLS public key
+    pub pubkey: String,
+    pub withdrawal_credentials: String,
+    pub fork_version: crate::eth2::eth_types::Version,
+    pub deposit_data: crate::eth2::eth_types::DepositResponse,
+    /// Commits to the BLS public key, as in keygen responses
+    pub evidence: AttestationEvidence,
+}
+
+impl ValidatorBundleBody {
+    /// The canonical encoding signed into a `ValidatorBundle`: a version tag followed by every
+    /// field decoded to bytes, each prefixed by its 4B big endian length, so verifiers need not
+    /// reproduce any JSON encoding
+    pub fn signing_bytes(&self) -> Result<Vec<u8>> {
+        fn push(out: &mut Vec<u8>, field: &[u8]) {
+            out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            out.extend_from_slice(field);
+        }
+        fn decode(hex_str: &str) -> Result<Vec<u8>> {
+            let hex_str: &str = strip_0x_prefix!(hex_str);
+            Ok(hex::decode(hex_str)?)
+        }
+
+        let dd = &self.deposit_data;
+        let mut out = Vec::new();
+        push(&mut out, b"secure-signer-validator-bundle-v1");
+        push(&mut out, &decode(&self.pubkey)?);
+        push(&mut out, &decode(&self.withdrawal_credentials)?);
+        push(&mut out, &self.fork_version);
+        push(&mut out, &decode(&dd.pubkey)?);
+        push(&mut out, &decode(&dd.withdrawal_credentials)?);
+        push(&mut out, &dd.amount.to_be_bytes());
+        push(&mut out, &decode(&dd.signature)?);
+        push(&mut out, &decode(&dd.deposit_message_root)?);
+        push(&mut out, &decode(&dd.deposit_data_root)?);
+
+        let e = &self.evidence;
+        let attestation_type: u8 = match e.attestation_type {
+            crate::io::remote_attestation::AttestationType::Epid => 0,
+            crate::io::remote_attestation::AttestationType::Dcap => 1,
+        };
+        push(&mut out, &[attestation_type]);
+        push(&mut out, e.raw_report.as_bytes());
+        push(&mut out, e.signed_report.as_bytes());
+        push(&mut out, e.signing_cert.as_bytes());
+        push(&mut out, e.dcap_quote.as_bytes());
+        match &e.dcap_collateral {
+            Some(c) => {
+                push(&mut out, &[1]);
+                push(&mut out, c.tcb_info.as_bytes());
+                push(&mut out, c.qe_identity.as_bytes());
+                push(&mut out, c.tcb_signing_chain.as_bytes());
+                push(&mut out, c.pck_crl.as_bytes());
+            }
+            None => push(&mut out, &[0]),
+        }
+        Ok(out)
+    }
+}
+
+/// A `ValidatorBundleBody` signed by an attested ETH key of the signer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ValidatorBundle {
+    pub bundle: ValidatorBundleBody,
+    /// Hex encoded compressed public key of the ETH key that signed `bundle`
+    pub signer_pubkey: String,
+    /// Commits to `signer_pubkey`, as in ETH keygen responses
+    pub signer_evidence: AttestationEvidence,
+    /// Hex encoded ECDSA signature over keccak256 of `bundle.signing_bytes()`
+    pub signature: String,
+}
+
+impl ValidatorBundle {
+    /// Returns true if `signature` is by `signer_pubkey` over `bundle`. Fails unless
+    /// `signer_evidence` attests `signer_pubkey` was generated in the enclave `mrenclave`, so a
+    /// bundle re-signed by any other key is rejected.
+    pub fn verify_signature(&self, mrenclave: &String) -> Result<bool> {
+        let signer = KeyGenResponse {
+            pk_hex: self.signer_pubkey.clone(),
+            evidence: self.signer_evidence.clone(),
+        }
+        .validate_eth_ra(mrenclave)?;
+        self.verify_signature_by(&signer)
+    }
+
+    /// Returns true if `signature` is by `signer` over `bundle`, for a signer already trusted
+    pub fn verify_signature_by(&self, signer: &EthPublicKey) -> Result<bool> {
+        let sig_hex: String = strip_0x_prefix!(&self.signature);
+        let sig: [u8; crate::constants::ETH_SIGNATURE_BYTES] = hex::decode(sig_hex)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid bundle signature length"))?;
+        eth_keys::verify_message(&self.bundle.signing_bytes()?, &sig, signer)
+    }
+
+    /// Returns true if the deposit data is for `pubkey` and the bundled withdrawal credentials,
+    /// its roots recompute and its signature verifies under `fork_version`
+    pub fn verify_deposit_data(&self) -> Result<bool> {
+        let dd = &self.bundle.deposit_data;
+        let pk = crate::crypto::bls_keys::bls_pk_from_hex(&self.bundle.pubkey)?;
+        let wc_hex: String = strip_0x_prefix!(&self.bundle.withdrawal_credentials);
+        let withdrawal_credentials: [u8; 32] = hex::decode(wc_hex)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid withdrawal credentials length"))?;
+        if crate::crypto::bls_keys::bls_pk_from_hex(&dd.pubkey)? != pk
+            || hex::decode(&dd.withdrawal_credentials)? != withdrawal_credentials
+        {
+            return Ok(false);
+        }
+        let sig = crate::crypto::bls_keys::bls_sig_from_hex(&dd.signature)?;
+
+        let deposit_message = crate::eth2::eth_types::DepositMessage {
+            pubkey: pk.to_bytes().to_vec().into(),
+            withdrawal_credentials,
+            amount: dd.amount,
+        };
+        let deposit_data = crate::eth2::eth_types::DepositData {
+            pubkey: pk.to_bytes().to_vec().into(),
+            withdrawal_credentials,
+            amount: dd.amount,
+            signature: sig.to_bytes().to_vec().into(),
+        };
+        let domain = crate::eth2::eth_signing::compute_domain(
+            crate::eth2::eth_types::DOMAIN_DEPOSIT,
+            Some(self.bundle.fork_version),
+            None,
+        );
+        let signing_root =
+            crate::eth2::eth_signing::compute_signing_root(deposit_message.clone(), domain);
+        Ok(
+            hex::encode(deposit_message.tree_hash_root().to_fixed_bytes())
+                == dd.deposit_message_root
+                && hex::encode(deposit_data.tree_hash_root().to_fixed_bytes())
+                    == dd.deposit_data_root
+                && crate::crypto::bls_verify::verify(
+                    &pk,
+                    &signing_root,
+                    &sig,
+                    crate::constants::CIPHER_SUITE.as_bytes(),
+                ),
+        )
+    }
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct AttestFreshBlsKeyPayload {
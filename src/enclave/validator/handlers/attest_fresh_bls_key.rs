@@ -13,11 +13,15 @@ pub async fn handler(
         keygen_payload.do_remote_attestation,
     ) {
         Ok(keygen_result) => {
+            crate::io::webhook::emit(crate::io::webhook::AuditEvent::keygen(
+                crate::io::key_management::KeyType::Bls,
+                &keygen_result.bls_pub_key,
+            ));
             (axum::http::status::StatusCode::CREATED, Json(keygen_result)).into_response()
         }
         Err(e) => {
             error!("attest_fresh_bls_key() failed with: {:?}", e);
-            axum::http::status::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            crate::enclave::shared::handlers::attestation_error_status(&e).into_response()
         }
     }
 }
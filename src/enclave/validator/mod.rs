@@ -82,8 +82,7 @@ pub fn attest_fresh_bls_key(
     save_bls_key(&secret_key_set)?;
 
     // Create a new slashing protection database
-    crate::eth2::slash_protection::SlashingProtectionData::from_pk_hex(&validator_pubkey.to_hex())?
-        .write()?;
+    crate::eth2::slash_protection::init_slashing_db_if_missing(&validator_pubkey.to_hex())?;
 
     // sign DepositMessage to deposit 32 ETH to beacon deposit contract
     let (signature, deposit_data_root) = crate::eth2::eth_signing::sign_full_deposit(
@@ -132,9 +131,115 @@ pub fn attest_fresh_bls_key(
             .collect(),
         withdrawal_credentials: hex::encode(withdrawal_credentials),
         fork_version: fork_version,
+        attestation_type: evidence.attestation_type,
+        dcap_quote: evidence.dcap_quote,
+        dcap_collateral: evidence.dcap_collateral,
     }))
 }
 
+/// The routes served by the Validator binary. Every route that signs, generates keys, or changes
+/// signer state is refused while `app_state.verify_only` is set.
+pub fn router(app_state: crate::enclave::shared::handlers::AppState) -> axum::Router {
+    use crate::enclave::shared::handlers::refuse_if_verify_only;
+    axum::Router::new()
+        // Endpoint to check health
+        .route(
+            "/upcheck",
+            axum::routing::get(crate::enclave::shared::handlers::health::handler),
+        )
+        // Endpoint to report the signing types supported by this Validator
+        .route(
+            "/eth/v1/capabilities",
+            axum::routing::get(crate::enclave::shared::handlers::capabilities::handler),
+        )
+        // Endpoint to securely generate and save a BLS sk
+        .route(
+            "/bls/v1/keygen",
+            refuse_if_verify_only(
+                axum::routing::post(
+                    crate::enclave::validator::handlers::attest_fresh_bls_key::handler,
+                ),
+                &app_state,
+            ),
+        )
+        // Endpoint to list all pks of saved bls keys in the enclave
+        .route(
+            "/eth/v1/keystores",
+            axum::routing::get(crate::enclave::shared::handlers::keystores::list_handler),
+        )
+        // Endpoint to verify a BLS signature over a signing root
+        .route(
+            "/eth/v1/verify",
+            axum::routing::post(crate::enclave::shared::handlers::verify::handler),
+        )
+        // Endpoint to verify a BLS aggregate signature against its public keys and messages
+        .route(
+            "/eth/v1/verify/aggregate",
+            axum::routing::post(crate::enclave::shared::handlers::verify::aggregate_handler),
+        )
+        // Endpoint to list saved keys, optionally filtered by ?type=bls|eth and ?attested=true|false
+        .route(
+            "/eth/v1/keys",
+            axum::routing::get(crate::enclave::shared::handlers::list_keys::handler),
+        )
+        // Endpoint to compact the slashing protection DB down to its high-water marks
+        .route(
+            "/eth/v1/slashing/compact",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::slashing_compact::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to read the slashing protection high-water marks of a BLS key
+        .route(
+            "/eth/v1/slashing/:bls_pk_hex",
+            axum::routing::get(crate::enclave::shared::handlers::slashing_watermarks::handler),
+        )
+        // Endpoint to map a validator index to a saved BLS key
+        .route(
+            "/eth/v1/validator_index",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::validator_index::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to request a signature using the BLS sk mapped to a validator index
+        .route(
+            "/api/v1/eth2/sign/index/:validator_index",
+            refuse_if_verify_only(
+                axum::routing::post(
+                    crate::enclave::shared::handlers::secure_sign_bls::by_index_handler,
+                ),
+                &app_state,
+            ),
+        )
+        // Endpoint to request a signature using BLS sk
+        .route(
+            "/api/v1/eth2/sign/:bls_pk_hex",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::web3signer_sign::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to sign a light client update's attested header as a sync committee member
+        .route(
+            "/eth/v1/light_client/sign/:bls_pk_hex",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::light_client_sign::handler),
+                &app_state,
+            ),
+        )
+        // Endpoint to request a signature over a raw SSZ container using BLS sk
+        .route(
+            "/api/v1/eth2/ssz/sign/:bls_pk_hex",
+            refuse_if_verify_only(
+                axum::routing::post(crate::enclave::shared::handlers::secure_sign_ssz::handler),
+                &app_state,
+            ),
+        )
+        .with_state(app_state)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,37 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+struct ValidatorResponse {
+    data: ValidatorData,
+}
+
+#[derive(Deserialize, Debug)]
+struct ValidatorData {
+    #[serde(with = "serde_utils::quoted_u64")]
+    index: u64,
+}
+
+/// Looks up the validator index of `pk_hex` at the head state of the beacon node at `beacon_url`
+pub async fn fetch_validator_index(beacon_url: &str, pk_hex: &str) -> Result<u64> {
+    let pk_hex: &str = crate::strip_0x_prefix!(pk_hex);
+    let url = format!(
+        "{}/eth/v1/beacon/states/head/validators/0x{}",
+        beacon_url.trim_end_matches('/'),
+        pk_hex
+    );
+    let resp = reqwest::get(&url)
+        .await
+        .with_context(|| format!("Failed to reach beacon node at {beacon_url}"))?;
+    if !resp.status().is_success() {
+        bail!(
+            "Beacon node returned {} looking up validator 0x{pk_hex}",
+            resp.status()
+        )
+    }
+    let resp: ValidatorResponse = resp
+        .json()
+        .await
+        .with_context(|| "Failed to parse beacon node validator response")?;
+    Ok(resp.data.index)
+}
@@ -0,0 +1,119 @@
+use super::eth_signing::{compute_domain, compute_signing_root, get_deposit_signature};
+use super::eth_types::{DepositMessage, DepositResponse, Gwei, Root, Version, DOMAIN_DEPOSIT};
+use crate::constants::{CIPHER_SUITE, MIN_DEPOSIT_AMOUNT};
+use crate::crypto::{bls_keys, bls_verify};
+
+use anyhow::{bail, Result};
+
+/// Signs a deposit of `amount_gwei` for the saved BLS key `pk_hex` under `fork_version`, the
+/// genesis fork version of the target network, as the Web3Signer DEPOSIT path does. The signature
+/// is verified before the deposit data is returned, in the JSON shape of one entry of the
+/// staking-deposit-cli's deposit_data-*.json file.
+pub fn generate_deposit_data(
+    pk_hex: &String,
+    withdrawal_credentials: [u8; 32],
+    amount_gwei: Gwei,
+    fork_version: Version,
+) -> Result<DepositResponse> {
+    let pk_hex = bls_keys::sanitize_bls_pk_hex(pk_hex)?;
+    if amount_gwei < MIN_DEPOSIT_AMOUNT {
+        bail!("Deposit amount {amount_gwei} Gwei is below the minimum of {MIN_DEPOSIT_AMOUNT} Gwei")
+    }
+    let pk = bls_keys::bls_pk_from_hex(&pk_hex)?;
+
+    let deposit_message = DepositMessage {
+        pubkey: pk.to_bytes().to_vec().into(),
+        withdrawal_credentials,
+        amount: amount_gwei,
+    };
+    let dd = get_deposit_signature(pk_hex, deposit_message.clone(), fork_version)?;
+
+    let domain = compute_domain(DOMAIN_DEPOSIT, Some(fork_version), None);
+    let signing_root: Root = compute_signing_root(deposit_message, domain);
+    let sig = bls_keys::bls_sig_from_hex(&dd.signature)?;
+    // Deposits name no genesis_validators_root, so they sign and verify under the default DST
+    if !bls_verify::verify(&pk, &signing_root, &sig, CIPHER_SUITE.as_bytes()) {
+        bail!("Deposit signature failed to verify")
+    }
+    Ok(dd)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eth2::eth_signing::compute_deposit_data_root;
+    use crate::eth2::eth_types::GENESIS_FORK_VERSION;
+    use crate::io::key_management::delete_bls_key;
+
+    #[test]
+    fn test_generate_deposit_data() {
+        let sk_set = bls_keys::new_bls_key(0);
+        bls_keys::save_bls_key(&sk_set).unwrap();
+        let pk_hex = sk_set.public_keys().public_key().to_hex();
+        let mut withdrawal_credentials = [0_u8; 32];
+        withdrawal_credentials[0] = 0x01;
+        withdrawal_credentials[12..].copy_from_slice(&[0x11; 20]);
+
+        let dd = generate_deposit_data(
+            &format!("0x{pk_hex}"),
+            withdrawal_credentials,
+            crate::constants::FULL_DEPOSIT_AMOUNT,
+            GENESIS_FORK_VERSION,
+        )
+        .unwrap();
+        assert_eq!(dd.pubkey, pk_hex);
+        assert_eq!(dd.fork_version, "00000000");
+        assert_eq!(dd.network_name, "mainnet");
+        assert_eq!(
+            dd.deposit_cli_version,
+            crate::constants::DEPOSIT_CLI_VERSION
+        );
+
+        // The tree_hash deposit_data_root matches the one the deposit contract computes
+        let signature = hex::decode(&dd.signature).unwrap();
+        let root = compute_deposit_data_root(
+            &sk_set.public_keys().public_key().to_bytes(),
+            withdrawal_credentials,
+            crate::constants::FULL_DEPOSIT_AMOUNT,
+            &signature,
+        );
+        assert_eq!(dd.deposit_data_root, hex::encode(root));
+
+        let json = serde_json::to_value(&dd).unwrap();
+        assert_eq!(json["amount"], serde_json::json!(32000000000_u64));
+        assert_eq!(json.as_object().unwrap().len(), 9);
+        assert!(!json["signature"].as_str().unwrap().starts_with("0x"));
+
+        let holesky = [0x01, 0x01, 0x70, 0x00];
+        let dd =
+            generate_deposit_data(&pk_hex, withdrawal_credentials, 1000000000, holesky).unwrap();
+        assert_eq!(dd.network_name, "holesky");
+        assert_ne!(dd.signature, hex::encode(&signature));
+
+        // Below the contract minimum, unknown prefixes and unknown keys are refused
+        assert!(generate_deposit_data(
+            &pk_hex,
+            withdrawal_credentials,
+            999999999,
+            GENESIS_FORK_VERSION
+        )
+        .is_err());
+        let mut bad_credentials = withdrawal_credentials;
+        bad_credentials[0] = 0x09;
+        assert!(generate_deposit_data(
+            &pk_hex,
+            bad_credentials,
+            crate::constants::FULL_DEPOSIT_AMOUNT,
+            GENESIS_FORK_VERSION
+        )
+        .is_err());
+        delete_bls_key(&pk_hex).unwrap();
+        assert!(generate_deposit_data(
+            &pk_hex,
+            withdrawal_credentials,
+            crate::constants::FULL_DEPOSIT_AMOUNT,
+            GENESIS_FORK_VERSION
+        )
+        .is_err());
+    }
+}
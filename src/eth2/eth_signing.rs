@@ -1,13 +1,61 @@
 use super::eth_types::*;
+use super::slash_protection::{SignedAttestationEpochs, SignedBlockSlot, SlashingProtectionData};
 use crate::crypto::bls_keys;
+use crate::io::key_management::KeyRole;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use blsttc::SecretKeySet;
-use log::info;
+use log::{error, info};
 use serde::{Deserialize, Serialize};
-use ssz::Encode;
+use ssz::{Decode, Encode};
+use std::sync::atomic::{AtomicBool, Ordering};
 use tree_hash::TreeHash;
 
+/// Domain types that are reserved for consensus objects protected by slashing rules
+const CONSENSUS_DOMAIN_TYPES: [DomainType; 11] = [
+    DOMAIN_BEACON_PROPOSER,
+    DOMAIN_BEACON_ATTESTER,
+    DOMAIN_RANDAO,
+    DOMAIN_DEPOSIT,
+    DOMAIN_VOLUNTARY_EXIT,
+    DOMAIN_SELECTION_PROOF,
+    DOMAIN_AGGREGATE_AND_PROOF,
+    DOMAIN_SYNC_COMMITTEE,
+    DOMAIN_SYNC_COMMITTEE_SELECTION_PROOF,
+    DOMAIN_CONTRIBUTION_AND_PROOF,
+    DOMAIN_BLS_TO_EXECUTION_CHANGE,
+];
+
+/// Signing paths that skip slashing protection are disabled unless explicitly allowed
+static ALLOW_SLASHING_BYPASS: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables the signing paths that bypass slashing protection
+pub fn set_allow_slashing_bypass(allow: bool) {
+    ALLOW_SLASHING_BYPASS.store(allow, Ordering::SeqCst);
+}
+
+/// Returns true if signing paths that bypass slashing protection are enabled
+pub fn slashing_bypass_allowed() -> bool {
+    ALLOW_SLASHING_BYPASS.load(Ordering::SeqCst)
+}
+
+/// Checks the role of the saved BLS key `pk_hex` permits signing a `consensus` object, or a
+/// non-consensus message otherwise. Keys without a role may sign consensus objects.
+pub fn check_key_role(pk_hex: &str, consensus: bool) -> Result<()> {
+    match (
+        crate::io::key_management::read_bls_key_role(pk_hex)?,
+        consensus,
+    ) {
+        (Some(KeyRole::General), true) => {
+            bail!("General key {pk_hex} may not sign consensus objects")
+        }
+        (Some(KeyRole::Validator), false) => {
+            bail!("Validator key {pk_hex} may only sign consensus objects")
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Return the signing root for the corresponding signing data.
 pub fn compute_signing_root<T: Encode + TreeHash>(ssz_object: T, domain: Domain) -> Root {
     let object_root = ssz_object.tree_hash_root().to_fixed_bytes();
@@ -18,6 +66,21 @@ pub fn compute_signing_root<T: Encode + TreeHash>(ssz_object: T, domain: Domain)
     sign_data.tree_hash_root().to_fixed_bytes()
 }
 
+/// Returns true if the sync committee `selection_proof` makes its signer an aggregator of a
+/// subcommittee of `subcommittee_size` members, `SYNC_SUBCOMMITTEE_SIZE` on mainnet.
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/altair/validator.md#aggregation-selection
+pub fn is_sync_committee_aggregator(selection_proof: &[u8], subcommittee_size: u64) -> bool {
+    use sha2::{Digest, Sha256};
+    let modulo = std::cmp::max(
+        1,
+        subcommittee_size / TARGET_AGGREGATORS_PER_SYNC_SUBCOMMITTEE,
+    );
+    let hash = Sha256::digest(selection_proof);
+    let mut prefix = [0_u8; 8];
+    prefix.copy_from_slice(&hash[..8]);
+    u64::from_le_bytes(prefix) % modulo == 0
+}
+
 /// Return the 32-byte fork data root for the ``current_version`` and ``genesis_validators_root``.
 /// This is used primarily in signature domains to avoid collisions across forks/chains.
 pub fn compute_fork_data_root(current_version: Version, genesis_validators_root: Root) -> Root {
@@ -74,9 +137,11 @@ pub fn compute_domain(
     d
 }
 
-/// Reusable signing function that signs SSZ objects by fetching bls sk from memory
+/// Reusable signing function that signs SSZ objects by fetching bls sk from memory. The sign is
+/// reported to the audit webhook as `signing_type`.
 pub fn secure_sign<T: Encode + TreeHash>(
     pk_hex: String,
+    signing_type: &str,
     msg: T,
     domain: Domain,
 ) -> Result<BLSSignature> {
@@ -84,9 +149,781 @@ pub fn secure_sign<T: Encode + TreeHash>(
     info!("Computed signingRoot: {:?}", hex::encode(root));
     let sig = bls_keys::bls_agg_sign_from_saved_sk(&pk_hex, &root)?;
     info!("Computed signature: {:?}", hex::encode(sig.to_bytes()));
+    audit_sign(&pk_hex, signing_type, &root);
     Ok(<_>::from(sig.to_bytes().to_vec()))
 }
 
+// Reports a released signature to the audit webhook, every signing path ends here
+fn audit_sign(pk_hex: &str, signing_type: &str, root: &Root) {
+    crate::io::webhook::emit(crate::io::webhook::AuditEvent::sign(
+        pk_hex,
+        signing_type,
+        root,
+    ));
+}
+
+/// Signs the signing root of an already hash-tree-rooted `object_root` under `domain`, the root
+/// every typed signing path ends in. The root is `hash_tree_root(SigningData)` of the SSZ
+/// container `{ object_root: Bytes32, domain: Bytes32 }`, i.e. `sha256(object_root || domain)`.
+/// Signs through `sign_and_record` under `CIPHER_SUITE`, so block and attestation domains need
+/// the `fields` slashing protection checks and records. Returns the signing root and signature.
+pub fn compute_and_sign_signing_data(
+    pk_hex: &String,
+    object_root: Root,
+    domain: Domain,
+    fields: Option<SlashableFields>,
+) -> Result<(Root, BLSSignature)> {
+    let pk_hex = bls_keys::sanitize_bls_pk_hex(pk_hex)?;
+    let domain_type: DomainType = domain[..4].try_into()?;
+    let signing_type = match (domain_type, fields) {
+        (DOMAIN_BEACON_PROPOSER, Some(SlashableFields::Block { .. })) => "BLOCK_V2",
+        (DOMAIN_BEACON_ATTESTER, Some(SlashableFields::Attestation { .. })) => "ATTESTATION",
+        (DOMAIN_BEACON_PROPOSER | DOMAIN_BEACON_ATTESTER, _) => bail!(
+            "Signing under domain type 0x{} needs its slashable fields",
+            hex::encode(domain_type)
+        ),
+        (_, Some(_)) => bail!("Only block and attestation domains have slashable fields"),
+        (_, None) => "SIGNING_DATA",
+    };
+    let root: Root = SigningData {
+        object_root,
+        domain,
+    }
+    .tree_hash_root()
+    .to_fixed_bytes();
+    info!("Computed signingRoot: {:?}", hex::encode(root));
+    let sig = sign_and_record(
+        &pk_hex,
+        signing_type,
+        fields,
+        root,
+        crate::constants::CIPHER_SUITE.as_bytes(),
+    )?;
+    Ok((root, <_>::from(sig.to_bytes().to_vec())))
+}
+
+/// Checks key shares may sign under `domain`. Key shares have no slashing protection DB, so
+/// consensus domains are refused, and only an opaque root is signed, so builder registrations are
+/// refused as their fee recipient could not be checked against the policy.
+pub fn check_key_share_domain(domain: &Domain) -> Result<()> {
+    let domain_type: DomainType = domain[..4].try_into()?;
+    if CONSENSUS_DOMAIN_TYPES.contains(&domain_type) {
+        bail!("Refusing to sign with key shares under a consensus domain type")
+    }
+    if domain_type == DOMAIN_APPLICATION_BUILDER {
+        bail!("Refusing to sign with key shares under the builder domain type")
+    }
+    Ok(())
+}
+
+/// Computes the signing root of `object_root` under `domain` and signs it with each of the saved
+/// key shares `group_ids`, returning the signing root, the group public keys and each share's
+/// index and partial signature. The domain must pass `check_key_share_domain` and no share may be
+/// a validator key.
+pub fn sign_root_with_key_shares(
+    group_ids: &[String],
+    object_root: Root,
+    domain: Domain,
+) -> Result<(
+    Root,
+    blsttc::PublicKeySet,
+    Vec<(usize, blsttc::SignatureShare)>,
+)> {
+    check_key_share_domain(&domain)?;
+    for group_id in group_ids {
+        check_key_role(group_id, false)?;
+    }
+    let sign_data = SigningData {
+        object_root,
+        domain,
+    };
+    let root: Root = sign_data.tree_hash_root().to_fixed_bytes();
+    info!("Computed signingRoot: {:?}", hex::encode(root));
+    let (pk_set, partials) = bls_keys::bls_sign_with_key_shares(group_ids, &root)?;
+    audit_sign(&pk_set.public_key().to_hex(), "THRESHOLD", &root);
+    Ok((root, pk_set, partials))
+}
+
+/// Signs an arbitrary non-consensus commitment. The `data` is hash-tree-rooted as a `List[byte]`
+/// and signed under the domain derived from `domain_type`. This bypasses slashing protection, so it
+/// is never allowed under a consensus domain type or with a validator key. Keys created without a
+/// role additionally require the slashing-bypass flag to be set.
+pub fn sign_commitment(
+    pk_hex: String,
+    domain_type: DomainType,
+    data: &[u8],
+) -> Result<BLSSignature> {
+    sign_commitment_with_bypass(pk_hex, domain_type, data, slashing_bypass_allowed())
+}
+
+// `sign_commitment` taking the slashing-bypass flag as `allow_bypass` rather than reading it
+fn sign_commitment_with_bypass(
+    pk_hex: String,
+    domain_type: DomainType,
+    data: &[u8],
+    allow_bypass: bool,
+) -> Result<BLSSignature> {
+    let pk_hex = bls_keys::sanitize_bls_pk_hex(&pk_hex)?;
+    check_key_role(&pk_hex, false)?;
+    let is_general =
+        crate::io::key_management::read_bls_key_role(&pk_hex)? == Some(KeyRole::General);
+    if !is_general && !allow_bypass {
+        bail!("Signing commitments requires the slashing-bypass flag to be enabled")
+    }
+    if CONSENSUS_DOMAIN_TYPES.contains(&domain_type) {
+        bail!("Refusing to sign a commitment under a consensus domain type")
+    }
+    bls_keys::check_message_size(data)?;
+    let commitment = Commitment::new(data.to_vec())
+        .map_err(|e| anyhow!("Commitment exceeds the maximum length: {:?}", e))?;
+    let domain = compute_domain(domain_type, None, None);
+    secure_sign(pk_hex, "COMMITMENT", commitment, domain)
+}
+
+/// Signs `header` as a sync committee member for a light client update included at
+/// `signature_slot`. As for a `SyncAggregate` the domain is the sync committee domain at the epoch
+/// of the slot before `signature_slot`, and the header must precede it. The fork is checked as for
+/// "SYNC_COMMITTEE_MESSAGE" requests. Returns the header root the signature is over and the
+/// signature, so light client updates can be served alongside their proofs.
+pub fn sign_light_client_header(
+    pk_hex: String,
+    header: BeaconBlockHeader,
+    fork_info: ForkInfo,
+    signature_slot: Slot,
+    allow_genesis_fork: bool,
+) -> Result<(Root, BLSSignature)> {
+    let pk_hex = bls_keys::sanitize_bls_pk_hex(&pk_hex)?;
+    if header.slot >= signature_slot {
+        bail!(
+            "Header at slot {} must precede the signature slot {signature_slot}",
+            header.slot
+        )
+    }
+    check_fork_info(&fork_info, allow_genesis_fork)?;
+    let header_root: Root = header.tree_hash_root().to_fixed_bytes();
+    let genesis_validators_root = fork_info.genesis_validators_root;
+    let domain = get_domain(
+        fork_info,
+        DOMAIN_SYNC_COMMITTEE,
+        Some(compute_epoch_at_slot(signature_slot.max(1) - 1)),
+    );
+    let root = compute_signing_root(header_root, domain);
+    info!("Computed signingRoot: {:?}", hex::encode(root));
+    let sig = sign_and_record(
+        &pk_hex,
+        "LIGHT_CLIENT_HEADER",
+        None,
+        root,
+        network_dst(&genesis_validators_root),
+    )?;
+    Ok((header_root, <_>::from(sig.to_bytes().to_vec())))
+}
+
+/// Signs a builder flow `BlindedBeaconBlock` under the proposer domain of `fork` and
+/// `genesis_validators_root`. The blinded block root equals the root of the full block, so the
+/// signature is valid for the unblinded proposal. The fork and slot are checked exactly as for
+/// "BLOCK" requests, through `check_fork_info` and `sign_and_record`.
+pub fn sign_blinded_beacon_block(
+    pk_hex: String,
+    blinded_block: BlindedBeaconBlock,
+    fork: Fork,
+    genesis_validators_root: Root,
+    allow_genesis_fork: bool,
+) -> Result<BLSSignature> {
+    let pk_hex = bls_keys::sanitize_bls_pk_hex(&pk_hex)?;
+    let fork_info = ForkInfo {
+        fork,
+        genesis_validators_root,
+    };
+    check_fork_info(&fork_info, allow_genesis_fork)?;
+    let slot = blinded_block.slot;
+    let domain = get_domain(
+        fork_info,
+        DOMAIN_BEACON_PROPOSER,
+        Some(compute_epoch_at_slot(slot)),
+    );
+    let root = compute_signing_root(blinded_block, domain);
+    info!("Computed signingRoot: {:?}", hex::encode(root));
+    let sig = sign_and_record(
+        &pk_hex,
+        "BLINDED_BLOCK",
+        Some(SlashableFields::Block { slot }),
+        root,
+        network_dst(&genesis_validators_root),
+    )?;
+    Ok(<_>::from(sig.to_bytes().to_vec()))
+}
+
+/// Signs the beacon block summarized by `header` under the proposer domain of `fork_info` at the
+/// header's slot. The slot and block root both come from `header`, so the recorded slot is always
+/// the signed block's, and the fork and slot are checked exactly as for "BLOCK_V2" requests.
+pub fn sign_beacon_block(
+    pk_hex: &String,
+    header: BeaconBlockHeader,
+    fork_info: ForkInfo,
+    allow_genesis_fork: bool,
+) -> Result<BLSSignature> {
+    let pk_hex = bls_keys::sanitize_bls_pk_hex(pk_hex)?;
+    if header.body_root == Root::default() {
+        bail!("Refusing to sign a block header with an all-zero body_root")
+    }
+    check_fork_info(&fork_info, allow_genesis_fork)?;
+    let slot = header.slot;
+    let genesis_validators_root = fork_info.genesis_validators_root;
+    let domain = get_domain(
+        fork_info,
+        DOMAIN_BEACON_PROPOSER,
+        Some(compute_epoch_at_slot(slot)),
+    );
+    let root = compute_signing_root(header, domain);
+    info!("Computed signingRoot: {:?}", hex::encode(root));
+    let sig = sign_and_record(
+        &pk_hex,
+        "BLOCK_V2",
+        Some(SlashableFields::Block { slot }),
+        root,
+        network_dst(&genesis_validators_root),
+    )?;
+    Ok(<_>::from(sig.to_bytes().to_vec()))
+}
+
+/// Signs a `VoluntaryExit` of `validator_index` at `epoch` under the voluntary exit domain of
+/// `fork_version` and `genesis_validators_root`, exactly as a "VOLUNTARY_EXIT" request with that
+/// fork. Returns the signing root and the signature.
+pub fn sign_voluntary_exit(
+    pk_hex: &String,
+    epoch: Epoch,
+    validator_index: ValidatorIndex,
+    fork_version: Version,
+    genesis_validators_root: Root,
+) -> Result<(Root, BLSSignature)> {
+    let pk_hex = bls_keys::sanitize_bls_pk_hex(pk_hex)?;
+    let msg = BLSSignMsg::VOLUNTARY_EXIT(VoluntaryExitRequest {
+        fork_info: ForkInfo {
+            fork: Fork {
+                previous_version: fork_version,
+                current_version: fork_version,
+                epoch: 0,
+            },
+            genesis_validators_root,
+        },
+        signingRoot: None,
+        voluntary_exit: VoluntaryExit {
+            epoch,
+            validator_index,
+        },
+    });
+    msg.check_fork_version(false)?;
+    let root = msg.to_signing_root(None);
+    info!("Computed signingRoot: {:?}", hex::encode(root));
+    let sig = sign_and_record(
+        &pk_hex,
+        msg.signing_type(),
+        msg.slashable_fields(),
+        root,
+        msg.dst(),
+    )?;
+    Ok((root, <_>::from(sig.to_bytes().to_vec())))
+}
+
+/// Signs a `BLSToExecutionChange` moving the withdrawals of `validator_index` to
+/// `to_execution_address`, with the saved key `pk_hex` as the validator's BLS withdrawal key. As
+/// in the Capella spec the domain is computed from the network's genesis fork version, so the
+/// change stays valid across forks. Returns the signing root and the signature.
+pub fn sign_bls_to_execution_change(
+    pk_hex: &String,
+    validator_index: ValidatorIndex,
+    to_execution_address: [u8; 20],
+    genesis_fork_version: Version,
+    genesis_validators_root: Root,
+) -> Result<(Root, BLSSignature)> {
+    let pk_hex = bls_keys::sanitize_bls_pk_hex(pk_hex)?;
+    check_key_role(&pk_hex, true)?;
+    let pk = bls_keys::bls_pk_from_hex(&pk_hex)?;
+    let change = BLSToExecutionChange {
+        validator_index,
+        from_bls_pubkey: pk.to_bytes().to_vec().into(),
+        to_execution_address: to_execution_address.to_vec().into(),
+    };
+    let domain = compute_domain(
+        DOMAIN_BLS_TO_EXECUTION_CHANGE,
+        Some(genesis_fork_version),
+        Some(genesis_validators_root),
+    );
+    let root = compute_signing_root(change, domain);
+    info!("Computed signingRoot: {:?}", hex::encode(root));
+    let sig = bls_keys::bls_sign_root_from_saved_sk_with_dst(
+        &pk_hex,
+        &root,
+        network_dst(&genesis_validators_root),
+    )?;
+    audit_sign(&pk_hex, "BLS_TO_EXECUTION_CHANGE", &root);
+    Ok((root, <_>::from(sig.to_bytes().to_vec())))
+}
+
+/// Checks `attestation` is a valid indexed attestation under the attester domain of `fork` and
+/// `genesis_validators_root`, looking up each attester's key in `pks_by_index`. As in the spec's
+/// `is_valid_indexed_attestation` the indices must be non-empty, sorted and unique.
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/phase0/beacon-chain.md#is_valid_indexed_attestation
+pub fn verify_indexed_attestation(
+    attestation: &IndexedAttestation,
+    pks_by_index: &std::collections::HashMap<ValidatorIndex, blsttc::PublicKey>,
+    fork: Fork,
+    genesis_validators_root: Root,
+) -> Result<()> {
+    let indices = &attestation.attesting_indices;
+    if indices.is_empty() {
+        bail!("Indexed attestation has no attesting indices")
+    }
+    if indices.windows(2).any(|w| w[0] >= w[1]) {
+        bail!("Attesting indices must be sorted and unique")
+    }
+    let pks = indices
+        .iter()
+        .map(|i| {
+            pks_by_index
+                .get(i)
+                .copied()
+                .ok_or_else(|| anyhow!("No public key for validator index {i}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let agg_pk = bls_keys::aggregate_committee_pubkeys(&pks, &vec![true; pks.len()])?;
+    let sig = bls_keys::bls_sig_from_bytes(&attestation.signature)?;
+
+    let fork_info = ForkInfo {
+        fork,
+        genesis_validators_root,
+    };
+    let domain = get_domain(
+        fork_info,
+        DOMAIN_BEACON_ATTESTER,
+        Some(attestation.data.target.epoch),
+    );
+    let root = compute_signing_root(attestation.data.clone(), domain);
+    if !bls_keys::bls_verify_with_dst(&agg_pk, &sig, &root, network_dst(&genesis_validators_root)) {
+        bail!("Invalid indexed attestation signature")
+    }
+    Ok(())
+}
+
+/// Builds 0x01 or 0x02 (EIP-7251 compounding) withdrawal credentials for a 20-byte execution address
+pub fn execution_withdrawal_credentials(prefix: u8, execution_address: &[u8]) -> Result<Bytes32> {
+    if prefix != ETH1_ADDRESS_WITHDRAWAL_PREFIX && prefix != COMPOUNDING_WITHDRAWAL_PREFIX {
+        bail!("Invalid execution withdrawal prefix 0x{:02x}", prefix)
+    }
+    if execution_address.len() != 20 {
+        bail!(
+            "Invalid execution address length {}",
+            execution_address.len()
+        )
+    }
+    let mut wc: Bytes32 = [0_u8; 32];
+    wc[0] = prefix;
+    wc[12..].copy_from_slice(execution_address);
+    Ok(wc)
+}
+
+/// Builds the 0x00 withdrawal credentials committing to the BLS withdrawal key `pubkey`
+pub fn bls_withdrawal_credentials(pubkey: &[u8; 48]) -> Bytes32 {
+    use sha2::{Digest, Sha256};
+    let mut wc: Bytes32 = Sha256::digest(pubkey).into();
+    wc[0] = BLS_WITHDRAWAL_PREFIX;
+    wc
+}
+
+/// Validates the prefix of `withdrawal_credentials`. Execution (0x01) and compounding (0x02)
+/// credentials must be the prefix, 11 zero bytes, then the 20-byte execution address.
+pub fn validate_withdrawal_credentials(withdrawal_credentials: &Bytes32) -> Result<()> {
+    match withdrawal_credentials[0] {
+        BLS_WITHDRAWAL_PREFIX => Ok(()),
+        ETH1_ADDRESS_WITHDRAWAL_PREFIX | COMPOUNDING_WITHDRAWAL_PREFIX => {
+            if withdrawal_credentials[1..12].iter().any(|b| *b != 0) {
+                bail!(
+                    "Execution withdrawal credentials must zero-pad the 20-byte execution address"
+                )
+            }
+            if withdrawal_credentials[12..].iter().all(|b| *b == 0) {
+                bail!("Execution withdrawal credentials have a zero execution address")
+            }
+            Ok(())
+        }
+        p => bail!("Unknown withdrawal credentials prefix 0x{:02x}", p),
+    }
+}
+
+/// The fields of a signing request that slashing protection is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlashableFields {
+    Block {
+        slot: Slot,
+    },
+    Attestation {
+        source_epoch: Epoch,
+        target_epoch: Epoch,
+    },
+}
+
+/// Why `sign_and_record` withheld a signature
+#[derive(Debug)]
+pub enum SignError {
+    /// The key's role forbids signing consensus objects
+    KeyRole(anyhow::Error),
+    /// The slashing protection DB is being compacted
+    Compacting,
+    /// The message is slashable against the validator's slashing protection DB
+    Slashable(anyhow::Error),
+    /// The message was signed but could not be recorded, so its signature was never released
+    Record(anyhow::Error),
+    /// The slashing protection DB could not be read, or the key could not sign
+    Internal(anyhow::Error),
+}
+
+impl std::fmt::Display for SignError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignError::KeyRole(e) | SignError::Internal(e) => write!(f, "{:?}", e),
+            SignError::Compacting => {
+                write!(
+                    f,
+                    "Slashing protection DB is being compacted, retry shortly"
+                )
+            }
+            SignError::Slashable(e) => write!(
+                f,
+                "Signing operation failed due to slashing protection rules: {:?}",
+                e
+            ),
+            SignError::Record(e) => {
+                write!(f, "Failed to record the signed message: {:?}", e)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SignError {}
+
+/// The single check-sign-record path of every consensus signature. Checks the key's role, then
+/// `fields` against the slash protection DB of `pk_hex` (attestations by
+/// `SlashingProtectionData::check_attestation_epochs`), signs `signing_root` under `dst` and
+/// records `fields`, pruning attestations below `weak_subjectivity_epoch`. An exact repeat of a
+/// recorded attestation, same epochs and signing root, is re-signed without a new record, so a
+/// retried request gets back the same signature. Released signatures are reported to the audit
+/// webhook. The signature is returned only once the record is committed. Both the compaction
+/// guard and the validator's signing lock are held throughout.
+pub fn sign_and_record(
+    pk_hex: &String,
+    signing_type: &str,
+    fields: Option<SlashableFields>,
+    signing_root: Root,
+    dst: &[u8],
+) -> std::result::Result<blsttc::Signature, SignError> {
+    use super::slash_protection;
+
+    // General keys never sign consensus objects, so never bypass slashing protection here
+    check_key_role(pk_hex, true).map_err(SignError::KeyRole)?;
+
+    // Held until the record is committed, so compaction never interleaves with a sign
+    let Some(_db_guard) = slash_protection::try_lock_for_signing() else {
+        return Err(SignError::Compacting);
+    };
+    // Held until the record is committed, so concurrent requests for this key are checked in turn
+    let validator_lock = slash_protection::validator_signing_lock(pk_hex);
+    let _validator_guard = validator_lock.lock().unwrap_or_else(|e| e.into_inner());
+
+    // The slashing DB must exist
+    let mut db = SlashingProtectionData::read(pk_hex).map_err(SignError::Internal)?;
+    let repeat = match fields {
+        Some(SlashableFields::Attestation {
+            source_epoch,
+            target_epoch,
+        }) => db.is_repeat_attestation(source_epoch, target_epoch, &signing_root),
+        _ => false,
+    };
+    match fields {
+        // Already recorded, the signature is served from the signature cache where it applies
+        _ if repeat => {}
+        Some(SlashableFields::Block { slot }) => {
+            if db.is_slashable_block_slot(slot) {
+                return Err(SignError::Slashable(anyhow!(
+                    "Block at slot {slot} is not above the highest signed slot {}",
+                    db.get_latest_signed_block_slot()
+                )));
+            }
+        }
+        Some(SlashableFields::Attestation {
+            source_epoch,
+            target_epoch,
+        }) => {
+            db.check_attestation_epochs(source_epoch, target_epoch)
+                .map_err(SignError::Slashable)?;
+        }
+        // Only block proposals and attestations are slashable
+        None => {}
+    }
+
+    // Sign the message before recording it, so a slot is never committed without a signature
+    // having been produced. Re-signing the same root is harmless as BLS signatures are
+    // deterministic.
+    let sig = bls_keys::bls_sign_root_from_saved_sk_with_dst(pk_hex, &signing_root, dst)
+        .map_err(SignError::Internal)?;
+    info!(
+        "{signing_type} signature: {:?}",
+        hex::encode(sig.to_bytes())
+    );
+
+    // The signature is only released once the record is committed
+    if let Some(fields) = fields.filter(|_| !repeat) {
+        let growable = crate::constants::ALLOW_GROWABLE_SLASH_PROTECTION_DB;
+        let recorded = match fields {
+            SlashableFields::Block { slot } => db.new_block(
+                SignedBlockSlot {
+                    slot,
+                    signing_root: Some(signing_root),
+                },
+                growable,
+            ),
+            SlashableFields::Attestation {
+                source_epoch,
+                target_epoch,
+            } => db
+                .new_attestation(
+                    SignedAttestationEpochs {
+                        source_epoch,
+                        target_epoch,
+                        signing_root: Some(signing_root),
+                    },
+                    growable,
+                )
+                .map(|_| db.prune_attestations(slash_protection::weak_subjectivity_epoch())),
+        };
+        recorded.and_then(|_| db.write()).map_err(|e| {
+            error!("Failed trying to update slash protection database: {:?}", e);
+            SignError::Record(e)
+        })?;
+    }
+    audit_sign(pk_hex, signing_type, &signing_root);
+    Ok(sig)
+}
+
+/// Decodes `ssz_bytes` as the container signed for `signing_type` (e.g. "ATTESTATION" decodes
+/// `AttestationData`) and returns its signing root under `domain`, together with the fields
+/// slashing protection must check. The domain must carry the domain type of `signing_type`.
+pub fn ssz_signing_root(
+    signing_type: &str,
+    ssz_bytes: &[u8],
+    domain: Domain,
+) -> Result<(Root, Option<SlashableFields>)> {
+    fn decode<T: Decode>(ssz_bytes: &[u8]) -> Result<T> {
+        T::from_ssz_bytes(ssz_bytes).map_err(|e| anyhow!("Invalid SSZ bytes: {:?}", e))
+    }
+
+    let (domain_type, signing_root, fields) = match signing_type.to_uppercase().as_str() {
+        "BLOCK" => {
+            let block: BeaconBlock = decode(ssz_bytes)?;
+            let fields = SlashableFields::Block { slot: block.slot };
+            let root = compute_signing_root(block, domain);
+            (DOMAIN_BEACON_PROPOSER, root, Some(fields))
+        }
+        "BLOCK_V2" => {
+            let header: BeaconBlockHeader = decode(ssz_bytes)?;
+            let fields = SlashableFields::Block { slot: header.slot };
+            let root = compute_signing_root(header, domain);
+            (DOMAIN_BEACON_PROPOSER, root, Some(fields))
+        }
+        "ATTESTATION" => {
+            let data: AttestationData = decode(ssz_bytes)?;
+            let fields = SlashableFields::Attestation {
+                source_epoch: data.source.epoch,
+                target_epoch: data.target.epoch,
+            };
+            let root = compute_signing_root(data, domain);
+            (DOMAIN_BEACON_ATTESTER, root, Some(fields))
+        }
+        "RANDAO_REVEAL" => {
+            let epoch: Epoch = decode(ssz_bytes)?;
+            (DOMAIN_RANDAO, compute_signing_root(epoch, domain), None)
+        }
+        "AGGREGATE_AND_PROOF" => {
+            let aggregate_and_proof: AggregateAndProof = decode(ssz_bytes)?;
+            let root = compute_signing_root(aggregate_and_proof, domain);
+            (DOMAIN_AGGREGATE_AND_PROOF, root, None)
+        }
+        "AGGREGATION_SLOT" => {
+            let slot: Slot = decode(ssz_bytes)?;
+            (
+                DOMAIN_SELECTION_PROOF,
+                compute_signing_root(slot, domain),
+                None,
+            )
+        }
+        "DEPOSIT" => {
+            let deposit: DepositMessage = decode(ssz_bytes)?;
+            (DOMAIN_DEPOSIT, compute_signing_root(deposit, domain), None)
+        }
+        "VOLUNTARY_EXIT" => {
+            let exit: VoluntaryExit = decode(ssz_bytes)?;
+            (
+                DOMAIN_VOLUNTARY_EXIT,
+                compute_signing_root(exit, domain),
+                None,
+            )
+        }
+        "SYNC_COMMITTEE_MESSAGE" => {
+            let beacon_block_root: Root = decode(ssz_bytes)?;
+            let root = compute_signing_root(beacon_block_root, domain);
+            (DOMAIN_SYNC_COMMITTEE, root, None)
+        }
+        "SYNC_COMMITTEE_SELECTION_PROOF" => {
+            let data: SyncAggregatorSelectionData = decode(ssz_bytes)?;
+            let root = compute_signing_root(data, domain);
+            (DOMAIN_SYNC_COMMITTEE_SELECTION_PROOF, root, None)
+        }
+        "SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF" => {
+            let contribution_and_proof: ContributionAndProof = decode(ssz_bytes)?;
+            let root = compute_signing_root(contribution_and_proof, domain);
+            (DOMAIN_CONTRIBUTION_AND_PROOF, root, None)
+        }
+        "VALIDATOR_REGISTRATION" => {
+            let registration: ValidatorRegistration = decode(ssz_bytes)?;
+            let root = compute_signing_root(registration, domain);
+            (DOMAIN_APPLICATION_BUILDER, root, None)
+        }
+        _ => bail!("Unknown signing type: {signing_type}"),
+    };
+
+    if domain[..4] != domain_type {
+        bail!(
+            "Domain type 0x{} does not match the {signing_type} domain type 0x{}",
+            hex::encode(&domain[..4]),
+            hex::encode(domain_type)
+        )
+    }
+    Ok((signing_root, fields))
+}
+
+/// The fork and DST checks of `BLSSignMsg::check_fork_version` and `BLSSignMsg::dst` for
+/// `ssz_signing_root` requests. Every signing type with fork info in JSON must name the
+/// `fork_version` and `genesis_validators_root` its `domain` was computed from, and the domain
+/// must match them. Returns the DST to sign under.
+pub fn check_ssz_fork(
+    signing_type: &str,
+    domain: Domain,
+    fork_version: Option<Version>,
+    genesis_validators_root: Option<Root>,
+    allow_genesis_fork: bool,
+) -> Result<&'static [u8]> {
+    // Deposits and builder registrations are always signed under the genesis fork version
+    if matches!(
+        signing_type.to_uppercase().as_str(),
+        "DEPOSIT" | "VALIDATOR_REGISTRATION"
+    ) {
+        return Ok(crate::constants::CIPHER_SUITE.as_bytes());
+    }
+    let (Some(fork_version), Some(genesis_validators_root)) =
+        (fork_version, genesis_validators_root)
+    else {
+        bail!("{signing_type} requests must name their fork version and genesis_validators_root")
+    };
+    let domain_type: DomainType = domain[..4].try_into()?;
+    if compute_domain(
+        domain_type,
+        Some(fork_version),
+        Some(genesis_validators_root),
+    ) != domain
+    {
+        bail!(
+            "Domain 0x{} was not computed from fork version 0x{} and genesis_validators_root 0x{}",
+            hex::encode(domain),
+            hex::encode(fork_version),
+            hex::encode(genesis_validators_root)
+        )
+    }
+    let fork_info = ForkInfo {
+        fork: Fork {
+            previous_version: fork_version,
+            current_version: fork_version,
+            epoch: 0,
+        },
+        genesis_validators_root,
+    };
+    check_fork_info(&fork_info, allow_genesis_fork)?;
+    Ok(network_dst(&genesis_validators_root))
+}
+
+/// Checks the `selection_proof` of `contribution_and_proof` is a signature by `pk` over its slot
+/// and subcommittee under `selection_domain` and `dst`, and that it selects `pk` as an aggregator
+/// of the subcommittee. Signing the outer container for an unchecked client-supplied proof could
+/// commit the validator to a duty it was never selected for.
+pub fn verify_contribution_selection_proof(
+    contribution_and_proof: &ContributionAndProof,
+    pk: &blsttc::PublicKey,
+    selection_domain: Domain,
+    dst: &[u8],
+) -> Result<()> {
+    let c = &contribution_and_proof.contribution;
+    let data = SyncAggregatorSelectionData {
+        slot: c.slot,
+        subcommittee_index: c.subcommittee_index,
+    };
+    let root = compute_signing_root(data, selection_domain);
+    let proof = bls_keys::bls_sig_from_bytes(&contribution_and_proof.selection_proof)?;
+    if !bls_keys::bls_verify_with_dst(pk, &proof, &root, dst) {
+        bail!(
+            "Selection proof for slot {} subcommittee {} was not signed by this validator",
+            c.slot,
+            c.subcommittee_index
+        )
+    }
+    if !is_sync_committee_aggregator(
+        &contribution_and_proof.selection_proof,
+        SYNC_SUBCOMMITTEE_SIZE,
+    ) {
+        bail!("Selection proof does not select this validator as a sync committee aggregator")
+    }
+    Ok(())
+}
+
+/// SSZ variant of `BLSSignMsg::check_selection_proof` for `ssz_signing_root` requests. The
+/// selection proof domain shares the fork data root of the contribution's `domain`, so only its
+/// domain type differs, and it is signed under the same `dst`. Other signing types carry no
+/// selection proof.
+pub fn check_ssz_selection_proof(
+    signing_type: &str,
+    ssz_bytes: &[u8],
+    domain: Domain,
+    pk_hex: &str,
+    dst: &[u8],
+) -> Result<()> {
+    if signing_type.to_uppercase() != "SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF" {
+        return Ok(());
+    }
+    let contribution_and_proof = ContributionAndProof::from_ssz_bytes(ssz_bytes)
+        .map_err(|e| anyhow!("Invalid SSZ bytes: {:?}", e))?;
+    let mut selection_domain = domain;
+    selection_domain[..4].copy_from_slice(&DOMAIN_SYNC_COMMITTEE_SELECTION_PROOF);
+    verify_contribution_selection_proof(
+        &contribution_and_proof,
+        &bls_keys::bls_pk_from_hex(pk_hex)?,
+        selection_domain,
+        dst,
+    )
+}
+
+/// SSZ variant of `BLSSignMsg::check_fee_recipient` for `ssz_signing_root` requests
+pub fn check_ssz_fee_recipient(
+    signing_type: &str,
+    ssz_bytes: &[u8],
+    pk_hex: &str,
+    policy: &crate::eth2::fee_recipient_policy::FeeRecipientPolicy,
+) -> Result<()> {
+    if signing_type.to_uppercase() != "VALIDATOR_REGISTRATION" {
+        return Ok(());
+    }
+    let registration = ValidatorRegistration::from_ssz_bytes(ssz_bytes)
+        .map_err(|e| anyhow!("Invalid SSZ bytes: {:?}", e))?;
+    policy.check(pk_hex, &registration.fee_recipient)
+}
+
 /// https://github.com/ethereum/consensus-specs/blob/dev/specs/phase0/validator.md#submit-deposit
 /// Modified to adhere to https://consensys.github.io/web3signer/web3signer-eth2.html#tag/Signing
 pub fn get_deposit_signature(
@@ -94,8 +931,9 @@ pub fn get_deposit_signature(
     deposit_message: DepositMessage,
     fork_version: Version,
 ) -> Result<DepositResponse> {
+    validate_withdrawal_credentials(&deposit_message.withdrawal_credentials)?;
     let domain = compute_domain(DOMAIN_DEPOSIT, Some(fork_version), None);
-    let sig = secure_sign(pk_hex, deposit_message.clone(), domain)?;
+    let sig = secure_sign(pk_hex, "DEPOSIT", deposit_message.clone(), domain)?;
 
     let dm_root = deposit_message.tree_hash_root().to_fixed_bytes();
 
@@ -115,6 +953,9 @@ pub fn get_deposit_signature(
         signature: hex::encode(&sig[..]),
         deposit_message_root: hex::encode(dm_root),
         deposit_data_root: hex::encode(dd_root),
+        fork_version: hex::encode(fork_version),
+        network_name: super::eth_types::network_name(&fork_version).to_string(),
+        deposit_cli_version: crate::constants::DEPOSIT_CLI_VERSION.to_string(),
     };
 
     Ok(dr)
@@ -147,6 +988,34 @@ pub fn sign_full_deposit(
     Ok((sig, dd_root))
 }
 
+/// The `deposit_data_root` the deposit contract computes in `deposit()`, hashed the same way
+/// as the contract rather than through `tree_hash`, so deposit files can be checked independently
+pub fn compute_deposit_data_root(
+    pubkey: &[u8; 48],
+    withdrawal_credentials: [u8; 32],
+    amount_gwei: Gwei,
+    signature: &[u8; 96],
+) -> Root {
+    use sha2::{Digest, Sha256};
+    let hash = |parts: &[&[u8]]| -> Root {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    };
+
+    let pubkey_root = hash(&[pubkey, &[0; 16]]);
+    let signature_root = hash(&[
+        &hash(&[&signature[..64]]),
+        &hash(&[&signature[64..], &[0; 32]]),
+    ]);
+    hash(&[
+        &hash(&[&pubkey_root, &withdrawal_credentials]),
+        &hash(&[&amount_gwei.to_le_bytes(), &[0; 24], &signature_root]),
+    ])
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "type")]
 #[allow(non_camel_case_types)]
@@ -180,6 +1049,58 @@ pub enum BLSSignMsg {
 }
 
 impl BLSSignMsg {
+    /// The Web3Signer `type` of every signing request this signer accepts
+    pub const SIGNING_TYPES: [&'static str; 12] = [
+        "BLOCK",
+        "BLOCK_V2",
+        "ATTESTATION",
+        "RANDAO_REVEAL",
+        "AGGREGATE_AND_PROOF",
+        "AGGREGATION_SLOT",
+        "DEPOSIT",
+        "VOLUNTARY_EXIT",
+        "SYNC_COMMITTEE_MESSAGE",
+        "SYNC_COMMITTEE_SELECTION_PROOF",
+        "SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF",
+        "VALIDATOR_REGISTRATION",
+    ];
+
+    /// The Web3Signer `type` of this request, one of `SIGNING_TYPES`
+    pub fn signing_type(&self) -> &'static str {
+        match self {
+            BLSSignMsg::BLOCK(_) | BLSSignMsg::block(_) => "BLOCK",
+            BLSSignMsg::BLOCK_V2(_) | BLSSignMsg::block_v2(_) => "BLOCK_V2",
+            BLSSignMsg::ATTESTATION(_) | BLSSignMsg::attestation(_) => "ATTESTATION",
+            BLSSignMsg::RANDAO_REVEAL(_) | BLSSignMsg::randao_reveal(_) => "RANDAO_REVEAL",
+            BLSSignMsg::AGGREGATE_AND_PROOF(_) | BLSSignMsg::aggregate_and_proof(_) => {
+                "AGGREGATE_AND_PROOF"
+            }
+            BLSSignMsg::AGGREGATION_SLOT(_) | BLSSignMsg::aggregation_slot(_) => "AGGREGATION_SLOT",
+            BLSSignMsg::DEPOSIT(_) | BLSSignMsg::deposit(_) => "DEPOSIT",
+            BLSSignMsg::VOLUNTARY_EXIT(_) | BLSSignMsg::voluntary_exit(_) => "VOLUNTARY_EXIT",
+            BLSSignMsg::SYNC_COMMITTEE_MESSAGE(_) | BLSSignMsg::sync_committee_message(_) => {
+                "SYNC_COMMITTEE_MESSAGE"
+            }
+            BLSSignMsg::SYNC_COMMITTEE_SELECTION_PROOF(_)
+            | BLSSignMsg::sync_committee_selection_proof(_) => "SYNC_COMMITTEE_SELECTION_PROOF",
+            BLSSignMsg::SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF(_)
+            | BLSSignMsg::sync_committee_contribution_and_proof(_) => {
+                "SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF"
+            }
+            BLSSignMsg::VALIDATOR_REGISTRATION(_) | BLSSignMsg::validator_registration(_) => {
+                "VALIDATOR_REGISTRATION"
+            }
+        }
+    }
+
+    /// Returns true if requests of `signing_type` are checked against the slash protection DB
+    pub fn is_slashable_type(signing_type: &str) -> bool {
+        matches!(
+            signing_type.to_uppercase().as_str(),
+            "BLOCK" | "BLOCK_V2" | "ATTESTATION"
+        )
+    }
+
     pub fn can_be_slashed(&self) -> bool {
         if let BLSSignMsg::BLOCK(_)
         | BLSSignMsg::block(_)
@@ -194,6 +1115,109 @@ impl BLSSignMsg {
         }
     }
 
+    /// Returns the slot or source/target epochs that slashing protection checks this message against
+    pub fn slashable_fields(&self) -> Option<SlashableFields> {
+        match self {
+            BLSSignMsg::BLOCK(m) | BLSSignMsg::block(m) => Some(SlashableFields::Block {
+                slot: m.block.slot(),
+            }),
+            BLSSignMsg::BLOCK_V2(m) | BLSSignMsg::block_v2(m) => Some(SlashableFields::Block {
+                slot: m.beacon_block.block_header.slot,
+            }),
+            BLSSignMsg::ATTESTATION(m) | BLSSignMsg::attestation(m) => {
+                Some(SlashableFields::Attestation {
+                    source_epoch: m.attestation.source.epoch,
+                    target_epoch: m.attestation.target.epoch,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the fork info of messages signed under a fork-dependent domain
+    pub fn fork_info(&self) -> Option<&ForkInfo> {
+        match self {
+            BLSSignMsg::BLOCK(m) | BLSSignMsg::block(m) => Some(&m.fork_info),
+            BLSSignMsg::BLOCK_V2(m) | BLSSignMsg::block_v2(m) => Some(&m.fork_info),
+            BLSSignMsg::ATTESTATION(m) | BLSSignMsg::attestation(m) => Some(&m.fork_info),
+            BLSSignMsg::RANDAO_REVEAL(m) | BLSSignMsg::randao_reveal(m) => Some(&m.fork_info),
+            BLSSignMsg::AGGREGATE_AND_PROOF(m) | BLSSignMsg::aggregate_and_proof(m) => {
+                Some(&m.fork_info)
+            }
+            BLSSignMsg::AGGREGATION_SLOT(m) | BLSSignMsg::aggregation_slot(m) => Some(&m.fork_info),
+            BLSSignMsg::VOLUNTARY_EXIT(m) | BLSSignMsg::voluntary_exit(m) => Some(&m.fork_info),
+            BLSSignMsg::SYNC_COMMITTEE_MESSAGE(m) | BLSSignMsg::sync_committee_message(m) => {
+                Some(&m.fork_info)
+            }
+            BLSSignMsg::SYNC_COMMITTEE_SELECTION_PROOF(m)
+            | BLSSignMsg::sync_committee_selection_proof(m) => Some(&m.fork_info),
+            BLSSignMsg::SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF(m)
+            | BLSSignMsg::sync_committee_contribution_and_proof(m) => Some(&m.fork_info),
+            // Deposits and builder registrations are always signed under the genesis fork version
+            BLSSignMsg::DEPOSIT(_)
+            | BLSSignMsg::deposit(_)
+            | BLSSignMsg::VALIDATOR_REGISTRATION(_)
+            | BLSSignMsg::validator_registration(_) => None,
+        }
+    }
+
+    /// Returns the DST to sign this message under, resolved from the network registry by the
+    /// genesis_validators_root of its fork info. Messages without fork info use `CIPHER_SUITE`.
+    pub fn dst(&self) -> &'static [u8] {
+        match self.fork_info() {
+            Some(f) => network_dst(&f.genesis_validators_root),
+            None => crate::constants::CIPHER_SUITE.as_bytes(),
+        }
+    }
+
+    /// Rejects consensus messages whose fork info carries the all-zero phase0 genesis fork version,
+    /// a common client bug that yields valid-looking signatures under the wrong domain.
+    /// If the genesis_validators_root identifies a known network, its fork versions must also
+    /// belong to that network's schedule, catching fork info copied between networks.
+    pub fn check_fork_version(&self, allow_genesis_fork: bool) -> Result<()> {
+        match self.fork_info() {
+            Some(f) => check_fork_info(f, allow_genesis_fork),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the selection proof embedded in a SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF request was
+    /// produced by the BLS key `pk_hex` and selects it as an aggregator, see
+    /// `verify_contribution_selection_proof`. Other messages carry no selection proof.
+    pub fn check_selection_proof(&self, pk_hex: &str) -> Result<()> {
+        let (BLSSignMsg::SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF(m)
+        | BLSSignMsg::sync_committee_contribution_and_proof(m)) = self
+        else {
+            return Ok(());
+        };
+        let epoch = compute_epoch_at_slot(m.contribution_and_proof.contribution.slot);
+        let domain = get_domain(
+            m.fork_info.clone(),
+            DOMAIN_SYNC_COMMITTEE_SELECTION_PROOF,
+            Some(epoch),
+        );
+        verify_contribution_selection_proof(
+            &m.contribution_and_proof,
+            &bls_keys::bls_pk_from_hex(pk_hex)?,
+            domain,
+            self.dst(),
+        )
+    }
+
+    /// Checks a VALIDATOR_REGISTRATION request registers `pk_hex` with a fee recipient `policy`
+    /// allows. Other messages carry no fee recipient.
+    pub fn check_fee_recipient(
+        &self,
+        pk_hex: &str,
+        policy: &crate::eth2::fee_recipient_policy::FeeRecipientPolicy,
+    ) -> Result<()> {
+        let (BLSSignMsg::VALIDATOR_REGISTRATION(m) | BLSSignMsg::validator_registration(m)) = self
+        else {
+            return Ok(());
+        };
+        policy.check(pk_hex, &m.validator_registration.fee_recipient)
+    }
+
     pub fn to_signing_root(&self, _genesis_fork_version: Option<Version>) -> Root {
         match self {
             // https://github.com/ethereum/consensus-specs/blob/dev/specs/phase0/validator.md#signature
@@ -201,9 +1225,9 @@ impl BLSSignMsg {
                 let domain = get_domain(
                     m.fork_info.clone(),
                     DOMAIN_BEACON_PROPOSER,
-                    Some(compute_epoch_at_slot(m.block.slot.clone())),
+                    Some(compute_epoch_at_slot(m.block.slot())),
                 );
-                compute_signing_root(m.block.clone(), domain)
+                compute_signing_root(m.block.header(), domain)
             }
             // https://github.com/ethereum/consensus-specs/blob/dev/specs/phase0/validator.md#signature
             BLSSignMsg::BLOCK_V2(m) | BLSSignMsg::block_v2(m) => {
@@ -301,4 +1325,1311 @@ impl BLSSignMsg {
             }
         }
     }
+
+    /// Returns the SSZ serialization of the object `to_signing_root` hashes, so a client can
+    /// recompute its hash tree root and catch any field-level serialization drift
+    pub fn signing_object_ssz(&self) -> Vec<u8> {
+        match self {
+            BLSSignMsg::BLOCK(m) | BLSSignMsg::block(m) => m.block.header().as_ssz_bytes(),
+            BLSSignMsg::BLOCK_V2(m) | BLSSignMsg::block_v2(m) => {
+                m.beacon_block.block_header.as_ssz_bytes()
+            }
+            BLSSignMsg::ATTESTATION(m) | BLSSignMsg::attestation(m) => m.attestation.as_ssz_bytes(),
+            BLSSignMsg::RANDAO_REVEAL(m) | BLSSignMsg::randao_reveal(m) => {
+                m.randao_reveal.epoch.as_ssz_bytes()
+            }
+            BLSSignMsg::AGGREGATE_AND_PROOF(m) | BLSSignMsg::aggregate_and_proof(m) => {
+                m.aggregate_and_proof.as_ssz_bytes()
+            }
+            BLSSignMsg::AGGREGATION_SLOT(m) | BLSSignMsg::aggregation_slot(m) => {
+                m.aggregation_slot.slot.as_ssz_bytes()
+            }
+            BLSSignMsg::DEPOSIT(m) | BLSSignMsg::deposit(m) => m.deposit.as_ssz_bytes(),
+            BLSSignMsg::VOLUNTARY_EXIT(m) | BLSSignMsg::voluntary_exit(m) => {
+                m.voluntary_exit.as_ssz_bytes()
+            }
+            BLSSignMsg::SYNC_COMMITTEE_MESSAGE(m) | BLSSignMsg::sync_committee_message(m) => {
+                m.sync_committee_message.beacon_block_root.as_ssz_bytes()
+            }
+            BLSSignMsg::SYNC_COMMITTEE_SELECTION_PROOF(m)
+            | BLSSignMsg::sync_committee_selection_proof(m) => {
+                m.sync_aggregator_selection_data.as_ssz_bytes()
+            }
+            BLSSignMsg::SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF(m)
+            | BLSSignMsg::sync_committee_contribution_and_proof(m) => {
+                m.contribution_and_proof.as_ssz_bytes()
+            }
+            BLSSignMsg::VALIDATOR_REGISTRATION(m) | BLSSignMsg::validator_registration(m) => {
+                m.validator_registration.as_ssz_bytes()
+            }
+        }
+    }
+}
+
+/// The checks of `BLSSignMsg::check_fork_version` on the fork info of any consensus message
+pub fn check_fork_info(f: &ForkInfo, allow_genesis_fork: bool) -> Result<()> {
+    if !allow_genesis_fork && f.fork.current_version == GENESIS_FORK_VERSION {
+        bail!(
+            "Refusing to sign under the genesis fork version 0x{}",
+            hex::encode(GENESIS_FORK_VERSION)
+        )
+    }
+    if let Some(network) = fork_schedule(&f.genesis_validators_root) {
+        for version in [f.fork.current_version, f.fork.previous_version] {
+            if !network.fork_versions.contains(&version) {
+                bail!(
+                    "Fork version 0x{} is not scheduled on {} (genesis_validators_root 0x{})",
+                    hex::encode(version),
+                    network.name,
+                    network.genesis_validators_root
+                )
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The `type` of a signing request is not one of `BLSSignMsg::SIGNING_TYPES`, as opposed to the
+/// request being malformed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSigningType {
+    pub signing_type: String,
+}
+
+impl std::fmt::Display for UnknownSigningType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Unknown signing type {}, expected one of {}",
+            self.signing_type,
+            BLSSignMsg::SIGNING_TYPES.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnknownSigningType {}
+
+impl UnknownSigningType {
+    /// Returns true if `e` was caused by an unknown signing type
+    pub fn is_cause_of(e: &anyhow::Error) -> bool {
+        e.chain().any(|c| c.is::<UnknownSigningType>())
+    }
+}
+
+/// Body of a signing request: a `BLSSignMsg` plus the schema `version` it was written against.
+/// Requests without a `version` are read as `SIGN_REQUEST_VERSION`. Version 0 is the schema
+/// from before the field existed, which has the same shape as version 1.
+#[derive(Debug)]
+pub struct SignRequest {
+    pub version: u32,
+    pub msg: BLSSignMsg,
+}
+
+impl SignRequest {
+    /// Parses `body` against the schema of `version`. Versions newer than this signer understands
+    /// are rejected rather than parsed as the current schema.
+    pub fn parse(version: u32, body: serde_json::Value) -> Result<BLSSignMsg> {
+        match version {
+            0 | 1 => {
+                if let Some(t) = body.get("type").and_then(|t| t.as_str()) {
+                    if !BLSSignMsg::SIGNING_TYPES
+                        .iter()
+                        .any(|s| *s == t || s.to_lowercase() == t)
+                    {
+                        return Err(UnknownSigningType {
+                            signing_type: t.to_string(),
+                        }
+                        .into());
+                    }
+                }
+                Ok(serde_json::from_value(body)?)
+            }
+            v => bail!(
+                "Unsupported signing request version {v}, this signer supports versions up to {}",
+                crate::constants::SIGN_REQUEST_VERSION
+            ),
+        }
+    }
+}
+
+impl SignRequest {
+    /// Reads a signing request from its JSON `body`, see `SignRequest::parse`. Unlike
+    /// deserializing, the error keeps its cause, e.g. `UnknownSigningType`.
+    pub fn from_value(body: serde_json::Value) -> Result<SignRequest> {
+        let serde_json::Value::Object(mut body) = body else {
+            bail!("Signing requests must be JSON objects")
+        };
+        let version = match body.remove("version") {
+            None => crate::constants::SIGN_REQUEST_VERSION,
+            Some(v) => serde_json::from_value(v)?,
+        };
+        let msg = SignRequest::parse(version, serde_json::Value::Object(body))?;
+        Ok(SignRequest { version, msg })
+    }
+}
+
+impl From<BLSSignMsg> for SignRequest {
+    fn from(msg: BLSSignMsg) -> Self {
+        SignRequest {
+            version: crate::constants::SIGN_REQUEST_VERSION,
+            msg,
+        }
+    }
+}
+
+impl Serialize for SignRequest {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut body = serde_json::to_value(&self.msg).map_err(serde::ser::Error::custom)?;
+        if let Some(fields) = body.as_object_mut() {
+            fields.insert("version".to_string(), self.version.into());
+        }
+        body.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SignRequest {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let body = serde_json::Map::deserialize(deserializer)?;
+        SignRequest::from_value(serde_json::Value::Object(body)).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::key_management::delete_bls_key;
+
+    #[test]
+    fn test_sign_request_versions() {
+        let body = |version: Option<u32>| {
+            let mut body = serde_json::json!({
+                "type": "RANDAO_REVEAL",
+                "fork_info": {
+                    "fork": {
+                        "previous_version": "0x03000000",
+                        "current_version": "0x04000000",
+                        "epoch": "2"
+                    },
+                    "genesis_validators_root": format!("0x{}", "2a".repeat(32))
+                },
+                "randao_reveal": { "epoch": "10" }
+            });
+            if let Some(v) = version {
+                body["version"] = v.into();
+            }
+            body.to_string()
+        };
+
+        let req: SignRequest = serde_json::from_str(&body(None)).unwrap();
+        assert_eq!(req.version, crate::constants::SIGN_REQUEST_VERSION);
+        assert!(matches!(req.msg, BLSSignMsg::RANDAO_REVEAL(_)));
+        let req: SignRequest = serde_json::from_str(&body(Some(0))).unwrap();
+        assert_eq!(req.version, 0);
+
+        let err = serde_json::from_str::<SignRequest>(&body(Some(7))).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unsupported signing request version 7"));
+
+        // Unknown types are told apart from malformed requests by their cause
+        let mut unknown: serde_json::Value = serde_json::from_str(&body(None)).unwrap();
+        unknown["type"] = "DEPOSIT_V9".into();
+        let err = SignRequest::from_value(unknown).unwrap_err();
+        assert!(UnknownSigningType::is_cause_of(&err));
+        let mut malformed: serde_json::Value = serde_json::from_str(&body(None)).unwrap();
+        malformed["randao_reveal"] = serde_json::json!({});
+        let err = SignRequest::from_value(malformed).unwrap_err();
+        assert!(!UnknownSigningType::is_cause_of(&err));
+
+        // Round trips with the version alongside the message fields
+        let req: SignRequest = serde_json::from_str(&body(Some(1))).unwrap();
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["type"], "RANDAO_REVEAL");
+    }
+
+    #[test]
+    fn test_check_fork_version() {
+        let randao_reveal = |current_version: Version| {
+            let mut fork_info = ForkInfo::default();
+            fork_info.fork.current_version = current_version;
+            BLSSignMsg::RANDAO_REVEAL(RandaoRevealRequest {
+                fork_info,
+                signingRoot: None,
+                randao_reveal: RandaoReveal { epoch: 10 },
+            })
+        };
+
+        let msg = randao_reveal(GENESIS_FORK_VERSION);
+        assert!(msg.check_fork_version(false).is_err());
+        assert!(msg.check_fork_version(true).is_ok());
+        assert!(randao_reveal([3, 0, 0, 0])
+            .check_fork_version(false)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_signing_object_ssz_rehashes_to_signing_root() {
+        let mut fork_info = ForkInfo::default();
+        fork_info.fork.current_version = [3, 0, 0, 0];
+
+        let exit = BLSSignMsg::VOLUNTARY_EXIT(VoluntaryExitRequest {
+            fork_info: fork_info.clone(),
+            signingRoot: None,
+            voluntary_exit: VoluntaryExit {
+                epoch: 12,
+                validator_index: 345,
+            },
+        });
+        let decoded = VoluntaryExit::from_ssz_bytes(&exit.signing_object_ssz()).unwrap();
+        assert_eq!((decoded.epoch, decoded.validator_index), (12, 345));
+        assert_eq!(exit.signing_type(), "VOLUNTARY_EXIT");
+        let domain = get_domain(fork_info.clone(), DOMAIN_VOLUNTARY_EXIT, Some(12));
+        assert_eq!(
+            compute_signing_root(decoded, domain),
+            exit.to_signing_root(None)
+        );
+
+        let randao = BLSSignMsg::RANDAO_REVEAL(RandaoRevealRequest {
+            fork_info: fork_info.clone(),
+            signingRoot: None,
+            randao_reveal: RandaoReveal { epoch: 10 },
+        });
+        let decoded = Epoch::from_ssz_bytes(&randao.signing_object_ssz()).unwrap();
+        let domain = get_domain(fork_info, DOMAIN_RANDAO, Some(10));
+        assert_eq!(
+            compute_signing_root(decoded, domain),
+            randao.to_signing_root(None)
+        );
+    }
+
+    #[test]
+    fn test_check_fork_version_against_network_schedule() {
+        let randao_reveal = |gvr_hex: &str, version: Version| {
+            let mut fork_info = ForkInfo::default();
+            fork_info.genesis_validators_root = hex::decode(gvr_hex).unwrap().try_into().unwrap();
+            fork_info.fork.previous_version = version;
+            fork_info.fork.current_version = version;
+            BLSSignMsg::RANDAO_REVEAL(RandaoRevealRequest {
+                fork_info,
+                signingRoot: None,
+                randao_reveal: RandaoReveal { epoch: 10 },
+            })
+        };
+        let mainnet = KNOWN_NETWORKS[0].genesis_validators_root;
+        let holesky_deneb = [0x05, 0x01, 0x70, 0x00];
+        assert_eq!(fork_schedule(&[0x2a; 32]).map(|n| n.name), None);
+
+        // Mainnet gvr with a holesky fork version
+        assert!(randao_reveal(mainnet, holesky_deneb)
+            .check_fork_version(false)
+            .is_err());
+        assert!(randao_reveal(mainnet, [0x04, 0x00, 0x00, 0x00])
+            .check_fork_version(false)
+            .is_ok());
+        assert!(
+            randao_reveal(KNOWN_NETWORKS[1].genesis_validators_root, holesky_deneb)
+                .check_fork_version(false)
+                .is_ok()
+        );
+
+        // Unknown networks are not checked against a schedule
+        assert!(randao_reveal(&hex::encode([0x2a; 32]), holesky_deneb)
+            .check_fork_version(false)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_validate_withdrawal_credentials() {
+        let addr = [0xab_u8; 20];
+        let wc = execution_withdrawal_credentials(COMPOUNDING_WITHDRAWAL_PREFIX, &addr).unwrap();
+        assert_eq!(wc[0], 0x02);
+        assert_eq!(&wc[12..], &addr);
+        assert!(validate_withdrawal_credentials(&wc).is_ok());
+
+        let wc = execution_withdrawal_credentials(ETH1_ADDRESS_WITHDRAWAL_PREFIX, &addr).unwrap();
+        assert!(validate_withdrawal_credentials(&wc).is_ok());
+        assert!(validate_withdrawal_credentials(&[0_u8; 32]).is_ok());
+
+        // Bad address length or prefix
+        assert!(
+            execution_withdrawal_credentials(COMPOUNDING_WITHDRAWAL_PREFIX, &[0xab; 19]).is_err()
+        );
+        assert!(execution_withdrawal_credentials(0x03, &addr).is_err());
+
+        // Non-zero padding before the execution address
+        let mut bad =
+            execution_withdrawal_credentials(COMPOUNDING_WITHDRAWAL_PREFIX, &addr).unwrap();
+        bad[5] = 1;
+        assert!(validate_withdrawal_credentials(&bad).is_err());
+
+        // Zero execution address
+        let mut bad = [0_u8; 32];
+        bad[0] = COMPOUNDING_WITHDRAWAL_PREFIX;
+        assert!(validate_withdrawal_credentials(&bad).is_err());
+
+        // Unknown prefix
+        let mut bad = wc;
+        bad[0] = 0x03;
+        assert!(validate_withdrawal_credentials(&bad).is_err());
+    }
+
+    #[test]
+    fn test_deposit_with_compounding_credentials() {
+        let sk_set = bls_keys::new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        bls_keys::save_bls_key(&sk_set).unwrap();
+        let withdrawal_credentials =
+            execution_withdrawal_credentials(COMPOUNDING_WITHDRAWAL_PREFIX, &[0x11_u8; 20])
+                .unwrap();
+        let deposit_message = DepositMessage {
+            pubkey: pk.to_bytes().to_vec().into(),
+            withdrawal_credentials,
+            amount: crate::constants::FULL_DEPOSIT_AMOUNT,
+        };
+
+        let resp =
+            get_deposit_signature(pk.to_hex(), deposit_message.clone(), GENESIS_FORK_VERSION)
+                .unwrap();
+        assert_eq!(
+            resp.withdrawal_credentials,
+            hex::encode(withdrawal_credentials)
+        );
+
+        // The deposit root computation is unchanged
+        let (sig, dd_root) =
+            sign_full_deposit(&sk_set, withdrawal_credentials, GENESIS_FORK_VERSION).unwrap();
+        assert_eq!(resp.signature, hex::encode(&sig[..]));
+        assert_eq!(resp.deposit_data_root, hex::encode(dd_root));
+
+        // Malformed compounding credentials are refused
+        let mut bad_message = deposit_message;
+        bad_message.withdrawal_credentials[1] = 0xff;
+        assert!(get_deposit_signature(pk.to_hex(), bad_message, GENESIS_FORK_VERSION).is_err());
+        delete_bls_key(&pk.to_hex()).unwrap();
+    }
+
+    #[test]
+    fn test_compute_deposit_data_root() {
+        // The 32 ETH deposit of the guardian keygen test payload, with 0x01 withdrawal credentials
+        let pubkey: [u8; 48] = hex::decode("b927f246ed54236ce810f1296e9ee85574c4a59d7472aa50f9674d8ba8eb0d8b697065e22f86cad69f8526ee343fa481").unwrap().try_into().unwrap();
+        let signature: [u8; 96] = hex::decode("8e5f46196fa45a0866bb1422d7283575683af565fe070d9af236a3cbf334b4ab0b5007400b5e6fb2fe86d2ed0f7206db14bc2f699483a9a2cf564db8201afeb374818e44182b000d2f97390a65599377a36da857381c3e7fb4fa1fd85786e1b9").unwrap().try_into().unwrap();
+        let root = compute_deposit_data_root(
+            &pubkey,
+            [0x01; 32],
+            crate::constants::FULL_DEPOSIT_AMOUNT,
+            &signature,
+        );
+        assert_eq!(
+            hex::encode(root),
+            "b28322d43f10b231f3631951f4fe444de646b8efee3647023068dd3d3db259ee"
+        );
+
+        // Agrees with the SSZ root of freshly signed deposits, and commits to every field
+        let sk_set = bls_keys::new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        let wc = execution_withdrawal_credentials(ETH1_ADDRESS_WITHDRAWAL_PREFIX, &[0x22_u8; 20])
+            .unwrap();
+        let (sig, dd_root) = sign_full_deposit(&sk_set, wc, GENESIS_FORK_VERSION).unwrap();
+        let sig: [u8; 96] = sig[..].try_into().unwrap();
+        let amount = crate::constants::FULL_DEPOSIT_AMOUNT;
+        assert_eq!(
+            compute_deposit_data_root(&pk.to_bytes(), wc, amount, &sig),
+            dd_root
+        );
+        assert_ne!(
+            compute_deposit_data_root(&pk.to_bytes(), wc, amount - 1, &sig),
+            dd_root
+        );
+        assert_ne!(
+            compute_deposit_data_root(&pk.to_bytes(), [0x01; 32], amount, &sig),
+            dd_root
+        );
+        assert_ne!(
+            compute_deposit_data_root(&pubkey, wc, amount, &sig),
+            dd_root
+        );
+    }
+
+    #[test]
+    fn test_sign_commitment() {
+        let sk_set = bls_keys::new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        bls_keys::save_bls_key(&sk_set).unwrap();
+        let domain_type: DomainType = [0xaa, 0, 0, 0];
+        let data = b"some commitment";
+
+        // Disabled by default. The flag is passed in rather than set, as it is process-global.
+        assert!(!slashing_bypass_allowed());
+        assert!(sign_commitment(pk.to_hex(), domain_type, data).is_err());
+        assert!(sign_commitment_with_bypass(pk.to_hex(), domain_type, data, false).is_err());
+
+        // Consensus domains are never allowed
+        assert!(
+            sign_commitment_with_bypass(pk.to_hex(), DOMAIN_BEACON_ATTESTER, data, true).is_err()
+        );
+
+        let sig = sign_commitment_with_bypass(pk.to_hex(), domain_type, data, true).unwrap();
+
+        // Signature is over the List[byte] hash tree root
+        let root = compute_signing_root(
+            Commitment::new(data.to_vec()).unwrap(),
+            compute_domain(domain_type, None, None),
+        );
+        let mut sig_bytes = [0_u8; crate::constants::BLS_SIG_BYTES];
+        sig_bytes.copy_from_slice(&sig);
+        assert!(pk.verify(&blsttc::Signature::from_bytes(sig_bytes).unwrap(), root));
+
+        delete_bls_key(&pk.to_hex()).unwrap();
+    }
+
+    #[test]
+    fn test_key_roles() {
+        let domain_type: DomainType = [0xaa, 0, 0, 0];
+        let data = b"some commitment";
+        let fork = Fork {
+            previous_version: [3, 0, 0, 0],
+            current_version: [4, 0, 0, 0],
+            epoch: 0,
+        };
+        let blinded_block = |slot| BlindedBeaconBlock::from(&full_block(slot));
+
+        // General keys sign commitments without the slashing-bypass flag, never consensus objects
+        let general = bls_keys::new_bls_key(0);
+        let general_pk = general.public_keys().public_key().to_hex();
+        bls_keys::save_bls_key_with_role(&general, KeyRole::General).unwrap();
+        assert!(!slashing_bypass_allowed());
+        assert!(sign_commitment(general_pk.clone(), domain_type, data).is_ok());
+        assert!(check_key_role(&general_pk, true).is_err());
+        let err = sign_blinded_beacon_block(
+            general_pk.clone(),
+            blinded_block(1),
+            fork.clone(),
+            [0; 32],
+            false,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("may not sign consensus objects"));
+
+        // Validator keys sign consensus objects under slashing protection, never commitments
+        let validator = bls_keys::new_bls_key(0);
+        let validator_pk = validator.public_keys().public_key().to_hex();
+        bls_keys::save_bls_key_with_role(&validator, KeyRole::Validator).unwrap();
+        SlashingProtectionData::from_pk_hex(&validator_pk)
+            .unwrap()
+            .write()
+            .unwrap();
+        let err = sign_commitment(validator_pk.clone(), domain_type, data).unwrap_err();
+        assert!(err.to_string().contains("may only sign consensus objects"));
+        sign_blinded_beacon_block(
+            validator_pk.clone(),
+            blinded_block(1),
+            fork.clone(),
+            [0; 32],
+            false,
+        )
+        .unwrap();
+        assert!(sign_blinded_beacon_block(
+            validator_pk.clone(),
+            blinded_block(1),
+            fork,
+            [0; 32],
+            false
+        )
+        .is_err());
+
+        // The role survives the key being marked attested
+        crate::io::key_management::mark_key_attested(
+            crate::io::key_management::KeyType::Bls,
+            &general_pk,
+        )
+        .unwrap();
+        assert_eq!(
+            crate::io::key_management::read_bls_key_role(&general_pk).unwrap(),
+            Some(KeyRole::General)
+        );
+
+        delete_bls_key(&general_pk).unwrap();
+        delete_bls_key(&validator_pk).unwrap();
+    }
+
+    #[test]
+    fn test_network_dst_separates_signatures() {
+        const TEST_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_DEVNET_";
+        let network = register_network(NetworkForkSchedule {
+            name: "dst-devnet",
+            genesis_validators_root:
+                "5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a5a",
+            fork_versions: &[[0x10, 0x00, 0x00, 0x00]],
+            dst: TEST_DST,
+        })
+        .unwrap();
+        assert_eq!(network_dst(&[0x5a; 32]), TEST_DST);
+        assert!(register_network(NetworkForkSchedule {
+            name: "mainnet-copy",
+            genesis_validators_root: KNOWN_NETWORKS[0].genesis_validators_root,
+            fork_versions: &[],
+            dst: TEST_DST,
+        })
+        .is_err());
+
+        let mut fork_info = ForkInfo::default();
+        fork_info.genesis_validators_root = [0x5a; 32];
+        fork_info.fork.previous_version = network.fork_versions[0];
+        fork_info.fork.current_version = network.fork_versions[0];
+        let req = BLSSignMsg::RANDAO_REVEAL(RandaoRevealRequest {
+            fork_info,
+            signingRoot: None,
+            randao_reveal: RandaoReveal { epoch: 10 },
+        });
+        assert!(req.check_fork_version(false).is_ok());
+        assert_eq!(req.dst(), TEST_DST);
+
+        let sk_set = bls_keys::new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        bls_keys::save_bls_key(&sk_set).unwrap();
+        let root = req.to_signing_root(None);
+        let sig =
+            bls_keys::bls_sign_root_from_saved_sk_with_dst(&pk.to_hex(), &root, req.dst()).unwrap();
+
+        // Verifies only under the network's DST
+        assert!(bls_keys::bls_verify_with_dst(&pk, &sig, &root, TEST_DST));
+        assert!(!bls_keys::bls_verify_with_dst(
+            &pk,
+            &sig,
+            &root,
+            crate::constants::CIPHER_SUITE.as_bytes()
+        ));
+        assert!(!pk.verify(&sig, root));
+
+        // Unknown networks keep signing under the default DST
+        let default_sig =
+            bls_keys::bls_sign_with_dst(&sk_set, &root, network_dst(&[0x2a; 32])).unwrap();
+        assert!(pk.verify(&default_sig, root));
+
+        delete_bls_key(&pk.to_hex()).unwrap();
+        assert!(unregister_network(&[0x5a; 32]));
+        assert_eq!(
+            network_dst(&[0x5a; 32]),
+            crate::constants::CIPHER_SUITE.as_bytes()
+        );
+        assert!(!unregister_network(&[0x5a; 32]));
+
+        // Registry files must name every field of a network with well-formed values
+        assert!(register_networks(r#"[{"name": "devnet"}]"#).is_err());
+        let short_version = format!(
+            r#"[{{"name": "devnet", "genesis_validators_root": "{}", "fork_versions": ["0x10"]}}]"#,
+            "5b".repeat(32)
+        );
+        assert!(register_networks(&short_version).is_err());
+    }
+
+    fn full_block(slot: Slot) -> BeaconBlock {
+        let root = format!("0x{}", "11".repeat(32));
+        let mut block: BeaconBlock = serde_json::from_value(serde_json::json!({
+            "slot": slot.to_string(),
+            "proposer_index": "7",
+            "parent_root": root,
+            "state_root": root,
+            "body": {
+                "randao_reveal": format!("0x{}", "22".repeat(96)),
+                "eth1_data": {
+                    "deposit_root": root,
+                    "deposit_count": "3",
+                    "block_hash": root
+                },
+                "graffiti": root,
+                "proposer_slashings": [],
+                "attester_slashings": [],
+                "attestations": [],
+                "deposits": [],
+                "voluntary_exits": [],
+                "sync_aggregate": {
+                    "sync_committee_bits": format!("0x{}", "ff".repeat(64)),
+                    "sync_committee_signature": format!("0x{}", "33".repeat(96))
+                },
+                "execution_payload": {
+                    "parent_hash": root,
+                    "fee_recipient": format!("0x{}", "44".repeat(20)),
+                    "state_root": root,
+                    "receipts_root": root,
+                    "logs_bloom": format!("0x{}", "00".repeat(256)),
+                    "prev_randao": root,
+                    "block_number": "100",
+                    "gas_limit": "30000000",
+                    "gas_used": "21000",
+                    "timestamp": "1700000000",
+                    "extra_data": "0x1234",
+                    "base_fee_per_gas": "7",
+                    "block_hash": root,
+                    "transactions": [],
+                    "withdrawals": []
+                },
+                "bls_to_execution_changes": []
+            }
+        }))
+        .unwrap();
+        let payload = &mut block.body.execution_payload;
+        payload
+            .transactions
+            .push(vec![0x02, 0xf8, 0x6c].into())
+            .unwrap();
+        payload
+            .withdrawals
+            .push(Withdrawal {
+                index: 1,
+                validator_index: 7,
+                address: vec![0x44; 20].into(),
+                amount: 32,
+            })
+            .unwrap();
+        block
+    }
+
+    #[test]
+    fn test_blinded_block_root_matches_full_block() {
+        let block = full_block(64);
+        let blinded = BlindedBeaconBlock::from(&block);
+        assert_eq!(blinded.tree_hash_root(), block.tree_hash_root());
+
+        // The header commits to the payload's transactions
+        let mut other = block.clone();
+        other.body.execution_payload.transactions = <_>::from(vec![]);
+        assert_ne!(
+            BlindedBeaconBlock::from(&other).tree_hash_root(),
+            blinded.tree_hash_root()
+        );
+    }
+
+    #[test]
+    fn test_sign_blinded_beacon_block() {
+        let sk_set = bls_keys::new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        bls_keys::save_bls_key(&sk_set).unwrap();
+        SlashingProtectionData::from_pk_hex(&pk.to_hex())
+            .unwrap()
+            .write()
+            .unwrap();
+
+        let block = full_block(64);
+        let mut fork_info = ForkInfo::default();
+        fork_info.fork.previous_version = [3, 0, 0, 0];
+        fork_info.fork.current_version = [4, 0, 0, 0];
+        let sig = sign_blinded_beacon_block(
+            pk.to_hex(),
+            BlindedBeaconBlock::from(&block),
+            fork_info.fork.clone(),
+            fork_info.genesis_validators_root,
+            false,
+        )
+        .unwrap();
+
+        // Verifies over the full block's signing root
+        let full_root = BLSSignMsg::BLOCK(BlockRequest {
+            fork_info: fork_info.clone(),
+            signingRoot: None,
+            block: block.clone().into(),
+        })
+        .to_signing_root(None);
+        let sig = bls_keys::bls_sig_from_bytes(&sig).unwrap();
+        assert!(pk.verify(&sig, full_root));
+
+        // The slot is now protected
+        let db = SlashingProtectionData::read(&pk.to_hex()).unwrap();
+        assert_eq!(db.get_latest_signed_block_slot(), 64);
+        assert!(sign_blinded_beacon_block(
+            pk.to_hex(),
+            BlindedBeaconBlock::from(&block),
+            fork_info.fork.clone(),
+            fork_info.genesis_validators_root,
+            false,
+        )
+        .is_err());
+
+        // The fork is checked as for "BLOCK" requests before anything is signed
+        let mut genesis_fork = fork_info.fork.clone();
+        genesis_fork.current_version = GENESIS_FORK_VERSION;
+        let err = sign_blinded_beacon_block(
+            pk.to_hex(),
+            BlindedBeaconBlock::from(&full_block(65)),
+            genesis_fork,
+            fork_info.genesis_validators_root,
+            false,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("genesis fork version"));
+        let db = SlashingProtectionData::read(&pk.to_hex()).unwrap();
+        assert_eq!(db.get_latest_signed_block_slot(), 64);
+
+        delete_bls_key(&pk.to_hex()).unwrap();
+    }
+
+    #[test]
+    fn test_sign_beacon_block() {
+        let sk_set = bls_keys::new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        bls_keys::save_bls_key(&sk_set).unwrap();
+        SlashingProtectionData::from_pk_hex(&pk.to_hex())
+            .unwrap()
+            .write()
+            .unwrap();
+
+        let mut fork_info = ForkInfo::default();
+        fork_info.fork.previous_version = [3, 0, 0, 0];
+        fork_info.fork.current_version = [4, 0, 0, 0];
+        let block = full_block(64);
+        let header = |slot| BeaconBlockHeader {
+            slot,
+            proposer_index: block.proposer_index,
+            parent_root: block.parent_root,
+            state_root: block.state_root,
+            body_root: block.body.tree_hash_root().to_fixed_bytes(),
+        };
+        let sig = sign_beacon_block(&pk.to_hex(), header(64), fork_info.clone(), false).unwrap();
+
+        // Same signing root as a "BLOCK" request for the block
+        let root = BLSSignMsg::BLOCK(BlockRequest {
+            fork_info: fork_info.clone(),
+            signingRoot: None,
+            block: block.clone().into(),
+        })
+        .to_signing_root(None);
+        let sig = bls_keys::bls_sig_from_bytes(&sig).unwrap();
+        assert!(pk.verify(&sig, root));
+
+        // The header's slot was recorded, so it cannot be signed again
+        let db = SlashingProtectionData::read(&pk.to_hex()).unwrap();
+        assert_eq!(db.get_latest_signed_block_slot(), 64);
+        assert!(sign_beacon_block(&pk.to_hex(), header(64), fork_info.clone(), false).is_err());
+
+        // Empty bodies and the genesis fork version are refused before the slot is recorded
+        let mut empty = header(65);
+        empty.body_root = Root::default();
+        let err = sign_beacon_block(&pk.to_hex(), empty, fork_info.clone(), false).unwrap_err();
+        assert!(err.to_string().contains("all-zero body_root"));
+        let err =
+            sign_beacon_block(&pk.to_hex(), header(65), ForkInfo::default(), false).unwrap_err();
+        assert!(err.to_string().contains("genesis fork version"));
+        sign_beacon_block(&pk.to_hex(), header(65), fork_info, false).unwrap();
+
+        delete_bls_key(&pk.to_hex()).unwrap();
+    }
+
+    #[test]
+    fn test_sign_voluntary_exit() {
+        let sk_set = bls_keys::new_bls_key(0);
+        let pk_hex = sk_set.public_keys().public_key().to_hex();
+        bls_keys::save_bls_key(&sk_set).unwrap();
+        SlashingProtectionData::from_pk_hex(&pk_hex)
+            .unwrap()
+            .write()
+            .unwrap();
+
+        let mainnet = &KNOWN_NETWORKS[0];
+        let gvr: Root = hex::decode(mainnet.genesis_validators_root)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let capella = mainnet.fork_versions[3];
+        let (root, sig) = sign_voluntary_exit(&pk_hex, 194048, 1337, capella, gvr).unwrap();
+
+        let exit = VoluntaryExit {
+            epoch: 194048,
+            validator_index: 1337,
+        };
+        let domain = compute_domain(DOMAIN_VOLUNTARY_EXIT, Some(capella), Some(gvr));
+        assert_eq!(root, compute_signing_root(exit, domain));
+
+        // Verifies against the stored key, and only over this exit
+        let pk = bls_keys::bls_pk_from_hex(&pk_hex).unwrap();
+        let sig = bls_keys::bls_sig_from_bytes(&sig).unwrap();
+        let dst = network_dst(&gvr);
+        assert!(crate::crypto::bls_verify::verify(&pk, &root, &sig, dst));
+        let (other_root, _) = sign_voluntary_exit(&pk_hex, 194048, 1338, capella, gvr).unwrap();
+        assert!(!crate::crypto::bls_verify::verify(
+            &pk,
+            &other_root,
+            &sig,
+            dst
+        ));
+
+        // Fork versions off the network's schedule are refused as for requests
+        assert!(sign_voluntary_exit(&pk_hex, 194048, 1337, [9, 9, 9, 9], gvr).is_err());
+
+        delete_bls_key(&pk_hex).unwrap();
+        assert!(sign_voluntary_exit(&pk_hex, 194048, 1337, capella, gvr).is_err());
+    }
+
+    #[test]
+    fn test_sign_bls_to_execution_change() {
+        use sha2::{Digest, Sha256};
+        let sk_set = bls_keys::new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        bls_keys::save_bls_key(&sk_set).unwrap();
+
+        let mainnet = &KNOWN_NETWORKS[0];
+        let gvr: Root = hex::decode(mainnet.genesis_validators_root)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let address = [0x42_u8; 20];
+        let (root, sig) =
+            sign_bls_to_execution_change(&pk.to_hex(), 7, address, GENESIS_FORK_VERSION, gvr)
+                .unwrap();
+        let sig = bls_keys::bls_sig_from_bytes(&sig).unwrap();
+        assert!(crate::crypto::bls_verify::verify(
+            &pk,
+            &root,
+            &sig,
+            network_dst(&gvr)
+        ));
+
+        // The signed message names the stored key, which the validator's 0x00 credentials commit to
+        let change = BLSToExecutionChange {
+            validator_index: 7,
+            from_bls_pubkey: pk.to_bytes().to_vec().into(),
+            to_execution_address: address.to_vec().into(),
+        };
+        let domain = compute_domain(
+            DOMAIN_BLS_TO_EXECUTION_CHANGE,
+            Some(GENESIS_FORK_VERSION),
+            Some(gvr),
+        );
+        assert_eq!(root, compute_signing_root(change.clone(), domain));
+        let bls_credentials = bls_withdrawal_credentials(&pk.to_bytes());
+        assert_eq!(bls_credentials[0], BLS_WITHDRAWAL_PREFIX);
+        assert_eq!(
+            bls_credentials[1..],
+            Sha256::digest(&change.from_bls_pubkey[..])[1..]
+        );
+        validate_withdrawal_credentials(&bls_credentials).unwrap();
+        // Once applied, the credentials pay out to the signed execution address
+        let execution_credentials = execution_withdrawal_credentials(
+            ETH1_ADDRESS_WITHDRAWAL_PREFIX,
+            &change.to_execution_address[..],
+        )
+        .unwrap();
+        assert_eq!(execution_credentials[12..], address);
+
+        delete_bls_key(&pk.to_hex()).unwrap();
+    }
+
+    #[test]
+    fn test_block_request_selects_body_by_fork_version() {
+        let capella_block = serde_json::to_value(full_block(64)).unwrap();
+        let mut deneb_block = capella_block.clone();
+        let body = deneb_block["body"].as_object_mut().unwrap();
+        body.insert(
+            "blob_kzg_commitments".to_string(),
+            serde_json::json!([format!("0x{}", "55".repeat(48))]),
+        );
+        let payload = body["execution_payload"].as_object_mut().unwrap();
+        payload.insert("blob_gas_used".to_string(), "131072".into());
+        payload.insert("excess_blob_gas".to_string(), "0".into());
+        let mut block = capella_block.clone();
+        let body = block["body"].as_object_mut().unwrap();
+        body.remove("bls_to_execution_changes");
+        body["execution_payload"]
+            .as_object_mut()
+            .unwrap()
+            .remove("withdrawals");
+        let bellatrix_block = block.clone();
+        block["body"]
+            .as_object_mut()
+            .unwrap()
+            .remove("execution_payload");
+        let altair_block = block.clone();
+        block["body"]
+            .as_object_mut()
+            .unwrap()
+            .remove("sync_aggregate");
+        let phase0_block = block;
+
+        let mainnet = &crate::eth2::eth_types::KNOWN_NETWORKS[0];
+        let version = |fork: usize| format!("0x{}", hex::encode(mainnet.fork_versions[fork]));
+        let request = |block: &serde_json::Value, version: &str, gvr: &str| {
+            serde_json::from_value::<BlockRequest>(serde_json::json!({
+                "fork_info": {
+                    "fork": {
+                        "previous_version": version,
+                        "current_version": version,
+                        "epoch": "0"
+                    },
+                    "genesis_validators_root": format!("0x{gvr}")
+                },
+                "block": block
+            }))
+        };
+        let mainnet_request = |block: &serde_json::Value, fork| {
+            request(block, &version(fork), mainnet.genesis_validators_root)
+        };
+
+        let req = mainnet_request(&phase0_block, 0).unwrap();
+        let ForkBeaconBlock::Phase0(phase0) = &req.block else {
+            panic!("expected a phase0 block, got {:?}", req.block.fork())
+        };
+        assert_eq!(
+            req.block.body_root(),
+            phase0.body.tree_hash_root().to_fixed_bytes()
+        );
+        let domain = get_domain(req.fork_info.clone(), DOMAIN_BEACON_PROPOSER, Some(2));
+        let exp_root = compute_signing_root(phase0.clone(), domain);
+        assert_eq!(BLSSignMsg::BLOCK(req).to_signing_root(None), exp_root);
+
+        let req = mainnet_request(&altair_block, 1).unwrap();
+        let ForkBeaconBlock::Altair(altair) = &req.block else {
+            panic!("expected an altair block, got {:?}", req.block.fork())
+        };
+        let domain = get_domain(req.fork_info.clone(), DOMAIN_BEACON_PROPOSER, Some(2));
+        let exp_root = compute_signing_root(altair.clone(), domain);
+        assert_ne!(
+            req.block.body_root(),
+            mainnet_request(&phase0_block, 0).unwrap().block.body_root()
+        );
+        assert_eq!(BLSSignMsg::BLOCK(req).to_signing_root(None), exp_root);
+
+        // Each later fork is hashed under its own container
+        let forks = [
+            (&bellatrix_block, 2, BlockBodyFork::Bellatrix),
+            (&capella_block, 3, BlockBodyFork::Capella),
+            (&deneb_block, 4, BlockBodyFork::Deneb),
+        ];
+        let mut body_roots = vec![];
+        for (block, fork, exp_fork) in forks {
+            let req = mainnet_request(block, fork).unwrap();
+            assert_eq!(req.block.fork(), exp_fork);
+            body_roots.push(req.block.body_root());
+        }
+        assert_eq!(
+            body_roots[1],
+            full_block(64).body.tree_hash_root().to_fixed_bytes()
+        );
+        assert_ne!(body_roots[0], body_roots[1]);
+        assert_ne!(body_roots[1], body_roots[2]);
+        let ForkBeaconBlock::Deneb(deneb) = mainnet_request(&deneb_block, 4).unwrap().block else {
+            panic!("expected a deneb block")
+        };
+        assert_eq!(deneb.body.blob_kzg_commitments.len(), 1);
+        assert_eq!(deneb.body.execution_payload.blob_gas_used, 131072);
+
+        // A body must carry exactly the fields of its fork
+        assert!(mainnet_request(&altair_block, 3).is_err());
+        assert!(mainnet_request(&capella_block, 1).is_err());
+        assert!(mainnet_request(&capella_block, 2).is_err());
+        assert!(mainnet_request(&deneb_block, 3).is_err());
+        assert!(mainnet_request(&capella_block, 4).is_err());
+
+        // Forks after Deneb, unscheduled versions and unknown networks are refused
+        assert!(mainnet_request(&deneb_block, 5).is_err());
+        assert!(request(
+            &capella_block,
+            "0x42000000",
+            mainnet.genesis_validators_root
+        )
+        .is_err());
+        assert!(request(&capella_block, &version(3), &"00".repeat(32)).is_err());
+    }
+
+    #[test]
+    fn test_is_sync_committee_aggregator() {
+        use sha2::{Digest, Sha256};
+        let prefix =
+            |proof: &[u8]| u64::from_le_bytes(Sha256::digest(proof)[..8].try_into().unwrap());
+        let proofs: Vec<Vec<u8>> = (0..=255_u8).map(|i| vec![i; 96]).collect();
+
+        // Mainnet subcommittees of 128 select 1 in 8, smaller ones proportionally more
+        let mod_8 = proofs.iter().find(|p| prefix(p) % 8 == 0).unwrap();
+        let mod_4 = proofs.iter().find(|p| prefix(p) % 8 == 4).unwrap();
+        let odd = proofs.iter().find(|p| prefix(p) % 2 == 1).unwrap();
+        assert!(is_sync_committee_aggregator(mod_8, SYNC_SUBCOMMITTEE_SIZE));
+        assert!(!is_sync_committee_aggregator(mod_4, SYNC_SUBCOMMITTEE_SIZE));
+        assert!(is_sync_committee_aggregator(mod_4, 64));
+        assert!(!is_sync_committee_aggregator(odd, 64));
+        assert!(!is_sync_committee_aggregator(odd, 32));
+
+        // Subcommittees of at most TARGET_AGGREGATORS_PER_SYNC_SUBCOMMITTEE all aggregate
+        for size in [0, 1, 8, TARGET_AGGREGATORS_PER_SYNC_SUBCOMMITTEE] {
+            assert!(proofs.iter().all(|p| is_sync_committee_aggregator(p, size)));
+        }
+
+        let selected = proofs
+            .iter()
+            .filter(|p| is_sync_committee_aggregator(p, SYNC_SUBCOMMITTEE_SIZE))
+            .count();
+        assert_eq!(
+            selected,
+            proofs.iter().filter(|p| prefix(p) % 8 == 0).count()
+        );
+    }
+
+    #[test]
+    fn test_contribution_selection_proof_is_checked() {
+        let fork_info = ForkInfo {
+            fork: Fork {
+                previous_version: [3, 0, 0, 0],
+                current_version: [4, 0, 0, 0],
+                epoch: 2,
+            },
+            genesis_validators_root: [0x2a; 32],
+        };
+        let sk_set = bls_keys::new_bls_key(0);
+        let pk_hex = sk_set.public_keys().public_key().to_hex();
+        let selection_proof = |sk_set: &SecretKeySet, slot: Slot, subcommittee_index: u64| {
+            let data = SyncAggregatorSelectionData {
+                slot,
+                subcommittee_index,
+            };
+            let domain = get_domain(
+                fork_info.clone(),
+                DOMAIN_SYNC_COMMITTEE_SELECTION_PROOF,
+                Some(compute_epoch_at_slot(slot)),
+            );
+            let sig = bls_keys::bls_agg_sign(sk_set, &compute_signing_root(data, domain));
+            BLSSignature::from(sig.to_bytes().to_vec())
+        };
+        let request = |slot: Slot, selection_proof: BLSSignature| {
+            let mut contribution_and_proof = ContributionAndProof::default();
+            contribution_and_proof.contribution.slot = slot;
+            contribution_and_proof.contribution.subcommittee_index = 1;
+            contribution_and_proof.selection_proof = selection_proof;
+            BLSSignMsg::SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF(
+                SyncCommitteeContributionAndProofRequest {
+                    fork_info: fork_info.clone(),
+                    signingRoot: None,
+                    contribution_and_proof,
+                },
+            )
+        };
+        let is_aggregator =
+            |proof: &BLSSignature| is_sync_committee_aggregator(proof, SYNC_SUBCOMMITTEE_SIZE);
+        let slots = 100..400;
+        let slot = slots
+            .clone()
+            .find(|s| is_aggregator(&selection_proof(&sk_set, *s, 1)))
+            .unwrap();
+        let unselected_slot = slots
+            .clone()
+            .find(|s| !is_aggregator(&selection_proof(&sk_set, *s, 1)))
+            .unwrap();
+
+        let valid = request(slot, selection_proof(&sk_set, slot, 1));
+        valid.check_selection_proof(&pk_hex).unwrap();
+
+        // A selection proof by another key, or for another slot or subcommittee, is rejected
+        let other_sk_set = bls_keys::new_bls_key(0);
+        let other_key = request(slot, selection_proof(&other_sk_set, slot, 1));
+        assert!(other_key.check_selection_proof(&pk_hex).is_err());
+        let other_subcommittee = request(slot, selection_proof(&sk_set, slot, 2));
+        assert!(other_subcommittee.check_selection_proof(&pk_hex).is_err());
+        let other_slot = request(slot, selection_proof(&sk_set, unselected_slot, 1));
+        assert!(other_slot.check_selection_proof(&pk_hex).is_err());
+
+        // A valid proof that does not select the validator as an aggregator is rejected
+        let unselected = request(
+            unselected_slot,
+            selection_proof(&sk_set, unselected_slot, 1),
+        );
+        let err = unselected.check_selection_proof(&pk_hex).unwrap_err();
+        assert!(err.to_string().contains("aggregator"));
+
+        // Requests signed from SSZ are checked under the matching selection proof domain
+        let BLSSignMsg::SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF(m) = &valid else {
+            unreachable!()
+        };
+        let domain = get_domain(
+            fork_info.clone(),
+            DOMAIN_CONTRIBUTION_AND_PROOF,
+            Some(compute_epoch_at_slot(slot)),
+        );
+        let ssz_bytes = m.contribution_and_proof.as_ssz_bytes();
+        check_ssz_selection_proof(
+            "SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF",
+            &ssz_bytes,
+            domain,
+            &pk_hex,
+            valid.dst(),
+        )
+        .unwrap();
+        let BLSSignMsg::SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF(m) = &other_key else {
+            unreachable!()
+        };
+        let ssz_bytes = m.contribution_and_proof.as_ssz_bytes();
+        assert!(check_ssz_selection_proof(
+            "SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF",
+            &ssz_bytes,
+            domain,
+            &pk_hex,
+            valid.dst(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_check_ssz_fork() {
+        let fork_version = [4, 0, 0, 0];
+        let gvr = [0x2a; 32];
+        let domain = compute_domain(DOMAIN_BEACON_ATTESTER, Some(fork_version), Some(gvr));
+        let dst = check_ssz_fork("ATTESTATION", domain, Some(fork_version), Some(gvr), false);
+        assert_eq!(dst.unwrap(), network_dst(&gvr));
+
+        // The fork must be named, match the domain and not be the genesis fork version
+        assert!(check_ssz_fork("ATTESTATION", domain, None, Some(gvr), false).is_err());
+        let err = check_ssz_fork("ATTESTATION", domain, Some([3, 0, 0, 0]), Some(gvr), false)
+            .unwrap_err();
+        assert!(err.to_string().contains("was not computed from"));
+        let genesis_domain = compute_domain(
+            DOMAIN_BEACON_ATTESTER,
+            Some(GENESIS_FORK_VERSION),
+            Some(gvr),
+        );
+        let genesis = Some(GENESIS_FORK_VERSION);
+        assert!(check_ssz_fork("ATTESTATION", genesis_domain, genesis, Some(gvr), false).is_err());
+        assert!(check_ssz_fork("ATTESTATION", genesis_domain, genesis, Some(gvr), true).is_ok());
+
+        // Deposits are always signed under the genesis fork version and the default DST
+        let deposit_domain = compute_domain(DOMAIN_DEPOSIT, None, None);
+        let dst = check_ssz_fork("deposit", deposit_domain, None, None, false).unwrap();
+        assert_eq!(dst, crate::constants::CIPHER_SUITE.as_bytes());
+    }
+
+    #[test]
+    fn test_verify_indexed_attestation() {
+        let fork = Fork {
+            previous_version: [3, 0, 0, 0],
+            current_version: [4, 0, 0, 0],
+            epoch: 2,
+        };
+        let gvr = [0x2a; 32];
+        let data = AttestationData {
+            slot: 100,
+            index: 1,
+            beacon_block_root: [0x11; 32],
+            source: Checkpoint {
+                epoch: 2,
+                root: [0x22; 32],
+            },
+            target: Checkpoint {
+                epoch: 3,
+                root: [0x33; 32],
+            },
+        };
+        let domain = get_domain(
+            ForkInfo {
+                fork: fork.clone(),
+                genesis_validators_root: gvr,
+            },
+            DOMAIN_BEACON_ATTESTER,
+            Some(data.target.epoch),
+        );
+        let root = compute_signing_root(data.clone(), domain);
+
+        let indices: Vec<ValidatorIndex> = vec![3, 8, 21, 40];
+        let sk_sets: Vec<_> = indices.iter().map(|_| bls_keys::new_bls_key(0)).collect();
+        let pks_by_index: std::collections::HashMap<_, _> = indices
+            .iter()
+            .zip(sk_sets.iter())
+            .map(|(i, s)| (*i, s.public_keys().public_key()))
+            .collect();
+        let sigs: Vec<_> = sk_sets
+            .iter()
+            .map(|s| bls_keys::bls_agg_sign(s, &root))
+            .collect();
+        let agg_sig = bls_keys::aggregate_bls_sigs(&sigs).unwrap();
+        let attestation =
+            |indices: Vec<ValidatorIndex>, data: AttestationData| IndexedAttestation {
+                attesting_indices: indices.into(),
+                data,
+                signature: <_>::from(agg_sig.to_bytes().to_vec()),
+            };
+
+        let valid = attestation(indices.clone(), data.clone());
+        verify_indexed_attestation(&valid, &pks_by_index, fork.clone(), gvr).unwrap();
+
+        // Another fork, network or vote invalidates the signature
+        let other_fork = Fork {
+            epoch: 10,
+            ..fork.clone()
+        };
+        assert!(verify_indexed_attestation(&valid, &pks_by_index, other_fork, gvr).is_err());
+        assert!(verify_indexed_attestation(&valid, &pks_by_index, fork.clone(), [0; 32]).is_err());
+        let mut other_data = data.clone();
+        other_data.beacon_block_root = [0x12; 32];
+        let invalid = attestation(indices.clone(), other_data);
+        assert!(verify_indexed_attestation(&invalid, &pks_by_index, fork.clone(), gvr).is_err());
+
+        // Missing attesters, unknown, unsorted or repeated indices are rejected
+        let missing = attestation(indices[..3].to_vec(), data.clone());
+        assert!(verify_indexed_attestation(&missing, &pks_by_index, fork.clone(), gvr).is_err());
+        let unknown = attestation(vec![3, 8, 21, 41], data.clone());
+        assert!(verify_indexed_attestation(&unknown, &pks_by_index, fork.clone(), gvr).is_err());
+        let unsorted = attestation(vec![8, 3, 21, 40], data.clone());
+        assert!(verify_indexed_attestation(&unsorted, &pks_by_index, fork.clone(), gvr).is_err());
+        let repeated = attestation(vec![3, 8, 8, 21, 40], data.clone());
+        assert!(verify_indexed_attestation(&repeated, &pks_by_index, fork.clone(), gvr).is_err());
+        let empty = attestation(vec![], data);
+        assert!(verify_indexed_attestation(&empty, &pks_by_index, fork, gvr).is_err());
+    }
+
+    #[test]
+    fn test_sign_light_client_header() {
+        let sk_set = bls_keys::new_bls_key(0);
+        let pk = sk_set.public_keys().public_key();
+        bls_keys::save_bls_key(&sk_set).unwrap();
+        SlashingProtectionData::from_pk_hex(&pk.to_hex())
+            .unwrap()
+            .write()
+            .unwrap();
+
+        let header = BeaconBlockHeader {
+            slot: 8191,
+            proposer_index: 7,
+            parent_root: [1; 32],
+            state_root: [2; 32],
+            body_root: [3; 32],
+        };
+        let mut fork_info = ForkInfo::default();
+        fork_info.fork.previous_version = [3, 0, 0, 0];
+        fork_info.fork.current_version = [4, 0, 0, 0];
+        fork_info.fork.epoch = 256;
+        fork_info.genesis_validators_root = [0x2a; 32];
+        let sign = |header: &BeaconBlockHeader, fork_info: &ForkInfo, signature_slot| {
+            sign_light_client_header(
+                pk.to_hex(),
+                header.clone(),
+                fork_info.clone(),
+                signature_slot,
+                false,
+            )
+        };
+
+        // Signed in the first slot of epoch 256, so under the fork of epoch 255
+        let (header_root, sig) = sign(&header, &fork_info, 8192).unwrap();
+        assert_eq!(header_root, header.tree_hash_root().to_fixed_bytes());
+        let dst = network_dst(&fork_info.genesis_validators_root);
+        let verify = |version, sig: &BLSSignature| {
+            let domain = compute_domain(DOMAIN_SYNC_COMMITTEE, Some(version), Some([0x2a; 32]));
+            let root = compute_signing_root(header_root, domain);
+            let sig = bls_keys::bls_sig_from_bytes(sig).unwrap();
+            bls_keys::bls_verify_with_dst(&pk, &sig, &root, dst)
+        };
+        assert!(verify([3, 0, 0, 0], &sig));
+        assert!(!verify([4, 0, 0, 0], &sig));
+        // One slot later the domain is the new fork's
+        let (_, sig) = sign(&header, &fork_info, 8193).unwrap();
+        assert!(verify([4, 0, 0, 0], &sig));
+
+        // Not valid under the attester domain
+        let root = compute_signing_root(
+            header_root,
+            compute_domain(DOMAIN_BEACON_ATTESTER, Some([3, 0, 0, 0]), Some([0x2a; 32])),
+        );
+        let sig = bls_keys::bls_sig_from_bytes(&sig).unwrap();
+        assert!(!bls_keys::bls_verify_with_dst(&pk, &sig, &root, dst));
+
+        // The header must precede the signature slot, and the fork is checked
+        assert!(sign(&header, &fork_info, 8191).is_err());
+        assert!(sign(&header, &ForkInfo::default(), 8192).is_err());
+
+        delete_bls_key(&pk.to_hex()).unwrap();
+    }
 }
@@ -1,3 +1,4 @@
+use anyhow::{anyhow, bail, Result};
 use num_bigint::BigUint;
 use serde::de::{self, Deserializer};
 use serde::ser::{self, Serializer};
@@ -7,8 +8,10 @@ use serde_utils::quoted_u64;
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use ssz_types::{typenum, BitList, BitVector, FixedVector, VariableList};
+use std::sync::Mutex;
 use tree_hash_derive::TreeHash;
 
+use crate::constants::CIPHER_SUITE;
 use crate::strip_0x_prefix;
 
 /// Types
@@ -60,10 +63,196 @@ pub const DOMAIN_AGGREGATE_AND_PROOF: DomainType = [6_u8, 0_u8, 0_u8, 0_u8]; //
 pub const DOMAIN_SYNC_COMMITTEE: DomainType = [7_u8, 0_u8, 0_u8, 0_u8]; // '0x07000000'
 pub const DOMAIN_SYNC_COMMITTEE_SELECTION_PROOF: DomainType = [8_u8, 0_u8, 0_u8, 0_u8]; // '0x08000000'
 pub const DOMAIN_CONTRIBUTION_AND_PROOF: DomainType = [9_u8, 0_u8, 0_u8, 0_u8]; // '0x09000000'
+pub const DOMAIN_BLS_TO_EXECUTION_CHANGE: DomainType = [10_u8, 0_u8, 0_u8, 0_u8]; // '0x0A000000'
 pub const DOMAIN_APPLICATION_MASK: DomainType = [0_u8, 0_u8, 0_u8, 1_u8]; // '0x00000001'
 pub const DOMAIN_APPLICATION_BUILDER: DomainType = [0_u8, 0_u8, 0_u8, 1_u8]; // '0x00000001'
 
 pub const GENESIS_FORK_VERSION: Version = [0_u8, 0_u8, 0_u8, 0_u8]; // '0x00000000'
+
+/// Fork versions scheduled on a known network, identified by its genesis_validators_root
+#[derive(Debug)]
+pub struct NetworkForkSchedule {
+    pub name: &'static str,
+    pub genesis_validators_root: &'static str,
+    pub fork_versions: &'static [Version],
+    /// Hash-to-curve domain separation tag used when signing for this network
+    pub dst: &'static [u8],
+}
+
+pub static KNOWN_NETWORKS: [NetworkForkSchedule; 3] = [
+    NetworkForkSchedule {
+        name: "mainnet",
+        genesis_validators_root: "4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95",
+        fork_versions: &[
+            [0x00, 0x00, 0x00, 0x00],
+            [0x01, 0x00, 0x00, 0x00],
+            [0x02, 0x00, 0x00, 0x00],
+            [0x03, 0x00, 0x00, 0x00],
+            [0x04, 0x00, 0x00, 0x00],
+            [0x05, 0x00, 0x00, 0x00],
+        ],
+        dst: CIPHER_SUITE.as_bytes(),
+    },
+    NetworkForkSchedule {
+        name: "holesky",
+        genesis_validators_root: "9143aa7c615a7f7115e2b6aac319c03529df8242ae705fba9df39b79c59fa8b1",
+        fork_versions: &[
+            [0x01, 0x01, 0x70, 0x00],
+            [0x02, 0x01, 0x70, 0x00],
+            [0x03, 0x01, 0x70, 0x00],
+            [0x04, 0x01, 0x70, 0x00],
+            [0x05, 0x01, 0x70, 0x00],
+            [0x06, 0x01, 0x70, 0x00],
+        ],
+        dst: CIPHER_SUITE.as_bytes(),
+    },
+    NetworkForkSchedule {
+        name: "sepolia",
+        genesis_validators_root: "d8ea171f3c94aea21ebc42a1ed61052acf3f9209c00e4efbaaddac09ed9b8078",
+        fork_versions: &[
+            [0x90, 0x00, 0x00, 0x69],
+            [0x90, 0x00, 0x00, 0x70],
+            [0x90, 0x00, 0x00, 0x71],
+            [0x90, 0x00, 0x00, 0x72],
+            [0x90, 0x00, 0x00, 0x73],
+            [0x90, 0x00, 0x00, 0x74],
+        ],
+        dst: CIPHER_SUITE.as_bytes(),
+    },
+];
+
+/// Networks added at runtime with `register_network`, consulted after `KNOWN_NETWORKS`
+static REGISTERED_NETWORKS: Mutex<Vec<&'static NetworkForkSchedule>> = Mutex::new(Vec::new());
+
+/// Adds a network to the registry, e.g. a devnet signing under its own DST. Errors if a network
+/// with the same genesis_validators_root is already known.
+pub fn register_network(network: NetworkForkSchedule) -> Result<&'static NetworkForkSchedule> {
+    let gvr: Root = hex::decode(network.genesis_validators_root)?
+        .try_into()
+        .map_err(|_| anyhow!("genesis_validators_root must be 32 bytes"))?;
+    let mut registered = REGISTERED_NETWORKS
+        .lock()
+        .map_err(|e| anyhow!("Network registry lock poisoned: {:?}", e))?;
+    let gvr_hex = hex::encode(gvr);
+    if KNOWN_NETWORKS
+        .iter()
+        .chain(registered.iter().copied())
+        .any(|n| n.genesis_validators_root.eq_ignore_ascii_case(&gvr_hex))
+    {
+        bail!("Network with gvr {gvr_hex} is already registered");
+    }
+    let network: &'static NetworkForkSchedule = Box::leak(Box::new(network));
+    registered.push(network);
+    Ok(network)
+}
+
+/// Name of the known network whose genesis fork version is `fork_version`, or "custom"
+pub fn network_name(fork_version: &Version) -> &'static str {
+    KNOWN_NETWORKS
+        .iter()
+        .find(|n| n.fork_versions.first() == Some(fork_version))
+        .map_or("custom", |n| n.name)
+}
+
+/// Returns the fork schedule of the known network with this genesis_validators_root
+pub fn fork_schedule(genesis_validators_root: &Root) -> Option<&'static NetworkForkSchedule> {
+    let gvr_hex = hex::encode(genesis_validators_root);
+    if let Some(network) = KNOWN_NETWORKS
+        .iter()
+        .find(|n| n.genesis_validators_root == gvr_hex)
+    {
+        return Some(network);
+    }
+    let registered = match REGISTERED_NETWORKS.lock() {
+        Ok(registered) => registered,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    registered
+        .iter()
+        .copied()
+        .find(|n| n.genesis_validators_root.eq_ignore_ascii_case(&gvr_hex))
+}
+
+/// Returns the DST used to sign for the network with this genesis_validators_root, falling back
+/// to `CIPHER_SUITE` for unknown networks
+pub fn network_dst(genesis_validators_root: &Root) -> &'static [u8] {
+    fork_schedule(genesis_validators_root)
+        .map(|n| n.dst)
+        .unwrap_or(CIPHER_SUITE.as_bytes())
+}
+
+/// Removes a network added with `register_network`, returning true if it was registered
+pub fn unregister_network(genesis_validators_root: &Root) -> bool {
+    let mut registered = match REGISTERED_NETWORKS.lock() {
+        Ok(registered) => registered,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let gvr_hex = hex::encode(genesis_validators_root);
+    let before = registered.len();
+    registered.retain(|n| !n.genesis_validators_root.eq_ignore_ascii_case(&gvr_hex));
+    registered.len() != before
+}
+
+// A network of the file read by `register_networks_from_env`, with hex encoded fork versions
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NetworkConfig {
+    name: String,
+    genesis_validators_root: String,
+    fork_versions: Vec<String>,
+    /// Defaults to `CIPHER_SUITE`
+    #[serde(default)]
+    dst: Option<String>,
+}
+
+/// Registers every network of the JSON array `networks_json`, returning how many were added.
+/// Errors on the first malformed or already known network.
+pub fn register_networks(networks_json: &str) -> Result<usize> {
+    let networks: Vec<NetworkConfig> = serde_json::from_str(networks_json)
+        .map_err(|e| anyhow!("Bad network registry file: {:?}", e))?;
+    let count = networks.len();
+    for network in networks {
+        let gvr: String = strip_0x_prefix!(network.genesis_validators_root);
+        let fork_versions = network
+            .fork_versions
+            .iter()
+            .map(|v| -> Result<Version> {
+                let v: String = strip_0x_prefix!(v);
+                hex::decode(v)?
+                    .try_into()
+                    .map_err(|_| anyhow!("Fork versions must be 4 bytes"))
+            })
+            .collect::<Result<Vec<Version>>>()?;
+        let dst: &'static [u8] = match network.dst {
+            Some(dst) => Box::leak(dst.into_bytes().into_boxed_slice()),
+            None => CIPHER_SUITE.as_bytes(),
+        };
+        register_network(NetworkForkSchedule {
+            name: Box::leak(network.name.into_boxed_str()),
+            genesis_validators_root: Box::leak(gvr.to_lowercase().into_boxed_str()),
+            fork_versions: Box::leak(fork_versions.into_boxed_slice()),
+            dst,
+        })?;
+    }
+    Ok(count)
+}
+
+/// Registers the networks of the file at `SECURE_SIGNER_NETWORKS_FILE`, see `register_networks`.
+/// Returns 0 if it is unset.
+pub fn register_networks_from_env() -> Result<usize> {
+    let path = match std::env::var("SECURE_SIGNER_NETWORKS_FILE") {
+        Ok(path) if !path.is_empty() => path,
+        _ => return Ok(0),
+    };
+    let networks_json = std::fs::read_to_string(&path)
+        .map_err(|e| anyhow!("Unable to read network registry file {path}: {:?}", e))?;
+    register_networks(&networks_json)
+}
+
+// Withdrawal prefixes
+pub const BLS_WITHDRAWAL_PREFIX: u8 = 0x00;
+pub const ETH1_ADDRESS_WITHDRAWAL_PREFIX: u8 = 0x01;
+pub const COMPOUNDING_WITHDRAWAL_PREFIX: u8 = 0x02; // EIP-7251
 pub const SLOTS_PER_EPOCH: u64 = 32;
 
 // altair
@@ -73,6 +262,8 @@ pub type SYNC_COMMITTEE_SIZE = typenum::U512;
 pub type SYNC_COMMITTEE_SUBNET_COUNT = typenum::U4;
 #[allow(non_camel_case_types)]
 pub type SYNC_COMMITTEE_SIZE_BY_SYNC_COMMITTEE_SUBNET_COUNT = typenum::U128; // 512 / 4
+pub const SYNC_SUBCOMMITTEE_SIZE: u64 = 128; // 512 / 4
+pub const TARGET_AGGREGATORS_PER_SYNC_SUBCOMMITTEE: u64 = 16;
 
 // bellatrix
 #[allow(non_camel_case_types)]
@@ -92,6 +283,16 @@ pub type MAX_BLS_TO_EXECUTION_CHANGES = typenum::U16;
 #[allow(non_camel_case_types)]
 pub type MAX_WITHDRAWALS_PER_PAYLOAD = typenum::U16;
 
+// deneb
+#[allow(non_camel_case_types)]
+pub type MAX_BLOB_COMMITMENTS_PER_BLOCK = typenum::U4096;
+pub type KZGCommitment = Bytes48;
+
+// non-consensus commitments
+#[allow(non_camel_case_types)]
+pub type MAX_COMMITMENT_BYTES = typenum::U1048576;
+pub type Commitment = VariableList<u8, MAX_COMMITMENT_BYTES>;
+
 // Custom deserializers
 pub fn from_hex_to_ssz_type<'de, D, T>(deserializer: D) -> Result<T, D::Error>
 where
@@ -116,6 +317,45 @@ where
     serializer.serialize_str(&hex_string)
 }
 
+pub fn from_hex_list_to_kzg_commitments<'de, D>(
+    deserializer: D,
+) -> Result<VariableList<KZGCommitment, MAX_BLOB_COMMITMENTS_PER_BLOCK>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let hex_strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let commitments = hex_strs
+        .iter()
+        .map(|hex_str| {
+            let hex_str: &str = strip_0x_prefix!(hex_str);
+            match hex::decode(hex_str) {
+                Ok(bs) if bs.len() == 48 => Ok(KZGCommitment::from(bs)),
+                Ok(bs) => Err(de::Error::custom(format!(
+                    "KZG commitment must be 48 bytes, got {}",
+                    bs.len()
+                ))),
+                Err(e) => Err(de::Error::custom(format!("Not valid hex: {:?}", e))),
+            }
+        })
+        .collect::<Result<Vec<KZGCommitment>, D::Error>>()?;
+    VariableList::new(commitments)
+        .map_err(|e| de::Error::custom(format!("Too many KZG commitments: {:?}", e)))
+}
+
+pub fn to_hex_list_from_kzg_commitments<S>(
+    commitments: &VariableList<KZGCommitment, MAX_BLOB_COMMITMENTS_PER_BLOCK>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let hex_strs: Vec<String> = commitments
+        .iter()
+        .map(|c| "0x".to_string() + &hex::encode(c.as_ssz_bytes()))
+        .collect();
+    hex_strs.serialize(serializer)
+}
+
 pub fn from_hex_to_ssz_bits_type<'de, D, T>(deserializer: D) -> Result<T, D::Error>
 where
     D: Deserializer<'de>,
@@ -433,6 +673,67 @@ pub struct SyncAggregate {
 }
 
 #[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/phase0/beacon-chain.md#beaconblockbody
+pub struct Phase0BeaconBlockBody {
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub randao_reveal: BLSSignature,
+    pub eth1_data: Eth1Data,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub graffiti: Bytes32,
+    pub proposer_slashings: VariableList<ProposerSlashing, MAX_PROPOSER_SLASHINGS>,
+    pub attester_slashings: VariableList<AttesterSlashing, MAX_ATTESTER_SLASHINGS>,
+    pub attestations: VariableList<Attestation, MAX_ATTESTATIONS>,
+    pub deposits: VariableList<Deposit, MAX_DEPOSITS>,
+    pub voluntary_exits: VariableList<SignedVoluntaryExit, MAX_VOLUNTARY_EXITS>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/altair/beacon-chain.md#beaconblockbody
+pub struct AltairBeaconBlockBody {
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub randao_reveal: BLSSignature,
+    pub eth1_data: Eth1Data,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub graffiti: Bytes32,
+    pub proposer_slashings: VariableList<ProposerSlashing, MAX_PROPOSER_SLASHINGS>,
+    pub attester_slashings: VariableList<AttesterSlashing, MAX_ATTESTER_SLASHINGS>,
+    pub attestations: VariableList<Attestation, MAX_ATTESTATIONS>,
+    pub deposits: VariableList<Deposit, MAX_DEPOSITS>,
+    pub voluntary_exits: VariableList<SignedVoluntaryExit, MAX_VOLUNTARY_EXITS>,
+    pub sync_aggregate: SyncAggregate, // # [New in Altair]
+}
+
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/bellatrix/beacon-chain.md#beaconblockbody
+pub struct BellatrixBeaconBlockBody {
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub randao_reveal: BLSSignature,
+    pub eth1_data: Eth1Data,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub graffiti: Bytes32,
+    pub proposer_slashings: VariableList<ProposerSlashing, MAX_PROPOSER_SLASHINGS>,
+    pub attester_slashings: VariableList<AttesterSlashing, MAX_ATTESTER_SLASHINGS>,
+    pub attestations: VariableList<Attestation, MAX_ATTESTATIONS>,
+    pub deposits: VariableList<Deposit, MAX_DEPOSITS>,
+    pub voluntary_exits: VariableList<SignedVoluntaryExit, MAX_VOLUNTARY_EXITS>,
+    pub sync_aggregate: SyncAggregate,
+    pub execution_payload: BellatrixExecutionPayload, // [New in Bellatrix]
+}
+
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct BeaconBlockBody {
     #[serde(
         deserialize_with = "from_hex_to_ssz_type",
@@ -459,6 +760,35 @@ pub struct BeaconBlockBody {
 }
 
 #[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/deneb/beacon-chain.md#beaconblockbody
+pub struct DenebBeaconBlockBody {
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub randao_reveal: BLSSignature,
+    pub eth1_data: Eth1Data,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub graffiti: Bytes32,
+    pub proposer_slashings: VariableList<ProposerSlashing, MAX_PROPOSER_SLASHINGS>,
+    pub attester_slashings: VariableList<AttesterSlashing, MAX_ATTESTER_SLASHINGS>,
+    pub attestations: VariableList<Attestation, MAX_ATTESTATIONS>,
+    pub deposits: VariableList<Deposit, MAX_DEPOSITS>,
+    pub voluntary_exits: VariableList<SignedVoluntaryExit, MAX_VOLUNTARY_EXITS>,
+    pub sync_aggregate: SyncAggregate,
+    pub execution_payload: DenebExecutionPayload,
+    pub bls_to_execution_changes:
+        VariableList<SignedBLSToExecutionChange, MAX_BLS_TO_EXECUTION_CHANGES>,
+    #[serde(
+        deserialize_with = "from_hex_list_to_kzg_commitments",
+        serialize_with = "to_hex_list_from_kzg_commitments"
+    )]
+    pub blob_kzg_commitments: VariableList<KZGCommitment, MAX_BLOB_COMMITMENTS_PER_BLOCK>, // [New in Deneb]
+}
+
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct ExecutionPayload {
     // Execution block header fields
     #[serde(with = "SerHex::<StrictPfx>")]
@@ -504,6 +834,173 @@ pub struct ExecutionPayload {
     pub withdrawals: VariableList<Withdrawal, MAX_WITHDRAWALS_PER_PAYLOAD>, // [New in Capella]
 }
 
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/bellatrix/beacon-chain.md#executionpayload
+pub struct BellatrixExecutionPayload {
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub parent_hash: Root,
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub fee_recipient: ExecutionAddress,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub state_root: Root,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub receipts_root: Root,
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub logs_bloom: FixedVector<u8, BYTES_PER_LOGS_BLOOM>,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub prev_randao: Root,
+    #[serde(with = "quoted_u64")]
+    pub block_number: u64,
+    #[serde(with = "quoted_u64")]
+    pub gas_limit: u64,
+    #[serde(with = "quoted_u64")]
+    pub gas_used: u64,
+    #[serde(with = "quoted_u64")]
+    pub timestamp: u64,
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub extra_data: VariableList<u8, MAX_EXTRA_DATA_BYTES>,
+    #[serde(
+        deserialize_with = "from_u256_string",
+        serialize_with = "to_u256_string"
+    )]
+    pub base_fee_per_gas: U256,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub block_hash: Root,
+    pub transactions: VariableList<Transaction, MAX_TRANSACTIONS_PER_PAYLOAD>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/deneb/beacon-chain.md#executionpayload
+pub struct DenebExecutionPayload {
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub parent_hash: Root,
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub fee_recipient: ExecutionAddress,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub state_root: Root,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub receipts_root: Root,
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub logs_bloom: FixedVector<u8, BYTES_PER_LOGS_BLOOM>,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub prev_randao: Root,
+    #[serde(with = "quoted_u64")]
+    pub block_number: u64,
+    #[serde(with = "quoted_u64")]
+    pub gas_limit: u64,
+    #[serde(with = "quoted_u64")]
+    pub gas_used: u64,
+    #[serde(with = "quoted_u64")]
+    pub timestamp: u64,
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub extra_data: VariableList<u8, MAX_EXTRA_DATA_BYTES>,
+    #[serde(
+        deserialize_with = "from_u256_string",
+        serialize_with = "to_u256_string"
+    )]
+    pub base_fee_per_gas: U256,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub block_hash: Root,
+    pub transactions: VariableList<Transaction, MAX_TRANSACTIONS_PER_PAYLOAD>,
+    pub withdrawals: VariableList<Withdrawal, MAX_WITHDRAWALS_PER_PAYLOAD>,
+    #[serde(with = "quoted_u64")]
+    pub blob_gas_used: u64, // [New in Deneb]
+    #[serde(with = "quoted_u64")]
+    pub excess_blob_gas: u64, // [New in Deneb]
+}
+
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/capella/beacon-chain.md#executionpayloadheader
+pub struct ExecutionPayloadHeader {
+    // Execution block header fields
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub parent_hash: Root,
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub fee_recipient: ExecutionAddress,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub state_root: Root,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub receipts_root: Root,
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub logs_bloom: FixedVector<u8, BYTES_PER_LOGS_BLOOM>,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub prev_randao: Root,
+    #[serde(with = "quoted_u64")]
+    pub block_number: u64,
+    #[serde(with = "quoted_u64")]
+    pub gas_limit: u64,
+    #[serde(with = "quoted_u64")]
+    pub gas_used: u64,
+    #[serde(with = "quoted_u64")]
+    pub timestamp: u64,
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub extra_data: VariableList<u8, MAX_EXTRA_DATA_BYTES>,
+    #[serde(
+        deserialize_with = "from_u256_string",
+        serialize_with = "to_u256_string"
+    )]
+    pub base_fee_per_gas: U256,
+    // Extra payload fields
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub block_hash: Root,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub transactions_root: Root,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub withdrawals_root: Root, // [New in Capella]
+}
+
+impl From<&ExecutionPayload> for ExecutionPayloadHeader {
+    fn from(p: &ExecutionPayload) -> Self {
+        ExecutionPayloadHeader {
+            parent_hash: p.parent_hash,
+            fee_recipient: p.fee_recipient.clone(),
+            state_root: p.state_root,
+            receipts_root: p.receipts_root,
+            logs_bloom: p.logs_bloom.clone(),
+            prev_randao: p.prev_randao,
+            block_number: p.block_number,
+            gas_limit: p.gas_limit,
+            gas_used: p.gas_used,
+            timestamp: p.timestamp,
+            extra_data: p.extra_data.clone(),
+            base_fee_per_gas: p.base_fee_per_gas.clone(),
+            block_hash: p.block_hash,
+            transactions_root: tree_hash::TreeHash::tree_hash_root(&p.transactions)
+                .to_fixed_bytes(),
+            withdrawals_root: tree_hash::TreeHash::tree_hash_root(&p.withdrawals).to_fixed_bytes(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
 pub struct BLSToExecutionChange {
     #[serde(with = "quoted_u64")]
@@ -531,6 +1028,7 @@ pub struct SignedBLSToExecutionChange {
 }
 
 #[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
 /// https://github.com/ethereum/consensus-specs/blob/dev/specs/phase0/beacon-chain.md#beaconblock
 /// used by Web3Signer type = "BLOCK" for phase 0 backward compatibility.
 pub struct BeaconBlock {
@@ -545,6 +1043,259 @@ pub struct BeaconBlock {
     pub body: BeaconBlockBody,
 }
 
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/phase0/beacon-chain.md#beaconblock
+pub struct Phase0BeaconBlock {
+    #[serde(with = "quoted_u64")]
+    pub slot: Slot,
+    #[serde(with = "quoted_u64")]
+    pub proposer_index: ValidatorIndex,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub parent_root: Root,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub state_root: Root,
+    pub body: Phase0BeaconBlockBody,
+}
+
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/altair/beacon-chain.md#beaconblock
+pub struct AltairBeaconBlock {
+    #[serde(with = "quoted_u64")]
+    pub slot: Slot,
+    #[serde(with = "quoted_u64")]
+    pub proposer_index: ValidatorIndex,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub parent_root: Root,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub state_root: Root,
+    pub body: AltairBeaconBlockBody,
+}
+
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/bellatrix/beacon-chain.md#beaconblock
+pub struct BellatrixBeaconBlock {
+    #[serde(with = "quoted_u64")]
+    pub slot: Slot,
+    #[serde(with = "quoted_u64")]
+    pub proposer_index: ValidatorIndex,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub parent_root: Root,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub state_root: Root,
+    pub body: BellatrixBeaconBlockBody,
+}
+
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+#[serde(deny_unknown_fields)]
+/// https://github.com/ethereum/consensus-specs/blob/dev/specs/deneb/beacon-chain.md#beaconblock
+pub struct DenebBeaconBlock {
+    #[serde(with = "quoted_u64")]
+    pub slot: Slot,
+    #[serde(with = "quoted_u64")]
+    pub proposer_index: ValidatorIndex,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub parent_root: Root,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub state_root: Root,
+    pub body: DenebBeaconBlockBody,
+}
+
+/// The block body shapes a type = "BLOCK" request can carry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockBodyFork {
+    Phase0,
+    Altair,
+    Bellatrix,
+    Capella,
+    Deneb,
+}
+
+impl BlockBodyFork {
+    /// Selects the body shape of a block at `slot` from the fork version in effect at its epoch.
+    /// The fork is found by the version's position in the schedule of the network identified by
+    /// the genesis_validators_root. Errors for unknown networks, versions not on the schedule and
+    /// forks after Deneb, as their blocks would be hashed under the wrong container.
+    pub fn at_slot(fork_info: &ForkInfo, slot: Slot) -> Result<Self> {
+        let version = if slot / SLOTS_PER_EPOCH < fork_info.fork.epoch {
+            fork_info.fork.previous_version
+        } else {
+            fork_info.fork.current_version
+        };
+        let Some(network) = fork_schedule(&fork_info.genesis_validators_root) else {
+            bail!(
+                "Unknown network with genesis_validators_root 0x{}, cannot select the block body",
+                hex::encode(fork_info.genesis_validators_root)
+            )
+        };
+        Ok(
+            match network.fork_versions.iter().position(|v| *v == version) {
+                Some(0) => BlockBodyFork::Phase0,
+                Some(1) => BlockBodyFork::Altair,
+                Some(2) => BlockBodyFork::Bellatrix,
+                Some(3) => BlockBodyFork::Capella,
+                Some(4) => BlockBodyFork::Deneb,
+                Some(_) => bail!(
+                    "Blocks of fork version 0x{} on {} are not supported",
+                    hex::encode(version),
+                    network.name
+                ),
+                None => bail!(
+                    "Fork version 0x{} is not scheduled on {}",
+                    hex::encode(version),
+                    network.name
+                ),
+            },
+        )
+    }
+}
+
+/// A type = "BLOCK" block with the body of the fork it was proposed in
+#[derive(Debug, Serialize, Clone)]
+#[serde(untagged)]
+pub enum ForkBeaconBlock {
+    Phase0(Phase0BeaconBlock),
+    Altair(AltairBeaconBlock),
+    Bellatrix(BellatrixBeaconBlock),
+    Capella(BeaconBlock),
+    Deneb(DenebBeaconBlock),
+}
+
+impl ForkBeaconBlock {
+    /// Parses `block` with the body shape of `fork`. Fields of other forks are refused.
+    pub fn from_json(block: serde_json::Value, fork: BlockBodyFork) -> Result<Self> {
+        Ok(match fork {
+            BlockBodyFork::Phase0 => ForkBeaconBlock::Phase0(serde_json::from_value(block)?),
+            BlockBodyFork::Altair => ForkBeaconBlock::Altair(serde_json::from_value(block)?),
+            BlockBodyFork::Bellatrix => ForkBeaconBlock::Bellatrix(serde_json::from_value(block)?),
+            BlockBodyFork::Capella => ForkBeaconBlock::Capella(serde_json::from_value(block)?),
+            BlockBodyFork::Deneb => ForkBeaconBlock::Deneb(serde_json::from_value(block)?),
+        })
+    }
+
+    pub fn fork(&self) -> BlockBodyFork {
+        match self {
+            ForkBeaconBlock::Phase0(_) => BlockBodyFork::Phase0,
+            ForkBeaconBlock::Altair(_) => BlockBodyFork::Altair,
+            ForkBeaconBlock::Bellatrix(_) => BlockBodyFork::Bellatrix,
+            ForkBeaconBlock::Capella(_) => BlockBodyFork::Capella,
+            ForkBeaconBlock::Deneb(_) => BlockBodyFork::Deneb,
+        }
+    }
+
+    pub fn slot(&self) -> Slot {
+        match self {
+            ForkBeaconBlock::Phase0(b) => b.slot,
+            ForkBeaconBlock::Altair(b) => b.slot,
+            ForkBeaconBlock::Bellatrix(b) => b.slot,
+            ForkBeaconBlock::Capella(b) => b.slot,
+            ForkBeaconBlock::Deneb(b) => b.slot,
+        }
+    }
+
+    /// The hash_tree_root of the body under its fork's container
+    pub fn body_root(&self) -> Root {
+        use tree_hash::TreeHash;
+        match self {
+            ForkBeaconBlock::Phase0(b) => b.body.tree_hash_root().to_fixed_bytes(),
+            ForkBeaconBlock::Altair(b) => b.body.tree_hash_root().to_fixed_bytes(),
+            ForkBeaconBlock::Bellatrix(b) => b.body.tree_hash_root().to_fixed_bytes(),
+            ForkBeaconBlock::Capella(b) => b.body.tree_hash_root().to_fixed_bytes(),
+            ForkBeaconBlock::Deneb(b) => b.body.tree_hash_root().to_fixed_bytes(),
+        }
+    }
+
+    /// The header of this block, which has the same hash_tree_root as the block itself
+    pub fn header(&self) -> BeaconBlockHeader {
+        let (slot, proposer_index, parent_root, state_root) = match self {
+            ForkBeaconBlock::Phase0(b) => (b.slot, b.proposer_index, b.parent_root, b.state_root),
+            ForkBeaconBlock::Altair(b) => (b.slot, b.proposer_index, b.parent_root, b.state_root),
+            ForkBeaconBlock::Bellatrix(b) => {
+                (b.slot, b.proposer_index, b.parent_root, b.state_root)
+            }
+            ForkBeaconBlock::Capella(b) => (b.slot, b.proposer_index, b.parent_root, b.state_root),
+            ForkBeaconBlock::Deneb(b) => (b.slot, b.proposer_index, b.parent_root, b.state_root),
+        };
+        BeaconBlockHeader {
+            slot,
+            proposer_index,
+            parent_root,
+            state_root,
+            body_root: self.body_root(),
+        }
+    }
+}
+
+impl From<BeaconBlock> for ForkBeaconBlock {
+    fn from(block: BeaconBlock) -> Self {
+        ForkBeaconBlock::Capella(block)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+/// https://github.com/ethereum/builder-specs/blob/main/specs/capella/builder.md#blindedbeaconblockbody
+pub struct BlindedBeaconBlockBody {
+    #[serde(
+        deserialize_with = "from_hex_to_ssz_type",
+        serialize_with = "to_hex_from_ssz_type"
+    )]
+    pub randao_reveal: BLSSignature,
+    pub eth1_data: Eth1Data,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub graffiti: Bytes32,
+    pub proposer_slashings: VariableList<ProposerSlashing, MAX_PROPOSER_SLASHINGS>,
+    pub attester_slashings: VariableList<AttesterSlashing, MAX_ATTESTER_SLASHINGS>,
+    pub attestations: VariableList<Attestation, MAX_ATTESTATIONS>,
+    pub deposits: VariableList<Deposit, MAX_DEPOSITS>,
+    pub voluntary_exits: VariableList<SignedVoluntaryExit, MAX_VOLUNTARY_EXITS>,
+    pub sync_aggregate: SyncAggregate,
+    pub execution_payload_header: ExecutionPayloadHeader, // Replaces the full execution_payload
+    pub bls_to_execution_changes:
+        VariableList<SignedBLSToExecutionChange, MAX_BLS_TO_EXECUTION_CHANGES>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
+/// https://github.com/ethereum/builder-specs/blob/main/specs/bellatrix/builder.md#blindedbeaconblock
+/// signed by proposers in the builder (MEV-Boost) flow. Its hash-tree-root equals that of the
+/// `BeaconBlock` carrying the full payload.
+pub struct BlindedBeaconBlock {
+    #[serde(with = "quoted_u64")]
+    pub slot: Slot,
+    #[serde(with = "quoted_u64")]
+    pub proposer_index: ValidatorIndex,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub parent_root: Root,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub state_root: Root,
+    pub body: BlindedBeaconBlockBody,
+}
+
+impl From<&BeaconBlock> for BlindedBeaconBlock {
+    fn from(b: &BeaconBlock) -> Self {
+        BlindedBeaconBlock {
+            slot: b.slot,
+            proposer_index: b.proposer_index,
+            parent_root: b.parent_root,
+            state_root: b.state_root,
+            body: BlindedBeaconBlockBody {
+                randao_reveal: b.body.randao_reveal.clone(),
+                eth1_data: b.body.eth1_data.clone(),
+                graffiti: b.body.graffiti,
+                proposer_slashings: b.body.proposer_slashings.clone(),
+                attester_slashings: b.body.attester_slashings.clone(),
+                attestations: b.body.attestations.clone(),
+                deposits: b.body.deposits.clone(),
+                voluntary_exits: b.body.voluntary_exits.clone(),
+                sync_aggregate: b.body.sync_aggregate.clone(),
+                execution_payload_header: ExecutionPayloadHeader::from(&b.body.execution_payload),
+                bls_to_execution_changes: b.body.bls_to_execution_changes.clone(),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Encode, Decode, TreeHash, Clone)]
 /// https://github.com/ethereum/consensus-specs/blob/dev/specs/capella/beacon-chain.md#withdrawal
 pub struct Withdrawal {
@@ -656,6 +1407,7 @@ pub struct AggregationSlot {
 }
 
 #[derive(Deserialize, Serialize, Debug)]
+#[serde(try_from = "RawBlockRequest")]
 #[allow(non_snake_case)]
 pub struct BlockRequest {
     pub fork_info: ForkInfo,
@@ -664,7 +1416,38 @@ pub struct BlockRequest {
     #[serde(serialize_with = "se_signing_root")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub signingRoot: Option<Root>,
-    pub block: BeaconBlock,
+    pub block: ForkBeaconBlock,
+}
+
+// A BlockRequest whose block is parsed once the fork_info has selected its body shape
+#[derive(Deserialize)]
+#[allow(non_snake_case)]
+struct RawBlockRequest {
+    fork_info: ForkInfo,
+    #[serde(default)]
+    #[serde(deserialize_with = "de_signing_root")]
+    signingRoot: Option<Root>,
+    block: serde_json::Value,
+}
+
+impl TryFrom<RawBlockRequest> for BlockRequest {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawBlockRequest) -> Result<Self> {
+        let slot: Slot = match raw.block.get("slot") {
+            Some(serde_json::Value::String(s)) => s.parse()?,
+            Some(serde_json::Value::Number(n)) => n
+                .as_u64()
+                .ok_or_else(|| anyhow!("Invalid block slot {n}"))?,
+            _ => bail!("Block is missing its slot"),
+        };
+        let fork = BlockBodyFork::at_slot(&raw.fork_info, slot)?;
+        Ok(BlockRequest {
+            block: ForkBeaconBlock::from_json(raw.block, fork)?,
+            fork_info: raw.fork_info,
+            signingRoot: raw.signingRoot,
+        })
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -746,7 +1529,7 @@ pub struct DepositRequest {
     pub genesis_fork_version: Version,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
 /// Custom response to work with https://launchpad.ethereum.org/en/upload-deposit-data
 pub struct DepositResponse {
     pub pubkey: String,
@@ -755,6 +1538,15 @@ pub struct DepositResponse {
     pub signature: String, // Signing over DepositMessage
     pub deposit_message_root: String,
     pub deposit_data_root: String,
+    /// Hex encoded genesis fork version the deposit was signed under
+    #[serde(default)]
+    pub fork_version: String,
+    /// See `network_name`
+    #[serde(default)]
+    pub network_name: String,
+    /// The staking-deposit-cli release whose deposit_data JSON shape this follows
+    #[serde(default)]
+    pub deposit_cli_version: String,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
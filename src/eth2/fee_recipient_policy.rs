@@ -0,0 +1,132 @@
+use super::eth_types::ExecutionAddress;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Fee recipients validators may register with builders, so a compromised client cannot redirect
+/// MEV rewards. A validator listed in `validators` may only use its own allowlist, others fall
+/// back to `default`. Without an applicable allowlist any fee recipient is accepted.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeeRecipientPolicy {
+    /// Hex encoded addresses allowed for every validator without its own allowlist
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Vec<String>>,
+    /// Hex encoded addresses allowed per hex encoded validator BLS public key
+    #[serde(default)]
+    pub validators: HashMap<String, Vec<String>>,
+}
+
+// Lowercase hex without a 0x prefix, so addresses and keys compare regardless of formatting
+fn normalize(hex: &str) -> String {
+    let hex: &str = crate::strip_0x_prefix!(hex);
+    hex.to_lowercase()
+}
+
+fn normalize_addresses(addresses: &[String]) -> Result<HashSet<String>> {
+    addresses
+        .iter()
+        .map(|a| {
+            let addr = normalize(a);
+            match hex::decode(&addr) {
+                Ok(bytes) if bytes.len() == 20 => Ok(addr),
+                _ => bail!("Invalid fee recipient {a}, expected a 20-byte hex address"),
+            }
+        })
+        .collect()
+}
+
+impl FeeRecipientPolicy {
+    /// Parses a policy such as `{"default": ["0x..."], "validators": {"0x<pubkey>": ["0x..."]}}`,
+    /// rejecting malformed addresses
+    pub fn from_json(json: &str) -> Result<Self> {
+        let policy: FeeRecipientPolicy =
+            serde_json::from_str(json).with_context(|| "Malformed fee recipient policy")?;
+        if let Some(default) = &policy.default {
+            normalize_addresses(default)?;
+        }
+        for addresses in policy.validators.values() {
+            normalize_addresses(addresses)?;
+        }
+        let validators = policy
+            .validators
+            .into_iter()
+            .map(|(pk, addresses)| (normalize(&pk), addresses))
+            .collect();
+        Ok(FeeRecipientPolicy {
+            validators,
+            ..policy
+        })
+    }
+
+    /// Reads the policy file at `SECURE_SIGNER_FEE_RECIPIENT_POLICY`, or allows every fee
+    /// recipient if it is not set
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("SECURE_SIGNER_FEE_RECIPIENT_POLICY") {
+            Ok(path) if !path.is_empty() => {
+                let json = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read fee recipient policy {path}"))?;
+                Self::from_json(&json)
+            }
+            _ => Ok(FeeRecipientPolicy::default()),
+        }
+    }
+
+    /// Errors unless `bls_pk_hex` may register with `fee_recipient`
+    pub fn check(&self, bls_pk_hex: &str, fee_recipient: &ExecutionAddress) -> Result<()> {
+        let allowlist = match self.validators.get(&normalize(bls_pk_hex)) {
+            Some(allowlist) => allowlist,
+            None => match &self.default {
+                Some(allowlist) => allowlist,
+                None => return Ok(()),
+            },
+        };
+        let fee_recipient = hex::encode(&fee_recipient[..]);
+        if !normalize_addresses(allowlist)?.contains(&fee_recipient) {
+            bail!("Fee recipient 0x{fee_recipient} is not allowed for validator {bls_pk_hex}")
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(byte: u8) -> ExecutionAddress {
+        ExecutionAddress::from(vec![byte; 20])
+    }
+
+    #[test]
+    fn test_fee_recipient_allowlists() {
+        let pk = "aa".repeat(48);
+        let other_pk = "bb".repeat(48);
+        let policy = FeeRecipientPolicy::from_json(&format!(
+            r#"{{
+                "default": ["0x{}"],
+                "validators": {{ "0x{}": ["0x{}", "0x{}"] }}
+            }}"#,
+            "11".repeat(20),
+            pk.to_uppercase(),
+            "22".repeat(20),
+            "3C".repeat(20),
+        ))
+        .unwrap();
+
+        // A validator's own allowlist replaces the default one
+        assert!(policy.check(&pk, &address(0x22)).is_ok());
+        assert!(policy.check(&format!("0x{pk}"), &address(0x3c)).is_ok());
+        assert!(policy.check(&pk, &address(0x11)).is_err());
+
+        assert!(policy.check(&other_pk, &address(0x11)).is_ok());
+        let err = policy.check(&other_pk, &address(0x22)).unwrap_err();
+        assert!(err.to_string().contains("is not allowed"));
+
+        // Without a policy anything is allowed
+        assert!(FeeRecipientPolicy::default()
+            .check(&pk, &address(0x99))
+            .is_ok());
+
+        assert!(FeeRecipientPolicy::from_json(r#"{"default": ["0x1234"]}"#).is_err());
+    }
+}
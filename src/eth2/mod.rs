@@ -1,3 +1,7 @@
+pub mod beacon_node;
+pub mod deposit;
 pub mod eth_signing;
 pub mod eth_types;
+pub mod fee_recipient_policy;
 pub mod slash_protection;
+pub mod test_vectors;
@@ -4,9 +4,12 @@ use super::eth_types::{
     de_signing_root, from_hex_to_ssz_type, se_signing_root, to_hex_from_ssz_type, BLSPubkey, Epoch,
     Root, Slot,
 };
-use crate::constants::SLASHING_PROTECTION_DIR;
+use crate::constants::{
+    SLASHING_GENESIS_VALIDATORS_ROOT_PATH, SLASHING_INTERCHANGE_FORMAT_VERSION,
+    SLASHING_PROTECTION_DIR,
+};
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use hex;
 use log::{debug, error};
 use serde::{Deserialize, Serialize};
@@ -14,8 +17,14 @@ use serde_hex::{SerHex, StrictPfx};
 use serde_utils::quoted_u64;
 use ssz::Encode;
 use ssz_types::FixedVector;
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock, RwLockReadGuard, TryLockError};
+
+/// Extension of a DB write that has not yet been renamed over the committed file
+const PENDING_WRITE_EXTENSION: &str = "pending";
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SlashingProtectionMetaData {
@@ -35,6 +44,25 @@ pub struct SlashingProtectionData {
     pub signed_attestations: Vec<SignedAttestationEpochs>,
 }
 
+/// Read-only view of a validator's slashing protection state. Fields are None until a block or
+/// attestation has been signed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SlashingWatermarks {
+    pub highest_signed_slot: Option<Slot>,
+    pub highest_source_epoch: Option<Epoch>,
+    pub highest_target_epoch: Option<Epoch>,
+    /// The oldest history still retained, only differs from the highs in a growable DB
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low_watermark: Option<LowWatermark>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct LowWatermark {
+    pub lowest_signed_slot: Option<Slot>,
+    pub lowest_source_epoch: Option<Epoch>,
+    pub lowest_target_epoch: Option<Epoch>,
+}
+
 impl SlashingProtectionData {
     pub fn new(pubkey: BLSPubkey) -> Self {
         SlashingProtectionData {
@@ -127,15 +155,156 @@ impl SlashingProtectionData {
         Ok(())
     }
 
+    /// Checks an attestation against every retained record under the consensus double and
+    /// surround vote rules, then against the high-water marks, which always survive pruning
+    pub fn check_attestation_epochs(&self, src: Epoch, tgt: Epoch) -> Result<()> {
+        if src > tgt {
+            bail!("Attestation source epoch {src} is after its target epoch {tgt}")
+        }
+        for a in self.signed_attestations.iter() {
+            if a.target_epoch == tgt {
+                bail!("Double vote, target epoch {tgt} was already signed")
+            }
+            if src < a.source_epoch && a.target_epoch < tgt {
+                bail!(
+                    "Surround vote, ({src}, {tgt}) surrounds the signed ({}, {})",
+                    a.source_epoch,
+                    a.target_epoch
+                )
+            }
+            if a.source_epoch < src && tgt < a.target_epoch {
+                bail!(
+                    "Surround vote, ({src}, {tgt}) is surrounded by the signed ({}, {})",
+                    a.source_epoch,
+                    a.target_epoch
+                )
+            }
+        }
+        if self.is_slashable_attestation_epochs(src, tgt) {
+            let (last_src, last_tgt) = self.get_latest_signed_attestation_epochs();
+            bail!(
+                "Attestation ({src}, {tgt}) is below the signed high-water marks ({last_src}, {last_tgt})"
+            )
+        }
+        Ok(())
+    }
+
+    /// Returns true if an attestation with epochs `(src, tgt)` was recorded with exactly
+    /// `signing_root`. EIP-3076 allows re-signing such a repeat, as it can never be slashable.
+    pub fn is_repeat_attestation(&self, src: Epoch, tgt: Epoch, signing_root: &Root) -> bool {
+        self.signed_attestations.iter().any(|a| {
+            a.source_epoch == src
+                && a.target_epoch == tgt
+                && a.signing_root.as_ref() == Some(signing_root)
+        })
+    }
+
+    /// Drops attestations targeting an epoch below `ws_epoch`, keeping a record at the highest
+    /// source and target epochs so the DB stays exactly as protective
+    pub fn prune_attestations(&mut self, ws_epoch: Epoch) {
+        if self.signed_attestations.is_empty() {
+            return;
+        }
+        let (src, tgt) = self.get_latest_signed_attestation_epochs();
+        self.signed_attestations
+            .retain(|a| a.target_epoch >= ws_epoch);
+        if self.get_latest_signed_attestation_epochs() != (src, tgt) {
+            self.signed_attestations.push(SignedAttestationEpochs {
+                source_epoch: src,
+                target_epoch: tgt,
+                signing_root: None,
+            });
+        }
+    }
+
+    /// Summarizes the highest (and lowest retained) signed slot and attestation epochs
+    pub fn watermarks(&self) -> SlashingWatermarks {
+        let slots = || self.signed_blocks.iter().map(|b| b.slot);
+        let sources = || self.signed_attestations.iter().map(|a| a.source_epoch);
+        let targets = || self.signed_attestations.iter().map(|a| a.target_epoch);
+        let low_watermark = (self.signed_blocks.len() > 1 || self.signed_attestations.len() > 1)
+            .then(|| LowWatermark {
+                lowest_signed_slot: slots().min(),
+                lowest_source_epoch: sources().min(),
+                lowest_target_epoch: targets().min(),
+            });
+        SlashingWatermarks {
+            highest_signed_slot: slots().max(),
+            highest_source_epoch: sources().max(),
+            highest_target_epoch: targets().max(),
+            low_watermark,
+        }
+    }
+
+    /// Merges `other` into this DB, keeping the higher of each high-water mark so the result is
+    /// at least as protective as either DB alone
+    pub fn merge_max(&mut self, other: &SlashingProtectionData) {
+        if let Some(b) = other.signed_blocks.iter().max_by_key(|b| b.slot) {
+            if self.signed_blocks.is_empty() || b.slot > self.get_latest_signed_block_slot() {
+                self.signed_blocks.push(b.clone());
+            }
+        }
+        if !other.signed_attestations.is_empty() {
+            let (other_src, other_tgt) = other.get_latest_signed_attestation_epochs();
+            let (src, tgt) = self.get_latest_signed_attestation_epochs();
+            if self.signed_attestations.is_empty() || other_src > src || other_tgt > tgt {
+                self.signed_attestations.push(SignedAttestationEpochs {
+                    source_epoch: src.max(other_src),
+                    target_epoch: tgt.max(other_tgt),
+                    signing_root: None,
+                });
+            }
+        }
+    }
+
+    /// Drops all history below the high-water marks, keeping one block at the highest signed slot
+    /// and one attestation at the highest source and target epochs. Slashing checks only compare
+    /// against these maxima, so the compacted DB is exactly as protective.
+    pub fn compact(&mut self) {
+        if let Some(b) = self.signed_blocks.iter().max_by_key(|b| b.slot).cloned() {
+            self.signed_blocks = vec![b];
+        }
+        if !self.signed_attestations.is_empty() {
+            let (source_epoch, target_epoch) = self.get_latest_signed_attestation_epochs();
+            let signing_root = self
+                .signed_attestations
+                .iter()
+                .find(|a| a.source_epoch == source_epoch && a.target_epoch == target_epoch)
+                .and_then(|a| a.signing_root);
+            self.signed_attestations = vec![SignedAttestationEpochs {
+                source_epoch,
+                target_epoch,
+                signing_root,
+            }];
+        }
+    }
+
     pub fn write(&self) -> Result<()> {
         let fname = hex::encode(self.pubkey.as_ssz_bytes());
+        #[cfg(test)]
+        if take_injected_write_failure(&fname) {
+            bail!("failed to write protection data: injected write failure")
+        }
         let file_path: PathBuf = [SLASHING_PROTECTION_DIR, &fname].iter().collect();
+        self.write_to(&file_path)
+    }
+
+    fn write_to(&self, file_path: &Path) -> Result<()> {
         if let Some(p) = file_path.parent() {
             fs::create_dir_all(p).with_context(|| "Failed to create slashing dir")?
         };
         let json = serde_json::to_string(&self)?;
         debug!("Writing Slash Protection DB:\n{json}");
-        fs::write(&file_path, json).with_context(|| "failed to write protection data")
+
+        // Write to a pending file then atomically rename it over the committed DB, so a crash
+        // mid-write leaves a stray pending file rather than a torn DB
+        let pending_path = file_path.with_extension(PENDING_WRITE_EXTENSION);
+        let mut f =
+            fs::File::create(&pending_path).with_context(|| "failed to write protection data")?;
+        f.write_all(json.as_bytes())
+            .and_then(|_| f.sync_all())
+            .with_context(|| "failed to write protection data")?;
+        fs::rename(&pending_path, file_path).with_context(|| "failed to commit protection data")
     }
 
     pub fn read(pk_hex: &str) -> Result<Self> {
@@ -149,6 +318,209 @@ impl SlashingProtectionData {
     }
 }
 
+/// Test-only: pubkeys whose next DB write fails, see `fail_next_write`
+#[cfg(test)]
+static INJECTED_WRITE_FAILURES: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+/// Test-only hook making the next `write` of `pk_hex`'s DB fail before touching disk, as if the
+/// disk were full. Keyed by pubkey so concurrently running tests never consume each other's
+/// failure. Not compiled outside of `cargo test`.
+#[cfg(test)]
+pub(crate) fn fail_next_write(pk_hex: &str) {
+    let pk_hex: String = strip_0x_prefix!(pk_hex);
+    INJECTED_WRITE_FAILURES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(pk_hex);
+}
+
+#[cfg(test)]
+fn take_injected_write_failure(pk_hex: &str) -> bool {
+    let mut failures = INJECTED_WRITE_FAILURES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    match failures.iter().position(|p| p == pk_hex) {
+        Some(i) => {
+            failures.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Taken exclusively while the slashing DB is compacted. Signing paths take it shared, and are
+/// rejected rather than blocked while a compaction runs.
+static SLASHING_DB_LOCK: RwLock<()> = RwLock::new(());
+
+/// Returns the shared guard to hold across a check-sign-record, or None while compacting
+pub fn try_lock_for_signing() -> Option<RwLockReadGuard<'static, ()>> {
+    match SLASHING_DB_LOCK.try_read() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(e)) => Some(e.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Returns the lock serializing check-sign-record for the validator `pk_hex`. Without it two
+/// concurrent requests could both pass the slashing check against the same stale DB.
+pub fn validator_signing_lock(pk_hex: &str) -> Arc<Mutex<()>> {
+    static LOCKS: OnceLock<Mutex<HashMap<String, Arc<Mutex<()>>>>> = OnceLock::new();
+    let pk_hex: String = strip_0x_prefix!(pk_hex);
+    let mut locks = LOCKS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    locks.entry(pk_hex.to_lowercase()).or_default().clone()
+}
+
+static WEAK_SUBJECTIVITY_EPOCH: OnceLock<Epoch> = OnceLock::new();
+
+/// Reads the epoch below which attestation history is pruned from
+/// `SECURE_SIGNER_WEAK_SUBJECTIVITY_EPOCH`, failing on a malformed value. Unset, all history is
+/// retained.
+pub fn init_weak_subjectivity_epoch_from_env() -> Result<Epoch> {
+    let epoch = match std::env::var("SECURE_SIGNER_WEAK_SUBJECTIVITY_EPOCH") {
+        Ok(epoch) if !epoch.is_empty() => epoch
+            .parse()
+            .map_err(|e| anyhow!("Bad SECURE_SIGNER_WEAK_SUBJECTIVITY_EPOCH {epoch}: {:?}", e))?,
+        _ => 0,
+    };
+    Ok(*WEAK_SUBJECTIVITY_EPOCH.get_or_init(|| epoch))
+}
+
+/// Epoch below which attestation history is pruned, 0 until
+/// `init_weak_subjectivity_epoch_from_env` has run
+pub fn weak_subjectivity_epoch() -> Epoch {
+    WEAK_SUBJECTIVITY_EPOCH.get().copied().unwrap_or(0)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompactionSummary {
+    pub databases: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Compacts every slashing protection DB (see `SlashingProtectionData::compact`) and removes
+/// stray pending writes, holding the slashing DB lock exclusively until done
+pub fn compact_slashing_db() -> Result<CompactionSummary> {
+    let _guard = match SLASHING_DB_LOCK.write() {
+        Ok(guard) => guard,
+        Err(e) => e.into_inner(),
+    };
+    compact_slashing_dir(Path::new(SLASHING_PROTECTION_DIR))
+}
+
+fn compact_slashing_dir(dir: &Path) -> Result<CompactionSummary> {
+    let mut summary = CompactionSummary::default();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries.collect::<std::io::Result<Vec<_>>>()?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(summary),
+        Err(e) => return Err(e).with_context(|| "Failed to read slashing dir"),
+    };
+
+    for entry in entries {
+        let path = entry.path();
+        summary.bytes_before += fs::metadata(&path)?.len();
+        // No write is in flight while the lock is held, so a pending file was never committed
+        if path
+            .extension()
+            .map_or(false, |e| e == PENDING_WRITE_EXTENSION)
+        {
+            fs::remove_file(&path)?;
+            continue;
+        }
+
+        let mut db: SlashingProtectionData = serde_json::from_slice(&fs::read(&path)?)
+            .with_context(|| format!("Corrupt slashing protection DB: {}", path.display()))?;
+        let before = db.watermarks();
+        db.compact();
+        let after = db.watermarks();
+        if (
+            before.highest_signed_slot,
+            before.highest_source_epoch,
+            before.highest_target_epoch,
+        ) != (
+            after.highest_signed_slot,
+            after.highest_source_epoch,
+            after.highest_target_epoch,
+        ) {
+            bail!(
+                "Compaction changed the high-water marks of {}",
+                path.display()
+            )
+        }
+        db.write_to(&path)?;
+        summary.bytes_after += fs::metadata(&path)?.len();
+        summary.databases += 1;
+    }
+    Ok(summary)
+}
+
+/// Startup integrity check of the slashing protection DB, to run before serving any signing
+/// requests. Pending writes left by a crash are rolled back: their signature was never released,
+/// so the last committed marks stay authoritative. Errors if a committed DB fails to parse.
+/// Returns the number of rolled back writes.
+pub fn recover_slashing_db() -> Result<usize> {
+    recover_slashing_dir(Path::new(SLASHING_PROTECTION_DIR))
+}
+
+fn recover_slashing_dir(dir: &Path) -> Result<usize> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries.collect::<std::io::Result<Vec<_>>>()?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).with_context(|| "Failed to read slashing dir"),
+    };
+
+    let mut rolled_back = 0;
+    for entry in entries {
+        let path = entry.path();
+        if path
+            .extension()
+            .map_or(false, |e| e == PENDING_WRITE_EXTENSION)
+        {
+            error!(
+                "Rolling back incomplete slashing DB write: {}",
+                path.display()
+            );
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to roll back {}", path.display()))?;
+            rolled_back += 1;
+            continue;
+        }
+        let json = fs::read(&path)?;
+        serde_json::from_slice::<SlashingProtectionData>(&json)
+            .with_context(|| format!("Corrupt slashing protection DB: {}", path.display()))?;
+    }
+    Ok(rolled_back)
+}
+
+/// Fsyncs every committed slashing protection DB and the directory holding them, so renames
+/// done by `SlashingProtectionData::write` survive a power loss
+pub fn sync_slashing_db() -> Result<()> {
+    let dir = Path::new(SLASHING_PROTECTION_DIR);
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| "Failed to read slashing dir"),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        if path
+            .extension()
+            .map_or(false, |e| e == PENDING_WRITE_EXTENSION)
+        {
+            continue;
+        }
+        fs::File::open(&path)
+            .and_then(|f| f.sync_all())
+            .with_context(|| format!("Failed to sync {}", path.display()))?;
+    }
+    fs::File::open(dir)
+        .and_then(|f| f.sync_all())
+        .with_context(|| "Failed to sync slashing dir")
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SignedAttestationEpochs {
     #[serde(with = "quoted_u64")]
@@ -181,7 +553,9 @@ pub struct SignedBlockSlot {
 /// Specs: https://eips.ethlibrary.io/eip-3076.html
 pub struct SlashingProtectionDB {
     pub metadata: SlashingProtectionMetaData,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub data: Vec<SlashingProtectionData>,
 }
@@ -189,7 +563,7 @@ pub struct SlashingProtectionDB {
 impl SlashingProtectionDB {
     pub fn new() -> Self {
         let metadata = SlashingProtectionMetaData {
-            interchange_format_version: "5".into(),
+            interchange_format_version: SLASHING_INTERCHANGE_FORMAT_VERSION.into(),
             genesis_validators_root: Root::default(),
         };
 
@@ -213,6 +587,386 @@ impl SlashingProtectionDB {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterchangeValidatorSummary {
+    pub pubkey: String,
+    pub signed_blocks: usize,
+    pub signed_attestations: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterchangeSummary {
+    pub interchange_format_version: String,
+    #[serde(with = "SerHex::<StrictPfx>")]
+    pub genesis_validators_root: Root,
+    pub validators: Vec<InterchangeValidatorSummary>,
+}
+
+/// Parses an EIP-3076 interchange file without importing it. Errors if the format version is
+/// unsupported or the genesis_validators_root does not match the expected one, otherwise returns
+/// the number of signed blocks and attestations per validator.
+pub fn validate_slashing_interchange(
+    json: &str,
+    genesis_validators_root: Root,
+) -> Result<InterchangeSummary> {
+    let db = SlashingProtectionDB::from_str(json).with_context(|| "Malformed interchange file")?;
+
+    if db.metadata.interchange_format_version != SLASHING_INTERCHANGE_FORMAT_VERSION {
+        bail!(
+            "Unsupported interchange_format_version: {}",
+            db.metadata.interchange_format_version
+        )
+    }
+
+    if db.metadata.genesis_validators_root != genesis_validators_root {
+        bail!(
+            "Mismatched genesis_validators_root: got 0x{}, expected 0x{}",
+            hex::encode(db.metadata.genesis_validators_root),
+            hex::encode(genesis_validators_root)
+        )
+    }
+
+    let validators = db
+        .data
+        .iter()
+        .map(|d| InterchangeValidatorSummary {
+            pubkey: format!("0x{}", hex::encode(d.pubkey.as_ssz_bytes())),
+            signed_blocks: d.signed_blocks.len(),
+            signed_attestations: d.signed_attestations.len(),
+        })
+        .collect();
+
+    Ok(InterchangeSummary {
+        interchange_format_version: db.metadata.interchange_format_version,
+        genesis_validators_root: db.metadata.genesis_validators_root,
+        validators,
+    })
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterchangeImportSummary {
+    /// Records above their validator's high-water marks, which the import raised
+    pub applied: usize,
+    /// Records at or below their validator's high-water marks, already covered by its DB
+    pub skipped: usize,
+}
+
+/// Imports an EIP-3076 interchange file checked by `validate_slashing_interchange`, merging each
+/// validator's records into its DB with `merge_max`. Records at or below the DB's high-water
+/// marks are skipped and a DB without applied records is left untouched, so re-importing the same
+/// file is cheap and applies nothing.
+pub fn import_slashing_interchange(
+    json: &str,
+    genesis_validators_root: Root,
+) -> Result<InterchangeImportSummary> {
+    validate_slashing_interchange(json, genesis_validators_root)?;
+    let db = SlashingProtectionDB::from_str(json)?;
+    let Some(_db_guard) = try_lock_for_signing() else {
+        bail!("Slashing protection DB is being compacted, retry shortly")
+    };
+
+    let mut summary = InterchangeImportSummary::default();
+    for data in db.data.iter() {
+        let pk_hex = hex::encode(data.pubkey.as_ssz_bytes());
+        // Held across the read-merge-write so no signature is recorded in between
+        let validator_lock = validator_signing_lock(&pk_hex);
+        let _validator_guard = validator_lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let file_path: PathBuf = [SLASHING_PROTECTION_DIR, &pk_hex].iter().collect();
+        let mut existing = if file_path.exists() {
+            SlashingProtectionData::read(&pk_hex)?
+        } else {
+            SlashingProtectionData::new(data.pubkey.clone())
+        };
+        let marks = existing.watermarks();
+        let new_blocks = data
+            .signed_blocks
+            .iter()
+            .filter(|b| !matches!(marks.highest_signed_slot, Some(slot) if b.slot <= slot))
+            .count();
+        let new_attestations = data
+            .signed_attestations
+            .iter()
+            .filter(
+                |a| match (marks.highest_source_epoch, marks.highest_target_epoch) {
+                    (Some(src), Some(tgt)) => a.source_epoch > src || a.target_epoch > tgt,
+                    _ => true,
+                },
+            )
+            .count();
+        let applied = new_blocks + new_attestations;
+        summary.applied += applied;
+        summary.skipped += data.signed_blocks.len() + data.signed_attestations.len() - applied;
+
+        if applied > 0 {
+            existing.merge_max(data);
+            existing.write()?;
+        }
+    }
+    Ok(summary)
+}
+
+/// Genesis validators root of the network the slashing DB protects, None until initialized
+pub fn slashing_db_genesis_validators_root() -> Result<Option<Root>> {
+    read_genesis_validators_root(Path::new(SLASHING_GENESIS_VALIDATORS_ROOT_PATH))
+}
+
+/// Initializes the slashing DB for the network identified by `genesis_validators_root`. Errors if
+/// it was already initialized for another network.
+pub fn init_slashing_db_genesis_validators_root(genesis_validators_root: Root) -> Result<()> {
+    init_genesis_validators_root(
+        Path::new(SLASHING_GENESIS_VALIDATORS_ROOT_PATH),
+        genesis_validators_root,
+    )
+}
+
+/// The genesis_validators_root to export the slashing DB under: the one it was initialized with,
+/// which `given` must match if set, or else `given`, initializing the DB with it. Errors if the DB
+/// is uninitialized and no root is given.
+pub fn resolve_genesis_validators_root(given: Option<Root>) -> Result<Root> {
+    resolve_genesis_validators_root_at(Path::new(SLASHING_GENESIS_VALIDATORS_ROOT_PATH), given)
+}
+
+fn resolve_genesis_validators_root_at(path: &Path, given: Option<Root>) -> Result<Root> {
+    match (read_genesis_validators_root(path)?, given) {
+        (Some(_), Some(gvr)) => check_genesis_validators_root(path, gvr).map(|_| gvr),
+        (Some(gvr), None) => Ok(gvr),
+        (None, Some(gvr)) => init_genesis_validators_root(path, gvr).map(|_| gvr),
+        (None, None) => bail!(
+            "The slashing DB has no genesis_validators_root, pass one or import an interchange file first"
+        ),
+    }
+}
+
+fn read_genesis_validators_root(path: &Path) -> Result<Option<Root>> {
+    let gvr_hex = match fs::read_to_string(path) {
+        Ok(gvr_hex) => gvr_hex,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| "Failed to read genesis_validators_root"),
+    };
+    let gvr_hex: String = strip_0x_prefix!(gvr_hex.trim());
+    let gvr: Root = hex::decode(gvr_hex)?
+        .try_into()
+        .map_err(|_| anyhow!("Corrupt genesis_validators_root: {}", path.display()))?;
+    Ok(Some(gvr))
+}
+
+// Errors if the DB at `path` was initialized with a root other than `genesis_validators_root`
+fn check_genesis_validators_root(path: &Path, genesis_validators_root: Root) -> Result<()> {
+    match read_genesis_validators_root(path)? {
+        Some(gvr) if gvr != genesis_validators_root => bail!(
+            "Mismatched genesis_validators_root: got 0x{}, the slashing DB was initialized with 0x{}",
+            hex::encode(genesis_validators_root),
+            hex::encode(gvr)
+        ),
+        _ => Ok(()),
+    }
+}
+
+// Written under a temporary name and linked into place, so a crash never leaves a torn root and of
+// two concurrent inits for different roots exactly one succeeds
+fn init_genesis_validators_root(path: &Path, genesis_validators_root: Root) -> Result<()> {
+    check_genesis_validators_root(path, genesis_validators_root)?;
+    if path.exists() {
+        return Ok(());
+    }
+    if let Some(p) = path.parent() {
+        fs::create_dir_all(p)?
+    };
+    let gvr_hex = format!("0x{}", hex::encode(genesis_validators_root));
+    let created = crate::io::create_new_atomically(path, gvr_hex.as_bytes())
+        .with_context(|| "Failed to write genesis_validators_root")?;
+    if !created {
+        check_genesis_validators_root(path, genesis_validators_root)?
+    }
+    Ok(())
+}
+
+/// Imports an EIP-3076 interchange file with `import_slashing_interchange`, so only ever raising
+/// each validator's high-water marks. Errors if the file's genesis_validators_root differs from
+/// the one the DB was initialized with, the first import initializes an uninitialized DB. The root
+/// is pinned before any record is merged, so concurrent imports for two networks never both merge.
+pub fn import_interchange(json: &str) -> Result<InterchangeImportSummary> {
+    let db = SlashingProtectionDB::from_str(json).with_context(|| "Malformed interchange file")?;
+    let gvr = db.metadata.genesis_validators_root;
+    validate_slashing_interchange(json, gvr)?;
+    init_genesis_validators_root(Path::new(SLASHING_GENESIS_VALIDATORS_ROOT_PATH), gvr)?;
+    import_slashing_interchange(json, gvr)
+}
+
+/// Exports every validator's slashing protection DB as an EIP-3076 complete interchange file
+/// under `genesis_validators_root`, see `resolve_genesis_validators_root`
+pub fn export_interchange(genesis_validators_root: Option<Root>) -> Result<String> {
+    let gvr = resolve_genesis_validators_root(genesis_validators_root)?;
+    let Some(_db_guard) = try_lock_for_signing() else {
+        bail!("Slashing protection DB is being compacted, retry shortly")
+    };
+    export_slashing_dir(Path::new(SLASHING_PROTECTION_DIR), gvr, None)
+}
+
+/// Exports the slashing protection DBs of the validators `pk_hexes` like `export_interchange`,
+/// under `genesis_validators_root`. Errors if the DB was initialized with another root.
+/// Validators without a DB are left out.
+pub fn export_interchange_for(
+    pk_hexes: &[String],
+    genesis_validators_root: Root,
+) -> Result<String> {
+    check_genesis_validators_root(
+        Path::new(SLASHING_GENESIS_VALIDATORS_ROOT_PATH),
+        genesis_validators_root,
+    )?;
+    let Some(_db_guard) = try_lock_for_signing() else {
+        bail!("Slashing protection DB is being compacted, retry shortly")
+    };
+    let pk_hexes: Vec<String> = pk_hexes
+        .iter()
+        .map(|pk_hex| {
+            let pk_hex: String = strip_0x_prefix!(pk_hex);
+            pk_hex.to_lowercase()
+        })
+        .collect();
+    export_slashing_dir(
+        Path::new(SLASHING_PROTECTION_DIR),
+        genesis_validators_root,
+        Some(&pk_hexes),
+    )
+}
+
+// Runs `f` on the DB of the validator `pk_hex`, None if it has none, holding the locks of a
+// check-sign-record so no signature is recorded in between and no compaction runs meanwhile.
+// Every write to a DB outside of `sign_and_record` goes through here.
+fn with_locked_db<T>(
+    pk_hex: &str,
+    f: impl FnOnce(Option<SlashingProtectionData>) -> Result<T>,
+) -> Result<T> {
+    let pk_hex: String = strip_0x_prefix!(pk_hex);
+    let Some(_db_guard) = try_lock_for_signing() else {
+        bail!("Slashing protection DB is being compacted, retry shortly")
+    };
+    let validator_lock = validator_signing_lock(&pk_hex);
+    let _validator_guard = validator_lock.lock().unwrap_or_else(|e| e.into_inner());
+    let file_path: PathBuf = [SLASHING_PROTECTION_DIR, &pk_hex].iter().collect();
+    let existing = if file_path.exists() {
+        Some(SlashingProtectionData::read(&pk_hex)?)
+    } else {
+        None
+    };
+    f(existing)
+}
+
+/// Starts an empty slashing protection DB for the validator `pk_hex` unless it already has one,
+/// so the records of a re-imported key are kept
+pub fn init_slashing_db_if_missing(pk_hex: &str) -> Result<()> {
+    with_locked_db(pk_hex, |existing| match existing {
+        Some(_) => Ok(()),
+        None => SlashingProtectionData::from_pk_hex(pk_hex)?.write(),
+    })
+}
+
+/// Merges `restored` into the slashing protection DB of the validator `pk_hex` with `merge_max`,
+/// starting the DB if it has none, so protection is never weakened
+pub fn merge_slashing_db(pk_hex: &str, restored: &SlashingProtectionData) -> Result<()> {
+    with_locked_db(pk_hex, |existing| {
+        let mut db = match existing {
+            Some(db) => db,
+            None => SlashingProtectionData::from_pk_hex(pk_hex)?,
+        };
+        db.merge_max(restored);
+        db.write()
+    })
+}
+
+/// Refuses a block at `slot` if it is not above the highest slot signed by `pk_hex`, otherwise
+/// commits it to the validator's DB before returning. The check and record of
+/// `eth_signing::sign_and_record` without the signature, for callers that record before signing:
+/// a sign failing after this leaves the slot burned, which is safe.
+pub fn check_and_record_block(pk_hex: &str, slot: Slot) -> Result<()> {
+    with_locked_db(pk_hex, |existing| {
+        let Some(mut db) = existing else {
+            bail!("No slashing protection DB for {pk_hex}")
+        };
+        if db.is_slashable_block_slot(slot) {
+            bail!(
+                "Block at slot {slot} is not above the highest signed slot {}",
+                db.get_latest_signed_block_slot()
+            )
+        }
+        let b = SignedBlockSlot {
+            slot,
+            signing_root: None,
+        };
+        db.new_block(b, crate::constants::ALLOW_GROWABLE_SLASH_PROTECTION_DB)?;
+        db.write()
+    })
+}
+
+/// Refuses an attestation that double votes, surrounds or is surrounded by one signed by
+/// `pk_hex` (see `SlashingProtectionData::check_attestation_epochs`), otherwise commits it to the
+/// validator's DB, pruned below `weak_subjectivity_epoch`, before returning. Like
+/// `check_and_record_block`, the check and record of `eth_signing::sign_and_record` without the
+/// signature.
+pub fn check_and_record_attestation(
+    pk_hex: &str,
+    source_epoch: Epoch,
+    target_epoch: Epoch,
+) -> Result<()> {
+    with_locked_db(pk_hex, |existing| {
+        let Some(mut db) = existing else {
+            bail!("No slashing protection DB for {pk_hex}")
+        };
+        db.check_attestation_epochs(source_epoch, target_epoch)?;
+        let a = SignedAttestationEpochs {
+            source_epoch,
+            target_epoch,
+            signing_root: None,
+        };
+        db.new_attestation(a, crate::constants::ALLOW_GROWABLE_SLASH_PROTECTION_DB)?;
+        db.prune_attestations(weak_subjectivity_epoch());
+        db.write()
+    })
+}
+
+// Exports every DB in `dir`, or only those named in `only`
+fn export_slashing_dir(
+    dir: &Path,
+    genesis_validators_root: Root,
+    only: Option<&[String]>,
+) -> Result<String> {
+    let mut interchange = SlashingProtectionDB::new();
+    interchange.metadata.genesis_validators_root = genesis_validators_root;
+    let mut paths = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(e) => return Err(e).with_context(|| "Failed to read slashing dir"),
+    };
+    paths.sort();
+
+    for path in paths.iter() {
+        // Writes are renamed into place, so a pending file was never committed
+        if path
+            .extension()
+            .is_some_and(|e| e == PENDING_WRITE_EXTENSION)
+        {
+            continue;
+        }
+        if let Some(only) = only {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            if !only.iter().any(|pk_hex| pk_hex == name) {
+                continue;
+            }
+        }
+        let data: SlashingProtectionData = serde_json::from_slice(&fs::read(path)?)
+            .with_context(|| format!("Corrupt slashing protection DB: {}", path.display()))?;
+        interchange.data.push(data);
+    }
+    Ok(serde_json::to_string(&interchange)?)
+}
+
 #[cfg(test)]
 pub mod test_slash_protection {
     use super::*;
@@ -293,6 +1047,525 @@ pub mod test_slash_protection {
         Ok(())
     }
 
+    fn dummy_gvr() -> Root {
+        let mut gvr = Root::default();
+        gvr.copy_from_slice(
+            &hex::decode("04700007fabc8282644aed6d1c7c9e21d38a03a0c4ba193f3afe428824b3a673")
+                .unwrap(),
+        );
+        gvr
+    }
+
+    #[test]
+    fn test_validate_slashing_interchange() -> Result<()> {
+        let raw = dummy_slash_protection_data();
+        let summary = validate_slashing_interchange(&raw, dummy_gvr())?;
+        assert_eq!(summary.interchange_format_version, "5");
+        assert_eq!(summary.genesis_validators_root, dummy_gvr());
+        assert_eq!(
+            summary.validators,
+            vec![InterchangeValidatorSummary {
+                pubkey: "0x8349434ad0700e79be65c0c7043945df426bd6d7e288c16671df69d822344f1b0ce8de80360a50550ad782b68035cb18".to_string(),
+                signed_blocks: 2,
+                signed_attestations: 2,
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reimporting_an_interchange_applies_nothing() -> Result<()> {
+        let pk_hex = crate::crypto::bls_keys::new_bls_key(0)
+            .public_keys()
+            .public_key()
+            .to_hex();
+        let raw = dummy_slash_protection_data().replace(
+            "8349434ad0700e79be65c0c7043945df426bd6d7e288c16671df69d822344f1b0ce8de80360a50550ad782b68035cb18",
+            &pk_hex,
+        );
+
+        let first = import_slashing_interchange(&raw, dummy_gvr())?;
+        assert_eq!(
+            first,
+            InterchangeImportSummary {
+                applied: 4,
+                skipped: 0
+            }
+        );
+        let marks = SlashingProtectionData::read(&pk_hex)?.watermarks();
+        assert_eq!(marks.highest_signed_slot, Some(81952));
+        assert_eq!(marks.highest_source_epoch, Some(2290));
+        assert_eq!(marks.highest_target_epoch, Some(3008));
+
+        let second = import_slashing_interchange(&raw, dummy_gvr())?;
+        assert_eq!(
+            second,
+            InterchangeImportSummary {
+                applied: 0,
+                skipped: 4
+            }
+        );
+        assert_eq!(SlashingProtectionData::read(&pk_hex)?.watermarks(), marks);
+
+        // Only records above the high-water marks are applied, and the marks never decrease
+        let raw = raw.replace(r#""slot": "81951""#, r#""slot": "90000""#);
+        let third = import_slashing_interchange(&raw, dummy_gvr())?;
+        assert_eq!(
+            third,
+            InterchangeImportSummary {
+                applied: 1,
+                skipped: 3
+            }
+        );
+        let marks = SlashingProtectionData::read(&pk_hex)?.watermarks();
+        assert_eq!(marks.highest_signed_slot, Some(90000));
+        assert_eq!(marks.highest_target_epoch, Some(3008));
+
+        assert!(import_slashing_interchange(&raw, Root::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_genesis_validators_root_is_fixed_by_first_init() -> Result<()> {
+        let path = Path::new("./etc/slashing_gvr_test");
+        let _ = fs::remove_file(path);
+        assert!(read_genesis_validators_root(path)?.is_none());
+        check_genesis_validators_root(path, Root::default())?;
+
+        init_genesis_validators_root(path, dummy_gvr())?;
+        init_genesis_validators_root(path, dummy_gvr())?;
+        assert_eq!(read_genesis_validators_root(path)?, Some(dummy_gvr()));
+        assert!(check_genesis_validators_root(path, Root::default()).is_err());
+        assert!(init_genesis_validators_root(path, Root::default()).is_err());
+        assert_eq!(read_genesis_validators_root(path)?, Some(dummy_gvr()));
+        fs::remove_file(path)?;
+
+        // Of concurrent first inits for different roots exactly one wins
+        let inits: Vec<_> = [dummy_gvr(), Root::default()]
+            .into_iter()
+            .map(|gvr| std::thread::spawn(move || init_genesis_validators_root(path, gvr)))
+            .collect();
+        let won: Vec<bool> = inits
+            .into_iter()
+            .map(|t| t.join().unwrap().is_ok())
+            .collect();
+        assert_eq!(won.iter().filter(|w| **w).count(), 1);
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_genesis_validators_root() -> Result<()> {
+        let path = Path::new("./etc/slashing_gvr_resolve_test");
+        let _ = fs::remove_file(path);
+
+        // An uninitialized DB needs a root, and is initialized with the first one given
+        assert!(resolve_genesis_validators_root_at(path, None).is_err());
+        assert!(read_genesis_validators_root(path)?.is_none());
+        assert_eq!(
+            resolve_genesis_validators_root_at(path, Some(dummy_gvr()))?,
+            dummy_gvr()
+        );
+        assert_eq!(resolve_genesis_validators_root_at(path, None)?, dummy_gvr());
+        assert!(resolve_genesis_validators_root_at(path, Some(Root::default())).is_err());
+        assert_eq!(read_genesis_validators_root(path)?, Some(dummy_gvr()));
+
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_interchange_round_trips() -> Result<()> {
+        let dir = Path::new("./etc/slashing_export_test/");
+        let _ = fs::remove_dir_all(dir);
+        let db = SlashingProtectionDB::from_str(&dummy_slash_protection_data())?;
+        db.data[0].write_to(&dir.join(hex::encode(db.data[0].pubkey.as_ssz_bytes())))?;
+        let mut other = SlashingProtectionData::from_pk_hex(&"ab".repeat(48))?;
+        other.new_block(
+            SignedBlockSlot {
+                slot: 7,
+                signing_root: None,
+            },
+            false,
+        )?;
+        let other_path = dir.join("ab".repeat(48));
+        other.write_to(&other_path)?;
+        fs::write(
+            other_path.with_extension(PENDING_WRITE_EXTENSION),
+            b"{\"pubkey",
+        )?;
+
+        let json = export_slashing_dir(dir, dummy_gvr(), None)?;
+        assert!(!json.contains("title"));
+        let summary = validate_slashing_interchange(&json, dummy_gvr())?;
+        assert_eq!(summary.validators.len(), 2);
+        let exported = SlashingProtectionDB::from_str(&json)?;
+        let marks: Vec<SlashingWatermarks> = exported.data.iter().map(|d| d.watermarks()).collect();
+        assert_eq!(marks[0], db.data[0].watermarks());
+        assert_eq!(marks[1].highest_signed_slot, Some(7));
+        assert!(validate_slashing_interchange(&json, Root::default()).is_err());
+
+        let only = export_slashing_dir(dir, dummy_gvr(), Some(&["ab".repeat(48)]))?;
+        let only = SlashingProtectionDB::from_str(&only)?;
+        assert_eq!(only.data.len(), 1);
+        assert_eq!(only.data[0].watermarks().highest_signed_slot, Some(7));
+
+        fs::remove_dir_all(dir)?;
+        assert!(export_slashing_dir(dir, dummy_gvr(), None)?.contains(r#""data":[]"#));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_slashing_interchange_wrong_gvr() {
+        let raw = dummy_slash_protection_data();
+        assert!(validate_slashing_interchange(&raw, Root::default()).is_err());
+    }
+
+    #[test]
+    fn test_validate_slashing_interchange_unsupported_version() {
+        let raw = dummy_slash_protection_data().replace(
+            r#""interchange_format_version": "5""#,
+            r#""interchange_format_version": "4""#,
+        );
+        assert!(validate_slashing_interchange(&raw, dummy_gvr()).is_err());
+    }
+
+    #[test]
+    fn test_recover_torn_write_keeps_committed_mark() -> Result<()> {
+        // Isolated from SLASHING_PROTECTION_DIR so concurrent tests' pending writes are untouched
+        let dir = Path::new("./etc/slashing_recovery_test/");
+        fs::create_dir_all(dir)?;
+        let pk_hex = "ab".repeat(48);
+        let mut data = SlashingProtectionData::from_pk_hex(&pk_hex)?;
+        let b = SignedBlockSlot {
+            slot: 10,
+            signing_root: None,
+        };
+        data.new_block(b, false)?;
+        let committed = dir.join(&pk_hex);
+        fs::write(&committed, serde_json::to_string(&data)?)?;
+
+        // Crash while committing slot 11: only half of the pending write reached disk
+        let b = SignedBlockSlot {
+            slot: 11,
+            signing_root: None,
+        };
+        data.new_block(b, false)?;
+        let json = serde_json::to_string(&data)?;
+        let pending = committed.with_extension(PENDING_WRITE_EXTENSION);
+        fs::write(&pending, &json[..json.len() / 2])?;
+
+        assert_eq!(recover_slashing_dir(dir)?, 1);
+        assert!(!pending.exists());
+        let recovered: SlashingProtectionData = serde_json::from_slice(&fs::read(&committed)?)?;
+        assert_eq!(recovered.get_latest_signed_block_slot(), 10);
+        assert_eq!(recover_slashing_dir(dir)?, 0);
+
+        // A torn committed DB is never silently discarded
+        fs::write(&committed, &json[..json.len() / 2])?;
+        assert!(recover_slashing_dir(dir).is_err());
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_injected_write_failure_fails_next_write_only() -> Result<()> {
+        let pk_hex = hex::encode(
+            crate::crypto::bls_keys::new_bls_key(0)
+                .public_keys()
+                .public_key()
+                .to_bytes(),
+        );
+        let mut data = SlashingProtectionData::from_pk_hex(&pk_hex)?;
+        data.write()?;
+
+        fail_next_write(&format!("0x{pk_hex}"));
+        let b = SignedBlockSlot {
+            slot: 10,
+            signing_root: None,
+        };
+        data.new_block(b, false)?;
+        assert!(data.write().is_err());
+        // The committed DB is untouched by the failed write
+        let committed = SlashingProtectionData::read(&pk_hex)?;
+        assert!(committed.signed_blocks.is_empty());
+
+        data.write()?;
+        let committed = SlashingProtectionData::read(&pk_hex)?;
+        assert_eq!(committed.get_latest_signed_block_slot(), 10);
+
+        let file_path: PathBuf = [SLASHING_PROTECTION_DIR, &pk_hex].iter().collect();
+        fs::remove_file(file_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_and_record_block() -> Result<()> {
+        let pk_hex = hex::encode(
+            crate::crypto::bls_keys::new_bls_key(0)
+                .public_keys()
+                .public_key()
+                .to_bytes(),
+        );
+        // The DB must exist, as for any other signing path
+        assert!(check_and_record_block(&pk_hex, 10).is_err());
+        SlashingProtectionData::from_pk_hex(&pk_hex)?.write()?;
+
+        check_and_record_block(&format!("0x{pk_hex}"), 10)?;
+        assert_eq!(
+            SlashingProtectionData::read(&pk_hex)?.get_latest_signed_block_slot(),
+            10
+        );
+        assert!(check_and_record_block(&pk_hex, 10).is_err());
+        assert!(check_and_record_block(&pk_hex, 9).is_err());
+
+        // A failed write commits nothing, so the slot can be retried
+        fail_next_write(&pk_hex);
+        assert!(check_and_record_block(&pk_hex, 11).is_err());
+        check_and_record_block(&pk_hex, 11)?;
+        assert_eq!(
+            SlashingProtectionData::read(&pk_hex)?.get_latest_signed_block_slot(),
+            11
+        );
+
+        let file_path: PathBuf = [SLASHING_PROTECTION_DIR, &pk_hex].iter().collect();
+        fs::remove_file(file_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_and_record_attestation() -> Result<()> {
+        let pk_hex = hex::encode(
+            crate::crypto::bls_keys::new_bls_key(0)
+                .public_keys()
+                .public_key()
+                .to_bytes(),
+        );
+        assert!(check_and_record_attestation(&pk_hex, 1, 2).is_err());
+        SlashingProtectionData::from_pk_hex(&pk_hex)?.write()?;
+
+        check_and_record_attestation(&pk_hex, 1, 2)?;
+        check_and_record_attestation(&format!("0x{pk_hex}"), 2, 3)?;
+        assert!(check_and_record_attestation(&pk_hex, 2, 3).is_err());
+        assert!(check_and_record_attestation(&pk_hex, 1, 4).is_err());
+        assert_eq!(
+            SlashingProtectionData::read(&pk_hex)?.get_latest_signed_attestation_epochs(),
+            (2, 3)
+        );
+
+        let file_path: PathBuf = [SLASHING_PROTECTION_DIR, &pk_hex].iter().collect();
+        fs::remove_file(file_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_surround_vote_rules() {
+        // Imported history, as in the interchange tests, so not bound to the high-water marks
+        let mut data = SlashingProtectionData::new(BLSPubkey::default());
+        for (source_epoch, target_epoch) in [(2, 3), (4, 7), (10, 20)] {
+            data.signed_attestations.push(SignedAttestationEpochs {
+                source_epoch,
+                target_epoch,
+                signing_root: None,
+            });
+        }
+        let err = |src, tgt| {
+            data.check_attestation_epochs(src, tgt)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(err(5, 4).contains("after its target"));
+        assert!(err(2, 3).contains("Double vote"));
+        assert!(err(3, 7).contains("Double vote"));
+        assert!(err(1, 4).contains("surrounds the signed (2, 3)"));
+        assert!(err(9, 21).contains("surrounds the signed (10, 20)"));
+        assert!(err(5, 6).contains("surrounded by the signed (4, 7)"));
+        assert!(err(11, 19).contains("surrounded by the signed (10, 20)"));
+        // Not slashable by the spec, but below the marks that are kept once history is pruned
+        assert!(err(10, 11).contains("high-water marks"));
+
+        assert!(data.check_attestation_epochs(10, 21).is_ok());
+        assert!(data.check_attestation_epochs(20, 21).is_ok());
+        assert!(data.check_attestation_epochs(21, 21).is_ok());
+    }
+
+    #[test]
+    fn test_pruning_keeps_attestation_marks() {
+        let mut data = SlashingProtectionData::new(BLSPubkey::default());
+        for (source_epoch, target_epoch) in [(2, 3), (4, 7), (10, 20), (12, 15)] {
+            data.signed_attestations.push(SignedAttestationEpochs {
+                source_epoch,
+                target_epoch,
+                signing_root: None,
+            });
+        }
+        data.prune_attestations(8);
+        let kept: Vec<(Epoch, Epoch)> = data
+            .signed_attestations
+            .iter()
+            .map(|a| (a.source_epoch, a.target_epoch))
+            .collect();
+        assert_eq!(kept, vec![(10, 20), (12, 15)]);
+
+        data.prune_attestations(100);
+        assert_eq!(data.signed_attestations.len(), 1);
+        assert_eq!(data.get_latest_signed_attestation_epochs(), (12, 20));
+        assert!(data.check_attestation_epochs(11, 21).is_err());
+        assert!(data.check_attestation_epochs(12, 21).is_ok());
+    }
+
+    // A single validator's history imported from an EIP-3076 interchange file
+    fn imported_history(
+        blocks: &[Slot],
+        attestations: &[(Epoch, Epoch)],
+    ) -> SlashingProtectionData {
+        let interchange = serde_json::json!({
+            "metadata": {
+                "interchange_format_version": "5",
+                "genesis_validators_root": format!("0x{}", hex::encode(dummy_gvr()))
+            },
+            "data": [{
+                "pubkey": "0x8349434ad0700e79be65c0c7043945df426bd6d7e288c16671df69d822344f1b0ce8de80360a50550ad782b68035cb18",
+                "signed_blocks": blocks
+                    .iter()
+                    .map(|slot| serde_json::json!({ "slot": slot.to_string() }))
+                    .collect::<Vec<_>>(),
+                "signed_attestations": attestations
+                    .iter()
+                    .map(|(src, tgt)| serde_json::json!({
+                        "source_epoch": src.to_string(),
+                        "target_epoch": tgt.to_string()
+                    }))
+                    .collect::<Vec<_>>()
+            }]
+        });
+        SlashingProtectionDB::from_str(&interchange.to_string())
+            .unwrap()
+            .data
+            .remove(0)
+    }
+
+    #[test]
+    fn test_eip_3076_interchange_cases() {
+        // Named after the cases of the EIP-3076 interchange test suite. Re-signing an identical
+        // block signing root is refused, which the suite allows of minimal implementations.
+        let db = imported_history(&[10], &[]);
+        assert!(
+            db.is_slashable_block_slot(10),
+            "single_validator_slashable_blocks"
+        );
+        assert!(
+            db.is_slashable_block_slot(9),
+            "single_validator_single_block"
+        );
+        assert!(
+            !db.is_slashable_block_slot(11),
+            "single_validator_single_block"
+        );
+
+        let db = imported_history(&[], &[(0, 0)]);
+        assert!(
+            db.check_attestation_epochs(0, 0).is_err(),
+            "single_validator_genesis_attestation"
+        );
+        assert!(db.check_attestation_epochs(0, 1).is_ok());
+
+        let db = imported_history(&[], &[(2, 3)]);
+        assert!(
+            db.check_attestation_epochs(2, 3).is_err(),
+            "single_validator_slashable_attestations_double_vote"
+        );
+        assert!(
+            db.check_attestation_epochs(1, 4).is_err(),
+            "single_validator_slashable_attestations_surrounds_existing"
+        );
+        assert!(db.check_attestation_epochs(3, 4).is_ok());
+
+        let db = imported_history(&[], &[(0, 3)]);
+        assert!(
+            db.check_attestation_epochs(1, 2).is_err(),
+            "single_validator_slashable_attestations_surrounded_by_existing"
+        );
+
+        let db = imported_history(&[], &[(8, 9)]);
+        assert!(
+            db.check_attestation_epochs(9, 8).is_err(),
+            "single_validator_source_greater_than_target"
+        );
+        assert!(db.check_attestation_epochs(9, 10).is_ok());
+    }
+
+    #[test]
+    fn test_is_repeat_attestation() -> Result<()> {
+        let mut db = SlashingProtectionData::from_pk_hex(&"ef".repeat(48))?;
+        db.new_attestation(
+            SignedAttestationEpochs {
+                source_epoch: 2,
+                target_epoch: 3,
+                signing_root: Some([1; 32]),
+            },
+            true,
+        )?;
+        assert!(db.is_repeat_attestation(2, 3, &[1; 32]));
+        // Another root, or the same root at other epochs, is not a repeat
+        assert!(!db.is_repeat_attestation(2, 3, &[2; 32]));
+        assert!(!db.is_repeat_attestation(1, 3, &[1; 32]));
+
+        // Imported records without a signing root are never repeats
+        let db = imported_history(&[], &[(2, 3)]);
+        assert!(!db.is_repeat_attestation(2, 3, &[0; 32]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_compaction_shrinks_db_and_keeps_watermarks() -> Result<()> {
+        let dir = Path::new("./etc/slashing_compaction_test/");
+        fs::create_dir_all(dir)?;
+        let pk_hex = "cd".repeat(48);
+        let mut data = SlashingProtectionData::from_pk_hex(&pk_hex)?;
+        for i in 1..=500 {
+            let b = SignedBlockSlot {
+                slot: i,
+                signing_root: Some([1; 32]),
+            };
+            data.new_block(b, true)?;
+            let a = SignedAttestationEpochs {
+                source_epoch: i / 2,
+                target_epoch: i,
+                signing_root: Some([2; 32]),
+            };
+            data.new_attestation(a, true)?;
+        }
+        let marks = data.watermarks();
+        let path = dir.join(&pk_hex);
+        data.write_to(&path)?;
+        fs::write(path.with_extension(PENDING_WRITE_EXTENSION), b"{\"pubkey")?;
+
+        let summary = compact_slashing_dir(dir)?;
+        assert_eq!(summary.databases, 1);
+        assert!(summary.bytes_after < summary.bytes_before / 100);
+        assert_eq!(fs::metadata(&path)?.len(), summary.bytes_after);
+        assert!(!path.with_extension(PENDING_WRITE_EXTENSION).exists());
+
+        let compacted: SlashingProtectionData = serde_json::from_slice(&fs::read(&path)?)?;
+        let compacted_marks = compacted.watermarks();
+        assert_eq!(
+            compacted_marks.highest_signed_slot,
+            marks.highest_signed_slot
+        );
+        assert_eq!(compacted_marks.highest_source_epoch, Some(250));
+        assert_eq!(compacted_marks.highest_target_epoch, Some(500));
+        assert!(compacted.is_slashable_block_slot(500));
+        assert!(compacted.is_slashable_attestation_epochs(249, 501));
+        assert!(!compacted.is_slashable_attestation_epochs(250, 501));
+
+        fs::remove_dir_all(dir)?;
+        Ok(())
+    }
+
     #[test]
     fn test_new_db() -> Result<()> {
         let db = SlashingProtectionDB::new();
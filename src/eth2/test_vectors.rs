@@ -0,0 +1,394 @@
+use super::eth_signing::BLSSignMsg;
+use super::eth_types::{GENESIS_FORK_VERSION, KNOWN_NETWORKS};
+use crate::crypto::bls_keys;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+/// EIP-2334 path of the key every vector is signed with
+pub const TEST_VECTOR_DERIVATION_PATH: &str = "m/12381/3600/0/0/0";
+
+/// A signing request of one type together with its expected signing root and signature
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TestVector {
+    #[serde(rename = "type")]
+    pub signing_type: String,
+    pub request: Value,
+    pub signing_root: String,
+    pub signature: String,
+}
+
+/// Deterministic interop fixture: a key derived from `seed` and one vector per signing type
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TestVectors {
+    pub seed: String,
+    pub derivation_path: String,
+    pub secret_key: String,
+    pub pubkey: String,
+    pub genesis_fork_version: String,
+    pub vectors: Vec<TestVector>,
+}
+
+// `len` bytes expanded from sha256(seed || label || counter)
+fn seeded_bytes(seed: &[u8], label: &str, len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len);
+    let mut counter = 0_u32;
+    while out.len() < len {
+        let mut hasher = Sha256::new();
+        hasher.update(seed);
+        hasher.update(label.as_bytes());
+        hasher.update(counter.to_be_bytes());
+        out.extend_from_slice(&hasher.finalize());
+        counter += 1;
+    }
+    out.truncate(len);
+    out
+}
+
+fn seeded_hex(seed: &[u8], label: &str, len: usize) -> String {
+    format!("0x{}", hex::encode(seeded_bytes(seed, label, len)))
+}
+
+// A quoted u64 below `bound`, as the Web3Signer API expects
+fn seeded_u64(seed: &[u8], label: &str, bound: u64) -> String {
+    let bytes: [u8; 8] = seeded_bytes(seed, label, 8).try_into().unwrap();
+    (u64::from_be_bytes(bytes) % bound).to_string()
+}
+
+fn signing_requests(seed: &[u8], pk_hex: &str) -> Vec<Value> {
+    let hex_of = |label: &str, len: usize| seeded_hex(seed, label, len);
+    let u64_below = |label: &str, bound: u64| seeded_u64(seed, label, bound);
+    let root = |label: &str| seeded_hex(seed, label, 32);
+    let sig = |label: &str| seeded_hex(seed, label, 96);
+    let slot = |label: &str| seeded_u64(seed, label, 1 << 32);
+    let index = |label: &str| seeded_u64(seed, label, 1 << 20);
+    let pubkey = format!("0x{pk_hex}");
+    let withdrawal_credentials = format!(
+        "0x010000000000000000000000{}",
+        hex::encode(seeded_bytes(seed, "deposit.execution_address", 20))
+    );
+
+    let fork_info = json!({
+        "fork": {
+            "previous_version": "0x03000000",
+            "current_version": "0x04000000",
+            "epoch": "0"
+        },
+        "genesis_validators_root": root("genesis_validators_root")
+    });
+    // Blocks select their body from a known network's schedule, so are proposed on mainnet Capella
+    let capella_version = format!("0x{}", hex::encode(KNOWN_NETWORKS[0].fork_versions[3]));
+    let block_fork_info = json!({
+        "fork": {
+            "previous_version": capella_version,
+            "current_version": capella_version,
+            "epoch": "0"
+        },
+        "genesis_validators_root": format!("0x{}", KNOWN_NETWORKS[0].genesis_validators_root)
+    });
+    let attestation_data = json!({
+        "slot": slot("attestation.slot"),
+        "index": index("attestation.index"),
+        "beacon_block_root": root("attestation.beacon_block_root"),
+        "source": { "epoch": "10", "root": root("attestation.source.root") },
+        "target": { "epoch": "11", "root": root("attestation.target.root") }
+    });
+
+    vec![
+        json!({
+            "type": "BLOCK",
+            "fork_info": block_fork_info,
+            "block": {
+                "slot": slot("block.slot"),
+                "proposer_index": index("block.proposer_index"),
+                "parent_root": root("block.parent_root"),
+                "state_root": root("block.state_root"),
+                "body": {
+                    "randao_reveal": sig("block.randao_reveal"),
+                    "eth1_data": {
+                        "deposit_root": root("block.eth1_data.deposit_root"),
+                        "deposit_count": index("block.eth1_data.deposit_count"),
+                        "block_hash": root("block.eth1_data.block_hash")
+                    },
+                    "graffiti": root("block.graffiti"),
+                    "proposer_slashings": [],
+                    "attester_slashings": [],
+                    "attestations": [],
+                    "deposits": [],
+                    "voluntary_exits": [],
+                    "sync_aggregate": {
+                        "sync_committee_bits": hex_of("block.sync_committee_bits", 64),
+                        "sync_committee_signature": sig("block.sync_committee_signature")
+                    },
+                    "execution_payload": {
+                        "parent_hash": root("block.execution_payload.parent_hash"),
+                        "fee_recipient": hex_of("block.execution_payload.fee_recipient", 20),
+                        "state_root": root("block.execution_payload.state_root"),
+                        "receipts_root": root("block.execution_payload.receipts_root"),
+                        "logs_bloom": hex_of("block.execution_payload.logs_bloom", 256),
+                        "prev_randao": root("block.execution_payload.prev_randao"),
+                        "block_number": slot("block.execution_payload.block_number"),
+                        "gas_limit": "30000000",
+                        "gas_used": "21000",
+                        "timestamp": slot("block.execution_payload.timestamp"),
+                        "extra_data": hex_of("block.execution_payload.extra_data", 8),
+                        "base_fee_per_gas": "63708707529687817917533240047805124624724989221198991928642968237818118949448",
+                        "block_hash": root("block.execution_payload.block_hash"),
+                        "transactions": [],
+                        "withdrawals": []
+                    },
+                    "bls_to_execution_changes": []
+                }
+            }
+        }),
+        json!({
+            "type": "BLOCK_V2",
+            "fork_info": fork_info,
+            "beacon_block": {
+                "version": "DENEB",
+                "block_header": {
+                    "slot": slot("block_v2.slot"),
+                    "proposer_index": index("block_v2.proposer_index"),
+                    "parent_root": root("block_v2.parent_root"),
+                    "state_root": root("block_v2.state_root"),
+                    "body_root": root("block_v2.body_root")
+                }
+            }
+        }),
+        json!({
+            "type": "ATTESTATION",
+            "fork_info": fork_info,
+            "attestation": attestation_data
+        }),
+        json!({
+            "type": "RANDAO_REVEAL",
+            "fork_info": fork_info,
+            "randao_reveal": { "epoch": slot("randao_reveal.epoch") }
+        }),
+        json!({
+            "type": "AGGREGATE_AND_PROOF",
+            "fork_info": fork_info,
+            "aggregate_and_proof": {
+                "aggregator_index": index("aggregate_and_proof.aggregator_index"),
+                "aggregate": {
+                    "aggregation_bits": "0x1234",
+                    "data": attestation_data,
+                    "signature": sig("aggregate_and_proof.aggregate.signature")
+                },
+                "selection_proof": sig("aggregate_and_proof.selection_proof")
+            }
+        }),
+        json!({
+            "type": "AGGREGATION_SLOT",
+            "fork_info": fork_info,
+            "aggregation_slot": { "slot": slot("aggregation_slot.slot") }
+        }),
+        json!({
+            "type": "DEPOSIT",
+            "deposit": {
+                "pubkey": pubkey,
+                "withdrawal_credentials": withdrawal_credentials,
+                "amount": "32000000000"
+            },
+            "genesis_fork_version": format!("0x{}", hex::encode(GENESIS_FORK_VERSION))
+        }),
+        json!({
+            "type": "VOLUNTARY_EXIT",
+            "fork_info": fork_info,
+            "voluntary_exit": {
+                "epoch": slot("voluntary_exit.epoch"),
+                "validator_index": index("voluntary_exit.validator_index")
+            }
+        }),
+        json!({
+            "type": "SYNC_COMMITTEE_MESSAGE",
+            "fork_info": fork_info,
+            "sync_committee_message": {
+                "slot": slot("sync_committee_message.slot"),
+                "beacon_block_root": root("sync_committee_message.beacon_block_root")
+            }
+        }),
+        json!({
+            "type": "SYNC_COMMITTEE_SELECTION_PROOF",
+            "fork_info": fork_info,
+            "sync_aggregator_selection_data": {
+                "slot": slot("sync_committee_selection_proof.slot"),
+                "subcommittee_index": u64_below("sync_committee_selection_proof.subcommittee_index", 4)
+            }
+        }),
+        json!({
+            "type": "SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF",
+            "fork_info": fork_info,
+            "contribution_and_proof": {
+                "aggregator_index": index("contribution_and_proof.aggregator_index"),
+                "contribution": {
+                    "slot": slot("contribution_and_proof.slot"),
+                    "beacon_block_root": root("contribution_and_proof.beacon_block_root"),
+                    "subcommittee_index": u64_below("contribution_and_proof.subcommittee_index", 4),
+                    "aggregation_bits": hex_of("contribution_and_proof.aggregation_bits", 16),
+                    "signature": sig("contribution_and_proof.signature")
+                },
+                "selection_proof": sig("contribution_and_proof.selection_proof")
+            }
+        }),
+        json!({
+            "type": "VALIDATOR_REGISTRATION",
+            "validator_registration": {
+                "fee_recipient": hex_of("validator_registration.fee_recipient", 20),
+                "gas_limit": "30000000",
+                "timestamp": slot("validator_registration.timestamp"),
+                "pubkey": pubkey
+            }
+        }),
+    ]
+}
+
+/// Generates deterministic keys, requests, signing roots and signatures for every signing type.
+/// The same `seed` (at least 32 bytes) always yields the same vectors.
+pub fn generate_test_vectors(seed: &[u8]) -> Result<TestVectors> {
+    let sk_set = bls_keys::derive_bls_key_at_path(seed, TEST_VECTOR_DERIVATION_PATH)?;
+    let pk_hex = sk_set.public_keys().public_key().to_hex();
+
+    let vectors = signing_requests(seed, &pk_hex)
+        .into_iter()
+        .map(|request| {
+            let msg: BLSSignMsg = serde_json::from_value(request.clone())
+                .with_context(|| format!("Malformed test vector request: {request}"))?;
+            let signing_root = msg.to_signing_root(Some(GENESIS_FORK_VERSION));
+            let sig = bls_keys::bls_agg_sign(&sk_set, &signing_root);
+            Ok(TestVector {
+                signing_type: request["type"].as_str().unwrap_or_default().to_string(),
+                request,
+                signing_root: format!("0x{}", hex::encode(signing_root)),
+                signature: format!("0x{}", hex::encode(sig.to_bytes())),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(TestVectors {
+        seed: format!("0x{}", hex::encode(seed)),
+        derivation_path: TEST_VECTOR_DERIVATION_PATH.to_string(),
+        secret_key: format!("0x{}", hex::encode(sk_set.to_bytes())),
+        pubkey: format!("0x{pk_hex}"),
+        genesis_fork_version: format!("0x{}", hex::encode(GENESIS_FORK_VERSION)),
+        vectors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blsttc::{PublicKey, Signature};
+
+    #[test]
+    fn test_vectors_are_stable_for_a_seed() {
+        let seed = [7_u8; 32];
+        let v1 = generate_test_vectors(&seed).unwrap();
+        let v2 = generate_test_vectors(&seed).unwrap();
+        assert_eq!(v1, v2);
+        assert_eq!(
+            serde_json::to_string(&v1).unwrap(),
+            serde_json::to_string(&v2).unwrap()
+        );
+
+        // Every signing type is covered and every signature verifies
+        let types: Vec<&str> = v1.vectors.iter().map(|v| v.signing_type.as_str()).collect();
+        assert_eq!(types, BLSSignMsg::SIGNING_TYPES);
+        let pk = PublicKey::from_hex(&v1.pubkey[2..]).unwrap();
+        for v in v1.vectors.iter() {
+            let root = hex::decode(&v.signing_root[2..]).unwrap();
+            let sig_bytes: [u8; 96] = hex::decode(&v.signature[2..]).unwrap().try_into().unwrap();
+            let sig = Signature::from_bytes(sig_bytes).unwrap();
+            assert!(pk.verify(&sig, root), "{} does not verify", v.signing_type);
+        }
+
+        // A different seed yields a different key and roots
+        let v3 = generate_test_vectors(&[8_u8; 32]).unwrap();
+        assert_ne!(v1.pubkey, v3.pubkey);
+        assert_ne!(v1.vectors[0].signing_root, v3.vectors[0].signing_root);
+        assert!(generate_test_vectors(&[7_u8; 16]).is_err());
+    }
+
+    #[test]
+    fn test_vectors_match_pinned_values() {
+        // Computed independently of this crate from the EIP-2333 derivation and the SSZ
+        // containers of each request
+        const EXPECTED_SIGNING_ROOTS: [(&str, &str); 12] = [
+            (
+                "BLOCK",
+                "0x3e71475809c171466b92c5a55eae40c1dbbb6791941aedceda4ab18edea387f9",
+            ),
+            (
+                "BLOCK_V2",
+                "0x0a771b7d31d337fa8672035c0969353fb59c8192aeefd232fad4984b32a499e2",
+            ),
+            (
+                "ATTESTATION",
+                "0x25646f31192d5ef3f3be31fb033381e53a17f3ac470da9b384845dd67bed00d9",
+            ),
+            (
+                "RANDAO_REVEAL",
+                "0xfa2c409bf7580f82bcc670a735fdd505aa7dc15fc7d7d235779e4b8be9618a9f",
+            ),
+            (
+                "AGGREGATE_AND_PROOF",
+                "0x0d6388cbcc48849ad2e82895abf4503f03e55dacad52feb6d9f88d478b0cffde",
+            ),
+            (
+                "AGGREGATION_SLOT",
+                "0x93b5cf81aa3e38eae25ce0cf3b70f3538e7bdc9edde35d5fc217c19d1c149cc1",
+            ),
+            (
+                "DEPOSIT",
+                "0x0d2c7d59dbdd628f4b25736b276fea54331f2a8b12de9fefb1e0a919dca0237e",
+            ),
+            (
+                "VOLUNTARY_EXIT",
+                "0x478093a44712b112761a7faf18a5f8e936637de7bbc7d59c88ceb99678c92fa2",
+            ),
+            (
+                "SYNC_COMMITTEE_MESSAGE",
+                "0x82dd525065af66c4cedb1f09dccdbd21b4547830395517f6e994ef4de50b641c",
+            ),
+            (
+                "SYNC_COMMITTEE_SELECTION_PROOF",
+                "0x2f221237c6929566b32521a03768043c1ef6bab50500a2c8df6bd48a42b2a780",
+            ),
+            (
+                "SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF",
+                "0x70630c98108e58c8b2ff85239dc635bbdcee2cb5c43b7ef1c7bf69d5f7df6096",
+            ),
+            (
+                "VALIDATOR_REGISTRATION",
+                "0xa0dbf918a675361dbf6d08896a33e0459d98b9fba216ec2107c959b053cecfe5",
+            ),
+        ];
+        let v = generate_test_vectors(&[7_u8; 32]).unwrap();
+        assert_eq!(
+            v.secret_key,
+            "0x46ab50eba3eafc30d80f6f46b0e38edc8c5e9a98263779d038a84e6e21a4c74f"
+        );
+        assert_eq!(
+            v.pubkey,
+            "0x9667f947fe148dd007b4d99174ef6baad09f2c167cb4a023e20e98451be4451307f098596a00492f7c926a399e91aa3c"
+        );
+        let roots: Vec<(&str, &str)> = v
+            .vectors
+            .iter()
+            .map(|v| (v.signing_type.as_str(), v.signing_root.as_str()))
+            .collect();
+        assert_eq!(roots, EXPECTED_SIGNING_ROOTS);
+
+        // A BLS signature is the only one verifying under its key and root, so verifying each
+        // against the pinned pubkey pins the signatures as well
+        let pk = PublicKey::from_hex(&v.pubkey[2..]).unwrap();
+        for v in v.vectors.iter() {
+            let root = hex::decode(&v.signing_root[2..]).unwrap();
+            let sig_bytes: [u8; 96] = hex::decode(&v.signature[2..]).unwrap().try_into().unwrap();
+            let sig = Signature::from_bytes(sig_bytes).unwrap();
+            assert!(pk.verify(&sig, root), "{} does not verify", v.signing_type);
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use crate::constants::SLASHING_PROTECTION_DIR;
+use crate::eth2::slash_protection::{self, SlashingProtectionData};
+use crate::io::key_management::{self, KeyMetadata, KeyType};
+use crate::strip_0x_prefix;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
+
+use std::path::PathBuf;
+
+/// Leading bytes of an encrypted key backup
+const BACKUP_MAGIC: &[u8; 4] = b"SSKB";
+const BACKUP_FORMAT_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+// magic | version | argon2id m_cost, t_cost, p_cost as u32 LE | salt | nonce
+const HEADER_LEN: usize = 4 + 1 + 12 + SALT_LEN + NONCE_LEN;
+
+// Argon2id cost of new backups, the OWASP minimum of 19 MiB and 2 passes
+const ARGON2_M_COST: u32 = 19 * 1024;
+const ARGON2_T_COST: u32 = 2;
+const ARGON2_P_COST: u32 = 1;
+
+/// A saved key with everything needed to sign with it again after a restore
+#[derive(Serialize, Deserialize)]
+struct BackedUpKey {
+    key_type: KeyType,
+    pk_hex: String,
+    sk_hex: String,
+    metadata: Option<KeyMetadata>,
+    /// BLS keys only
+    slashing_protection: Option<SlashingProtectionData>,
+}
+
+impl Drop for BackedUpKey {
+    fn drop(&mut self) {
+        self.sk_hex.zeroize();
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct KeyBackup {
+    keys: Vec<BackedUpKey>,
+}
+
+/// Bundles every saved BLS and ETH key, with its metadata and slashing protection state, into
+/// an archive encrypted under `password`. Restore it with `import_all_keys_encrypted`.
+pub fn export_all_keys_encrypted(password: &str) -> Result<Vec<u8>> {
+    let keys: Vec<(KeyType, String)> = key_management::list_keys(None, None)?
+        .into_iter()
+        .map(|k| (k.key_type, k.pk_hex))
+        .collect();
+    export_keys_encrypted(&keys, password)
+}
+
+/// Like `export_all_keys_encrypted` but only bundles `keys`
+pub fn export_keys_encrypted(keys: &[(KeyType, String)], password: &str) -> Result<Vec<u8>> {
+    let mut backup = KeyBackup::default();
+    for (key_type, pk_hex) in keys {
+        let pk_hex: String = strip_0x_prefix!(pk_hex);
+        let entry = match key_type {
+            KeyType::Bls => {
+                let sk = Zeroizing::new(key_management::read_bls_key(&pk_hex)?);
+                BackedUpKey {
+                    key_type: KeyType::Bls,
+                    sk_hex: hex::encode(&*sk),
+                    metadata: key_management::read_bls_key_metadata(&pk_hex)?,
+                    slashing_protection: read_slashing_protection(&pk_hex)?,
+                    pk_hex,
+                }
+            }
+            KeyType::Eth => {
+                let sk = Zeroizing::new(key_management::read_eth_key(&pk_hex)?);
+                BackedUpKey {
+                    key_type: KeyType::Eth,
+                    sk_hex: hex::encode(&*sk),
+                    metadata: key_management::read_eth_key_metadata(&pk_hex)?,
+                    slashing_protection: None,
+                    pk_hex,
+                }
+            }
+        };
+        backup.keys.push(entry);
+    }
+    let plaintext = Zeroizing::new(serde_json::to_vec(&backup)?);
+    encrypt_backup(&plaintext, password)
+}
+
+/// Restores the keys of an archive made by `export_all_keys_encrypted`, returning how many were
+/// restored. Keys already saved are kept, and must match the archived secret. The restored
+/// slashing protection state is max-merged with any saved one, so protection is never weakened.
+pub fn import_all_keys_encrypted(archive: &[u8], password: &str) -> Result<usize> {
+    let plaintext = decrypt_backup(archive, password)?;
+    let backup: KeyBackup =
+        serde_json::from_slice(&plaintext).with_context(|| "Malformed key backup")?;
+
+    for key in backup.keys.iter() {
+        let pk_hex = &key.pk_hex;
+        match key.key_type {
+            KeyType::Bls => {
+                if key_management::bls_key_exists(pk_hex) {
+                    check_saved_secret(&key_management::read_bls_key(pk_hex)?, key)?;
+                } else {
+                    key_management::write_bls_key(pk_hex, &key.sk_hex)?;
+                    if let Some(metadata) = &key.metadata {
+                        key_management::write_bls_key_metadata(pk_hex, metadata)?;
+                    }
+                }
+                if let Some(restored) = &key.slashing_protection {
+                    slash_protection::merge_slashing_db(pk_hex, restored)?;
+                }
+            }
+            KeyType::Eth => {
+                if key_management::eth_key_exists(pk_hex) {
+                    check_saved_secret(&key_management::read_eth_key(pk_hex)?, key)?;
+                } else {
+                    key_management::write_eth_key(pk_hex, &key.sk_hex)?;
+                    if let Some(metadata) = &key.metadata {
+                        key_management::write_eth_key_metadata(pk_hex, metadata)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(backup.keys.len())
+}
+
+// Errors if the key saved for `key.pk_hex` is not the archived one
+fn check_saved_secret(saved_sk: &[u8], key: &BackedUpKey) -> Result<()> {
+    let saved_sk = Zeroizing::new(saved_sk.to_vec());
+    let archived_sk = Zeroizing::new(hex::decode(&key.sk_hex)?);
+    if *saved_sk != *archived_sk {
+        bail!(
+            "A different secret key is already saved for pk {}",
+            key.pk_hex
+        )
+    }
+    Ok(())
+}
+
+// Reads the slashing protection DB of `pk_hex`, None if none was saved
+fn read_slashing_protection(pk_hex: &str) -> Result<Option<SlashingProtectionData>> {
+    let file_path: PathBuf = [SLASHING_PROTECTION_DIR, pk_hex].iter().collect();
+    if !file_path.exists() {
+        return Ok(None);
+    }
+    SlashingProtectionData::read(pk_hex).map(Some)
+}
+
+fn derive_backup_cipher(
+    password: &str,
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> Result<Aes256Gcm> {
+    let params = Params::new(m_cost, t_cost, p_cost, Some(32))
+        .map_err(|e| anyhow!("Invalid argon2id parameters: {e}"))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut key[..])
+        .map_err(|e| anyhow!("Failed to derive backup key: {e}"))?;
+    Aes256Gcm::new_from_slice(&key[..]).map_err(|e| anyhow!("Bad backup key length: {:?}", e))
+}
+
+// Encrypts `plaintext` with AES-256-GCM under an argon2id key, authenticating the header
+fn encrypt_backup(plaintext: &[u8], password: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let mut archive = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+    archive.extend_from_slice(BACKUP_MAGIC);
+    archive.push(BACKUP_FORMAT_VERSION);
+    for cost in [ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST] {
+        archive.extend_from_slice(&cost.to_le_bytes());
+    }
+    archive.extend_from_slice(&salt);
+    archive.extend_from_slice(&nonce);
+
+    let cipher =
+        derive_backup_cipher(password, &salt, ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST)?;
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: &archive,
+            },
+        )
+        .map_err(|_| anyhow!("Failed to encrypt key backup"))?;
+    archive.extend_from_slice(&ciphertext);
+    Ok(archive)
+}
+
+fn decrypt_backup(archive: &[u8], password: &str) -> Result<Zeroizing<Vec<u8>>> {
+    if archive.len() < HEADER_LEN || &archive[..4] != BACKUP_MAGIC {
+        bail!("Not a key backup archive")
+    }
+    if archive[4] != BACKUP_FORMAT_VERSION {
+        bail!("Unsupported key backup version {}", archive[4])
+    }
+    // The costs are only authenticated once the key is derived, so an archive could name any.
+    // Every archive of this format version is written with the same costs, so pin them.
+    let cost = |i: usize| u32::from_le_bytes(archive[5 + 4 * i..9 + 4 * i].try_into().unwrap());
+    if [cost(0), cost(1), cost(2)] != [ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST] {
+        bail!(
+            "Unexpected key backup argon2id costs m = {}, t = {}, p = {}",
+            cost(0),
+            cost(1),
+            cost(2)
+        )
+    }
+    let (header, ciphertext) = archive.split_at(HEADER_LEN);
+    let salt = &header[17..17 + SALT_LEN];
+    let nonce = &header[17 + SALT_LEN..];
+
+    let cipher = derive_backup_cipher(password, salt, ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST)?;
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| anyhow!("Failed to decrypt key backup, wrong password or corrupt archive"))?;
+    Ok(Zeroizing::new(plaintext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{bls_keys, eth_keys};
+    use crate::eth2::slash_protection::{SignedAttestationEpochs, SignedBlockSlot};
+
+    #[test]
+    fn test_backup_round_trip_keeps_keys_and_slashing_marks() {
+        let sk_set = bls_keys::new_bls_key(0);
+        let bls_pk_hex = sk_set.public_keys().public_key().to_hex();
+        bls_keys::save_bls_key(&sk_set).unwrap();
+        let eth_pk = eth_keys::eth_key_gen().unwrap();
+        let eth_pk_hex = eth_keys::eth_pk_to_hex(&eth_pk);
+
+        let mut db = SlashingProtectionData::from_pk_hex(&bls_pk_hex).unwrap();
+        db.new_block(
+            SignedBlockSlot {
+                slot: 100,
+                signing_root: None,
+            },
+            false,
+        )
+        .unwrap();
+        db.new_attestation(
+            SignedAttestationEpochs {
+                source_epoch: 4,
+                target_epoch: 5,
+                signing_root: None,
+            },
+            false,
+        )
+        .unwrap();
+        db.write().unwrap();
+
+        let bls_sk = key_management::read_bls_key(&bls_pk_hex).unwrap();
+        let eth_sk = key_management::read_eth_key(&eth_pk_hex).unwrap();
+        let keys = [
+            (KeyType::Bls, bls_pk_hex.clone()),
+            (KeyType::Eth, eth_pk_hex.clone()),
+        ];
+        let archive = export_keys_encrypted(&keys, "hunter2").unwrap();
+        assert!(import_all_keys_encrypted(&archive, "wrong password").is_err());
+        // Archives naming other argon2id costs are refused before deriving a key
+        let mut costly = archive.clone();
+        costly[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = import_all_keys_encrypted(&costly, "hunter2").unwrap_err();
+        assert!(err.to_string().contains("argon2id costs"));
+
+        // Lose the keys, and replace the slashing DB with one that is ahead on the target epoch
+        // but behind on the slot
+        key_management::delete_bls_key(&bls_pk_hex).unwrap();
+        key_management::delete_eth_key(&eth_pk_hex).unwrap();
+        let mut db = SlashingProtectionData::from_pk_hex(&bls_pk_hex).unwrap();
+        db.new_block(
+            SignedBlockSlot {
+                slot: 10,
+                signing_root: None,
+            },
+            false,
+        )
+        .unwrap();
+        db.new_attestation(
+            SignedAttestationEpochs {
+                source_epoch: 3,
+                target_epoch: 9,
+                signing_root: None,
+            },
+            false,
+        )
+        .unwrap();
+        db.write().unwrap();
+
+        assert_eq!(import_all_keys_encrypted(&archive, "hunter2").unwrap(), 2);
+        assert_eq!(key_management::read_bls_key(&bls_pk_hex).unwrap(), bls_sk);
+        assert_eq!(key_management::read_eth_key(&eth_pk_hex).unwrap(), eth_sk);
+        let watermarks = SlashingProtectionData::read(&bls_pk_hex)
+            .unwrap()
+            .watermarks();
+        assert_eq!(watermarks.highest_signed_slot, Some(100));
+        assert_eq!(watermarks.highest_source_epoch, Some(4));
+        assert_eq!(watermarks.highest_target_epoch, Some(9));
+
+        // Importing again is a no-op for keys that are already saved
+        assert_eq!(import_all_keys_encrypted(&archive, "hunter2").unwrap(), 2);
+
+        key_management::delete_bls_key(&bls_pk_hex).unwrap();
+        key_management::delete_eth_key(&eth_pk_hex).unwrap();
+    }
+}
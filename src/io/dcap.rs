@@ -0,0 +1,1018 @@
+use anyhow::{bail, Context, Result};
+use log::{debug, warn};
+use openssl::asn1::{Asn1Time, Asn1TimeRef};
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey, EcKeyRef};
+use openssl::ecdsa::EcdsaSig;
+use openssl::nid::Nid;
+use openssl::pkey::HasPublic;
+use openssl::stack::Stack;
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::{CrlStatus, X509Crl, X509StoreContext, X509};
+use serde::Deserialize;
+use serde_derive::Serialize;
+use serde_json::value::RawValue;
+use sha2::{Digest, Sha256};
+
+/// The quote format version verified here
+const QUOTE_VERSION: u16 = 3;
+/// Quote header attestation key type of ECDSA-256-with-P-256 keys
+const ECDSA_P256_KEY_TYPE: u16 = 2;
+/// Offset of the QE report body in a v3 ECDSA quote: header (48B) || ISV report body (384B) ||
+/// signature data length (4B) || ISV report signature (64B) || attestation key (64B)
+const QE_REPORT_OFFSET: usize = 48 + 384 + 4 + 64 + 64;
+const REPORT_BODY_BYTES: usize = 384;
+/// The header and ISV report body, the span covered by the quote signature
+pub const QUOTE_SIGNED_BYTES: usize = 48 + REPORT_BODY_BYTES;
+/// Certification data type of a PEM PCK leaf || intermediate || root chain
+const PCK_CERT_CHAIN_TYPE: u16 = 5;
+/// DER encoding of the SGX extensions OID 1.2.840.113741.1.13.1 carried by PCK certificates
+const SGX_EXTENSIONS_OID: [u8; 9] = [0x2a, 0x86, 0x48, 0x86, 0xf8, 0x4d, 0x01, 0x0d, 0x01];
+
+/// TCB status of a platform or Quoting Enclave as assigned by Intel's collateral
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcbStatus {
+    UpToDate,
+    SWHardeningNeeded,
+    ConfigurationNeeded,
+    ConfigurationAndSWHardeningNeeded,
+    OutOfDate,
+    OutOfDateConfigurationNeeded,
+    Revoked,
+}
+
+/// DCAP collateral as served by the Intel PCS or a caching service: the signed TCB info and QE
+/// identity documents plus the PEM chain (signing cert then root CA) that signed them, and the
+/// PEM CRL of the CA that issued the PCK certificate
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DcapCollateral {
+    pub tcb_info: String,
+    pub qe_identity: String,
+    pub tcb_signing_chain: String,
+    pub pck_crl: String,
+}
+
+/// The TCB of the attesting platform, as read from the SGX extensions of its PCK certificate
+#[derive(Debug, Clone, Default)]
+pub struct PlatformTcb {
+    /// Hex-encoded 6B family-model-stepping-platform-custom SKU
+    pub fmspc: String,
+    pub sgx_tcb_components: [u8; 16],
+    pub pcesvn: u16,
+}
+
+/// The fields of the Quoting Enclave's report checked against the QE identity
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QeReport {
+    pub miscselect: [u8; 4],
+    pub attributes: [u8; 16],
+    pub mrsigner: [u8; 32],
+    pub isvprodid: u16,
+    pub isvsvn: u16,
+}
+
+impl PlatformTcb {
+    /// Reads the FMSPC, TCB components and PCESVN from the SGX extensions of a DER PCK cert
+    pub fn from_pck_der(der: &[u8]) -> Result<Self> {
+        let fmspc = sgx_extension_value(der, &[0x04])?;
+        if fmspc.len() != 6 {
+            bail!("Expected a 6B FMSPC, got {}B", fmspc.len())
+        }
+        let mut platform = PlatformTcb {
+            fmspc: hex::encode(fmspc),
+            ..Default::default()
+        };
+        for (i, svn) in platform.sgx_tcb_components.iter_mut().enumerate() {
+            *svn = der_uint(sgx_extension_value(der, &[0x02, i as u8 + 1])?)?
+                .try_into()
+                .with_context(|| "TCB component SVN exceeds 255")?;
+        }
+        platform.pcesvn = der_uint(sgx_extension_value(der, &[0x02, 17])?)?;
+        Ok(platform)
+    }
+}
+
+impl QeReport {
+    /// Extracts the QE report body from a v3 ECDSA quote
+    pub fn from_quote(quote: &[u8]) -> Result<Self> {
+        let Some(body) = quote.get(QE_REPORT_OFFSET..QE_REPORT_OFFSET + REPORT_BODY_BYTES) else {
+            bail!("Quote of {}B is too short to hold a QE report", quote.len())
+        };
+        let mut report = QeReport::default();
+        report.miscselect.copy_from_slice(&body[16..20]);
+        report.attributes.copy_from_slice(&body[48..64]);
+        report.mrsigner.copy_from_slice(&body[128..160]);
+        report.isvprodid = u16::from_le_bytes(body[256..258].try_into()?);
+        report.isvsvn = u16::from_le_bytes(body[258..260].try_into()?);
+        Ok(report)
+    }
+}
+
+/// The outcome of verifying collateral, exposed so callers can enforce their own TCB policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcbVerdict {
+    pub tcb_status: TcbStatus,
+    pub qe_tcb_status: TcbStatus,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug)]
+struct TcbInfo {
+    id: String,
+    version: u32,
+    nextUpdate: String,
+    fmspc: String,
+    tcbLevels: Vec<TcbLevel>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TcbLevel {
+    tcb: Tcb,
+    #[serde(rename = "tcbStatus")]
+    tcb_status: TcbStatus,
+}
+
+#[derive(Deserialize, Debug)]
+struct Tcb {
+    sgxtcbcomponents: Vec<TcbComponent>,
+    pcesvn: u16,
+}
+
+#[derive(Deserialize, Debug)]
+struct TcbComponent {
+    svn: u8,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Debug)]
+struct QeIdentity {
+    id: String,
+    nextUpdate: String,
+    miscselect: String,
+    miscselectMask: String,
+    attributes: String,
+    attributesMask: String,
+    mrsigner: String,
+    isvprodid: u16,
+    tcbLevels: Vec<QeTcbLevel>,
+}
+
+#[derive(Deserialize, Debug)]
+struct QeTcbLevel {
+    tcb: QeTcb,
+    #[serde(rename = "tcbStatus")]
+    tcb_status: TcbStatus,
+}
+
+#[derive(Deserialize, Debug)]
+struct QeTcb {
+    isvsvn: u16,
+}
+
+// A collateral document together with the exact bytes its signature covers
+#[derive(Deserialize)]
+struct SignedDocument<'a> {
+    #[serde(alias = "tcbInfo", alias = "enclaveIdentity", borrow)]
+    body: &'a RawValue,
+    signature: String,
+}
+
+/// Verifies DCAP collateral against Intel's SGX root CA and evaluates the TCB status of the
+/// platform and its Quoting Enclave. Revoked TCBs are rejected unless explicitly allowed.
+pub struct DcapCollateralVerifier {
+    root_ca: X509,
+    allow_revoked: bool,
+}
+
+impl DcapCollateralVerifier {
+    /// `root_ca_pem` is the trusted Intel SGX Root CA certificate
+    pub fn new(root_ca_pem: &[u8]) -> Result<Self> {
+        let root_ca = X509::from_pem(root_ca_pem).with_context(|| "Bad SGX root CA pem")?;
+        Ok(DcapCollateralVerifier {
+            root_ca,
+            allow_revoked: false,
+        })
+    }
+
+    /// Returns a verdict for revoked TCBs rather than failing, leaving the policy to the caller
+    pub fn allow_revoked(mut self, allow: bool) -> Self {
+        self.allow_revoked = allow;
+        self
+    }
+
+    /// Verifies the collateral signatures, then matches `platform` against the TCB info and
+    /// `qe_report` against the QE identity
+    pub fn verify(
+        &self,
+        collateral: &DcapCollateral,
+        platform: &PlatformTcb,
+        qe_report: &QeReport,
+    ) -> Result<TcbVerdict> {
+        let signing_cert = self.verify_signing_chain(&collateral.tcb_signing_chain)?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+
+        let tcb_info: TcbInfo =
+            verify_document(&collateral.tcb_info, &signing_cert).with_context(|| "Bad TCB info")?;
+        if tcb_info.id != "SGX" || tcb_info.version != 3 {
+            bail!(
+                "Unsupported TCB info {} v{}, expected SGX v3",
+                tcb_info.id,
+                tcb_info.version
+            )
+        }
+        check_fresh("TCB info", &tcb_info.nextUpdate, now)?;
+        let tcb_status = platform_tcb_status(&tcb_info, platform)?;
+
+        let qe_identity: QeIdentity = verify_document(&collateral.qe_identity, &signing_cert)
+            .with_context(|| "Bad QE identity")?;
+        if qe_identity.id != "QE" {
+            bail!(
+                "Unexpected enclave identity {}, expected QE",
+                qe_identity.id
+            )
+        }
+        check_fresh("QE identity", &qe_identity.nextUpdate, now)?;
+        let qe_tcb_status = qe_tcb_status(&qe_identity, qe_report)?;
+
+        debug!(
+            "DCAP TCB status {:?}, QE TCB status {:?}",
+            tcb_status, qe_tcb_status
+        );
+        let verdict = TcbVerdict {
+            tcb_status,
+            qe_tcb_status,
+        };
+        if verdict.is_revoked() {
+            if !self.allow_revoked {
+                bail!("Attesting platform TCB is revoked: {:?}", verdict)
+            }
+            warn!("Accepting revoked TCB: {:?}", verdict);
+        }
+        Ok(verdict)
+    }
+
+    /// Verifies a v3 ECDSA `quote` end to end: the header, the PCK chain and CRL, the QE report
+    /// signature and its binding to the attestation key, the quote signature, then `collateral`
+    /// against the TCB recorded in the PCK certificate
+    pub fn verify_quote(&self, quote: &[u8], collateral: &DcapCollateral) -> Result<TcbVerdict> {
+        check_quote_header(quote)?;
+        let Some(sig_len) = quote.get(QUOTE_SIGNED_BYTES..QUOTE_SIGNED_BYTES + 4) else {
+            bail!("Quote of {}B is too short to hold a signature", quote.len())
+        };
+        let sig_len = u32::from_le_bytes(sig_len.try_into()?) as usize;
+        let Some(mut sig_data) =
+            quote.get(QUOTE_SIGNED_BYTES + 4..QUOTE_SIGNED_BYTES + 4 + sig_len)
+        else {
+            bail!("Quote signature data is truncated")
+        };
+        let isv_sig = take(&mut sig_data, 64)?;
+        let attest_key = take(&mut sig_data, 64)?;
+        let qe_report = take(&mut sig_data, REPORT_BODY_BYTES)?;
+        let qe_sig = take(&mut sig_data, 64)?;
+        let qe_auth_len = u16::from_le_bytes(take(&mut sig_data, 2)?.try_into()?) as usize;
+        let qe_auth = take(&mut sig_data, qe_auth_len)?;
+        let cert_type = u16::from_le_bytes(take(&mut sig_data, 2)?.try_into()?);
+        let cert_len = u32::from_le_bytes(take(&mut sig_data, 4)?.try_into()?) as usize;
+        let cert_data = take(&mut sig_data, cert_len)?;
+        if cert_type != PCK_CERT_CHAIN_TYPE {
+            bail!("Unsupported quote certification data type {cert_type}")
+        }
+
+        let (pck, pck_issuer) = self.verify_pck_chain(cert_data)?;
+        check_pck_crl(
+            &collateral.pck_crl,
+            &pck,
+            &pck_issuer,
+            &Asn1Time::days_from_now(0)?,
+        )?;
+        if !verify_raw_ecdsa(qe_report, qe_sig, &pck.public_key()?.ec_key()?)? {
+            bail!("QE report signature does not verify against the PCK certificate")
+        }
+
+        // The QE commits to the attestation key in the first half of its report data
+        let mut hasher = Sha256::new();
+        hasher.update(attest_key);
+        hasher.update(qe_auth);
+        if qe_report[320..352] != hasher.finalize()[..] {
+            bail!("QE report does not commit to the quote attestation key")
+        }
+
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+        let attest_key = EcKey::from_public_key_affine_coordinates(
+            &group,
+            &BigNum::from_slice(&attest_key[..32])?,
+            &BigNum::from_slice(&attest_key[32..])?,
+        )?;
+        if !verify_raw_ecdsa(&quote[..QUOTE_SIGNED_BYTES], isv_sig, &attest_key)? {
+            bail!("Quote signature does not verify against the attestation key")
+        }
+
+        let platform = PlatformTcb::from_pck_der(&pck.to_der()?)?;
+        self.verify(collateral, &platform, &QeReport::from_quote(quote)?)
+    }
+
+    // Checks the PCK chain ends in our root CA, returning the PCK leaf certificate and its issuer
+    fn verify_pck_chain(&self, chain_pem: &[u8]) -> Result<(X509, X509)> {
+        let end = chain_pem.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+        let x509s = X509::stack_from_pem(&chain_pem[..end])?;
+        let (Some(pck_x509), Some(root_x509)) = (x509s.first(), x509s.last()) else {
+            bail!("Expected a PCK certificate chain")
+        };
+        if x509s.len() < 2 || root_x509.to_der()? != self.root_ca.to_der()? {
+            bail!("PCK certificate chain is not rooted in the trusted SGX root CA")
+        }
+
+        let mut builder = X509StoreBuilder::new()?;
+        builder.add_cert(self.root_ca.clone())?;
+        let trust = builder.build();
+        let mut cert_chain: Stack<X509> = Stack::new()?;
+        for intermediate in x509s[1..x509s.len() - 1].iter() {
+            cert_chain.push(intermediate.clone())?;
+        }
+        let mut store = X509StoreContext::new()?;
+        match store.init(
+            trust.as_ref(),
+            pck_x509.as_ref(),
+            cert_chain.as_ref(),
+            |c| c.verify_cert(),
+        ) {
+            Ok(true) => Ok((pck_x509.clone(), x509s[1].clone())),
+            _ => bail!("Failed to verify the PCK certificate"),
+        }
+    }
+
+    // Checks the chain is the TCB signing cert issued by our root CA, returning the signing cert
+    fn verify_signing_chain(&self, chain_pem: &str) -> Result<X509> {
+        let x509s = X509::stack_from_pem(chain_pem.as_bytes())?;
+        let (Some(signing_x509), Some(root_x509)) = (x509s.first(), x509s.get(1)) else {
+            bail!("Expected the TCB signing cert followed by the root CA")
+        };
+        if root_x509.to_der()? != self.root_ca.to_der()? {
+            bail!("TCB signing chain is not rooted in the trusted SGX root CA")
+        }
+
+        let mut builder = X509StoreBuilder::new()?;
+        builder.add_cert(self.root_ca.clone())?;
+        let trust = builder.build();
+        let cert_chain: Stack<X509> = Stack::new()?;
+        let mut store = X509StoreContext::new()?;
+        match store.init(
+            trust.as_ref(),
+            signing_x509.as_ref(),
+            cert_chain.as_ref(),
+            |c| c.verify_cert(),
+        ) {
+            Ok(true) => Ok(signing_x509.clone()),
+            _ => bail!("Failed to verify the TCB signing certificate"),
+        }
+    }
+}
+
+impl TcbVerdict {
+    pub fn is_revoked(&self) -> bool {
+        self.tcb_status == TcbStatus::Revoked || self.qe_tcb_status == TcbStatus::Revoked
+    }
+}
+
+// Checks the quote header is a v3 quote signed by an ECDSA P-256 attestation key
+fn check_quote_header(quote: &[u8]) -> Result<()> {
+    let Some(header) = quote.get(..4) else {
+        bail!("Quote of {}B is too short to hold a header", quote.len())
+    };
+    let version = u16::from_le_bytes([header[0], header[1]]);
+    if version != QUOTE_VERSION {
+        bail!("Unsupported quote version {version}, expected {QUOTE_VERSION}")
+    }
+    let key_type = u16::from_le_bytes([header[2], header[3]]);
+    if key_type != ECDSA_P256_KEY_TYPE {
+        bail!("Unsupported attestation key type {key_type}, expected ECDSA P-256")
+    }
+    Ok(())
+}
+
+// Checks the PCK CRL is signed by the PCK's issuing CA, is current at `now` and does not revoke
+// the PCK certificate
+fn check_pck_crl(crl_pem: &str, pck: &X509, issuer: &X509, now: &Asn1TimeRef) -> Result<()> {
+    let crl = X509Crl::from_pem(crl_pem.as_bytes()).with_context(|| "Bad PCK CRL pem")?;
+    if !crl.verify(&issuer.public_key()?)? {
+        bail!("PCK CRL is not signed by the PCK certificate's issuer")
+    }
+    match crl.next_update() {
+        Some(next_update) if now.compare(next_update)? == std::cmp::Ordering::Less => {}
+        _ => bail!("PCK CRL is expired, fetch fresh collateral"),
+    }
+    match crl.get_by_cert(pck) {
+        CrlStatus::NotRevoked => Ok(()),
+        CrlStatus::Revoked(_) => bail!("PCK certificate is revoked"),
+        CrlStatus::RemoveFromCrl(_) => bail!("PCK certificate is on hold"),
+    }
+}
+
+// Checks the ECDSA P-256 signature (hex r || s) over the document body, then parses the body
+fn verify_document<T: serde::de::DeserializeOwned>(json: &str, signing_cert: &X509) -> Result<T> {
+    let doc: SignedDocument = serde_json::from_str(json)?;
+    let sig_bytes = hex::decode(&doc.signature)?;
+    let key = signing_cert.public_key()?.ec_key()?;
+    if !verify_raw_ecdsa(doc.body.get().as_bytes(), &sig_bytes, &key)? {
+        bail!("Collateral signature does not verify")
+    }
+    Ok(serde_json::from_str(doc.body.get())?)
+}
+
+// Checks a raw r || s ECDSA P-256 signature over sha256(msg)
+fn verify_raw_ecdsa<T: HasPublic>(msg: &[u8], sig: &[u8], key: &EcKeyRef<T>) -> Result<bool> {
+    if sig.len() != 64 {
+        bail!("Expected a 64B signature, got {}B", sig.len())
+    }
+    let sig = EcdsaSig::from_private_components(
+        BigNum::from_slice(&sig[..32])?,
+        BigNum::from_slice(&sig[32..])?,
+    )?;
+    Ok(sig.verify(&Sha256::digest(msg), key)?)
+}
+
+// Splits the first `n` bytes off `buf`
+fn take<'a>(buf: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+    if buf.len() < n {
+        bail!("Quote signature data is truncated")
+    }
+    let (head, tail) = buf.split_at(n);
+    *buf = tail;
+    Ok(head)
+}
+
+// Finds the SGX extension with OID suffix `oid_suffix` and returns the contents of its value
+fn sgx_extension_value<'a>(der: &'a [u8], oid_suffix: &[u8]) -> Result<&'a [u8]> {
+    let mut needle = vec![0x06, (SGX_EXTENSIONS_OID.len() + oid_suffix.len()) as u8];
+    needle.extend_from_slice(&SGX_EXTENSIONS_OID);
+    needle.extend_from_slice(oid_suffix);
+    let Some(start) = der.windows(needle.len()).position(|w| w == &needle[..]) else {
+        bail!("PCK certificate is missing SGX extension {:?}", oid_suffix)
+    };
+    let value = &der[start + needle.len()..];
+    let Some(&len) = value.get(1) else {
+        bail!("Truncated SGX extension {:?}", oid_suffix)
+    };
+    if len >= 0x80 || value.len() < 2 + len as usize {
+        bail!("Bad SGX extension {:?} length", oid_suffix)
+    }
+    Ok(&value[2..2 + len as usize])
+}
+
+// Decodes a small non-negative DER INTEGER
+fn der_uint(bytes: &[u8]) -> Result<u16> {
+    if bytes.is_empty() || bytes.len() > 3 {
+        bail!("Bad SGX extension integer")
+    }
+    let value = bytes.iter().fold(0_u32, |acc, b| acc << 8 | *b as u32);
+    u16::try_from(value).with_context(|| "SGX extension integer exceeds 16 bits")
+}
+
+fn check_fresh(name: &str, next_update: &str, now: u64) -> Result<()> {
+    if parse_utc_timestamp(next_update)? < now {
+        bail!("{name} expired at {next_update}, fetch fresh collateral")
+    }
+    Ok(())
+}
+
+// Parses an ISO 8601 UTC timestamp such as "2024-01-31T12:00:00Z" to unix seconds
+fn parse_utc_timestamp(ts: &str) -> Result<u64> {
+    let bad = || anyhow::anyhow!("Bad timestamp {ts}");
+    let b = ts.as_bytes();
+    if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || b[10] != b'T' || !ts.ends_with('Z') {
+        return Err(bad());
+    }
+    let num = |r: std::ops::Range<usize>| -> Result<i64> {
+        ts.get(r).and_then(|s| s.parse().ok()).ok_or_else(bad)
+    };
+    let (y, m, d) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let secs = num(11..13)? * 3600 + num(14..16)? * 60 + num(17..19)?;
+
+    // Days since the epoch of the civil date y-m-d
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    u64::try_from(days * 86400 + secs).map_err(|_| bad())
+}
+
+// The status of the first (highest) TCB level every platform component meets
+fn platform_tcb_status(tcb_info: &TcbInfo, platform: &PlatformTcb) -> Result<TcbStatus> {
+    if !tcb_info.fmspc.eq_ignore_ascii_case(&platform.fmspc) {
+        bail!(
+            "TCB info is for FMSPC {}, platform has {}",
+            tcb_info.fmspc,
+            platform.fmspc
+        )
+    }
+    for level in tcb_info.tcbLevels.iter() {
+        if level.tcb.sgxtcbcomponents.len() != platform.sgx_tcb_components.len() {
+            bail!(
+                "TCB level has {} components",
+                level.tcb.sgxtcbcomponents.len()
+            )
+        }
+        let components_met = level
+            .tcb
+            .sgxtcbcomponents
+            .iter()
+            .zip(platform.sgx_tcb_components.iter())
+            .all(|(c, svn)| *svn >= c.svn);
+        if components_met && platform.pcesvn >= level.tcb.pcesvn {
+            return Ok(level.tcb_status);
+        }
+    }
+    bail!("Platform TCB is below every level in the TCB info")
+}
+
+// Matches the QE report against the identity and returns the status of its ISV SVN
+fn qe_tcb_status(identity: &QeIdentity, report: &QeReport) -> Result<TcbStatus> {
+    let masked_eq = |value: &[u8], expected: &str, mask: &str| -> Result<bool> {
+        let (expected, mask) = (hex::decode(expected)?, hex::decode(mask)?);
+        if expected.len() != value.len() || mask.len() != value.len() {
+            bail!("Bad QE identity field length")
+        }
+        Ok(value
+            .iter()
+            .zip(expected.iter().zip(mask.iter()))
+            .all(|(v, (e, m))| v & m == e & m))
+    };
+    if !masked_eq(
+        &report.miscselect,
+        &identity.miscselect,
+        &identity.miscselectMask,
+    )? {
+        bail!("QE MISCSELECT does not match the QE identity")
+    }
+    if !masked_eq(
+        &report.attributes,
+        &identity.attributes,
+        &identity.attributesMask,
+    )? {
+        bail!("QE attributes do not match the QE identity")
+    }
+    if !identity
+        .mrsigner
+        .eq_ignore_ascii_case(&hex::encode(report.mrsigner))
+    {
+        bail!("QE MRSIGNER does not match the QE identity")
+    }
+    if identity.isvprodid != report.isvprodid {
+        bail!("QE ISVPRODID does not match the QE identity")
+    }
+    identity
+        .tcbLevels
+        .iter()
+        .find(|level| report.isvsvn >= level.tcb.isvsvn)
+        .map(|level| level.tcb_status)
+        .ok_or_else(|| anyhow::anyhow!("QE ISVSVN {} is below every level", report.isvsvn))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::{Asn1Object, Asn1OctetString};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::{PKey, Private};
+    use openssl::x509::{X509Builder, X509Extension, X509NameBuilder};
+
+    const MRSIGNER: [u8; 32] = [0x8c; 32];
+
+    fn cert(cn: &str, key: &PKey<Private>, issuer: Option<(&X509, &PKey<Private>)>) -> X509 {
+        cert_with_extensions(cn, key, issuer, vec![])
+    }
+
+    fn cert_with_extensions(
+        cn: &str,
+        key: &PKey<Private>,
+        issuer: Option<(&X509, &PKey<Private>)>,
+        extensions: Vec<X509Extension>,
+    ) -> X509 {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, cn).unwrap();
+        let name = name.build();
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        let serial = BigNum::from_u32(rand::random::<u32>()).unwrap();
+        builder
+            .set_serial_number(&serial.to_asn1_integer().unwrap())
+            .unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(30).unwrap())
+            .unwrap();
+        for extension in extensions {
+            builder.append_extension(extension).unwrap();
+        }
+        match issuer {
+            Some((issuer_cert, issuer_key)) => {
+                builder.set_issuer_name(issuer_cert.subject_name()).unwrap();
+                builder.sign(issuer_key, MessageDigest::sha256()).unwrap();
+            }
+            None => {
+                let ca = openssl::x509::extension::BasicConstraints::new()
+                    .critical()
+                    .ca()
+                    .build()
+                    .unwrap();
+                builder.append_extension(ca).unwrap();
+                builder.set_issuer_name(&name).unwrap();
+                builder.sign(key, MessageDigest::sha256()).unwrap();
+            }
+        }
+        builder.build()
+    }
+
+    fn p256_key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn sign_document(field: &str, body: &str, key: &PKey<Private>) -> String {
+        let sig = EcdsaSig::sign(&Sha256::digest(body.as_bytes()), &key.ec_key().unwrap()).unwrap();
+        let mut sig_bytes = sig.r().to_vec_padded(32).unwrap();
+        sig_bytes.extend(sig.s().to_vec_padded(32).unwrap());
+        format!(
+            r#"{{"{field}":{body},"signature":"{}"}}"#,
+            hex::encode(sig_bytes)
+        )
+    }
+
+    fn tcb_level(svn: u8, pcesvn: u16, status: &str) -> String {
+        let components = vec![format!(r#"{{"svn":{svn}}}"#); 16].join(",");
+        format!(
+            r#"{{"tcb":{{"sgxtcbcomponents":[{components}],"pcesvn":{pcesvn}}},"tcbDate":"2023-08-09T00:00:00Z","tcbStatus":"{status}"}}"#
+        )
+    }
+
+    // Collateral whose levels are UpToDate at svn 5, OutOfDate at svn 2 and Revoked below
+    fn sample_collateral(root: &X509, root_key: &PKey<Private>) -> DcapCollateral {
+        let signing_key = p256_key();
+        let signing_cert = cert(
+            "Intel SGX TCB Signing",
+            &signing_key,
+            Some((root, root_key)),
+        );
+        let tcb_info = format!(
+            r#"{{"id":"SGX","version":3,"issueDate":"2024-01-01T00:00:00Z","nextUpdate":"2999-01-01T00:00:00Z","fmspc":"00906ED50000","pceId":"0000","tcbType":0,"tcbEvaluationDataNumber":16,"tcbLevels":[{},{},{}]}}"#,
+            tcb_level(5, 11, "UpToDate"),
+            tcb_level(2, 5, "OutOfDate"),
+            tcb_level(0, 0, "Revoked"),
+        );
+        let qe_identity = format!(
+            r#"{{"id":"QE","version":2,"issueDate":"2024-01-01T00:00:00Z","nextUpdate":"2999-01-01T00:00:00Z","tcbEvaluationDataNumber":16,"miscselect":"00000000","miscselectMask":"FFFFFFFF","attributes":"11000000000000000000000000000000","attributesMask":"FBFFFFFFFFFFFFFF0000000000000000","mrsigner":"{}","isvprodid":1,"tcbLevels":[{{"tcb":{{"isvsvn":8}},"tcbDate":"2023-08-09T00:00:00Z","tcbStatus":"UpToDate"}}]}}"#,
+            hex::encode(MRSIGNER)
+        );
+        DcapCollateral {
+            tcb_info: sign_document("tcbInfo", &tcb_info, &signing_key),
+            qe_identity: sign_document("enclaveIdentity", &qe_identity, &signing_key),
+            tcb_signing_chain: format!(
+                "{}{}",
+                String::from_utf8(signing_cert.to_pem().unwrap()).unwrap(),
+                String::from_utf8(root.to_pem().unwrap()).unwrap()
+            ),
+            pck_crl: pck_crl(root, root_key, &[]),
+        }
+    }
+
+    // DER tag || length || contents
+    fn der(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        match contents.len() {
+            len @ 0..=0x7f => out.push(len as u8),
+            len @ 0x80..=0xff => out.extend_from_slice(&[0x81, len as u8]),
+            len => out.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]),
+        }
+        out.extend_from_slice(contents);
+        out
+    }
+
+    // A v2 CRL issued and signed by `issuer`, revoking the certificates with serials `revoked`
+    fn pck_crl(issuer: &X509, issuer_key: &PKey<Private>, revoked: &[&X509]) -> String {
+        // ecdsa-with-SHA256
+        let sig_alg = der(
+            0x30,
+            &der(0x06, &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02]),
+        );
+        let mut entries = Vec::new();
+        for cert in revoked {
+            let mut serial = cert.serial_number().to_bn().unwrap().to_vec();
+            if serial.is_empty() || serial[0] & 0x80 != 0 {
+                serial.insert(0, 0);
+            }
+            let mut entry = der(0x02, &serial);
+            entry.extend(der(0x17, b"200101000000Z"));
+            entries.extend(der(0x30, &entry));
+        }
+        let mut tbs = der(0x02, &[1]);
+        tbs.extend_from_slice(&sig_alg);
+        tbs.extend(issuer.subject_name().to_der().unwrap());
+        tbs.extend(der(0x17, b"200101000000Z"));
+        tbs.extend(der(0x18, b"29990101000000Z"));
+        if !entries.is_empty() {
+            tbs.extend(der(0x30, &entries));
+        }
+        let tbs = der(0x30, &tbs);
+
+        let sig = EcdsaSig::sign(&Sha256::digest(&tbs), &issuer_key.ec_key().unwrap()).unwrap();
+        let mut sig_bits = vec![0];
+        sig_bits.extend(sig.to_der().unwrap());
+        let mut crl = tbs;
+        crl.extend_from_slice(&sig_alg);
+        crl.extend(der(0x03, &sig_bits));
+        let crl = X509Crl::from_der(&der(0x30, &crl)).unwrap();
+        String::from_utf8(crl.to_pem().unwrap()).unwrap()
+    }
+
+    // The SGX extensions of a PCK certificate with every TCB component at `svn`
+    fn sgx_extensions_der(svn: u8, pcesvn: u8) -> Vec<u8> {
+        let oid = |suffix: &[u8]| {
+            let mut der = vec![0x30, 0x00, 0x06, 9 + suffix.len() as u8];
+            der.extend_from_slice(&SGX_EXTENSIONS_OID);
+            der.extend_from_slice(suffix);
+            der
+        };
+        let mut der = oid(&[0x04]);
+        der.extend_from_slice(&[0x04, 0x06, 0x00, 0x90, 0x6e, 0xd5, 0x00, 0x00]);
+        for i in 1..=16_u8 {
+            der.extend(oid(&[0x02, i]));
+            der.extend_from_slice(&[0x02, 0x01, svn]);
+        }
+        der.extend(oid(&[0x02, 17]));
+        der.extend_from_slice(&[0x02, 0x01, pcesvn]);
+        der
+    }
+
+    // A PCK certificate issued by the root CA whose SGX extensions carry platform(svn)
+    fn pck_cert(key: &PKey<Private>, root: &X509, root_key: &PKey<Private>, svn: u8) -> X509 {
+        let extension = X509Extension::new_from_der(
+            &Asn1Object::from_str("1.2.840.113741.1.13.1").unwrap(),
+            false,
+            &Asn1OctetString::new_from_bytes(&sgx_extensions_der(svn, 11)).unwrap(),
+        )
+        .unwrap();
+        cert_with_extensions(
+            "Intel SGX PCK Certificate",
+            key,
+            Some((root, root_key)),
+            vec![extension],
+        )
+    }
+
+    fn raw_ecdsa_sign(msg: &[u8], key: &PKey<Private>) -> Vec<u8> {
+        let sig = EcdsaSig::sign(&Sha256::digest(msg), &key.ec_key().unwrap()).unwrap();
+        let mut sig_bytes = sig.r().to_vec_padded(32).unwrap();
+        sig_bytes.extend(sig.s().to_vec_padded(32).unwrap());
+        sig_bytes
+    }
+
+    // A v3 ECDSA quote whose QE report, signed by `pck_key`, commits to `bound_key` (by default
+    // the attestation key actually signing the quote) and whose certification data is `chain`
+    fn sample_quote(
+        pck_key: &PKey<Private>,
+        chain: &[&X509],
+        bound_key: Option<[u8; 64]>,
+    ) -> Vec<u8> {
+        let attest_key = p256_key();
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let (mut x, mut y) = (BigNum::new().unwrap(), BigNum::new().unwrap());
+        let mut ctx = openssl::bn::BigNumContext::new().unwrap();
+        attest_key
+            .ec_key()
+            .unwrap()
+            .public_key()
+            .affine_coordinates_gfp(&group, &mut x, &mut y, &mut ctx)
+            .unwrap();
+        let mut attest_pk = x.to_vec_padded(32).unwrap();
+        attest_pk.extend(y.to_vec_padded(32).unwrap());
+        let qe_auth = b"qe auth data".to_vec();
+
+        let report = qe_report();
+        let mut qe_body = vec![0_u8; REPORT_BODY_BYTES];
+        qe_body[16..20].copy_from_slice(&report.miscselect);
+        qe_body[48..64].copy_from_slice(&report.attributes);
+        qe_body[128..160].copy_from_slice(&report.mrsigner);
+        qe_body[256..258].copy_from_slice(&report.isvprodid.to_le_bytes());
+        qe_body[258..260].copy_from_slice(&report.isvsvn.to_le_bytes());
+        let mut hasher = Sha256::new();
+        hasher.update(bound_key.map_or(attest_pk.clone(), |k| k.to_vec()));
+        hasher.update(&qe_auth);
+        qe_body[320..352].copy_from_slice(&hasher.finalize());
+
+        let mut quote = QUOTE_VERSION.to_le_bytes().to_vec();
+        quote.extend_from_slice(&ECDSA_P256_KEY_TYPE.to_le_bytes());
+        quote.resize(QUOTE_SIGNED_BYTES, 0);
+        let chain_pem: Vec<u8> = chain.iter().flat_map(|c| c.to_pem().unwrap()).collect();
+        let mut sig_data = raw_ecdsa_sign(&quote, &attest_key);
+        sig_data.extend_from_slice(&attest_pk);
+        sig_data.extend_from_slice(&qe_body);
+        sig_data.extend(raw_ecdsa_sign(&qe_body, pck_key));
+        sig_data.extend_from_slice(&(qe_auth.len() as u16).to_le_bytes());
+        sig_data.extend_from_slice(&qe_auth);
+        sig_data.extend_from_slice(&PCK_CERT_CHAIN_TYPE.to_le_bytes());
+        sig_data.extend_from_slice(&(chain_pem.len() as u32).to_le_bytes());
+        sig_data.extend_from_slice(&chain_pem);
+        quote.extend_from_slice(&(sig_data.len() as u32).to_le_bytes());
+        quote.extend_from_slice(&sig_data);
+        quote
+    }
+
+    fn platform(svn: u8) -> PlatformTcb {
+        PlatformTcb {
+            fmspc: "00906ed50000".to_string(),
+            sgx_tcb_components: [svn; 16],
+            pcesvn: 11,
+        }
+    }
+
+    fn qe_report() -> QeReport {
+        let mut attributes = [0_u8; 16];
+        attributes[0] = 0x15;
+        QeReport {
+            miscselect: [0; 4],
+            attributes,
+            mrsigner: MRSIGNER,
+            isvprodid: 1,
+            isvsvn: 8,
+        }
+    }
+
+    #[test]
+    fn test_verify_out_of_date_collateral() {
+        let root_key = p256_key();
+        let root = cert("Intel SGX Root CA", &root_key, None);
+        let collateral = sample_collateral(&root, &root_key);
+        let verifier = DcapCollateralVerifier::new(&root.to_pem().unwrap()).unwrap();
+
+        let verdict = verifier
+            .verify(&collateral, &platform(3), &qe_report())
+            .unwrap();
+        assert_eq!(verdict.tcb_status, TcbStatus::OutOfDate);
+        assert_eq!(verdict.qe_tcb_status, TcbStatus::UpToDate);
+        let verdict = verifier
+            .verify(&collateral, &platform(5), &qe_report())
+            .unwrap();
+        assert_eq!(verdict.tcb_status, TcbStatus::UpToDate);
+
+        // Revoked TCBs are rejected unless the caller opts in
+        assert!(verifier
+            .verify(&collateral, &platform(1), &qe_report())
+            .is_err());
+        let verdict = DcapCollateralVerifier::new(&root.to_pem().unwrap())
+            .unwrap()
+            .allow_revoked(true)
+            .verify(&collateral, &platform(1), &qe_report())
+            .unwrap();
+        assert!(verdict.is_revoked());
+
+        // A QE with another signer, or tampered collateral, fails
+        let mut other_qe = qe_report();
+        other_qe.mrsigner[0] ^= 1;
+        assert!(verifier
+            .verify(&collateral, &platform(5), &other_qe)
+            .is_err());
+        let tampered = DcapCollateral {
+            tcb_info: collateral.tcb_info.replace("\"OutOfDate\"", "\"UpToDate\""),
+            ..collateral.clone()
+        };
+        assert!(verifier
+            .verify(&tampered, &platform(3), &qe_report())
+            .is_err());
+
+        // Collateral signed under another root is not trusted
+        let other_key = p256_key();
+        let other_root = cert("Intel SGX Root CA", &other_key, None);
+        let untrusted = sample_collateral(&other_root, &other_key);
+        assert!(verifier
+            .verify(&untrusted, &platform(5), &qe_report())
+            .is_err());
+    }
+
+    #[test]
+    fn test_qe_report_from_quote() {
+        let mut quote = vec![0_u8; QE_REPORT_OFFSET + REPORT_BODY_BYTES + 64];
+        let body = &mut quote[QE_REPORT_OFFSET..];
+        body[48] = 0x15;
+        body[128..160].copy_from_slice(&MRSIGNER);
+        body[256..258].copy_from_slice(&1_u16.to_le_bytes());
+        body[258..260].copy_from_slice(&8_u16.to_le_bytes());
+        assert_eq!(QeReport::from_quote(&quote).unwrap(), qe_report());
+        assert!(QeReport::from_quote(&quote[..QE_REPORT_OFFSET]).is_err());
+    }
+
+    #[test]
+    fn test_verify_quote() {
+        let root_key = p256_key();
+        let root = cert("Intel SGX Root CA", &root_key, None);
+        let verifier = DcapCollateralVerifier::new(&root.to_pem().unwrap()).unwrap();
+        let pck_key = p256_key();
+        let pck = pck_cert(&pck_key, &root, &root_key, 5);
+        let collateral = sample_collateral(&root, &root_key);
+
+        // A valid quote from an up to date platform passes
+        let quote = sample_quote(&pck_key, &[&pck, &root], None);
+        let verdict = verifier.verify_quote(&quote, &collateral).unwrap();
+        assert_eq!(verdict.tcb_status, TcbStatus::UpToDate);
+        assert_eq!(verdict.qe_tcb_status, TcbStatus::UpToDate);
+
+        // The CRL revoking the PCK serial rejects it, while revoking another certificate does not
+        let other = cert(
+            "Intel SGX PCK Certificate",
+            &p256_key(),
+            Some((&root, &root_key)),
+        );
+        let revoked = DcapCollateral {
+            pck_crl: pck_crl(&root, &root_key, &[&other, &pck]),
+            ..collateral.clone()
+        };
+        let err = verifier.verify_quote(&quote, &revoked).unwrap_err();
+        assert!(format!("{:?}", err).contains("PCK certificate is revoked"));
+        let unrelated = DcapCollateral {
+            pck_crl: pck_crl(&root, &root_key, &[&other]),
+            ..collateral.clone()
+        };
+        assert!(verifier.verify_quote(&quote, &unrelated).is_ok());
+
+        // A CRL not signed by the PCK's issuer is refused
+        let forged = DcapCollateral {
+            pck_crl: pck_crl(&root, &p256_key(), &[]),
+            ..collateral.clone()
+        };
+        assert!(verifier.verify_quote(&quote, &forged).is_err());
+
+        // A QE report signature from another key is rejected
+        let mut bad_qe_sig = quote.clone();
+        bad_qe_sig[QUOTE_SIGNED_BYTES + 4 + 64 + 64 + REPORT_BODY_BYTES] ^= 1;
+        let err = verifier.verify_quote(&bad_qe_sig, &collateral).unwrap_err();
+        assert!(format!("{:?}", err).contains("QE report signature does not verify"));
+        let other_signer = sample_quote(&p256_key(), &[&pck, &root], None);
+        assert!(verifier.verify_quote(&other_signer, &collateral).is_err());
+
+        // A QE report committing to another attestation key is rejected
+        let unbound = sample_quote(&pck_key, &[&pck, &root], Some([7; 64]));
+        let err = verifier.verify_quote(&unbound, &collateral).unwrap_err();
+        assert!(format!("{:?}", err).contains("does not commit to the quote attestation key"));
+
+        // The TCB is read from the PCK certificate
+        let old_pck = pck_cert(&pck_key, &root, &root_key, 3);
+        let quote = sample_quote(&pck_key, &[&old_pck, &root], None);
+        let verdict = verifier.verify_quote(&quote, &collateral).unwrap();
+        assert_eq!(verdict.tcb_status, TcbStatus::OutOfDate);
+    }
+
+    #[test]
+    fn test_platform_tcb_from_pck_extensions() {
+        let der = sgx_extensions_der(5, 11);
+        let got = PlatformTcb::from_pck_der(&der).unwrap();
+        assert_eq!(got.fmspc, platform(5).fmspc);
+        assert_eq!(got.sgx_tcb_components, platform(5).sgx_tcb_components);
+        assert_eq!(got.pcesvn, platform(5).pcesvn);
+
+        // A certificate without the PCESVN extension is rejected
+        assert!(PlatformTcb::from_pck_der(&der[..der.len() - 16]).is_err());
+    }
+
+    #[test]
+    fn test_quote_header_checks() {
+        let header = |version: u16, key_type: u16| {
+            let mut quote = version.to_le_bytes().to_vec();
+            quote.extend_from_slice(&key_type.to_le_bytes());
+            quote
+        };
+        assert!(check_quote_header(&header(3, 2)).is_ok());
+        assert!(check_quote_header(&header(4, 2)).is_err());
+        assert!(check_quote_header(&header(3, 3)).is_err());
+        assert!(check_quote_header(&[3, 0]).is_err());
+
+        // Quotes of another format are rejected before anything else is parsed
+        let root_key = p256_key();
+        let root = cert("Intel SGX Root CA", &root_key, None);
+        let verifier = DcapCollateralVerifier::new(&root.to_pem().unwrap()).unwrap();
+        let mut quote = header(2, 2);
+        quote.resize(QUOTE_SIGNED_BYTES + 4, 0);
+        let err = verifier
+            .verify_quote(&quote, &sample_collateral(&root, &root_key))
+            .unwrap_err();
+        assert!(format!("{:?}", err).contains("Unsupported quote version 2"));
+    }
+
+    #[test]
+    fn test_parse_utc_timestamp() {
+        assert_eq!(parse_utc_timestamp("1970-01-01T00:00:00Z").unwrap(), 0);
+        assert_eq!(
+            parse_utc_timestamp("2024-02-29T12:34:56Z").unwrap(),
+            1709210096
+        );
+        assert!(parse_utc_timestamp("2024-02-29 12:34:56").is_err());
+    }
+}
@@ -1,14 +1,269 @@
-use crate::constants::{BLS_KEYS_DIR, ETH_KEYS_DIR};
+use crate::constants::{
+    BLS_GROUPS_DIR, BLS_KEYS_DIR, BLS_KEY_MACS_DIR, BLS_KEY_METADATA_DIR, ETH_KEYS_DIR,
+    ETH_KEY_MACS_DIR, ETH_KEY_METADATA_DIR, KEY_MAC_KEY_PATH, VALIDATOR_INDICES_DIR,
+};
+use crate::io::unix_now;
 use crate::strip_0x_prefix;
 use anyhow::{bail, Context, Result};
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::{Zeroize, Zeroizing};
 
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Error returned when attempting to write a key to a file that already exists
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCollision {
+    pub pk_hex: String,
+}
+
+impl std::fmt::Display for KeyCollision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "KeyCollision: a key is already saved for pk {}",
+            self.pk_hex
+        )
+    }
+}
+
+impl std::error::Error for KeyCollision {}
+
+/// The kind of secret key held by the store
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyType {
+    #[default]
+    Bls,
+    Eth,
+}
+
+/// What a BLS key may sign, fixed when the key is created
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyRole {
+    /// Signs consensus objects only, always under slashing protection
+    Validator,
+    /// Signs non-consensus messages only, such as commitments, without slashing protection
+    General,
+}
+
+/// Sidecar metadata saved alongside a key
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyMetadata {
+    #[serde(default)]
+    pub key_type: KeyType,
+    /// True once remote attestation evidence committing to the key was produced
+    #[serde(default)]
+    pub attested: bool,
+    /// The EIP-2334 path the key was derived at, if known
+    #[serde(default)]
+    pub derivation_path: Option<String>,
+    /// None for keys saved before roles existed, which sign consensus objects as validator keys
+    /// and non-consensus messages only when the slashing-bypass flag is set
+    #[serde(default)]
+    pub role: Option<KeyRole>,
+    /// Unix time in seconds the key was saved, None for keys saved before it was recorded
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    /// True for ETH keys in the ECIES provisioning key ring, see `add_provisioning_key`
+    #[serde(default)]
+    pub provisioning: bool,
+}
+
+/// A saved key as returned by `list_keys`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KeyEntry {
+    pub key_type: KeyType,
+    pub pk_hex: String,
+    pub attested: bool,
+}
+
+/// An ordered list of directories searched when reading keys, plus the single directory new keys
+/// are written to. The write directory is searched first, so a key written there is the one read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyStore {
+    read_dirs: Vec<PathBuf>,
+    write_dir: PathBuf,
+}
+
+impl KeyStore {
+    pub fn new(read_dirs: Vec<PathBuf>, write_dir: PathBuf) -> Self {
+        KeyStore {
+            read_dirs,
+            write_dir,
+        }
+    }
+
+    /// A store that reads and writes the single directory `dir`
+    pub fn single(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        KeyStore::new(vec![dir.clone()], dir)
+    }
+
+    /// A store writing to `write_dir` that also reads the comma-separated dirs in env var `var`
+    fn from_env(write_dir: &str, var: &str) -> Self {
+        let read_dirs = std::env::var(var)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+            .collect();
+        KeyStore::new(read_dirs, PathBuf::from(write_dir))
+    }
+
+    // The write dir followed by the other read dirs in search order
+    fn search_dirs(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.write_dir).chain(self.other_dirs())
+    }
+
+    // The read dirs other than the write dir
+    fn other_dirs(&self) -> impl Iterator<Item = &PathBuf> {
+        self.read_dirs.iter().filter(move |d| **d != self.write_dir)
+    }
+
+    pub fn write_dir(&self) -> &Path {
+        &self.write_dir
+    }
+
+    /// The path a key named `fname` is written to
+    pub fn write_path(&self, fname: &str) -> PathBuf {
+        self.write_dir.join(fname)
+    }
+
+    /// Returns the path of the first key named `fname` across the read dirs
+    pub fn find(&self, fname: &str) -> Option<PathBuf> {
+        self.search_dirs()
+            .map(|d| d.join(fname))
+            .find(|p| key_exists(p))
+    }
+
+    /// Returns the paths of every key named `fname` across the read dirs
+    pub fn find_all(&self, fname: &str) -> Vec<PathBuf> {
+        self.search_dirs()
+            .map(|d| d.join(fname))
+            .filter(|p| key_exists(p))
+            .collect()
+    }
+
+    /// Fails with `KeyCollision` if a key named `fname` is saved outside the write dir, which a
+    /// key written under the same name would shadow
+    pub fn check_collision(&self, fname: &str) -> Result<()> {
+        if self.other_dirs().any(|d| key_exists(&d.join(fname))) {
+            return Err(KeyCollision {
+                pk_hex: fname.to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Returns the distinct key names across every read dir. Dirs that do not exist are skipped,
+    /// but it is an error if none of them can be read.
+    pub fn list(&self) -> Result<Vec<String>> {
+        let mut seen = HashSet::new();
+        let mut keys = Vec::new();
+        let mut last_err = None;
+        let mut any_read = false;
+        for dir in self.search_dirs() {
+            match list_fnames(dir) {
+                Ok(fnames) => {
+                    any_read = true;
+                    keys.extend(fnames.into_iter().filter(|f| seen.insert(f.clone())));
+                }
+                Err(e) => last_err = Some(e),
+            }
+        }
+        match (any_read, last_err) {
+            (false, Some(e)) => Err(e),
+            _ => Ok(keys),
+        }
+    }
+
+    /// Streams the distinct key names across every read dir, reading each dir as the names are
+    /// taken. A name already saved in an earlier dir is skipped, so no set of seen names is kept.
+    /// Dirs that do not exist are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = Result<String>> + '_ {
+        let dirs: Vec<&PathBuf> = self.search_dirs().collect();
+        let earlier_dirs = dirs.clone();
+        dirs.into_iter()
+            .enumerate()
+            .filter(|(_, dir)| dir.exists())
+            .flat_map(move |(i, dir)| {
+                let earlier = earlier_dirs[..i].to_vec();
+                let fnames: Box<dyn Iterator<Item = Result<String>> + '_> = match iter_fnames(dir) {
+                    Ok(fnames) => Box::new(fnames.filter(move |fname| match fname {
+                        Ok(fname) => !earlier.iter().any(|d| key_exists(&d.join(fname))),
+                        Err(_) => true,
+                    })),
+                    Err(e) => Box::new(std::iter::once(Err(e))),
+                };
+                fnames
+            })
+    }
+}
+
+/// The store backing BLS secret keys, extra read dirs come from `SECURE_SIGNER_BLS_KEY_READ_DIRS`
+pub fn bls_key_store() -> &'static KeyStore {
+    static STORE: OnceLock<KeyStore> = OnceLock::new();
+    STORE.get_or_init(|| KeyStore::from_env(BLS_KEYS_DIR, "SECURE_SIGNER_BLS_KEY_READ_DIRS"))
+}
+
+/// The store backing ETH secret keys, extra read dirs come from `SECURE_SIGNER_ETH_KEY_READ_DIRS`
+pub fn eth_key_store() -> &'static KeyStore {
+    static STORE: OnceLock<KeyStore> = OnceLock::new();
+    STORE.get_or_init(|| KeyStore::from_env(ETH_KEYS_DIR, "SECURE_SIGNER_ETH_KEY_READ_DIRS"))
+}
+
+// Directories already created by `ensure_dir`, so `create_dir_all` is not called on every write
+static ENSURED_DIRS: Mutex<Option<HashSet<PathBuf>>> = Mutex::new(None);
+
+#[cfg(test)]
+static CREATE_DIR_CALLS: Mutex<Option<std::collections::HashMap<PathBuf, usize>>> =
+    Mutex::new(None);
+
+// Creates the directory `p` unless it was already ensured. `force` skips the cached flag.
+fn ensure_dir(p: &Path, force: bool) -> Result<()> {
+    let mut ensured = match ENSURED_DIRS.lock() {
+        Ok(ensured) => ensured,
+        Err(e) => e.into_inner(),
+    };
+    let ensured = ensured.get_or_insert_with(HashSet::new);
+    if !force && ensured.contains(p) {
+        return Ok(());
+    }
+
+    #[cfg(test)]
+    if let Ok(mut calls) = CREATE_DIR_CALLS.lock() {
+        *calls
+            .get_or_insert_with(Default::default)
+            .entry(p.to_path_buf())
+            .or_insert(0) += 1;
+    }
+
+    fs::create_dir_all(p).with_context(|| "Failed to create keys dir")?;
+    ensured.insert(p.to_path_buf());
+    Ok(())
+}
 
 // Writes the sk_hex string to the specified path
 fn write_key(file_path: PathBuf, sk_hex: &str) -> Result<()> {
     if let Some(p) = file_path.parent() {
-        fs::create_dir_all(p).with_context(|| "Failed to create keys dir")?
+        ensure_dir(p, false)?
+    };
+    if fs::write(&file_path, sk_hex).is_ok() {
+        return Ok(());
+    }
+
+    // The cached directory may have been removed since it was ensured, re-create and retry once
+    if let Some(p) = file_path.parent() {
+        ensure_dir(p, true)?
     };
     fs::write(&file_path, sk_hex).with_context(|| "failed to write sk")
 }
@@ -18,8 +273,17 @@ pub fn write_eth_key(pk_hex: &String, sk_hex: &String) -> Result<()> {
     // Sanitize inputs
     let pk_hex: &str = strip_0x_prefix!(pk_hex);
     let sk_hex: &str = strip_0x_prefix!(sk_hex);
-    let file_path: PathBuf = [ETH_KEYS_DIR, pk_hex].iter().collect();
-    write_key(file_path, sk_hex)
+    let store = eth_key_store();
+
+    // Never overwrite or shadow an existing key
+    if store.find(pk_hex).is_some() {
+        return Err(KeyCollision {
+            pk_hex: pk_hex.to_string(),
+        }
+        .into());
+    }
+    write_key(store.write_path(pk_hex), sk_hex)?;
+    write_key_mac(KeyType::Eth, pk_hex, sk_hex.as_bytes())
 }
 
 /// Writes the hex-encoded BLS secret key to a file named from `fname`
@@ -27,49 +291,426 @@ pub fn write_bls_key(pk_hex: &String, sk_hex: &String) -> Result<()> {
     // Sanitize inputs
     let pk_hex: &str = strip_0x_prefix!(pk_hex);
     let sk_hex: &str = strip_0x_prefix!(sk_hex);
-    let file_path: PathBuf = [BLS_KEYS_DIR, pk_hex].iter().collect();
-    write_key(file_path, sk_hex)
+    let store = bls_key_store();
+
+    // Never shadow a key saved in another read dir
+    store.check_collision(pk_hex)?;
+    write_key(store.write_path(pk_hex), sk_hex)?;
+    write_key_mac(KeyType::Bls, pk_hex, sk_hex.as_bytes())?;
+    write_key_metadata(
+        pk_hex,
+        &KeyMetadata {
+            key_type: KeyType::Bls,
+            created_at: Some(unix_now()?),
+            ..read_key_metadata(KeyType::Bls, pk_hex)?.unwrap_or_default()
+        },
+    )
+}
+
+/// Writes the hex-encoded BLS group public key set to a file named from `group_id`
+pub fn write_bls_group_pubkey(group_id: &String, pk_set_hex: &String) -> Result<()> {
+    // Sanitize inputs
+    let group_id: &str = strip_0x_prefix!(group_id);
+    let pk_set_hex: &str = strip_0x_prefix!(pk_set_hex);
+    let file_path: PathBuf = [BLS_GROUPS_DIR, group_id].iter().collect();
+    write_key(file_path, pk_set_hex)
+}
+
+// MAC key loaded or generated on first use of `key_mac_key`
+static KEY_MAC_KEY: OnceLock<Zeroizing<[u8; 32]>> = OnceLock::new();
+
+// Binds the sealed MAC key to its purpose, see `sealing::seal`
+const KEY_MAC_KEY_AAD: &[u8] = b"key_mac_key";
+
+// Returns the key authenticating saved key files, generating and saving it sealed if none exists
+// yet. A key saved in plaintext by an older build is sealed in place. Both writes go through a
+// synced temporary file, so a crash never leaves an empty or torn key behind.
+fn key_mac_key() -> Result<&'static Zeroizing<[u8; 32]>> {
+    if let Some(key) = KEY_MAC_KEY.get() {
+        return Ok(key);
+    }
+    let path = Path::new(KEY_MAC_KEY_PATH);
+    let read_saved = |key: &mut [u8; 32]| -> Result<()> {
+        let saved = Zeroizing::new(read_key(path.to_path_buf())?);
+        if saved.len() == key.len() {
+            key.copy_from_slice(&saved);
+            let sealed = crate::io::sealing::seal(&key[..], KEY_MAC_KEY_AAD)?;
+            return crate::io::write_atomically(path, hex::encode(sealed).as_bytes())
+                .with_context(|| "Failed to seal the saved key MAC key");
+        }
+        let unsealed = crate::io::sealing::unseal(&saved, KEY_MAC_KEY_AAD)
+            .with_context(|| "Failed to unseal the key MAC key")?;
+        if unsealed.len() != key.len() {
+            bail!("Bad key MAC key: expected {} bytes", key.len())
+        }
+        key.copy_from_slice(&unsealed);
+        Ok(())
+    };
+    let mut key = Zeroizing::new([0_u8; 32]);
+    if key_exists(path) {
+        read_saved(&mut *key)?;
+        return Ok(KEY_MAC_KEY.get_or_init(|| key));
+    }
+
+    rand::thread_rng().fill_bytes(&mut key[..]);
+    let sealed_hex = hex::encode(crate::io::sealing::seal(&key[..], KEY_MAC_KEY_AAD)?);
+    if let Some(p) = path.parent() {
+        ensure_dir(p, false)?
+    };
+    let created = crate::io::create_new_atomically(path, sealed_hex.as_bytes())
+        .with_context(|| "Failed to save key MAC key")?;
+    // Another thread or process created it first, use its key instead
+    if !created {
+        read_saved(&mut *key)?;
+    }
+    Ok(KEY_MAC_KEY.get_or_init(|| key))
+}
+
+fn mac_dir(key_type: KeyType) -> &'static str {
+    match key_type {
+        KeyType::Bls => BLS_KEY_MACS_DIR,
+        KeyType::Eth => ETH_KEY_MACS_DIR,
+    }
+}
+
+/// HMAC-SHA256 over the key file name and contents, so a file swapped in under another key's
+/// name fails verification as well as a modified one
+fn key_file_mac(fname: &str, contents: &[u8]) -> Result<[u8; 32]> {
+    let mut msg = Zeroizing::new(Vec::with_capacity(fname.len() + 1 + contents.len()));
+    msg.extend_from_slice(fname.as_bytes());
+    msg.push(0);
+    msg.extend_from_slice(contents);
+    // The HKDF extract step is exactly HMAC(salt, ikm)
+    let (prk, _) = Hkdf::<Sha256>::extract(Some(&key_mac_key()?[..]), &msg);
+    let mut mac = [0_u8; 32];
+    mac.copy_from_slice(&prk);
+    Ok(mac)
+}
+
+// Writes the MAC of the `key_type` key file `pk_hex` holding `contents` to its sidecar dir
+fn write_key_mac(key_type: KeyType, pk_hex: &str, contents: &[u8]) -> Result<()> {
+    let mac = key_file_mac(pk_hex, contents)?;
+    let file_path: PathBuf = [mac_dir(key_type), pk_hex].iter().collect();
+    write_key(file_path, &hex::encode(mac))
+}
+
+/// Refuses key files lacking a MAC, set by `SECURE_SIGNER_REQUIRE_KEY_MACS`. Off by default, as
+/// keys saved before MACs were written, or placed in the read dir, have none until first read.
+/// Once every key has been read, strict mode also catches a key file whose MAC was deleted.
+pub fn require_key_macs() -> bool {
+    static REQUIRE: OnceLock<bool> = OnceLock::new();
+    *REQUIRE.get_or_init(|| std::env::var("SECURE_SIGNER_REQUIRE_KEY_MACS").is_ok())
+}
+
+// Verifies `contents` read from the `key_type` key file `pk_hex` against its saved MAC, see
+// `verify_key_mac_with`
+fn verify_key_mac(key_type: KeyType, pk_hex: &str, contents: &[u8]) -> Result<()> {
+    verify_key_mac_with(key_type, pk_hex, contents, require_key_macs())
+}
+
+// A key without a MAC is refused if `require` is set, otherwise it is MACed as first read so
+// later changes to its file are caught
+fn verify_key_mac_with(
+    key_type: KeyType,
+    pk_hex: &str,
+    contents: &[u8],
+    require: bool,
+) -> Result<()> {
+    let file_path: PathBuf = [mac_dir(key_type), pk_hex].iter().collect();
+    if !key_exists(&file_path) {
+        if require {
+            bail!("No MAC saved for key file {pk_hex}")
+        }
+        return write_key_mac(key_type, pk_hex, contents)
+            .with_context(|| format!("Failed to save the MAC of key file {pk_hex}"));
+    }
+    let mut saved = [0_u8; 32];
+    let saved_hex = fs::read(&file_path).with_context(|| "Unable to read key MAC")?;
+    ct_hex_decode(&saved_hex, &mut saved).with_context(|| "Unable to hex-decode key MAC")?;
+    let mac = key_file_mac(pk_hex, contents)?;
+    let diff = saved
+        .iter()
+        .zip(mac.iter())
+        .fold(0, |acc, (a, b)| acc | (a ^ b));
+    if diff != 0 {
+        bail!("Key file {pk_hex} failed its integrity check")
+    }
+    Ok(())
+}
+
+fn metadata_dir(key_type: KeyType) -> &'static str {
+    match key_type {
+        KeyType::Bls => BLS_KEY_METADATA_DIR,
+        KeyType::Eth => ETH_KEY_METADATA_DIR,
+    }
+}
+
+/// Writes the sidecar metadata of the `metadata.key_type` key `pk_hex`
+fn write_key_metadata(pk_hex: &str, metadata: &KeyMetadata) -> Result<()> {
+    let pk_hex: &str = strip_0x_prefix!(pk_hex);
+    let mut metadata = metadata.clone();
+    if metadata.created_at.is_none() {
+        // Rewriting the metadata keeps when the key was saved
+        metadata.created_at =
+            read_key_metadata(metadata.key_type, pk_hex)?.and_then(|m| m.created_at);
+    }
+    let file_path: PathBuf = [metadata_dir(metadata.key_type), pk_hex].iter().collect();
+    let json = serde_json::to_string(&metadata)?;
+    write_key(file_path, &json)
+}
+
+/// Writes the sidecar metadata of the BLS key `pk_hex`
+pub fn write_bls_key_metadata(pk_hex: &str, metadata: &KeyMetadata) -> Result<()> {
+    write_key_metadata(
+        pk_hex,
+        &KeyMetadata {
+            key_type: KeyType::Bls,
+            ..metadata.clone()
+        },
+    )
+}
+
+/// Writes the sidecar metadata of the ETH key `pk_hex`
+pub fn write_eth_key_metadata(pk_hex: &str, metadata: &KeyMetadata) -> Result<()> {
+    write_key_metadata(
+        pk_hex,
+        &KeyMetadata {
+            key_type: KeyType::Eth,
+            ..metadata.clone()
+        },
+    )
+}
+
+/// Records that remote attestation evidence was produced for the `key_type` key `pk_hex`
+pub fn mark_key_attested(key_type: KeyType, pk_hex: &str) -> Result<()> {
+    let metadata = read_key_metadata(key_type, pk_hex)?.unwrap_or_default();
+    write_key_metadata(
+        pk_hex,
+        &KeyMetadata {
+            key_type,
+            attested: true,
+            ..metadata
+        },
+    )
+}
+
+/// Error returned when mapping a validator index already mapped to another key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidatorIndexTaken {
+    pub validator_index: u64,
+    pub pk_hex: String,
+}
+
+impl std::fmt::Display for ValidatorIndexTaken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Validator index {} is already mapped to pk {}",
+            self.validator_index, self.pk_hex
+        )
+    }
+}
+
+impl std::error::Error for ValidatorIndexTaken {}
+
+impl ValidatorIndexTaken {
+    pub fn is_cause_of(e: &anyhow::Error) -> bool {
+        e.chain().any(|c| c.is::<ValidatorIndexTaken>())
+    }
+}
+
+/// Maps the beacon chain `validator_index` to the BLS public key `pk_hex`. An index already mapped
+/// to another key is only remapped if `overwrite`, failing with `ValidatorIndexTaken` otherwise.
+pub fn write_validator_index(validator_index: u64, pk_hex: &str, overwrite: bool) -> Result<()> {
+    let pk_hex: &str = strip_0x_prefix!(pk_hex);
+    let file_path: PathBuf = [VALIDATOR_INDICES_DIR, &validator_index.to_string()]
+        .iter()
+        .collect();
+    if overwrite {
+        return write_key(file_path, pk_hex);
+    }
+    if let Some(p) = file_path.parent() {
+        ensure_dir(p, false)?
+    };
+    // Created exclusively, so two concurrent mappings of one index cannot both succeed
+    match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&file_path)
+    {
+        Ok(mut f) => std::io::Write::write_all(&mut f, pk_hex.as_bytes())
+            .with_context(|| "Failed to write validator index"),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            match read_validator_index(validator_index)? {
+                Some(mapped) if mapped.eq_ignore_ascii_case(pk_hex) => Ok(()),
+                mapped => Err(ValidatorIndexTaken {
+                    validator_index,
+                    pk_hex: mapped.unwrap_or_default(),
+                }
+                .into()),
+            }
+        }
+        Err(e) => Err(e).with_context(|| "Failed to write validator index"),
+    }
 }
 
 /// Writes the BLS secret key to a keystore file
 pub fn write_bls_keystore(pk_hex: &String, sk: &[u8], password: &String) -> Result<String> {
     // Create the keys dir if it does not exist
-    fs::create_dir_all(BLS_KEYS_DIR).with_context(|| "Failed to create keys dir")?;
+    let store = bls_key_store();
+    fs::create_dir_all(store.write_dir()).with_context(|| "Failed to create keys dir")?;
 
     // Sanitize inputs
     let pk_hex: &str = strip_0x_prefix!(pk_hex);
     let mut rng = rand::thread_rng();
 
     // Create encrypted keystore
-    let uuid = eth_keystore::encrypt_key(BLS_KEYS_DIR, &mut rng, sk, password, Some(pk_hex))?;
+    let uuid = eth_keystore::encrypt_key(store.write_dir(), &mut rng, sk, password, Some(pk_hex))?;
     Ok(uuid)
 }
 
 /// Reads hex-encoded secret key from the specified path and returns the hex-decoded bytes
 fn read_key(file_path: PathBuf) -> Result<Vec<u8>> {
-    let sk_rec_bytes = fs::read(&file_path).with_context(|| "Unable to read secret key")?;
-    hex::decode(sk_rec_bytes).with_context(|| "Unable to hex-decode secret key")
+    let sk_rec_bytes =
+        Zeroizing::new(fs::read(&file_path).with_context(|| "Unable to read secret key")?);
+    decode_key(&sk_rec_bytes)
+}
+
+/// Like `read_key` but first verifies the file against the MAC saved for the `key_type` key
+/// `pk_hex`. The bytes verified are the bytes decoded, so the file cannot change in between.
+fn read_key_verified(file_path: PathBuf, key_type: KeyType, pk_hex: &str) -> Result<Vec<u8>> {
+    let sk_rec_bytes =
+        Zeroizing::new(fs::read(&file_path).with_context(|| "Unable to read secret key")?);
+    verify_key_mac(key_type, pk_hex, &sk_rec_bytes)?;
+    decode_key(&sk_rec_bytes)
+}
+
+// Hex-decodes the secret key file contents `sk_rec_bytes`
+fn decode_key(sk_rec_bytes: &[u8]) -> Result<Vec<u8>> {
+    if sk_rec_bytes.len() % 2 != 0 {
+        bail!("Unable to hex-decode secret key: odd length")
+    }
+    let mut sk_bytes = vec![0_u8; sk_rec_bytes.len() / 2];
+    if let Err(e) = ct_hex_decode(sk_rec_bytes, &mut sk_bytes) {
+        sk_bytes.zeroize();
+        return Err(e).with_context(|| "Unable to hex-decode secret key");
+    }
+    Ok(sk_bytes)
+}
+
+// Maps a hex digit to its value, or to 0xffff if `c` is not a hex digit, without branching on `c`
+fn ct_hex_nibble(c: u8) -> u16 {
+    let c = c as i16;
+    let mut v: i16 = -1;
+    // '0'..='9'
+    v += (((0x2f - c) & (c - 0x3a)) >> 8) & (c - 0x2f);
+    // 'A'..='F'
+    v += (((0x40 - c) & (c - 0x47)) >> 8) & (c - 0x36);
+    // 'a'..='f'
+    v += (((0x60 - c) & (c - 0x67)) >> 8) & (c - 0x56);
+    v as u16
+}
+
+/// Constant-time hex decode of secret material into `out`, which must be exactly half the length
+/// of `hex`. Every byte is decoded and errors are only checked once the whole input has been
+/// processed, so invalid input takes the same path as valid input.
+fn ct_hex_decode(hex: &[u8], out: &mut [u8]) -> Result<()> {
+    if hex.len() != 2 * out.len() {
+        bail!("Expected {} hex chars, got {}", 2 * out.len(), hex.len())
+    }
+    let mut err: u16 = 0;
+    for (pair, byte) in hex.chunks_exact(2).zip(out.iter_mut()) {
+        let decoded = (ct_hex_nibble(pair[0]) << 4) | ct_hex_nibble(pair[1]);
+        err |= decoded >> 8;
+        *byte = decoded as u8;
+    }
+    if err != 0 {
+        bail!("Invalid hex character")
+    }
+    Ok(())
 }
 
 /// Reads hex-encoded ETH secret key from a file named from `pk_hex` and returns the bytes
 pub fn read_eth_key(pk_hex: &str) -> Result<Vec<u8>> {
     let pk_hex: &str = strip_0x_prefix!(pk_hex);
-    let file_path: PathBuf = [ETH_KEYS_DIR, pk_hex].iter().collect();
-    read_key(file_path)
+    let store = eth_key_store();
+    read_key_verified(
+        store
+            .find(pk_hex)
+            .unwrap_or_else(|| store.write_path(pk_hex)),
+        KeyType::Eth,
+        pk_hex,
+    )
 }
 
 /// Reads hex-encoded BLS secret key from a file named from `pk_hex` and returns the bytes
 pub fn read_bls_key(pk_hex: &str) -> Result<Vec<u8>> {
     let pk_hex: &str = strip_0x_prefix!(pk_hex);
-    let file_path: PathBuf = [BLS_KEYS_DIR, pk_hex].iter().collect();
+    let store = bls_key_store();
+    read_key_verified(
+        store
+            .find(pk_hex)
+            .unwrap_or_else(|| store.write_path(pk_hex)),
+        KeyType::Bls,
+        pk_hex,
+    )
+}
+
+/// Reads hex-encoded BLS group public key set from a file named from `group_id` and returns the bytes
+pub fn read_bls_group_pubkey(group_id: &str) -> Result<Vec<u8>> {
+    let group_id: &str = strip_0x_prefix!(group_id);
+    let file_path: PathBuf = [BLS_GROUPS_DIR, group_id].iter().collect();
     read_key(file_path)
 }
 
+/// Reads the sidecar metadata of the `key_type` key `pk_hex`, returning None if none was saved
+fn read_key_metadata(key_type: KeyType, pk_hex: &str) -> Result<Option<KeyMetadata>> {
+    let pk_hex: &str = strip_0x_prefix!(pk_hex);
+    let file_path: PathBuf = [metadata_dir(key_type), pk_hex].iter().collect();
+    if !key_exists(&file_path) {
+        return Ok(None);
+    }
+    let json = fs::read(&file_path).with_context(|| "Unable to read key metadata")?;
+    let metadata = serde_json::from_slice(&json).with_context(|| "Unable to parse key metadata")?;
+    Ok(Some(metadata))
+}
+
+/// Reads the sidecar metadata of the BLS key `pk_hex`, returning None if none was saved
+pub fn read_bls_key_metadata(pk_hex: &str) -> Result<Option<KeyMetadata>> {
+    read_key_metadata(KeyType::Bls, pk_hex)
+}
+
+/// Reads the sidecar metadata of the ETH key `pk_hex`, returning None if none was saved
+pub fn read_eth_key_metadata(pk_hex: &str) -> Result<Option<KeyMetadata>> {
+    read_key_metadata(KeyType::Eth, pk_hex)
+}
+
+/// Returns the role the BLS key `pk_hex` was created with, None if it predates roles
+pub fn read_bls_key_role(pk_hex: &str) -> Result<Option<KeyRole>> {
+    Ok(read_bls_key_metadata(pk_hex)?.and_then(|m| m.role))
+}
+
+/// Returns the BLS public key hex mapped to `validator_index`, or None if it is unknown
+pub fn read_validator_index(validator_index: u64) -> Result<Option<String>> {
+    let file_path: PathBuf = [VALIDATOR_INDICES_DIR, &validator_index.to_string()]
+        .iter()
+        .collect();
+    if !key_exists(&file_path) {
+        return Ok(None);
+    }
+    let pk_hex =
+        fs::read_to_string(&file_path).with_context(|| "Unable to read validator index")?;
+    Ok(Some(pk_hex))
+}
+
 /// Reads BLS secret key from encrypted keystore
 pub fn read_bls_keystore(pk_hex: &String, password: &String) -> Result<Vec<u8>> {
     // Sanitize inputs
     let pk_hex: &str = strip_0x_prefix!(pk_hex);
-    let file_path: PathBuf = [BLS_KEYS_DIR, pk_hex].iter().collect();
+    let store = bls_key_store();
+    let file_path = store
+        .find(pk_hex)
+        .unwrap_or_else(|| store.write_path(pk_hex));
     let sk_bytes = eth_keystore::decrypt_key(file_path, password)?;
     Ok(sk_bytes)
 }
@@ -80,72 +721,187 @@ fn delete_key(file_path: PathBuf) -> Result<()> {
         .with_context(|| format!("failed to delete key at: {:?}", file_path.as_os_str()))
 }
 
+// Deletes every copy of the key `pk_hex` across the read dirs of `store`, so no copy left in
+// another dir is read in its place. Errors if none is saved.
+fn delete_from_store(store: &KeyStore, pk_hex: &str) -> Result<()> {
+    let paths = store.find_all(pk_hex);
+    if paths.is_empty() {
+        return delete_key(store.write_path(pk_hex));
+    }
+    for path in paths {
+        delete_key(path)?;
+    }
+    Ok(())
+}
+
 /// Deletes the ETH secret key saved at the specified path
 pub fn delete_eth_key(pk_hex: &str) -> Result<()> {
     let pk_hex: &str = strip_0x_prefix!(pk_hex);
-    let file_path: PathBuf = [ETH_KEYS_DIR, pk_hex].iter().collect();
-    delete_key(file_path)
+    delete_from_store(eth_key_store(), pk_hex)?;
+
+    // Remove the sidecar metadata and MAC if any were saved
+    let metadata_path: PathBuf = [ETH_KEY_METADATA_DIR, pk_hex].iter().collect();
+    if key_exists(&metadata_path) {
+        delete_key(metadata_path)?;
+    }
+    let mac_path: PathBuf = [ETH_KEY_MACS_DIR, pk_hex].iter().collect();
+    if key_exists(&mac_path) {
+        delete_key(mac_path)?;
+    }
+    Ok(())
 }
 
 /// Deletes the BLS secret key saved at the specified path
 pub fn delete_bls_key(pk_hex: &str) -> Result<()> {
+    delete_bls_key_with_force(pk_hex, false)
+}
+
+/// Seconds after a BLS key is saved during which deleting it requires `force`, read from
+/// `SECURE_SIGNER_KEY_PROTECTION_SECS`. Defaults to 0, leaving fresh keys unprotected, and fails
+/// on a malformed value rather than leaving keys unprotected.
+pub fn key_protection_window() -> Result<u64> {
+    parse_key_protection_window(
+        std::env::var("SECURE_SIGNER_KEY_PROTECTION_SECS")
+            .ok()
+            .as_deref(),
+    )
+}
+
+fn parse_key_protection_window(secs: Option<&str>) -> Result<u64> {
+    match secs {
+        Some(secs) if !secs.is_empty() => secs
+            .parse()
+            .with_context(|| format!("Bad SECURE_SIGNER_KEY_PROTECTION_SECS {secs}")),
+        _ => Ok(0),
+    }
+}
+
+// Rejects deleting a key saved less than `window` seconds ago unless `force` is set. Keys without
+// a recorded creation time are not protected.
+fn check_key_protection(pk_hex: &str, window: u64, force: bool) -> Result<()> {
+    if force || window == 0 {
+        return Ok(());
+    }
+    let Some(created_at) = read_bls_key_metadata(pk_hex)?.and_then(|m| m.created_at) else {
+        return Ok(());
+    };
+    let age = unix_now()?.saturating_sub(created_at);
+    if age < window {
+        bail!(
+            "BLS key {pk_hex} was saved {age}s ago, within the {window}s protection window. Force the delete to remove it."
+        )
+    }
+    Ok(())
+}
+
+/// Deletes the BLS key `pk_hex` like `delete_bls_key`, with `force` bypassing the protection
+/// window for recently saved keys
+pub fn delete_bls_key_with_force(pk_hex: &str, force: bool) -> Result<()> {
+    delete_bls_key_within(pk_hex, key_protection_window()?, force)
+}
+
+fn delete_bls_key_within(pk_hex: &str, window: u64, force: bool) -> Result<()> {
     let pk_hex: &str = strip_0x_prefix!(pk_hex);
-    let file_path: PathBuf = [BLS_KEYS_DIR, pk_hex].iter().collect();
-    delete_key(file_path)
+    check_key_protection(pk_hex, window, force)?;
+    // Evicted only once no copy of the key file is left to be read back into the caches
+    crate::crypto::key_cache::delete_and_evict(pk_hex, || {
+        delete_from_store(bls_key_store(), pk_hex)
+    })?;
+    crate::crypto::signature_cache::evict_pk(pk_hex);
+
+    // Remove the sidecar metadata and MAC if any were saved
+    let metadata_path: PathBuf = [BLS_KEY_METADATA_DIR, pk_hex].iter().collect();
+    if key_exists(&metadata_path) {
+        delete_key(metadata_path)?;
+    }
+    let mac_path: PathBuf = [BLS_KEY_MACS_DIR, pk_hex].iter().collect();
+    if key_exists(&mac_path) {
+        delete_key(mac_path)?;
+    }
+    Ok(())
 }
 
 /// Return true if the key at the specified path exists
-fn key_exists(file_path: &PathBuf) -> bool {
+fn key_exists(file_path: &Path) -> bool {
     file_path.exists()
 }
 
 /// Return true if the ETH key at the specified path exists
 pub fn eth_key_exists(pk_hex: &str) -> bool {
     let pk_hex: &str = strip_0x_prefix!(pk_hex);
-    let file_path: PathBuf = [ETH_KEYS_DIR, pk_hex].iter().collect();
-    key_exists(&file_path)
+    eth_key_store().find(pk_hex).is_some()
 }
 
 /// Return true if the BLS key at the specified path exists
 pub fn bls_key_exists(pk_hex: &str) -> bool {
     let pk_hex: &str = strip_0x_prefix!(pk_hex);
-    let file_path: PathBuf = [BLS_KEYS_DIR, pk_hex].iter().collect();
-    key_exists(&file_path)
+    bls_key_store().find(pk_hex).is_some()
 }
 
 /// Return the file names in the specified directory
-fn list_fnames(path_to_dir: &str) -> Result<Vec<String>> {
-    let paths = fs::read_dir(path_to_dir).with_context(|| "No keys saved in dir")?;
+fn list_fnames(path_to_dir: &Path) -> Result<Vec<String>> {
+    iter_fnames(path_to_dir)?.collect()
+}
 
-    let mut keys: Vec<String> = Vec::new();
-    for path in paths {
+// Streams the file names in the specified directory as it is read
+fn iter_fnames(path_to_dir: &Path) -> Result<impl Iterator<Item = Result<String>>> {
+    let paths = fs::read_dir(path_to_dir).with_context(|| "No keys saved in dir")?;
+    Ok(paths.map(|path| {
         // Get the paths to each file in this dir
-        let p = match path.as_ref().err() {
-            Some(e) => bail!("failed to find path: {}", e),
-            _ => path.unwrap(),
-        };
+        let p = path.with_context(|| "failed to find path")?;
 
         // remove path prefix, to grab just the file name
-        let fname = p.file_name();
-
-        match fname.to_os_string().into_string() {
-            Ok(s) => keys.push(s),
-            Err(e) => bail!("Error, bad file name in list_keys(): {:?}", e),
-        }
-    }
-    Ok(keys)
+        p.file_name()
+            .into_string()
+            .map_err(|e| anyhow::anyhow!("Error, bad file name in list_keys(): {:?}", e))
+    }))
 }
 
 /// Returns the file names of each of the saved bls secret keys, where each fname
 /// is assumed to be the compressed public key in hex without the `0x` prefix.
 pub fn list_bls_keys() -> Result<Vec<String>> {
-    list_fnames(BLS_KEYS_DIR)
+    bls_key_store().list()
 }
 
 /// Returns the file names of each of the saved eth secret keys, where each fname
 /// is assumed to be the eth wallet address derived from the eth public key in hex without the `0x` prefix.
 pub fn list_eth_keys() -> Result<Vec<String>> {
-    list_fnames(ETH_KEYS_DIR)
+    eth_key_store().list()
+}
+
+/// Returns the saved keys matching `key_type` and `attested`, where None matches any value.
+/// A store none of whose dirs exist yet holds no keys.
+pub fn list_keys(key_type: Option<KeyType>, attested: Option<bool>) -> Result<Vec<KeyEntry>> {
+    iter_keys(key_type, attested).collect()
+}
+
+/// Streams the saved keys matching `key_type` and `attested` like `list_keys`, reading the key
+/// dirs and metadata as the entries are taken
+pub fn iter_keys(
+    key_type: Option<KeyType>,
+    attested: Option<bool>,
+) -> impl Iterator<Item = Result<KeyEntry>> {
+    [
+        (KeyType::Bls, bls_key_store()),
+        (KeyType::Eth, eth_key_store()),
+    ]
+    .into_iter()
+    .filter(move |(t, _)| key_type.map_or(true, |k| k == *t))
+    .flat_map(|(t, store)| store.iter().map(move |pk_hex| (t, pk_hex)))
+    .filter_map(move |(t, pk_hex)| {
+        let entry = pk_hex.and_then(|pk_hex| {
+            let is_attested = read_key_metadata(t, &pk_hex)?.is_some_and(|m| m.attested);
+            Ok(KeyEntry {
+                key_type: t,
+                pk_hex,
+                attested: is_attested,
+            })
+        });
+        match entry {
+            Ok(entry) if attested.is_some_and(|a| a != entry.attested) => None,
+            entry => Some(entry),
+        }
+    })
 }
 
 #[cfg(test)]
@@ -161,6 +917,32 @@ mod test_key_management {
         fs::read_to_string(file_path).with_context(|| "failed to read")
     }
 
+    #[test]
+    fn test_ct_hex_decode() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        for hex_str in [hex::encode(&bytes), hex::encode_upper(&bytes)] {
+            let mut out = vec![0_u8; bytes.len()];
+            ct_hex_decode(hex_str.as_bytes(), &mut out).unwrap();
+            assert_eq!(out, bytes);
+        }
+
+        // Wrong or odd lengths
+        let mut out = [0_u8; 2];
+        assert!(ct_hex_decode(b"abc", &mut out).is_err());
+        assert!(ct_hex_decode(b"abcdef", &mut out).is_err());
+
+        // Invalid chars at either end are rejected only after the whole input is decoded
+        for bad in ["g0abcd", "0xabcd", "abcd0z", "ab cd\n"] {
+            let mut out = vec![0xee_u8; bad.len() / 2];
+            assert!(ct_hex_decode(bad.as_bytes(), &mut out).is_err(), "{bad}");
+        }
+        let mut out = [0_u8; 3];
+        assert!(ct_hex_decode(b"g0abcd", &mut out).is_err());
+        assert_eq!(out[1..], [0xab, 0xcd]);
+        assert!(ct_hex_decode(b"abcd0z", &mut out).is_err());
+        assert_eq!(out[..2], [0xab, 0xcd]);
+    }
+
     #[test]
     fn test_write_key() {
         let file_path: PathBuf = [KEYS_DIR, "test"].iter().collect();
@@ -174,6 +956,32 @@ mod test_key_management {
         fs::remove_dir_all("./etc").ok();
     }
 
+    #[test]
+    fn test_write_key_ensures_dir_once() {
+        let dir: PathBuf = [KEYS_DIR, "test_ensure_dir"].iter().collect();
+        fs::remove_dir_all(&dir).ok();
+        let calls = || -> usize {
+            CREATE_DIR_CALLS
+                .lock()
+                .unwrap()
+                .as_ref()
+                .and_then(|c| c.get(&dir).copied())
+                .unwrap_or(0)
+        };
+        let before = calls();
+
+        write_key(dir.join("a"), "abcdef").unwrap();
+        write_key(dir.join("b"), "123456").unwrap();
+        assert_eq!(calls(), before + 1);
+
+        // Removing the dir out from under the cache is recovered from on the next write
+        fs::remove_dir_all(&dir).unwrap();
+        write_key(dir.join("c"), "abcdef").unwrap();
+        assert_eq!(read_file(&dir.join("c")).unwrap(), "abcdef");
+        assert_eq!(calls(), before + 2);
+        fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn test_write_eth_key() {
         fs::remove_dir_all("./etc").ok();
@@ -188,6 +996,28 @@ mod test_key_management {
         fs::remove_dir_all("./etc").ok();
     }
 
+    #[test]
+    fn test_write_eth_key_collision() {
+        fs::remove_dir_all("./etc").ok();
+        let pk_hex = "0x1234abcd";
+
+        // Pre-plant a key file
+        let file_path: PathBuf = [ETH_KEYS_DIR, "1234abcd"].iter().collect();
+        write_key(file_path.clone(), "planted").unwrap();
+
+        let err = write_eth_key(&pk_hex.to_string(), &"0xabcdef123456".to_string()).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<KeyCollision>(),
+            Some(&KeyCollision {
+                pk_hex: "1234abcd".to_string()
+            })
+        );
+
+        // The planted file is untouched
+        assert_eq!(read_file(&file_path).unwrap(), "planted");
+        fs::remove_dir_all("./etc").ok();
+    }
+
     #[test]
     fn test_write_bls_key() {
         fs::remove_dir_all("./etc").ok();
@@ -242,6 +1072,59 @@ mod test_key_management {
         assert!(!bls_key_exists(pk_hex));
     }
 
+    #[test]
+    fn test_tampered_key_file_fails_mac_check() {
+        let pk_hex = "0x5eed0451";
+        let sk_hex = "0xabcdef123456";
+        write_bls_key(&pk_hex.to_string(), &sk_hex.to_string()).unwrap();
+        assert!(read_bls_key(pk_hex).is_ok());
+
+        // Flip a byte, keeping the file valid hex so only the MAC can catch it
+        let file_path: PathBuf = [BLS_KEYS_DIR, "5eed0451"].iter().collect();
+        fs::write(&file_path, "bbcdef123456").unwrap();
+        let err = read_bls_key(pk_hex).unwrap_err();
+        assert!(err.to_string().contains("failed its integrity check"));
+
+        // A validly MACed file swapped in under this name is rejected too
+        write_bls_key(&"0x5eed0452".to_string(), &"0x654321fedcba".to_string()).unwrap();
+        fs::copy(
+            [BLS_KEYS_DIR, "5eed0452"].iter().collect::<PathBuf>(),
+            &file_path,
+        )
+        .unwrap();
+        assert!(read_bls_key(pk_hex).is_err());
+
+        fs::write(&file_path, "abcdef123456").unwrap();
+        assert_eq!(
+            read_bls_key(pk_hex).unwrap(),
+            vec![0xab, 0xcd, 0xef, 0x12, 0x34, 0x56]
+        );
+
+        // A key without a MAC, e.g. saved by an older build, is refused in strict mode
+        let mac_path: PathBuf = [BLS_KEY_MACS_DIR, "5eed0451"].iter().collect();
+        let mac = fs::read(&mac_path).unwrap();
+        fs::remove_file(&mac_path).unwrap();
+        let contents = fs::read(&file_path).unwrap();
+        let err = verify_key_mac_with(KeyType::Bls, "5eed0451", &contents, true).unwrap_err();
+        assert!(err.to_string().contains("No MAC saved"));
+        // Otherwise it is MACed on first read, so its file can't be changed afterwards
+        assert!(read_bls_key(pk_hex).is_ok());
+        assert_eq!(fs::read(&mac_path).unwrap(), mac);
+        fs::write(&file_path, "bbcdef123456").unwrap();
+        assert!(read_bls_key(pk_hex).is_err());
+        fs::write(&file_path, "abcdef123456").unwrap();
+
+        // The MAC key is only saved sealed
+        let saved = read_key(PathBuf::from(KEY_MAC_KEY_PATH)).unwrap();
+        let unsealed = crate::io::sealing::unseal(&saved, KEY_MAC_KEY_AAD).unwrap();
+        assert_eq!(unsealed.as_slice(), &key_mac_key().unwrap()[..]);
+        delete_bls_key(pk_hex).unwrap();
+        delete_bls_key("0x5eed0452").unwrap();
+        assert!(!key_exists(
+            &[BLS_KEY_MACS_DIR, "5eed0451"].iter().collect::<PathBuf>()
+        ));
+    }
+
     #[test]
     fn test_write_read_delete_bls_keystore() {
         fs::remove_dir_all("./etc").ok();
@@ -324,4 +1207,214 @@ mod test_key_management {
         let bls_keys = list_bls_keys().unwrap();
         assert_eq!(bls_keys.len(), 0);
     }
+
+    #[test]
+    fn test_key_store_reads_secondary_dir() {
+        let base: PathBuf = [KEYS_DIR, "test_key_store"].iter().collect();
+        fs::remove_dir_all(&base).ok();
+        let baked = base.join("baked");
+        let provisioned = base.join("provisioned");
+        let store = KeyStore::new(vec![baked.clone()], provisioned.clone());
+
+        // A key only present in the read-only dir is found and listed
+        write_key(baked.join("1234abcd"), "abcdef").unwrap();
+        assert_eq!(store.find("1234abcd"), Some(baked.join("1234abcd")));
+        assert_eq!(
+            read_key(store.find("1234abcd").unwrap()).unwrap(),
+            vec![0xab, 0xcd, 0xef]
+        );
+        assert!(store.find("5678ef01").is_none());
+
+        // New keys land in the write dir, and listing merges both dirs
+        write_key(store.write_path("5678ef01"), "123456").unwrap();
+        assert!(key_exists(&provisioned.join("5678ef01")));
+        assert!(!key_exists(&baked.join("5678ef01")));
+        assert_eq!(store.find("5678ef01"), Some(provisioned.join("5678ef01")));
+
+        let mut keys = store.list().unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["1234abcd".to_string(), "5678ef01".to_string()]);
+        fs::remove_dir_all(&base).ok();
+    }
+
+    #[test]
+    fn test_key_store_search_order() {
+        let base: PathBuf = [KEYS_DIR, "test_key_store_order"].iter().collect();
+        fs::remove_dir_all(&base).ok();
+        let first = base.join("first");
+        let second = base.join("second");
+        let write = base.join("write");
+        let store = KeyStore::new(vec![first.clone(), second.clone()], write.clone());
+
+        write_key(second.join("1234abcd"), "222222").unwrap();
+        assert_eq!(store.find("1234abcd"), Some(second.join("1234abcd")));
+        write_key(first.join("1234abcd"), "111111").unwrap();
+        assert_eq!(store.find("1234abcd"), Some(first.join("1234abcd")));
+        let err = store.check_collision("1234abcd").unwrap_err();
+        assert!(err.downcast_ref::<KeyCollision>().is_some());
+        assert!(store.check_collision("5678ef01").is_ok());
+
+        // The write dir is searched first, so a key written there is the one read
+        write_key(write.join("1234abcd"), "333333").unwrap();
+        assert_eq!(store.find("1234abcd"), Some(write.join("1234abcd")));
+        assert_eq!(
+            store.find_all("1234abcd"),
+            vec![
+                write.join("1234abcd"),
+                first.join("1234abcd"),
+                second.join("1234abcd")
+            ]
+        );
+        // Also when it is listed among the read dirs
+        let listed = KeyStore::new(vec![first.clone(), write.clone()], write.clone());
+        assert_eq!(listed.find("1234abcd"), Some(write.join("1234abcd")));
+
+        // A key saved in several dirs is listed once, and missing dirs are skipped
+        assert_eq!(store.list().unwrap(), vec!["1234abcd".to_string()]);
+        let sparse = KeyStore::new(vec![base.join("missing")], write.clone());
+        assert_eq!(sparse.list().unwrap(), vec!["1234abcd".to_string()]);
+        assert!(KeyStore::single(base.join("missing")).list().is_err());
+
+        // Deleting removes every copy, none is left to be read in its place
+        delete_from_store(&store, "1234abcd").unwrap();
+        assert!(store.find("1234abcd").is_none());
+        assert!(delete_from_store(&store, "1234abcd").is_err());
+        fs::remove_dir_all(&base).ok();
+    }
+
+    #[test]
+    fn test_list_keys_filters() {
+        fs::remove_dir_all("./etc").ok();
+        write_bls_key(&"0xb1".to_string(), &"0xaa".to_string()).unwrap();
+        write_bls_key(&"0xb2".to_string(), &"0xbb".to_string()).unwrap();
+        write_eth_key(&"0xe1".to_string(), &"0xcc".to_string()).unwrap();
+        write_eth_key(&"0xe2".to_string(), &"0xdd".to_string()).unwrap();
+        mark_key_attested(KeyType::Bls, "0xb1").unwrap();
+        mark_key_attested(KeyType::Eth, "e1").unwrap();
+
+        let names = |key_type, attested| -> Vec<String> {
+            let mut got: Vec<String> = list_keys(key_type, attested)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.pk_hex)
+                .collect();
+            got.sort();
+            got
+        };
+        let bls = Some(KeyType::Bls);
+        let eth = Some(KeyType::Eth);
+        assert_eq!(names(None, None), vec!["b1", "b2", "e1", "e2"]);
+        assert_eq!(names(bls, None), vec!["b1", "b2"]);
+        assert_eq!(names(eth, None), vec!["e1", "e2"]);
+        assert_eq!(names(None, Some(true)), vec!["b1", "e1"]);
+        assert_eq!(names(None, Some(false)), vec!["b2", "e2"]);
+        assert_eq!(names(bls, Some(true)), vec!["b1"]);
+        assert_eq!(names(bls, Some(false)), vec!["b2"]);
+        assert_eq!(names(eth, Some(true)), vec!["e1"]);
+        assert_eq!(names(eth, Some(false)), vec!["e2"]);
+
+        // Attesting keeps previously saved metadata
+        write_bls_key_metadata(
+            "b2",
+            &KeyMetadata {
+                derivation_path: Some("m/12381/3600/0/0/0".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        mark_key_attested(KeyType::Bls, "b2").unwrap();
+        let metadata = read_bls_key_metadata("b2").unwrap().unwrap();
+        assert!(metadata.attested);
+        assert_eq!(
+            metadata.derivation_path.as_deref(),
+            Some("m/12381/3600/0/0/0")
+        );
+        fs::remove_dir_all("./etc").ok();
+    }
+
+    #[test]
+    fn test_delete_protection_window() {
+        let pk_hex = "0x5eed0458";
+        write_bls_key(&pk_hex.to_string(), &"0x1234".to_string()).unwrap();
+        let created_at = read_bls_key_metadata(pk_hex)
+            .unwrap()
+            .unwrap()
+            .created_at
+            .unwrap();
+        assert!(created_at.abs_diff(unix_now().unwrap()) <= 1);
+
+        // Within the window deleting requires force
+        assert!(delete_bls_key_within(pk_hex, 3600, false).is_err());
+        assert!(bls_key_exists(pk_hex));
+        check_key_protection("5eed0458", 0, false).unwrap();
+
+        // Rewriting the metadata keeps the creation time
+        write_bls_key_metadata(
+            pk_hex,
+            &KeyMetadata {
+                attested: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(delete_bls_key_within(pk_hex, 3600, false).is_err());
+        assert!(bls_key_exists(pk_hex));
+        delete_bls_key_within(pk_hex, 3600, true).unwrap();
+        assert!(!bls_key_exists(pk_hex));
+        assert_eq!(read_bls_key_metadata(pk_hex).unwrap(), None);
+
+        // Outside the window the key can be deleted without force
+        write_bls_key(&pk_hex.to_string(), &"0x1234".to_string()).unwrap();
+        write_bls_key_metadata(
+            pk_hex,
+            &KeyMetadata {
+                created_at: Some(created_at - 7200),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        delete_bls_key_within(pk_hex, 3600, false).unwrap();
+        assert!(!bls_key_exists(pk_hex));
+        assert_eq!(read_bls_key_metadata(pk_hex).unwrap(), None);
+
+        // A malformed window fails rather than leaving fresh keys unprotected
+        assert_eq!(parse_key_protection_window(None).unwrap(), 0);
+        assert_eq!(parse_key_protection_window(Some("")).unwrap(), 0);
+        assert_eq!(parse_key_protection_window(Some("3600")).unwrap(), 3600);
+        assert!(parse_key_protection_window(Some("1h")).is_err());
+        assert!(parse_key_protection_window(Some("-1")).is_err());
+    }
+
+    #[test]
+    fn test_list_keys_empty_store() {
+        fs::remove_dir_all("./etc").ok();
+        assert!(list_keys(None, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_write_read_validator_index() {
+        fs::remove_dir_all("./etc").ok();
+        assert_eq!(read_validator_index(42).unwrap(), None);
+        write_validator_index(42, "0x1234abcd", false).unwrap();
+        assert_eq!(
+            read_validator_index(42).unwrap(),
+            Some("1234abcd".to_string())
+        );
+        assert_eq!(read_validator_index(43).unwrap(), None);
+
+        // Mapping the same key again is a no-op, another key needs `overwrite`
+        write_validator_index(42, "1234ABCD", false).unwrap();
+        let err = write_validator_index(42, "0x5678ef01", false).unwrap_err();
+        assert!(ValidatorIndexTaken::is_cause_of(&err));
+        assert_eq!(
+            read_validator_index(42).unwrap(),
+            Some("1234abcd".to_string())
+        );
+        write_validator_index(42, "0x5678ef01", true).unwrap();
+        assert_eq!(
+            read_validator_index(42).unwrap(),
+            Some("5678ef01".to_string())
+        );
+        fs::remove_dir_all("./etc").ok();
+    }
 }
@@ -1,2 +1,90 @@
+pub mod backup;
+pub mod dcap;
 pub mod key_management;
 pub mod remote_attestation;
+pub mod sealing;
+pub mod webhook;
+
+/// Returns the current unix time in seconds
+pub fn unix_now() -> anyhow::Result<u64> {
+    Ok(std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs())
+}
+
+// A temporary file beside `path` that no concurrent writer shares
+fn temp_path(path: &std::path::Path) -> std::path::PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{:016x}.tmp", rand::random::<u64>()));
+    path.with_file_name(name)
+}
+
+// Writes and syncs `contents` to a fresh temporary file beside `path`, returning its path
+fn write_synced_temp(
+    path: &std::path::Path,
+    contents: &[u8],
+) -> std::io::Result<std::path::PathBuf> {
+    let tmp = temp_path(path);
+    let written = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp)
+        .and_then(|mut f| {
+            std::io::Write::write_all(&mut f, contents)?;
+            f.sync_all()
+        });
+    if let Err(e) = written {
+        _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    Ok(tmp)
+}
+
+/// Replaces `path` with `contents` by renaming a synced temporary file over it, so a crash leaves
+/// either the old or the new contents and never a torn file
+pub fn write_atomically(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+    let tmp = write_synced_temp(path, contents)?;
+    std::fs::rename(&tmp, path).map_err(|e| {
+        _ = std::fs::remove_file(&tmp);
+        e
+    })
+}
+
+/// Creates `path` holding `contents` unless it exists, returning false if it did. The file is
+/// written and synced under a temporary name then linked into place, so `path` is never seen
+/// partially written and of concurrent creators exactly one succeeds.
+pub fn create_new_atomically(path: &std::path::Path, contents: &[u8]) -> std::io::Result<bool> {
+    let tmp = write_synced_temp(path, contents)?;
+    let linked = std::fs::hard_link(&tmp, path);
+    _ = std::fs::remove_file(&tmp);
+    match linked {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atomic_writes() {
+        let dir = std::path::Path::new("./etc/atomic_write_test/");
+        _ = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join("file");
+
+        // Only the first creator wins, later ones leave its contents in place
+        assert!(create_new_atomically(&path, b"first").unwrap());
+        assert!(!create_new_atomically(&path, b"second").unwrap());
+        assert_eq!(std::fs::read(&path).unwrap(), b"first");
+
+        write_atomically(&path, b"replaced").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"replaced");
+
+        // No temporary files are left behind
+        assert_eq!(std::fs::read_dir(dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
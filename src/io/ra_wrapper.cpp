@@ -6,7 +6,8 @@
 
 
 extern "C"
-void do_epid_ra(uint8_t data[64], char * report, char * signature, char * signing_cert) {
+void do_epid_ra(uint8_t data[64], char * report, char * signature, char * signing_cert,
+                long timeout_secs, long connect_timeout_secs) {
   // 64 Byte report data to embed in the intel-signed report
   sgx_report_data_t report_data = {0};
   for (int i = 0; i < 64; ++i) {
@@ -18,6 +19,9 @@ void do_epid_ra(uint8_t data[64], char * report, char * signature, char * signin
   RaIasServerCfg ias_server;
   ias_server.endpoint = RA_CONF_STR(kConfIasServer);
   ias_server.accesskey = RA_CONF_STR(kConfIasAccessKey);
+  // libcurl gives up on each IAS request after these, so a hung IAS cannot hold the caller
+  ias_server.timeout_secs = timeout_secs;
+  ias_server.connect_timeout_secs = connect_timeout_secs;
   std::string spid = RA_CONF_STR(kConfSPID);
 
   ra::occlum::RaEnclaveQuote ra;
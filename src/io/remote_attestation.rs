@@ -1,36 +1,370 @@
-use anyhow::{bail, Context, Result};
+use crate::constants::{
+    IAS_BASE_BACKOFF_MS, IAS_CONNECT_TIMEOUT_SECS, IAS_MAX_RETRIES, IAS_TIMEOUT_SECS,
+};
+use crate::io::dcap::{DcapCollateral, DcapCollateralVerifier, TcbVerdict, QUOTE_SIGNED_BYTES};
+
+use anyhow::{anyhow, bail, Context, Result};
 use blsttc::PublicKey;
 use ecies::PublicKey as EthPublicKey;
-use log::{debug, info};
+use log::{debug, info, warn};
 use openssl::stack::Stack;
 use openssl::x509::store::X509StoreBuilder;
 use openssl::x509::{X509StoreContext, X509};
 use serde::Deserialize;
 use serde_derive::Serialize;
+use sha2::{Digest, Sha256};
 
 use std::ffi::CString;
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_long};
+use std::thread;
+use std::time::Duration;
 
 #[cfg(feature = "sgx")]
 #[link(name = "epid")]
 extern "C" {
-    /// The cpp function for epid remote attestation with IAS defined in src/ra_wrapper.cpp
+    /// The cpp function for epid remote attestation with IAS defined in src/ra_wrapper.cpp,
+    /// whose IAS requests give up after the given timeouts in seconds
     fn do_epid_ra(
         data: *const u8,
         report: *mut c_char,
         signature: *mut c_char,
         signing_cert: *mut c_char,
+        timeout_secs: c_long,
+        connect_timeout_secs: c_long,
     );
 }
 
 #[cfg(not(feature = "sgx"))]
-// Use this func sig for local development
-pub fn do_epid_ra(
+// Use this func sig for local development, where there is no IAS and so an empty report
+pub unsafe extern "C" fn do_epid_ra(
     _data: *const u8,
-    _report: *mut c_char,
-    _signature: *mut c_char,
-    _signing_cert: *mut c_char,
+    report: *mut c_char,
+    signature: *mut c_char,
+    signing_cert: *mut c_char,
+    _timeout_secs: c_long,
+    _connect_timeout_secs: c_long,
 ) {
+    for buf in [report, signature, signing_cert] {
+        *buf = 0;
+    }
+}
+
+// The signature of `do_epid_ra`, so tests can stand in for IAS
+type EpidRaFn =
+    unsafe extern "C" fn(*const u8, *mut c_char, *mut c_char, *mut c_char, c_long, c_long);
+
+/// Generates a DCAP ECDSA quote over `report_data` through Occlum's `/dev/sgx` ioctls
+#[cfg(feature = "sgx")]
+fn generate_dcap_quote(report_data: &[u8; 64]) -> Result<Vec<u8>> {
+    use std::os::unix::io::AsRawFd;
+
+    const SGXIOC_GET_DCAP_QUOTE_SIZE: u64 = 0x80047307;
+    const SGXIOC_GEN_DCAP_QUOTE: u64 = 0xc0187308;
+
+    #[repr(C)]
+    struct GenDcapQuoteArg {
+        report_data: *const u8,
+        quote_size: *mut u32,
+        quote_buf: *mut u8,
+    }
+
+    let sgx = std::fs::File::open("/dev/sgx").with_context(|| "Failed to open /dev/sgx")?;
+    let fd = sgx.as_raw_fd();
+    let mut quote_size: u32 = 0;
+    let ret = unsafe {
+        libc::ioctl(
+            fd,
+            SGXIOC_GET_DCAP_QUOTE_SIZE as _,
+            &mut quote_size as *mut u32,
+        )
+    };
+    if ret < 0 {
+        bail!(
+            "Failed to get the DCAP quote size: {}",
+            std::io::Error::last_os_error()
+        )
+    }
+
+    let mut quote = vec![0_u8; quote_size as usize];
+    let mut arg = GenDcapQuoteArg {
+        report_data: report_data.as_ptr(),
+        quote_size: &mut quote_size,
+        quote_buf: quote.as_mut_ptr(),
+    };
+    let ret = unsafe {
+        libc::ioctl(
+            fd,
+            SGXIOC_GEN_DCAP_QUOTE as _,
+            &mut arg as *mut GenDcapQuoteArg,
+        )
+    };
+    if ret < 0 {
+        bail!(
+            "Failed to generate a DCAP quote: {}",
+            std::io::Error::last_os_error()
+        )
+    }
+    quote.truncate(quote_size as usize);
+    Ok(quote)
+}
+
+#[cfg(not(feature = "sgx"))]
+// DCAP quotes can only be produced inside the enclave
+fn generate_dcap_quote(_report_data: &[u8; 64]) -> Result<Vec<u8>> {
+    bail!("DCAP quote generation requires the sgx feature")
+}
+
+/// The remote attestation mode that produced a piece of evidence
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AttestationType {
+    /// ECDSA quote checked against Intel DCAP collateral
+    Dcap,
+    /// EPID quote checked by IAS
+    #[default]
+    Epid,
+}
+
+impl std::str::FromStr for AttestationType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "dcap" => Ok(AttestationType::Dcap),
+            "epid" => Ok(AttestationType::Epid),
+            _ => bail!("Unknown attestation type {s}, expected dcap or epid"),
+        }
+    }
+}
+
+/// The attestation types to try in order, read from the comma separated
+/// `SECURE_SIGNER_ATTESTATION_TYPES` (e.g. "dcap,epid"). Defaults to EPID only.
+pub fn attestation_preference() -> Result<Vec<AttestationType>> {
+    match std::env::var("SECURE_SIGNER_ATTESTATION_TYPES") {
+        Ok(types) => types.split(',').map(|t| t.parse()).collect(),
+        Err(_) => Ok(vec![AttestationType::Epid]),
+    }
+}
+
+// DCAP collateral is kept fresh by the operator at the JSON file `SECURE_SIGNER_DCAP_COLLATERAL`
+fn dcap_collateral_path() -> Option<String> {
+    std::env::var("SECURE_SIGNER_DCAP_COLLATERAL").ok()
+}
+
+fn load_dcap_collateral(path: Option<&str>) -> Result<DcapCollateral> {
+    let path = path.with_context(|| "SECURE_SIGNER_DCAP_COLLATERAL is not set")?;
+    let json = std::fs::read(&path).with_context(|| format!("Failed to read {path}"))?;
+    serde_json::from_slice(&json).with_context(|| format!("Bad DCAP collateral in {path}"))
+}
+
+/// A verifier trusting the SGX root CA PEM at `SECURE_SIGNER_DCAP_ROOT_CA`
+pub fn dcap_verifier_from_env() -> Result<DcapCollateralVerifier> {
+    let path = std::env::var("SECURE_SIGNER_DCAP_ROOT_CA")
+        .with_context(|| "SECURE_SIGNER_DCAP_ROOT_CA must be set to verify DCAP evidence")?;
+    let pem = std::fs::read(&path).with_context(|| format!("Failed to read {path}"))?;
+    DcapCollateralVerifier::new(&pem)
+}
+
+/// Selects how a public key (and optional nonce) is packed into the 64B report data
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportDataLayout {
+    /// pk || zero padding
+    #[default]
+    RawPubkey,
+    /// sha256(pk) || 32 zero bytes
+    Sha256Pubkey,
+    /// sha256(pk) || 32B nonce
+    Sha256PubkeyWithNonce,
+}
+
+impl ReportDataLayout {
+    /// Composes the 64B report data for `pk_bytes` according to this layout
+    pub fn build(&self, pk_bytes: &[u8], nonce: Option<&[u8; 32]>) -> Result<[u8; 64]> {
+        let mut report_data = [0_u8; 64];
+        match self {
+            ReportDataLayout::RawPubkey => {
+                if nonce.is_some() {
+                    bail!("RawPubkey report data layout does not take a nonce")
+                }
+                if pk_bytes.len() > 64 {
+                    bail!("remote attestation report data exceed 64B limit!")
+                }
+                report_data[..pk_bytes.len()].copy_from_slice(pk_bytes);
+            }
+            ReportDataLayout::Sha256Pubkey => {
+                if nonce.is_some() {
+                    bail!("Sha256Pubkey report data layout does not take a nonce")
+                }
+                report_data[..32].copy_from_slice(&Sha256::digest(pk_bytes));
+            }
+            ReportDataLayout::Sha256PubkeyWithNonce => {
+                let nonce = match nonce {
+                    Some(n) => n,
+                    None => bail!("Sha256PubkeyWithNonce report data layout requires a nonce"),
+                };
+                report_data[..32].copy_from_slice(&Sha256::digest(pk_bytes));
+                report_data[32..].copy_from_slice(nonce);
+            }
+        }
+        Ok(report_data)
+    }
+
+    /// Verifies that `report_data` commits to `pk_bytes` (and `nonce`) under this layout
+    pub fn verify(
+        &self,
+        report_data: &[u8; 64],
+        pk_bytes: &[u8],
+        nonce: Option<&[u8; 32]>,
+    ) -> Result<()> {
+        let expected = self.build(pk_bytes, nonce)?;
+        if report_data != &expected {
+            bail!(
+                "Remote attestation payload does not match the expected {:?} layout",
+                self
+            )
+        }
+        Ok(())
+    }
+}
+
+/// Timeout and retry bounds for remote attestation round trips to IAS. The timeouts are enforced
+/// by the IAS client itself, so an attempt that times out is over rather than left running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IasClientConfig {
+    /// Maximum time a single attestation attempt may take, rounded up to whole seconds for IAS
+    pub timeout: Duration,
+    /// Maximum time to wait on connecting to IAS within an attempt
+    pub connect_timeout: Duration,
+    /// Attempts made after the first one fails
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled after each further failure
+    pub base_backoff: Duration,
+}
+
+impl Default for IasClientConfig {
+    fn default() -> Self {
+        IasClientConfig {
+            timeout: Duration::from_secs(IAS_TIMEOUT_SECS),
+            connect_timeout: Duration::from_secs(IAS_CONNECT_TIMEOUT_SECS),
+            max_retries: IAS_MAX_RETRIES,
+            base_backoff: Duration::from_millis(IAS_BASE_BACKOFF_MS),
+        }
+    }
+}
+
+impl IasClientConfig {
+    /// Reads the bounds from `SECURE_SIGNER_IAS_TIMEOUT_SECS`,
+    /// `SECURE_SIGNER_IAS_CONNECT_TIMEOUT_SECS`, `SECURE_SIGNER_IAS_MAX_RETRIES` and
+    /// `SECURE_SIGNER_IAS_BASE_BACKOFF_MS`, defaulting any unset one. Fails on a malformed value.
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        Ok(IasClientConfig {
+            timeout: Duration::from_secs(env_or(
+                "SECURE_SIGNER_IAS_TIMEOUT_SECS",
+                defaults.timeout.as_secs(),
+            )?),
+            connect_timeout: Duration::from_secs(env_or(
+                "SECURE_SIGNER_IAS_CONNECT_TIMEOUT_SECS",
+                defaults.connect_timeout.as_secs(),
+            )?),
+            max_retries: env_or("SECURE_SIGNER_IAS_MAX_RETRIES", defaults.max_retries)?,
+            base_backoff: Duration::from_millis(env_or(
+                "SECURE_SIGNER_IAS_BASE_BACKOFF_MS",
+                defaults.base_backoff.as_millis() as u64,
+            )?),
+        })
+    }
+
+    // Whole seconds for the IAS client, which treats 0 as its own default
+    fn timeout_secs(&self) -> c_long {
+        secs_rounded_up(self.timeout)
+    }
+
+    fn connect_timeout_secs(&self) -> c_long {
+        secs_rounded_up(self.connect_timeout)
+    }
+
+    fn backoff(&self, retry: u32) -> Duration {
+        self.base_backoff.saturating_mul(1 << retry.min(16))
+    }
+
+    /// Upper bound on the time spent across every attempt and backoff
+    pub fn max_duration(&self) -> Duration {
+        let backoff: Duration = (0..self.max_retries).map(|i| self.backoff(i)).sum();
+        self.timeout
+            .saturating_mul(self.max_retries + 1)
+            .saturating_add(backoff)
+    }
+}
+
+fn secs_rounded_up(d: Duration) -> c_long {
+    let secs = d.as_secs() + u64::from(d.subsec_nanos() > 0);
+    c_long::try_from(secs).unwrap_or(c_long::MAX)
+}
+
+// The value of the env var `name`, or `default` if it is unset or empty
+fn env_or<T>(name: &str, default: T) -> Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Debug,
+{
+    match std::env::var(name) {
+        Ok(v) if !v.is_empty() => v.parse().map_err(|e| anyhow!("Bad {name} {v}: {:?}", e)),
+        _ => Ok(default),
+    }
+}
+
+/// The attestation service could not be reached within the configured bounds
+#[derive(Debug)]
+pub struct AttestationUnavailable {
+    pub attempts: u32,
+    pub reason: String,
+}
+
+impl std::fmt::Display for AttestationUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Remote attestation service unavailable after {} attempt(s): {}",
+            self.attempts, self.reason
+        )
+    }
+}
+
+impl std::error::Error for AttestationUnavailable {}
+
+impl AttestationUnavailable {
+    /// Returns true if `e` was caused by an unreachable attestation service
+    pub fn is_cause_of(e: &anyhow::Error) -> bool {
+        e.chain().any(|c| c.is::<AttestationUnavailable>())
+    }
+}
+
+/// Runs `attempt` until it succeeds, retrying up to `config.max_retries` times with exponential
+/// backoff. Each attempt must bound itself by `config`'s timeouts, as the IAS client does, so no
+/// attempt outlives this call or overlaps the next one.
+pub fn with_ias_retries<T, F>(config: &IasClientConfig, mut attempt: F) -> Result<T>
+where
+    F: FnMut() -> Result<T>,
+{
+    let mut reason = String::new();
+    for i in 0..=config.max_retries {
+        if i > 0 {
+            thread::sleep(config.backoff(i - 1));
+        }
+
+        match attempt() {
+            Ok(v) => return Ok(v),
+            Err(e) => reason = format!("{:?}", e),
+        }
+        warn!("Remote attestation attempt {} failed: {}", i + 1, reason);
+    }
+
+    Err(AttestationUnavailable {
+        attempts: config.max_retries + 1,
+        reason,
+    }
+    .into())
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
@@ -38,24 +372,99 @@ pub struct AttestationEvidence {
     pub raw_report: String,
     pub signed_report: String,
     pub signing_cert: String,
+    /// The mode that produced this evidence, evidence from before DCAP support is EPID
+    #[serde(default)]
+    pub attestation_type: AttestationType,
+    /// Base64 ECDSA quote, set for DCAP evidence
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub dcap_quote: String,
+    /// The collateral the DCAP quote was produced against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dcap_collateral: Option<DcapCollateral>,
 }
 
 impl AttestationEvidence {
     pub fn new(data: &[u8]) -> Result<Self> {
+        Self::new_with_config(data, &IasClientConfig::from_env()?)
+    }
+
+    /// Attests to `data` with the types from `attestation_preference`, bounding the IAS round trip
+    /// by `config`. Fails with `AttestationUnavailable` if no attestation type is available.
+    pub fn new_with_config(data: &[u8], config: &IasClientConfig) -> Result<Self> {
+        Self::new_with_preference(data, config, &attestation_preference()?)
+    }
+
+    /// Attests to `data` with each type in `preference` in turn, falling back to the next one
+    /// while the previous is unavailable. The evidence records the type that produced it.
+    pub fn new_with_preference(
+        data: &[u8],
+        config: &IasClientConfig,
+        preference: &[AttestationType],
+    ) -> Result<Self> {
         info!("Attempting Remote Attestation");
         if data.len() > 64 {
             bail!("remote attestation report data exceed 64B limit!")
         }
 
-        let mut sized_data: Vec<u8> = Vec::with_capacity(64);
-        // add the data
-        sized_data.extend_from_slice(data);
-        // pad any remaining bytes with 0
-        sized_data.extend_from_slice(&vec![0; 64 - data.len()]);
-
         let mut report_data = [0_u8; 64];
-        report_data.clone_from_slice(&sized_data[..]);
+        report_data[..data.len()].copy_from_slice(data);
+
+        Self::attest_in_order(preference, |attestation_type| match attestation_type {
+            AttestationType::Dcap => Self::dcap_ra(&report_data, dcap_collateral_path().as_deref()),
+            AttestationType::Epid => {
+                with_ias_retries(config, || Self::epid_ra(&report_data, config))
+            }
+        })
+    }
+
+    // Only an unavailable attestation type falls through, any other failure is returned as is
+    fn attest_in_order<F>(preference: &[AttestationType], mut attempt: F) -> Result<Self>
+    where
+        F: FnMut(AttestationType) -> Result<Self>,
+    {
+        let mut last_err = None;
+        for attestation_type in preference.iter().copied() {
+            match attempt(attestation_type) {
+                Ok(mut evidence) => {
+                    evidence.attestation_type = attestation_type;
+                    return Ok(evidence);
+                }
+                Err(e) if AttestationUnavailable::is_cause_of(&e) => {
+                    warn!("{:?} attestation unavailable: {:?}", attestation_type, e);
+                    last_err = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| anyhow!("No remote attestation types configured")))
+    }
 
+    // Attests with the collateral at `collateral_path`, which is unavailable if unset or unreadable
+    fn dcap_ra(report_data: &[u8; 64], collateral_path: Option<&str>) -> Result<Self> {
+        let unavailable = |e: anyhow::Error| AttestationUnavailable {
+            attempts: 1,
+            reason: format!("{:?}", e),
+        };
+        let collateral = load_dcap_collateral(collateral_path).map_err(unavailable)?;
+        let quote = generate_dcap_quote(report_data).map_err(unavailable)?;
+        Ok(AttestationEvidence {
+            attestation_type: AttestationType::Dcap,
+            dcap_quote: openssl::base64::encode_block(&quote),
+            dcap_collateral: Some(collateral),
+            ..Default::default()
+        })
+    }
+
+    fn epid_ra(report_data: &[u8; 64], config: &IasClientConfig) -> Result<Self> {
+        Self::epid_ra_with(report_data, config, do_epid_ra)
+    }
+
+    // Marshals the report buffers through `ffi`, which fills them in from IAS
+    fn epid_ra_with(
+        report_data: &[u8; 64],
+        config: &IasClientConfig,
+        ffi: EpidRaFn,
+    ) -> Result<Self> {
         // sufficient sized buffers
         //TODO: vec with capacity; Currently using vec with capacity leads to failure in attestation
         let a = [1_u8; 5000].to_vec();
@@ -77,7 +486,14 @@ impl AttestationEvidence {
 
         unsafe {
             // call cpp EPID remote attestation lib
-            do_epid_ra(&report_data as *const u8, raw_rpt, raw_sig, raw_cert);
+            ffi(
+                report_data as *const u8,
+                raw_rpt,
+                raw_sig,
+                raw_cert,
+                config.timeout_secs(),
+                config.connect_timeout_secs(),
+            );
             _rpt = CString::from_raw(raw_rpt);
             _sig = CString::from_raw(raw_sig);
             _cert = CString::from_raw(raw_cert);
@@ -90,13 +506,68 @@ impl AttestationEvidence {
         let signing_cert =
             String::from_utf8(_cert.to_bytes().to_vec()).expect("failed to conv to String");
 
+        // do_epid_ra leaves the filler bytes untouched when IAS could not produce a report
+        if raw_report.as_bytes().first() == Some(&1) {
+            bail!("IAS did not return an attestation report")
+        }
+
         Ok(AttestationEvidence {
             raw_report,
             signed_report,
             signing_cert,
+            ..Default::default()
         })
     }
 
+    /// Verifies the evidence according to the mode that produced it: the IAS signing certificate
+    /// for EPID, or the quote and its collateral for DCAP
+    pub fn verify(&self) -> Result<()> {
+        match self.attestation_type {
+            AttestationType::Epid => self.verify_intel_signing_certificate(),
+            AttestationType::Dcap => self
+                .verify_dcap_quote(&dcap_verifier_from_env()?)
+                .map(|_| ()),
+        }
+    }
+
+    /// Verifies the DCAP quote against the collateral it carries, returning the TCB verdict
+    pub fn verify_dcap_quote(&self, verifier: &DcapCollateralVerifier) -> Result<TcbVerdict> {
+        if self.attestation_type != AttestationType::Dcap {
+            bail!(
+                "Evidence was produced by {:?} attestation",
+                self.attestation_type
+            )
+        }
+        let Some(collateral) = &self.dcap_collateral else {
+            bail!("DCAP evidence is missing its collateral")
+        };
+        verifier.verify_quote(&self.dcap_quote_bytes()?, collateral)
+    }
+
+    fn dcap_quote_bytes(&self) -> Result<Vec<u8>> {
+        openssl::base64::decode_block(&self.dcap_quote).with_context(|| "Bad base64 DCAP quote")
+    }
+
+    // The quote header and enclave report body from the IAS report or the DCAP quote
+    fn quote_body(&self) -> Result<QuoteBody> {
+        match self.attestation_type {
+            AttestationType::Epid => {
+                let report: AttestationReport = serde_json::from_slice(self.raw_report.as_bytes())
+                    .with_context(|| {
+                        "Couldn't get AttestationReport from AttestationEvidence.raw_report"
+                    })?;
+                report.deserialize_quote_body()
+            }
+            AttestationType::Dcap => {
+                let quote = self.dcap_quote_bytes()?;
+                let Some(body) = quote.get(..QUOTE_SIGNED_BYTES) else {
+                    bail!("DCAP quote of {}B is too short", quote.len())
+                };
+                QuoteBody::from_bytes(body)
+            }
+        }
+    }
+
     /// Verifies attestation evidence IAS signatures. During remote attestation
     /// IAS returns their signing certificate and root CA as concatenated PEMs.
     /// This function verifies that the signing certificate is rooted in Intel's root CA.
@@ -178,24 +649,39 @@ impl AttestationEvidence {
         }
     }
 
+    /// Attests to `pk_bytes` packed according to `layout`
+    pub fn new_with_layout(
+        pk_bytes: &[u8],
+        layout: ReportDataLayout,
+        nonce: Option<&[u8; 32]>,
+    ) -> Result<Self> {
+        let report_data = layout.build(pk_bytes, nonce)?;
+        Self::new(&report_data)
+    }
+
     pub fn get_report_data(&self) -> Result<[u8; 64]> {
-        let report: AttestationReport = serde_json::from_slice(self.raw_report.as_bytes())
-            .with_context(|| {
-                "Couldn't get AttestationReport from AttestationEvidence.raw_report"
-            })?;
-        let body = report.deserialize_quote_body()?;
+        let body = self.quote_body()?;
         let mut report_bytes: [u8; 64] = [0_u8; 64];
         report_bytes.clone_from_slice(&body.REPORTDATA[0..64]);
         Ok(report_bytes)
     }
 
+    /// Verifies all 64 report data bytes equal `expected`, including any trailing padding
+    pub fn verify_report_data_exact(&self, expected: [u8; 64]) -> Result<()> {
+        let got = self.get_report_data()?;
+        if got != expected {
+            bail!(
+                "Remote attestation report data mismatch: expected {}, got {}",
+                hex::encode(expected),
+                hex::encode(got)
+            )
+        }
+        Ok(())
+    }
+
     /// Expets the BLS public key to be in the first 48 bytes of report body
     pub fn get_bls_pk(&self) -> Result<PublicKey> {
-        let report: AttestationReport = serde_json::from_slice(self.raw_report.as_bytes())
-            .with_context(|| {
-                "Couldn't get AttestationReport from AttestationEvidence.raw_report"
-            })?;
-        let body = report.deserialize_quote_body()?;
+        let body = self.quote_body()?;
         let mut pk_bytes: [u8; 48] = [0_u8; 48];
         pk_bytes.clone_from_slice(&body.REPORTDATA[0..48]);
         match  PublicKey::from_bytes(pk_bytes) {
@@ -205,31 +691,19 @@ impl AttestationEvidence {
     }
 
     pub fn get_eth_pk(&self) -> Result<EthPublicKey> {
-        let report: AttestationReport = serde_json::from_slice(self.raw_report.as_bytes())
-            .with_context(|| {
-                "Couldn't get AttestationReport from AttestationEvidence.raw_report"
-            })?;
-        let body = report.deserialize_quote_body()?;
+        let body = self.quote_body()?;
         let pk_bytes = &body.REPORTDATA[0..33];
         let pk = EthPublicKey::parse_slice(pk_bytes, None)?;
         Ok(pk)
     }
 
     pub fn get_mrenclave(&self) -> Result<String> {
-        let report: AttestationReport = serde_json::from_slice(self.raw_report.as_bytes())
-            .with_context(|| {
-                "Couldn't get AttestationReport from AttestationEvidence.raw_report"
-            })?;
-        let body = report.deserialize_quote_body()?;
+        let body = self.quote_body()?;
         Ok(body.MRENCLAVE)
     }
 
     pub fn get_mrsigner(&self) -> Result<String> {
-        let report: AttestationReport = serde_json::from_slice(self.raw_report.as_bytes())
-            .with_context(|| {
-                "Couldn't get AttestationReport from AttestationEvidence.raw_report"
-            })?;
-        let body = report.deserialize_quote_body()?;
+        let body = self.quote_body()?;
         Ok(body.MRSIGNER)
     }
 }
@@ -271,7 +745,14 @@ impl AttestationReport {
     pub fn deserialize_quote_body(&self) -> Result<QuoteBody> {
         let body = &self.isvEnclaveQuoteBody;
         let body_decoded = openssl::base64::decode_block(body)?;
+        QuoteBody::from_bytes(&body_decoded)
+    }
+}
 
+impl QuoteBody {
+    /// Parses the 48B quote header and 384B report body shared by EPID and DCAP v3 quotes. DCAP
+    /// headers have no GID or basename, those fields then hold the QE vendor ID and user data.
+    pub fn from_bytes(body_decoded: &[u8]) -> Result<Self> {
         if body_decoded.len() != 432 {
             bail!("base64 decoded quote body was not the right length of 432B!")
         }
@@ -361,4 +842,277 @@ mod tests {
         assert_eq!(exp_eth_pk, got_pk);
         Ok(())
     }
+
+    #[test]
+    fn test_report_data_layouts_round_trip() -> Result<()> {
+        let pk = eth_keys::new_eth_key()?.1;
+        let pk_bytes = pk.serialize_compressed();
+        let other_pk_bytes = eth_keys::new_eth_key()?.1.serialize_compressed();
+        let nonce = [7_u8; 32];
+
+        for (layout, nonce) in [
+            (ReportDataLayout::RawPubkey, None),
+            (ReportDataLayout::Sha256Pubkey, None),
+            (ReportDataLayout::Sha256PubkeyWithNonce, Some(&nonce)),
+        ] {
+            let report_data = layout.build(&pk_bytes, nonce)?;
+            layout.verify(&report_data, &pk_bytes, nonce)?;
+            assert!(layout.verify(&report_data, &other_pk_bytes, nonce).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_report_data_layouts_are_distinct() -> Result<()> {
+        let pk_bytes = eth_keys::new_eth_key()?.1.serialize_compressed();
+        let nonce = [7_u8; 32];
+
+        let raw = ReportDataLayout::default().build(&pk_bytes, None)?;
+        assert_eq!(&raw[0..33], &pk_bytes[..]);
+        assert!(raw[33..].iter().all(|b| *b == 0));
+
+        let hashed = ReportDataLayout::Sha256Pubkey.build(&pk_bytes, None)?;
+        assert!(ReportDataLayout::RawPubkey
+            .verify(&hashed, &pk_bytes, None)
+            .is_err());
+
+        let with_nonce = ReportDataLayout::Sha256PubkeyWithNonce.build(&pk_bytes, Some(&nonce))?;
+        assert_eq!(&with_nonce[0..32], &hashed[0..32]);
+        assert!(ReportDataLayout::Sha256PubkeyWithNonce
+            .verify(&with_nonce, &pk_bytes, Some(&[8_u8; 32]))
+            .is_err());
+        assert!(ReportDataLayout::Sha256PubkeyWithNonce
+            .build(&pk_bytes, None)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unresponsive_ias_fails_within_bound() {
+        use std::io::{Read, Write};
+        use std::net::{SocketAddr, TcpListener, TcpStream};
+        use std::sync::OnceLock;
+
+        static IAS_ADDR: OnceLock<SocketAddr> = OnceLock::new();
+
+        // Stands in for the IAS client, which talks to IAS within the timeouts it is given
+        // and leaves the buffers untouched when no report comes back
+        unsafe extern "C" fn hung_epid_ra(
+            _data: *const u8,
+            _report: *mut c_char,
+            _signature: *mut c_char,
+            _signing_cert: *mut c_char,
+            timeout_secs: c_long,
+            connect_timeout_secs: c_long,
+        ) {
+            let secs = |s: c_long| Duration::from_secs(s as u64);
+            let addr = IAS_ADDR.get().unwrap();
+            if let Ok(mut stream) = TcpStream::connect_timeout(addr, secs(connect_timeout_secs)) {
+                stream.set_read_timeout(Some(secs(timeout_secs))).unwrap();
+                stream
+                    .write_all(b"POST /attestation/v4/report HTTP/1.1\r\n\r\n")
+                    .unwrap();
+                let _ = stream.read_to_end(&mut Vec::new());
+            }
+        }
+
+        // Mock IAS endpoint that accepts connections but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        IAS_ADDR.set(listener.local_addr().unwrap()).unwrap();
+
+        let config = IasClientConfig {
+            timeout: Duration::from_secs(1),
+            connect_timeout: Duration::from_secs(1),
+            max_retries: 1,
+            base_backoff: Duration::from_millis(50),
+        };
+        let start = std::time::Instant::now();
+        let res = with_ias_retries(&config, || {
+            AttestationEvidence::epid_ra_with(&[7; 64], &config, hung_epid_ra)
+        });
+        let elapsed = start.elapsed();
+
+        let err = res.unwrap_err();
+        assert!(AttestationUnavailable::is_cause_of(&err));
+        assert_eq!(
+            err.downcast_ref::<AttestationUnavailable>()
+                .unwrap()
+                .attempts,
+            2
+        );
+        assert!(elapsed >= config.timeout * 2);
+        assert!(elapsed < config.max_duration() + Duration::from_millis(500));
+        drop(listener);
+    }
+
+    #[test]
+    fn test_ias_retry_recovers_after_failure() -> Result<()> {
+        use std::sync::atomic::{AtomicI64, Ordering};
+
+        static TIMEOUTS: [AtomicI64; 2] = [AtomicI64::new(0), AtomicI64::new(0)];
+
+        // Stands in for IAS, recording the timeouts it was given and answering with a report
+        unsafe extern "C" fn fake_epid_ra(
+            _data: *const u8,
+            report: *mut c_char,
+            signature: *mut c_char,
+            signing_cert: *mut c_char,
+            timeout_secs: c_long,
+            connect_timeout_secs: c_long,
+        ) {
+            TIMEOUTS[0].store(timeout_secs as i64, Ordering::SeqCst);
+            TIMEOUTS[1].store(connect_timeout_secs as i64, Ordering::SeqCst);
+            for (buf, v) in [
+                (report, "report\0"),
+                (signature, "sig\0"),
+                (signing_cert, "cert\0"),
+            ] {
+                std::ptr::copy_nonoverlapping(v.as_ptr() as *const c_char, buf, v.len());
+            }
+        }
+
+        let config = IasClientConfig {
+            timeout: Duration::from_millis(1500),
+            connect_timeout: Duration::from_secs(1),
+            max_retries: 2,
+            base_backoff: Duration::from_millis(10),
+        };
+        let mut calls = 0;
+        let got = with_ias_retries(&config, || {
+            calls += 1;
+            if calls == 1 {
+                bail!("connection refused")
+            }
+            AttestationEvidence::epid_ra_with(&[7; 64], &config, fake_epid_ra)
+        })?;
+        assert_eq!(calls, 2);
+        assert_eq!(got.raw_report, "report");
+        assert_eq!(got.signed_report, "sig");
+        assert_eq!(got.signing_cert, "cert");
+        assert_eq!(TIMEOUTS[0].load(Ordering::SeqCst), 2);
+        assert_eq!(TIMEOUTS[1].load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ias_config_from_env() -> Result<()> {
+        assert_eq!(env_or("SECURE_SIGNER_TEST_UNSET_IAS_VALUE", 7_u32)?, 7);
+        std::env::set_var("SECURE_SIGNER_TEST_IAS_VALUE", "12");
+        assert_eq!(env_or("SECURE_SIGNER_TEST_IAS_VALUE", 7_u32)?, 12);
+        std::env::set_var("SECURE_SIGNER_TEST_IAS_VALUE", "twelve");
+        assert!(env_or("SECURE_SIGNER_TEST_IAS_VALUE", 7_u32).is_err());
+        std::env::remove_var("SECURE_SIGNER_TEST_IAS_VALUE");
+        assert_eq!(secs_rounded_up(Duration::from_millis(1)), 1);
+        assert_eq!(secs_rounded_up(Duration::from_secs(3)), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dcap_unavailable_falls_back_to_epid() -> Result<()> {
+        let preference = [AttestationType::Dcap, AttestationType::Epid];
+        let mut tried = Vec::new();
+        let evidence = AttestationEvidence::attest_in_order(&preference, |attestation_type| {
+            tried.push(attestation_type);
+            match attestation_type {
+                AttestationType::Dcap => Err(AttestationUnavailable {
+                    attempts: 1,
+                    reason: "no DCAP collateral".to_string(),
+                }
+                .into()),
+                AttestationType::Epid => Ok(fetch_dummy_bls_evidence()),
+            }
+        })?;
+        assert_eq!(tried, preference.to_vec());
+        assert_eq!(evidence.attestation_type, AttestationType::Epid);
+
+        // The recorded mode survives serialization and selects the EPID verifier
+        let evidence: AttestationEvidence =
+            serde_json::from_str(&serde_json::to_string(&evidence)?)?;
+        assert_eq!(evidence.attestation_type, AttestationType::Epid);
+        evidence.verify()?;
+        assert!(evidence
+            .verify_dcap_quote(&DcapCollateralVerifier::new(
+                &X509::stack_from_pem(evidence.signing_cert.as_bytes())?[1].to_pem()?
+            )?)
+            .is_err());
+
+        // Without collateral DCAP attestation is unavailable rather than failing outright
+        let err = AttestationEvidence::dcap_ra(&[0; 64], None).unwrap_err();
+        assert!(AttestationUnavailable::is_cause_of(&err));
+        let err = AttestationEvidence::dcap_ra(&[0; 64], Some("/nonexistent/collateral.json"))
+            .unwrap_err();
+        assert!(AttestationUnavailable::is_cause_of(&err));
+
+        // Other failures are not masked by falling back
+        assert!(
+            AttestationEvidence::attest_in_order(&preference, |attestation_type| {
+                match attestation_type {
+                    AttestationType::Dcap => bail!("malformed quote"),
+                    AttestationType::Epid => Ok(fetch_dummy_bls_evidence()),
+                }
+            })
+            .is_err()
+        );
+        assert!(
+            AttestationEvidence::attest_in_order(&[], |_| Ok(fetch_dummy_bls_evidence())).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dcap_evidence_reads_quote_body() -> Result<()> {
+        let mut quote = vec![0_u8; QUOTE_SIGNED_BYTES + 4];
+        quote[48 + 64..48 + 96].copy_from_slice(&[0xaa; 32]);
+        quote[48 + 128..48 + 160].copy_from_slice(&[0xbb; 32]);
+        quote[48 + 320..48 + 384].copy_from_slice(&[0xcc; 64]);
+        let evidence = AttestationEvidence {
+            attestation_type: AttestationType::Dcap,
+            dcap_quote: openssl::base64::encode_block(&quote),
+            ..Default::default()
+        };
+        assert_eq!(evidence.get_mrenclave()?, hex::encode([0xaa; 32]));
+        assert_eq!(evidence.get_mrsigner()?, hex::encode([0xbb; 32]));
+        assert_eq!(evidence.get_report_data()?, [0xcc; 64]);
+
+        // DCAP evidence is never checked against the IAS path
+        assert!(evidence.verify().is_err());
+        assert_eq!(
+            "dcap, EPID"
+                .split(',')
+                .map(|t| t.parse())
+                .collect::<Result<Vec<AttestationType>>>()?,
+            vec![AttestationType::Dcap, AttestationType::Epid]
+        );
+        assert!("tdx".parse::<AttestationType>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_report_data_trailing_bytes_are_zeroed() -> Result<()> {
+        for (evidence, pk_bytes) in [
+            (
+                fetch_dummy_bls_evidence(),
+                fetch_dummy_bls_evidence().get_bls_pk()?.to_bytes().to_vec(),
+            ),
+            (
+                fetch_dummy_eth_evidence(),
+                fetch_dummy_eth_evidence()
+                    .get_eth_pk()?
+                    .serialize_compressed()
+                    .to_vec(),
+            ),
+        ] {
+            let got = evidence.get_report_data()?;
+            assert!(got[pk_bytes.len()..].iter().all(|b| *b == 0));
+
+            let expected = ReportDataLayout::RawPubkey.build(&pk_bytes, None)?;
+            evidence.verify_report_data_exact(expected)?;
+
+            // A single differing trailing byte is rejected
+            let mut tampered = expected;
+            tampered[63] = 1;
+            assert!(evidence.verify_report_data_exact(tampered).is_err());
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,159 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes128Gcm, Nonce};
+use anyhow::{anyhow, bail, Result};
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+// Prefixes sealed blobs, so a blob of another format fails to unseal rather than decrypt garbage
+const SEALED_VERSION: u8 = 1;
+const SEALED_NONCE_BYTES: usize = 12;
+const SEALED_TAG_BYTES: usize = 16;
+
+/// Encrypts `plaintext` under this enclave's sealing key, binding `aad` (e.g. what the blob is)
+/// so a blob sealed for one purpose cannot be unsealed for another
+pub fn seal(plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    let cipher = sealing_cipher()?;
+    let mut nonce = [0_u8; SEALED_NONCE_BYTES];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| anyhow!("Failed to seal data"))?;
+    let mut sealed = Vec::with_capacity(1 + SEALED_NONCE_BYTES + ciphertext.len());
+    sealed.push(SEALED_VERSION);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypts a blob produced by `seal` with the same `aad`. Fails for blobs sealed by another
+/// enclave signer or modified since.
+pub fn unseal(sealed: &[u8], aad: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    if sealed.len() < 1 + SEALED_NONCE_BYTES + SEALED_TAG_BYTES {
+        bail!("Sealed data is too short")
+    }
+    if sealed[0] != SEALED_VERSION {
+        bail!("Unknown sealed data version {}", sealed[0])
+    }
+    let (nonce, ciphertext) = sealed[1..].split_at(SEALED_NONCE_BYTES);
+    let plaintext = sealing_cipher()?
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| anyhow!("Failed to unseal data"))?;
+    Ok(Zeroizing::new(plaintext))
+}
+
+fn sealing_cipher() -> Result<Aes128Gcm> {
+    let key = sealing_key()?;
+    Aes128Gcm::new_from_slice(&key[..]).map_err(|e| anyhow!("Bad sealing key length: {:?}", e))
+}
+
+/// Derives the MRSIGNER-bound seal key through Occlum's `/dev/sgx` ioctls, so builds of the same
+/// signer that are upgraded in place can still unseal
+#[cfg(feature = "sgx")]
+fn sealing_key() -> Result<Zeroizing<[u8; 16]>> {
+    use anyhow::Context;
+    use std::os::unix::io::AsRawFd;
+
+    const SGXIOC_GET_KEY: u64 = 0xc010730b;
+    const SGX_KEYSELECT_SEAL: u16 = 4;
+    const SGX_KEYPOLICY_MRSIGNER: u16 = 2;
+    // Every attribute but the mode bits, as the SGX SDK masks them for sealing
+    const SGX_FLAGS_MASK: u64 = 0xffff_ffff_ffff_fff3;
+    const SGX_XFRM_MASK: u64 = 0;
+    const SGX_MISC_MASK: u32 = 0xf000_0000;
+
+    #[repr(C)]
+    struct KeyRequest {
+        key_name: u16,
+        key_policy: u16,
+        isv_svn: u16,
+        reserved1: u16,
+        cpu_svn: [u8; 16],
+        attribute_mask_flags: u64,
+        attribute_mask_xfrm: u64,
+        key_id: [u8; 32],
+        misc_mask: u32,
+        config_svn: u16,
+        reserved2: [u8; 434],
+    }
+
+    #[repr(C)]
+    struct GetKeyArg {
+        key_request: *const KeyRequest,
+        key: *mut [u8; 16],
+    }
+
+    let request = KeyRequest {
+        key_name: SGX_KEYSELECT_SEAL,
+        key_policy: SGX_KEYPOLICY_MRSIGNER,
+        isv_svn: 0,
+        reserved1: 0,
+        cpu_svn: [0; 16],
+        attribute_mask_flags: SGX_FLAGS_MASK,
+        attribute_mask_xfrm: SGX_XFRM_MASK,
+        key_id: *b"puffer-secure-signer-sealing-key",
+        misc_mask: SGX_MISC_MASK,
+        config_svn: 0,
+        reserved2: [0; 434],
+    };
+    let mut key = Zeroizing::new([0_u8; 16]);
+    let mut arg = GetKeyArg {
+        key_request: &request,
+        key: &mut *key,
+    };
+    let sgx = std::fs::File::open("/dev/sgx").with_context(|| "Failed to open /dev/sgx")?;
+    let ret = unsafe {
+        libc::ioctl(
+            sgx.as_raw_fd(),
+            SGXIOC_GET_KEY as _,
+            &mut arg as *mut GetKeyArg,
+        )
+    };
+    if ret < 0 {
+        bail!(
+            "Failed to get the sealing key: {}",
+            std::io::Error::last_os_error()
+        )
+    }
+    Ok(key)
+}
+
+#[cfg(not(feature = "sgx"))]
+// Local development has no enclave to seal to, so a fixed key only exercises the format
+fn sealing_key() -> Result<Zeroizing<[u8; 16]>> {
+    Ok(Zeroizing::new(*b"dev-sealing-key!"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_unseal() {
+        let sealed = seal(b"secret", b"purpose").unwrap();
+        assert_eq!(unseal(&sealed, b"purpose").unwrap().as_slice(), b"secret");
+        let sealed_again = seal(b"secret", b"purpose").unwrap();
+        assert_ne!(sealed, sealed_again);
+
+        // Another purpose, a modified blob or another format fails to unseal
+        assert!(unseal(&sealed, b"other purpose").is_err());
+        let mut modified = sealed.clone();
+        *modified.last_mut().unwrap() ^= 1;
+        assert!(unseal(&modified, b"purpose").is_err());
+        let mut versioned = sealed.clone();
+        versioned[0] = 2;
+        assert!(unseal(&versioned, b"purpose").is_err());
+        assert!(unseal(&sealed[..20], b"purpose").is_err());
+    }
+}
@@ -0,0 +1,274 @@
+use crate::constants::{WEBHOOK_QUEUE_CAPACITY, WEBHOOK_TIMEOUT_MILLIS};
+use crate::io::key_management::KeyType;
+
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Header carrying `sha256=<hex HMAC-SHA256 of the body>` on every webhook delivery
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Secure-Signer-Signature";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOperation {
+    Keygen,
+    Sign,
+    Import,
+    Delete,
+}
+
+/// Metadata of a successful keygen, sign, import or delete pushed to the audit webhook. Never carries secrets.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AuditEvent {
+    pub operation: AuditOperation,
+    pub key_type: KeyType,
+    /// Hex encoded public key, without a 0x prefix
+    pub pubkey: String,
+    /// The Web3Signer `type` of a signed message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_root: Option<String>,
+    /// Unix seconds
+    pub timestamp: u64,
+}
+
+impl AuditEvent {
+    pub fn keygen(key_type: KeyType, pubkey: &str) -> Self {
+        let pubkey: String = crate::strip_0x_prefix!(pubkey);
+        AuditEvent {
+            operation: AuditOperation::Keygen,
+            key_type,
+            pubkey,
+            signing_type: None,
+            signing_root: None,
+            timestamp: crate::io::unix_now().unwrap_or_default(),
+        }
+    }
+
+    pub fn sign(
+        pubkey: &str,
+        signing_type: &str,
+        signing_root: &crate::eth2::eth_types::Root,
+    ) -> Self {
+        AuditEvent {
+            operation: AuditOperation::Sign,
+            signing_type: Some(signing_type.to_string()),
+            signing_root: Some(format!("0x{}", hex::encode(signing_root))),
+            ..AuditEvent::keygen(KeyType::Bls, pubkey)
+        }
+    }
+
+    pub fn import(pubkey: &str) -> Self {
+        AuditEvent {
+            operation: AuditOperation::Import,
+            ..AuditEvent::keygen(KeyType::Bls, pubkey)
+        }
+    }
+
+    pub fn delete(pubkey: &str) -> Self {
+        AuditEvent {
+            operation: AuditOperation::Delete,
+            ..AuditEvent::keygen(KeyType::Bls, pubkey)
+        }
+    }
+}
+
+/// Where audit events are delivered and the key their payloads are authenticated with
+#[derive(Clone, Debug)]
+pub struct WebhookConfig {
+    pub url: String,
+    pub hmac_key: Vec<u8>,
+}
+
+impl WebhookConfig {
+    /// Reads `SECURE_SIGNER_WEBHOOK_URL` and the hex encoded `SECURE_SIGNER_WEBHOOK_HMAC_KEY`.
+    /// Returns None if no URL is set, and errors if a URL is set without a key.
+    pub fn from_env() -> Result<Option<Self>> {
+        let url = match std::env::var("SECURE_SIGNER_WEBHOOK_URL") {
+            Ok(url) if !url.is_empty() => url,
+            _ => return Ok(None),
+        };
+        let key_hex = std::env::var("SECURE_SIGNER_WEBHOOK_HMAC_KEY")
+            .with_context(|| "SECURE_SIGNER_WEBHOOK_HMAC_KEY must be set with a webhook URL")?;
+        let key_hex: String = crate::strip_0x_prefix!(key_hex);
+        let hmac_key =
+            hex::decode(key_hex).with_context(|| "Unable to hex-decode the webhook HMAC key")?;
+        Ok(Some(WebhookConfig { url, hmac_key }))
+    }
+}
+
+/// Returns the `WEBHOOK_SIGNATURE_HEADER` value authenticating `body` under `key`
+pub fn sign_payload(key: &[u8], body: &[u8]) -> String {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Best-effort delivery of audit events. Events are queued without blocking and posted one at a
+/// time by a background task, so a slow or failing receiver never delays or fails a response.
+pub struct Webhook {
+    tx: tokio::sync::mpsc::Sender<AuditEvent>,
+}
+
+impl Webhook {
+    /// Starts the delivery task, must be called from within a tokio runtime
+    pub fn spawn(config: WebhookConfig) -> Self {
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<AuditEvent>(WEBHOOK_QUEUE_CAPACITY);
+        tokio::spawn(async move {
+            let client = reqwest::Client::new();
+            while let Some(event) = rx.recv().await {
+                if let Err(e) = deliver(&client, &config, &event).await {
+                    warn!("Failed to deliver audit event to webhook: {:?}", e);
+                }
+            }
+        });
+        Webhook { tx }
+    }
+
+    /// Queues `event`, returning false if it was dropped because the queue is full
+    pub fn emit(&self, event: AuditEvent) -> bool {
+        match self.tx.try_send(event) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Dropping audit event: {}", e);
+                false
+            }
+        }
+    }
+}
+
+async fn deliver(
+    client: &reqwest::Client,
+    config: &WebhookConfig,
+    event: &AuditEvent,
+) -> Result<()> {
+    let body = serde_json::to_vec(event)?;
+    let signature = sign_payload(&config.hmac_key, &body);
+    client
+        .post(&config.url)
+        .timeout(Duration::from_millis(WEBHOOK_TIMEOUT_MILLIS))
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(WEBHOOK_SIGNATURE_HEADER, signature)
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+static WEBHOOK: OnceLock<Webhook> = OnceLock::new();
+
+/// Starts the process-wide webhook if one is configured by `WebhookConfig::from_env`. Returns
+/// true if it was started.
+pub fn init_webhook_from_env() -> Result<bool> {
+    let Some(config) = WebhookConfig::from_env()? else {
+        return Ok(false);
+    };
+    Ok(WEBHOOK.set(Webhook::spawn(config)).is_ok())
+}
+
+/// Queues `event` on the process-wide webhook, a no-op unless one was started
+pub fn emit(event: AuditEvent) {
+    if let Some(webhook) = WEBHOOK.get() {
+        webhook.emit(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Serves a receiver on a random local port, forwarding each delivery's signature and body
+    fn mock_receiver() -> (
+        String,
+        tokio::sync::mpsc::UnboundedReceiver<(String, Vec<u8>)>,
+    ) {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let app = axum::Router::new().route(
+            "/audit",
+            axum::routing::post(
+                move |headers: axum::http::HeaderMap, body: axum::body::Bytes| {
+                    let tx = tx.clone();
+                    async move {
+                        let signature = headers
+                            .get(WEBHOOK_SIGNATURE_HEADER)
+                            .and_then(|v| v.to_str().ok())
+                            .unwrap_or_default()
+                            .to_string();
+                        tx.send((signature, body.to_vec())).unwrap();
+                        axum::http::StatusCode::NO_CONTENT
+                    }
+                },
+            ),
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/audit", listener.local_addr().unwrap());
+        tokio::spawn(
+            axum::Server::from_tcp(listener)
+                .unwrap()
+                .serve(app.into_make_service()),
+        );
+        (url, rx)
+    }
+
+    #[tokio::test]
+    async fn test_webhook_delivers_signed_events() {
+        let (url, mut rx) = mock_receiver();
+        let hmac_key = b"webhook hmac key".to_vec();
+        let webhook = Webhook::spawn(WebhookConfig {
+            url,
+            hmac_key: hmac_key.clone(),
+        });
+
+        let keygen = AuditEvent::keygen(KeyType::Bls, &format!("0x{}", "ab".repeat(48)));
+        let sign = AuditEvent::sign(&"ab".repeat(48), "ATTESTATION", &[7_u8; 32]);
+        assert!(webhook.emit(keygen.clone()));
+        assert!(webhook.emit(sign.clone()));
+
+        for exp in [keygen, sign] {
+            let (signature, body) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(signature, sign_payload(&hmac_key, &body));
+            assert_ne!(signature, sign_payload(b"other key", &body));
+            let got: AuditEvent = serde_json::from_slice(&body).unwrap();
+            assert_eq!(got, exp);
+        }
+
+        let json = serde_json::to_value(AuditEvent::sign("ab", "BLOCK", &[0; 32])).unwrap();
+        assert_eq!(json["operation"], "sign");
+        assert_eq!(json["key_type"], "bls");
+        assert_eq!(json["signing_type"], "BLOCK");
+        let json = serde_json::to_value(AuditEvent::delete("0xab")).unwrap();
+        assert_eq!(json["operation"], "delete");
+        assert_eq!(json["pubkey"], "ab");
+        assert!(json.get("signing_root").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_never_blocks_on_a_full_queue() {
+        // Nothing listens on the URL, so deliveries fail and the queue fills up
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/audit", listener.local_addr().unwrap());
+        drop(listener);
+        let webhook = Webhook::spawn(WebhookConfig {
+            url,
+            hmac_key: vec![1; 32],
+        });
+
+        let event = AuditEvent::keygen(KeyType::Eth, "02ab");
+        let start = std::time::Instant::now();
+        let queued = (0..2 * WEBHOOK_QUEUE_CAPACITY)
+            .filter(|_| webhook.emit(event.clone()))
+            .count();
+        assert!(queued <= WEBHOOK_QUEUE_CAPACITY + 1);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
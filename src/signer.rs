@@ -0,0 +1,150 @@
+use crate::crypto::{bls_keys, key_cache, signature_cache};
+use crate::eth2::eth_signing::{self, BLSSignMsg};
+use crate::eth2::eth_types::Version;
+use crate::eth2::slash_protection;
+
+use anyhow::{anyhow, bail, Result};
+use blsttc::Signature;
+use std::sync::RwLock;
+
+/// In-process signing API for embedders that do not run the HTTP server. Signing applies the same
+/// fork version and slashing protection checks as the signing endpoints. Durability is driven
+/// explicitly through `flush` and `shutdown` rather than by signal handling.
+pub struct Signer {
+    genesis_fork_version: Version,
+    allow_genesis_fork: bool,
+    /// False once `shutdown` has been called. Signs hold the read lock for their whole duration,
+    /// so `shutdown` returns only after in-flight signs have finished.
+    accepting: RwLock<bool>,
+}
+
+impl Signer {
+    pub fn new(genesis_fork_version: Version) -> Self {
+        Signer {
+            genesis_fork_version,
+            allow_genesis_fork: false,
+            accepting: RwLock::new(true),
+        }
+    }
+
+    /// Allows consensus messages signed under the all-zero genesis fork version, see
+    /// `BLSSignMsg::check_fork_version`
+    pub fn allow_genesis_fork(mut self, allow: bool) -> Self {
+        self.allow_genesis_fork = allow;
+        self
+    }
+
+    /// Signs `req` with the saved BLS key `pk_hex`. Block proposals and attestations are checked
+    /// against and recorded in the slash protection DB by `eth_signing::sign_and_record`, the same
+    /// path the signing endpoints use, before the signature is returned.
+    pub fn sign(&self, pk_hex: &str, req: &BLSSignMsg) -> Result<Signature> {
+        let accepting = self
+            .accepting
+            .read()
+            .map_err(|e| anyhow!("Signer lock poisoned: {:?}", e))?;
+        if !*accepting {
+            bail!("Signer is shut down")
+        }
+        let pk_hex = bls_keys::sanitize_bls_pk_hex(&pk_hex.to_string())?;
+        if !crate::io::key_management::bls_key_exists(&pk_hex) {
+            bail!("Unknown BLS public key: {pk_hex}")
+        }
+        req.check_fork_version(self.allow_genesis_fork)?;
+
+        let signing_root = req.to_signing_root(Some(self.genesis_fork_version));
+        let sig = eth_signing::sign_and_record(
+            &pk_hex,
+            req.signing_type(),
+            req.slashable_fields(),
+            signing_root,
+            req.dst(),
+        )?;
+        Ok(sig)
+    }
+
+    /// Makes every committed slashing mark durable and drops cached secret keys and signatures
+    pub fn flush(&self) -> Result<()> {
+        slash_protection::sync_slashing_db()?;
+        key_cache::clear();
+        signature_cache::clear();
+        Ok(())
+    }
+
+    /// Stops accepting signs, waits for in-flight ones to finish, then flushes. Idempotent.
+    pub fn shutdown(&self) -> Result<()> {
+        let mut accepting = self
+            .accepting
+            .write()
+            .map_err(|e| anyhow!("Signer lock poisoned: {:?}", e))?;
+        *accepting = false;
+        self.flush()
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        match self.accepting.read() {
+            Ok(accepting) => !*accepting,
+            Err(_) => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eth2::eth_types::{
+        BeaconBlockHeader, BlockV2Request, BlockV2RequestWrapper, ForkInfo,
+    };
+    use crate::io::key_management::delete_bls_key;
+
+    fn block_v2(slot: u64) -> BLSSignMsg {
+        let mut fork_info = ForkInfo::default();
+        fork_info.fork.previous_version = [3, 0, 0, 0];
+        fork_info.fork.current_version = [4, 0, 0, 0];
+        BLSSignMsg::BLOCK_V2(BlockV2Request {
+            fork_info,
+            signingRoot: None,
+            beacon_block: BlockV2RequestWrapper {
+                version: "DENEB".to_string(),
+                block_header: BeaconBlockHeader {
+                    slot,
+                    proposer_index: 1,
+                    parent_root: [1; 32],
+                    state_root: [2; 32],
+                    body_root: [3; 32],
+                },
+            },
+        })
+    }
+
+    #[test]
+    fn test_flush_persists_latest_slashing_mark() {
+        let sk_set = bls_keys::new_bls_key(0);
+        let pk_hex = sk_set.public_keys().public_key().to_hex();
+        bls_keys::save_bls_key(&sk_set).unwrap();
+        slash_protection::SlashingProtectionData::from_pk_hex(&pk_hex)
+            .unwrap()
+            .write()
+            .unwrap();
+
+        let signer = Signer::new([0; 4]);
+        signer.sign(&pk_hex, &block_v2(10)).unwrap();
+        signer.sign(&pk_hex, &block_v2(11)).unwrap();
+        assert!(signer.sign(&pk_hex, &block_v2(11)).is_err());
+        signer.flush().unwrap();
+
+        let db = slash_protection::SlashingProtectionData::read(&pk_hex).unwrap();
+        assert_eq!(db.get_latest_signed_block_slot(), 11);
+        assert!(key_cache::get(&pk_hex).is_none());
+
+        // Still usable after a flush, but not after a shutdown
+        signer.sign(&pk_hex, &block_v2(12)).unwrap();
+        signer.shutdown().unwrap();
+        assert!(signer.is_shut_down());
+        assert!(signer.sign(&pk_hex, &block_v2(13)).is_err());
+        signer.shutdown().unwrap();
+
+        let db = slash_protection::SlashingProtectionData::read(&pk_hex).unwrap();
+        assert_eq!(db.get_latest_signed_block_slot(), 12);
+        delete_bls_key(&pk_hex).unwrap();
+    }
+}
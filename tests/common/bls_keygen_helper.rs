@@ -88,7 +88,7 @@ async fn test_bls_key_in_remote_attestation_evidence() {
         dbg!(&port);
 
         // Verify the report is valid
-        resp.evidence.verify_intel_signing_certificate().unwrap();
+        resp.evidence.verify().unwrap();
 
         // Verify the payload
         let pk_hex: String = strip_0x_prefix!(&resp.pk_hex);
@@ -87,7 +87,7 @@ async fn test_eth_key_in_remote_attestation_evidence() {
         dbg!(&resp.pk_hex);
 
         // Verify the report is valid
-        resp.evidence.verify_intel_signing_certificate().unwrap();
+        resp.evidence.verify().unwrap();
 
         // Verify the payload
         let pk = eth_keys::eth_pk_from_hex(&resp.pk_hex).unwrap();
@@ -97,5 +97,10 @@ async fn test_eth_key_in_remote_attestation_evidence() {
             hex::encode(&got_payload[0..ETH_COMPRESSED_PK_BYTES]),
             hex::encode(pk.serialize_compressed())
         );
+
+        // The trailing bytes must be zero padding rather than leftover memory
+        let mut expected = [0_u8; 64];
+        expected[0..ETH_COMPRESSED_PK_BYTES].copy_from_slice(&pk.serialize_compressed());
+        resp.evidence.verify_report_data_exact(expected).unwrap();
     }
 }
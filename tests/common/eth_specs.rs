@@ -111,7 +111,7 @@ fn get_test_vec_block(ssz_file: &Path, root_file: &Path) -> Result<BLSSignMsg> {
     let req = BlockRequest {
         fork_info: get_fork_info(),
         signingRoot: None,
-        block,
+        block: block.into(),
     };
     let b = BLSSignMsg::BLOCK(req);
     Ok(b)
@@ -233,6 +233,20 @@ fn get_test_vec_sync_committee_contribution_and_proof(
     Ok(s)
 }
 
+/// Loads every ssz_random vector of `container_name` in the mainnet spec tests of `fork`, checking
+/// each container's tree_root_hash against the expected root
+pub fn get_fork_test_containers<T: Decode + TreeHash>(
+    fork: &str,
+    container_name: &str,
+) -> Result<Vec<T>> {
+    let base_dir = format!("./tests/consensus-spec-tests/tests/mainnet/{fork}/ssz_static/");
+    let path: PathBuf = [base_dir.as_str(), container_name].iter().collect();
+    get_testvec_file_names(&path)?
+        .iter()
+        .map(|(ssz_file, root_file)| get_test_vec_container::<T>(ssz_file, root_file))
+        .collect()
+}
+
 pub fn get_all_test_vecs(container_name: &str) -> Result<Vec<BLSSignMsg>> {
     let path: PathBuf = [BASE_DIR, container_name].iter().collect();
     let file_paths = get_testvec_file_names(&path).unwrap();
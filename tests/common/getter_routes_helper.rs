@@ -1,7 +1,7 @@
 use crate::common::{
     bls_keygen_helper::register_new_bls_key, eth_keygen_helper::register_new_eth_key,
 };
-use puffersecuresigner::enclave::types::ListKeysResponse;
+use puffersecuresigner::enclave::types::{ListKeyInfoResponse, ListKeysResponse};
 
 use super::read_secure_signer_port;
 
@@ -38,7 +38,7 @@ pub async fn mock_list_bls_keys_route() -> Result<axum_test::TestResponse> {
         .route(
             "/eth/v1/keystores",
             axum::routing::get(
-                puffersecuresigner::enclave::shared::handlers::list_bls_keys::handler,
+                puffersecuresigner::enclave::shared::handlers::keystores::list_handler,
             ),
         )
         .into_make_service();
@@ -172,3 +172,73 @@ async fn verify_list_eth_keys_works() {
 
     assert_eq!(keys.data.len(), num_exist + 2);
 }
+
+pub async fn mock_slashing_watermarks_route(bls_pk_hex: &str) -> Result<axum_test::TestResponse> {
+    let test_app = axum::Router::new()
+        .route(
+            "/eth/v1/slashing/:bls_pk_hex",
+            axum::routing::get(
+                puffersecuresigner::enclave::shared::handlers::slashing_watermarks::handler,
+            ),
+        )
+        .into_make_service();
+
+    let server = axum_test::TestServer::new(test_app)?;
+
+    Ok(server
+        .get(&format!("/eth/v1/slashing/{}", bls_pk_hex))
+        .await)
+}
+
+pub async fn mock_list_keys_route(query: &str) -> Result<axum_test::TestResponse> {
+    let test_app = axum::Router::new()
+        .route(
+            "/eth/v1/keys",
+            axum::routing::get(puffersecuresigner::enclave::shared::handlers::list_keys::handler),
+        )
+        .into_make_service();
+
+    let server = axum_test::TestServer::new(test_app)?;
+
+    Ok(server.get(&format!("/eth/v1/keys{}", query)).await)
+}
+
+#[tokio::test]
+async fn verify_list_keys_filters() {
+    if read_secure_signer_port().is_some() {
+        return;
+    }
+    let attested_pk = register_new_bls_key(None).await.pk_hex;
+    let sk = puffersecuresigner::crypto::bls_keys::new_bls_key(0);
+    puffersecuresigner::crypto::bls_keys::save_bls_key(&sk).unwrap();
+    let unattested_pk = format!("0x{}", sk.public_keys().public_key().to_hex());
+
+    let list = |query: &'static str| async move {
+        let resp = mock_list_keys_route(query).await.unwrap();
+        assert_eq!(resp.status_code(), 200);
+        let keys: ListKeyInfoResponse = serde_json::from_slice(resp.as_bytes()).unwrap();
+        keys.data
+            .into_iter()
+            .map(|k| k.pubkey)
+            .collect::<Vec<String>>()
+    };
+
+    let keys = list("?type=bls&attested=true").await;
+    assert!(keys.contains(&attested_pk));
+    assert!(!keys.contains(&unattested_pk));
+
+    let keys = list("?type=bls&attested=false").await;
+    assert!(!keys.contains(&attested_pk));
+    assert!(keys.contains(&unattested_pk));
+
+    let keys = list("?type=eth").await;
+    assert!(!keys.contains(&attested_pk));
+    assert!(!keys.contains(&unattested_pk));
+
+    let keys = list("").await;
+    assert!(keys.contains(&attested_pk));
+    assert!(keys.contains(&unattested_pk));
+
+    let resp = mock_list_keys_route("?type=rsa").await.unwrap();
+    assert_eq!(resp.status_code(), 400);
+}
@@ -0,0 +1,266 @@
+use puffersecuresigner::enclave::types::{
+    DeleteKeystoresRequest, DeleteKeystoresResponse, ImportKeystoresRequest,
+    ImportKeystoresResponse, ListKeystoresResponse,
+};
+
+use anyhow::{Context, Result};
+use reqwest::{Client, StatusCode};
+use serde_json;
+
+fn keystores_test_server() -> Result<axum_test::TestServer> {
+    let test_app = axum::Router::new()
+        .route(
+            "/eth/v1/keystores",
+            axum::routing::get(
+                puffersecuresigner::enclave::shared::handlers::keystores::list_handler,
+            )
+            .post(puffersecuresigner::enclave::shared::handlers::keystores::import_handler)
+            .delete(puffersecuresigner::enclave::shared::handlers::keystores::delete_handler),
+        )
+        .into_make_service();
+
+    axum_test::TestServer::new(test_app)
+}
+
+pub async fn mock_list_keystores_route() -> Result<axum_test::TestResponse> {
+    let server = keystores_test_server()?;
+    Ok(server.get("/eth/v1/keystores").await)
+}
+
+pub async fn mock_import_keystores_route(
+    req: &ImportKeystoresRequest,
+) -> Result<axum_test::TestResponse> {
+    let server = keystores_test_server()?;
+    Ok(server.post("/eth/v1/keystores").json(req).await)
+}
+
+pub async fn mock_delete_keystores_route(
+    req: &DeleteKeystoresRequest,
+) -> Result<axum_test::TestResponse> {
+    let server = keystores_test_server()?;
+    Ok(server.delete("/eth/v1/keystores").json(req).await)
+}
+
+pub async fn make_list_keystores_request(
+    port: Option<u16>,
+) -> Result<(ListKeystoresResponse, StatusCode)> {
+    match port {
+        // Make the actual http req to a running Secure-Signer instance
+        Some(p) => {
+            let url = format!("http://localhost:{}/eth/v1/keystores", p);
+            let resp = Client::new().get(&url).send().await?;
+            let status = resp.status();
+            let list: ListKeystoresResponse = resp
+                .json()
+                .await
+                .with_context(|| "Failed to parse to ListKeystoresResponse")?;
+            Ok((list, status))
+        }
+        // Mock an http request
+        None => {
+            let resp = mock_list_keystores_route().await?;
+            let list: ListKeystoresResponse = serde_json::from_slice(resp.as_bytes())
+                .with_context(|| "Failed to parse to ListKeystoresResponse")?;
+            Ok((list, resp.status_code().into()))
+        }
+    }
+}
+
+pub async fn make_import_keystores_request(
+    port: Option<u16>,
+    req: &ImportKeystoresRequest,
+) -> Result<(ImportKeystoresResponse, StatusCode)> {
+    match port {
+        // Make the actual http req to a running Secure-Signer instance
+        Some(p) => {
+            let url = format!("http://localhost:{}/eth/v1/keystores", p);
+            let resp = Client::new().post(&url).json(req).send().await?;
+            let status = resp.status();
+            let imported: ImportKeystoresResponse = resp
+                .json()
+                .await
+                .with_context(|| "Failed to parse to ImportKeystoresResponse")?;
+            Ok((imported, status))
+        }
+        // Mock an http request
+        None => {
+            let resp = mock_import_keystores_route(req).await?;
+            let imported: ImportKeystoresResponse = serde_json::from_slice(resp.as_bytes())
+                .with_context(|| "Failed to parse to ImportKeystoresResponse")?;
+            Ok((imported, resp.status_code().into()))
+        }
+    }
+}
+
+pub async fn make_delete_keystores_request(
+    port: Option<u16>,
+    req: &DeleteKeystoresRequest,
+) -> Result<(DeleteKeystoresResponse, StatusCode)> {
+    match port {
+        // Make the actual http req to a running Secure-Signer instance
+        Some(p) => {
+            let url = format!("http://localhost:{}/eth/v1/keystores", p);
+            let resp = Client::new().delete(&url).json(req).send().await?;
+            let status = resp.status();
+            let deleted: DeleteKeystoresResponse = resp
+                .json()
+                .await
+                .with_context(|| "Failed to parse to DeleteKeystoresResponse")?;
+            Ok((deleted, status))
+        }
+        // Mock an http request
+        None => {
+            let resp = mock_delete_keystores_route(req).await?;
+            let deleted: DeleteKeystoresResponse = serde_json::from_slice(resp.as_bytes())
+                .with_context(|| "Failed to parse to DeleteKeystoresResponse")?;
+            Ok((deleted, resp.status_code().into()))
+        }
+    }
+}
+
+// An EIP-3076 interchange file recording a block at `slot` for the validator `pk_hex`
+fn interchange_with_block(pk_hex: &str, gvr_hex: &str, slot: u64) -> String {
+    serde_json::json!({
+        "metadata": {
+            "interchange_format_version": "5",
+            "genesis_validators_root": format!("0x{gvr_hex}")
+        },
+        "data": [{
+            "pubkey": format!("0x{pk_hex}"),
+            "signed_blocks": [{ "slot": slot.to_string() }],
+            "signed_attestations": []
+        }]
+    })
+    .to_string()
+}
+
+#[tokio::test]
+async fn test_keystores_import_list_delete() {
+    use puffersecuresigner::crypto::{bls_keys, keystore};
+    use puffersecuresigner::enclave::types::{DeleteKeystoreStatus, ImportKeystoreStatus};
+    use puffersecuresigner::eth2::slash_protection::{
+        SlashingProtectionDB, SlashingProtectionData,
+    };
+    use puffersecuresigner::io::key_management;
+
+    // The genesis_validators_root of the signing tests' fork info
+    let gvr_hex = "270d43e74ce340de4bca2b1936beca0f4f5408d9e78aec4850920baf659d5b69";
+    let password = "keymanager password🔑";
+    // Cheap parameters keep the test fast
+    let params = keystore::KeystoreScryptParams { n: 16, r: 8, p: 1 };
+    let sk_set = bls_keys::new_bls_key(0);
+    bls_keys::save_bls_key(&sk_set).unwrap();
+    let pk_hex = sk_set.public_keys().public_key().to_hex();
+    let keystore_json =
+        keystore::export_eip2335_keystore_with_params(&pk_hex, password, params).unwrap();
+    key_management::delete_bls_key(&pk_hex).unwrap();
+
+    let import = |keystores: Vec<String>, passwords: Vec<&str>, slashing_protection| {
+        ImportKeystoresRequest {
+            keystores,
+            passwords: passwords.into_iter().map(String::from).collect(),
+            slashing_protection,
+        }
+    };
+
+    // Every keystore needs a password
+    let req = import(vec![keystore_json.clone()], vec![], None);
+    let resp = mock_import_keystores_route(&req).await.unwrap();
+    assert_eq!(resp.status_code(), 400);
+
+    // Nothing is imported alongside a malformed interchange file
+    let bad_interchange = interchange_with_block(&pk_hex, &"00".repeat(31), 100);
+    let req = import(
+        vec![keystore_json.clone()],
+        vec![password],
+        Some(bad_interchange),
+    );
+    let (resp, status) = make_import_keystores_request(None, &req).await.unwrap();
+    assert_eq!(status, 200);
+    assert_eq!(resp.data[0].status, ImportKeystoreStatus::Error);
+    assert!(!key_management::bls_key_exists(&pk_hex));
+
+    let interchange = interchange_with_block(&pk_hex, gvr_hex, 100);
+    let req = import(
+        vec![keystore_json.clone(), keystore_json.clone()],
+        vec![password, "wrong password"],
+        Some(interchange),
+    );
+    let (resp, status) = make_import_keystores_request(None, &req).await.unwrap();
+    assert_eq!(status, 200);
+    let statuses: Vec<ImportKeystoreStatus> = resp.data.iter().map(|s| s.status).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ImportKeystoreStatus::Imported,
+            ImportKeystoreStatus::Duplicate
+        ]
+    );
+    assert!(key_management::bls_key_exists(&pk_hex));
+    let db = SlashingProtectionData::read(&pk_hex).unwrap();
+    assert_eq!(db.watermarks().highest_signed_slot, Some(100));
+
+    let (list, status) = make_list_keystores_request(None).await.unwrap();
+    assert_eq!(status, 200);
+    let listed = list
+        .data
+        .iter()
+        .find(|k| k.validating_pubkey == format!("0x{pk_hex}"))
+        .unwrap();
+    assert!(!listed.readonly);
+    assert_eq!(listed.pubkey, listed.validating_pubkey);
+
+    let req = DeleteKeystoresRequest {
+        pubkeys: vec![
+            format!("0x{}", pk_hex.to_uppercase()),
+            format!("0x{}", "ab".repeat(48)),
+            "0x1234".to_string(),
+        ],
+    };
+    // Nothing is deleted under another network's genesis_validators_root
+    let server = keystores_test_server().unwrap();
+    let url = format!(
+        "/eth/v1/keystores?genesis_validators_root=0x{}",
+        "00".repeat(32)
+    );
+    let resp = server.delete(&url).json(&req).await;
+    assert_eq!(resp.status_code(), 400);
+    assert!(resp.text().contains("Mismatched genesis_validators_root"));
+    assert!(key_management::bls_key_exists(&pk_hex));
+    let url = format!("/eth/v1/keystores?genesis_validators_root=0x{gvr_hex}");
+    assert_eq!(server.delete(&url).json(&req).await.status_code(), 200);
+    bls_keys::save_bls_key(&sk_set).unwrap();
+
+    let (resp, status) = make_delete_keystores_request(None, &req).await.unwrap();
+    assert_eq!(status, 200);
+    let statuses: Vec<DeleteKeystoreStatus> = resp.data.iter().map(|s| s.status).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            DeleteKeystoreStatus::Deleted,
+            DeleteKeystoreStatus::NotFound,
+            DeleteKeystoreStatus::Error
+        ]
+    );
+    assert!(resp.data[2].message.contains("Invalid bls_pk_hex length"));
+    assert!(!key_management::bls_key_exists(&pk_hex));
+
+    // The exported history covers only the deleted key and can be imported elsewhere
+    let exported = SlashingProtectionDB::from_str(&resp.slashing_protection).unwrap();
+    assert_eq!(
+        hex::encode(exported.metadata.genesis_validators_root),
+        gvr_hex
+    );
+    assert_eq!(exported.data.len(), 1);
+    assert_eq!(exported.data[0].watermarks().highest_signed_slot, Some(100));
+
+    // Deleting again finds nothing, and re-importing resumes from the kept history
+    let (resp, _) = make_delete_keystores_request(None, &req).await.unwrap();
+    assert_eq!(resp.data[0].status, DeleteKeystoreStatus::NotFound);
+    let req = import(vec![keystore_json], vec![password], None);
+    let (resp, _) = make_import_keystores_request(None, &req).await.unwrap();
+    assert_eq!(resp.data[0].status, ImportKeystoreStatus::Imported);
+    let db = SlashingProtectionData::read(&pk_hex).unwrap();
+    assert_eq!(db.watermarks().highest_signed_slot, Some(100));
+    key_management::delete_bls_key(&pk_hex).unwrap();
+}
@@ -7,6 +7,7 @@ pub mod bls_keygen_helper;
 pub mod eth_keygen_helper;
 pub mod eth_specs;
 pub mod getter_routes_helper;
+pub mod keystores_helper;
 pub mod signing_helper;
 
 /// Reads the `SECURE_SIGNER_PORT` environment variable.
@@ -28,6 +29,29 @@ pub fn read_secure_signer_port() -> Option<u16> {
     port
 }
 
+/// Registers the network of the signing tests' fork info, whose blocks carry the Capella body
+/// under its fork version 0x00000001, so type = "BLOCK" requests select their body
+pub fn register_test_network() {
+    static REGISTERED: std::sync::Once = std::sync::Once::new();
+    REGISTERED.call_once(|| {
+        puffersecuresigner::eth2::eth_types::register_network(
+            puffersecuresigner::eth2::eth_types::NetworkForkSchedule {
+                name: "signing-tests",
+                genesis_validators_root:
+                    "270d43e74ce340de4bca2b1936beca0f4f5408d9e78aec4850920baf659d5b69",
+                fork_versions: &[
+                    [0x00, 0x00, 0x00, 0x00],
+                    [0x01, 0x00, 0x00, 0x00],
+                    [0x02, 0x00, 0x00, 0x00],
+                    [0x00, 0x00, 0x00, 0x01],
+                ],
+                dst: puffersecuresigner::constants::CIPHER_SUITE.as_bytes(),
+            },
+        )
+        .unwrap();
+    });
+}
+
 /// hardcoded bls sk from Lighthouse Web3Signer tests
 pub fn setup_dummy_keypair() -> String {
     // dummy key
@@ -10,6 +10,18 @@ use serde_json;
 pub async fn mock_secure_sign_route(
     bls_pk: &String,
     signing_data: BLSSignMsg,
+) -> Result<axum_test::TestResponse> {
+    let state = puffersecuresigner::enclave::shared::handlers::AppState {
+        genesis_fork_version: GENESIS_FORK_VERSION,
+        ..Default::default()
+    };
+    mock_secure_sign_route_with_state(bls_pk, signing_data, state).await
+}
+
+pub async fn mock_secure_sign_route_with_state(
+    bls_pk: &String,
+    signing_data: BLSSignMsg,
+    state: puffersecuresigner::enclave::shared::handlers::AppState,
 ) -> Result<axum_test::TestResponse> {
     let uri = format!("/api/v1/eth2/sign/{}", bls_pk);
     let test_app = axum::Router::new()
@@ -19,9 +31,7 @@ pub async fn mock_secure_sign_route(
                 puffersecuresigner::enclave::shared::handlers::secure_sign_bls::handler,
             ),
         )
-        .with_state(puffersecuresigner::enclave::shared::handlers::AppState {
-            genesis_fork_version: GENESIS_FORK_VERSION,
-        })
+        .with_state(state)
         .into_make_service();
 
     let server = axum_test::TestServer::new(test_app)?;
@@ -29,7 +39,76 @@ pub async fn mock_secure_sign_route(
     Ok(server.post(&uri).json(&signing_data).await)
 }
 
-/// Makes a request to Secure-Aggregator aggregate_route on the specified port
+/// Like `mock_secure_sign_route_with_state` but sends `signing_data` tagged with schema `version`
+pub async fn mock_secure_sign_route_with_version(
+    bls_pk: &String,
+    signing_data: BLSSignMsg,
+    version: u32,
+    state: puffersecuresigner::enclave::shared::handlers::AppState,
+) -> Result<axum_test::TestResponse> {
+    let uri = format!("/api/v1/eth2/sign/{}", bls_pk);
+    let test_app = axum::Router::new()
+        .route(
+            "/api/v1/eth2/sign/:bls_pk_hex",
+            axum::routing::post(
+                puffersecuresigner::enclave::shared::handlers::secure_sign_bls::handler,
+            ),
+        )
+        .with_state(state)
+        .into_make_service();
+
+    let server = axum_test::TestServer::new(test_app)?;
+    let req = puffersecuresigner::eth2::eth_signing::SignRequest {
+        version,
+        msg: signing_data,
+    };
+
+    Ok(server.post(&uri).json(&req).await)
+}
+
+/// Registers `validator_index` for `bls_pk` through a mocked `/eth/v1/validator_index` route and
+/// then signs `signing_data` by that index
+pub async fn mock_sign_by_validator_index_route(
+    bls_pk: &String,
+    validator_index: u64,
+    signing_data: BLSSignMsg,
+) -> Result<(axum_test::TestResponse, axum_test::TestResponse)> {
+    let state = puffersecuresigner::enclave::shared::handlers::AppState {
+        genesis_fork_version: GENESIS_FORK_VERSION,
+        ..Default::default()
+    };
+    let test_app = axum::Router::new()
+        .route(
+            "/eth/v1/validator_index",
+            axum::routing::post(
+                puffersecuresigner::enclave::shared::handlers::validator_index::handler,
+            ),
+        )
+        .route(
+            "/api/v1/eth2/sign/index/:validator_index",
+            axum::routing::post(
+                puffersecuresigner::enclave::shared::handlers::secure_sign_bls::by_index_handler,
+            ),
+        )
+        .with_state(state)
+        .into_make_service();
+
+    let server = axum_test::TestServer::new(test_app)?;
+
+    let register = server
+        .post("/eth/v1/validator_index")
+        .json(&serde_json::json!({
+            "pubkey": bls_pk,
+            "validator_index": validator_index.to_string(),
+        }))
+        .await;
+    let sign = server
+        .post(&format!("/api/v1/eth2/sign/index/{}", validator_index))
+        .json(&signing_data)
+        .await;
+    Ok((register, sign))
+}
+
 pub async fn request_secure_sign_route(
     bls_pk: &String,
     sign_msg: &BLSSignMsg,
@@ -60,6 +139,23 @@ pub async fn make_signing_route_request(
 ) -> Result<(
     Option<puffersecuresigner::enclave::types::SignatureResponse>,
     StatusCode,
+)> {
+    let state = puffersecuresigner::enclave::shared::handlers::AppState {
+        genesis_fork_version: GENESIS_FORK_VERSION,
+        ..Default::default()
+    };
+    make_signing_route_request_with_state(signing_data, bls_pk_hex, port, state).await
+}
+
+/// Like `make_signing_route_request`, but mocked requests are served with `state`
+pub async fn make_signing_route_request_with_state(
+    signing_data: BLSSignMsg,
+    bls_pk_hex: &String,
+    port: Option<u16>,
+    state: puffersecuresigner::enclave::shared::handlers::AppState,
+) -> Result<(
+    Option<puffersecuresigner::enclave::types::SignatureResponse>,
+    StatusCode,
 )> {
     match port {
         // Make the actual http req to a running Secure-Signer instance
@@ -75,7 +171,7 @@ pub async fn make_signing_route_request(
         }
         // Mock an http request
         None => {
-            let resp = mock_secure_sign_route(&bls_pk_hex, signing_data).await?;
+            let resp = mock_secure_sign_route_with_state(&bls_pk_hex, signing_data, state).await?;
             let status = resp.status_code();
             let sig: Option<puffersecuresigner::enclave::types::SignatureResponse> =
                 serde_json::from_slice(resp.as_bytes()).ok();
@@ -1,10 +1,13 @@
 use crate::common;
 use crate::common::bls_keygen_helper::register_new_bls_key;
+use crate::common::getter_routes_helper::mock_slashing_watermarks_route;
 use crate::common::{eth_specs, signing_helper::*};
 use puffersecuresigner::eth2::eth_signing::*;
 use puffersecuresigner::eth2::eth_types::*;
+use puffersecuresigner::eth2::slash_protection::SlashingWatermarks;
 use puffersecuresigner::strip_0x_prefix;
 use std::path::PathBuf;
+use tree_hash::TreeHash;
 
 const START_SRC_EPOCH: u64 = 1234;
 const START_TGT_EPOCH: u64 = 1235;
@@ -60,6 +63,65 @@ pub async fn test_aggregate_route_fails_from_invalid_pk_hex() {
     assert_eq!(status, 400);
 }
 
+#[tokio::test]
+pub async fn test_sign_with_unknown_key_returns_404() {
+    let req = attestation_req(START_SRC_EPOCH, START_TGT_EPOCH);
+    let bls_pk_hex = format!("0x{}", blsttc::SecretKey::random().public_key().to_hex());
+    let resp = mock_secure_sign_route(&bls_pk_hex, req).await.unwrap();
+    assert_eq!(resp.status_code(), 404);
+
+    let body: serde_json::Value = serde_json::from_slice(resp.as_bytes()).unwrap();
+    assert_eq!(
+        body,
+        serde_json::json!({"error": {"code": "UNKNOWN_KEY", "pubkey": bls_pk_hex}})
+    );
+}
+
+#[tokio::test]
+pub async fn test_debug_attestation_response_echoes_root() {
+    let bls_pk_hex = register_new_bls_key(None).await.pk_hex;
+    let req = attestation_req(START_SRC_EPOCH, START_TGT_EPOCH);
+    let signing_data: AttestationRequest =
+        serde_json::from_str(&mock_attestation_request(START_SRC_EPOCH, START_TGT_EPOCH)).unwrap();
+
+    let test_app = axum::Router::new()
+        .route(
+            "/api/v1/eth2/sign/:bls_pk_hex",
+            axum::routing::post(
+                puffersecuresigner::enclave::shared::handlers::secure_sign_bls::handler,
+            ),
+        )
+        .with_state(puffersecuresigner::enclave::shared::handlers::AppState {
+            genesis_fork_version: GENESIS_FORK_VERSION,
+            debug_responses: true,
+            ..Default::default()
+        })
+        .into_make_service();
+    let server = axum_test::TestServer::new(test_app).unwrap();
+    let resp = server
+        .post(&format!("/api/v1/eth2/sign/{}", bls_pk_hex))
+        .json(&req)
+        .await;
+    assert_eq!(resp.status_code(), 200);
+
+    let resp: puffersecuresigner::enclave::types::SignatureResponse =
+        serde_json::from_slice(resp.as_bytes()).unwrap();
+    let exp_root = signing_data.attestation.tree_hash_root().to_fixed_bytes();
+    assert_eq!(
+        resp.attestation_root,
+        Some(format!("0x{}", hex::encode(exp_root)))
+    );
+    let echoed = resp.attestation.unwrap();
+    assert_eq!(echoed.tree_hash_root().to_fixed_bytes(), exp_root);
+    assert_eq!(echoed.target.epoch, START_TGT_EPOCH);
+
+    // The exact SSZ bytes that were hashed re-hash to the attestation root
+    let ssz_hex: String = strip_0x_prefix!(resp.signing_object_ssz.unwrap());
+    let ssz_bytes = hex::decode(ssz_hex).unwrap();
+    let decoded = <AttestationData as ssz::Decode>::from_ssz_bytes(&ssz_bytes).unwrap();
+    assert_eq!(decoded.tree_hash_root().to_fixed_bytes(), exp_root);
+}
+
 #[tokio::test]
 pub async fn test_aggregate_attestation_happy_path() {
     let port = common::read_secure_signer_port();
@@ -86,6 +148,44 @@ pub async fn test_aggregate_attestation_happy_path_test_vec() {
     assert_eq!(exp_sig.unwrap(), got_sig);
 }
 
+#[tokio::test]
+pub async fn test_compute_and_sign_signing_data_matches_attestation_route() {
+    let port = None;
+    let bls_pk_hex = register_new_bls_key(port).await.pk_hex;
+    let signing_data: AttestationRequest =
+        serde_json::from_str(&mock_attestation_request(START_SRC_EPOCH, START_TGT_EPOCH)).unwrap();
+    let req = attestation_req(START_SRC_EPOCH, START_TGT_EPOCH);
+    let (resp, status) = make_signing_route_request(req, &bls_pk_hex, port)
+        .await
+        .unwrap();
+    assert_eq!(status, 200);
+    let got_sig = resp.unwrap().signature;
+    let got_sig: String = strip_0x_prefix!(got_sig);
+
+    // Signing the pre-computed object root directly is an exact repeat of the route's signature
+    let domain = get_domain(
+        signing_data.fork_info.clone(),
+        DOMAIN_BEACON_ATTESTER,
+        Some(signing_data.attestation.target.epoch),
+    );
+    let object_root = signing_data.attestation.tree_hash_root().to_fixed_bytes();
+    let fields = Some(SlashableFields::Attestation {
+        source_epoch: START_SRC_EPOCH,
+        target_epoch: START_TGT_EPOCH,
+    });
+    assert!(compute_and_sign_signing_data(&bls_pk_hex, object_root, domain, None).is_err());
+    let (root, sig) =
+        compute_and_sign_signing_data(&bls_pk_hex, object_root, domain, fields).unwrap();
+    assert_eq!(
+        root,
+        BLSSignMsg::ATTESTATION(signing_data).to_signing_root(None)
+    );
+    assert_eq!(got_sig, hex::encode(&sig[..]));
+
+    // The attestation was recorded, so another root for the same target is refused
+    assert!(compute_and_sign_signing_data(&bls_pk_hex, [0x2a; 32], domain, fields).is_err());
+}
+
 #[tokio::test]
 pub async fn test_slash_protection_allows_non_slashable_attestation() {
     let port = common::read_secure_signer_port();
@@ -104,6 +204,69 @@ pub async fn test_slash_protection_allows_non_slashable_attestation() {
     assert_eq!(status, 200);
 }
 
+#[tokio::test]
+pub async fn test_slashing_watermarks_track_signed_attestations() {
+    let bls_pk_hex = register_new_bls_key(None).await.pk_hex;
+    let watermarks = || async {
+        let resp = mock_slashing_watermarks_route(&bls_pk_hex).await.unwrap();
+        assert_eq!(resp.status_code(), 200);
+        serde_json::from_slice::<SlashingWatermarks>(resp.as_bytes()).unwrap()
+    };
+    assert_eq!(watermarks().await, SlashingWatermarks::default());
+
+    let req = attestation_req(START_SRC_EPOCH, START_TGT_EPOCH);
+    let resp = mock_secure_sign_route(&bls_pk_hex, req).await.unwrap();
+    assert_eq!(resp.status_code(), 200);
+
+    let got = watermarks().await;
+    assert_eq!(got.highest_signed_slot, None);
+    assert_eq!(got.highest_source_epoch, Some(START_SRC_EPOCH));
+    assert_eq!(got.highest_target_epoch, Some(START_TGT_EPOCH));
+
+    // A rejected slashable request leaves the marks untouched
+    let req = attestation_req(START_SRC_EPOCH - 1, START_TGT_EPOCH + 1);
+    let resp = mock_secure_sign_route(&bls_pk_hex, req).await.unwrap();
+    assert_eq!(resp.status_code(), 412);
+    assert_eq!(watermarks().await, got);
+
+    let resp = mock_slashing_watermarks_route(&format!(
+        "0x{}",
+        blsttc::SecretKey::random().public_key().to_hex()
+    ))
+    .await
+    .unwrap();
+    assert_eq!(resp.status_code(), 404);
+}
+
+#[tokio::test]
+pub async fn test_sign_by_validator_index() {
+    let bls_pk_hex = register_new_bls_key(None).await.pk_hex;
+    let validator_index = u64::from_str_radix(&bls_pk_hex[2..10], 16).unwrap();
+    let req = attestation_req(START_SRC_EPOCH, START_TGT_EPOCH);
+    let signing_root = req.to_signing_root(Some(GENESIS_FORK_VERSION));
+
+    let (register, sign) = mock_sign_by_validator_index_route(&bls_pk_hex, validator_index, req)
+        .await
+        .unwrap();
+    assert_eq!(register.status_code(), 200);
+    assert_eq!(sign.status_code(), 200);
+
+    let resp: puffersecuresigner::enclave::types::SignatureResponse =
+        serde_json::from_slice(sign.as_bytes()).unwrap();
+    let pk = puffersecuresigner::crypto::bls_keys::bls_pk_from_hex(&bls_pk_hex).unwrap();
+    let sig = puffersecuresigner::crypto::bls_keys::bls_sig_from_hex(&resp.signature).unwrap();
+    assert!(pk.verify(&sig, signing_root));
+
+    // An index without a mapping is unknown
+    let unknown_pk = format!("0x{}", blsttc::SecretKey::random().public_key().to_hex());
+    let req = attestation_req(START_SRC_EPOCH, START_TGT_EPOCH);
+    let (register, sign) = mock_sign_by_validator_index_route(&unknown_pk, u64::MAX, req)
+        .await
+        .unwrap();
+    assert_eq!(register.status_code(), 404);
+    assert_eq!(sign.status_code(), 404);
+}
+
 #[tokio::test]
 pub async fn test_slash_protection_prevents_decreasing_source() {
     let port = common::read_secure_signer_port();
@@ -132,14 +295,44 @@ pub async fn test_slash_protection_prevents_same_target() {
         .unwrap();
     assert_eq!(status, 200);
 
-    // mock data for ATTESTATION request (attempt a slashable offense - non-increasing target)
-    let req = attestation_req(START_SRC_EPOCH, START_TGT_EPOCH);
+    // mock data for ATTESTATION request (attempt a slashable offense - double vote)
+    let mut signing_data: AttestationRequest =
+        serde_json::from_str(&mock_attestation_request(START_SRC_EPOCH, START_TGT_EPOCH)).unwrap();
+    signing_data.attestation.beacon_block_root = [0x2a; 32];
+    let req = BLSSignMsg::ATTESTATION(signing_data);
     let (_resp, status) = make_signing_route_request(req, &bls_pk_hex, port)
         .await
         .unwrap();
     assert_eq!(status, 412);
 }
 
+#[tokio::test]
+pub async fn test_slash_protection_allows_exact_retry() {
+    let port = common::read_secure_signer_port();
+    let bls_pk_hex = register_new_bls_key(port).await.pk_hex;
+    let req = attestation_req(START_SRC_EPOCH, START_TGT_EPOCH);
+    let (resp, status) = make_signing_route_request(req, &bls_pk_hex, port)
+        .await
+        .unwrap();
+    assert_eq!(status, 200);
+    let sig = resp.unwrap().signature;
+
+    // A retry of the identical attestation, e.g. after a lost response, gets the same signature
+    let req = attestation_req(START_SRC_EPOCH, START_TGT_EPOCH);
+    let (resp, status) = make_signing_route_request(req, &bls_pk_hex, port)
+        .await
+        .unwrap();
+    assert_eq!(status, 200);
+    assert_eq!(resp.unwrap().signature, sig);
+
+    // The retry recorded nothing, so the next target may still be signed
+    let req = attestation_req(START_SRC_EPOCH, START_TGT_EPOCH + 1);
+    let (_resp, status) = make_signing_route_request(req, &bls_pk_hex, port)
+        .await
+        .unwrap();
+    assert_eq!(status, 200);
+}
+
 #[tokio::test]
 pub async fn test_slash_protection_prevents_decreasing_target() {
     let port = common::read_secure_signer_port();
@@ -213,3 +406,104 @@ async fn test_attestation_eth2_specs() {
         }
     }
 }
+
+#[tokio::test]
+pub async fn test_attestation_ssz_signature_matches_json() {
+    use axum::http::{HeaderName, HeaderValue};
+    use puffersecuresigner::enclave::shared::handlers::secure_sign_ssz;
+    use ssz::Encode;
+
+    let bls_pk_hex = register_new_bls_key(None).await.pk_hex;
+    let signing_data: AttestationRequest =
+        serde_json::from_str(&mock_attestation_request(START_SRC_EPOCH, START_TGT_EPOCH)).unwrap();
+    let (resp, status) = make_signing_route_request(
+        attestation_req(START_SRC_EPOCH, START_TGT_EPOCH),
+        &bls_pk_hex,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(status, 200);
+    let json_sig = resp.unwrap().signature;
+
+    let test_app = axum::Router::new()
+        .route(
+            "/api/v1/eth2/ssz/sign/:bls_pk_hex",
+            axum::routing::post(secure_sign_ssz::handler),
+        )
+        .with_state(puffersecuresigner::enclave::shared::handlers::AppState::default())
+        .into_make_service();
+    let server = axum_test::TestServer::new(test_app).unwrap();
+    let fork_info = signing_data.fork_info.clone();
+    let ssz_sign_attestation =
+        |attestation: &AttestationData, domain: Domain, content_type: &str, gvr: [u8; 32]| {
+            let hex_header =
+                |bytes: &[u8]| HeaderValue::from_str(&format!("0x{}", hex::encode(bytes)));
+            server
+                .post(&format!("/api/v1/eth2/ssz/sign/{}", bls_pk_hex))
+                .content_type(content_type)
+                .add_header(
+                    HeaderName::from_static("x-signing-type"),
+                    HeaderValue::from_static("ATTESTATION"),
+                )
+                .add_header(
+                    HeaderName::from_static("x-signing-domain"),
+                    hex_header(&domain).unwrap(),
+                )
+                .add_header(
+                    HeaderName::from_static("x-fork-version"),
+                    hex_header(&fork_info.fork.current_version).unwrap(),
+                )
+                .add_header(
+                    HeaderName::from_static("x-genesis-validators-root"),
+                    hex_header(&gvr).unwrap(),
+                )
+                .bytes(attestation.as_ssz_bytes().into())
+        };
+    let ssz_sign_with_gvr = |domain: Domain, content_type: &str, gvr: [u8; 32]| {
+        ssz_sign_attestation(&signing_data.attestation, domain, content_type, gvr)
+    };
+    let ssz_sign = |domain: Domain, content_type: &str| {
+        ssz_sign_with_gvr(domain, content_type, fork_info.genesis_validators_root)
+    };
+    let domain = get_domain(
+        signing_data.fork_info.clone(),
+        DOMAIN_BEACON_ATTESTER,
+        Some(START_TGT_EPOCH),
+    );
+
+    // Wrong content type or a domain of another type is refused
+    let resp = ssz_sign(domain, "application/json").await;
+    assert_eq!(resp.status_code(), 415);
+    let randao_domain = get_domain(
+        signing_data.fork_info.clone(),
+        DOMAIN_RANDAO,
+        Some(START_TGT_EPOCH),
+    );
+    let resp = ssz_sign(randao_domain, "application/octet-stream").await;
+    assert_eq!(resp.status_code(), 400);
+
+    // The domain must have been computed from the named fork
+    let resp = ssz_sign_with_gvr(domain, "application/octet-stream", [0x2a; 32]).await;
+    assert_eq!(resp.status_code(), 400);
+    assert!(resp.text().contains("was not computed from"));
+
+    // Another attestation for the same target is still protected when sent as SSZ
+    let mut double_vote = signing_data.attestation.clone();
+    double_vote.beacon_block_root = [0x2a; 32];
+    let resp = ssz_sign_attestation(
+        &double_vote,
+        domain,
+        "application/octet-stream",
+        fork_info.genesis_validators_root,
+    )
+    .await;
+    assert_eq!(resp.status_code(), 412);
+
+    // The identical attestation sent as SSZ is an exact repeat of the JSON one
+    let resp = ssz_sign(domain, "application/octet-stream").await;
+    assert_eq!(resp.status_code(), 200);
+    let resp: puffersecuresigner::enclave::types::SignatureResponse =
+        serde_json::from_slice(resp.as_bytes()).unwrap();
+    assert_eq!(resp.signature, json_sig);
+}
@@ -10,6 +10,7 @@ const START_SLOT: u64 = 1234;
 
 fn block_proposal_request(slot: u64) -> BLSSignMsg {
     // Create a BlockRequest
+    common::register_test_network();
     let req = mock_propose_block_request(slot);
     let signing_data: BlockRequest = serde_json::from_str(&req).unwrap();
     BLSSignMsg::BLOCK(signing_data)
@@ -167,6 +168,56 @@ pub async fn test_slash_protection_prevents_decreasing_slot() {
     assert_eq!(status, 412);
 }
 
+#[tokio::test]
+async fn test_dropped_sign_request_keeps_slashing_db_consistent() {
+    use puffersecuresigner::eth2::slash_protection::SlashingProtectionData;
+
+    let bls_pk_hex = register_new_bls_key(None).await.pk_hex;
+    let req = block_proposal_request(START_SLOT);
+    let exp_root = req.to_signing_root(Some(GENESIS_FORK_VERSION));
+    let state = puffersecuresigner::enclave::shared::handlers::AppState {
+        genesis_fork_version: GENESIS_FORK_VERSION,
+        ..Default::default()
+    };
+
+    // Poll the handler once then drop it, as axum does when a client disconnects
+    let fut = puffersecuresigner::enclave::shared::handlers::secure_sign_bls::handler(
+        axum::extract::Path(bls_pk_hex.clone()),
+        axum::extract::State(state),
+        axum::extract::Query(Default::default()),
+        axum::Json(req.into()),
+    );
+    assert!(tokio::time::timeout(std::time::Duration::ZERO, fut)
+        .await
+        .is_err());
+
+    // The in-flight signing completes in the background and commits the full record
+    let mut recorded = None;
+    for _ in 0..100 {
+        let db = SlashingProtectionData::read(&bls_pk_hex).unwrap();
+        if let Some(b) = db.signed_blocks.last() {
+            recorded = Some(b.clone());
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    let recorded = recorded.expect("dropped request was never committed");
+    assert_eq!(recorded.slot, START_SLOT);
+    assert_eq!(recorded.signing_root, Some(exp_root));
+
+    // The DB remains usable: the same slot is protected and the next slot signs
+    let (_resp, status) =
+        make_signing_route_request(block_proposal_request(START_SLOT), &bls_pk_hex, None)
+            .await
+            .unwrap();
+    assert_eq!(status, 412);
+    let (_resp, status) =
+        make_signing_route_request(block_proposal_request(START_SLOT + 1), &bls_pk_hex, None)
+            .await
+            .unwrap();
+    assert_eq!(status, 200);
+}
+
 async fn perf_test(n: u64, bls_pk_hex: &String, port: Option<u16>) {
     for i in 1..n {
         let req = block_proposal_request(i);
@@ -204,7 +255,7 @@ async fn test_block_eth2_specs() {
     let mut slashable = false;
     for msg in msgs.into_iter() {
         if let BLSSignMsg::BLOCK(msg) = &msg {
-            slot = msg.block.slot;
+            slot = msg.block.slot();
             if slot <= last_slot {
                 slashable = true;
             }
@@ -223,3 +274,66 @@ async fn test_block_eth2_specs() {
         }
     }
 }
+
+#[test]
+fn test_fork_block_body_roots_match_spec_vectors() {
+    use tree_hash::TreeHash;
+
+    let bodies =
+        eth_specs::get_fork_test_containers::<Phase0BeaconBlockBody>("phase0", "BeaconBlockBody")
+            .unwrap();
+    assert!(!bodies.is_empty());
+    let bodies =
+        eth_specs::get_fork_test_containers::<AltairBeaconBlockBody>("altair", "BeaconBlockBody")
+            .unwrap();
+    assert!(!bodies.is_empty());
+    let bodies = eth_specs::get_fork_test_containers::<BellatrixBeaconBlockBody>(
+        "bellatrix",
+        "BeaconBlockBody",
+    )
+    .unwrap();
+    assert!(!bodies.is_empty());
+    let bodies =
+        eth_specs::get_fork_test_containers::<BeaconBlockBody>("capella", "BeaconBlockBody")
+            .unwrap();
+    assert!(!bodies.is_empty());
+
+    // Each fork's block signs over its own body root
+    let blocks: Vec<ForkBeaconBlock> =
+        eth_specs::get_fork_test_containers::<Phase0BeaconBlock>("phase0", "BeaconBlock")
+            .unwrap()
+            .into_iter()
+            .map(ForkBeaconBlock::Phase0)
+            .chain(
+                eth_specs::get_fork_test_containers::<AltairBeaconBlock>("altair", "BeaconBlock")
+                    .unwrap()
+                    .into_iter()
+                    .map(ForkBeaconBlock::Altair),
+            )
+            .chain(
+                eth_specs::get_fork_test_containers::<BellatrixBeaconBlock>(
+                    "bellatrix",
+                    "BeaconBlock",
+                )
+                .unwrap()
+                .into_iter()
+                .map(ForkBeaconBlock::Bellatrix),
+            )
+            .chain(
+                eth_specs::get_fork_test_containers::<BeaconBlock>("capella", "BeaconBlock")
+                    .unwrap()
+                    .into_iter()
+                    .map(ForkBeaconBlock::Capella),
+            )
+            .collect();
+    for block in blocks {
+        let exp_root = match &block {
+            ForkBeaconBlock::Phase0(b) => b.tree_hash_root(),
+            ForkBeaconBlock::Altair(b) => b.tree_hash_root(),
+            ForkBeaconBlock::Bellatrix(b) => b.tree_hash_root(),
+            ForkBeaconBlock::Capella(b) => b.tree_hash_root(),
+            ForkBeaconBlock::Deneb(b) => b.tree_hash_root(),
+        };
+        assert_eq!(block.header().tree_hash_root(), exp_root);
+    }
+}
@@ -47,6 +47,54 @@ fn mock_sync_committee_contribution_and_proof_request() -> String {
     req
 }
 
+/// Returns the mock contribution at the first slot from its own where `bls_pk_hex` is selected as
+/// an aggregator, carrying the selection proof the signer produced for it
+async fn selected_contribution_and_proof_request(
+    bls_pk_hex: &String,
+    port: Option<u16>,
+) -> BLSSignMsg {
+    let BLSSignMsg::SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF(mut req) =
+        sync_committee_contribution_and_proof_request()
+    else {
+        unreachable!()
+    };
+    let first_slot = req.contribution_and_proof.contribution.slot;
+    for slot in first_slot..first_slot + 256 {
+        let selection_proof_req =
+            BLSSignMsg::SYNC_COMMITTEE_SELECTION_PROOF(SyncCommitteeSelectionProofRequest {
+                fork_info: req.fork_info.clone(),
+                signingRoot: None,
+                sync_aggregator_selection_data: SyncAggregatorSelectionData {
+                    slot,
+                    subcommittee_index: req.contribution_and_proof.contribution.subcommittee_index,
+                },
+            });
+        let (resp, status) = make_signing_route_request(selection_proof_req, bls_pk_hex, port)
+            .await
+            .unwrap();
+        assert_eq!(status, 200);
+        let resp = resp.unwrap();
+        if resp.is_aggregator == Some(true) {
+            let sig: String = strip_0x_prefix!(resp.signature);
+            req.signingRoot = None;
+            req.contribution_and_proof.contribution.slot = slot;
+            req.contribution_and_proof.selection_proof = hex::decode(sig).unwrap().into();
+            return BLSSignMsg::SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF(req);
+        }
+    }
+    panic!("{bls_pk_hex} was never selected as an aggregator")
+}
+
+/// Signing state that skips the selection proof check, for fixed vectors whose selection proofs
+/// were not produced by the signing key
+fn trust_selection_proofs_state() -> puffersecuresigner::enclave::shared::handlers::AppState {
+    puffersecuresigner::enclave::shared::handlers::AppState {
+        genesis_fork_version: GENESIS_FORK_VERSION,
+        trust_selection_proofs: true,
+        ..Default::default()
+    }
+}
+
 #[tokio::test]
 async fn test_aggregate_route_fails_from_invalid_pk_hex() {
     let port = common::read_secure_signer_port();
@@ -61,23 +109,49 @@ async fn test_aggregate_route_fails_from_invalid_pk_hex() {
 #[tokio::test]
 async fn test_aggregate_sync_committee_contribution_and_proof_happy_path() {
     let port = common::read_secure_signer_port();
-    let req = sync_committee_contribution_and_proof_request();
     let bls_pk_hex = register_new_bls_key(port).await.pk_hex;
+    let req = selected_contribution_and_proof_request(&bls_pk_hex, port).await;
     let (_resp, status) = make_signing_route_request(req, &bls_pk_hex, port)
         .await
         .unwrap();
     assert_eq!(status, 200);
 }
 
+#[tokio::test]
+async fn test_aggregate_sync_committee_contribution_and_proof_rejects_foreign_selection_proof() {
+    let port = common::read_secure_signer_port();
+    let bls_pk_hex = register_new_bls_key(port).await.pk_hex;
+    let other_pk_hex = register_new_bls_key(port).await.pk_hex;
+
+    // A selection proof produced by another validator is rejected
+    let req = selected_contribution_and_proof_request(&other_pk_hex, port).await;
+    let (_resp, status) = make_signing_route_request(req, &bls_pk_hex, port)
+        .await
+        .unwrap();
+    assert_eq!(status, 400);
+
+    // As is the mock's arbitrary selection proof
+    let req = sync_committee_contribution_and_proof_request();
+    let (_resp, status) = make_signing_route_request(req, &bls_pk_hex, port)
+        .await
+        .unwrap();
+    assert_eq!(status, 400);
+}
+
 #[tokio::test]
 async fn test_aggregate_sync_committee_contribution_and_proof_happy_path_test_vec() {
     let port = None;
     let exp_sig = Some("ae7248f762bf491101f3621bb0b1c85dd2264cdec4ebfcc4774c41d41229123728046722e16cf676742a1ac32b1d3d7611042c5d0e5b813d8c71477ccd2e1a4264a66eb3eb3d58b68641c592f210650c0e182357acf1dde03be8fda1011377b3".to_string());
     let req = sync_committee_contribution_and_proof_request();
     let bls_pk_hex = common::setup_dummy_keypair();
-    let (resp, status) = make_signing_route_request(req, &bls_pk_hex, port)
-        .await
-        .unwrap();
+    let (resp, status) = make_signing_route_request_with_state(
+        req,
+        &bls_pk_hex,
+        port,
+        trust_selection_proofs_state(),
+    )
+    .await
+    .unwrap();
     assert_eq!(status, 200);
     let sig = resp.unwrap().signature;
     let got_sig: String = strip_0x_prefix!(sig);
@@ -92,18 +166,88 @@ async fn test_sync_committee_contribution_eth2_specs() {
     dbg!(&path);
 
     let port = common::read_secure_signer_port();
-    let req = sync_committee_contribution_and_proof_request();
     let bls_pk_hex = register_new_bls_key(port).await.pk_hex;
+    let req = selected_contribution_and_proof_request(&bls_pk_hex, port).await;
     let (_resp, status) = make_signing_route_request(req, &bls_pk_hex, port)
         .await
         .unwrap();
     assert_eq!(status, 200);
 
+    // The spec vectors carry random selection proofs, so sign them locally without the check
+    let bls_pk_hex = common::setup_dummy_keypair();
     let msgs = eth_specs::get_all_test_vecs("ContributionAndProof").unwrap();
     for msg in msgs.into_iter() {
-        let (_resp, status) = make_signing_route_request(msg, &bls_pk_hex, port)
-            .await
-            .unwrap();
+        let (_resp, status) = make_signing_route_request_with_state(
+            msg,
+            &bls_pk_hex,
+            None,
+            trust_selection_proofs_state(),
+        )
+        .await
+        .unwrap();
         assert_eq!(status, 200);
     }
 }
+
+#[tokio::test]
+async fn test_ssz_contribution_and_proof_honors_trust_selection_proofs() {
+    use axum::http::{HeaderName, HeaderValue};
+    use puffersecuresigner::enclave::shared::handlers::{secure_sign_ssz, AppState};
+    use ssz::Encode;
+
+    let BLSSignMsg::SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF(req) =
+        sync_committee_contribution_and_proof_request()
+    else {
+        unreachable!()
+    };
+    let bls_pk_hex = common::setup_dummy_keypair();
+    let epoch = compute_epoch_at_slot(req.contribution_and_proof.contribution.slot);
+    let domain = get_domain(
+        req.fork_info.clone(),
+        DOMAIN_CONTRIBUTION_AND_PROOF,
+        Some(epoch),
+    );
+    let (req, bls_pk_hex) = (&req, &bls_pk_hex);
+    let ssz_sign = |state: AppState| async move {
+        let test_app = axum::Router::new()
+            .route(
+                "/api/v1/eth2/ssz/sign/:bls_pk_hex",
+                axum::routing::post(secure_sign_ssz::handler),
+            )
+            .with_state(state)
+            .into_make_service();
+        let server = axum_test::TestServer::new(test_app).unwrap();
+        let hex_header =
+            |bytes: &[u8]| HeaderValue::from_str(&format!("0x{}", hex::encode(bytes))).unwrap();
+        server
+            .post(&format!("/api/v1/eth2/ssz/sign/{}", bls_pk_hex))
+            .content_type("application/octet-stream")
+            .add_header(
+                HeaderName::from_static("x-signing-type"),
+                HeaderValue::from_static("SYNC_COMMITTEE_CONTRIBUTION_AND_PROOF"),
+            )
+            .add_header(
+                HeaderName::from_static("x-signing-domain"),
+                hex_header(&domain),
+            )
+            .add_header(
+                HeaderName::from_static("x-fork-version"),
+                hex_header(&req.fork_info.fork.current_version),
+            )
+            .add_header(
+                HeaderName::from_static("x-genesis-validators-root"),
+                hex_header(&req.fork_info.genesis_validators_root),
+            )
+            .bytes(req.contribution_and_proof.as_ssz_bytes().into())
+            .await
+            .status_code()
+    };
+
+    // The mock's arbitrary selection proof is refused unless selection proofs are trusted
+    assert_eq!(ssz_sign(AppState::default()).await, 400);
+    let state = AppState {
+        trust_selection_proofs: true,
+        ..Default::default()
+    };
+    assert_eq!(ssz_sign(state).await, 200);
+}
@@ -6,6 +6,7 @@ pub mod block_v2;
 pub mod contribution_and_proof;
 pub mod deposit;
 pub mod randao_reveal;
+pub mod slashing_concurrency;
 pub mod sync_committee_message;
 pub mod sync_committee_selection_proof;
 pub mod validator_registration;
@@ -12,6 +12,15 @@ fn randao_reveal_request() -> BLSSignMsg {
     BLSSignMsg::RANDAO_REVEAL(signing_data)
 }
 
+// The mock request is signed under the all-zero genesis fork version, which must be opted into
+fn genesis_fork_state() -> puffersecuresigner::enclave::shared::handlers::AppState {
+    puffersecuresigner::enclave::shared::handlers::AppState {
+        genesis_fork_version: GENESIS_FORK_VERSION,
+        allow_genesis_fork: true,
+        ..Default::default()
+    }
+}
+
 pub fn mock_randao_reveal_request() -> String {
     let req = format!(
         r#"
@@ -49,9 +58,10 @@ pub async fn test_aggregate_randao_reveal_happy_path() {
     let port = common::read_secure_signer_port();
     let req = randao_reveal_request();
     let bls_pk_hex = register_new_bls_key(port).await.pk_hex;
-    let (_resp, status) = make_signing_route_request(req, &bls_pk_hex, port)
-        .await
-        .unwrap();
+    let (_resp, status) =
+        make_signing_route_request_with_state(req, &bls_pk_hex, port, genesis_fork_state())
+            .await
+            .unwrap();
     assert_eq!(status, 200);
 }
 
@@ -61,11 +71,134 @@ pub async fn test_aggregate_randao_reveal_happy_path_test_vec() {
     let exp_sig = Some("a8c5eb481ef1c3ea345bc9cb9ce9918e18ef052d8287bacd3b1e1bbd34bc4e1e016602b778535d5b582bc35ea6d2ded106ea2cfec06f8b6c5bd049dbf0a544207ac3b21c634b8e78c2c0135a0000e961adae192203ef168de1edb83618d1a76d".to_string());
     let req = randao_reveal_request();
     let bls_pk_hex = common::setup_dummy_keypair();
-    let (resp, status) = make_signing_route_request(req, &bls_pk_hex, port)
-        .await
-        .unwrap();
+    let (resp, status) =
+        make_signing_route_request_with_state(req, &bls_pk_hex, port, genesis_fork_state())
+            .await
+            .unwrap();
     assert_eq!(status, 200);
     let sig = resp.unwrap().signature;
     let got_sig: String = strip_0x_prefix!(sig);
     assert_eq!(exp_sig.unwrap(), got_sig);
 }
+
+#[tokio::test]
+pub async fn test_randao_reveal_genesis_fork_rejected_by_default() {
+    let req = randao_reveal_request();
+    let bls_pk_hex = register_new_bls_key(None).await.pk_hex;
+    let (resp, status) = make_signing_route_request(req, &bls_pk_hex, None)
+        .await
+        .unwrap();
+    assert_eq!(status, 400);
+    assert!(resp.is_none());
+}
+
+#[tokio::test]
+pub async fn test_randao_reveal_signing_request_versions() {
+    let bls_pk_hex = register_new_bls_key(None).await.pk_hex;
+
+    // Current and pre-versioning requests are both signed
+    for version in [puffersecuresigner::constants::SIGN_REQUEST_VERSION, 0] {
+        let resp = mock_secure_sign_route_with_version(
+            &bls_pk_hex,
+            randao_reveal_request(),
+            version,
+            genesis_fork_state(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status_code(), 200);
+    }
+
+    // A future version is rejected rather than parsed as the current schema
+    let resp = mock_secure_sign_route_with_version(
+        &bls_pk_hex,
+        randao_reveal_request(),
+        puffersecuresigner::constants::SIGN_REQUEST_VERSION + 1,
+        genesis_fork_state(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status_code(), 422);
+    assert!(resp
+        .text()
+        .contains("Unsupported signing request version 2"));
+}
+
+#[tokio::test]
+pub async fn test_randao_reveal_affine_signature() {
+    let req = randao_reveal_request();
+    let bls_pk_hex = register_new_bls_key(None).await.pk_hex;
+    let state = puffersecuresigner::enclave::shared::handlers::AppState {
+        affine_signatures: true,
+        ..genesis_fork_state()
+    };
+    let (resp, status) = make_signing_route_request_with_state(req, &bls_pk_hex, None, state)
+        .await
+        .unwrap();
+    assert_eq!(status, 200);
+
+    // The affine point decompresses to the compressed signature
+    let resp = resp.unwrap();
+    let affine_hex = resp.signature_affine.unwrap();
+    let affine: String = strip_0x_prefix!(affine_hex);
+    let affine = hex::decode(affine).unwrap();
+    assert_eq!(affine.len(), 192);
+    let sig = puffersecuresigner::crypto::bls_keys::bls_sig_from_affine_bytes(&affine).unwrap();
+    assert_eq!(
+        sig,
+        puffersecuresigner::crypto::bls_keys::bls_sig_from_hex(&resp.signature).unwrap()
+    );
+}
+
+#[tokio::test]
+pub async fn test_randao_reveal_affine_signature_per_request() {
+    use puffersecuresigner::enclave::shared::handlers::{secure_sign_bls, AppState};
+    use puffersecuresigner::enclave::types::SignatureResponse;
+
+    let req = serde_json::to_value(randao_reveal_request()).unwrap();
+    let bls_pk_hex = register_new_bls_key(None).await.pk_hex;
+    let validator_index = (1 << 40) + rand::random::<u32>() as u64;
+    puffersecuresigner::io::key_management::write_validator_index(
+        validator_index,
+        &bls_pk_hex,
+        false,
+    )
+    .unwrap();
+    let sign = |state: AppState, uri: String| {
+        let req = req.clone();
+        async move {
+            let test_app = axum::Router::new()
+                .route(
+                    "/api/v1/eth2/sign/:bls_pk_hex",
+                    axum::routing::post(secure_sign_bls::handler),
+                )
+                .route(
+                    "/api/v1/eth2/sign/index/:validator_index",
+                    axum::routing::post(secure_sign_bls::by_index_handler),
+                )
+                .with_state(state)
+                .into_make_service();
+            let server = axum_test::TestServer::new(test_app).unwrap();
+            let resp = server.post(&uri).json(&req).await;
+            assert_eq!(resp.status_code(), 200);
+            let resp: SignatureResponse = serde_json::from_slice(resp.as_bytes()).unwrap();
+            resp.signature_affine
+        }
+    };
+    let by_key = format!("/api/v1/eth2/sign/{bls_pk_hex}");
+    let by_index = format!("/api/v1/eth2/sign/index/{validator_index}");
+    let affine_state = AppState {
+        affine_signatures: true,
+        ..genesis_fork_state()
+    };
+
+    // A request may opt into or out of the affine point whatever the signer default
+    assert!(sign(genesis_fork_state(), by_key.clone()).await.is_none());
+    let uri = format!("{by_key}?affine=true");
+    assert!(sign(genesis_fork_state(), uri).await.is_some());
+    let uri = format!("{by_index}?affine=true");
+    assert!(sign(genesis_fork_state(), uri).await.is_some());
+    assert!(sign(affine_state.clone(), by_index.clone()).await.is_some());
+    let uri = format!("{by_index}?affine=false");
+    assert!(sign(affine_state, uri).await.is_none());
+}
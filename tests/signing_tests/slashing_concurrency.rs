@@ -0,0 +1,344 @@
+use crate::common::bls_keygen_helper::register_new_bls_key;
+use puffersecuresigner::enclave::shared::handlers::{secure_sign_bls, AppState};
+use puffersecuresigner::eth2::eth_signing::*;
+use puffersecuresigner::eth2::eth_types::*;
+use puffersecuresigner::eth2::slash_protection;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const VALIDATORS: usize = 3;
+const REQUESTS_PER_ROUND: usize = 120;
+const MAX_SLOT: Slot = 12;
+const MAX_SOURCE_EPOCH: Epoch = 6;
+// There are more block requests than validator and slot pairs, so some must be refused
+const _: () = assert!(REQUESTS_PER_ROUND / 2 > VALIDATORS * MAX_SLOT as usize);
+
+/// A slashable duty. Requests with the same slot (or epochs) but another `variant` sign a
+/// different root, so the plan contains double proposals and double votes as well as surrounds.
+#[derive(Clone, Copy, Debug)]
+enum Duty {
+    Block {
+        slot: Slot,
+        variant: u8,
+    },
+    Attestation {
+        source: Epoch,
+        target: Epoch,
+        variant: u8,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct PlannedRequest {
+    validator: usize,
+    duty: Duty,
+    // Times the task yields before sending, perturbing the launch order reproducibly
+    yields: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct AcceptedSignature {
+    validator: usize,
+    duty: Duty,
+    signing_root: Root,
+}
+
+/// Requests alternate between blocks and attestations, the rest is drawn from `seed`
+fn plan(seed: u64) -> Vec<PlannedRequest> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..REQUESTS_PER_ROUND)
+        .map(|i| {
+            let duty = if i % 2 == 0 {
+                Duty::Block {
+                    slot: rng.gen_range(1..=MAX_SLOT),
+                    variant: rng.gen_range(0..2),
+                }
+            } else {
+                let source = rng.gen_range(0..=MAX_SOURCE_EPOCH);
+                Duty::Attestation {
+                    source,
+                    target: source + rng.gen_range(1..=3),
+                    variant: rng.gen_range(0..2),
+                }
+            };
+            PlannedRequest {
+                validator: rng.gen_range(0..VALIDATORS),
+                duty,
+                yields: rng.gen_range(0..8),
+            }
+        })
+        .collect()
+}
+
+fn fork_info() -> ForkInfo {
+    ForkInfo {
+        fork: Fork {
+            previous_version: [0, 0, 0, 1],
+            current_version: [0, 0, 0, 1],
+            epoch: 0,
+        },
+        genesis_validators_root: [0x27; 32],
+    }
+}
+
+fn sign_msg(duty: Duty) -> BLSSignMsg {
+    match duty {
+        Duty::Block { slot, variant } => BLSSignMsg::BLOCK_V2(BlockV2Request {
+            fork_info: fork_info(),
+            signingRoot: None,
+            beacon_block: BlockV2RequestWrapper {
+                version: "BELLATRIX".to_string(),
+                block_header: BeaconBlockHeader {
+                    slot,
+                    proposer_index: 0,
+                    parent_root: [0; 32],
+                    state_root: [0; 32],
+                    body_root: [variant; 32],
+                },
+            },
+        }),
+        Duty::Attestation {
+            source,
+            target,
+            variant,
+        } => BLSSignMsg::ATTESTATION(AttestationRequest {
+            fork_info: fork_info(),
+            signingRoot: None,
+            attestation: AttestationData {
+                slot: target * 32,
+                index: 0,
+                beacon_block_root: [variant; 32],
+                source: Checkpoint {
+                    epoch: source,
+                    root: [0; 32],
+                },
+                target: Checkpoint {
+                    epoch: target,
+                    root: [0; 32],
+                },
+            },
+        }),
+    }
+}
+
+/// Whether the validator signing both `a` and `b` could be slashed, judged from the signed
+/// objects alone rather than the signer's DB, see EIP-3076 and the consensus slashing rules
+fn mutually_slashable(a: &AcceptedSignature, b: &AcceptedSignature) -> bool {
+    if a.validator != b.validator || a.signing_root == b.signing_root {
+        return false;
+    }
+    match (a.duty, b.duty) {
+        (Duty::Block { slot: s1, .. }, Duty::Block { slot: s2, .. }) => s1 == s2,
+        (
+            Duty::Attestation {
+                source: s1,
+                target: t1,
+                ..
+            },
+            Duty::Attestation {
+                source: s2,
+                target: t2,
+                ..
+            },
+        ) => t1 == t2 || (s1 < s2 && t2 < t1) || (s2 < s1 && t1 < t2),
+        _ => false,
+    }
+}
+
+/// Issues every planned request concurrently for `validators`, returning the accepted
+/// signatures and the number refused by slashing protection. Tasks are launched in plan order on
+/// the test's single-threaded runtime, only the blocking signing tasks race each other. With
+/// `writers`, every fourth request is accompanied by a task rewriting its validator's DB the way
+/// key imports and backup restores do.
+async fn run_round(
+    requests: &[PlannedRequest],
+    validators: &[String],
+    writers: bool,
+) -> (Vec<AcceptedSignature>, usize) {
+    let state = AppState {
+        genesis_fork_version: GENESIS_FORK_VERSION,
+        ..Default::default()
+    };
+    let tasks: Vec<_> = requests
+        .iter()
+        .map(|r| {
+            let r = *r;
+            let state = state.clone();
+            let bls_pk_hex = validators[r.validator].clone();
+            tokio::spawn(async move {
+                for _ in 0..r.yields {
+                    tokio::task::yield_now().await;
+                }
+                let msg = sign_msg(r.duty);
+                let signing_root = msg.to_signing_root(Some(GENESIS_FORK_VERSION));
+                let resp = secure_sign_bls::handler(
+                    axum::extract::Path(bls_pk_hex),
+                    axum::extract::State(state),
+                    axum::extract::Query(Default::default()),
+                    axum::Json(msg.into()),
+                )
+                .await;
+                (r, signing_root, resp.status().as_u16())
+            })
+        })
+        .collect();
+    let writer_tasks: Vec<_> = requests
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| writers && i % 4 == 0)
+        .map(|(_, r)| {
+            let pk_hex = validators[r.validator].clone();
+            tokio::task::spawn_blocking(move || {
+                slash_protection::init_slashing_db_if_missing(&pk_hex)?;
+                // An empty restore rewrites the DB without adding records
+                let restored = slash_protection::SlashingProtectionData::from_pk_hex(&pk_hex)?;
+                slash_protection::merge_slashing_db(&pk_hex, &restored)
+            })
+        })
+        .collect();
+
+    for task in writer_tasks {
+        task.await.unwrap().unwrap();
+    }
+    let mut accepted = Vec::new();
+    let mut refused = 0;
+    for task in tasks {
+        let (r, signing_root, status) = task.await.unwrap();
+        match status {
+            200 => accepted.push(AcceptedSignature {
+                validator: r.validator,
+                duty: r.duty,
+                signing_root,
+            }),
+            412 => refused += 1,
+            _ => panic!("Unexpected status {status} for {:?}", r),
+        }
+    }
+    (accepted, refused)
+}
+
+#[tokio::test]
+async fn test_concurrent_signing_never_releases_slashable_signatures() {
+    for seed in 0..4 {
+        let mut validators = Vec::with_capacity(VALIDATORS);
+        for _ in 0..VALIDATORS {
+            validators.push(register_new_bls_key(None).await.pk_hex);
+        }
+        let requests = plan(seed);
+        let (accepted, refused) = run_round(&requests, &validators, false).await;
+
+        assert_eq!(accepted.len() + refused, requests.len());
+        assert!(refused > 0, "seed {seed} exercised no conflicting requests");
+        for v in 0..VALIDATORS {
+            assert!(
+                accepted.iter().any(|a| a.validator == v),
+                "seed {seed} signed nothing for validator {v}"
+            );
+        }
+        for (i, a) in accepted.iter().enumerate() {
+            for b in &accepted[i + 1..] {
+                assert!(
+                    !mutually_slashable(a, b),
+                    "seed {seed} released slashable signatures {:?} and {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_db_writers_never_drop_signed_records() {
+    for seed in 4..8 {
+        let mut validators = Vec::with_capacity(VALIDATORS);
+        for _ in 0..VALIDATORS {
+            validators.push(register_new_bls_key(None).await.pk_hex);
+        }
+        let requests = plan(seed);
+        let (accepted, _) = run_round(&requests, &validators, true).await;
+
+        // Were a record overwritten by a writer, a conflicting duty for it would now be signed
+        let conflicting: Vec<PlannedRequest> = accepted
+            .iter()
+            .map(|a| PlannedRequest {
+                validator: a.validator,
+                duty: match a.duty {
+                    Duty::Block { slot, variant } => Duty::Block {
+                        slot,
+                        variant: variant ^ 1,
+                    },
+                    Duty::Attestation {
+                        source,
+                        target,
+                        variant,
+                    } => Duty::Attestation {
+                        source,
+                        target,
+                        variant: variant ^ 1,
+                    },
+                },
+                yields: 0,
+            })
+            .collect();
+        let (resigned, _) = run_round(&conflicting, &validators, false).await;
+        assert!(
+            resigned.is_empty(),
+            "seed {seed} lost the records of {:?}",
+            resigned
+        );
+    }
+}
+
+#[test]
+fn test_slashability_rules() {
+    let signed = |validator, duty, root: u8| AcceptedSignature {
+        validator,
+        duty,
+        signing_root: [root; 32],
+    };
+    let block = |slot| Duty::Block { slot, variant: 0 };
+    let att = |source, target| Duty::Attestation {
+        source,
+        target,
+        variant: 0,
+    };
+
+    // Double proposal, and the same block signed twice
+    assert!(mutually_slashable(
+        &signed(0, block(3), 1),
+        &signed(0, block(3), 2)
+    ));
+    assert!(!mutually_slashable(
+        &signed(0, block(3), 1),
+        &signed(0, block(3), 1)
+    ));
+    assert!(!mutually_slashable(
+        &signed(0, block(3), 1),
+        &signed(0, block(4), 2)
+    ));
+    // Double vote and surround votes, in either order
+    assert!(mutually_slashable(
+        &signed(0, att(1, 3), 1),
+        &signed(0, att(2, 3), 2)
+    ));
+    assert!(mutually_slashable(
+        &signed(0, att(1, 4), 1),
+        &signed(0, att(2, 3), 2)
+    ));
+    assert!(mutually_slashable(
+        &signed(0, att(2, 3), 1),
+        &signed(0, att(1, 4), 2)
+    ));
+    assert!(!mutually_slashable(
+        &signed(0, att(1, 2), 1),
+        &signed(0, att(2, 3), 2)
+    ));
+    // Different validators never slash each other
+    assert!(!mutually_slashable(
+        &signed(0, block(3), 1),
+        &signed(1, block(3), 2)
+    ));
+
+    // Plans are reproducible from their seed
+    assert_eq!(format!("{:?}", plan(7)), format!("{:?}", plan(7)));
+}
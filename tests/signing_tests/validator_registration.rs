@@ -66,3 +66,49 @@ async fn test_aggregate_validator_registration_happy_path_test_vec() {
     let got_sig: String = strip_0x_prefix!(sig);
     assert_eq!(exp_sig.unwrap(), got_sig);
 }
+
+fn fee_recipient_state(
+    policy_json: &str,
+) -> puffersecuresigner::enclave::shared::handlers::AppState {
+    let policy =
+        puffersecuresigner::eth2::fee_recipient_policy::FeeRecipientPolicy::from_json(policy_json)
+            .unwrap();
+    puffersecuresigner::enclave::shared::handlers::AppState {
+        genesis_fork_version: GENESIS_FORK_VERSION,
+        fee_recipient_policy: std::sync::Arc::new(policy),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_validator_registration_to_allowed_fee_recipient() {
+    let bls_pk_hex = register_new_bls_key(None).await.pk_hex;
+    // The mock registration pays 0x2a2a..2a
+    let state = fee_recipient_state(&format!(
+        r#"{{"validators": {{"{}": ["0x{}"]}}}}"#,
+        bls_pk_hex,
+        "2a".repeat(20)
+    ));
+    let (resp, status) = make_signing_route_request_with_state(
+        validator_registration_request(),
+        &bls_pk_hex,
+        None,
+        state,
+    )
+    .await
+    .unwrap();
+    assert_eq!(status, 200);
+    assert!(resp.is_some());
+}
+
+#[tokio::test]
+async fn test_validator_registration_to_disallowed_fee_recipient() {
+    let bls_pk_hex = register_new_bls_key(None).await.pk_hex;
+    let state = fee_recipient_state(&format!(r#"{{"default": ["0x{}"]}}"#, "11".repeat(20)));
+    let resp =
+        mock_secure_sign_route_with_state(&bls_pk_hex, validator_registration_request(), state)
+            .await
+            .unwrap();
+    assert_eq!(resp.status_code(), 403);
+    assert!(resp.text().contains("is not allowed"));
+}